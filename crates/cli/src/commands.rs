@@ -1,7 +1,10 @@
 pub mod collections;
+pub mod completions;
 pub mod generate;
 pub mod history;
 pub mod import;
 pub mod new;
+pub mod prepared;
 pub mod request;
 pub mod show;
+pub mod template;
@@ -1,6 +1,9 @@
 use crate::{GlobalArgs, Subcommand};
 use clap::Parser;
-use slumber_core::db::Database;
+use slumber_core::{
+    collection::{Collection, CollectionFile},
+    db::Database,
+};
 use std::{path::PathBuf, process::ExitCode};
 
 /// View and modify request collection metadata
@@ -25,21 +28,44 @@ enum CollectionsSubcommand {
         /// The path the collection to migrate *into*
         to: PathBuf,
     },
+    /// Check the collection file for problems and unused definitions
+    ///
+    /// Reports chains that reference missing recipes, chains that are never
+    /// used, and profiles with no data (or the same data as another profile).
+    /// Exits with an error code if anything is reported.
+    Lint,
 }
 
 impl Subcommand for CollectionsCommand {
-    async fn execute(self, _global: GlobalArgs) -> anyhow::Result<ExitCode> {
-        let database = Database::load()?;
+    async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
         match self.subcommand {
             CollectionsSubcommand::List => {
+                let database = Database::load()?;
                 for path in database.collections()? {
                     println!("{}", path.display());
                 }
             }
             CollectionsSubcommand::Migrate { from, to } => {
+                let database = Database::load()?;
                 database.merge_collections(&from, &to)?;
                 println!("Migrated {} into {}", from.display(), to.display());
             }
+            CollectionsSubcommand::Lint => {
+                let collection_path =
+                    CollectionFile::try_path(None, global.file)?;
+                let collection = Collection::load(&collection_path)?;
+                let problems: Vec<String> = collection
+                    .validate()
+                    .into_iter()
+                    .chain(collection.lint())
+                    .collect();
+                for problem in &problems {
+                    println!("{problem}");
+                }
+                if !problems.is_empty() {
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
         }
         Ok(ExitCode::SUCCESS)
     }
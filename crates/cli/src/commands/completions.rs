@@ -0,0 +1,28 @@
+use crate::{Args, GlobalArgs, Subcommand, COMMAND_NAME};
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
+use std::{io, process::ExitCode};
+
+/// Print a static shell completion script
+///
+/// This is intended for packagers that need completions as a file. The static
+/// script can't complete dynamic values such as profile and recipe IDs. For
+/// full completions, source the dynamic script instead (e.g.
+/// `COMPLETE=bash slumber`).
+#[derive(Clone, Debug, Parser)]
+pub struct CompletionsCommand {
+    /// Shell to generate completions for
+    shell: Shell,
+}
+
+impl Subcommand for CompletionsCommand {
+    async fn execute(self, _global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        clap_complete::generate(
+            self.shell,
+            &mut Args::command(),
+            COMMAND_NAME,
+            &mut io::stdout(),
+        );
+        Ok(ExitCode::SUCCESS)
+    }
+}
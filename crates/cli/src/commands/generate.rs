@@ -1,13 +1,41 @@
 use crate::{commands::request::BuildRequestCommand, GlobalArgs, Subcommand};
 use clap::{Parser, ValueEnum};
-use slumber_core::template::TemplateError;
+use slumber_core::{
+    collection::{Collection, CollectionFile},
+    http::RequestTicket,
+    template::TemplateError,
+};
 use std::process::ExitCode;
 
-/// Render a request and generate an equivalent for a third-party client
+/// Render a request and generate an equivalent for a third-party client, or
+/// export the entire collection to an external format
 #[derive(Clone, Debug, Parser)]
 #[clap(visible_alias = "gen")]
 pub struct GenerateCommand {
+    #[command(subcommand)]
     format: GenerateFormat,
+}
+
+/// Third-party format to generate
+#[derive(Clone, Debug, clap::Subcommand)]
+pub enum GenerateFormat {
+    /// cURL command
+    Curl(GenerateRequestArgs),
+    /// JavaScript `fetch()` call
+    Fetch(GenerateRequestArgs),
+    /// HTTPie command
+    Httpie(GenerateRequestArgs),
+    /// OpenAPI v3.0 specification of the entire collection
+    Openapi {
+        /// Output format for the spec
+        #[clap(long, value_enum, default_value_t)]
+        format: SpecFormat,
+    },
+}
+
+/// Arguments for generating a single request
+#[derive(Clone, Debug, Parser)]
+pub struct GenerateRequestArgs {
     #[clap(flatten)]
     build_request: BuildRequestCommand,
     /// Execute triggered sub-requests. By default, if a request dependency is
@@ -16,14 +44,45 @@ pub struct GenerateCommand {
     execute_triggers: bool,
 }
 
-/// Third-party client to generate for
-#[derive(Clone, Debug, ValueEnum)]
-pub enum GenerateFormat {
-    Curl,
+/// Serialization format for generated specs
+#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+pub enum SpecFormat {
+    #[default]
+    Yaml,
+    Json,
 }
 
 impl Subcommand for GenerateCommand {
     async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        let output = match self.format {
+            GenerateFormat::Curl(args) => {
+                args.build(global).await?.record().to_curl()?
+            }
+            GenerateFormat::Fetch(args) => {
+                args.build(global).await?.record().to_fetch()?
+            }
+            GenerateFormat::Httpie(args) => {
+                args.build(global).await?.record().to_httpie()?
+            }
+            GenerateFormat::Openapi { format } => {
+                let collection_path =
+                    CollectionFile::try_path(None, global.file)?;
+                let collection = Collection::load(&collection_path)?;
+                let spec = slumber_import::to_openapi(&collection);
+                match format {
+                    SpecFormat::Yaml => serde_yaml::to_string(&spec)?,
+                    SpecFormat::Json => serde_json::to_string_pretty(&spec)?,
+                }
+            }
+        };
+        println!("{output}");
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+impl GenerateRequestArgs {
+    /// Build the request, without sending it
+    async fn build(self, global: GlobalArgs) -> anyhow::Result<RequestTicket> {
         let (_, ticket) = self
             .build_request
             // User has to explicitly opt into executing triggered requests
@@ -41,7 +100,6 @@ impl Subcommand for GenerateCommand {
                     error
                 }
             })?;
-        println!("{}", ticket.record().to_curl()?);
-        Ok(ExitCode::SUCCESS)
+        Ok(ticket)
     }
 }
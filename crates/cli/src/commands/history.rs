@@ -6,6 +6,7 @@ use crate::{
 use anyhow::anyhow;
 use clap::Parser;
 use clap_complete::ArgValueCompleter;
+use slumber_config::{Config, Timezone};
 use slumber_core::{
     collection::{CollectionFile, ProfileId, RecipeId},
     db::{Database, DatabaseMode, ProfileFilter},
@@ -66,7 +67,11 @@ enum RecipeOrRequest {
 impl Subcommand for HistoryCommand {
     async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
         let collection_path = CollectionFile::try_path(None, global.file)?;
+        let config = Config::load()?;
         let database = Database::load()?
+            .with_busy_timeout(config.database_busy_timeout)?
+            .with_compression(config.database_compression)
+            .with_encryption(&config.database_encryption)?
             .into_collection(&collection_path, DatabaseMode::ReadOnly)?;
 
         match self.subcommand {
@@ -78,7 +83,7 @@ impl Subcommand for HistoryCommand {
                 };
                 let exchanges =
                     database.get_all_requests(profile_filter, &recipe)?;
-                Self::print_list(exchanges);
+                Self::print_list(exchanges, config.timezone);
             }
             HistorySubcommand::Get { request, display } => {
                 let exchange = match request {
@@ -102,14 +107,14 @@ impl Subcommand for HistoryCommand {
 }
 
 impl HistoryCommand {
-    fn print_list(exchanges: Vec<ExchangeSummary>) {
+    fn print_list(exchanges: Vec<ExchangeSummary>, timezone: Timezone) {
         for exchange in exchanges {
             println!(
                 "{}\t{}\t{}\t{}",
                 exchange.profile_id.as_deref().unwrap_or_default(),
                 exchange.id,
                 exchange.status.as_str(),
-                format_time_iso(&exchange.start_time),
+                format_time_iso(&timezone.localize(&exchange.start_time)),
             );
         }
     }
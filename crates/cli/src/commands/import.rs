@@ -1,10 +1,14 @@
 use crate::{GlobalArgs, Subcommand};
 use anyhow::Context;
-use clap::{Parser, ValueEnum};
+use clap::{ArgGroup, Parser, ValueEnum};
+use slumber_core::collection::{
+    Collection, CollectionSource, MergeConflict, RecipeId,
+};
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{self, Write},
-    path::PathBuf,
+    iter,
+    path::{Path, PathBuf},
     process::ExitCode,
 };
 
@@ -13,6 +17,9 @@ use std::{
 /// See docs for more info on formats:
 /// https://slumber.lucaspickering.me/book/cli/import.html
 #[derive(Clone, Debug, Parser)]
+#[clap(group(
+    ArgGroup::new("combine").args(["additional_inputs", "merge"]).multiple(true)
+))]
 pub struct ImportCommand {
     /// Input format
     format: Format,
@@ -20,6 +27,22 @@ pub struct ImportCommand {
     input_file: PathBuf,
     /// Destination for the new slumber collection file [default: stdout]
     output_file: Option<PathBuf>,
+    /// Additional files to import, in the same format. Can be given multiple
+    /// times. When importing multiple files, each file's recipes are put in
+    /// a folder named after the file. If that name is already taken, a
+    /// numeric suffix is added
+    #[clap(long = "input", value_name = "FILE")]
+    additional_inputs: Vec<PathBuf>,
+    /// Merge the imported recipes, profiles, and chains into this existing
+    /// collection file. The merged collection is written to the output, so
+    /// pass the same path as the output to update it in place
+    #[clap(long, value_name = "COLLECTION")]
+    merge: Option<PathBuf>,
+    /// How to handle IDs defined in both the existing and imported
+    /// collections, or in multiple imported files. Only applies with --merge
+    /// or --input
+    #[clap(long, value_enum, default_value_t, requires = "combine")]
+    on_conflict: OnConflict,
 }
 
 #[derive(Copy, Clone, Debug, ValueEnum)]
@@ -36,15 +59,69 @@ enum Format {
     Rest,
 }
 
+#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+enum OnConflict {
+    /// Keep the existing item and discard the imported one
+    #[default]
+    Skip,
+    /// Keep both, adding a numeric suffix to the imported item's ID
+    Rename,
+    /// Replace the existing item with the imported one
+    Overwrite,
+}
+
+impl Format {
+    /// Import a single file in this format
+    fn import(self, input_file: &Path) -> anyhow::Result<Collection> {
+        match self {
+            Self::Insomnia => slumber_import::from_insomnia(input_file),
+            Self::Openapi => slumber_import::from_openapi(input_file),
+            Self::Rest => slumber_import::from_rest(input_file),
+        }
+    }
+}
+
+impl From<OnConflict> for MergeConflict {
+    fn from(on_conflict: OnConflict) -> Self {
+        match on_conflict {
+            OnConflict::Skip => Self::Skip,
+            OnConflict::Rename => Self::Rename,
+            OnConflict::Overwrite => Self::Overwrite,
+        }
+    }
+}
+
 impl Subcommand for ImportCommand {
     async fn execute(self, _global: GlobalArgs) -> anyhow::Result<ExitCode> {
         // Load the input
-        let collection = match self.format {
-            Format::Insomnia => {
-                slumber_import::from_insomnia(&self.input_file)?
+        let collection = self.import_all()?;
+        let output = match &self.merge {
+            Some(path) => {
+                let source = fs::read_to_string(path).context(format!(
+                    "Error reading collection file {path:?}"
+                ))?;
+                let mut existing = Collection::load_source(path)?;
+                let existing_source = CollectionSource::new(
+                    path, source, &existing,
+                )
+                .context(format!("Error merging into collection {path:?}"))?;
+                existing
+                    .merge(collection, self.on_conflict.into())
+                    .context("Error merging imported collection")?;
+                // Edit the existing file in place where possible, to retain
+                // comments and formatting
+                match existing_source.update(&existing) {
+                    Ok(output) => output,
+                    Err(error) => {
+                        eprintln!(
+                            "Unable to preserve formatting of {path:?}, \
+                            rewriting the entire collection: {error:#}"
+                        );
+                        serde_yaml::to_string(&existing)?
+                    }
+                }
             }
-            Format::Openapi => slumber_import::from_openapi(&self.input_file)?,
-            Format::Rest => slumber_import::from_rest(&self.input_file)?,
+            None => serde_yaml::to_string(&collection)?,
         };
 
         // Write the output
@@ -62,8 +139,144 @@ impl Subcommand for ImportCommand {
             ),
             None => Box::new(io::stdout()),
         };
-        serde_yaml::to_writer(&mut writer, &collection)?;
+        writer.write_all(output.as_bytes())?;
 
         Ok(ExitCode::SUCCESS)
     }
 }
+
+impl ImportCommand {
+    /// Import every input file and combine them into one collection. A
+    /// single file is imported as-is. With multiple files, each file's
+    /// recipes are nested in a folder named after the file, then the
+    /// collections are merged in order. IDs that still collide (profiles,
+    /// chains, and recipe IDs, which are unique across folders) are handled
+    /// by --on-conflict.
+    fn import_all(&self) -> anyhow::Result<Collection> {
+        if self.additional_inputs.is_empty() {
+            return self.format.import(&self.input_file);
+        }
+
+        let mut collection = Collection::default();
+        for input_file in
+            iter::once(&self.input_file).chain(&self.additional_inputs)
+        {
+            let mut imported = self.format.import(input_file)?;
+            let stem = input_file
+                .file_stem()
+                .unwrap_or(input_file.as_os_str())
+                .to_string_lossy()
+                .into_owned();
+            // Each file gets its own folder. If the name is already used by
+            // another file's folder or by a recipe, add a suffix
+            let folder_id = iter::once(RecipeId::from(stem.clone()))
+                .chain((2..).map(|i| format!("{stem}_{i}").into()))
+                .find(|id| {
+                    collection.recipes.get(id).is_none()
+                        && imported.recipes.get(id).is_none()
+                })
+                .expect("Ran out of IDs");
+            imported
+                .nest_recipes(folder_id, None)
+                .context(format!("Error importing {input_file:?}"))?;
+            collection
+                .merge(imported, self.on_conflict.into())
+                .context(format!("Error merging {input_file:?}"))?;
+        }
+        Ok(collection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use rstest::rstest;
+    use slumber_core::{
+        collection::HasId,
+        test_util::{temp_dir, TempDir},
+    };
+
+    /// Write REST files and import them all together. Return the path of every
+    /// node in the imported recipe tree, e.g. `folder/recipe`
+    fn import_rest(temp_dir: &TempDir, files: &[(&str, &str)]) -> Vec<String> {
+        let paths = files
+            .iter()
+            .map(|(path, content)| {
+                let path = temp_dir.join(path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(&path, content).unwrap();
+                path
+            })
+            .collect_vec();
+        let command = ImportCommand {
+            format: Format::Rest,
+            input_file: paths[0].clone(),
+            output_file: None,
+            additional_inputs: paths[1..].to_vec(),
+            merge: None,
+            on_conflict: OnConflict::Skip,
+        };
+        let collection = command.import_all().unwrap();
+        collection
+            .recipes
+            .iter()
+            .map(|(lookup_key, node)| {
+                lookup_key.ancestors().iter().chain([node.id()]).join("/")
+            })
+            .collect()
+    }
+
+    /// Files with the same name in different directories each get their own
+    /// folder
+    #[rstest]
+    fn test_import_all_same_stem(temp_dir: TempDir) {
+        let paths = import_rest(
+            &temp_dir,
+            &[
+                ("users/api.http", "# @name list\nGET https://users.fish\n"),
+                ("fish/api.http", "# @name get\nGET https://fish.fish\n"),
+            ],
+        );
+        assert_eq!(paths, ["api", "api/list_0", "api_2", "api_2/get_0"]);
+    }
+
+    /// A file named the same as a recipe ID gets a suffixed folder, so the
+    /// recipe isn't lost
+    #[rstest]
+    #[case::same_file(
+        &[
+            ("login_0.http", "# @name login\nPOST https://fish.fish\n"),
+            ("fish.http", "# @name list\nGET https://fish.fish\n"),
+        ],
+        &["login_0_2", "login_0_2/login_0", "fish", "fish/list_0"],
+    )]
+    #[case::other_file(
+        &[
+            ("auth.http", "# @name login\nPOST https://fish.fish\n"),
+            ("login_0.http", "# @name list\nGET https://fish.fish\n"),
+        ],
+        &["auth", "auth/login_0", "login_0_2", "login_0_2/list_0"],
+    )]
+    fn test_import_all_stem_matches_recipe(
+        temp_dir: TempDir,
+        #[case] files: &[(&str, &str)],
+        #[case] expected: &[&str],
+    ) {
+        assert_eq!(import_rest(&temp_dir, files), expected);
+    }
+
+    /// `--on-conflict` only makes sense when combining collections
+    #[rstest]
+    #[case::alone(&[], false)]
+    #[case::input(&["--input", "b.yml"], true)]
+    #[case::merge(&["--merge", "slumber.yml"], true)]
+    fn test_on_conflict_requires(#[case] args: &[&str], #[case] valid: bool) {
+        let result = ImportCommand::try_parse_from(
+            ["import", "openapi", "a.yml", "--on-conflict", "rename"]
+                .into_iter()
+                .chain(args.iter().copied()),
+        );
+        assert_eq!(result.is_ok(), valid);
+    }
+}
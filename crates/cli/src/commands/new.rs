@@ -51,10 +51,10 @@ mod tests {
     use rstest::rstest;
     use slumber_core::{
         collection::{
-            Chain, ChainSource, Collection, Folder, Profile, Recipe,
-            RecipeBody, RecipeNode,
+            Chain, ChainSource, Collection, Folder, JsonTemplate, Profile,
+            Recipe, RecipeBody, RecipeNode,
         },
-        http::{content_type::ContentType, HttpMethod},
+        http::HttpMethod,
         test_util::{by_id, temp_dir, Factory, TempDir},
     };
     use std::{env, fs};
@@ -82,6 +82,9 @@ mod tests {
         };
         let global_args = GlobalArgs {
             file: global_file_arg.map(PathBuf::from),
+            color: Default::default(),
+            no_color: false,
+            error_format: Default::default(),
         };
 
         command.execute(global_args).await.unwrap();
@@ -106,6 +109,10 @@ mod tests {
                 id: "example".into(),
                 name: Some("Example Profile".into()),
                 default: false,
+                from_dotenv: None,
+                color: None,
+                label: None,
+                chains: Default::default(),
                 data: indexmap! {
                     "host".into() => "https://httpbin.org".into()
                 },
@@ -136,17 +143,19 @@ mod tests {
                         name: Some("Example Request 2".into()),
                         method: HttpMethod::Post,
                         url: "{{host}}/anything".into(),
-                        body: Some(RecipeBody::Raw {
-                            body: "{\n  \"data\": \"{{chains.example}}\"\n}"
-                                .into(),
-                            content_type: Some(ContentType::Json),
-                        }),
+                        body: Some(RecipeBody::Json(JsonTemplate::Object(
+                            indexmap! {
+                                "data".into() => JsonTemplate::String(
+                                    "{{chains.example}}".into(),
+                                ),
+                            },
+                        ))),
                         ..Recipe::factory(())
                     })]),
                 }),
             ])
             .into(),
-            _ignore: serde::de::IgnoredAny,
+            variables: Default::default(),
         };
         assert_eq!(collection, expected);
     }
@@ -0,0 +1,107 @@
+use crate::{
+    commands::request::{
+        check_protected, BuildRequestCommand, DisplayExchangeCommand,
+        HTTP_ERROR_EXIT_CODE,
+    },
+    GlobalArgs, Subcommand,
+};
+use anyhow::Context;
+use clap::Parser;
+use slumber_config::Config;
+use slumber_core::http::{HttpEngine, PreparedRequest};
+use std::{fs::File, path::PathBuf, process::ExitCode};
+
+/// Build and send a request as separate steps. This lets a request be rendered
+/// once and saved, then sent later (possibly on another machine)
+// This is a top-level command instead of `slumber request build` and
+// `slumber request send-prepared`, because `slumber request` takes a recipe ID
+// as a positional argument. Subcommands there would shadow any recipe named
+// `build` or `send-prepared`. Not a doc comment, so it stays out of `--help`
+#[derive(Clone, Debug, Parser)]
+pub struct PreparedCommand {
+    #[command(subcommand)]
+    step: PreparedStep,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+enum PreparedStep {
+    /// Render a recipe and save the request to a file, without sending it.
+    /// Send it later with `slumber prepared send`
+    Build {
+        #[clap(flatten)]
+        build: BuildRequestCommand,
+
+        /// File to save the prepared request to, as JSON
+        #[clap(long)]
+        output: PathBuf,
+    },
+
+    /// Send a request that was saved with `slumber prepared build`. No
+    /// templates are rendered, and the collection file is not needed. The
+    /// request is not saved to history.
+    Send {
+        /// File written by `slumber prepared build`
+        path: PathBuf,
+
+        #[clap(flatten)]
+        display: DisplayExchangeCommand,
+
+        /// Set process exit code based on HTTP response status. Same as
+        /// `--exit-status` for `slumber request`
+        #[clap(long)]
+        exit_status: bool,
+
+        /// Allow sending requests to hosts listed in the `protected_hosts`
+        /// config field. Without this, those requests are blocked.
+        #[clap(long)]
+        allow_protected: bool,
+    },
+}
+
+impl Subcommand for PreparedCommand {
+    async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        match self.step {
+            PreparedStep::Build { build, output } => {
+                let (_, ticket) = build.build_request(global, true).await?;
+                let prepared = PreparedRequest::new(&ticket)?;
+                let file = File::create(&output).with_context(|| {
+                    format!("Error opening file {output:?}")
+                })?;
+                serde_json::to_writer_pretty(file, &prepared).with_context(
+                    || format!("Error writing prepared request to {output:?}"),
+                )?;
+                Ok(ExitCode::SUCCESS)
+            }
+            PreparedStep::Send {
+                path,
+                display,
+                exit_status,
+                allow_protected,
+            } => {
+                let config = Config::load()?;
+                let file = File::open(&path)
+                    .with_context(|| format!("Error opening file {path:?}"))?;
+                let prepared: PreparedRequest = serde_json::from_reader(file)
+                    .with_context(|| {
+                    format!("Error loading prepared request from {path:?}")
+                })?;
+                let ticket =
+                    HttpEngine::new(&config.http).load_prepared(prepared)?;
+                check_protected(&config, ticket.record(), allow_protected)?;
+                display.write_request(ticket.record());
+
+                let exchange = ticket.send_detached().await?;
+                let status = exchange.response.status;
+                display.write_response(&exchange.response)?;
+
+                if exit_status
+                    && !config.success_statuses.contains(status.as_u16())
+                {
+                    Ok(ExitCode::from(HTTP_ERROR_EXIT_CODE))
+                } else {
+                    Ok(ExitCode::SUCCESS)
+                }
+            }
+        }
+    }
+}
@@ -1,44 +1,71 @@
 use crate::{
-    completions::{complete_profile, complete_recipe},
+    completions::{complete_folder, complete_profile, complete_recipe},
+    script::run_script,
+    transaction::save_transaction,
     GlobalArgs, Subcommand,
 };
-use anyhow::{anyhow, Context};
-use clap::{Parser, ValueHint};
+use anyhow::{bail, Context};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use clap::{Parser, ValueEnum, ValueHint};
 use clap_complete::ArgValueCompleter;
 use dialoguer::{Input, Password, Select as DialoguerSelect};
+use futures::future;
 use indexmap::IndexMap;
-use itertools::Itertools;
 use slumber_config::Config;
 use slumber_core::{
     collection::{Collection, CollectionFile, ProfileId, RecipeId},
-    db::{CollectionDatabase, Database, DatabaseMode},
+    db::{Database, DatabaseMode},
     http::{
-        BuildOptions, HttpEngine, RequestRecord, RequestSeed, RequestTicket,
-        ResponseRecord,
+        BuildOptions, Exchange, HttpEngine, RequestRecord, RequestSeed,
+        RequestTicket, ResponseRecord,
     },
-    template::{Prompt, Prompter, Select, TemplateContext, TemplateError},
-    util::{MaybeStr, ResultTraced},
+    template::{
+        Prompt, PromptCache, Prompter, Select, Template, TemplateChunk,
+        TemplateContext, TemplateError, Value,
+    },
+    util::{format_duration, MaybeStr, ResultTraced},
 };
 use std::{
     error::Error,
-    fs::OpenOptions,
+    fs::{File, OpenOptions},
     io::{self, IsTerminal, Write},
     path::{Path, PathBuf},
     process::ExitCode,
-    str::FromStr,
 };
 use tracing::warn;
 
 /// Exit code to return when `exit_status` flag is set and the HTTP response has
 /// an error status code
-const HTTP_ERROR_EXIT_CODE: u8 = 2;
+pub const HTTP_ERROR_EXIT_CODE: u8 = 2;
 
 /// Execute a single request, and print its response
 #[derive(Clone, Debug, Parser)]
 #[clap(aliases=&["req", "rq"])]
 pub struct RequestCommand {
+    /// ID of the recipe to render into a request
+    #[clap(
+        add = ArgValueCompleter::new(complete_recipe),
+        required_unless_present = "folder",
+    )]
+    recipe_id: Option<RecipeId>,
+
+    /// Send every recipe under this folder (including nested folders) and
+    /// print a table of results, instead of a single response
+    #[clap(
+        long,
+        conflicts_with = "recipe_id",
+        add = ArgValueCompleter::new(complete_folder),
+    )]
+    folder: Option<RecipeId>,
+
+    /// Send the request to this URL instead of the recipe's URL. Query
+    /// parameters, headers, authentication, and body still come from the
+    /// recipe. Can contain templates.
+    #[clap(long, conflicts_with = "folder")]
+    url: Option<Template>,
+
     #[clap(flatten)]
-    build_request: BuildRequestCommand,
+    context: RequestContextArgs,
 
     #[clap(flatten)]
     display: DisplayExchangeCommand,
@@ -48,23 +75,62 @@ pub struct RequestCommand {
     #[clap(long)]
     dry_run: bool,
 
+    /// Output format for the request printed by `--dry-run`
+    #[clap(
+        long,
+        value_enum,
+        default_value_t,
+        requires = "dry_run",
+        conflicts_with = "folder"
+    )]
+    format: DryRunFormat,
+
+    /// Render every template in the recipe and report all errors, instead of
+    /// stopping at the first one. The request is not sent, and triggered
+    /// sub-requests are not executed.
+    #[clap(long, conflicts_with_all = ["folder", "dry_run"])]
+    check: bool,
+
+    /// Save the request and response, with timing and metadata, to a single
+    /// file. The format is based on the extension: `.md` for markdown,
+    /// anything else for JSON
+    #[clap(long, conflicts_with_all = ["folder", "dry_run", "check"])]
+    save_transaction: Option<PathBuf>,
+
     /// Set process exit code based on HTTP response status. If the status is
-    /// <400, exit code is 0. If it's >=400, exit code is 2.
-    #[clap(long)]
+    /// in the `success_statuses` config field (2xx and 3xx by default), exit
+    /// code is 0. Otherwise, exit code is 2. With `--folder`, this applies if
+    /// *any* response has an error status.
+    #[clap(long, visible_alias = "fail-on-error-status")]
     exit_status: bool,
 }
 
-/// A helper for any subcommand that needs to build requests. This handles
-/// common args, as well as setting up context for rendering requests
+/// A helper for any subcommand that needs to build a single request. This
+/// handles common args, as well as setting up context for rendering requests
 #[derive(Clone, Debug, Parser)]
 pub struct BuildRequestCommand {
     /// ID of the recipe to render into a request
     #[clap(add = ArgValueCompleter::new(complete_recipe))]
     recipe_id: RecipeId,
 
-    /// ID of the profile to pull template values from. If omitted and the
-    /// collection has default profile defined, use that profile. Otherwise,
-    /// profile data will not be available.
+    /// Use this URL instead of the recipe's URL. Query parameters, headers,
+    /// authentication, and body still come from the recipe. Can contain
+    /// templates.
+    #[clap(long)]
+    url: Option<Template>,
+
+    #[clap(flatten)]
+    context: RequestContextArgs,
+}
+
+/// Args that control how requests are rendered, independent of *which*
+/// requests are being rendered
+#[derive(Clone, Debug, Parser)]
+pub struct RequestContextArgs {
+    /// ID of the profile to pull template values from. If omitted, fall back
+    /// to `$SLUMBER_PROFILE`, then the recipe's `profile` field, then the
+    /// `default_profile` config field, then the collection's default profile.
+    /// If none of those are defined, profile data will not be available.
     #[clap(
         long = "profile",
         short,
@@ -72,15 +138,28 @@ pub struct BuildRequestCommand {
     )]
     profile: Option<ProfileId>,
 
-    /// List of key=value template field overrides
+    /// List of template field overrides. Use key=value for a string, or
+    /// key:=value for a JSON value. key:=@file.json loads JSON from a file
     #[clap(
         long = "override",
         short = 'o',
-        value_parser = parse_key_val::<String, String>,
+        value_parser = parse_override,
         // There's no reasonable way of doing completions on this, so disable
         value_hint = ValueHint::Other,
     )]
-    overrides: Vec<(String, String)>,
+    overrides: Vec<(String, Value)>,
+
+    /// Seed for random template functions (e.g. `random_int()`), so generated
+    /// values are the same on every run. Overrides the `random_seed` config
+    /// field
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Allow sending requests to hosts listed in the `protected_hosts` config
+    /// field, including requests triggered by chains. Without this, those
+    /// requests are blocked.
+    #[clap(long)]
+    allow_protected: bool,
 }
 
 /// Helper for any subcommand that prints exchange (request/response)
@@ -102,48 +181,339 @@ pub struct DisplayExchangeCommand {
 
 impl Subcommand for RequestCommand {
     async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
-        let (database, ticket) = self
-            .build_request
-            // Don't execute sub-requests in a dry run
-            .build_request(global, !self.dry_run)
-            .await
-            .map_err(|error| {
-                // If the build failed because triggered requests are disabled,
-                // replace it with a custom error message
-                if TemplateError::has_trigger_disabled_error(&error) {
-                    error.context(
-                        "Triggered requests are disabled with `--dry-run`",
-                    )
-                } else {
-                    error
-                }
-            })?;
+        // Clap enforces that exactly one of these is given
+        match (self.recipe_id.clone(), self.folder.clone()) {
+            (Some(recipe_id), _) if self.check => {
+                self.check_recipe(global, recipe_id).await
+            }
+            (Some(recipe_id), _) => self.send_recipe(global, recipe_id).await,
+            (None, Some(folder_id)) => {
+                self.send_folder(global, folder_id).await
+            }
+            (None, None) => bail!("Recipe ID or `--folder` is required"),
+        }
+    }
+}
+
+impl RequestCommand {
+    /// Send a single recipe and print its response
+    async fn send_recipe(
+        self,
+        global: GlobalArgs,
+        recipe_id: RecipeId,
+    ) -> anyhow::Result<ExitCode> {
+        let allow_protected = self.context.allow_protected;
+        let (template_context, ticket) = BuildRequestCommand {
+            recipe_id: recipe_id.clone(),
+            url: self.url,
+            context: self.context,
+        }
+        // Don't execute sub-requests in a dry run
+        .build_request(global, !self.dry_run)
+        .await
+        .map_err(dry_run_error)?;
 
         if self.dry_run {
-            println!("{:#?}", ticket.record());
+            print_dry_run(ticket.record(), self.format)?;
             Ok(ExitCode::SUCCESS)
         } else {
+            let config = Config::load()?;
+            check_protected(&config, ticket.record(), allow_protected)?;
             self.display.write_request(ticket.record());
 
             // Run the request
-            let exchange = ticket.send(&database).await?;
+            let exchange = ticket.send(&template_context.database).await?;
             let status = exchange.response.status;
 
             self.display.write_response(&exchange.response)?;
+            if let Some(path) = &self.save_transaction {
+                save_transaction(&exchange, path)?;
+            }
+
+            // The script's exit code takes precedence over --exit-status
+            let script = template_context
+                .collection
+                .recipes
+                .get_recipe(&recipe_id)
+                .and_then(|recipe| recipe.script.as_deref());
+            if let Some(script) = script {
+                if let Some(code) = run_script(&recipe_id, script, &exchange)? {
+                    return Ok(ExitCode::from(code));
+                }
+            }
 
-            if self.exit_status && status.as_u16() >= 400 {
+            if self.exit_status
+                && !config.success_statuses.contains(status.as_u16())
+            {
                 Ok(ExitCode::from(HTTP_ERROR_EXIT_CODE))
             } else {
                 Ok(ExitCode::SUCCESS)
             }
         }
     }
+
+    /// Render each template in a recipe individually, and print an error for
+    /// each one that fails. Unlike a normal build, this doesn't stop at the
+    /// first failure, so all problems can be fixed in one pass.
+    async fn check_recipe(
+        self,
+        global: GlobalArgs,
+        recipe_id: RecipeId,
+    ) -> anyhow::Result<ExitCode> {
+        let (_, template_context) =
+            self.context
+                .template_context(global, false, Some(&recipe_id))?;
+        let recipe = template_context
+            .collection
+            .recipes
+            .try_get_recipe(&recipe_id)?;
+
+        let mut num_error = 0;
+        for (field, template) in recipe.templates() {
+            let chunks = template.render_chunks(&template_context).await;
+            for chunk in chunks {
+                if let TemplateChunk::Error(error) = chunk {
+                    num_error += 1;
+                    let mut error = anyhow::Error::from(error);
+                    if TemplateError::has_trigger_disabled_error(&error) {
+                        error = error.context(
+                            "Triggered requests are disabled with `--check`",
+                        );
+                    }
+                    eprintln!("{field}: {error:#}");
+                }
+            }
+        }
+
+        if num_error > 0 {
+            eprintln!("\n{num_error} template error(s) in `{recipe_id}`");
+            Ok(ExitCode::FAILURE)
+        } else {
+            eprintln!("All templates in `{recipe_id}` rendered successfully");
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+
+    /// Send every recipe in a folder, then print a summary table. Requests are
+    /// sent concurrently, up to the `max_concurrent_requests` limit enforced by
+    /// the HTTP engine.
+    async fn send_folder(
+        self,
+        global: GlobalArgs,
+        folder_id: RecipeId,
+    ) -> anyhow::Result<ExitCode> {
+        let allow_protected = self.context.allow_protected;
+        let (http_engine, mut template_context) =
+            self.context.template_context(global, !self.dry_run, None)?;
+        // Chain results are already shared across the folder because we use a
+        // single context, but different chains may still ask the same question
+        template_context.prompt_cache = Some(PromptCache::default());
+        let recipe_ids = template_context
+            .collection
+            .recipes
+            .folder_recipe_ids(&folder_id)?;
+        if recipe_ids.is_empty() {
+            eprintln!("Folder `{folder_id}` does not contain any recipes");
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        let config = Config::load()?;
+        let results = future::join_all(recipe_ids.iter().map(|recipe_id| {
+            let seed =
+                RequestSeed::new((*recipe_id).clone(), BuildOptions::default());
+            // Enqueue up front so queued recipes run in folder order
+            let queue = template_context
+                .collection
+                .recipes
+                .get_recipe(recipe_id)
+                .and_then(|recipe| recipe.queue.as_deref());
+            let mut queue_slot = http_engine.enqueue(queue);
+            let http_engine = &http_engine;
+            let template_context = &template_context;
+            let dry_run = self.dry_run;
+            let config = &config;
+            async move {
+                queue_slot.wait().await;
+                let ticket = http_engine
+                    .build(seed, template_context)
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .map_err(dry_run_error)?;
+                if dry_run {
+                    return Ok(FolderResult::Built(ticket));
+                }
+                check_protected(config, ticket.record(), allow_protected)?;
+                let exchange = ticket.send(&template_context.database).await?;
+                Ok::<_, anyhow::Error>(FolderResult::Sent(exchange))
+            }
+        }))
+        .await;
+
+        let success_statuses = &config.success_statuses;
+        let mut num_error = 0;
+        let mut num_http_error = 0;
+        let id_width = recipe_ids
+            .iter()
+            .map(|id| id.len())
+            .max()
+            .unwrap_or_default();
+        for (recipe_id, result) in recipe_ids.iter().zip(results) {
+            let outcome = match result {
+                Ok(FolderResult::Built(ticket)) => {
+                    let record = ticket.record();
+                    format!("{} {}", record.method, record.url)
+                }
+                Ok(FolderResult::Sent(exchange)) => {
+                    let status = exchange.response.status;
+                    if !success_statuses.contains(status.as_u16()) {
+                        num_http_error += 1;
+                    }
+                    format!(
+                        "{status}\t{}",
+                        format_duration(&exchange.duration())
+                    )
+                }
+                Err(error) => {
+                    num_error += 1;
+                    format!("Error: {error:#}")
+                }
+            };
+            println!("{recipe_id:<id_width$}\t{outcome}");
+        }
+
+        let num_success = recipe_ids.len() - num_error - num_http_error;
+        eprintln!(
+            "\n{num_success} succeeded, {num_http_error} HTTP error(s), \
+            {num_error} failed"
+        );
+        if num_error > 0 {
+            Ok(ExitCode::FAILURE)
+        } else if self.exit_status && num_http_error > 0 {
+            Ok(ExitCode::from(HTTP_ERROR_EXIT_CODE))
+        } else {
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+/// Output format for `--dry-run`
+#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+enum DryRunFormat {
+    /// HTTP-style request line, headers, and body
+    #[default]
+    Plain,
+    /// JSON object with method, URL, headers, and body
+    Json,
+    /// Equivalent curl command
+    Curl,
+}
+
+/// Print a built request for `--dry-run`
+fn print_dry_run(
+    request: &RequestRecord,
+    format: DryRunFormat,
+) -> anyhow::Result<()> {
+    match format {
+        DryRunFormat::Plain => {
+            println!(
+                "{} {} {}",
+                request.method, request.url, request.http_version
+            );
+            for (header, value) in &request.headers {
+                println!("{}: {}", header, MaybeStr(value.as_bytes()));
+            }
+            if let Some(body) = &request.body {
+                println!();
+                println!("{:#}", MaybeStr(body));
+            }
+        }
+        DryRunFormat::Json => {
+            // Group repeated headers into a list, same as the recipe format
+            let mut headers = serde_json::Map::new();
+            for name in request.headers.keys() {
+                let mut values = request
+                    .headers
+                    .get_all(name)
+                    .iter()
+                    .map(|value| {
+                        serde_json::Value::from(
+                            String::from_utf8_lossy(value.as_bytes())
+                                .into_owned(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let value = if values.len() == 1 {
+                    values.remove(0)
+                } else {
+                    values.into()
+                };
+                headers.insert(name.to_string(), value);
+            }
+            let mut json = serde_json::json!({
+                "method": request.method.to_string(),
+                "url": request.url.as_str(),
+                "headers": headers,
+                "body": null,
+            });
+            // Binary bodies can't be represented as a JSON string without
+            // losing bytes, so they get base64-encoded into a separate field
+            if let Some(body) = &request.body {
+                match std::str::from_utf8(body) {
+                    Ok(text) => json["body"] = text.into(),
+                    Err(_) => {
+                        json["body_base64"] =
+                            BASE64_STANDARD.encode(body).into()
+                    }
+                }
+            }
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+        DryRunFormat::Curl => println!("{}", request.to_curl()?),
+    }
+    Ok(())
+}
+
+/// Refuse to send a request to a protected host, unless the user opted in with
+/// `--allow-protected`. This has to be checked after the build, because the
+/// host isn't known until the URL is rendered.
+pub fn check_protected(
+    config: &Config,
+    request: &RequestRecord,
+    allow_protected: bool,
+) -> anyhow::Result<()> {
+    if !allow_protected && config.http.is_protected(&request.url) {
+        bail!(
+            "Blocked request to protected host `{}` ({} {}). Pass \
+            `--allow-protected` to send it anyway",
+            request.url.host_str().unwrap_or_default(),
+            request.method,
+            request.url,
+        );
+    }
+    Ok(())
+}
+
+/// Outcome of a single recipe in a folder run
+enum FolderResult {
+    /// Request was built but not sent (dry run)
+    Built(RequestTicket),
+    Sent(Exchange),
+}
+
+/// If the build failed because triggered requests are disabled, replace it
+/// with a custom error message
+fn dry_run_error(error: anyhow::Error) -> anyhow::Error {
+    if TemplateError::has_trigger_disabled_error(&error) {
+        error.context("Triggered requests are disabled with `--dry-run`")
+    } else {
+        error
+    }
 }
 
 impl BuildRequestCommand {
-    /// Render the request specified by the user. This returns the HTTP engine
-    /// too so it can be re-used if necessary (iff `trigger_dependencies` is
-    /// enabled).
+    /// Render the request specified by the user. This returns the template
+    /// context too, so its database can be re-used and its render state can be
+    /// inspected (e.g. to find sensitive values).
     ///
     /// `trigger_dependencies` controls whether chained requests can be executed
     /// if their triggers apply.
@@ -151,7 +521,36 @@ impl BuildRequestCommand {
         self,
         global: GlobalArgs,
         trigger_dependencies: bool,
-    ) -> anyhow::Result<(CollectionDatabase, RequestTicket)> {
+    ) -> anyhow::Result<(TemplateContext, RequestTicket)> {
+        let (http_engine, template_context) = self.context.template_context(
+            global,
+            trigger_dependencies,
+            Some(&self.recipe_id),
+        )?;
+        let options = BuildOptions {
+            url: self.url,
+            ..Default::default()
+        };
+        let seed = RequestSeed::new(self.recipe_id, options);
+        let request = http_engine.build(seed, &template_context).await?;
+        Ok((template_context, request))
+    }
+}
+
+impl RequestContextArgs {
+    /// Load the collection, config, and database, and set up everything needed
+    /// to render requests.
+    ///
+    /// `trigger_dependencies` controls whether chained requests can be executed
+    /// if their triggers apply. `recipe_id` is the recipe being built, if
+    /// there's a single one, so its `profile` field can be used for profile
+    /// selection.
+    pub fn template_context(
+        self,
+        global: GlobalArgs,
+        trigger_dependencies: bool,
+        recipe_id: Option<&RecipeId>,
+    ) -> anyhow::Result<(HttpEngine, TemplateContext)> {
         let collection_path = CollectionFile::try_path(None, global.file)?;
         let config = Config::load()?;
         let collection = Collection::load(&collection_path)?;
@@ -159,30 +558,26 @@ impl BuildRequestCommand {
         // really intuitive, and could have a large perf impact for scripting
         // and large responses
         let database = Database::load()?
+            .with_busy_timeout(config.database_busy_timeout)?
+            .with_compression(config.database_compression)
+            .with_encryption(&config.database_encryption)?
             .into_collection(&collection_path, DatabaseMode::ReadOnly)?;
-        let http_engine = HttpEngine::new(&config.http);
-
-        // Validate profile ID, so we can provide a good error if it's invalid
-        if let Some(profile_id) = &self.profile {
-            collection.profiles.get(profile_id).ok_or_else(|| {
-                anyhow!(
-                    "No profile with ID `{profile_id}`; options are: {}",
-                    collection.profiles.keys().format(", ")
-                )
-            })?;
-        }
+        let http_engine = HttpEngine::new(&config.http)
+            .allow_protected_hosts(self.allow_protected);
 
-        // Fall back to default profile if defined in the collection
-        let selected_profile = self.profile.or_else(|| {
-            let default_profile = collection.default_profile()?;
-            Some(default_profile.id.clone())
-        });
+        // Validate the profile ID so we can provide a good error if it's
+        // invalid, then fall back to env/recipe/config/default if not given
+        let selected_profile = collection.resolve_profile(
+            self.profile,
+            recipe_id,
+            config.default_profile.as_ref(),
+        )?;
 
-        // Build the request
         let overrides: IndexMap<_, _> = self.overrides.into_iter().collect();
         let template_context = TemplateContext {
             selected_profile,
             collection: collection.into(),
+            collection_path,
             // Passing the HTTP engine is how we tell the template renderer that
             // it's ok to execute subrequests during render
             http_engine: if trigger_dependencies {
@@ -190,14 +585,14 @@ impl BuildRequestCommand {
             } else {
                 None
             },
-            database: database.clone(),
+            database,
             overrides,
             prompter: Box::new(CliPrompter),
+            prompt_cache: None,
+            random_seed: self.seed.or(config.random_seed),
             state: Default::default(),
         };
-        let seed = RequestSeed::new(self.recipe_id, BuildOptions::default());
-        let request = http_engine.build(seed, &template_context).await?;
-        Ok((database, request))
+        Ok((http_engine, template_context))
     }
 }
 
@@ -325,18 +720,108 @@ impl Prompter for CliPrompter {
     }
 }
 
-/// Parse a single key=value pair for an argument
-fn parse_key_val<T, U>(
+/// Parse a single override argument. Supports two forms:
+/// - `key=value` overrides with a plain string
+/// - `key:=value` overrides with a typed value. The value is parsed as JSON,
+///   or if it starts with `@`, loaded as JSON from the given file path
+fn parse_override(
     s: &str,
-) -> Result<(T, U), Box<dyn Error + Send + Sync + 'static>>
-where
-    T: FromStr,
-    T::Err: Error + Send + Sync + 'static,
-    U: FromStr,
-    U::Err: Error + Send + Sync + 'static,
-{
+) -> Result<(String, Value), Box<dyn Error + Send + Sync + 'static>> {
     let (key, value) = s
         .split_once('=')
         .ok_or_else(|| format!("invalid key=value: no \"=\" found in `{s}`"))?;
-    Ok((key.parse()?, value.parse()?))
+    if let Some(key) = key.strip_suffix(':') {
+        let json: serde_json::Value = if let Some(path) =
+            value.strip_prefix('@')
+        {
+            let file = File::open(path)
+                .map_err(|error| format!("error opening `{path}`: {error}"))?;
+            serde_json::from_reader(file)
+                .map_err(|error| format!("invalid JSON in `{path}`: {error}"))?
+        } else {
+            serde_json::from_str(value)
+                .map_err(|error| format!("invalid JSON `{value}`: {error}"))?
+        };
+        Ok((key.to_owned(), json.into()))
+    } else {
+        Ok((key.to_owned(), Value::String(value.to_owned())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use slumber_core::{
+        assert_err,
+        test_util::{temp_dir, TempDir},
+    };
+
+    #[rstest]
+    #[case::string("field=value", "field", Value::String("value".into()))]
+    #[case::string_json("field=123", "field", Value::String("123".into()))]
+    #[case::string_colon("a:b=c", "a:b", Value::String("c".into()))]
+    #[case::empty("field=", "field", Value::String("".into()))]
+    #[case::integer("field:=123", "field", Value::Integer(123))]
+    #[case::float("field:=1.5", "field", Value::Float(1.5))]
+    #[case::boolean("field:=true", "field", Value::Boolean(true))]
+    #[case::null("field:=null", "field", Value::Null)]
+    #[case::quoted_string(r#"field:="hi""#, "field", Value::String("hi".into()))]
+    #[case::array(
+        "field:=[1,2]",
+        "field",
+        Value::Array(vec![Value::Integer(1), Value::Integer(2)]),
+    )]
+    fn test_parse_override(
+        #[case] input: &str,
+        #[case] expected_key: &str,
+        #[case] expected_value: Value,
+    ) {
+        let (key, value) = parse_override(input).unwrap();
+        assert_eq!(key, expected_key);
+        assert_eq!(value, expected_value);
+    }
+
+    #[rstest]
+    fn test_parse_override_file(temp_dir: TempDir) {
+        let path = temp_dir.join("data.json");
+        std::fs::write(&path, r#"{"a": [1, true]}"#).unwrap();
+        let (key, value) =
+            parse_override(&format!("field:=@{}", path.display())).unwrap();
+        assert_eq!(key, "field");
+        assert_eq!(
+            value,
+            Value::Object(
+                [(
+                    "a".to_owned(),
+                    Value::Array(vec![Value::Integer(1), Value::Boolean(true)])
+                )]
+                .into_iter()
+                .collect()
+            )
+        );
+    }
+
+    #[rstest]
+    #[case::no_equals("field", "no \"=\" found")]
+    #[case::invalid_json("field:=hello", "invalid JSON `hello`")]
+    #[case::missing_file("field:=@not-a-real-file.json", "error opening")]
+    fn test_parse_override_error(
+        #[case] input: &str,
+        #[case] expected_error: &str,
+    ) {
+        assert_err!(parse_override(input), expected_error);
+    }
+
+    /// Recipe IDs that match the names of other commands aren't shadowed by
+    /// them
+    #[rstest]
+    #[case::build("build")]
+    #[case::send("send")]
+    #[case::send_prepared("send-prepared")]
+    fn test_recipe_id_not_shadowed(#[case] recipe_id: &str) {
+        let command =
+            RequestCommand::try_parse_from(["request", recipe_id]).unwrap();
+        assert_eq!(command.recipe_id, Some(recipe_id.into()));
+    }
 }
@@ -1,13 +1,18 @@
-use crate::{GlobalArgs, Subcommand};
-use clap::Parser;
+use crate::{commands::request::BuildRequestCommand, GlobalArgs, Subcommand};
+use clap::{Parser, ValueEnum};
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools;
 use serde::Serialize;
 use slumber_config::Config;
 use slumber_core::{
-    collection::{Collection, CollectionFile},
+    collection::{Collection, CollectionFile, RecipeId},
     db::Database,
-    util::paths,
+    http::{RequestRecord, SENSITIVE_PLACEHOLDER},
+    template::TemplateError,
+    util::{paths, MaybeStr},
 };
-use std::{borrow::Cow, path::Path, process::ExitCode};
+use std::{borrow::Cow, fmt::Write, path::Path, process::ExitCode, sync::Arc};
+use url::{form_urlencoded, Url};
 
 /// Print meta information about Slumber (config, collections, etc.)
 #[derive(Clone, Debug, Parser)]
@@ -16,7 +21,7 @@ pub struct ShowCommand {
     target: ShowTarget,
 }
 
-#[derive(Copy, Clone, Debug, clap::Subcommand)]
+#[derive(Clone, Debug, clap::Subcommand)]
 enum ShowTarget {
     /// Print the path of all directories/files that Slumber uses
     Paths,
@@ -24,6 +29,37 @@ enum ShowTarget {
     Config,
     /// Print current request collection
     Collection,
+    /// Print the dependency graph between recipes, as defined by request
+    /// chains
+    Graph {
+        /// Output format for the graph
+        #[clap(long, value_enum, default_value_t)]
+        format: GraphFormat,
+    },
+    /// Render a recipe and print the resulting request, without sending it
+    Request {
+        #[clap(flatten)]
+        build_request: BuildRequestCommand,
+        /// Execute triggered sub-requests. By default, if a request dependency
+        /// is triggered (e.g. if it is expired), an error will be thrown
+        /// instead
+        #[clap(long)]
+        execute_triggers: bool,
+        /// Print sensitive values (chains marked `sensitive` and
+        /// authentication headers) instead of masking them
+        #[clap(long)]
+        reveal: bool,
+    },
+}
+
+/// Output format for the dependency graph
+#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+enum GraphFormat {
+    /// Indented tree, with each recipe followed by its dependencies
+    #[default]
+    Tree,
+    /// Graphviz DOT
+    Dot,
 }
 
 impl Subcommand for ShowCommand {
@@ -53,12 +89,326 @@ impl Subcommand for ShowCommand {
                 let collection = Collection::load(&collection_path)?;
                 println!("{}", to_yaml(&collection));
             }
+            ShowTarget::Graph { format } => {
+                let collection_path =
+                    CollectionFile::try_path(None, global.file)?;
+                let collection = Collection::load(&collection_path)?;
+                let graph = collection.dependency_graph();
+                let output = match format {
+                    GraphFormat::Tree => graph_to_tree(&graph),
+                    GraphFormat::Dot => graph_to_dot(&graph),
+                };
+                print!("{output}");
+            }
+            ShowTarget::Request {
+                build_request,
+                execute_triggers,
+                reveal,
+            } => {
+                let (template_context, ticket) = build_request
+                    .build_request(global, execute_triggers)
+                    .await
+                    .map_err(|error| {
+                        // If the build failed because triggered requests are
+                        // disabled, replace it with a custom error message
+                        if TemplateError::has_trigger_disabled_error(&error) {
+                            error.context(
+                                "Triggered requests are disabled by default; \
+                                 pass `--execute-triggers` to enable",
+                            )
+                        } else {
+                            error
+                        }
+                    })?;
+                let masker = if reveal {
+                    Masker::default()
+                } else {
+                    Masker::new(template_context.state.sensitive_values())
+                };
+                print_request(ticket.record(), &masker);
+            }
         }
         Ok(ExitCode::SUCCESS)
     }
 }
 
+/// Print a rendered request in a format similar to an HTTP/1.1 message
+fn print_request(request: &RequestRecord, masker: &Masker) {
+    print!("{}", format_request(request, masker));
+}
+
+/// Format a rendered request for [print_request]
+fn format_request(request: &RequestRecord, masker: &Masker) -> String {
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "{} {} {}",
+        request.method,
+        masker.mask(request.url.as_str()),
+        request.http_version
+    );
+    for (header, value) in &request.headers {
+        let value = if masker.enabled && value.is_sensitive() {
+            // Authentication headers are flagged by the HTTP client, and
+            // could contain an encoded version of a sensitive value
+            SENSITIVE_PLACEHOLDER.into()
+        } else {
+            masker.mask(&MaybeStr(value.as_bytes()).to_string())
+        };
+        let _ = writeln!(output, "{header}: {value}");
+    }
+    if let Some(body) = &request.body {
+        let _ = writeln!(output);
+        let _ = writeln!(
+            output,
+            "{}",
+            masker.mask(&format!("{:#}", MaybeStr(body)))
+        );
+    }
+    output
+}
+
+/// Replace sensitive values in rendered text with a placeholder
+#[derive(Debug, Default)]
+struct Masker {
+    enabled: bool,
+    /// Sensitive values that appear in the rendered request, in every
+    /// encoding they could appear in. Values that aren't valid UTF-8 can't be
+    /// searched for in text, so they're dropped
+    values: Vec<String>,
+}
+
+impl Masker {
+    fn new(values: Vec<Arc<Vec<u8>>>) -> Self {
+        let mut values: Vec<String> = values
+            .iter()
+            .filter_map(|value| std::str::from_utf8(value).ok())
+            .flat_map(Self::encodings)
+            .filter(|value| !value.is_empty())
+            .unique()
+            .collect();
+        // Replace longer values first, in case one contains another
+        values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+        Self {
+            enabled: true,
+            values,
+        }
+    }
+
+    /// Get each form a value can take in a rendered request. A value that's
+    /// rendered into the URL or body gets encoded when the request is built,
+    /// so masking only the raw value would leak the encoded one.
+    fn encodings(value: &str) -> [String; 5] {
+        // Use the same encoding as the request builder: form encoding for
+        // query parameters and form bodies, and the URL parser's encoding for
+        // values templated directly into the URL path or query
+        let form = form_urlencoded::byte_serialize(value.as_bytes()).collect();
+        let mut url =
+            Url::parse("http://localhost").expect("Static URL is valid");
+        url.set_path(value);
+        let path = url.path().strip_prefix('/').unwrap_or_default().to_owned();
+        url.set_query(Some(value));
+        let query = url.query().unwrap_or_default().to_owned();
+        // Strings in JSON bodies are escaped. Serializing a string can't fail
+        let json = serde_json::to_string(value).unwrap_or_default();
+        let json = json
+            .strip_prefix('"')
+            .and_then(|json| json.strip_suffix('"'))
+            .unwrap_or_default()
+            .to_owned();
+        [value.to_owned(), form, path, query, json]
+    }
+
+    fn mask<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for value in &self.values {
+            if text.contains(value.as_str()) {
+                text =
+                    text.replace(value.as_str(), SENSITIVE_PLACEHOLDER).into();
+            }
+        }
+        text
+    }
+}
+
+/// Recipe dependency graph, as returned by [Collection::dependency_graph]
+type DependencyGraph<'a> = IndexMap<&'a RecipeId, IndexSet<&'a RecipeId>>;
+
+/// Render a dependency graph as an indented tree. Each recipe that no other
+/// recipe depends on is a root, and its dependencies are nested beneath it.
+/// Recipes in a cycle have no root, so they're printed at the top level too.
+fn graph_to_tree(graph: &DependencyGraph) -> String {
+    fn add_node<'a>(
+        output: &mut String,
+        graph: &DependencyGraph<'a>,
+        path: &mut Vec<&'a RecipeId>,
+        recipe_id: &'a RecipeId,
+        printed: &mut IndexSet<&'a RecipeId>,
+    ) {
+        let indent = "  ".repeat(path.len());
+        if path.contains(&recipe_id) {
+            let _ = writeln!(output, "{indent}{recipe_id} (cycle)");
+            return;
+        }
+        let _ = writeln!(output, "{indent}{recipe_id}");
+        printed.insert(recipe_id);
+        path.push(recipe_id);
+        for dependency in graph.get(recipe_id).into_iter().flatten() {
+            add_node(output, graph, path, dependency, printed);
+        }
+        path.pop();
+    }
+
+    let dependencies: IndexSet<&RecipeId> =
+        graph.values().flatten().copied().collect();
+    let mut output = String::new();
+    let mut printed = IndexSet::new();
+    let roots = graph
+        .keys()
+        .filter(|recipe_id| !dependencies.contains(*recipe_id));
+    for recipe_id in roots {
+        add_node(&mut output, graph, &mut vec![], recipe_id, &mut printed);
+    }
+    // Anything unreachable from a root must be part of a cycle
+    for recipe_id in graph.keys() {
+        if !printed.contains(recipe_id) {
+            add_node(&mut output, graph, &mut vec![], recipe_id, &mut printed);
+        }
+    }
+    output
+}
+
+/// Render a dependency graph in Graphviz DOT format. Edges point from each
+/// recipe to the recipes it depends on.
+fn graph_to_dot(graph: &DependencyGraph) -> String {
+    // Quote IDs so any characters are allowed
+    let quote = |recipe_id: &RecipeId| {
+        format!("\"{}\"", recipe_id.to_string().replace('"', "\\\""))
+    };
+    let mut output = "digraph {\n".to_owned();
+    for (recipe_id, dependencies) in graph {
+        let _ = writeln!(output, "  {};", quote(recipe_id));
+        for dependency in dependencies {
+            let _ = writeln!(
+                output,
+                "  {} -> {};",
+                quote(recipe_id),
+                quote(dependency)
+            );
+        }
+    }
+    output.push_str("}\n");
+    output
+}
+
 fn to_yaml<T: Serialize>(value: &T) -> String {
     // Panic is intentional, indicates a wonky bug
     serde_yaml::to_string(value).expect("Error serializing")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderValue, AUTHORIZATION};
+    use rstest::rstest;
+    use slumber_core::test_util::{header_map, Factory};
+
+    /// Build a graph from static IDs
+    fn graph<'a>(
+        ids: &'a [RecipeId],
+        edges: &[(usize, &[usize])],
+    ) -> DependencyGraph<'a> {
+        edges
+            .iter()
+            .map(|(recipe, dependencies)| {
+                (
+                    &ids[*recipe],
+                    dependencies.iter().map(|i| &ids[*i]).collect(),
+                )
+            })
+            .collect()
+    }
+
+    /// Roots are printed at the top level with dependencies nested beneath,
+    /// and cycles are cut off
+    #[test]
+    fn test_graph_to_tree() {
+        let ids: Vec<RecipeId> = ["login", "get_user", "loop1", "loop2"]
+            .map(RecipeId::from)
+            .into();
+        let graph = graph(&ids, &[(0, &[]), (1, &[0]), (2, &[3]), (3, &[2])]);
+        assert_eq!(
+            graph_to_tree(&graph),
+            "get_user\n  login\nloop1\n  loop2\n    loop1 (cycle)\n"
+        );
+    }
+
+    #[test]
+    fn test_graph_to_dot() {
+        let ids: Vec<RecipeId> =
+            ["login", "get_user"].map(RecipeId::from).into();
+        let graph = graph(&ids, &[(0, &[]), (1, &[0])]);
+        assert_eq!(
+            graph_to_dot(&graph),
+            "digraph {\n  \"login\";\n  \"get_user\";\n  \
+             \"get_user\" -> \"login\";\n}\n"
+        );
+    }
+
+    /// Sensitive values are masked in their raw form, and in each form they
+    /// can be encoded into when the request is built
+    #[rstest]
+    #[case::raw("hunter2", "token=hunter2", "token=<sensitive>")]
+    #[case::form_encoded("a b&c", "token=a+b%26c", "token=<sensitive>")]
+    #[case::percent_encoded("a b", "/users/a%20b/", "/users/<sensitive>/")]
+    #[case::json_escaped(
+        r#"say "hi""#,
+        r#"{"message":"say \"hi\""}"#,
+        r#"{"message":"<sensitive>"}"#
+    )]
+    #[case::longest_first("abc", "abcd abc", "<sensitive> <sensitive>")]
+    #[case::unrelated("hunter2", "token=hunter3", "token=hunter3")]
+    fn test_masker(
+        #[case] value: &str,
+        #[case] text: &str,
+        #[case] expected: &str,
+    ) {
+        let masker = Masker::new(vec![
+            Arc::new(value.as_bytes().to_vec()),
+            Arc::new(b"abcd".to_vec()),
+        ]);
+        assert_eq!(masker.mask(text), expected);
+    }
+
+    /// Sensitive values are masked everywhere they appear in the printed
+    /// request, including in encoded form. With `--reveal`, nothing is masked
+    #[test]
+    fn test_format_request() {
+        let mut authorization = HeaderValue::from_static("Basic dXNlcjpwYXNz");
+        authorization.set_sensitive(true);
+        let mut headers = header_map([("X-Token", "a b&c")]);
+        headers.insert(AUTHORIZATION, authorization);
+        let request = RequestRecord {
+            url: "http://localhost/users/a%20b&c?token=a+b%26c"
+                .parse()
+                .unwrap(),
+            headers,
+            body: Some("token=a+b%26c".into()),
+            ..RequestRecord::factory(())
+        };
+
+        let masker = Masker::new(vec![Arc::new(b"a b&c".to_vec())]);
+        assert_eq!(
+            format_request(&request, &masker),
+            "GET http://localhost/users/<sensitive>?token=<sensitive> \
+             HTTP/1.1\nx-token: <sensitive>\nauthorization: <sensitive>\n\n\
+             token=<sensitive>\n"
+        );
+        assert_eq!(
+            format_request(&request, &Masker::default()),
+            "GET http://localhost/users/a%20b&c?token=a+b%26c HTTP/1.1\n\
+             x-token: a b&c\nauthorization: Basic dXNlcjpwYXNz\n\n\
+             token=a+b%26c\n"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{commands::request::RequestContextArgs, GlobalArgs, Subcommand};
+use anyhow::Context;
+use clap::Parser;
+use slumber_core::template::{Template, TemplateChunk};
+use std::{
+    io::{self, Write},
+    process::ExitCode,
+};
+
+/// Render templates against the collection, without building a request
+#[derive(Clone, Debug, Parser)]
+pub struct TemplateCommand {
+    #[command(subcommand)]
+    subcommand: TemplateSubcommand,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+enum TemplateSubcommand {
+    /// Render a template string and print the result. Useful for testing
+    /// profile values, chains, and functions in isolation. If any key fails
+    /// to render, each error is printed and nothing is written to stdout.
+    Render {
+        /// Template to render, e.g. '{{host}}/fishes'
+        template: String,
+
+        #[clap(flatten)]
+        context: RequestContextArgs,
+
+        /// Execute triggered sub-requests. By default, if a request dependency
+        /// is triggered (e.g. if it is expired), an error will be thrown
+        /// instead
+        #[clap(long)]
+        execute_triggers: bool,
+    },
+}
+
+impl Subcommand for TemplateCommand {
+    async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        match self.subcommand {
+            TemplateSubcommand::Render {
+                template,
+                context,
+                execute_triggers,
+            } => {
+                let template: Template =
+                    template.parse().context("Invalid template")?;
+                let (_, template_context) =
+                    context.template_context(global, execute_triggers, None)?;
+                let chunks = template.render_chunks(&template_context).await;
+
+                let mut output = Vec::new();
+                let mut num_error = 0;
+                for chunk in chunks {
+                    match chunk {
+                        TemplateChunk::Raw(text) => {
+                            output.extend_from_slice(text.as_bytes())
+                        }
+                        TemplateChunk::Rendered { value, .. } => {
+                            output.extend_from_slice(&value)
+                        }
+                        TemplateChunk::Error(error) => {
+                            num_error += 1;
+                            eprintln!("{:#}", anyhow::Error::from(error));
+                        }
+                    }
+                }
+
+                if num_error > 0 {
+                    eprintln!("\n{num_error} template error(s)");
+                    Ok(ExitCode::FAILURE)
+                } else {
+                    // Output may not be UTF-8, so write the raw bytes
+                    io::stdout().write_all(&output)?;
+                    Ok(ExitCode::SUCCESS)
+                }
+            }
+        }
+    }
+}
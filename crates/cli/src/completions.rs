@@ -23,12 +23,27 @@ pub fn complete_recipe(current: &OsStr) -> Vec<CompletionCandidate> {
         collection
             .recipes
             .iter()
-            // Include recipe IDs only. Folder IDs are never passed to the CLI
+            // Include recipe IDs only. Folders are completed separately
             .filter_map(|(_, node)| Some(&node.recipe()?.id)),
         current,
     )
 }
 
+/// Provide completions for folder IDs
+pub fn complete_folder(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Ok(collection) = load_collection() else {
+        return Vec::new();
+    };
+
+    get_candidates(
+        collection
+            .recipes
+            .iter()
+            .filter_map(|(_, node)| Some(&node.folder()?.id)),
+        current,
+    )
+}
+
 fn load_collection() -> anyhow::Result<Collection> {
     // For now we just lean on the default collection paths. In the future we
     // should be able to look for a --file arg in the command and use that path
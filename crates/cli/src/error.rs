@@ -0,0 +1,157 @@
+//! Top-level error reporting for CLI commands
+
+use clap::ValueEnum;
+use serde::Serialize;
+use slumber_core::{
+    http::{RequestBuildError, RequestError},
+    template::{ChainError, TemplateError},
+};
+use std::{error::Error as StdError, io};
+
+/// Format for errors printed to stderr when a command fails
+#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+pub enum ErrorFormat {
+    /// Human-readable message, followed by each cause on its own line
+    #[default]
+    Text,
+    /// A single JSON object with a stable error code, the message, and the
+    /// cause chain
+    Json,
+}
+
+/// Stable classification of an error, for consumers that want to branch on
+/// the failure kind without parsing messages. **These are part of the public
+/// interface**; don't rename them.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ErrorCode {
+    /// Request failed to build, e.g. due to a template error
+    RequestBuild,
+    /// Request was built, but failed to send or receive a response
+    Request,
+    /// Template rendering failed outside of a request build
+    Template,
+    /// Error from the HTTP client outside of a request
+    Http,
+    /// Collection or other file couldn't be deserialized
+    Parse,
+    /// File system or other I/O error
+    Io,
+    /// Anything we can't classify
+    Unknown,
+}
+
+impl ErrorCode {
+    /// Classify an error by its outermost recognized type in the cause chain.
+    /// The outer error is the more meaningful one (e.g. a template error that
+    /// caused a request build error is a request build error).
+    fn classify(error: &anyhow::Error) -> Self {
+        error
+            .chain()
+            .find_map(Self::from_error)
+            .unwrap_or(Self::Unknown)
+    }
+
+    fn from_error(error: &(dyn StdError + 'static)) -> Option<Self> {
+        if error.is::<RequestBuildError>() {
+            Some(Self::RequestBuild)
+        } else if error.is::<RequestError>() {
+            Some(Self::Request)
+        } else if error.is::<TemplateError>() || error.is::<ChainError>() {
+            Some(Self::Template)
+        } else if error.is::<reqwest::Error>() {
+            Some(Self::Http)
+        } else if error.is::<serde_yaml::Error>()
+            || error.is::<serde_json::Error>()
+        {
+            Some(Self::Parse)
+        } else if error.is::<io::Error>() {
+            Some(Self::Io)
+        } else {
+            None
+        }
+    }
+}
+
+/// JSON representation of an error
+#[derive(Debug, Serialize)]
+struct JsonError {
+    code: ErrorCode,
+    message: String,
+    /// Messages of each underlying cause, outermost first
+    causes: Vec<String>,
+}
+
+impl From<&anyhow::Error> for JsonError {
+    fn from(error: &anyhow::Error) -> Self {
+        Self {
+            code: ErrorCode::classify(error),
+            message: error.to_string(),
+            causes: error.chain().skip(1).map(ToString::to_string).collect(),
+        }
+    }
+}
+
+/// Print an error from a failed command to stderr in the given format
+pub fn print_error(error: &anyhow::Error, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => {
+            eprintln!("{error}");
+            error
+                .chain()
+                .skip(1)
+                .for_each(|cause| eprintln!("  {cause}"));
+        }
+        ErrorFormat::Json => {
+            // Serialization can't fail: the error is just strings and an enum
+            let json = serde_json::to_string(&JsonError::from(error))
+                .expect("Error serializing error");
+            eprintln!("{json}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    #[case::unknown(anyhow!("oh no"), ErrorCode::Unknown)]
+    #[case::template(
+        anyhow::Error::from(TemplateError::NoProfileSelected)
+            .context("Rendering"),
+        ErrorCode::Template
+    )]
+    #[case::io(
+        anyhow::Error::from(io::Error::other("disk on fire"))
+            .context("Reading file"),
+        ErrorCode::Io
+    )]
+    #[case::parse(
+        anyhow::Error::from(serde_json::from_str::<()>("{").unwrap_err()),
+        ErrorCode::Parse
+    )]
+    fn test_classify(
+        #[case] error: anyhow::Error,
+        #[case] expected: ErrorCode,
+    ) {
+        assert_eq!(ErrorCode::classify(&error), expected);
+    }
+
+    #[test]
+    fn test_json_error() {
+        let error = anyhow::Error::from(io::Error::other("disk on fire"))
+            .context("Reading file");
+        assert_eq!(
+            serde_json::to_value(JsonError::from(&error)).unwrap(),
+            json!({
+                "code": "io",
+                "message": "Reading file",
+                "causes": ["disk on fire"],
+            })
+        );
+    }
+}
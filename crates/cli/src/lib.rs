@@ -9,15 +9,26 @@
 
 mod commands;
 mod completions;
+mod error;
+mod script;
+mod transaction;
+
+pub use error::{print_error, ErrorFormat};
 
 use crate::commands::{
-    collections::CollectionsCommand, generate::GenerateCommand,
-    history::HistoryCommand, import::ImportCommand, new::NewCommand,
-    request::RequestCommand, show::ShowCommand,
+    collections::CollectionsCommand, completions::CompletionsCommand,
+    generate::GenerateCommand, history::HistoryCommand, import::ImportCommand,
+    new::NewCommand, prepared::PreparedCommand, request::RequestCommand,
+    show::ShowCommand, template::TemplateCommand,
 };
-use clap::{CommandFactory, Parser};
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
 use clap_complete::CompleteEnv;
-use std::{path::PathBuf, process::ExitCode};
+use std::{
+    env,
+    io::{self, IsTerminal},
+    path::PathBuf,
+    process::ExitCode,
+};
 
 const COMMAND_NAME: &str = "slumber";
 
@@ -31,6 +42,10 @@ const COMMAND_NAME: &str = "slumber";
 pub struct Args {
     #[command(flatten)]
     pub global: GlobalArgs,
+    /// Re-send the selected recipe whenever the collection file changes. Only
+    /// applies to the TUI
+    #[clap(long)]
+    pub watch: bool,
     #[command(subcommand)]
     pub subcommand: Option<CliCommand>,
 }
@@ -42,9 +57,28 @@ impl Args {
         CompleteEnv::with_factory(Args::command).complete();
     }
 
-    /// Alias for [clap::Parser::parse]
+    /// Alias for [clap::Parser::parse], with additional validation that
+    /// clap can't express declaratively
     pub fn parse() -> Self {
-        <Self as Parser>::parse()
+        let args = <Self as Parser>::parse();
+        if let Err(error) = args.validate() {
+            error.exit();
+        }
+        args
+    }
+
+    /// Reject argument combinations that would otherwise be silently ignored.
+    /// `--watch` is a parent arg, so clap's `conflicts_with` can't be used to
+    /// forbid it alongside a subcommand.
+    fn validate(&self) -> Result<(), clap::Error> {
+        if self.watch && self.subcommand.is_some() {
+            return Err(Self::command().error(
+                ErrorKind::ArgumentConflict,
+                "`--watch` only applies to the TUI and cannot be used with a \
+                subcommand",
+            ));
+        }
+        Ok(())
     }
 }
 
@@ -58,18 +92,83 @@ pub struct GlobalArgs {
     /// logic from the given directory rather than the current.
     #[clap(long, short)]
     pub file: Option<PathBuf>,
+    /// When to use color in output. `auto` enables color only if the output
+    /// is a terminal and the `NO_COLOR` environment variable is not set
+    #[clap(long, value_enum, default_value_t, global = true)]
+    color: ColorChoice,
+    /// Disable color in output. Alias for `--color never`
+    #[clap(long, global = true, conflicts_with = "color")]
+    no_color: bool,
+    /// Format for error messages when a command fails. `json` prints a single
+    /// object with a stable error `code`, the `message`, and its `causes`
+    #[clap(long, value_enum, default_value_t, global = true)]
+    pub error_format: ErrorFormat,
+}
+
+impl GlobalArgs {
+    /// Should color be used when writing to the given stream? All colored
+    /// output should be gated on this, so the user's choice is respected
+    /// everywhere.
+    pub fn color_enabled(&self, stream: &impl Terminal) -> bool {
+        if self.no_color {
+            return false;
+        }
+        match self.color {
+            ColorChoice::Auto => {
+                // https://no-color.org/
+                let no_color =
+                    env::var_os("NO_COLOR").is_some_and(|var| !var.is_empty());
+                !no_color && stream.is_terminal()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// A stream that may be attached to a terminal. This mirrors
+/// [std::io::IsTerminal], which is sealed and therefore can't be implemented
+/// by test fakes.
+pub trait Terminal {
+    /// Is this stream attached to a terminal?
+    fn is_terminal(&self) -> bool;
+}
+
+impl Terminal for io::Stdout {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(self)
+    }
+}
+
+impl Terminal for io::Stderr {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(self)
+    }
+}
+
+/// When to use color in CLI output
+#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+enum ColorChoice {
+    /// Use color if the output is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
 }
 
 /// A CLI subcommand
 #[derive(Clone, Debug, clap::Subcommand)]
 pub enum CliCommand {
     Collections(CollectionsCommand),
+    Completions(CompletionsCommand),
     Generate(GenerateCommand),
     History(HistoryCommand),
     Import(ImportCommand),
     New(NewCommand),
+    Prepared(PreparedCommand),
     Request(RequestCommand),
     Show(ShowCommand),
+    Template(TemplateCommand),
 }
 
 impl CliCommand {
@@ -77,12 +176,15 @@ impl CliCommand {
     pub async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
         match self {
             Self::Collections(command) => command.execute(global).await,
+            Self::Completions(command) => command.execute(global).await,
             Self::Generate(command) => command.execute(global).await,
             Self::History(command) => command.execute(global).await,
             Self::Import(command) => command.execute(global).await,
             Self::New(command) => command.execute(global).await,
+            Self::Prepared(command) => command.execute(global).await,
             Self::Request(command) => command.execute(global).await,
             Self::Show(command) => command.execute(global).await,
+            Self::Template(command) => command.execute(global).await,
         }
     }
 }
@@ -94,3 +196,70 @@ trait Subcommand {
     /// Execute the subcommand
     async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Stand-in for stdout/stderr, so tests don't depend on whether they're
+    /// run from a terminal
+    struct FakeStream {
+        terminal: bool,
+    }
+
+    impl Terminal for FakeStream {
+        fn is_terminal(&self) -> bool {
+            self.terminal
+        }
+    }
+
+    /// Test color selection
+    #[rstest]
+    #[case::default_terminal(&[], true, None, true)]
+    #[case::default_not_terminal(&[], false, None, false)]
+    #[case::default_no_color_env(&[], true, Some("1"), false)]
+    #[case::default_empty_no_color_env(&[], true, Some(""), true)]
+    #[case::always(&["--color", "always"], false, None, true)]
+    #[case::always_no_color_env(&["--color", "always"], true, Some("1"), true)]
+    #[case::never(&["--color", "never"], true, None, false)]
+    #[case::no_color_flag(&["--no-color"], true, None, false)]
+    #[case::subcommand(
+        &["show", "paths", "--color", "always"], false, None, true
+    )]
+    fn test_color_enabled(
+        #[case] args: &[&str],
+        #[case] terminal: bool,
+        #[case] no_color_env: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        let _guard = env_lock::lock_env([("NO_COLOR", no_color_env)]);
+        let args =
+            Args::try_parse_from([COMMAND_NAME].iter().chain(args)).unwrap();
+        assert_eq!(
+            args.global.color_enabled(&FakeStream { terminal }),
+            expected
+        );
+    }
+
+    /// `--watch` is only valid without a subcommand
+    #[rstest]
+    #[case::tui(&["--watch"], true)]
+    #[case::subcommand(&["--watch", "request", "login"], false)]
+    fn test_watch_validation(#[case] args: &[&str], #[case] valid: bool) {
+        let args =
+            Args::try_parse_from([COMMAND_NAME].iter().chain(args)).unwrap();
+        assert_eq!(args.validate().is_ok(), valid);
+    }
+
+    #[test]
+    fn test_color_conflict() {
+        assert!(Args::try_parse_from([
+            COMMAND_NAME,
+            "--color",
+            "always",
+            "--no-color"
+        ])
+        .is_err());
+    }
+}
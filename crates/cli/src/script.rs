@@ -0,0 +1,227 @@
+//! Recipe scripts, run against an exchange after a request is sent from the
+//! CLI. Scripts are written in [Rhai](https://rhai.rs), and get the request
+//! and response as object maps. They can print, extract values, and set the
+//! exit code of the process.
+
+use anyhow::{anyhow, Context};
+use reqwest::header::HeaderMap;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Position, Scope, INT};
+use slumber_core::{
+    collection::RecipeId,
+    http::{Exchange, RequestRecord, ResponseRecord},
+    util::MaybeStr,
+};
+
+/// Maximum number of operations a script can run. This stops a script that's
+/// stuck in an infinite loop, rather than hanging the CLI forever
+const MAX_OPERATIONS: u64 = 1_000_000;
+/// Maximum depth of nested function calls, to catch runaway recursion. Rhai's
+/// default is 64 in release builds but 8 in debug builds, because debug stack
+/// frames are much larger. Use one limit that's safe in both, so scripts
+/// behave the same regardless of the build.
+const MAX_CALL_LEVELS: usize = 32;
+/// Maximum length of a string created by a script, in bytes. This has to be
+/// large enough to hold response bodies
+const MAX_STRING_SIZE: usize = 128 * 1024 * 1024;
+
+/// Run a recipe's script against a completed exchange. If the script calls
+/// `exit` with a code, return that code so it can be used as the exit code of
+/// the process.
+pub fn run_script(
+    recipe_id: &RecipeId,
+    script: &str,
+    exchange: &Exchange,
+) -> anyhow::Result<Option<u8>> {
+    let mut scope = Scope::new();
+    scope.push_constant("request", request_map(&exchange.request));
+    scope.push_constant("response", response_map(&exchange.response)?);
+
+    match engine().run_with_scope(&mut scope, script) {
+        Ok(()) => Ok(None),
+        Err(error) => match *error {
+            EvalAltResult::ErrorTerminated(code, _) => {
+                let code = u8::try_from(code.as_int().unwrap_or_default())
+                    .map_err(|_| {
+                        anyhow!("Exit code must be between 0 and 255, got {code}")
+                    })?;
+                Ok(Some(code))
+            }
+            // Rhai's messages for exceeded limits don't say what the limit is
+            EvalAltResult::ErrorTooManyOperations(_) => Err(anyhow!(
+                "Script exceeded the limit of {MAX_OPERATIONS} operations; \
+                 check for an infinite loop"
+            )),
+            EvalAltResult::ErrorStackOverflow(_) => Err(anyhow!(
+                "Script exceeded the limit of {MAX_CALL_LEVELS} nested \
+                 function calls; check for infinite recursion"
+            )),
+            EvalAltResult::ErrorDataTooLarge(_, _) => Err(anyhow!(
+                "Script created a string larger than the limit of \
+                 {MAX_STRING_SIZE} bytes"
+            )),
+            // Rhai errors aren't Send/Sync, so we can't wrap them directly
+            error => Err(anyhow!("{error}")),
+        },
+    }
+    .with_context(|| format!("Error in script for recipe `{recipe_id}`"))
+}
+
+/// Build a scripting engine with our custom functions registered. Limits are
+/// set so a buggy script fails with an error instead of hanging the process or
+/// exhausting memory
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    // Keep debug output out of stdout, so it can't mix with the response body
+    engine.on_debug(|text, _, _| eprintln!("{text}"));
+    // Rhai's built-in `exit` is just an early return. Override it for
+    // integers so the script can set the exit code of the process. This uses
+    // the termination error because it can't be caught by `try`.
+    engine.register_fn("exit", |code: INT| -> Result<(), Box<EvalAltResult>> {
+        Err(EvalAltResult::ErrorTerminated(code.into(), Position::NONE).into())
+    });
+    engine
+}
+
+/// Convert a request to a script value
+fn request_map(request: &RequestRecord) -> Map {
+    let mut map = Map::new();
+    map.insert("method".into(), request.method.to_string().into());
+    map.insert("url".into(), request.url.to_string().into());
+    map.insert("headers".into(), headers_map(&request.headers).into());
+    map.insert(
+        "body".into(),
+        request
+            .body_str()
+            .ok()
+            .flatten()
+            .map(Dynamic::from)
+            .unwrap_or_default(),
+    );
+    map
+}
+
+/// Convert a response to a script value. If the body is JSON, it's parsed and
+/// included as `json`
+fn response_map(response: &ResponseRecord) -> anyhow::Result<Map> {
+    let json = response
+        .body
+        .text()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(text).ok())
+        .map(rhai::serde::to_dynamic)
+        .transpose()
+        .map_err(|error| anyhow!("{error}"))?
+        .unwrap_or_default();
+
+    let mut map = Map::new();
+    map.insert("status".into(), INT::from(response.status.as_u16()).into());
+    map.insert("headers".into(), headers_map(&response.headers).into());
+    map.insert(
+        "body".into(),
+        response.body.text().map(Dynamic::from).unwrap_or_default(),
+    );
+    map.insert("json".into(), json);
+    Ok(map)
+}
+
+/// Convert headers to a map of name:value. Header names are lowercase. If a
+/// header is repeated, its values are joined with `, `
+fn headers_map(headers: &HeaderMap) -> Map {
+    let mut map = Map::new();
+    for name in headers.keys() {
+        let value = headers
+            .get_all(name)
+            .iter()
+            .map(|value| MaybeStr(value.as_bytes()).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        map.insert(name.as_str().into(), value.into());
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+    use rstest::rstest;
+    use slumber_core::{
+        assert_err,
+        http::{RequestId, ResponseBody},
+        test_util::{header_map, Factory},
+    };
+
+    fn exchange() -> Exchange {
+        let id = RequestId::new();
+        Exchange::factory((
+            RequestRecord::factory(id),
+            ResponseRecord {
+                status: StatusCode::NOT_FOUND,
+                headers: header_map([("Content-Type", "application/json")]),
+                body: ResponseBody::new(
+                    r#"{"error": "Not found", "codes": [1, 2]}"#.into(),
+                ),
+                ..ResponseRecord::factory(id)
+            },
+        ))
+    }
+
+    #[rstest]
+    #[case::no_exit("let status = response.status;", None)]
+    #[case::exit("exit(3);", Some(3))]
+    #[case::exit_early("exit(3); exit(4);", Some(3))]
+    #[case::exit_uncaught("try { exit(3); } catch { exit(4); }", Some(3))]
+    #[case::exit_in_function("fn check() { exit(7); } check();", Some(7))]
+    #[case::status("if response.status != 200 { exit(1); }", Some(1))]
+    #[case::json(
+        r#"if response.json.error == "Not found" { exit(response.json.codes[1]); }"#,
+        Some(2)
+    )]
+    #[case::headers(
+        r#"if response.headers["content-type"] == "application/json" {
+            exit(5);
+        }"#,
+        Some(5)
+    )]
+    #[case::request(
+        r#"if request.method == "GET" && request.url == "http://localhost/url" {
+            exit(6);
+        }"#,
+        Some(6)
+    )]
+    fn test_run_script(#[case] script: &str, #[case] expected: Option<u8>) {
+        assert_eq!(
+            run_script(&"recipe1".into(), script, &exchange()).unwrap(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::syntax("exit(", "Syntax error")]
+    #[case::runtime("throw \"bad response\";", "bad response")]
+    #[case::exit_range("exit(256);", "Exit code must be between 0 and 255")]
+    #[case::unknown_variable("exit(code);", "Variable not found: code")]
+    #[case::infinite_loop(
+        "loop {}",
+        "Script exceeded the limit of 1000000 operations"
+    )]
+    #[case::infinite_recursion(
+        "fn f() { f() } f();",
+        "Script exceeded the limit of 32 nested function calls"
+    )]
+    #[case::string_too_large(
+        "let s = \"\"; s.pad(200_000_000, 'a');",
+        "Script created a string larger than the limit of 134217728 bytes"
+    )]
+    fn test_run_script_error(
+        #[case] script: &str,
+        #[case] expected_error: &str,
+    ) {
+        assert_err!(
+            run_script(&"recipe1".into(), script, &exchange()),
+            expected_error
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Export a single request/response exchange as a self-contained,
+//! human-readable file
+
+use anyhow::Context;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use slumber_core::{
+    collection::{ProfileId, RecipeId},
+    http::{Exchange, RequestId},
+    util::{format_duration, format_time_iso, MaybeStr},
+};
+use std::{fs, path::Path};
+
+/// File format for a saved transaction
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TransactionFormat {
+    Json,
+    Markdown,
+}
+
+impl TransactionFormat {
+    /// Pick a format based on a file's extension. `.md` and `.markdown` get
+    /// markdown, anything else gets JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("md" | "markdown") => Self::Markdown,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// Write a request and its response to a file. The format is determined by
+/// the file extension.
+pub fn save_transaction(
+    exchange: &Exchange,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let content = match TransactionFormat::from_path(path) {
+        TransactionFormat::Json => {
+            serde_json::to_string_pretty(&Transaction::new(exchange))?
+        }
+        TransactionFormat::Markdown => to_markdown(exchange),
+    };
+    fs::write(path, content)
+        .with_context(|| format!("Error writing transaction to {path:?}"))
+}
+
+/// Serializable form of an [Exchange]
+#[derive(Debug, Serialize)]
+struct Transaction<'a> {
+    id: RequestId,
+    profile_id: Option<&'a ProfileId>,
+    recipe_id: &'a RecipeId,
+    start_time: String,
+    end_time: String,
+    duration_ms: i64,
+    request: TransactionRequest,
+    response: TransactionResponse,
+}
+
+#[derive(Debug, Serialize)]
+struct TransactionRequest {
+    method: String,
+    url: String,
+    http_version: String,
+    headers: Vec<TransactionHeader>,
+    body: TransactionBody,
+}
+
+#[derive(Debug, Serialize)]
+struct TransactionResponse {
+    status: u16,
+    http_version: String,
+    headers: Vec<TransactionHeader>,
+    body: TransactionBody,
+}
+
+/// Headers are stored as a list rather than a map, because names can repeat
+#[derive(Debug, Serialize)]
+struct TransactionHeader {
+    name: String,
+    value: String,
+}
+
+/// A request/response body. Binary bodies are omitted, because they aren't
+/// useful in a human-readable file. Only their size is included.
+#[derive(Debug, Serialize)]
+struct TransactionBody {
+    size: usize,
+    text: Option<String>,
+}
+
+impl<'a> Transaction<'a> {
+    fn new(exchange: &'a Exchange) -> Self {
+        let request = &exchange.request;
+        let response = &exchange.response;
+        Self {
+            id: exchange.id,
+            profile_id: request.profile_id.as_ref(),
+            recipe_id: &request.recipe_id,
+            start_time: format_time_iso(&exchange.start_time).to_string(),
+            end_time: format_time_iso(&exchange.end_time).to_string(),
+            duration_ms: exchange.duration().num_milliseconds(),
+            request: TransactionRequest {
+                method: request.method.to_string(),
+                url: request.url.to_string(),
+                http_version: request.http_version.to_string(),
+                headers: headers(&request.headers),
+                body: TransactionBody::new(
+                    request.body.as_deref().unwrap_or_default(),
+                ),
+            },
+            response: TransactionResponse {
+                status: response.status.as_u16(),
+                http_version: response.http_version.to_string(),
+                headers: headers(&response.headers),
+                body: TransactionBody::new(response.body.bytes()),
+            },
+        }
+    }
+}
+
+impl TransactionBody {
+    fn new(bytes: &[u8]) -> Self {
+        Self {
+            size: bytes.len(),
+            text: std::str::from_utf8(bytes).ok().map(String::from),
+        }
+    }
+}
+
+fn headers(headers: &HeaderMap) -> Vec<TransactionHeader> {
+    headers
+        .iter()
+        .map(|(name, value)| TransactionHeader {
+            name: name.to_string(),
+            value: MaybeStr(value.as_bytes()).to_string(),
+        })
+        .collect()
+}
+
+/// Render an exchange as a markdown document. Request and response are each
+/// shown in a code block, in the same form as they'd appear on the wire
+fn to_markdown(exchange: &Exchange) -> String {
+    let request = &exchange.request;
+    let response = &exchange.response;
+
+    let mut request_block = format!(
+        "{} {} {}\n",
+        request.method, request.url, request.http_version
+    );
+    write_headers(&mut request_block, &request.headers);
+    write_body(
+        &mut request_block,
+        request.body.as_deref().unwrap_or_default(),
+    );
+
+    let mut response_block =
+        format!("{} {}\n", response.http_version, response.status);
+    write_headers(&mut response_block, &response.headers);
+    write_body(&mut response_block, response.body.bytes());
+
+    let profile = request
+        .profile_id
+        .as_ref()
+        .map(|profile_id| format!("`{profile_id}`"))
+        .unwrap_or_else(|| "None".into());
+    format!(
+        "# {method} {url}\n\
+        \n\
+        - **Recipe:** `{recipe_id}`\n\
+        - **Profile:** {profile}\n\
+        - **Request ID:** `{id}`\n\
+        - **Sent:** {start_time}\n\
+        - **Duration:** {duration}\n\
+        \n\
+        ## Request\n\
+        \n\
+        {request_block}\
+        \n\
+        ## Response\n\
+        \n\
+        {response_block}",
+        method = request.method,
+        url = request.url,
+        recipe_id = request.recipe_id,
+        id = exchange.id,
+        start_time = format_time_iso(&exchange.start_time),
+        duration = format_duration(&exchange.duration()),
+        request_block = code_block(&request_block),
+        response_block = code_block(&response_block),
+    )
+}
+
+fn write_headers(output: &mut String, headers: &HeaderMap) {
+    for (name, value) in headers {
+        output.push_str(&format!("{name}: {}\n", MaybeStr(value.as_bytes())));
+    }
+}
+
+fn write_body(output: &mut String, body: &[u8]) {
+    if body.is_empty() {
+        return;
+    }
+    output.push('\n');
+    match std::str::from_utf8(body) {
+        Ok(text) => {
+            output.push_str(text);
+            if !text.ends_with('\n') {
+                output.push('\n');
+            }
+        }
+        Err(_) => {
+            output.push_str(&format!("<binary body, {} bytes>\n", body.len()))
+        }
+    }
+}
+
+/// Wrap content in a fenced code block. The fence is made longer than any run
+/// of backticks in the content, so the content can't break out of the block
+fn code_block(content: &str) -> String {
+    let longest_run = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat((longest_run + 1).max(3));
+    format!("{fence}http\n{content}{fence}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::json("transaction.json", TransactionFormat::Json)]
+    #[case::markdown("transaction.md", TransactionFormat::Markdown)]
+    #[case::markdown_long("transaction.markdown", TransactionFormat::Markdown)]
+    #[case::no_extension("transaction", TransactionFormat::Json)]
+    fn test_format_from_path(
+        #[case] path: &str,
+        #[case] expected: TransactionFormat,
+    ) {
+        assert_eq!(TransactionFormat::from_path(Path::new(path)), expected);
+    }
+
+    #[rstest]
+    #[case::plain("body\n", "```http\nbody\n```\n")]
+    #[case::backticks("```\n", "````http\n```\n````\n")]
+    fn test_code_block(#[case] content: &str, #[case] expected: &str) {
+        assert_eq!(code_block(content), expected);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::mime::MimePattern;
+use glob::{Pattern, PatternError};
+use mime::Mime;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Rules to force how response bodies are prettified and highlighted in the
+/// TUI, regardless of their `Content-Type` header. Useful for APIs that return
+/// JSON as `text/plain` or similar.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(transparent)]
+pub struct ContentTypeOverrides(Vec<ContentTypeOverride>);
+
+impl ContentTypeOverrides {
+    /// Get the format of the **first** rule that matches the given request URL
+    /// and response MIME type. Return `None` if no rule matches, in which case
+    /// the `Content-Type` header should be used.
+    pub fn get(&self, url: &str, mime: Option<&Mime>) -> Option<BodyFormat> {
+        self.0
+            .iter()
+            .find(|rule| rule.matches(url, mime))
+            .map(|rule| rule.format)
+    }
+}
+
+/// A single entry in [ContentTypeOverrides]. A rule matches if **all** of its
+/// given patterns match. If no patterns are given, it matches everything.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(deny_unknown_fields)]
+pub struct ContentTypeOverride {
+    /// Glob pattern for the request URL, e.g. `https://example.com/legacy/*`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<UrlPattern>,
+    /// Glob pattern for the response MIME type, e.g. `text/*`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<MimePattern>,
+    /// How to treat matching bodies
+    format: BodyFormat,
+}
+
+impl ContentTypeOverride {
+    fn matches(&self, url: &str, mime: Option<&Mime>) -> bool {
+        let url_matches = self
+            .url
+            .as_ref()
+            .map_or(true, |pattern| pattern.matches(url));
+        let mime_matches = match (&self.content_type, mime) {
+            (None, _) => true,
+            (Some(pattern), Some(mime)) => pattern.matches(mime.essence_str()),
+            // A pattern can't match a missing header
+            (Some(_), None) => false,
+        };
+        url_matches && mime_matches
+    }
+}
+
+/// How a response body should be prettified and highlighted
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyFormat {
+    Json,
+    Xml,
+    /// Show the body as-is, with no prettification or highlighting
+    Text,
+}
+
+/// Newtype for [glob::Pattern] so we can define ser/de for it
+#[derive(
+    Clone,
+    Debug,
+    derive_more::Display,
+    derive_more::Deref,
+    Serialize,
+    Deserialize,
+    PartialEq,
+)]
+#[serde(try_from = "String", into = "String")]
+struct UrlPattern(Pattern);
+
+impl From<UrlPattern> for String {
+    fn from(value: UrlPattern) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for UrlPattern {
+    type Error = PatternError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(Self(Pattern::from_str(&value)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use slumber_core::util::parse_yaml;
+
+    #[rstest]
+    #[case::url(
+        "https://legacy.example.com/users",
+        None,
+        Some(BodyFormat::Json)
+    )]
+    #[case::url_and_mime(
+        "https://example.com/feed",
+        Some("text/plain"),
+        Some(BodyFormat::Xml)
+    )]
+    #[case::mime_only(
+        "https://example.com/users",
+        Some("text/plain; charset=utf-8"),
+        Some(BodyFormat::Text)
+    )]
+    #[case::priority(
+        "https://legacy.example.com/users",
+        Some("text/plain"),
+        Some(BodyFormat::Json)
+    )]
+    #[case::missing_mime("https://example.com/feed", None, None)]
+    #[case::no_match("https://example.com/users", Some("text/html"), None)]
+    fn test_get(
+        #[case] url: &str,
+        #[case] mime: Option<&str>,
+        #[case] expected: Option<BodyFormat>,
+    ) {
+        let yaml = "
+- url: https://legacy.example.com/*
+  format: json
+- url: https://example.com/feed
+  content_type: text/plain
+  format: xml
+- content_type: text/plain
+  format: text
+";
+        let overrides: ContentTypeOverrides =
+            parse_yaml(yaml.as_bytes()).unwrap();
+        let mime = mime.map(|mime| mime.parse::<Mime>().unwrap());
+        assert_eq!(overrides.get(url, mime.as_ref()), expected);
+    }
+}
@@ -146,6 +146,17 @@ pub enum Action {
     Edit,
     /// Reset temporary recipe override to its default value
     Reset,
+    /// Temporarily switch the selected recipe to the next common HTTP method
+    #[display("Cycle Method")]
+    CycleMethod,
+    /// Flip a single recipe field between its rendered preview and raw
+    /// template
+    #[display("Toggle Raw")]
+    ToggleRaw,
+    /// Switch the recipe list between the folder tree and a flat list
+    /// grouped by HTTP method
+    #[display("Toggle Grouping")]
+    ToggleGrouping,
     /// Open content in the configured external pager
     View,
     /// Browse request history
@@ -159,11 +170,20 @@ pub enum Action {
     /// Force a collection reload (typically it's automatic)
     #[display("Reload Collection")]
     ReloadCollection,
+    /// Send the most recent request again, with the same options
+    #[display("Resend Last Request")]
+    ResendLast,
     /// Embiggen a pane
     Fullscreen,
     /// Open the actions modal
     #[display("Actions")]
     OpenActions,
+    /// Open the command palette, to search and run any bound action
+    #[display("Command Palette")]
+    CommandPalette,
+    /// Open the log of recent notifications and errors
+    #[display("Notifications")]
+    NotificationLog,
     #[display("Help")]
     /// Open the help modal
     OpenHelp,
@@ -201,6 +221,57 @@ impl Action {
             _ => true,
         }
     }
+
+    /// Short description of what this action does, for the command palette
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::LeftClick
+            | Action::RightClick
+            | Action::ScrollUp
+            | Action::ScrollDown
+            | Action::ScrollLeft
+            | Action::ScrollRight => "Mouse action",
+            Action::Quit => "Exit current dialog, or the entire app",
+            Action::ForceQuit => "Exit the app, regardless",
+            Action::PreviousPane => "Select previous pane in the cycle",
+            Action::NextPane => "Select next pane in the cycle",
+            Action::Up
+            | Action::Down
+            | Action::Left
+            | Action::Right
+            | Action::PageUp
+            | Action::PageDown
+            | Action::Home
+            | Action::End => "Navigate",
+            Action::Submit => "Send a request, submit a text box, etc.",
+            Action::Toggle => "Toggle a checkbox on/off",
+            Action::Cancel => "Cancel current dialog or request",
+            Action::Edit => "Apply a temporary override to a recipe value",
+            Action::Reset => "Reset temporary recipe override to its default",
+            Action::CycleMethod => {
+                "Temporarily switch the recipe to the next HTTP method"
+            }
+            Action::ToggleRaw => "Show raw template for the selected field",
+            Action::ToggleGrouping => "Group recipes by folder or HTTP method",
+            Action::View => "Open the selected content in your pager",
+            Action::History => "Open request history for a recipe",
+            Action::Search => "Open/select search for current pane",
+            Action::Export => "Enter command for exporting response data",
+            Action::ReloadCollection => "Force reload collection file",
+            Action::ResendLast => {
+                "Re-send the most recent request with the same options"
+            }
+            Action::Fullscreen => "Fullscreen current pane",
+            Action::OpenActions => "Open actions menu",
+            Action::CommandPalette => "Search and run any action",
+            Action::NotificationLog => "Show recent notifications and errors",
+            Action::OpenHelp => "Open help dialog",
+            Action::SelectProfileList => "Open Profile List dialog",
+            Action::SelectRecipeList => "Select Recipe List pane",
+            Action::SelectRecipe => "Select Recipe pane",
+            Action::SelectResponse => "Select Request/Response pane",
+        }
+    }
 }
 
 /// One or more key combinations, which should correspond to a single action
@@ -9,26 +9,34 @@
 //! crate, instead of the core crate, it at least pushes those dependencies down
 //! the compile chain a bit further.
 
+mod body_format;
 mod input;
 mod mime;
+mod status;
 mod theme;
+mod timezone;
 
+pub use body_format::{BodyFormat, ContentTypeOverrides};
 pub use input::{Action, InputBinding, KeyCombination};
-pub use theme::Theme;
+pub use status::{StatusCodeMatcher, StatusCodes};
+pub use theme::{Theme, ThemePreset};
+pub use timezone::Timezone;
 
 use crate::mime::MimeMap;
 use anyhow::Context;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use slumber_core::{
-    http::HttpEngineConfig,
+    collection::{serde_duration, ProfileId},
+    db::{Database, DatabaseEncryptionConfig, HistoryRetention},
+    http::{content_type::Indent, HttpEngineConfig, HttpMethod},
     util::{
         parse_yaml,
         paths::{self, create_parent, expand_home},
         ResultTraced,
     },
 };
-use std::{env, fs::OpenOptions, path::PathBuf};
+use std::{env, fs::OpenOptions, path::PathBuf, time::Duration};
 use tracing::info;
 
 const PATH_ENV_VAR: &str = "SLUMBER_CONFIG_PATH";
@@ -41,14 +49,46 @@ const FILE: &str = "config.yml";
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Config {
+    /// Send a request automatically when a recipe is selected in the TUI. This
+    /// only applies to safe methods (e.g. `GET`), unless overridden per
+    /// recipe
+    pub auto_send: bool,
     /// Configuration for in-app query and export commands
     pub commands: CommandsConfig,
+    /// Force response bodies to be prettified/highlighted as a particular
+    /// format, based on the request URL and/or response content type. Takes
+    /// precedence over the `Content-Type` header
+    pub content_type_overrides: ContentTypeOverrides,
+    /// HTTP methods to rotate through with the `cycle_method` action in the
+    /// TUI, in order
+    pub cycle_methods: Vec<HttpMethod>,
+    /// At-rest encryption for request/response bodies in the history database
+    pub database_encryption: DatabaseEncryptionConfig,
+    /// Compress request/response bodies in the history database
+    pub database_compression: bool,
+    /// How long to wait for another Slumber process to release its lock on
+    /// the history database before a query fails
+    #[serde(with = "serde_duration")]
+    pub database_busy_timeout: Duration,
+    /// Profile to use when none is selected via `--profile`,
+    /// `$SLUMBER_PROFILE`, or the recipe. Takes precedence over the profile
+    /// marked `default: true` in the collection. Ignored for collections that
+    /// don't have a profile with this ID.
+    pub default_profile: Option<ProfileId>,
+    /// Limits on how much request history to keep. Old requests are deleted
+    /// when the TUI starts. Unlimited by default.
+    pub history_retention: HistoryRetention,
+    /// Maximum number of requests to keep in history, across all collections.
+    /// When exceeded, the oldest requests are deleted. Unlimited by default.
+    pub history_max_rows: Option<usize>,
     /// Command to use for in-app editing. If provided, overrides
     /// `VISUAL`/`EDITOR` environment variables. This only supports a single
     /// command, *not* a content type map. This is because there isn't much
     /// value in it, and plumbing the content type around to support it is
     /// annoying.
     pub editor: Option<String>,
+    /// Indentation for prettified JSON and XML bodies in the TUI
+    pub indent: Indent,
     /// Command to use to browse response bodies. If provided, overrides
     /// `PAGER` environment variable.  This could be a single command, or a map
     /// of {content_type: command} to use different commands based on response
@@ -60,10 +100,36 @@ pub struct Config {
     /// Should templates be rendered inline in the UI, or should we show the
     /// raw text?
     pub preview_templates: bool,
+    /// Seed for random template functions (e.g. `random_int()`). If set,
+    /// random values are generated deterministically, so each request
+    /// produces the same values every time
+    pub random_seed: Option<u64>,
+    /// Should image responses be rendered as images in the TUI?
+    pub inline_images: InlineImages,
+    /// After the collection file changes, wait this long for further changes
+    /// before reloading. Editors often write a file several times per save,
+    /// so this coalesces them into a single reload
+    #[serde(with = "serde_duration")]
+    pub reload_debounce: Duration,
+    /// Batch writes of TUI state (selections, toggles, etc.) and save them to
+    /// the database on this interval, as well as on exit. If `None`, state is
+    /// written immediately on every change
+    #[serde(with = "serde_duration::option")]
+    pub ui_state_save_interval: Option<Duration>,
+    /// Response status codes that are considered successful. Others are
+    /// displayed as errors, and can fail the CLI with `--exit-status`
+    pub success_statuses: StatusCodes,
     /// Overrides for default key bindings
     pub input_bindings: IndexMap<Action, InputBinding>,
-    /// Visual configuration for the TUI (e.g. colors)
-    pub theme: Theme,
+    /// Visual configuration for the TUI (e.g. colors). If not given, the TUI
+    /// picks a default based on the terminal's background color.
+    pub theme: Option<Theme>,
+    /// Path to a YAML file containing a [Theme]. If given, the theme is loaded
+    /// from this file and replaces `theme`. Relative paths are resolved
+    /// relative to the config file.
+    pub theme_file: Option<PathBuf>,
+    /// Timezone to display timestamps in
+    pub timezone: Timezone,
     /// Enable debug monitor in TUI
     pub debug: bool,
 }
@@ -112,7 +178,14 @@ impl Config {
                 .append(true)
                 .read(true)
                 .open(&path)?;
-            let config = parse_yaml::<Self>(&file)?;
+            let mut config = parse_yaml::<Self>(&file)?;
+            if let Some(theme_file) = &config.theme_file {
+                let theme_path = path
+                    .parent()
+                    .unwrap_or(&path)
+                    .join(expand_home(theme_file));
+                config.theme = Some(Theme::load(&theme_path)?);
+            }
             Ok::<_, anyhow::Error>(config)
         })()
         .context(format!("Error loading configuration from {path:?}"))
@@ -123,18 +196,56 @@ impl Config {
 impl Default for Config {
     fn default() -> Self {
         Self {
+            auto_send: false,
             commands: CommandsConfig::default(),
+            content_type_overrides: ContentTypeOverrides::default(),
+            cycle_methods: vec![
+                HttpMethod::Get,
+                HttpMethod::Post,
+                HttpMethod::Put,
+                HttpMethod::Patch,
+                HttpMethod::Delete,
+            ],
+            database_encryption: Default::default(),
+            database_busy_timeout: Database::DEFAULT_BUSY_TIMEOUT,
+            database_compression: false,
+            default_profile: None,
+            history_retention: HistoryRetention::default(),
+            history_max_rows: None,
             editor: Default::default(),
+            indent: Indent::default(),
             pager: Default::default(),
             http: Default::default(),
             preview_templates: true,
+            random_seed: None,
+            inline_images: InlineImages::default(),
+            reload_debounce: Duration::from_millis(300),
+            ui_state_save_interval: None,
+            success_statuses: StatusCodes::default(),
             input_bindings: Default::default(),
             theme: Default::default(),
+            theme_file: None,
+            timezone: Default::default(),
             debug: false,
         }
     }
 }
 
+/// Control rendering of image responses in the TUI
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InlineImages {
+    /// Render images only if the terminal supports a graphics protocol
+    /// (kitty, iTerm2, or sixel)
+    #[default]
+    Auto,
+    /// Always render images. If the terminal doesn't support a graphics
+    /// protocol, fall back to unicode half-blocks
+    Always,
+    /// Never render images; show binary content instead
+    Never,
+}
+
 /// Configuration for in-app query and export commands
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(default, deny_unknown_fields)]
@@ -168,6 +279,10 @@ impl Default for CommandsConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ratatui::style::Color;
+    use rstest::rstest;
+    use slumber_core::test_util::{temp_dir, TempDir};
+    use std::fs;
 
     #[test]
     fn test_custom_config_path() {
@@ -181,4 +296,46 @@ mod tests {
             dirs::home_dir().unwrap().join("dotfiles/slumber.yml")
         );
     }
+
+    /// A theme file is loaded relative to the config file, and replaces the
+    /// inline theme
+    #[rstest]
+    fn test_theme_file(temp_dir: TempDir) {
+        let config_path = temp_dir.join("config.yml");
+        fs::write(
+            &config_path,
+            "theme_file: themes/house.yml\ntheme:\n  primary_color: red\n",
+        )
+        .unwrap();
+        fs::create_dir(temp_dir.join("themes")).unwrap();
+        fs::write(
+            temp_dir.join("themes/house.yml"),
+            "primary_color: magenta\n",
+        )
+        .unwrap();
+        let _guard = env_lock::lock_env([(
+            PATH_ENV_VAR,
+            Some(config_path.to_str().unwrap()),
+        )]);
+
+        let theme = Config::load().unwrap().theme.unwrap();
+        assert_eq!(theme.primary_color, Color::Magenta);
+        // Fields missing from the file use the default
+        assert_eq!(theme.secondary_color, Color::Yellow);
+    }
+
+    /// `connect_timeout` is flattened in from the HTTP config, and uses the
+    /// standard duration format
+    #[rstest]
+    #[case::default("", None)]
+    #[case::null("connect_timeout: null", None)]
+    #[case::seconds("connect_timeout: 5s", Some(Duration::from_secs(5)))]
+    #[case::millis("connect_timeout: 250ms", Some(Duration::from_millis(250)))]
+    fn test_connect_timeout(
+        #[case] input: &str,
+        #[case] expected: Option<Duration>,
+    ) {
+        let config = parse_yaml::<Config>(input.as_bytes()).unwrap();
+        assert_eq!(config.http.connect_timeout, expected);
+    }
 }
@@ -79,7 +79,7 @@ impl<'de, V: Deserialize<'de>> Deserialize<'de> for MimeMap<V> {
     PartialEq,
 )]
 #[serde(try_from = "String", into = "String")]
-struct MimePattern(Pattern);
+pub(crate) struct MimePattern(Pattern);
 
 impl Default for MimePattern {
     fn default() -> Self {
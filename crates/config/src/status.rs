@@ -0,0 +1,159 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// A set of HTTP status codes, used to determine which responses are
+/// considered successful. Each entry is either an exact code (`404`) or a
+/// class of codes (`2xx`).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StatusCodes(Vec<StatusCodeMatcher>);
+
+impl StatusCodes {
+    pub fn contains(&self, status: u16) -> bool {
+        self.0.iter().any(|matcher| matcher.matches(status))
+    }
+}
+
+impl Default for StatusCodes {
+    /// All 2xx and 3xx codes
+    fn default() -> Self {
+        Self(vec![
+            StatusCodeMatcher::Class(2),
+            StatusCodeMatcher::Class(3),
+        ])
+    }
+}
+
+#[cfg(test)]
+impl<const N: usize> From<[StatusCodeMatcher; N]> for StatusCodes {
+    fn from(matchers: [StatusCodeMatcher; N]) -> Self {
+        Self(matchers.into())
+    }
+}
+
+/// A single entry in [StatusCodes]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "StatusCodeInput", into = "String")]
+pub enum StatusCodeMatcher {
+    /// An exact status code, e.g. `404`
+    Code(u16),
+    /// All codes with the same first digit, e.g. `2xx`
+    Class(u8),
+}
+
+impl StatusCodeMatcher {
+    fn matches(self, status: u16) -> bool {
+        match self {
+            Self::Code(code) => status == code,
+            Self::Class(class) => status / 100 == class as u16,
+        }
+    }
+}
+
+impl Display for StatusCodeMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Code(code) => write!(f, "{code}"),
+            Self::Class(class) => write!(f, "{class}xx"),
+        }
+    }
+}
+
+impl FromStr for StatusCodeMatcher {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            anyhow!(
+                "Invalid status code `{s}`; expected a code such as `404` or \
+                a class such as `2xx`"
+            )
+        };
+        let s = s.to_ascii_lowercase();
+        let matcher = match s.strip_suffix("xx") {
+            Some(class) => Self::Class(class.parse().map_err(|_| error())?),
+            None => Self::Code(s.parse().map_err(|_| error())?),
+        };
+        match matcher {
+            Self::Code(100..=599) | Self::Class(1..=5) => Ok(matcher),
+            _ => Err(error()),
+        }
+    }
+}
+
+/// Codes can be given as a YAML integer or string, but classes must be
+/// strings
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StatusCodeInput {
+    Code(u16),
+    Text(String),
+}
+
+impl TryFrom<StatusCodeInput> for StatusCodeMatcher {
+    type Error = anyhow::Error;
+
+    fn try_from(value: StatusCodeInput) -> Result<Self, Self::Error> {
+        match value {
+            StatusCodeInput::Code(code) => code.to_string().parse(),
+            StatusCodeInput::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<StatusCodeMatcher> for String {
+    fn from(value: StatusCodeMatcher) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_test::{assert_de_tokens, assert_de_tokens_error, Token};
+
+    #[rstest]
+    #[case::code(&[Token::U16(404)], StatusCodeMatcher::Code(404))]
+    #[case::code_str(&[Token::Str("404")], StatusCodeMatcher::Code(404))]
+    #[case::class(&[Token::Str("2xx")], StatusCodeMatcher::Class(2))]
+    #[case::class_upper(&[Token::Str("5XX")], StatusCodeMatcher::Class(5))]
+    fn test_deserialize(
+        #[case] tokens: &[Token],
+        #[case] expected: StatusCodeMatcher,
+    ) {
+        assert_de_tokens(&expected, tokens);
+    }
+
+    #[rstest]
+    #[case::out_of_range(&[Token::U16(600)], "`600`")]
+    #[case::bad_class(&[Token::Str("9xx")], "`9xx`")]
+    #[case::garbage(&[Token::Str("ok")], "`ok`")]
+    fn test_deserialize_error(#[case] tokens: &[Token], #[case] input: &str) {
+        assert_de_tokens_error::<StatusCodeMatcher>(
+            tokens,
+            &format!(
+                "Invalid status code {input}; expected a code such as `404` \
+                or a class such as `2xx`"
+            ),
+        );
+    }
+
+    #[rstest]
+    #[case::default_success(StatusCodes::default(), 204, true)]
+    #[case::default_redirect(StatusCodes::default(), 301, true)]
+    #[case::default_error(StatusCodes::default(), 404, false)]
+    #[case::code(
+        [StatusCodeMatcher::Class(2), StatusCodeMatcher::Code(404)].into(),
+        404,
+        true
+    )]
+    fn test_contains(
+        #[case] status_codes: StatusCodes,
+        #[case] status: u16,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(status_codes.contains(status), expected);
+    }
+}
@@ -1,10 +1,16 @@
+use anyhow::Context;
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
+use slumber_core::util::parse_yaml;
+use std::{fs::File, path::Path};
 
 /// User-configurable visual settings. These are used to generate the full style
 /// set.
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(default, deny_unknown_fields)]
+///
+/// Deserialization optionally starts from a built-in [ThemePreset], with any
+/// individually specified fields overriding the preset.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "ThemeDeserialize")]
 pub struct Theme {
     pub primary_color: Color,
     /// Theoretically we could calculate this bsed on primary color, but for
@@ -17,6 +23,28 @@ pub struct Theme {
     pub error_color: Color,
 }
 
+impl Theme {
+    /// Default theme for terminals with a light background. The default
+    /// secondary color (yellow) is unreadable on white, so swap it out
+    pub fn light() -> Self {
+        Self {
+            secondary_color: Color::Magenta,
+            ..Self::default()
+        }
+    }
+
+    /// Load a theme from a standalone YAML file. This allows themes to be
+    /// shared independently of the rest of the config. Any missing fields use
+    /// the default value (or the preset's value, if a preset is given).
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        (|| {
+            let file = File::open(path)?;
+            parse_yaml::<Self>(&file)
+        })()
+        .context(format!("Error loading theme from {path:?}"))
+    }
+}
+
 impl Default for Theme {
     fn default() -> Self {
         Self {
@@ -28,3 +56,162 @@ impl Default for Theme {
         }
     }
 }
+
+/// A built-in color palette, which can be used as a starting point for a theme
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+    Dracula,
+    SolarizedDark,
+    SolarizedLight,
+    Gruvbox,
+    Nord,
+}
+
+impl ThemePreset {
+    /// Get the theme for this preset
+    pub fn theme(self) -> Theme {
+        match self {
+            Self::Dracula => DRACULA,
+            Self::SolarizedDark => SOLARIZED_DARK,
+            Self::SolarizedLight => SOLARIZED_LIGHT,
+            Self::Gruvbox => GRUVBOX,
+            Self::Nord => NORD,
+        }
+    }
+}
+
+const DRACULA: Theme = Theme {
+    primary_color: Color::Rgb(0xbd, 0x93, 0xf9),
+    primary_text_color: Color::Rgb(0x28, 0x2a, 0x36),
+    secondary_color: Color::Rgb(0xff, 0x79, 0xc6),
+    success_color: Color::Rgb(0x50, 0xfa, 0x7b),
+    error_color: Color::Rgb(0xff, 0x55, 0x55),
+};
+
+const SOLARIZED_DARK: Theme = Theme {
+    primary_color: Color::Rgb(0x26, 0x8b, 0xd2),
+    primary_text_color: Color::Rgb(0xfd, 0xf6, 0xe3),
+    secondary_color: Color::Rgb(0xb5, 0x89, 0x00),
+    success_color: Color::Rgb(0x85, 0x99, 0x00),
+    error_color: Color::Rgb(0xdc, 0x32, 0x2f),
+};
+
+/// Yellow is hard to read on a light background, so this uses orange instead
+const SOLARIZED_LIGHT: Theme = Theme {
+    primary_color: Color::Rgb(0x26, 0x8b, 0xd2),
+    primary_text_color: Color::Rgb(0xfd, 0xf6, 0xe3),
+    secondary_color: Color::Rgb(0xcb, 0x4b, 0x16),
+    success_color: Color::Rgb(0x85, 0x99, 0x00),
+    error_color: Color::Rgb(0xdc, 0x32, 0x2f),
+};
+
+const GRUVBOX: Theme = Theme {
+    primary_color: Color::Rgb(0x83, 0xa5, 0x98),
+    primary_text_color: Color::Rgb(0x28, 0x28, 0x28),
+    secondary_color: Color::Rgb(0xfa, 0xbd, 0x2f),
+    success_color: Color::Rgb(0xb8, 0xbb, 0x26),
+    error_color: Color::Rgb(0xfb, 0x49, 0x34),
+};
+
+const NORD: Theme = Theme {
+    primary_color: Color::Rgb(0x88, 0xc0, 0xd0),
+    primary_text_color: Color::Rgb(0x2e, 0x34, 0x40),
+    secondary_color: Color::Rgb(0xeb, 0xcb, 0x8b),
+    success_color: Color::Rgb(0xa3, 0xbe, 0x8c),
+    error_color: Color::Rgb(0xbf, 0x61, 0x6a),
+};
+
+/// Deserialization intermediate for [Theme]. Every field is optional so we can
+/// tell which ones the user specified, and apply them on top of the preset.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeDeserialize {
+    preset: Option<ThemePreset>,
+    primary_color: Option<Color>,
+    primary_text_color: Option<Color>,
+    secondary_color: Option<Color>,
+    success_color: Option<Color>,
+    error_color: Option<Color>,
+}
+
+impl From<ThemeDeserialize> for Theme {
+    fn from(value: ThemeDeserialize) -> Self {
+        let base = value.preset.map(ThemePreset::theme).unwrap_or_default();
+        Self {
+            primary_color: value.primary_color.unwrap_or(base.primary_color),
+            primary_text_color: value
+                .primary_text_color
+                .unwrap_or(base.primary_text_color),
+            secondary_color: value
+                .secondary_color
+                .unwrap_or(base.secondary_color),
+            success_color: value.success_color.unwrap_or(base.success_color),
+            error_color: value.error_color.unwrap_or(base.error_color),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use slumber_core::{
+        assert_err,
+        test_util::{temp_dir, TempDir},
+    };
+    use std::fs;
+
+    #[rstest]
+    fn test_load(temp_dir: TempDir) {
+        let path = temp_dir.join("theme.yml");
+        fs::write(&path, "primary_color: '#ff0000'\nerror_color: '9'\n")
+            .unwrap();
+        let theme = Theme::load(&path).unwrap();
+        assert_eq!(theme.primary_color, Color::Rgb(255, 0, 0));
+        assert_eq!(theme.error_color, Color::Indexed(9));
+        assert_eq!(theme.success_color, Color::Green);
+    }
+
+    /// Invalid colors are rejected, with the offending field in the error
+    #[rstest]
+    #[case::invalid_color("success_color: notacolor\n", "success_color")]
+    #[case::unknown_field(
+        "accent_color: red\n",
+        "unknown field `accent_color`"
+    )]
+    fn test_load_error(
+        temp_dir: TempDir,
+        #[case] content: &str,
+        #[case] expected_error: &str,
+    ) {
+        let path = temp_dir.join("theme.yml");
+        fs::write(&path, content).unwrap();
+        assert_err!(Theme::load(&path), expected_error);
+    }
+
+    /// Presets are used as a base, with individual fields overriding them
+    #[rstest]
+    #[case::preset("preset: nord", NORD.primary_color, NORD.error_color)]
+    #[case::preset_override(
+        "preset: dracula\nerror_color: red",
+        DRACULA.primary_color,
+        Color::Red
+    )]
+    #[case::no_preset("error_color: magenta", Color::Blue, Color::Magenta)]
+    fn test_preset(
+        #[case] yaml: &str,
+        #[case] expected_primary: Color,
+        #[case] expected_error: Color,
+    ) {
+        let theme = parse_yaml::<Theme>(yaml.as_bytes()).unwrap();
+        assert_eq!(theme.primary_color, expected_primary);
+        assert_eq!(theme.error_color, expected_error);
+    }
+
+    #[rstest]
+    fn test_load_missing_file(temp_dir: TempDir) {
+        let path = temp_dir.join("theme.yml");
+        assert_err!(Theme::load(&path), "Error loading theme from");
+    }
+}
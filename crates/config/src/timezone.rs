@@ -0,0 +1,114 @@
+use anyhow::anyhow;
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// Timezone used to display timestamps. Timestamps are always stored in UTC;
+/// this only controls how they're presented to the user.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Timezone {
+    Utc,
+    /// Timezone of the host system
+    #[default]
+    Local,
+    /// A named IANA timezone, e.g. `America/New_York`
+    Named(Tz),
+}
+
+impl Timezone {
+    /// Convert a UTC timestamp into this timezone, for display
+    pub fn localize(&self, time: &DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Self::Utc => time.fixed_offset(),
+            Self::Local => time.with_timezone(&Local).fixed_offset(),
+            Self::Named(tz) => time.with_timezone(tz).fixed_offset(),
+        }
+    }
+}
+
+impl Display for Timezone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Utc => write!(f, "utc"),
+            Self::Local => write!(f, "local"),
+            Self::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+impl FromStr for Timezone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "utc" | "UTC" => Ok(Self::Utc),
+            "local" => Ok(Self::Local),
+            _ => s.parse::<Tz>().map(Self::Named).map_err(|_| {
+                anyhow!(
+                    "Invalid timezone `{s}`; expected `utc`, `local`, or an \
+                    IANA timezone name such as `America/New_York`"
+                )
+            }),
+        }
+    }
+}
+
+impl From<Timezone> for String {
+    fn from(value: Timezone) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for Timezone {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::rstest;
+    use serde_test::{assert_de_tokens, assert_de_tokens_error, Token};
+
+    #[rstest]
+    #[case::utc("utc", Timezone::Utc)]
+    #[case::utc_upper("UTC", Timezone::Utc)]
+    #[case::local("local", Timezone::Local)]
+    #[case::named("America/New_York", Timezone::Named(Tz::America__New_York))]
+    fn test_deserialize(
+        #[case] input: &'static str,
+        #[case] expected: Timezone,
+    ) {
+        assert_de_tokens(&expected, &[Token::Str(input)]);
+    }
+
+    #[test]
+    fn test_deserialize_error() {
+        assert_de_tokens_error::<Timezone>(
+            &[Token::Str("Mars/Olympus_Mons")],
+            "Invalid timezone `Mars/Olympus_Mons`; expected `utc`, `local`, \
+            or an IANA timezone name such as `America/New_York`",
+        );
+    }
+
+    #[test]
+    fn test_localize() {
+        let time = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            Timezone::Utc.localize(&time).to_rfc3339(),
+            "2024-01-01T12:00:00+00:00"
+        );
+        assert_eq!(
+            Timezone::Named(Tz::America__New_York)
+                .localize(&time)
+                .to_rfc3339(),
+            "2024-01-01T07:00:00-05:00"
+        );
+    }
+}
@@ -2,13 +2,21 @@
 //! possible
 
 mod cereal;
+mod edit;
+mod extends;
+mod ignore;
+mod include;
+mod merge;
 mod models;
 mod recipe_tree;
 
-pub use cereal::HasId;
+pub use cereal::{serde_duration, HasId};
+pub use edit::CollectionSource;
+pub use merge::MergeConflict;
 pub use models::*;
 pub use recipe_tree::*;
 
+use crate::util::{parse_yaml, paths::expand_home};
 use anyhow::{anyhow, Context};
 use itertools::Itertools;
 use std::{
@@ -32,6 +40,10 @@ const CONFIG_FILES: &[&str] = &[
     ".slumber.yaml",
 ];
 
+/// Environment variable to specify the collection file (or a directory to
+/// search). The `--file` flag takes precedence over this.
+pub const COLLECTION_ENV_VAR: &str = "SLUMBER_COLLECTION";
+
 /// A wrapper around a request collection, to handle functionality around the
 /// file system.
 #[derive(Debug)]
@@ -74,11 +86,21 @@ impl CollectionFile {
         &self.path
     }
 
+    /// Does the collection file include other files via the `include` field?
+    /// Re-reads the file from disk, so this reflects the latest content. If
+    /// the file can't be read or parsed, return `false`.
+    pub fn has_includes(&self) -> bool {
+        fs::File::open(&self.path)
+            .ok()
+            .and_then(|file| parse_yaml::<serde_yaml::Value>(file).ok())
+            .is_some_and(|yaml| include::has_includes(&yaml))
+    }
+
     /// Get the path to the collection file, returning an error if none is
-    /// available. This will use the override if given, otherwise it will fall
-    /// back to searching the given directory for a collection. If a directory
-    /// is given for the override, search that directory (relative to the
-    /// given/current).
+    /// available. This will use the override if given, then
+    /// `$SLUMBER_COLLECTION` if set, otherwise it will fall back to searching
+    /// the given directory for a collection. If a directory is given for the
+    /// override, search that directory (relative to the given/current).
     ///
     /// If the directory to search is not given, default to the current
     /// directory. This is configurable just for testing.
@@ -92,6 +114,13 @@ impl CollectionFile {
             env::current_dir()?
         };
 
+        // Explicit override takes precedence over the env var
+        let override_path = override_path.or_else(|| {
+            env::var_os(COLLECTION_ENV_VAR)
+                .filter(|value| !value.is_empty())
+                .map(|value| expand_home(PathBuf::from(value)).into_owned())
+        });
+
         // If the override is a dir, search that dir instead. If it's a file,
         // just return it
         if let Some(override_path) = override_path {
@@ -186,13 +215,12 @@ mod tests {
     use crate::{
         assert_err,
         http::{content_type::ContentType, HttpMethod},
+        template::Template,
         test_util::{by_id, temp_dir, test_data_dir, TempDir},
     };
     use indexmap::indexmap;
     use pretty_assertions::assert_eq;
     use rstest::rstest;
-    use serde::de::IgnoredAny;
-    use serde_json::json;
     use std::{fs, fs::File, time::Duration};
 
     /// Test various cases of try_path
@@ -229,6 +257,7 @@ mod tests {
         File::create(child_dir.join("override.yml")).unwrap();
         let expected: PathBuf = temp_dir.join(expected);
 
+        let _guard = env_lock::lock_env([(COLLECTION_ENV_VAR, None::<&str>)]);
         let actual = CollectionFile::try_path(
             Some(child_dir),
             override_path.map(PathBuf::from),
@@ -257,6 +286,7 @@ mod tests {
         #[case] override_path: Option<&str>,
         #[case] expected_err: &str,
     ) {
+        let _guard = env_lock::lock_env([(COLLECTION_ENV_VAR, None::<&str>)]);
         assert_err!(
             CollectionFile::try_path(
                 Some(temp_dir.to_path_buf()),
@@ -266,6 +296,203 @@ mod tests {
         );
     }
 
+    /// `$SLUMBER_COLLECTION` is used if `--file` isn't given, and can point
+    /// to either a file or a directory to search
+    #[rstest]
+    #[case::file(None, Some("env.yml"), "env.yml")]
+    #[case::directory(None, Some("env_dir"), "env_dir/slumber.yml")]
+    #[case::empty(None, Some(""), "slumber.yml")]
+    #[case::override_wins(
+        Some("override.yml"),
+        Some("env.yml"),
+        "override.yml"
+    )]
+    fn test_try_path_env(
+        temp_dir: TempDir,
+        #[case] override_path: Option<&str>,
+        #[case] env_value: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        File::create(temp_dir.join("slumber.yml")).unwrap();
+        File::create(temp_dir.join("override.yml")).unwrap();
+        let env_dir = temp_dir.join("env_dir");
+        fs::create_dir(&env_dir).unwrap();
+        File::create(env_dir.join("slumber.yml")).unwrap();
+        File::create(temp_dir.join("env.yml")).unwrap();
+
+        // Relative env paths are resolved against the search directory
+        let _guard = env_lock::lock_env([(COLLECTION_ENV_VAR, env_value)]);
+        let actual = CollectionFile::try_path(
+            Some(temp_dir.to_path_buf()),
+            override_path.map(PathBuf::from),
+        )
+        .unwrap();
+        assert_eq!(actual, temp_dir.join(expected));
+    }
+
+    /// Profile data can be loaded from a dotenv file, relative to the
+    /// collection file. Explicit values override the file
+    #[rstest]
+    #[tokio::test]
+    async fn test_from_dotenv(temp_dir: TempDir) {
+        fs::write(
+            temp_dir.join("slumber.yml"),
+            "
+profiles:
+  local:
+    from_dotenv: config/.env
+    data:
+      host: http://localhost:3000
+",
+        )
+        .unwrap();
+        fs::create_dir(temp_dir.join("config")).unwrap();
+        fs::write(
+            temp_dir.join("config/.env"),
+            "host=http://localhost:8000\n\
+            # Comment\n\
+            TOKEN=\"abc 123\"\n\
+            RAW='{{not_a_template}}'\n",
+        )
+        .unwrap();
+
+        let collection = CollectionFile::load(temp_dir.join("slumber.yml"))
+            .await
+            .unwrap()
+            .collection;
+        let profile = collection.profiles.get(&"local".into()).unwrap();
+        assert_eq!(
+            profile.data,
+            indexmap! {
+                "host".into() => "http://localhost:3000".into(),
+                "TOKEN".into() => "abc 123".into(),
+                "RAW".into() =>
+                    ProfileValue::Template(Template::raw("{{not_a_template}}".into())),
+            }
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_from_dotenv_missing(temp_dir: TempDir) {
+        fs::write(
+            temp_dir.join("slumber.yml"),
+            "profiles:\n  local:\n    from_dotenv: .env\n    data: {}\n",
+        )
+        .unwrap();
+        assert_err!(
+            CollectionFile::load(temp_dir.join("slumber.yml")).await,
+            "Error loading dotenv file"
+        );
+    }
+
+    /// Included files are merged into the collection, recursively, with paths
+    /// relative to the including file. Recipes can extend across files
+    #[rstest]
+    #[tokio::test]
+    async fn test_include(temp_dir: TempDir) {
+        fs::write(
+            temp_dir.join("slumber.yml"),
+            "
+include: [shared/profiles.yml, recipes.yml]
+profiles:
+  local:
+    data:
+      host: http://localhost:3000
+requests:
+  login: !request
+    method: POST
+    url: \"{{host}}/login\"
+",
+        )
+        .unwrap();
+        fs::create_dir(temp_dir.join("shared")).unwrap();
+        fs::write(
+            temp_dir.join("shared/profiles.yml"),
+            "
+include: [chains.yml]
+.ignore:
+  unused: true
+profiles:
+  prod:
+    data:
+      host: https://example.com
+",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.join("shared/chains.yml"),
+            "
+chains:
+  token:
+    source: !file
+      path: token.txt
+",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.join("recipes.yml"),
+            "
+requests:
+  logout: !request
+    extends: login
+    url: \"{{host}}/logout\"
+",
+        )
+        .unwrap();
+
+        let collection = CollectionFile::load(temp_dir.join("slumber.yml"))
+            .await
+            .unwrap()
+            .collection;
+        assert_eq!(
+            collection.profiles.keys().collect::<Vec<_>>(),
+            [&ProfileId::from("local"), &"prod".into()]
+        );
+        assert_eq!(
+            collection.chains.keys().collect::<Vec<_>>(),
+            [&ChainId::from("token")]
+        );
+        let logout = collection.recipes.get_recipe(&"logout".into()).unwrap();
+        assert_eq!(logout.method, HttpMethod::Post);
+        assert_eq!(logout.url, "{{host}}/logout".into());
+    }
+
+    #[rstest]
+    #[case::missing("include: [missing.yml]", &[], "Error loading included file")]
+    #[case::cycle(
+        "include: [a.yml]",
+        &[("a.yml", "include: [b.yml]"), ("b.yml", "include: [a.yml]")],
+        "Include cycle"
+    )]
+    #[case::duplicate(
+        "include: [a.yml]\nprofiles:\n  local:\n    data: {}",
+        &[("a.yml", "profiles:\n  local:\n    data: {}")],
+        "`profiles.local` is already defined"
+    )]
+    #[case::unknown_field(
+        "include: [a.yml]",
+        &[("a.yml", "name: Other")],
+        "Unknown field `name`"
+    )]
+    #[case::not_a_list("include: a.yml", &[], "must be a list of file paths")]
+    #[tokio::test]
+    async fn test_include_error(
+        temp_dir: TempDir,
+        #[case] collection: &str,
+        #[case] files: &[(&str, &str)],
+        #[case] expected_error: &str,
+    ) {
+        fs::write(temp_dir.join("slumber.yml"), collection).unwrap();
+        for (path, content) in files {
+            fs::write(temp_dir.join(path), content).unwrap();
+        }
+        assert_err!(
+            CollectionFile::load(temp_dir.join("slumber.yml")).await,
+            expected_error
+        );
+    }
+
     /// A catch-all regression test, to make sure we don't break anything in the
     /// collection format. This lives at the bottom because it's huge.
     #[rstest]
@@ -281,6 +508,10 @@ mod tests {
                     id: "profile1".into(),
                     name: Some("Profile 1".into()),
                     default: false,
+                    from_dotenv: None,
+                    color: None,
+                    label: None,
+                    chains: Default::default(),
                     data: indexmap! {
                         "user_guid".into() => "abc123".into(),
                         "username".into() => "xX{{chains.username}}Xx".into(),
@@ -292,6 +523,19 @@ mod tests {
                     id: "profile2".into(),
                     name: Some("Profile 2".into()),
                     default: true,
+                    from_dotenv: None,
+                    color: None,
+                    label: None,
+                    chains: by_id([Chain {
+                        id: "command".into(),
+                        source: ChainSource::command(["whoami"]),
+                        sensitive: true,
+                        selector: None,
+                        selector_mode: SelectorMode::default(),
+                        content_type: None,
+                        trim: ChainOutputTrim::None,
+                        decode: vec![],
+                    }]),
                     data: indexmap! {
                         "host".into() => "https://httpbin.org".into(),
 
@@ -307,6 +551,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "command_stdin".into(),
@@ -319,6 +564,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "command_trim_none".into(),
@@ -328,6 +574,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "command_trim_start".into(),
@@ -337,6 +584,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::Start,
+                    decode: vec![],
                 },
                 Chain {
                     id: "command_trim_end".into(),
@@ -346,6 +594,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::End,
+                    decode: vec![],
                 },
                 Chain {
                     id: "command_trim_both".into(),
@@ -355,6 +604,21 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::Both,
+                    decode: vec![],
+                },
+                Chain {
+                    id: "command_decode".into(),
+                    source: ChainSource::command(["whoami"]),
+                    sensitive: false,
+                    selector: None,
+                    selector_mode: SelectorMode::default(),
+                    content_type: None,
+                    trim: ChainOutputTrim::None,
+                    decode: vec![
+                        DecodeStep::Base64,
+                        DecodeStep::Json,
+                        DecodeStep::Gunzip,
+                    ],
                 },
                 Chain {
                     id: "prompt_sensitive".into(),
@@ -367,6 +631,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "prompt_default".into(),
@@ -379,28 +644,33 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "file".into(),
                     source: ChainSource::File {
                         path: "./README.md".into(),
+                        relative_to: PathRoot::Collection,
                     },
                     sensitive: false,
                     selector: None,
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "file_content_type".into(),
                     source: ChainSource::File {
                         path: "./data.json".into(),
+                        relative_to: PathRoot::Cwd,
                     },
                     sensitive: false,
                     selector: None,
                     selector_mode: SelectorMode::default(),
                     content_type: Some(ContentType::Json),
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "request_selector".into(),
@@ -414,6 +684,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "request_trigger_never".into(),
@@ -427,6 +698,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "request_trigger_no_history".into(),
@@ -440,6 +712,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "request_trigger_expire".into(),
@@ -455,6 +728,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "request_trigger_always".into(),
@@ -468,6 +742,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "request_section_body".into(),
@@ -481,6 +756,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
                 Chain {
                     id: "request_section_header".into(),
@@ -496,6 +772,7 @@ mod tests {
                     selector_mode: SelectorMode::default(),
                     content_type: None,
                     trim: ChainOutputTrim::None,
+                    decode: vec![],
                 },
             ]),
             recipes: by_id([
@@ -516,9 +793,17 @@ mod tests {
                         ("sudo".into(), "yes_please".into()),
                         ("fast".into(), "no_thanks".into()),
                     ],
-                    headers: indexmap! {
-                        "accept".into() => "application/json".into(),
-                    },
+                    headers: vec![("accept".into(), "application/json".into())],
+                    persist: true,
+                    cache: None,
+                    confirm: false,
+                    queue: None,
+                    http_version: None,
+                    accept: None,
+                    auto_send: None,
+                    env: Default::default(),
+                    profile: None,
+                    script: None,
                 }),
                 RecipeNode::Folder(Folder {
                     id: "users".into(),
@@ -535,25 +820,48 @@ mod tests {
                                 ("value".into(), "{{field1}}".into()),
                                 ("value".into(), "{{field2}}".into()),
                             ],
-                            headers: indexmap! {},
+                            headers: vec![],
+                            persist: true,
+                            cache: None,
+                            confirm: false,
+                            queue: None,
+                            http_version: None,
+                            accept: None,
+                            auto_send: None,
+                            env: Default::default(),
+                            profile: None,
+                            script: None,
                         }),
                         RecipeNode::Recipe(Recipe {
                             id: "json_body".into(),
                             name: Some("Modify User".into()),
                             method: HttpMethod::Put,
                             url: "{{host}}/anything/{{user_guid}}".into(),
-                            body: Some(RecipeBody::Raw {
-                                body: json!({"username": "new username"})
-                                    .into(),
-                                content_type: Some(ContentType::Json),
-                            }),
+                            body: Some(RecipeBody::Json(JsonTemplate::Object(
+                                indexmap! {
+                                    "username".into() => JsonTemplate::String(
+                                        "new username".into()
+                                    ),
+                                },
+                            ))),
                             authentication: Some(Authentication::Bearer(
                                 "{{chains.auth_token}}".into(),
                             )),
                             query: vec![],
-                            headers: indexmap! {
-                                "accept".into() => "application/json".into(),
-                            },
+                            headers: vec![(
+                                "accept".into(),
+                                "application/json".into(),
+                            )],
+                            persist: true,
+                            cache: None,
+                            confirm: false,
+                            queue: None,
+                            http_version: None,
+                            accept: None,
+                            auto_send: None,
+                            env: Default::default(),
+                            profile: None,
+                            script: None,
                         }),
                         RecipeNode::Recipe(Recipe {
                             id: "json_body_but_not".into(),
@@ -561,19 +869,28 @@ mod tests {
                             method: HttpMethod::Put,
                             url: "{{host}}/anything/{{user_guid}}".into(),
 
-                            body: Some(RecipeBody::Raw {
-                                body: json!(r#"{"warning": "NOT an object"}"#)
-                                    .into(),
-                                content_type: Some(ContentType::Json),
-                            }),
+                            body: Some(RecipeBody::Json(JsonTemplate::String(
+                                r#"{"warning": "NOT an object"}"#.into(),
+                            ))),
                             authentication: Some(Authentication::Basic {
                                 username: "{{username}}".into(),
                                 password: Some("{{password}}".into()),
                             }),
                             query: vec![],
-                            headers: indexmap! {
-                                "accept".into() => "application/json".into(),
-                            },
+                            headers: vec![(
+                                "accept".into(),
+                                "application/json".into(),
+                            )],
+                            persist: true,
+                            cache: None,
+                            confirm: false,
+                            queue: None,
+                            http_version: None,
+                            accept: None,
+                            auto_send: None,
+                            env: Default::default(),
+                            profile: None,
+                            script: None,
                         }),
                         RecipeNode::Recipe(Recipe {
                             id: "form_urlencoded_body".into(),
@@ -586,15 +903,26 @@ mod tests {
                             })),
                             authentication: None,
                             query: vec![],
-                            headers: indexmap! {
-                                "accept".into() => "application/json".into(),
-                            },
+                            headers: vec![(
+                                "accept".into(),
+                                "application/json".into(),
+                            )],
+                            persist: true,
+                            cache: None,
+                            confirm: false,
+                            queue: None,
+                            http_version: None,
+                            accept: None,
+                            auto_send: None,
+                            env: Default::default(),
+                            profile: None,
+                            script: None,
                         }),
                     ]),
                 }),
             ])
             .into(),
-            _ignore: IgnoredAny,
+            variables: Default::default(),
         };
         assert_eq!(*loaded, expected);
     }
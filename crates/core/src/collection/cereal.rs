@@ -2,8 +2,8 @@
 
 use crate::{
     collection::{
-        recipe_tree::RecipeNode, Chain, ChainId, Profile, ProfileId, Recipe,
-        RecipeBody, RecipeId,
+        recipe_tree::RecipeNode, Chain, ChainId, JsonTemplate, Profile,
+        ProfileId, ProfileValue, Recipe, RecipeBody, RecipeId,
     },
     http::content_type::ContentType,
     template::Template,
@@ -11,15 +11,16 @@ use crate::{
 use anyhow::Context;
 use indexmap::IndexMap;
 use itertools::Itertools;
+use mime::Mime;
 use serde::{
     de::{
-        self, EnumAccess, Error as _, MapAccess, SeqAccess, VariantAccess,
-        Visitor,
+        self, EnumAccess, Error as _, IntoDeserializer, MapAccess, SeqAccess,
+        VariantAccess, Visitor,
     },
     ser::Error as _,
     Deserialize, Deserializer, Serialize, Serializer,
 };
-use std::{hash::Hash, str::FromStr};
+use std::{hash::Hash, marker::PhantomData, str::FromStr};
 
 /// A type that has an `id` field. This is ripe for a derive macro, maybe a fun
 /// project some day?
@@ -134,8 +135,67 @@ where
     Ok(profiles)
 }
 
+/// A single value or a list of values. Used for map values that can be
+/// repeated, e.g. `{"id": 1}` or `{"id": [1, 2]}`. This is similar to an
+/// untagged enum, but it preserves the error from deserializing the inner
+/// value.
+pub struct OneOrMany<T>(pub Vec<T>);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for OneOrMany<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct OneOrManyVisitor<T>(PhantomData<T>);
+
+        macro_rules! visit_primitive {
+            ($func:ident, $type:ty) => {
+                fn $func<E>(self, v: $type) -> Result<Self::Value, E>
+                where
+                    E: de::Error,
+                {
+                    T::deserialize(v.into_deserializer())
+                        .map(|value| OneOrMany(vec![value]))
+                }
+            };
+        }
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for OneOrManyVisitor<T> {
+            type Value = OneOrMany<T>;
+
+            fn expecting(
+                &self,
+                formatter: &mut std::fmt::Formatter,
+            ) -> std::fmt::Result {
+                formatter.write_str("value or sequence of values")
+            }
+
+            visit_primitive!(visit_bool, bool);
+            visit_primitive!(visit_u64, u64);
+            visit_primitive!(visit_i64, i64);
+            visit_primitive!(visit_f64, f64);
+            visit_primitive!(visit_str, &str);
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut values =
+                    Vec::with_capacity(seq.size_hint().unwrap_or(2));
+                while let Some(value) = seq.next_element()? {
+                    values.push(value);
+                }
+                Ok(OneOrMany(values))
+            }
+        }
+
+        deserializer.deserialize_any(OneOrManyVisitor(PhantomData))
+    }
+}
+
 /// Deserialize query parameters from either a sequence of `key=value` or a map
-/// of `key: value`. Serialie back to a sequence `key=value`, since that will
+/// of `key: value`. In the map format, the value can also be a list to repeat
+/// the parameter. Serialie back to a sequence `key=value`, since that will
 /// always support duplicate keys
 pub mod serde_query_parameters {
     use super::*;
@@ -215,8 +275,12 @@ pub mod serde_query_parameters {
             {
                 let mut query: Vec<(String, Template)> =
                     Vec::with_capacity(map.size_hint().unwrap_or(5));
-                while let Some((key, value)) = map.next_entry()? {
-                    query.push((key, value));
+                while let Some((key, OneOrMany(values))) =
+                    map.next_entry::<String, OneOrMany<Template>>()?
+                {
+                    query.extend(
+                        values.into_iter().map(|value| (key.clone(), value)),
+                    );
                 }
                 Ok(query)
             }
@@ -226,23 +290,56 @@ pub mod serde_query_parameters {
     }
 }
 
-/// Deserialize a header map, lowercasing all header names. Headers are
-/// case-insensitive (and must be lowercase in HTTP/2+), so forcing the case
-/// makes lookups on the map easier.
-pub fn deserialize_headers<'de, D>(
-    deserializer: D,
-) -> Result<IndexMap<String, Template>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    // This involves an extra allocation, but it makes the logic a lot easier.
-    // These maps should be small anyway
-    let headers: IndexMap<String, Template> =
-        IndexMap::deserialize(deserializer)?;
-    Ok(headers
-        .into_iter()
-        .map(|(k, v)| (k.to_ascii_lowercase(), v))
-        .collect())
+/// Serialize/deserialize headers as a map of `name: value`, where each value
+/// can be a single value or a list of values to repeat the header. Header names
+/// are lowercased during deserialization. Headers are case-insensitive (and
+/// must be lowercase in HTTP/2+), so forcing the case makes lookups easier.
+pub mod serde_headers {
+    use super::*;
+    use serde::ser::SerializeMap;
+
+    pub fn serialize<S>(
+        headers: &[(String, Template)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Group repeated headers back into lists
+        let mut grouped: IndexMap<&str, Vec<&Template>> = IndexMap::new();
+        for (header, value) in headers {
+            grouped.entry(header).or_default().push(value);
+        }
+
+        let mut map = serializer.serialize_map(Some(grouped.len()))?;
+        for (header, values) in grouped {
+            if let [value] = values.as_slice() {
+                map.serialize_entry(header, value)?;
+            } else {
+                map.serialize_entry(header, &values)?;
+            }
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<Vec<(String, Template)>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // This involves an extra allocation, but it makes the logic a lot
+        // easier. These maps should be small anyway
+        let headers: IndexMap<String, OneOrMany<Template>> =
+            IndexMap::deserialize(deserializer)?;
+        Ok(headers
+            .into_iter()
+            .flat_map(|(header, OneOrMany(values))| {
+                let header = header.to_ascii_lowercase();
+                values.into_iter().map(move |value| (header.clone(), value))
+            })
+            .collect())
+    }
 }
 
 impl RecipeBody {
@@ -250,15 +347,41 @@ impl RecipeBody {
     // by macros, but we need custom implementation
     const STRUCT_NAME: &'static str = "RecipeBody";
     const VARIANT_JSON: &'static str = "json";
+    const VARIANT_XML: &'static str = "xml";
     const VARIANT_FORM_URLENCODED: &'static str = "form_urlencoded";
     const VARIANT_FORM_MULTIPART: &'static str = "form_multipart";
+    const VARIANT_COMMAND: &'static str = "command";
+    const VARIANT_VARIANTS: &'static str = "variants";
     const ALL_VARIANTS: &'static [&'static str] = &[
         Self::VARIANT_JSON,
+        Self::VARIANT_XML,
         Self::VARIANT_FORM_URLENCODED,
         Self::VARIANT_FORM_MULTIPART,
+        Self::VARIANT_COMMAND,
+        Self::VARIANT_VARIANTS,
     ];
 }
 
+/// Intermediate ser/de representation for [RecipeBody::Command]. `Mime` doesn't
+/// implement serde traits so we go through a string
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CommandBody {
+    command: Vec<Template>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+}
+
+/// Intermediate ser/de representation for [RecipeBody::Variants]. Generic so
+/// we can serialize from references without cloning the whole body
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VariantsBody<S, V> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    select: Option<S>,
+    variants: V,
+}
+
 /// Custom serialization for RecipeBody, so the `Raw` variant serializes as a
 /// scalar without a tag
 impl Serialize for RecipeBody {
@@ -289,6 +412,18 @@ impl Serialize for RecipeBody {
                     &json,
                 )
             }
+            RecipeBody::Json(json) => serializer.serialize_newtype_variant(
+                Self::STRUCT_NAME,
+                1,
+                Self::VARIANT_JSON,
+                json,
+            ),
+            RecipeBody::Xml(body) => serializer.serialize_newtype_variant(
+                Self::STRUCT_NAME,
+                6,
+                Self::VARIANT_XML,
+                body,
+            ),
             RecipeBody::FormUrlencoded(value) => serializer
                 .serialize_newtype_variant(
                     Self::STRUCT_NAME,
@@ -303,7 +438,70 @@ impl Serialize for RecipeBody {
                     Self::VARIANT_FORM_MULTIPART,
                     value,
                 ),
+            RecipeBody::Command {
+                command,
+                content_type,
+            } => serializer.serialize_newtype_variant(
+                Self::STRUCT_NAME,
+                4,
+                Self::VARIANT_COMMAND,
+                &CommandBody {
+                    command: command.clone(),
+                    content_type: content_type.as_ref().map(Mime::to_string),
+                },
+            ),
+            RecipeBody::Variants { select, variants } => serializer
+                .serialize_newtype_variant(
+                    Self::STRUCT_NAME,
+                    5,
+                    Self::VARIANT_VARIANTS,
+                    &VariantsBody {
+                        select: select.as_ref(),
+                        variants,
+                    },
+                ),
+        }
+    }
+}
+
+/// Serialize as the equivalent JSON value, with each template as a string
+impl Serialize for JsonTemplate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.to_json().serialize(serializer)
+    }
+}
+
+/// Deserialize any JSON value, parsing each string as a template
+impl<'de> Deserialize<'de> for JsonTemplate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        fn parse<E: de::Error>(
+            value: serde_json::Value,
+        ) -> Result<JsonTemplate, E> {
+            Ok(match value {
+                serde_json::Value::Null => JsonTemplate::Null,
+                serde_json::Value::Bool(b) => JsonTemplate::Bool(b),
+                serde_json::Value::Number(n) => JsonTemplate::Number(n),
+                serde_json::Value::String(s) => {
+                    JsonTemplate::String(s.parse().map_err(E::custom)?)
+                }
+                serde_json::Value::Array(values) => JsonTemplate::Array(
+                    values.into_iter().map(parse).collect::<Result<_, _>>()?,
+                ),
+                serde_json::Value::Object(map) => JsonTemplate::Object(
+                    map.into_iter()
+                        .map(|(key, value)| Ok((key, parse(value)?)))
+                        .collect::<Result<_, E>>()?,
+                ),
+            })
         }
+
+        parse(serde_json::Value::deserialize(deserializer)?)
     }
 }
 
@@ -361,18 +559,10 @@ impl<'de> Deserialize<'de> for RecipeBody {
                 let (tag, value) = data.variant::<String>()?;
                 match tag.as_str() {
                     RecipeBody::VARIANT_JSON => {
-                        // Pretty print the JSON now and parse it as a template.
-                        // This is inefficient because we stringify the value
-                        // then immediately clone it during the parse :(
-                        let json: serde_json::Value =
-                            value.newtype_variant()?;
-                        let body = format!("{json:#}")
-                            .parse()
-                            .map_err(A::Error::custom)?;
-                        Ok(RecipeBody::Raw {
-                            body,
-                            content_type: Some(ContentType::Json),
-                        })
+                        Ok(RecipeBody::Json(value.newtype_variant()?))
+                    }
+                    RecipeBody::VARIANT_XML => {
+                        Ok(RecipeBody::Xml(value.newtype_variant()?))
                     }
                     RecipeBody::VARIANT_FORM_URLENCODED => {
                         Ok(RecipeBody::FormUrlencoded(value.newtype_variant()?))
@@ -380,6 +570,52 @@ impl<'de> Deserialize<'de> for RecipeBody {
                     RecipeBody::VARIANT_FORM_MULTIPART => {
                         Ok(RecipeBody::FormMultipart(value.newtype_variant()?))
                     }
+                    RecipeBody::VARIANT_COMMAND => {
+                        let CommandBody {
+                            command,
+                            content_type,
+                        } = value.newtype_variant()?;
+                        let content_type = content_type
+                            .map(|content_type| {
+                                content_type.parse::<Mime>().map_err(|error| {
+                                    A::Error::custom(format!(
+                                        "Invalid content type \
+                                        `{content_type}`: {error}"
+                                    ))
+                                })
+                            })
+                            .transpose()?;
+                        Ok(RecipeBody::Command {
+                            command,
+                            content_type,
+                        })
+                    }
+                    RecipeBody::VARIANT_VARIANTS => {
+                        let VariantsBody::<
+                            Template,
+                            IndexMap<String, RecipeBody>,
+                        > {
+                            select,
+                            variants,
+                        } = value.newtype_variant()?;
+                        if variants.is_empty() {
+                            return Err(A::Error::custom(
+                                "Body variants cannot be empty",
+                            ));
+                        }
+                        if let Some(name) =
+                            variants.iter().find_map(|(name, body)| {
+                                matches!(body, RecipeBody::Variants { .. })
+                                    .then_some(name)
+                            })
+                        {
+                            return Err(A::Error::custom(format!(
+                                "Body variant `{name}` cannot contain nested \
+                                variants"
+                            )));
+                        }
+                        Ok(RecipeBody::Variants { select, variants })
+                    }
                     other => Err(A::Error::unknown_variant(
                         other,
                         RecipeBody::ALL_VARIANTS,
@@ -392,6 +628,33 @@ impl<'de> Deserialize<'de> for RecipeBody {
     }
 }
 
+impl Serialize for ProfileValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Template(template) => template.serialize(serializer),
+            Self::Value(value) => value.serialize(serializer),
+        }
+    }
+}
+
+// Strings are parsed as templates, anything else is kept as a typed value
+impl<'de> Deserialize<'de> for ProfileValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(template) => {
+                Ok(Self::Template(template.parse().map_err(D::Error::custom)?))
+            }
+            value => Ok(Self::Value(value)),
+        }
+    }
+}
+
 /// Serialize/deserialize a duration with unit shorthand. This does *not* handle
 /// subsecond precision. Supported units are:
 /// - s
@@ -401,6 +664,7 @@ impl<'de> Deserialize<'de> for RecipeBody {
 ///
 /// Examples: `30s`, `5m`, `12h`, `3d`
 pub mod serde_duration {
+    use anyhow::anyhow;
     use derive_more::Display;
     use itertools::Itertools;
     use serde::{de::Error, Deserialize, Deserializer, Serializer};
@@ -410,6 +674,12 @@ pub mod serde_duration {
 
     #[derive(Debug, Display, EnumIter, EnumString)]
     enum Unit {
+        #[display("us")]
+        #[strum(serialize = "us")]
+        Microsecond,
+        #[display("ms")]
+        #[strum(serialize = "ms")]
+        Millisecond,
         #[display("s")]
         #[strum(serialize = "s")]
         Second,
@@ -431,15 +701,30 @@ pub mod serde_duration {
     where
         S: Serializer,
     {
-        // Always serialize as seconds, because it's easiest. Sub-second
-        // precision is lost
-        S::serialize_str(serializer, &format!("{}s", duration.as_secs()))
+        // Use the largest unit that can represent the value exactly, so it
+        // round trips. Sub-microsecond precision is lost
+        let s = if duration.subsec_nanos() == 0 {
+            format!("{}s", duration.as_secs())
+        } else if duration.subsec_micros() % 1000 == 0 {
+            format!("{}ms", duration.as_millis())
+        } else {
+            format!("{}us", duration.as_micros())
+        };
+        S::serialize_str(serializer, &s)
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
     where
         D: Deserializer<'de>,
     {
+        let input = String::deserialize(deserializer)?;
+        parse(&input).map_err(D::Error::custom)
+    }
+
+    /// Parse a duration string in the format `<quantity><unit>`, e.g. `12d`.
+    /// This is the same format used for deserialization, exposed for other
+    /// places that accept durations.
+    pub fn parse(input: &str) -> anyhow::Result<Duration> {
         fn quantity(input: &mut &str) -> PResult<u64> {
             digit1.parse_to().parse_next(input)
         }
@@ -448,31 +733,69 @@ pub mod serde_duration {
             take_while(1.., char::is_alphabetic).parse_next(input)
         }
 
-        let input = String::deserialize(deserializer)?;
         let (quantity, unit) = (quantity, unit)
-            .parse(&input)
+            .parse(input)
             // The format is so simple there isn't much value in spitting out a
             // specific parsing error, just use a canned one
             .map_err(|_| {
-                D::Error::custom(
-                    "Invalid duration, must be `<quantity><unit>` (e.g. `12d`)",
+                anyhow!(
+                    "Invalid duration, must be `<quantity><unit>` (e.g. `12d`)"
                 )
             })?;
 
         let unit = unit.parse().map_err(|_| {
-            D::Error::custom(format!(
+            anyhow!(
                 "Unknown duration unit `{unit}`; must be one of {}",
                 Unit::iter()
                     .format_with(", ", |unit, f| f(&format_args!("`{unit}`")))
-            ))
+            )
         })?;
-        let seconds = match unit {
-            Unit::Second => quantity,
-            Unit::Minute => quantity * 60,
-            Unit::Hour => quantity * 60 * 60,
-            Unit::Day => quantity * 60 * 60 * 24,
+        let seconds = |multiplier: u64| {
+            quantity
+                .checked_mul(multiplier)
+                .map(Duration::from_secs)
+                .ok_or_else(|| anyhow!("Duration `{input}` is too large"))
         };
-        Ok(Duration::from_secs(seconds))
+        match unit {
+            Unit::Microsecond => Ok(Duration::from_micros(quantity)),
+            Unit::Millisecond => Ok(Duration::from_millis(quantity)),
+            Unit::Second => seconds(1),
+            Unit::Minute => seconds(60),
+            Unit::Hour => seconds(60 * 60),
+            Unit::Day => seconds(60 * 60 * 24),
+        }
+    }
+
+    /// Serialize/deserialize an optional duration, with the same format
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+        use std::time::Duration;
+
+        pub fn serialize<S>(
+            duration: &Option<Duration>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match duration {
+                Some(duration) => super::serialize(duration, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D>(
+            deserializer: D,
+        ) -> Result<Option<Duration>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(with = "super")] Duration);
+
+            let wrapper = Option::<Wrapper>::deserialize(deserializer)?;
+            Ok(wrapper.map(|Wrapper(duration)| duration))
+        }
     }
 }
 
@@ -524,6 +847,46 @@ mod tests {
         assert_err!(serde_yaml::from_value::<Wrap>(yaml), expected_error);
     }
 
+    /// Strings are templates, everything else is a typed value. Round trips
+    /// should be no-ops.
+    #[rstest]
+    #[case::template("{{user_id}}", ProfileValue::Template("{{user_id}}".into()))]
+    #[case::number(3, ProfileValue::Value(json!(3)))]
+    #[case::bool(true, ProfileValue::Value(json!(true)))]
+    #[case::null(serde_yaml::Value::Null, ProfileValue::Value(json!(null)))]
+    #[case::sequence(
+        serde_yaml::Value::Sequence(vec![1.into(), "two".into()]),
+        ProfileValue::Value(json!([1, "two"])),
+    )]
+    #[case::mapping(
+        mapping([("a", 1)]),
+        ProfileValue::Value(json!({"a": 1})),
+    )]
+    fn test_serde_profile_value(
+        #[case] yaml: impl Into<serde_yaml::Value>,
+        #[case] expected: ProfileValue,
+    ) {
+        let yaml = yaml.into();
+        assert_eq!(
+            serde_yaml::from_value::<ProfileValue>(yaml.clone()).unwrap(),
+            expected,
+            "Deserialization mismatch"
+        );
+        assert_eq!(
+            serde_yaml::to_value(&expected).unwrap(),
+            yaml,
+            "Serialization mismatch"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_profile_value_error() {
+        assert_err!(
+            serde_yaml::from_value::<ProfileValue>("{{".into()),
+            "invalid identifier"
+        );
+    }
+
     /// Test serializing and deserializing recipe bodies. Round trips should all
     /// be no-ops. We use serde_yaml instead of serde_test because the handling
     /// of enums is a bit different, and we specifically only care about YAML.
@@ -533,29 +896,34 @@ mod tests {
         "{{user_id}}"
     )]
     #[case::json(
-        RecipeBody::Raw {
-            body: serde_json::to_string_pretty(&json!({"user": "{{user_id}}"}))
-                .unwrap()
-                .into(),
-            content_type: Some(ContentType::Json),
-        },
+        RecipeBody::Json(JsonTemplate::Object(indexmap! {
+            "user".into() => JsonTemplate::String("{{user_id}}".into()),
+            "age".into() => JsonTemplate::Number(3.into()),
+        })),
         serde_yaml::Value::Tagged(Box::new(TaggedValue {
             tag: Tag::new("json"),
-            value: mapping([("user", "{{user_id}}")])
+            value: mapping([
+                ("user", serde_yaml::Value::from("{{user_id}}")),
+                ("age", 3.into()),
+            ])
         })),
     )]
     #[case::json_nested(
-        RecipeBody::Raw {
-            body: serde_json::to_string_pretty(
-                &json!(r#"{"warning": "NOT an object"}"#)
-            ).unwrap().into(),
-            content_type: Some(ContentType::Json),
-        },
+        RecipeBody::Json(JsonTemplate::String(
+            r#"{"warning": "NOT an object"}"#.into()
+        )),
         serde_yaml::Value::Tagged(Box::new(TaggedValue {
             tag: Tag::new("json"),
             value: r#"{"warning": "NOT an object"}"#.into()
         })),
     )]
+    #[case::xml(
+        RecipeBody::Xml("<user id=\"{{user_id}}\"/>".into()),
+        serde_yaml::Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new("xml"),
+            value: "<user id=\"{{user_id}}\"/>".into(),
+        })),
+    )]
     #[case::form_urlencoded(
         RecipeBody::FormUrlencoded(indexmap! {
             "username".into() => "{{username}}".into(),
@@ -569,6 +937,59 @@ mod tests {
             ])
         }))
     )]
+    #[case::command(
+        RecipeBody::Command {
+            command: vec!["protoc".into(), "{{file}}".into()],
+            content_type: Some("application/x-protobuf".parse().unwrap()),
+        },
+        serde_yaml::Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new("command"),
+            value: mapping([
+                (
+                    "command",
+                    serde_yaml::Value::Sequence(vec![
+                        "protoc".into(),
+                        "{{file}}".into(),
+                    ]),
+                ),
+                ("content_type", "application/x-protobuf".into()),
+            ])
+        }))
+    )]
+    #[case::variants(
+        RecipeBody::Variants {
+            select: Some("{{encoding}}".into()),
+            variants: indexmap! {
+                "raw".into() => RecipeBody::Raw {
+                    body: "{{user_id}}".into(),
+                    content_type: None,
+                },
+                "form".into() => RecipeBody::FormUrlencoded(indexmap! {
+                    "user".into() => "{{user_id}}".into(),
+                }),
+            },
+        },
+        serde_yaml::Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new("variants"),
+            value: mapping([
+                ("select", serde_yaml::Value::from("{{encoding}}")),
+                (
+                    "variants",
+                    mapping([
+                        ("raw", serde_yaml::Value::from("{{user_id}}")),
+                        (
+                            "form",
+                            serde_yaml::Value::Tagged(Box::new(TaggedValue {
+                                tag: Tag::new("form_urlencoded"),
+                                value: mapping([("user", "{{user_id}}")]),
+                            })),
+                        ),
+                    ])
+                    .into(),
+                ),
+            ])
+        }))
+    )]
     fn test_serde_recipe_body(
         #[case] body: RecipeBody,
         #[case] yaml: impl Into<serde_yaml::Value>,
@@ -605,7 +1026,18 @@ mod tests {
             value: "{{user_id}}".into()
         })),
         "unknown variant `raw`, expected one of \
-        `json`, `form_urlencoded`, `form_multipart`",
+        `json`, `xml`, `form_urlencoded`, `form_multipart`, `command`, \
+        `variants`",
+    )]
+    #[case::command_invalid_content_type(
+        serde_yaml::Value::Tagged(Box::new(TaggedValue{
+            tag: Tag::new("command"),
+            value: mapping([
+                ("command", serde_yaml::Value::Sequence(vec!["echo".into()])),
+                ("content_type", "not a mime".into()),
+            ]),
+        })),
+        "Invalid content type `not a mime`",
     )]
     #[case::form_urlencoded_wrong_type(
         serde_yaml::Value::Tagged(Box::new(TaggedValue{
@@ -614,6 +1046,32 @@ mod tests {
         })),
         "invalid type: string \"{{user_id}}\", expected a map"
     )]
+    #[case::variants_empty(
+        serde_yaml::Value::Tagged(Box::new(TaggedValue{
+            tag: Tag::new("variants"),
+            value: mapping([("variants", Mapping::default())]),
+        })),
+        "Body variants cannot be empty"
+    )]
+    #[case::variants_nested(
+        serde_yaml::Value::Tagged(Box::new(TaggedValue{
+            tag: Tag::new("variants"),
+            value: mapping([(
+                "variants",
+                mapping([(
+                    "inner",
+                    serde_yaml::Value::Tagged(Box::new(TaggedValue{
+                        tag: Tag::new("variants"),
+                        value: mapping([(
+                            "variants",
+                            mapping([("raw", "hello")]),
+                        )]),
+                    })),
+                )]),
+            )]),
+        })),
+        "Body variant `inner` cannot contain nested variants"
+    )]
     fn test_deserialize_recipe_error(
         #[case] yaml: impl Into<serde_yaml::Value>,
         #[case] expected_error: &str,
@@ -657,6 +1115,27 @@ mod tests {
             Token::SeqEnd,
         ],
     )]
+    #[case::map_list(
+        &[
+            Token::Map { len: None },
+            Token::Str("id"),
+            Token::Seq { len: None },
+            Token::I64(1),
+            Token::Str("{{value}}"),
+            Token::SeqEnd,
+            Token::Str("param"),
+            Token::Str("value"),
+            Token::MapEnd,
+        ],
+        vec![("id", "1"), ("id", "{{value}}"), ("param", "value")],
+        &[
+            Token::Seq { len: Some(3) },
+            Token::Str("id=1"),
+            Token::Str("id={{value}}"),
+            Token::Str("param=value"),
+            Token::SeqEnd,
+        ],
+    )]
     #[case::unit(
         &[Token::Unit],
         vec![],
@@ -683,6 +1162,68 @@ mod tests {
         assert_ser_tokens(&expected_value, expected_tokens);
     }
 
+    /// Test serializing/deserializing headers. Repeated headers are
+    /// deserialized from a list, and serialized back to one
+    #[rstest]
+    #[case::single(
+        &[
+            Token::Map { len: None },
+            Token::Str("Accept"),
+            Token::Str("application/json"),
+            Token::MapEnd,
+        ],
+        vec![("accept", "application/json")],
+        &[
+            Token::Map { len: Some(1) },
+            Token::Str("accept"),
+            Token::Str("application/json"),
+            Token::MapEnd,
+        ],
+    )]
+    #[case::repeated(
+        &[
+            Token::Map { len: None },
+            Token::Str("Cookie"),
+            Token::Seq { len: None },
+            Token::Str("a=1"),
+            Token::Str("b={{value}}"),
+            Token::SeqEnd,
+            Token::Str("accept"),
+            Token::Str("*/*"),
+            Token::MapEnd,
+        ],
+        vec![("cookie", "a=1"), ("cookie", "b={{value}}"), ("accept", "*/*")],
+        &[
+            Token::Map { len: Some(2) },
+            Token::Str("cookie"),
+            Token::Seq { len: Some(2) },
+            Token::Str("a=1"),
+            Token::Str("b={{value}}"),
+            Token::SeqEnd,
+            Token::Str("accept"),
+            Token::Str("*/*"),
+            Token::MapEnd,
+        ],
+    )]
+    fn test_serde_headers(
+        #[case] input_tokens: &[Token],
+        #[case] expected_value: Vec<(&str, &str)>,
+        #[case] expected_tokens: &[Token],
+    ) {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        #[serde(transparent)]
+        struct Wrap(#[serde(with = "serde_headers")] Vec<(String, Template)>);
+
+        let expected_value = Wrap(
+            expected_value
+                .into_iter()
+                .map(|(header, value)| (header.into(), value.into()))
+                .collect(),
+        );
+        assert_de_tokens::<Wrap>(&expected_value, input_tokens);
+        assert_ser_tokens(&expected_value, expected_tokens);
+    }
+
     /// A wrapper that forces serde_test to use our custom serialize/deserialize
     /// functions
     #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -692,9 +1233,11 @@ mod tests {
     #[rstest]
     #[case::seconds_short(Duration::from_secs(3), "3s")]
     #[case::seconds_long(Duration::from_secs(3000), "3000s")]
-    // Subsecond precision is lost
-    #[case::seconds_subsecond_lost(Duration::from_millis(400), "0s")]
-    #[case::seconds_subsecond_round_down(Duration::from_millis(1999), "1s")]
+    #[case::milliseconds(Duration::from_millis(400), "400ms")]
+    #[case::milliseconds_over_second(Duration::from_millis(1999), "1999ms")]
+    #[case::microseconds(Duration::from_micros(1500), "1500us")]
+    // Sub-microsecond precision is lost
+    #[case::nanoseconds_lost(Duration::from_nanos(1_500_999), "1500us")]
     fn test_serialize_duration(
         #[case] duration: Duration,
         #[case] expected: &'static str,
@@ -703,6 +1246,9 @@ mod tests {
     }
 
     #[rstest]
+    #[case::microseconds("250us", Duration::from_micros(250))]
+    #[case::milliseconds("250ms", Duration::from_millis(250))]
+    #[case::milliseconds_large("1500ms", Duration::from_millis(1500))]
     #[case::seconds_zero("0s", Duration::from_secs(0))]
     #[case::seconds_short("1s", Duration::from_secs(1))]
     #[case::seconds_longer("100s", Duration::from_secs(100))]
@@ -733,9 +1279,14 @@ mod tests {
         "3.5s",
         "Invalid duration, must be `<quantity><unit>` (e.g. `12d`)"
     )]
+    #[case::overflow(
+        "999999999999999999d",
+        "Duration `999999999999999999d` is too large"
+    )]
     #[case::invalid_unit(
         "3hr",
-        "Unknown duration unit `hr`; must be one of `s`, `m`, `h`, `d`"
+        "Unknown duration unit `hr`; must be one of `us`, `ms`, `s`, `m`, `h`, \
+        `d`"
     )]
     fn test_deserialize_duration_error(
         #[case] s: &'static str,
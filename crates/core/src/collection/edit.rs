@@ -0,0 +1,621 @@
+//! Write programmatic changes back to a collection file, without clobbering
+//! the user's comments and formatting. Serde can't round-trip comments, so
+//! instead we diff the serialized collection before and after the change, and
+//! apply only the changed entries to the original text. Anything that didn't
+//! change is left exactly as the user wrote it.
+
+use crate::{
+    collection::{include::has_includes, Collection},
+    util::parse_yaml,
+};
+use anyhow::{anyhow, bail, Context};
+use serde_yaml::{Mapping, Value};
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// The text of a collection file, plus a snapshot of the collection it was
+/// loaded as. Use this to write modifications to the collection back to the
+/// file.
+pub struct CollectionSource {
+    path: PathBuf,
+    source: String,
+    original: Value,
+}
+
+impl CollectionSource {
+    /// Snapshot a collection before modifying it. `source` is the content of
+    /// the file at `path` that the collection was loaded from. Fails if the
+    /// file includes other files, because entries from those files would be
+    /// written into this one.
+    pub fn new(
+        path: &Path,
+        source: String,
+        collection: &Collection,
+    ) -> anyhow::Result<Self> {
+        let yaml: Value = parse_yaml(source.as_bytes())?;
+        if has_includes(&yaml) {
+            bail!(
+                "Collections that include other files can't be edited. Edit \
+                the included files by hand instead"
+            );
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            source,
+            original: serde_yaml::to_value(collection)?,
+        })
+    }
+
+    /// Get the file content for the modified collection. Entries that
+    /// differ from the original snapshot are added, replaced, or removed in
+    /// the original text. Fails if a changed entry is in a part of the file
+    /// that can't be edited safely (e.g. a flow-style mapping), or if the
+    /// edited file doesn't load as the modified collection (e.g. because a
+    /// replaced entry defined an anchor used elsewhere). In that case the
+    /// caller should fall back to serializing the entire collection.
+    pub fn update(&self, collection: &Collection) -> anyhow::Result<String> {
+        let updated = serde_yaml::to_value(collection)?;
+        let (Value::Mapping(original), Value::Mapping(updated_mapping)) =
+            (&self.original, &updated)
+        else {
+            bail!("Collection is not a mapping");
+        };
+        let mut document = Document::new(&self.source);
+        diff_mapping(
+            &mut document,
+            &mut Vec::new(),
+            original,
+            updated_mapping,
+        )?;
+        let output = document.to_string();
+
+        // We only understand a subset of YAML, so make sure the edits didn't
+        // break anything
+        let reloaded = Collection::parse_reader(output.as_bytes(), &self.path)
+            .context("Edited collection fails to load")?;
+        if serde_yaml::to_value(&reloaded)? != updated {
+            bail!("Edited collection does not match the modified collection");
+        }
+        Ok(output)
+    }
+}
+
+/// Apply the differences between two mappings to the document. The mapping
+/// is located at `path` within the document.
+fn diff_mapping(
+    document: &mut Document,
+    path: &mut Vec<String>,
+    original: &Mapping,
+    updated: &Mapping,
+) -> anyhow::Result<()> {
+    for key in original.keys() {
+        if !updated.contains_key(key) {
+            document.remove(path, key_str(key)?)?;
+        }
+    }
+
+    for (key, value) in updated {
+        let key = key_str(key)?;
+        let Some(original_value) = original.get(key) else {
+            document.insert(path, key, value)?;
+            continue;
+        };
+        if original_value == value {
+            continue;
+        }
+
+        match (original_value, value) {
+            // Top-level sections (profiles, requests, etc.) are diffed by
+            // entry
+            (Value::Mapping(original), Value::Mapping(updated))
+                if path.is_empty() =>
+            {
+                path.push(key.to_owned());
+                diff_mapping(document, path, original, updated)?;
+                path.pop();
+            }
+            // Folders are diffed by child, as long as the folder itself is
+            // unchanged
+            _ => match (folder_parts(original_value), folder_parts(value)) {
+                (
+                    Some((original_folder, original_children)),
+                    Some((updated_folder, updated_children)),
+                ) if original_folder == updated_folder => {
+                    path.extend([key.to_owned(), "requests".to_owned()]);
+                    diff_mapping(
+                        document,
+                        path,
+                        &original_children,
+                        &updated_children,
+                    )?;
+                    path.truncate(path.len() - 2);
+                }
+                _ => document.replace(path, key, value)?,
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Split a serialized folder into its own fields and its children
+fn folder_parts(value: &Value) -> Option<(Mapping, Mapping)> {
+    let Value::Tagged(tagged) = value else {
+        return None;
+    };
+    let Value::Mapping(folder) = &tagged.value else {
+        return None;
+    };
+    if tagged.tag != "folder" {
+        return None;
+    }
+    let mut folder = folder.clone();
+    let children = match folder.remove("requests") {
+        Some(Value::Mapping(children)) => children,
+        _ => Mapping::new(),
+    };
+    Some((folder, children))
+}
+
+fn key_str(key: &Value) -> anyhow::Result<&str> {
+    key.as_str()
+        .ok_or_else(|| anyhow!("Unsupported mapping key `{key:?}`"))
+}
+
+/// A YAML document, as lines of text. This understands just enough of YAML's
+/// block structure to locate mapping entries by indentation.
+struct Document {
+    lines: Vec<String>,
+    /// Line ending of the original source, so it's preserved on output
+    line_ending: &'static str,
+}
+
+/// The location of a mapping's entries within the document
+struct MappingLocation {
+    /// Lines containing the mapping's entries
+    lines: Range<usize>,
+    /// Indentation of the mapping's keys
+    indent: usize,
+}
+
+impl Document {
+    fn new(source: &str) -> Self {
+        Self {
+            lines: source.lines().map(String::from).collect(),
+            line_ending: if source.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            },
+        }
+    }
+
+    /// Add an entry to the end of the mapping at `path`. If the mapping is a
+    /// top-level section that isn't in the file yet, it's created.
+    fn insert(
+        &mut self,
+        path: &[String],
+        key: &str,
+        value: &Value,
+    ) -> anyhow::Result<()> {
+        let (position, entry) = match self.find_mapping(path)? {
+            Some(location) if location.indent > 0 => (
+                self.content_end(location.lines),
+                render_entry(key, value, location.indent)?,
+            ),
+            Some(_) => (self.end(), render_entry(key, value, 0)?),
+            None if path.len() == 1 => {
+                let section = Mapping::from_iter([(key.into(), value.clone())]);
+                (self.end(), render_entry(&path[0], &section.into(), 0)?)
+            }
+            None => bail!("Mapping `{}` not found", path.join(".")),
+        };
+
+        // Separate top-level sections with a blank line
+        let blank_line = position == self.end()
+            && position > 0
+            && !self.lines[position - 1].trim().is_empty();
+        self.lines.splice(
+            position..position,
+            blank_line.then(String::new).into_iter().chain(entry),
+        );
+        Ok(())
+    }
+
+    /// Replace an existing entry in the mapping at `path`, in place
+    fn replace(
+        &mut self,
+        path: &[String],
+        key: &str,
+        value: &Value,
+    ) -> anyhow::Result<()> {
+        let (lines, indent) = self.find_entry(path, key)?;
+        let entry = render_entry(key, value, indent)?;
+        self.lines.splice(lines, entry);
+        Ok(())
+    }
+
+    /// Remove an entry from the mapping at `path`
+    fn remove(&mut self, path: &[String], key: &str) -> anyhow::Result<()> {
+        let (lines, _) = self.find_entry(path, key)?;
+        self.lines.drain(lines);
+        Ok(())
+    }
+
+    /// Find the lines and indentation of an entry within the mapping at
+    /// `path`
+    fn find_entry(
+        &self,
+        path: &[String],
+        key: &str,
+    ) -> anyhow::Result<(Range<usize>, usize)> {
+        let location = self
+            .find_mapping(path)?
+            .ok_or_else(|| anyhow!("Mapping `{}` not found", path.join(".")))?;
+        let lines = self.entry_lines(&location, key).ok_or_else(|| {
+            anyhow!("Entry `{key}` not found in `{}`", path.join("."))
+        })?;
+        Ok((lines, location.indent))
+    }
+
+    /// Find the mapping at a path of keys. The empty path is the root.
+    /// Return `None` if any key along the path is missing.
+    fn find_mapping(
+        &self,
+        path: &[String],
+    ) -> anyhow::Result<Option<MappingLocation>> {
+        let mut location = MappingLocation {
+            lines: 0..self.lines.len(),
+            indent: 0,
+        };
+        for key in path {
+            let Some(lines) = self.entry_lines(&location, key) else {
+                return Ok(None);
+            };
+            let key_line = &self.lines[lines.start];
+            if !is_block_mapping(key_line) {
+                bail!("`{key}` is not a block mapping");
+            }
+            let children = lines.start + 1..lines.end;
+            location = MappingLocation {
+                indent: children
+                    .clone()
+                    .map(|i| &self.lines[i])
+                    .find(|line| is_content(line))
+                    .map(|line| indent(line))
+                    // Empty mapping, use a reasonable default
+                    .unwrap_or(indent(key_line) + 2),
+                lines: children,
+            };
+        }
+        Ok(Some(location))
+    }
+
+    /// Find the lines of a single entry within a mapping
+    fn entry_lines(
+        &self,
+        location: &MappingLocation,
+        key: &str,
+    ) -> Option<Range<usize>> {
+        let start = location.lines.clone().find(|&i| {
+            let line = &self.lines[i];
+            is_content(line)
+                && indent(line) == location.indent
+                && line_key(line) == Some(key)
+        })?;
+        // The entry ends at the next line that isn't nested under it
+        let end = (start + 1..location.lines.end)
+            .find(|&i| {
+                let line = &self.lines[i];
+                is_content(line) && indent(line) <= location.indent
+            })
+            .unwrap_or(location.lines.end);
+        Some(start..self.content_end(start..end))
+    }
+
+    /// Get the index after the last non-blank line in the document. New
+    /// top-level entries go here, after any trailing comments.
+    fn end(&self) -> usize {
+        self.lines
+            .iter()
+            .rposition(|line| !line.trim().is_empty())
+            .map(|i| i + 1)
+            .unwrap_or(0)
+    }
+
+    /// Get the index after the last content line in a range. Trailing blank
+    /// lines and comments are excluded, because they generally belong to
+    /// whatever comes next.
+    fn content_end(&self, lines: Range<usize>) -> usize {
+        lines
+            .rev()
+            .find(|&i| is_content(&self.lines[i]))
+            .map(|i| i + 1)
+            .unwrap_or(lines.start)
+    }
+}
+
+impl std::fmt::Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            write!(f, "{line}{}", self.line_ending)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialize a single mapping entry, indented to the given level
+fn render_entry(
+    key: &str,
+    value: &Value,
+    indent: usize,
+) -> anyhow::Result<Vec<String>> {
+    let entry = Mapping::from_iter([(key.into(), value.clone())]);
+    let yaml = serde_yaml::to_string(&entry)?;
+    Ok(yaml
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{:indent$}{line}", "")
+            }
+        })
+        .collect())
+}
+
+/// Does this line contain anything other than whitespace or a comment?
+fn is_content(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with('#')
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Split a `key: value` line into its key and value. Quoted keys are
+/// supported, but only without escape sequences.
+fn split_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    let (key, rest) = match line.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let end = line[1..].find(quote)? + 1;
+            (&line[1..end], &line[end + 1..])
+        }
+        _ => {
+            let end = line.find(':')?;
+            (&line[..end], &line[end..])
+        }
+    };
+    let value = rest.strip_prefix(':')?;
+    (value.is_empty() || value.starts_with(' ')).then_some((key, value))
+}
+
+fn line_key(line: &str) -> Option<&str> {
+    split_line(line).map(|(key, _)| key)
+}
+
+/// Is the value of this `key:` line a block mapping on the following lines?
+/// It can have a tag or anchor, but no other inline content.
+fn is_block_mapping(line: &str) -> bool {
+    let Some((_, value)) = split_line(line) else {
+        return false;
+    };
+    value
+        .split_whitespace()
+        .take_while(|token| !token.starts_with('#'))
+        .all(|token| token.starts_with('!') || token.starts_with('&'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_err, collection::MergeConflict};
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    /// Merge `other` into the collection defined by `source`, and get the
+    /// updated source
+    fn merge(source: &str, other: &str) -> anyhow::Result<String> {
+        let mut collection: Collection = serde_yaml::from_str(source).unwrap();
+        let collection_source = CollectionSource::new(
+            Path::new("slumber.yml"),
+            source.to_owned(),
+            &collection,
+        )
+        .unwrap();
+        collection
+            .merge(
+                serde_yaml::from_str(other).unwrap(),
+                MergeConflict::Overwrite,
+            )
+            .unwrap();
+        let updated = collection_source.update(&collection)?;
+
+        // Updated source should load as the modified collection
+        let loaded: Collection = serde_yaml::from_str(&updated).unwrap();
+        assert_eq!(
+            serde_yaml::to_value(&loaded).unwrap(),
+            serde_yaml::to_value(&collection).unwrap()
+        );
+        Ok(updated)
+    }
+
+    /// Comments and formatting of unchanged entries are retained
+    #[test]
+    fn test_update() {
+        let source = r#"# My collection
+profiles:
+  local:
+    data:
+      host: http://localhost # Default port
+requests:
+  # Auth stuff
+  login: !request
+    method: POST
+    url: "{{host}}/login"
+  fish: !folder
+    requests:
+      list_fish: !request
+        method: GET
+        url: "{{host}}/fishes" # All of them
+"#;
+        let updated = merge(
+            source,
+            r#"
+profiles:
+  remote:
+    data:
+      host: https://example.com
+requests:
+  login: !request
+    method: POST
+    url: "{{host}}/v2/login"
+  fish: !folder
+    requests:
+      get_fish: !request
+        method: GET
+        url: "{{host}}/fishes/1"
+"#,
+        )
+        .unwrap();
+
+        for unchanged in [
+            "# My collection\nprofiles:\n  local:\n    data:\n      \
+            host: http://localhost # Default port\n",
+            "  # Auth stuff\n  login: !request\n",
+            "      list_fish: !request\n        method: GET\n        \
+            url: \"{{host}}/fishes\" # All of them\n",
+        ] {
+            assert!(
+                updated.contains(unchanged),
+                "Expected {unchanged:?} in:\n{updated}"
+            );
+        }
+        assert!(updated.contains("v2/login"));
+        assert!(!updated.contains("\"{{host}}/login\""));
+        // New entries are appended to the mappings they belong in
+        let remote = updated.find("  remote:").unwrap();
+        assert!(remote < updated.find("requests:").unwrap());
+        let get_fish = updated.find("      get_fish: !request").unwrap();
+        assert!(get_fish > updated.find("list_fish").unwrap());
+    }
+
+    /// Missing top-level sections are added to the end of the file
+    #[test]
+    fn test_update_new_section() {
+        let source = "requests:
+  login: !request
+    method: POST
+    url: /login
+# Trailing comment
+";
+        let updated = merge(
+            source,
+            "
+chains:
+  username:
+    source: !env
+      variable: USER
+",
+        )
+        .unwrap();
+        assert!(updated.starts_with(source), "{updated}");
+        assert!(updated.contains("\nchains:\n  username:\n"), "{updated}");
+    }
+
+    /// Flow-style mappings can't be edited
+    #[test]
+    fn test_update_flow_mapping() {
+        assert_err!(
+            merge(
+                "requests: {}\n",
+                "
+requests:
+  login: !request
+    method: POST
+    url: /login
+",
+            ),
+            "`requests` is not a block mapping"
+        );
+    }
+
+    /// Replacing an entry that defines an anchor would orphan its aliases, so
+    /// the edit is rejected rather than writing a broken file
+    #[test]
+    fn test_update_anchor() {
+        assert_err!(
+            merge(
+                "profiles:
+  local:
+    data: &data
+      host: http://localhost
+  remote:
+    data: *data
+",
+                "
+profiles:
+  local:
+    data:
+      host: http://127.0.0.1
+",
+            ),
+            "Edited collection fails to load"
+        );
+    }
+
+    /// Windows line endings are retained
+    #[test]
+    fn test_update_crlf() {
+        let source =
+            "requests:\r\n  login: !request\r\n    method: POST\r\n    \
+            url: /login\r\n";
+        let updated = merge(
+            source,
+            "
+requests:
+  logout: !request
+    method: POST
+    url: /logout
+",
+        )
+        .unwrap();
+        assert!(updated.starts_with(source), "{updated:?}");
+        assert!(updated.contains("  logout: !request\r\n"), "{updated:?}");
+        assert!(!updated.replace("\r\n", "").contains('\n'), "{updated:?}");
+    }
+
+    #[rstest]
+    #[case::plain("  key: value", Some(("key", " value")))]
+    #[case::no_value("key:", Some(("key", "")))]
+    #[case::double_quoted(r#""a: b": c"#, Some(("a: b", " c")))]
+    #[case::single_quoted("'a': !tag", Some(("a", " !tag")))]
+    #[case::url("url: http://localhost", Some(("url", " http://localhost")))]
+    #[case::not_a_key("http://localhost", None)]
+    #[case::sequence("- item", None)]
+    fn test_split_line(
+        #[case] line: &str,
+        #[case] expected: Option<(&str, &str)>,
+    ) {
+        assert_eq!(split_line(line), expected);
+    }
+
+    /// Collections that include other files can't be edited, because entries
+    /// from the included files would be written into the main file
+    #[test]
+    fn test_new_include() {
+        let source = "include:\n  - requests.yml\n";
+        assert_err!(
+            CollectionSource::new(
+                Path::new("slumber.yml"),
+                source.to_owned(),
+                &Collection::default(),
+            )
+            .map(|_| ()),
+            "Collections that include other files can't be edited"
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Recipe inheritance via the `extends` field. This is resolved on the raw
+//! YAML, *before* deserialization, because a child recipe is allowed to omit
+//! required fields (e.g. `url`) that it inherits from its parent.
+
+use anyhow::{anyhow, bail};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde_yaml::{Mapping, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Field on a recipe that names the recipe it inherits from
+const EXTENDS_FIELD: &str = "extends";
+/// Headers are merged by name, instead of being replaced wholesale
+const HEADERS_FIELD: &str = "headers";
+/// Recipe names aren't inherited, so the child is distinguishable in the UI
+const NAME_FIELD: &str = "name";
+
+/// Replace every recipe in a collection that has an `extends` field with the
+/// merged result of itself and its parent. Parents can extend other recipes,
+/// so inheritance chains are resolved recursively. Cycles are an error.
+pub fn resolve_extends(collection: &mut Value) -> anyhow::Result<()> {
+    let Some(requests) = collection.get_mut("requests") else {
+        return Ok(());
+    };
+
+    let mut recipes = IndexMap::new();
+    let mut folders = HashSet::new();
+    collect_nodes(requests, &mut recipes, &mut folders);
+    if !recipes
+        .values()
+        .any(|recipe| recipe.contains_key(EXTENDS_FIELD))
+    {
+        return Ok(());
+    }
+
+    let mut resolver = Resolver {
+        recipes: &recipes,
+        folders: &folders,
+        resolved: HashMap::new(),
+        stack: Vec::new(),
+    };
+    for id in recipes.keys() {
+        resolver.resolve(id)?;
+    }
+    let mut resolved = resolver.resolved;
+    replace_recipes(requests, &mut resolved);
+    Ok(())
+}
+
+/// Find every recipe and folder in a `requests` mapping, recursively. Recipes
+/// are cloned out so they can be resolved without borrowing the tree.
+fn collect_nodes(
+    requests: &Value,
+    recipes: &mut IndexMap<String, Mapping>,
+    folders: &mut HashSet<String>,
+) {
+    let Value::Mapping(requests) = requests else {
+        return;
+    };
+    for (id, node) in requests {
+        let (Some(id), Value::Tagged(node)) = (id.as_str(), node) else {
+            continue;
+        };
+        if node.tag == "request" {
+            if let Value::Mapping(recipe) = &node.value {
+                recipes.insert(id.to_owned(), recipe.clone());
+            }
+        } else if node.tag == "folder" {
+            folders.insert(id.to_owned());
+            if let Some(children) = node.value.get("requests") {
+                collect_nodes(children, recipes, folders);
+            }
+        }
+    }
+}
+
+/// Write resolved recipes back into the tree
+fn replace_recipes(
+    requests: &mut Value,
+    resolved: &mut HashMap<String, Mapping>,
+) {
+    let Value::Mapping(requests) = requests else {
+        return;
+    };
+    for (id, node) in requests {
+        let (Some(id), Value::Tagged(node)) = (id.as_str(), node) else {
+            continue;
+        };
+        if node.tag == "request" {
+            if let Some(recipe) = resolved.remove(id) {
+                node.value = Value::Mapping(recipe);
+            }
+        } else if node.tag == "folder" {
+            if let Some(children) = node.value.get_mut("requests") {
+                replace_recipes(children, resolved);
+            }
+        }
+    }
+}
+
+struct Resolver<'a> {
+    recipes: &'a IndexMap<String, Mapping>,
+    folders: &'a HashSet<String>,
+    /// Recipes whose inheritance has been fully resolved
+    resolved: HashMap<String, Mapping>,
+    /// IDs of the recipes currently being resolved, for cycle detection
+    stack: Vec<&'a str>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Get the fully resolved form of a recipe, resolving its parents first
+    fn resolve(&mut self, id: &'a str) -> anyhow::Result<Mapping> {
+        if let Some(recipe) = self.resolved.get(id) {
+            return Ok(recipe.clone());
+        }
+        if let Some(start) = self.stack.iter().position(|other| *other == id) {
+            let cycle = self.stack[start..]
+                .iter()
+                .chain([&id])
+                .map(|id| format!("`{id}`"))
+                .join(" -> ");
+            bail!("Recipe inheritance cycle: {cycle}");
+        }
+
+        let mut recipe = self.recipes[id].clone();
+        if let Some(parent_id) = recipe.remove(EXTENDS_FIELD) {
+            let parent_id = parent_id.as_str().ok_or_else(|| {
+                anyhow!("Recipe `{id}`: `extends` must be a recipe ID")
+            })?;
+            let (parent_id, _) =
+                self.recipes.get_key_value(parent_id).ok_or_else(|| {
+                    if self.folders.contains(parent_id) {
+                        anyhow!(
+                            "Recipe `{id}` extends folder `{parent_id}`; \
+                            only recipes can be extended"
+                        )
+                    } else {
+                        anyhow!(
+                            "Recipe `{id}` extends unknown recipe \
+                            `{parent_id}`"
+                        )
+                    }
+                })?;
+
+            self.stack.push(id);
+            let parent = self.resolve(parent_id)?;
+            self.stack.pop();
+            recipe = merge(parent, recipe);
+        }
+
+        self.resolved.insert(id.to_owned(), recipe.clone());
+        Ok(recipe)
+    }
+}
+
+/// Apply a child recipe's fields on top of its parent's. Fields given in the
+/// child replace the parent's, except for headers, which are merged by name.
+fn merge(mut parent: Mapping, child: Mapping) -> Mapping {
+    parent.remove(NAME_FIELD);
+    for (field, value) in child {
+        match (parent.get_mut(&field), value) {
+            (
+                Some(Value::Mapping(parent_headers)),
+                Value::Mapping(child_headers),
+            ) if field.as_str() == Some(HEADERS_FIELD) => {
+                parent_headers.extend(child_headers)
+            }
+            (_, value) => {
+                parent.insert(field, value);
+            }
+        }
+    }
+    parent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_err;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn resolve(yaml: &str) -> anyhow::Result<Value> {
+        let mut value: Value = serde_yaml::from_str(yaml).unwrap();
+        resolve_extends(&mut value)?;
+        Ok(value)
+    }
+
+    /// Child fields override the parent's, headers are merged, and the name
+    /// isn't inherited
+    #[test]
+    fn test_override() {
+        let actual = resolve(
+            r#"
+requests:
+  base: !request
+    name: Base
+    method: GET
+    url: "{{host}}/fishes"
+    headers:
+      Accept: application/json
+      X-Fish: big
+  child: !request
+    extends: base
+    method: POST
+    headers:
+      X-Fish: small
+"#,
+        )
+        .unwrap();
+        let expected: Value = serde_yaml::from_str(
+            r#"
+requests:
+  base: !request
+    name: Base
+    method: GET
+    url: "{{host}}/fishes"
+    headers:
+      Accept: application/json
+      X-Fish: big
+  child: !request
+    method: POST
+    url: "{{host}}/fishes"
+    headers:
+      Accept: application/json
+      X-Fish: small
+"#,
+        )
+        .unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    /// Inheritance chains resolve through multiple levels, and across folders
+    #[test]
+    fn test_chain() {
+        let actual = resolve(
+            r#"
+requests:
+  folder: !folder
+    requests:
+      grandchild: !request
+        extends: child
+        url: /grandchild
+  child: !request
+    extends: base
+    query:
+      big: "true"
+  base: !request
+    method: GET
+    url: /base
+"#,
+        )
+        .unwrap();
+        let expected: Value = serde_yaml::from_str(
+            r#"
+requests:
+  folder: !folder
+    requests:
+      grandchild: !request
+        method: GET
+        url: /grandchild
+        query:
+          big: "true"
+  child: !request
+    method: GET
+    url: /base
+    query:
+      big: "true"
+  base: !request
+    method: GET
+    url: /base
+"#,
+        )
+        .unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case::cycle(
+        "requests:
+  a: !request
+    extends: b
+  b: !request
+    extends: c
+  c: !request
+    extends: b",
+        "Recipe inheritance cycle: `b` -> `c` -> `b`"
+    )]
+    #[case::self_cycle(
+        "requests:
+  a: !request
+    extends: a",
+        "Recipe inheritance cycle: `a` -> `a`"
+    )]
+    #[case::unknown(
+        "requests:
+  a: !request
+    extends: b",
+        "Recipe `a` extends unknown recipe `b`"
+    )]
+    #[case::folder(
+        "requests:
+  a: !request
+    extends: f
+  f: !folder
+    requests: {}",
+        "Recipe `a` extends folder `f`; only recipes can be extended"
+    )]
+    #[case::not_string(
+        "requests:
+  a: !request
+    extends: [b]",
+        "Recipe `a`: `extends` must be a recipe ID"
+    )]
+    fn test_error(#[case] yaml: &str, #[case] expected_error: &str) {
+        assert_err!(resolve(yaml), expected_error);
+    }
+}
@@ -0,0 +1,133 @@
+//! Fields starting with `.` are ignored throughout the structure of a
+//! collection: at the root, and in every profile, chain, recipe, and folder.
+//! This lets users annotate a collection (e.g. `.note: Deprecated, use v2`)
+//! or stash values for YAML anchors without triggering unknown field errors.
+//! Like `extends`, this is applied to the raw YAML before deserialization, so
+//! the models can keep denying unknown fields.
+
+use serde_yaml::{Mapping, Value};
+
+/// Fields with this prefix are ignored
+const IGNORE_PREFIX: char = '.';
+
+/// Remove all ignored fields from the structural mappings of a collection.
+/// User data (profile values, headers, bodies, etc.) is left untouched, so
+/// keys starting with `.` can still be sent in requests.
+pub fn strip_ignored_fields(collection: &mut Value) {
+    let Value::Mapping(collection) = collection else {
+        return;
+    };
+    strip(collection);
+    for field in ["profiles", "chains"] {
+        if let Some(Value::Mapping(entries)) = collection.get_mut(field) {
+            for entry in entries.values_mut() {
+                if let Value::Mapping(entry) = entry {
+                    strip(entry);
+                }
+            }
+        }
+    }
+    if let Some(requests) = collection.get_mut("requests") {
+        strip_nodes(requests);
+    }
+}
+
+/// Strip ignored fields from every recipe and folder in a `requests`
+/// mapping, recursively
+fn strip_nodes(requests: &mut Value) {
+    let Value::Mapping(requests) = requests else {
+        return;
+    };
+    for node in requests.values_mut() {
+        let Value::Tagged(node) = node else {
+            continue;
+        };
+        let Value::Mapping(fields) = &mut node.value else {
+            continue;
+        };
+        strip(fields);
+        if node.tag == "folder" {
+            if let Some(children) = fields.get_mut("requests") {
+                strip_nodes(children);
+            }
+        }
+    }
+}
+
+/// Remove ignored fields from a single mapping
+fn strip(mapping: &mut Mapping) {
+    mapping.retain(|key, _| {
+        !key.as_str()
+            .is_some_and(|key| key.starts_with(IGNORE_PREFIX))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Dotted fields are removed from structural mappings, but not from user
+    /// data or IDs
+    #[test]
+    fn test_strip_ignored_fields() {
+        let mut actual: Value = serde_yaml::from_str(
+            r#"
+.ignore:
+  base: &base
+    Accept: application/json
+profiles:
+  local:
+    .note: For local dev only
+    data:
+      .host: http://localhost
+chains:
+  token:
+    .doc: Loaded from a file
+    source: !file
+      path: token.txt
+requests:
+  .shared: !request
+    method: GET
+    url: "{{host}}"
+  folder1: !folder
+    .note: Fish stuff
+    requests:
+      recipe1: !request
+        .note: Lists fish
+        method: GET
+        url: "{{host}}/fishes"
+        headers:
+          .x-custom: yes
+"#,
+        )
+        .unwrap();
+        strip_ignored_fields(&mut actual);
+
+        let expected: Value = serde_yaml::from_str(
+            r#"
+profiles:
+  local:
+    data:
+      .host: http://localhost
+chains:
+  token:
+    source: !file
+      path: token.txt
+requests:
+  .shared: !request
+    method: GET
+    url: "{{host}}"
+  folder1: !folder
+    requests:
+      recipe1: !request
+        method: GET
+        url: "{{host}}/fishes"
+        headers:
+          .x-custom: yes
+"#,
+        )
+        .unwrap();
+        assert_eq!(actual, expected);
+    }
+}
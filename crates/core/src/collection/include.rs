@@ -0,0 +1,150 @@
+//! Collection composition via the top-level `include` field. Each included
+//! file is a partial collection, whose profiles, variables, chains, and
+//! requests are added to the collection that includes it. Like `extends`, this
+//! is resolved on the raw YAML, *before* deserialization, so a recipe can
+//! extend a recipe from another file.
+
+use crate::util::{parse_yaml, paths::expand_home};
+use anyhow::{anyhow, bail, Context};
+use itertools::Itertools;
+use serde_yaml::{Mapping, Value};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+/// Field on the collection that lists files to include
+const INCLUDE_FIELD: &str = "include";
+/// Top-level fields that can be defined in an included file. Entries are
+/// added to the same field in the including collection
+const MERGED_FIELDS: &[&str] = &["profiles", "variables", "chains", "requests"];
+
+/// Load every file listed in the collection's `include` field and add its
+/// contents to the collection. Included files can include other files, so
+/// includes are resolved recursively. Relative paths are resolved against the
+/// directory of the file that includes them. Cycles are an error, as is
+/// defining the same ID in more than one file.
+pub fn resolve_includes(
+    collection: &mut Value,
+    path: &Path,
+) -> anyhow::Result<()> {
+    // Skip canonicalization if there's nothing to include, so content that
+    // isn't on disk yet can still be parsed
+    if !has_includes(collection) {
+        return Ok(());
+    }
+    let mut stack = vec![path.canonicalize()?];
+    resolve(collection, path, &mut stack)
+}
+
+/// Does the raw collection YAML include any other files?
+pub fn has_includes(collection: &Value) -> bool {
+    collection
+        .as_mapping()
+        .is_some_and(|mapping| mapping.contains_key(INCLUDE_FIELD))
+}
+
+/// Resolve includes for a single file. `stack` holds the canonical paths of
+/// every file currently being included, for cycle detection
+fn resolve(
+    collection: &mut Value,
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    let Value::Mapping(collection) = collection else {
+        return Ok(());
+    };
+    let Some(includes) = collection.remove(INCLUDE_FIELD) else {
+        return Ok(());
+    };
+    let includes: Vec<PathBuf> = serde_yaml::from_value(includes)
+        .map_err(|_| anyhow!("`include` must be a list of file paths"))?;
+
+    let dir = path.parent().unwrap_or(path);
+    for include_path in includes {
+        let include_path = dir.join(expand_home(include_path));
+        include(collection, &include_path, stack).with_context(|| {
+            format!("Error loading included file {include_path:?}")
+        })?;
+    }
+    Ok(())
+}
+
+/// Load a single included file, resolve its own includes, and add its
+/// contents to the including collection
+fn include(
+    collection: &mut Mapping,
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    let canonical = path.canonicalize()?;
+    if let Some(start) = stack.iter().position(|other| *other == canonical) {
+        let cycle = stack[start..]
+            .iter()
+            .chain([&canonical])
+            .map(|path| format!("{path:?}"))
+            .join(" -> ");
+        bail!("Include cycle: {cycle}");
+    }
+
+    let mut included: Value = parse_yaml(&File::open(path)?)?;
+    stack.push(canonical);
+    let result = resolve(&mut included, path, stack);
+    stack.pop();
+    result?;
+    merge(collection, included)
+}
+
+/// Add the contents of an included file to the including collection
+fn merge(collection: &mut Mapping, included: Value) -> anyhow::Result<()> {
+    let included = match included {
+        Value::Mapping(included) => included,
+        // Empty file
+        Value::Null => return Ok(()),
+        _ => bail!("Included file must be a mapping"),
+    };
+
+    for (field, entries) in included {
+        let Some(field) = field.as_str() else {
+            bail!("Invalid field {field:?}");
+        };
+        // Ignored fields (e.g. `.ignore`) stay in their own file
+        if field.starts_with('.') {
+            continue;
+        }
+        if !MERGED_FIELDS.contains(&field) {
+            bail!(
+                "Unknown field `{field}`; included files can only define {}",
+                MERGED_FIELDS
+                    .iter()
+                    .map(|field| format!("`{field}`"))
+                    .join(", ")
+            );
+        }
+        let entries = match entries {
+            Value::Mapping(entries) => entries,
+            Value::Null => continue,
+            _ => bail!("`{field}` must be a mapping"),
+        };
+
+        let target = collection
+            .entry(field.into())
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+        if target.is_null() {
+            *target = Value::Mapping(Mapping::new());
+        }
+        let Value::Mapping(target) = target else {
+            bail!("`{field}` must be a mapping");
+        };
+        for (id, entry) in entries {
+            if target.contains_key(&id) {
+                bail!(
+                    "`{field}.{}` is already defined",
+                    id.as_str().unwrap_or_default()
+                );
+            }
+            target.insert(id, entry);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,589 @@
+//! Merge one collection into another, e.g. to add imported recipes to an
+//! existing collection
+
+use crate::{
+    collection::{
+        Collection, Folder, HasId, ProfileId, RecipeId, RecipeNode,
+        RecipeNodeType, RecipeTree,
+    },
+    template::Identifier,
+};
+use indexmap::{map::Entry, IndexMap};
+use std::{collections::HashSet, fmt::Display, mem};
+
+/// How to handle an ID that's defined in both collections being merged
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum MergeConflict {
+    /// Keep the existing item and discard the incoming one
+    #[default]
+    Skip,
+    /// Keep both, giving the incoming item a new ID with a numeric suffix
+    Rename,
+    /// Replace the existing item with the incoming one
+    Overwrite,
+}
+
+impl Collection {
+    /// Merge another collection into this one. Profiles, chains, variables,
+    /// and recipes from `other` are added to `self`. Folders with the same ID
+    /// are merged recursively. Any other ID defined in both collections is
+    /// handled according to `on_conflict`.
+    ///
+    /// Variables can't be renamed, because nothing would reference the new
+    /// name. With [MergeConflict::Rename], conflicting variables are skipped.
+    pub fn merge(
+        &mut self,
+        other: Self,
+        on_conflict: MergeConflict,
+    ) -> anyhow::Result<()> {
+        // Only one profile can be the default. Ours takes precedence
+        let has_default = self.profiles.values().any(|profile| profile.default);
+        let profiles = other.profiles.into_values().map(|mut profile| {
+            profile.default &= !has_default;
+            profile
+        });
+        merge_map(&mut self.profiles, profiles, on_conflict, ProfileId::from);
+        merge_map(
+            &mut self.chains,
+            other.chains.into_values(),
+            on_conflict,
+            |id| Identifier::escape(&id).into(),
+        );
+
+        for (key, value) in other.variables {
+            match self.variables.entry(key) {
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+                Entry::Occupied(mut entry) => {
+                    if on_conflict == MergeConflict::Overwrite {
+                        entry.insert(value);
+                    }
+                }
+            }
+        }
+
+        let mut ids: HashSet<RecipeId> = self
+            .recipes
+            .iter()
+            .map(|(_, node)| node.id().clone())
+            .collect();
+        let mut tree = mem::take(&mut self.recipes).into_tree();
+        merge_nodes(
+            &mut tree,
+            &mut Vec::new(),
+            other.recipes.into_tree(),
+            &mut ids,
+            on_conflict,
+        );
+        self.recipes = RecipeTree::new(tree)?;
+        Ok(())
+    }
+
+    /// Move all recipes and folders into a new folder at the root of the
+    /// tree. Use this to namespace recipes by their source before merging
+    /// several collections together. Fails if any existing node already has
+    /// the folder's ID.
+    pub fn nest_recipes(
+        &mut self,
+        id: RecipeId,
+        name: Option<String>,
+    ) -> anyhow::Result<()> {
+        let folder = Folder {
+            id: id.clone(),
+            name,
+            children: mem::take(&mut self.recipes).into_tree(),
+        };
+        self.recipes = RecipeTree::new(IndexMap::from([(id, folder.into())]))?;
+        Ok(())
+    }
+}
+
+/// Merge a flat map of profiles or chains
+fn merge_map<T: HasId>(
+    target: &mut IndexMap<T::Id, T>,
+    source: impl IntoIterator<Item = T>,
+    on_conflict: MergeConflict,
+    new_id: impl Fn(String) -> T::Id,
+) where
+    T::Id: Display,
+{
+    for mut item in source {
+        let id = item.id().clone();
+        if !target.contains_key(&id) {
+            target.insert(id, item);
+            continue;
+        }
+        match on_conflict {
+            MergeConflict::Skip => {}
+            MergeConflict::Rename => {
+                let id = unique_id(&id, |id| target.contains_key(id), &new_id);
+                item.set_id(id.clone());
+                target.insert(id, item);
+            }
+            // Inserting an existing key retains its position
+            MergeConflict::Overwrite => {
+                target.insert(id, item);
+            }
+        }
+    }
+}
+
+/// Merge recipe nodes into the folder at `path` within the `root` tree.
+/// Recipe IDs are unique across the entire tree, so `ids` tracks every ID in
+/// the target tree to detect conflicts in other branches.
+fn merge_nodes(
+    root: &mut IndexMap<RecipeId, RecipeNode>,
+    path: &mut Vec<RecipeId>,
+    source: IndexMap<RecipeId, RecipeNode>,
+    ids: &mut HashSet<RecipeId>,
+    on_conflict: MergeConflict,
+) {
+    for (id, mut node) in source {
+        // Folder contents are merged one node at a time, after the folder
+        // itself is in place
+        let children = match &mut node {
+            RecipeNode::Folder(folder) => mem::take(&mut folder.children),
+            RecipeNode::Recipe(_) => IndexMap::new(),
+        };
+        let existing = level_mut(root, path).get(&id).map(RecipeNodeType::from);
+        let is_folder = matches!(node, RecipeNode::Folder(_));
+
+        let id = match existing {
+            // Folders that exist in both are always merged
+            Some(RecipeNodeType::Folder) if is_folder => id,
+            _ if !ids.contains(&id) => {
+                ids.insert(id.clone());
+                level_mut(root, path).insert(id.clone(), node);
+                id
+            }
+            _ => match on_conflict {
+                MergeConflict::Skip => continue,
+                MergeConflict::Rename => {
+                    let id =
+                        unique_id(&id, |id| ids.contains(id), RecipeId::from);
+                    node.set_id(id.clone());
+                    ids.insert(id.clone());
+                    level_mut(root, path).insert(id.clone(), node);
+                    id
+                }
+                MergeConflict::Overwrite => {
+                    let level = level_mut(root, path);
+                    if let Some(existing) = level.get_mut(&id) {
+                        // Replace in place to retain position
+                        forget_ids(&mem::replace(existing, node), ids);
+                    } else {
+                        // The conflicting node is in another branch
+                        if let Some(removed) = remove_node(root, &id) {
+                            forget_ids(&removed, ids);
+                        }
+                        level_mut(root, path).insert(id.clone(), node);
+                    }
+                    ids.insert(id.clone());
+                    id
+                }
+            },
+        };
+
+        if !children.is_empty() {
+            path.push(id);
+            merge_nodes(root, path, children, ids, on_conflict);
+            path.pop();
+        }
+    }
+}
+
+/// Get the children of the folder at `path`. Empty path is the root
+fn level_mut<'a>(
+    root: &'a mut IndexMap<RecipeId, RecipeNode>,
+    path: &[RecipeId],
+) -> &'a mut IndexMap<RecipeId, RecipeNode> {
+    path.iter().fold(root, |nodes, id| match nodes.get_mut(id) {
+        Some(RecipeNode::Folder(folder)) => &mut folder.children,
+        _ => panic!("Merge path {path:?} does not point to a folder"),
+    })
+}
+
+/// Remove a node from anywhere in the tree
+fn remove_node(
+    nodes: &mut IndexMap<RecipeId, RecipeNode>,
+    id: &RecipeId,
+) -> Option<RecipeNode> {
+    if let Some(node) = nodes.shift_remove(id) {
+        return Some(node);
+    }
+    nodes.values_mut().find_map(|node| match node {
+        RecipeNode::Folder(folder) => remove_node(&mut folder.children, id),
+        RecipeNode::Recipe(_) => None,
+    })
+}
+
+/// Remove the IDs of a node and all its descendants from the ID set
+fn forget_ids(node: &RecipeNode, ids: &mut HashSet<RecipeId>) {
+    ids.remove(node.id());
+    if let RecipeNode::Folder(folder) = node {
+        for child in folder.children.values() {
+            forget_ids(child, ids);
+        }
+    }
+}
+
+/// Generate a new ID by appending the lowest numeric suffix that isn't taken
+fn unique_id<Id: Display>(
+    id: &Id,
+    exists: impl Fn(&Id) -> bool,
+    new_id: impl Fn(String) -> Id,
+) -> Id {
+    (2..)
+        .map(|i| new_id(format!("{id}_{i}")))
+        .find(|id| !exists(id))
+        .expect("Ran out of IDs")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_err;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn collection(yaml: &str) -> Collection {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    /// Recipes are merged into the tree, with folders merged recursively
+    #[rstest]
+    #[case::skip(
+        MergeConflict::Skip,
+        r#"
+requests:
+  login: !request
+    method: POST
+    url: /login
+  users: !folder
+    requests:
+      get_user: !request
+        method: GET
+        url: /users/1
+      delete_user: !request
+        method: DELETE
+        url: /users/1
+  health: !request
+    method: GET
+    url: /health
+"#
+    )]
+    #[case::rename(
+        MergeConflict::Rename,
+        r#"
+requests:
+  login: !request
+    method: POST
+    url: /login
+  users: !folder
+    requests:
+      get_user: !request
+        method: GET
+        url: /users/1
+      get_user_2: !request
+        method: GET
+        url: /v2/users/1
+      delete_user: !request
+        method: DELETE
+        url: /users/1
+  login_2: !request
+    method: POST
+    url: /v2/login
+  health: !request
+    method: GET
+    url: /health
+"#
+    )]
+    #[case::overwrite(
+        MergeConflict::Overwrite,
+        r#"
+requests:
+  login: !request
+    method: POST
+    url: /v2/login
+  users: !folder
+    requests:
+      get_user: !request
+        method: GET
+        url: /v2/users/1
+      delete_user: !request
+        method: DELETE
+        url: /users/1
+  health: !request
+    method: GET
+    url: /health
+"#
+    )]
+    fn test_merge_recipes(
+        #[case] on_conflict: MergeConflict,
+        #[case] expected: &str,
+    ) {
+        let mut target = collection(
+            r#"
+requests:
+  login: !request
+    method: POST
+    url: /login
+  users: !folder
+    requests:
+      get_user: !request
+        method: GET
+        url: /users/1
+"#,
+        );
+        let source = collection(
+            r#"
+requests:
+  login: !request
+    method: POST
+    url: /v2/login
+  users: !folder
+    requests:
+      get_user: !request
+        method: GET
+        url: /v2/users/1
+      delete_user: !request
+        method: DELETE
+        url: /users/1
+  health: !request
+    method: GET
+    url: /health
+"#,
+        );
+        target.merge(source, on_conflict).unwrap();
+        assert_eq!(target, collection(expected));
+    }
+
+    /// IDs are unique across the whole tree, so a conflict can be with a
+    /// node in a different folder. Overwriting moves the node to where the
+    /// incoming collection puts it.
+    #[rstest]
+    #[case::skip(
+        MergeConflict::Skip,
+        r#"
+requests:
+  folder: !folder
+    requests:
+      login: !request
+        method: POST
+        url: /login
+"#
+    )]
+    #[case::rename(
+        MergeConflict::Rename,
+        r#"
+requests:
+  folder: !folder
+    requests:
+      login: !request
+        method: POST
+        url: /login
+  login_2: !request
+    method: POST
+    url: /v2/login
+"#
+    )]
+    #[case::overwrite(
+        MergeConflict::Overwrite,
+        r#"
+requests:
+  folder: !folder
+    requests: {}
+  login: !request
+    method: POST
+    url: /v2/login
+"#
+    )]
+    fn test_merge_recipes_other_branch(
+        #[case] on_conflict: MergeConflict,
+        #[case] expected: &str,
+    ) {
+        let mut target = collection(
+            r#"
+requests:
+  folder: !folder
+    requests:
+      login: !request
+        method: POST
+        url: /login
+"#,
+        );
+        let source = collection(
+            r#"
+requests:
+  login: !request
+    method: POST
+    url: /v2/login
+"#,
+        );
+        target.merge(source, on_conflict).unwrap();
+        assert_eq!(target, collection(expected));
+    }
+
+    /// Nesting collections before merging them keeps each one's recipes in a
+    /// separate folder. Recipe IDs are still globally unique, so conflicts
+    /// across folders are still handled by the conflict strategy
+    #[test]
+    fn test_nest_recipes() {
+        let mut target = Collection::default();
+        for (id, url) in [("users", "/users"), ("fish", "/fish")] {
+            let mut source = collection(&format!(
+                r#"
+requests:
+  list: !request
+    method: GET
+    url: {url}
+"#
+            ));
+            source.nest_recipes(id.into(), None).unwrap();
+            target.merge(source, MergeConflict::Rename).unwrap();
+        }
+        assert_eq!(
+            target,
+            collection(
+                r#"
+requests:
+  users: !folder
+    requests:
+      list: !request
+        method: GET
+        url: /users
+  fish: !folder
+    requests:
+      list_2: !request
+        method: GET
+        url: /fish
+"#
+            )
+        );
+
+        // Folder ID can't collide with a node inside it
+        let mut source = collection(
+            r#"
+requests:
+  users: !request
+    method: GET
+    url: /users
+"#,
+        );
+        assert_err!(
+            source.nest_recipes("users".into(), None),
+            "Duplicate recipe/folder ID `users`"
+        );
+    }
+
+    /// Profiles, chains, and variables are merged as flat maps. The existing
+    /// default profile takes precedence over an incoming one.
+    #[rstest]
+    #[case::skip(
+        MergeConflict::Skip,
+        r#"
+variables:
+  host: http://localhost
+  port: "80"
+profiles:
+  dev:
+    default: true
+    data:
+      token: abc
+  prod:
+    data:
+      token: xyz
+chains:
+  username:
+    source: !env
+      variable: USER
+"#
+    )]
+    #[case::rename(
+        MergeConflict::Rename,
+        r#"
+variables:
+  host: http://localhost
+  port: "80"
+profiles:
+  dev:
+    default: true
+    data:
+      token: abc
+  dev_2:
+    data:
+      token: def
+  prod:
+    data:
+      token: xyz
+chains:
+  username:
+    source: !env
+      variable: USER
+  username_2:
+    source: !env
+      variable: USERNAME
+"#
+    )]
+    #[case::overwrite(
+        MergeConflict::Overwrite,
+        r#"
+variables:
+  host: https://example.com
+  port: "80"
+profiles:
+  dev:
+    data:
+      token: def
+  prod:
+    data:
+      token: xyz
+chains:
+  username:
+    source: !env
+      variable: USERNAME
+"#
+    )]
+    fn test_merge_flat(
+        #[case] on_conflict: MergeConflict,
+        #[case] expected: &str,
+    ) {
+        let mut target = collection(
+            r#"
+variables:
+  host: http://localhost
+profiles:
+  dev:
+    default: true
+    data:
+      token: abc
+chains:
+  username:
+    source: !env
+      variable: USER
+"#,
+        );
+        let source = collection(
+            r#"
+variables:
+  host: https://example.com
+  port: "80"
+profiles:
+  dev:
+    data:
+      token: def
+  prod:
+    default: true
+    data:
+      token: xyz
+chains:
+  username:
+    source: !env
+      variable: USERNAME
+"#,
+        );
+        target.merge(source, on_conflict).unwrap();
+        assert_eq!(target, collection(expected));
+    }
+}
@@ -3,20 +3,39 @@
 use crate::{
     collection::{
         cereal,
+        extends::resolve_extends,
+        ignore::strip_ignored_fields,
+        include::resolve_includes,
         recipe_tree::{RecipeNode, RecipeTree},
     },
-    http::{content_type::ContentType, query::Query, HttpMethod},
+    http::{
+        content_type::ContentType, query::Query, xml, HttpMethod,
+        HttpVersionPolicy,
+    },
     template::{Identifier, Template},
-    util::{parse_yaml, ResultTraced},
+    util::{parse_yaml, paths::expand_home, ResultTraced},
 };
-use anyhow::Context;
+use anyhow::{bail, Context};
 use derive_more::{Deref, Display, From, FromStr};
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools;
 use mime::Mime;
 use reqwest::header;
 use serde::{Deserialize, Serialize};
-use std::{fs::File, path::PathBuf, time::Duration};
-use tracing::info;
+use std::{
+    collections::{HashSet, VecDeque},
+    env,
+    fs::File,
+    io::Read,
+    mem,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tracing::{info, warn};
+
+/// Environment variable that selects a profile when none is given explicitly.
+/// See [Collection::resolve_profile]
+pub const PROFILE_ENV_VAR: &str = "SLUMBER_PROFILE";
 
 /// A collection of profiles, requests, etc. This is the primary Slumber unit
 /// of configuration.
@@ -29,18 +48,16 @@ use tracing::info;
 pub struct Collection {
     #[serde(default, deserialize_with = "cereal::deserialize_profiles")]
     pub profiles: IndexMap<ProfileId, Profile>,
+    /// Values available to all recipes, regardless of the selected profile.
+    /// Profile values take precedence over these
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub variables: IndexMap<String, ProfileValue>,
     #[serde(default, deserialize_with = "cereal::deserialize_id_map")]
     pub chains: IndexMap<ChainId, Chain>,
     /// Internally we call these recipes, but to a user `requests` is more
     /// intuitive
     #[serde(default, rename = "requests")]
     pub recipes: RecipeTree,
-    /// A hack-ish to allow users to add arbitrary data to their collection
-    /// file without triggering a unknown field error. Ideally we could
-    /// ignore anything that starts with `.` (recursively) but that
-    /// requires a custom serde impl for each type, or changes to the macro
-    #[serde(default, skip_serializing, rename = ".ignore")]
-    pub _ignore: serde::de::IgnoredAny,
 }
 
 impl Collection {
@@ -49,8 +66,15 @@ impl Collection {
         info!(?path, "Loading collection file");
 
         let load = || {
-            let file = File::open(path)?;
-            let collection = parse_yaml(&file)?;
+            let mut collection = Self::parse(path)?;
+            // Dotenv paths are relative to the collection file
+            let dir = path.parent().unwrap_or(path);
+            for profile in collection.profiles.values_mut() {
+                profile.load_dotenv(dir)?;
+            }
+            for problem in collection.validate() {
+                warn!(?path, "{problem}");
+            }
             Ok::<_, anyhow::Error>(collection)
         };
 
@@ -58,6 +82,34 @@ impl Collection {
             .context(format!("Error loading collection from {path:?}"))
             .traced()
     }
+
+    /// Load a collection file for the purpose of modifying and writing it
+    /// back out. Unlike [Self::load], dotenv files are *not* loaded, so their
+    /// values don't leak into the output. Recipe inheritance is still
+    /// resolved though, because child recipes can't be deserialized without
+    /// it.
+    pub fn load_source(path: &PathBuf) -> anyhow::Result<Self> {
+        Self::parse(path)
+            .context(format!("Error loading collection from {path:?}"))
+            .traced()
+    }
+
+    fn parse(path: &PathBuf) -> anyhow::Result<Self> {
+        Self::parse_reader(File::open(path)?, path)
+    }
+
+    /// Parse collection content from a reader. `path` is the file that the
+    /// content belongs to, which is used to resolve includes
+    pub(super) fn parse_reader(
+        reader: impl Read,
+        path: &Path,
+    ) -> anyhow::Result<Self> {
+        let mut yaml: serde_yaml::Value = parse_yaml(reader)?;
+        resolve_includes(&mut yaml, path)?;
+        strip_ignored_fields(&mut yaml);
+        resolve_extends(&mut yaml)?;
+        Ok(serde_path_to_error::deserialize(yaml)?)
+    }
 }
 
 /// Mutually exclusive hot-swappable config group
@@ -74,7 +126,61 @@ pub struct Profile {
     /// custom deserializer function.
     #[serde(default)]
     pub default: bool,
-    pub data: IndexMap<String, Template>,
+    /// Path to a `.env` file to load additional data from. Relative paths are
+    /// resolved relative to the collection file. Values defined in `data` take
+    /// precedence over values from the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_dotenv: Option<PathBuf>,
+    /// Color to tint this profile with in the TUI, e.g. `red` or `#ff0000`.
+    /// Accepts the same formats as theme colors. Parsed by the TUI rather
+    /// than here, because the core doesn't know about terminal colors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Short label shown next to the profile name in the TUI, e.g. `PROD`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Chains that apply only when this profile is selected. A chain here
+    /// takes precedence over a collection-level chain with the same ID, so a
+    /// profile can redefine where a value comes from
+    #[serde(
+        default,
+        deserialize_with = "cereal::deserialize_id_map",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub chains: IndexMap<ChainId, Chain>,
+    pub data: IndexMap<String, ProfileValue>,
+}
+
+/// A single value in a profile's data. Strings are parsed as templates, which
+/// preserves the original (string-only) behavior. Any other YAML value
+/// (number, boolean, null, sequence, mapping) is stored as a typed value, and
+/// rendered as JSON when referenced from a template. This makes it possible
+/// to inject structured data, e.g. an array, into a JSON body.
+///
+/// Serialization/deserialization is implemented manually, in the `cereal`
+/// module
+#[derive(Clone, Debug, From, PartialEq)]
+pub enum ProfileValue {
+    Template(Template),
+    Value(serde_json::Value),
+}
+
+impl ProfileValue {
+    /// Get a template that will render to this value. For typed values, this
+    /// is a raw template of the JSON representation. Useful for previews.
+    pub fn to_template(&self) -> Template {
+        match self {
+            Self::Template(template) => template.clone(),
+            Self::Value(value) => Template::raw(value.to_string()),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test"))]
+impl From<&str> for ProfileValue {
+    fn from(value: &str) -> Self {
+        Self::Template(value.into())
+    }
 }
 
 impl Profile {
@@ -86,6 +192,35 @@ impl Profile {
     pub fn default(&self) -> bool {
         self.default
     }
+
+    /// Load values from this profile's `.env` file (if any) into its data.
+    /// Values from the file are treated as raw strings, *not* templates.
+    /// Existing values in the profile override those from the file.
+    fn load_dotenv(&mut self, dir: &Path) -> anyhow::Result<()> {
+        let Some(dotenv_path) = &self.from_dotenv else {
+            return Ok(());
+        };
+        let path = dir.join(expand_home(dotenv_path));
+        let error_context = || {
+            format!(
+                "Error loading dotenv file {path:?} for profile `{}`",
+                self.id
+            )
+        };
+
+        let mut data = IndexMap::new();
+        for item in
+            dotenvy::from_path_iter(&path).with_context(error_context)?
+        {
+            let (key, value) = item.with_context(error_context)?;
+            data.insert(key, ProfileValue::Template(Template::raw(value)));
+        }
+        // Explicit values take precedence. Dotenv values retain their position
+        // if overridden
+        data.extend(mem::take(&mut self.data));
+        self.data = data;
+        Ok(())
+    }
 }
 
 #[cfg(any(test, feature = "test"))]
@@ -95,6 +230,10 @@ impl crate::test_util::Factory for Profile {
             id: ProfileId::factory(()),
             name: None,
             default: false,
+            from_dotenv: None,
+            color: None,
+            label: None,
+            chains: IndexMap::new(),
             data: IndexMap::new(),
         }
     }
@@ -117,6 +256,17 @@ impl crate::test_util::Factory for Profile {
 #[serde(transparent)]
 pub struct ProfileId(String);
 
+impl ProfileId {
+    /// Get the profile ID requested via `$SLUMBER_PROFILE`, if any. An empty
+    /// value is treated as unset.
+    pub fn from_env() -> Option<Self> {
+        env::var(PROFILE_ENV_VAR)
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(Self::from)
+    }
+}
+
 #[cfg(any(test, feature = "test"))]
 impl From<&str> for ProfileId {
     fn from(value: &str) -> Self {
@@ -186,8 +336,71 @@ pub struct Recipe {
     pub authentication: Option<Authentication>,
     #[serde(default, with = "cereal::serde_query_parameters")]
     pub query: Vec<(String, Template)>,
-    #[serde(default, deserialize_with = "cereal::deserialize_headers")]
-    pub headers: IndexMap<String, Template>,
+    /// Header names can be repeated, to send multiple values for a header
+    #[serde(default, with = "cereal::serde_headers")]
+    pub headers: Vec<(String, Template)>,
+    /// Shorthand for the `Accept` header. An explicit `Accept` header takes
+    /// precedence over this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accept: Option<AcceptPreset>,
+    /// Should exchanges for this recipe be saved in history? If disabled,
+    /// responses are only held in memory for the current session
+    #[serde(default = "persist_default", skip_serializing_if = "is_true")]
+    pub persist: bool,
+    /// Reuse the most recent response for this recipe, instead of sending a
+    /// new request, if it was received within this long ago and the rendered
+    /// request is identical. Responses are read from history, so this has no
+    /// effect if `persist` is disabled
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "cereal::serde_duration::option"
+    )]
+    pub cache: Option<Duration>,
+    /// Ask the user for confirmation before sending a request from the TUI.
+    /// Useful for destructive requests, e.g. `DELETE`
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub confirm: bool,
+    /// Name of a queue to run this recipe's requests through. Requests for
+    /// all recipes in the same queue are sent one at a time, in the order they
+    /// were submitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue: Option<String>,
+    /// HTTP version(s) to use for this recipe's requests. Overrides the
+    /// global `http_version` config field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_version: Option<HttpVersionPolicy>,
+    /// Send a request automatically when this recipe is selected in the TUI.
+    /// Overrides the global `auto_send` config field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_send: Option<bool>,
+    /// Environment variables to set for any commands run while building this
+    /// recipe, e.g. command chains and command bodies. These are passed to
+    /// each subprocess; the Slumber process environment is not modified.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub env: IndexMap<String, Template>,
+    /// Profile to build this recipe with when none is given via `--profile`
+    /// or `$SLUMBER_PROFILE`. Only applies to the CLI; the TUI always uses
+    /// the profile selected in the profile list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<ProfileId>,
+    /// [Rhai](https://rhai.rs) script to run against the response after the
+    /// request is sent from the CLI. Can print, extract values, and set the
+    /// exit code of the process
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+}
+
+fn persist_default() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 impl Recipe {
@@ -196,6 +409,14 @@ impl Recipe {
         self.name.as_deref().unwrap_or(&self.id)
     }
 
+    /// Should a request be sent automatically when this recipe is selected?
+    /// `default` is the global `auto_send` config value, which only applies to
+    /// safe methods. Recipes that require confirmation are never auto-sent.
+    pub fn auto_send(&self, default: bool) -> bool {
+        !self.confirm
+            && self.auto_send.unwrap_or(default && self.method.is_safe())
+    }
+
     /// Guess the value that the `Content-Type` header will have for a generated
     /// request. This will use the raw header if it's present and a valid MIME
     /// type, otherwise it will fall back to the content type of the body, if
@@ -204,10 +425,58 @@ impl Recipe {
     /// will fail.
     pub fn mime(&self) -> Option<Mime> {
         self.headers
-            .get(header::CONTENT_TYPE.as_str())
-            .and_then(|template| template.display().parse::<Mime>().ok())
+            .iter()
+            .find(|(header, _)| header == header::CONTENT_TYPE.as_str())
+            .and_then(|(_, template)| template.display().parse::<Mime>().ok())
             .or_else(|| self.body.as_ref()?.mime())
     }
+
+    /// Get every template in this recipe, each labelled with a path to the
+    /// field it came from, e.g. `headers.Content-Type`. Useful for checking
+    /// all templates at once, rather than stopping at the first error.
+    pub fn templates(&self) -> Vec<(String, &Template)> {
+        let mut templates = vec![("url".to_owned(), &self.url)];
+        templates.extend(
+            self.query
+                .iter()
+                .map(|(param, template)| (format!("query.{param}"), template)),
+        );
+        templates.extend(
+            self.headers.iter().map(|(header, template)| {
+                (format!("headers.{header}"), template)
+            }),
+        );
+        match &self.authentication {
+            Some(Authentication::Basic { username, password }) => {
+                templates
+                    .push(("authentication.username".to_owned(), username));
+                if let Some(password) = password {
+                    templates
+                        .push(("authentication.password".to_owned(), password));
+                }
+            }
+            Some(Authentication::Bearer(token)) => {
+                templates.push(("authentication.token".to_owned(), token));
+            }
+            Some(Authentication::Helper { command }) => {
+                templates.extend(command.iter().enumerate().map(
+                    |(i, template)| {
+                        (format!("authentication.command[{i}]"), template)
+                    },
+                ));
+            }
+            None => {}
+        }
+        if let Some(body) = &self.body {
+            templates.extend(body.templates("body"));
+        }
+        templates.extend(
+            self.env.iter().map(|(variable, template)| {
+                (format!("env.{variable}"), template)
+            }),
+        );
+        templates
+    }
 }
 
 #[cfg(any(test, feature = "test"))]
@@ -221,7 +490,17 @@ impl crate::test_util::Factory for Recipe {
             body: None,
             authentication: None,
             query: Vec::new(),
-            headers: IndexMap::new(),
+            headers: Vec::new(),
+            persist: true,
+            cache: None,
+            confirm: false,
+            queue: None,
+            http_version: None,
+            accept: None,
+            auto_send: None,
+            env: IndexMap::new(),
+            profile: None,
+            script: None,
         }
     }
 }
@@ -283,6 +562,7 @@ impl crate::test_util::Factory for Chain {
             selector_mode: SelectorMode::default(),
             content_type: None,
             trim: ChainOutputTrim::default(),
+            decode: Vec::new(),
         }
     }
 }
@@ -290,17 +570,48 @@ impl crate::test_util::Factory for Chain {
 /// Shortcut for defining authentication method. If this is defined in addition
 /// to the `Authorization` header, that header will end up being included in the
 /// request twice.
-///
-/// Type parameter allows this to be re-used for post-render purposes (with
-/// `T=String`).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
 #[serde(rename_all = "snake_case", deny_unknown_fields)]
-pub enum Authentication<T = Template> {
+pub enum Authentication {
     /// `Authorization: Basic {username:password | base64}`
-    Basic { username: T, password: Option<T> },
+    Basic {
+        username: Template,
+        password: Option<Template>,
+    },
     /// `Authorization: Bearer {token}`
-    Bearer(T),
+    Bearer(Template),
+    /// Run an external program to get a credential, similar to git's
+    /// credential helpers. The program prints either a raw token, which is
+    /// sent as `Authorization: Bearer {token}`, or a JSON object with
+    /// `header`, `value`, and an optional `ttl` in seconds. Each element of
+    /// the command is a template.
+    Helper { command: Vec<Template> },
+}
+
+/// Shortcut for common `Accept` header values, so users don't have to type out
+/// MIME types
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[serde(rename_all = "snake_case")]
+pub enum AcceptPreset {
+    /// `application/json`
+    Json,
+    /// `application/xml`
+    Xml,
+    /// `*/*`
+    Any,
+}
+
+impl AcceptPreset {
+    /// Get the `Accept` header value for this preset
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Xml => "application/xml",
+            Self::Any => "*/*",
+        }
+    }
 }
 
 /// Template for a request body. `Raw` is the "default" variant, which
@@ -309,31 +620,49 @@ pub enum Authentication<T = Template> {
 /// HTTP engine uses the variant to determine not only how to serialize the
 /// body, but also other parameters of the request (e.g. the `Content-Type`
 /// header).
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
 pub enum RecipeBody {
     /// Plain string/bytes body
     Raw {
         body: Template,
-        /// For structured body types such as `!json`, we'll stringify during
-        /// deserialization then just store the content type. This makes
-        /// internal logic much simpler because we can just work with templates
+        /// Content type of the body, if known. Used for the `Content-Type`
+        /// header and syntax highlighting
         content_type: Option<ContentType>,
     },
+    /// Structured JSON body, where each string is a template
+    Json(JsonTemplate),
+    /// XML document (e.g. a SOAP envelope), as a single template. The rendered
+    /// body must be well-formed XML
+    Xml(Template),
     /// `application/x-www-form-urlencoded` fields. Values must be strings
     FormUrlencoded(IndexMap<String, Template>),
     /// `multipart/form-data` fields. Values can be binary
     FormMultipart(IndexMap<String, Template>),
+    /// Run an external program and use its stdout as the body. Useful when
+    /// the body has to be generated by a separate tool, e.g. for protobuf
+    /// encoding
+    Command {
+        /// Program and its arguments. Each element is a template
+        command: Vec<Template>,
+        /// Value for the `Content-Type` header. Not set if omitted
+        content_type: Option<Mime>,
+    },
+    /// Multiple representations of the same body, e.g. JSON and form. One is
+    /// selected at build time. Variants cannot be nested.
+    Variants {
+        /// Rendered to get the name of the variant to use. If omitted, the
+        /// first variant is used. Can be overridden via `BuildOptions`
+        select: Option<Template>,
+        variants: IndexMap<String, RecipeBody>,
+    },
 }
 
 impl RecipeBody {
     /// Build a JSON body *without* parsing the internal strings as templates.
     /// Useful for importing from external formats.
     pub fn untemplated_json(value: serde_json::Value) -> Self {
-        Self::Raw {
-            body: Template::raw(format!("{value:#}")),
-            content_type: Some(ContentType::Json),
-        }
+        Self::Json(JsonTemplate::raw(value))
     }
 
     /// Get the anticipated MIME type that will appear in the `Content-Type`
@@ -345,10 +674,123 @@ impl RecipeBody {
             RecipeBody::Raw { content_type, .. } => {
                 content_type.as_ref().map(ContentType::to_mime)
             }
+            RecipeBody::Json(_) => Some(mime::APPLICATION_JSON),
+            RecipeBody::Xml(_) => Some(xml::mime()),
             RecipeBody::FormUrlencoded(_) => {
                 Some(mime::APPLICATION_WWW_FORM_URLENCODED)
             }
             RecipeBody::FormMultipart(_) => Some(mime::MULTIPART_FORM_DATA),
+            RecipeBody::Command { content_type, .. } => content_type.clone(),
+            // The selected variant isn't known until the request is built
+            RecipeBody::Variants { .. } => None,
+        }
+    }
+
+    /// Get every template in this body, labelled by field path. See
+    /// [Recipe::templates]
+    fn templates<'a>(&'a self, prefix: &str) -> Vec<(String, &'a Template)> {
+        match self {
+            RecipeBody::Raw { body, .. } | RecipeBody::Xml(body) => {
+                vec![(prefix.to_owned(), body)]
+            }
+            RecipeBody::Json(json) => json.templates(prefix),
+            RecipeBody::FormUrlencoded(fields)
+            | RecipeBody::FormMultipart(fields) => fields
+                .iter()
+                .map(|(field, template)| {
+                    (format!("{prefix}.{field}"), template)
+                })
+                .collect(),
+            RecipeBody::Command { command, .. } => command
+                .iter()
+                .enumerate()
+                .map(|(i, template)| {
+                    (format!("{prefix}.command[{i}]"), template)
+                })
+                .collect(),
+            RecipeBody::Variants { select, variants } => select
+                .iter()
+                .map(|select| (format!("{prefix}.select"), select))
+                .chain(variants.iter().flat_map(|(name, variant)| {
+                    variant.templates(&format!("{prefix}.variants.{name}"))
+                }))
+                .collect(),
+        }
+    }
+}
+
+/// A JSON value where every string is a template. This is the structured form
+/// of a `!json` body. Each string is rendered on its own and the result is
+/// serialized as JSON, so the output is always valid JSON: rendered values are
+/// escaped as needed, and non-string values (e.g. numbers) keep their type.
+#[derive(Clone, Debug)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+pub enum JsonTemplate {
+    Null,
+    Bool(bool),
+    Number(serde_json::Number),
+    String(Template),
+    Array(Vec<JsonTemplate>),
+    /// Object keys are *not* templates
+    Object(IndexMap<String, JsonTemplate>),
+}
+
+impl JsonTemplate {
+    /// Build from a JSON value *without* parsing the strings as templates
+    pub fn raw(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::Bool(b) => Self::Bool(b),
+            serde_json::Value::Number(n) => Self::Number(n),
+            serde_json::Value::String(s) => Self::String(Template::raw(s)),
+            serde_json::Value::Array(values) => {
+                Self::Array(values.into_iter().map(Self::raw).collect())
+            }
+            serde_json::Value::Object(map) => Self::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, Self::raw(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Convert to a JSON value, with each template shown as its source text.
+    /// This is how the body is displayed before rendering
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Null => serde_json::Value::Null,
+            Self::Bool(b) => (*b).into(),
+            Self::Number(n) => n.clone().into(),
+            Self::String(template) => template.display().into(),
+            Self::Array(values) => {
+                values.iter().map(Self::to_json).collect::<Vec<_>>().into()
+            }
+            Self::Object(map) => serde_json::Value::Object(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), value.to_json()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Get every template in the value, labelled by its path
+    fn templates<'a>(&'a self, prefix: &str) -> Vec<(String, &'a Template)> {
+        match self {
+            Self::Null | Self::Bool(_) | Self::Number(_) => vec![],
+            Self::String(template) => vec![(prefix.to_owned(), template)],
+            Self::Array(values) => values
+                .iter()
+                .enumerate()
+                .flat_map(|(i, value)| {
+                    value.templates(&format!("{prefix}[{i}]"))
+                })
+                .collect(),
+            Self::Object(map) => map
+                .iter()
+                .flat_map(|(key, value)| {
+                    value.templates(&format!("{prefix}.{key}"))
+                })
+                .collect(),
         }
     }
 }
@@ -391,6 +833,9 @@ pub struct Chain {
     pub content_type: Option<ContentType>,
     #[serde(default)]
     pub trim: ChainOutputTrim,
+    /// Transformations to apply to the value, in order, before the selector
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decode: Vec<DecodeStep>,
 }
 
 /// Unique ID for a chain, provided by the user
@@ -432,7 +877,12 @@ pub enum ChainSource {
     #[serde(rename = "env")]
     Environment { variable: Template },
     /// Load data from a file
-    File { path: Template },
+    File {
+        path: Template,
+        /// Directory that a relative path is resolved against
+        #[serde(default)]
+        relative_to: PathRoot,
+    },
     /// Prompt the user for a value
     Prompt {
         /// Descriptor to show to the user
@@ -458,6 +908,34 @@ pub enum ChainSource {
     },
 }
 
+impl ChainSource {
+    /// Get every template in this source. Useful for finding chains that
+    /// depend on other chains.
+    pub fn templates(&self) -> Vec<&Template> {
+        match self {
+            Self::Command { command, stdin } => {
+                command.iter().chain(stdin).collect()
+            }
+            Self::Environment { variable } => vec![variable],
+            Self::File { path, .. } => vec![path],
+            Self::Prompt { message, default } => {
+                message.iter().chain(default).collect()
+            }
+            Self::Request { section, .. } => match section {
+                ChainRequestSection::Body => vec![],
+                ChainRequestSection::Header(header) => vec![header],
+            },
+            Self::Select { message, options } => {
+                let options: Vec<&Template> = match options {
+                    SelectOptions::Fixed(options) => options.iter().collect(),
+                    SelectOptions::Dynamic(options) => vec![options],
+                };
+                message.iter().chain(options).collect()
+            }
+        }
+    }
+}
+
 /// Static or dynamic list of options for a select chain
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
@@ -532,6 +1010,19 @@ pub enum SelectorMode {
     Array,
 }
 
+/// Directory that a relative file path is resolved against
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum PathRoot {
+    /// The directory containing the collection file, so paths work regardless
+    /// of where Slumber is run from
+    #[default]
+    Collection,
+    /// The current working directory
+    Cwd,
+}
+
 /// Trim whitespace from rendered output
 #[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
@@ -548,12 +1039,281 @@ pub enum ChainOutputTrim {
     Both,
 }
 
+/// A single transformation in a chain's `decode` pipeline. Steps are applied
+/// in order to the raw chain value, before the selector.
+#[derive(Copy, Clone, Debug, Display, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodeStep {
+    /// Decode standard base64 (with padding)
+    #[display("base64")]
+    Base64,
+    /// Parse the value as JSON. If it's a string, the string's contents are
+    /// output, which undoes one layer of JSON encoding. Any other JSON value
+    /// is output as-is.
+    #[display("json")]
+    Json,
+    /// Decompress gzip data
+    #[display("gunzip")]
+    Gunzip,
+}
+
 impl Collection {
     /// Get the profile marked as `default: true`, if any. At most one profile
     /// can be marked as default.
     pub fn default_profile(&self) -> Option<&Profile> {
         self.profiles.values().find(|profile| profile.default)
     }
+
+    /// Determine which profile should be used when the user hasn't picked one
+    /// interactively. This is shared between the CLI and TUI so the two agree
+    /// on which profile (and therefore which credentials) gets used.
+    /// Precedence is:
+    /// - `explicit`, e.g. from the `--profile` flag
+    /// - Value of `$SLUMBER_PROFILE`, if set and non-empty
+    /// - The `profile` field of the recipe being built, if any
+    /// - See [Self::fallback_profile]
+    ///
+    /// Return an error if a requested profile (via argument, env var, or
+    /// recipe) doesn't exist, so we never silently fall back to a different
+    /// set of credentials.
+    pub fn resolve_profile(
+        &self,
+        explicit: Option<ProfileId>,
+        recipe_id: Option<&RecipeId>,
+        config_default: Option<&ProfileId>,
+    ) -> anyhow::Result<Option<ProfileId>> {
+        let requested = explicit.or_else(ProfileId::from_env).or_else(|| {
+            recipe_id
+                .and_then(|recipe_id| self.recipes.get_recipe(recipe_id))
+                .and_then(|recipe| recipe.profile.clone())
+        });
+        match requested {
+            Some(profile_id) => {
+                if !self.profiles.contains_key(&profile_id) {
+                    bail!(
+                        "No profile with ID `{profile_id}`; options are: {}",
+                        self.profiles.keys().format(", ")
+                    );
+                }
+                Ok(Some(profile_id))
+            }
+            None => Ok(self.fallback_profile(config_default).cloned()),
+        }
+    }
+
+    /// Get the profile to use when nothing more specific was requested:
+    /// - `config_default`, i.e. the `default_profile` config field, if this
+    ///   collection has a profile with that ID. The config is shared between
+    ///   all collections, so a missing profile is skipped rather than an error
+    /// - The profile marked `default: true` in the collection
+    pub fn fallback_profile(
+        &self,
+        config_default: Option<&ProfileId>,
+    ) -> Option<&ProfileId> {
+        config_default
+            .and_then(|profile_id| self.profiles.get(profile_id))
+            .or_else(|| self.default_profile())
+            .map(|profile| &profile.id)
+    }
+
+    /// Get a chain by ID. If a profile is given and it defines a chain with
+    /// this ID, that takes precedence over the collection-level chain.
+    pub fn get_chain(
+        &self,
+        chain_id: &ChainId,
+        profile_id: Option<&ProfileId>,
+    ) -> Option<&Chain> {
+        profile_id
+            .and_then(|profile_id| self.profiles.get(profile_id))
+            .and_then(|profile| profile.chains.get(chain_id))
+            .or_else(|| self.chains.get(chain_id))
+    }
+
+    /// Check the collection for problems that would otherwise only appear when
+    /// a template is rendered, and return a message for each one. Currently
+    /// this checks that every request chain references an existing recipe,
+    /// and that every recipe `profile` references an existing profile.
+    /// Chain selectors don't need to be checked here, because they're parsed
+    /// during deserialization.
+    ///
+    /// Problems are returned rather than treated as a load error, so that a
+    /// single stale chain doesn't make the rest of the collection unusable.
+    pub fn validate(&self) -> Vec<String> {
+        // Include profile-scoped chains, labelled with their profile
+        let profile_chains = self.profiles.values().flat_map(|profile| {
+            profile.chains.values().map(move |chain| {
+                (format!("`{}` in profile `{}`", chain.id, profile.id), chain)
+            })
+        });
+        let mut problems: Vec<String> = self
+            .chains
+            .values()
+            .map(|chain| (format!("`{}`", chain.id), chain))
+            .chain(profile_chains)
+            .filter_map(|(label, chain)| {
+                let ChainSource::Request { recipe, .. } = &chain.source else {
+                    return None;
+                };
+                match self.recipes.get(recipe) {
+                    Some(RecipeNode::Recipe(_)) => None,
+                    Some(RecipeNode::Folder(_)) => Some(format!(
+                        "Chain {label} references folder `{recipe}`; request \
+                         chains must reference a recipe",
+                    )),
+                    None => Some(format!(
+                        "Chain {label} references unknown recipe `{recipe}`",
+                    )),
+                }
+            })
+            .collect();
+
+        problems.extend(
+            self.recipes
+                .iter()
+                .filter_map(|(_, node)| node.recipe())
+                .filter_map(|recipe| {
+                    let profile_id = recipe.profile.as_ref()?;
+                    (!self.profiles.contains_key(profile_id)).then(|| {
+                        format!(
+                            "Recipe `{}` references unknown profile \
+                             `{profile_id}`",
+                            recipe.id
+                        )
+                    })
+                }),
+        );
+        problems
+    }
+
+    /// Find things in the collection that are defined but have no effect:
+    /// chains that are never referenced, and profiles with no data to
+    /// distinguish them from other profiles. These aren't errors, so unlike
+    /// [Self::validate], they're only checked on request (`slumber
+    /// collections lint`).
+    pub fn lint(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        // Every template in the collection, with the ID of the chain it
+        // belongs to (if any), so a chain referencing itself doesn't count
+        let recipe_templates = self
+            .recipes
+            .iter()
+            .filter_map(|(_, node)| node.recipe())
+            .flat_map(|recipe| recipe.templates())
+            .map(|(_, template)| (None, template));
+        let value_templates = self
+            .profiles
+            .values()
+            .flat_map(|profile| profile.data.values())
+            .chain(self.variables.values())
+            .filter_map(|value| match value {
+                ProfileValue::Template(template) => Some((None, template)),
+                ProfileValue::Value(_) => None,
+            });
+        let all_chains = self.chains.values().chain(
+            self.profiles
+                .values()
+                .flat_map(|profile| profile.chains.values()),
+        );
+        let chain_templates = all_chains.flat_map(|chain| {
+            chain
+                .source
+                .templates()
+                .into_iter()
+                .map(|template| (Some(&chain.id), template))
+        });
+        let referenced: HashSet<&ChainId> = recipe_templates
+            .chain(value_templates)
+            .chain(chain_templates)
+            .flat_map(|(owner, template)| {
+                template
+                    .chain_ids()
+                    .into_iter()
+                    .filter(move |id| Some(*id) != owner)
+            })
+            .collect();
+
+        for chain in self.chains.values() {
+            if !referenced.contains(&chain.id) {
+                problems.push(format!("Chain `{}` is never used", chain.id));
+            }
+        }
+        for profile in self.profiles.values() {
+            for chain in profile.chains.values() {
+                if !referenced.contains(&chain.id) {
+                    problems.push(format!(
+                        "Chain `{}` in profile `{}` is never used",
+                        chain.id, profile.id
+                    ));
+                }
+            }
+        }
+
+        // Profile-scoped chains can't be compared, so any profile with its
+        // own chains is considered distinct
+        for (i, profile) in self.profiles.values().enumerate() {
+            if !profile.chains.is_empty() {
+                continue;
+            }
+            if profile.data.is_empty() {
+                problems.push(format!("Profile `{}` has no data", profile.id));
+            } else if let Some(other) =
+                self.profiles.values().take(i).find(|other| {
+                    other.chains.is_empty() && other.data == profile.data
+                })
+            {
+                problems.push(format!(
+                    "Profile `{}` has the same data as profile `{}`",
+                    profile.id, other.id
+                ));
+            }
+        }
+
+        problems
+    }
+
+    /// Build a graph of the dependencies between recipes. A recipe depends on
+    /// another if it references a request chain for that recipe, either
+    /// directly or through another chain. Every recipe in the collection is
+    /// included as a key, in collection order, even if it has no
+    /// dependencies. Chains that don't exist are ignored. Only collection-level
+    /// chains are considered, because the graph isn't specific to a profile.
+    pub fn dependency_graph(&self) -> IndexMap<&RecipeId, IndexSet<&RecipeId>> {
+        self.recipes
+            .recipe_ids()
+            .map(|recipe_id| {
+                // Unwrap is safe because we just got the ID from the tree
+                let recipe = self.recipes.get_recipe(recipe_id).unwrap();
+                let mut dependencies = IndexSet::new();
+                // Track visited chains to dedupe, and so cycles don't blow up
+                let mut visited = IndexSet::new();
+                let mut queue: VecDeque<&ChainId> = recipe
+                    .templates()
+                    .into_iter()
+                    .flat_map(|(_, template)| template.chain_ids())
+                    .collect();
+                while let Some(chain_id) = queue.pop_front() {
+                    if !visited.insert(chain_id) {
+                        continue;
+                    }
+                    let Some(chain) = self.chains.get(chain_id) else {
+                        continue;
+                    };
+                    if let ChainSource::Request { recipe, .. } = &chain.source {
+                        dependencies.insert(recipe);
+                    }
+                    queue.extend(
+                        chain
+                            .source
+                            .templates()
+                            .into_iter()
+                            .flat_map(Template::chain_ids),
+                    );
+                }
+                (recipe_id, dependencies)
+            })
+            .collect()
+    }
 }
 
 /// Test-only helpers
@@ -600,7 +1360,10 @@ impl crate::test_util::Factory for Collection {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_util::Factory;
+    use crate::{
+        assert_err,
+        test_util::{by_id, Factory},
+    };
     use indexmap::indexmap;
     use rstest::rstest;
 
@@ -655,10 +1418,10 @@ mod tests {
         #[case] body: Option<RecipeBody>,
         #[case] expected: Option<&str>,
     ) {
-        let mut headers = IndexMap::new();
-        if let Some(header) = header {
-            headers.insert("content-type".into(), header.into());
-        }
+        let headers = header
+            .map(|header| ("content-type".into(), header.into()))
+            .into_iter()
+            .collect();
         let recipe = Recipe {
             headers,
             body,
@@ -667,4 +1430,387 @@ mod tests {
         let expected = expected.and_then(|value| value.parse::<Mime>().ok());
         assert_eq!(recipe.mime(), expected);
     }
+
+    /// Test profile resolution precedence: explicit > env var > recipe >
+    /// config default > collection default
+    #[rstest]
+    #[case::explicit(Some("p1"), Some("p2"), Some("r_p2"), Some("p2"), "p1")]
+    #[case::env(None, Some("p2"), Some("r_p1"), Some("p1"), "p2")]
+    #[case::recipe(None, None, Some("r_p1"), Some("p2"), "p1")]
+    #[case::empty_env(None, Some(""), Some("r_p1"), None, "p1")]
+    #[case::config(None, None, Some("r_none"), Some("p2"), "p2")]
+    #[case::config_no_recipe(None, None, None, Some("p2"), "p2")]
+    #[case::config_unknown(None, None, None, Some("bogus"), "default")]
+    #[case::default(None, None, Some("r_none"), None, "default")]
+    fn test_resolve_profile(
+        #[case] explicit: Option<&str>,
+        #[case] env_value: Option<&str>,
+        #[case] recipe_id: Option<&str>,
+        #[case] config_default: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let collection = collection_with_profiles();
+        let result = {
+            let _guard = env_lock::lock_env([(PROFILE_ENV_VAR, env_value)]);
+            collection.resolve_profile(
+                explicit.map(ProfileId::from),
+                recipe_id.map(RecipeId::from).as_ref(),
+                config_default.map(ProfileId::from).as_ref(),
+            )
+        };
+        assert_eq!(result.unwrap(), Some(ProfileId::from(expected)));
+    }
+
+    /// Requesting an unknown profile, via flag, env, or recipe, is an error
+    #[rstest]
+    #[case::explicit(Some("bogus"), None, None)]
+    #[case::env(None, Some("bogus"), None)]
+    #[case::recipe(None, None, Some("r_bogus"))]
+    fn test_resolve_profile_unknown(
+        #[case] explicit: Option<&str>,
+        #[case] env_value: Option<&str>,
+        #[case] recipe_id: Option<&str>,
+    ) {
+        let collection = collection_with_profiles();
+        let result = {
+            let _guard = env_lock::lock_env([(PROFILE_ENV_VAR, env_value)]);
+            collection.resolve_profile(
+                explicit.map(ProfileId::from),
+                recipe_id.map(RecipeId::from).as_ref(),
+                None,
+            )
+        };
+        assert_err!(result, "No profile with ID `bogus`");
+    }
+
+    fn collection_with_profiles() -> Collection {
+        let recipe = |id: &str, profile: Option<&str>| {
+            RecipeNode::Recipe(Recipe {
+                id: id.into(),
+                profile: profile.map(ProfileId::from),
+                ..Recipe::factory(())
+            })
+        };
+        Collection {
+            profiles: by_id([
+                Profile {
+                    id: "p1".into(),
+                    ..Profile::factory(())
+                },
+                Profile {
+                    id: "p2".into(),
+                    ..Profile::factory(())
+                },
+                Profile {
+                    id: "default".into(),
+                    default: true,
+                    ..Profile::factory(())
+                },
+            ]),
+            recipes: by_id([
+                recipe("r_p1", Some("p1")),
+                recipe("r_p2", Some("p2")),
+                recipe("r_bogus", Some("bogus")),
+                recipe("r_none", None),
+            ])
+            .into(),
+            ..Collection::factory(())
+        }
+    }
+
+    #[test]
+    fn test_templates() {
+        let recipe = Recipe {
+            url: "{{host}}/fish".into(),
+            query: vec![("big".into(), "true".into())],
+            headers: vec![
+                ("Accept".into(), "text/plain".into()),
+                ("Accept".into(), "text/html".into()),
+            ],
+            authentication: Some(Authentication::Basic {
+                username: "user".into(),
+                password: None,
+            }),
+            body: Some(RecipeBody::FormUrlencoded(indexmap! {
+                "name".into() => "Nemo".into(),
+            })),
+            env: indexmap! {"TOKEN".into() => "{{chains.token}}".into()},
+            ..Recipe::factory(())
+        };
+        let labels = recipe
+            .templates()
+            .into_iter()
+            .map(|(label, template)| (label, template.display().into_owned()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                ("url", "{{host}}/fish"),
+                ("query.big", "true"),
+                ("headers.Accept", "text/plain"),
+                ("headers.Accept", "text/html"),
+                ("authentication.username", "user"),
+                ("body.name", "Nemo"),
+                ("env.TOKEN", "{{chains.token}}"),
+            ]
+            .map(|(label, template)| (label.to_owned(), template.to_owned()))
+        );
+    }
+
+    /// Request chains must reference a recipe that exists, and recipes must
+    /// reference a profile that exists
+    #[test]
+    fn test_validate() {
+        let request_chain = |id: &'static str, recipe: &'static str| Chain {
+            id: id.into(),
+            source: ChainSource::Request {
+                recipe: recipe.into(),
+                trigger: Default::default(),
+                section: Default::default(),
+            },
+            ..Chain::factory(())
+        };
+        let collection = Collection {
+            profiles: by_id([Profile {
+                id: "prod".into(),
+                chains: by_id([request_chain("ok", "prod_login")]),
+                ..Profile::factory(())
+            }]),
+            chains: by_id([
+                request_chain("ok", "login"),
+                request_chain("unknown", "logout"),
+                request_chain("folder", "users"),
+                Chain {
+                    id: "command".into(),
+                    source: ChainSource::command(["echo"]),
+                    ..Chain::factory(())
+                },
+            ]),
+            recipes: by_id([
+                RecipeNode::Recipe(Recipe {
+                    id: "login".into(),
+                    profile: Some("prod".into()),
+                    ..Recipe::factory(())
+                }),
+                RecipeNode::Recipe(Recipe {
+                    id: "stale".into(),
+                    profile: Some("dev".into()),
+                    ..Recipe::factory(())
+                }),
+                RecipeNode::Folder(Folder {
+                    id: "users".into(),
+                    ..Folder::factory(())
+                }),
+            ])
+            .into(),
+            ..Collection::default()
+        };
+        assert_eq!(
+            collection.validate(),
+            [
+                "Chain `unknown` references unknown recipe `logout`",
+                "Chain `folder` references folder `users`; request chains \
+                 must reference a recipe",
+                "Chain `ok` in profile `prod` references unknown recipe \
+                 `prod_login`",
+                "Recipe `stale` references unknown profile `dev`",
+            ]
+        );
+    }
+
+    /// Unused chains and indistinct profiles are reported
+    #[test]
+    fn test_lint() {
+        let chain = |id: &'static str, command: &'static str| Chain {
+            id: id.into(),
+            source: ChainSource::command([command]),
+            ..Chain::factory(())
+        };
+        let profile = |id: &'static str, host: Option<&'static str>| Profile {
+            id: id.into(),
+            data: host
+                .map(|host| ("host".into(), host.into()))
+                .into_iter()
+                .collect(),
+            ..Profile::factory(())
+        };
+        let collection = Collection {
+            profiles: by_id([
+                profile("local", Some("http://localhost")),
+                profile("prod", Some("{{chains.prod_host}}")),
+                profile("copy", Some("http://localhost")),
+                profile("empty", None),
+                Profile {
+                    chains: by_id([chain("scoped", "echo")]),
+                    ..profile("scoped", None)
+                },
+            ]),
+            chains: by_id([
+                chain("prod_host", "echo"),
+                chain("in_recipe", "echo"),
+                chain("nested", "{{chains.in_recipe}}"),
+                chain("in_env", "echo"),
+                chain("unused", "echo"),
+                chain("self", "{{chains.self}}"),
+            ]),
+            recipes: by_id([RecipeNode::Recipe(Recipe {
+                url: "{{host}}/{{chains.in_recipe}}/{{chains.nested}}".into(),
+                // Chains used only in env are still used
+                env: indexmap! {"TOKEN".into() => "{{chains.in_env}}".into()},
+                ..Recipe::factory(())
+            })])
+            .into(),
+            ..Collection::default()
+        };
+        assert_eq!(
+            collection.lint(),
+            [
+                "Chain `unused` is never used",
+                "Chain `self` is never used",
+                "Chain `scoped` in profile `scoped` is never used",
+                "Profile `copy` has the same data as profile `local`",
+                "Profile `empty` has no data",
+            ]
+        );
+    }
+
+    /// Profile-scoped chains take precedence over collection-level chains
+    #[rstest]
+    #[case::profile_override(Some("prod"), "ok", Some("prod"))]
+    #[case::profile_fallback(Some("prod"), "other", Some("collection"))]
+    #[case::no_profile(None, "ok", Some("collection"))]
+    #[case::other_profile(Some("dev"), "ok", Some("collection"))]
+    #[case::unknown(Some("prod"), "unknown", None)]
+    fn test_get_chain(
+        #[case] profile_id: Option<&str>,
+        #[case] chain_id: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let command_chain = |id: &str, output: &str| Chain {
+            id: id.into(),
+            source: ChainSource::command(["echo", output]),
+            ..Chain::factory(())
+        };
+        let collection = Collection {
+            profiles: by_id([
+                Profile {
+                    id: "prod".into(),
+                    chains: by_id([command_chain("ok", "prod")]),
+                    ..Profile::factory(())
+                },
+                Profile {
+                    id: "dev".into(),
+                    ..Profile::factory(())
+                },
+            ]),
+            chains: by_id([
+                command_chain("ok", "collection"),
+                command_chain("other", "collection"),
+            ]),
+            ..Collection::default()
+        };
+        let profile_id = profile_id.map(ProfileId::from);
+        let chain = collection
+            .get_chain(&chain_id.into(), profile_id.as_ref())
+            .map(|chain| &chain.source);
+        assert_eq!(
+            chain,
+            expected
+                .map(|output| ChainSource::command(["echo", output]))
+                .as_ref()
+        );
+    }
+
+    /// Dependencies are found through direct request chains, and
+    /// transitively through other chains
+    #[test]
+    fn test_dependency_graph() {
+        let request_chain = |id: &'static str, recipe: &'static str| Chain {
+            id: id.into(),
+            source: ChainSource::Request {
+                recipe: recipe.into(),
+                trigger: Default::default(),
+                section: Default::default(),
+            },
+            ..Chain::factory(())
+        };
+        let recipe = |id: &'static str, url: &'static str| Recipe {
+            id: id.into(),
+            url: url.into(),
+            ..Recipe::factory(())
+        };
+        let collection = Collection {
+            chains: by_id([
+                request_chain("token", "login"),
+                request_chain("user_id", "get_user"),
+                Chain {
+                    id: "wrapped".into(),
+                    source: ChainSource::command(["echo", "{{chains.token}}"]),
+                    ..Chain::factory(())
+                },
+            ]),
+            recipes: by_id([
+                recipe("login", "/login"),
+                recipe("get_user", "/users?token={{chains.token}}"),
+                recipe(
+                    "update_user",
+                    "/users/{{chains.user_id}}?token={{chains.wrapped}}\
+                     &x={{chains.unknown}}",
+                ),
+            ])
+            .into(),
+            ..Collection::default()
+        };
+
+        let graph = collection
+            .dependency_graph()
+            .into_iter()
+            .map(|(recipe_id, dependencies)| {
+                (
+                    recipe_id.to_string(),
+                    dependencies
+                        .into_iter()
+                        .map(RecipeId::to_string)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            graph,
+            [
+                ("login", vec![]),
+                ("get_user", vec!["login"]),
+                ("update_user", vec!["get_user", "login"]),
+            ]
+            .map(|(recipe_id, dependencies)| (
+                recipe_id.to_owned(),
+                dependencies.into_iter().map(String::from).collect()
+            ))
+        );
+    }
+
+    /// Test auto-send eligibility, with the global default and per-recipe
+    /// overrides
+    #[rstest]
+    #[case::default_off(HttpMethod::Get, None, false, false, false)]
+    #[case::default_safe(HttpMethod::Get, None, false, true, true)]
+    #[case::default_unsafe(HttpMethod::Post, None, false, true, false)]
+    #[case::override_on(HttpMethod::Post, Some(true), false, false, true)]
+    #[case::override_off(HttpMethod::Get, Some(false), false, true, false)]
+    #[case::confirm(HttpMethod::Get, Some(true), true, true, false)]
+    fn test_recipe_auto_send(
+        #[case] method: HttpMethod,
+        #[case] auto_send: Option<bool>,
+        #[case] confirm: bool,
+        #[case] default: bool,
+        #[case] expected: bool,
+    ) {
+        let recipe = Recipe {
+            method,
+            auto_send,
+            confirm,
+            ..Recipe::factory(())
+        };
+        assert_eq!(recipe.auto_send(default), expected);
+    }
 }
@@ -107,6 +107,12 @@ impl RecipeTree {
         Ok(new)
     }
 
+    /// Take ownership of the inner tree, e.g. to modify it. Use [Self::new]
+    /// to rebuild the tree afterward.
+    pub(super) fn into_tree(self) -> IndexMap<RecipeId, RecipeNode> {
+        self.tree
+    }
+
     /// Get a recipe/folder's tree lookup key by is unique ID
     pub fn get_lookup_key(&self, id: &RecipeId) -> Option<&RecipeLookupKey> {
         self.nodes_by_id.get(id)
@@ -163,6 +169,26 @@ impl RecipeTree {
             .filter(|id| self.get_recipe(id).is_some())
     }
 
+    /// Get the IDs of all **recipes** under a folder, recursively, in tree
+    /// order. Return an error if the ID isn't in the tree or isn't a folder
+    pub fn folder_recipe_ids(
+        &self,
+        folder_id: &RecipeId,
+    ) -> anyhow::Result<Vec<&RecipeId>> {
+        self.get(folder_id)
+            .and_then(RecipeNode::folder)
+            .ok_or_else(|| anyhow!("No folder with ID `{}`", folder_id))?;
+        Ok(self
+            .nodes_by_id
+            .iter()
+            .filter(|(id, lookup_key)| {
+                lookup_key.ancestors().contains(folder_id)
+                    && self.get_recipe(id).is_some()
+            })
+            .map(|(id, _)| id)
+            .collect())
+    }
+
     /// Get a flat iterator over all nodes in the tree, using depth first
     /// search. Each yielded item will include the lookup key to retrieve
     /// that item.
@@ -381,6 +407,25 @@ mod tests {
         );
     }
 
+    /// Get all recipes under a folder, including nested folders
+    #[rstest]
+    fn test_folder_recipe_ids(tree: IndexMap<RecipeId, RecipeNode>) {
+        let tree = RecipeTree::new(tree).unwrap();
+        assert_eq!(
+            tree.folder_recipe_ids(&id("f1")).unwrap(),
+            vec![&id("r2"), &id("r3")]
+        );
+        assert_eq!(tree.folder_recipe_ids(&id("f2")).unwrap(), vec![&id("r2")]);
+        assert_err!(
+            tree.folder_recipe_ids(&id("r1")),
+            "No folder with ID `r1`"
+        );
+        assert_err!(
+            tree.folder_recipe_ids(&id("unknown")),
+            "No folder with ID `unknown`"
+        );
+    }
+
     /// Deserializing with a duplicate ID anywhere in the tree should fail
     #[rstest]
     #[case::anywhere(
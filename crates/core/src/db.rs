@@ -1,26 +1,45 @@
 //! The database is responsible for persisting data, including requests and
 //! responses.
 
+mod compression;
 mod convert;
+mod encryption;
 mod migrations;
+mod retention;
+
+pub use compression::BodyCompression;
+pub use encryption::{DatabaseEncryptionConfig, KeySource};
+pub use retention::HistoryRetention;
 
 use crate::{
     collection::{ProfileId, RecipeId},
-    db::convert::{CollectionPath, JsonEncoded, SqlWrap},
-    http::{Exchange, ExchangeSummary, RequestId},
+    db::{
+        convert::{exchange_from_row, CollectionPath, JsonEncoded, SqlWrap},
+        encryption::DatabaseCipher,
+    },
+    http::{Exchange, ExchangeSummary, PeerCertificate, RequestId},
     util::{paths, ResultTraced},
 };
 use anyhow::{anyhow, Context};
+use chrono::Utc;
 use derive_more::Display;
-use rusqlite::{named_params, Connection, DatabaseName, OptionalExtension};
+use rusqlite::{
+    named_params, Connection, DatabaseName, ErrorCode, OptionalExtension,
+};
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
+    borrow::Cow,
     fmt::Debug,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
 };
-use tracing::{debug, info, trace};
+use tracing::{debug, info, trace, warn};
 use uuid::Uuid;
 
 /// A SQLite database for persisting data. Generally speaking, any error that
@@ -44,10 +63,39 @@ pub struct Database {
     /// one connection per thread, but the code would be a bit more
     /// complicated.
     connection: Arc<Mutex<Connection>>,
+    /// Used to encrypt sensitive values before writing them, and decrypt them
+    /// on read. If `None`, values are written in plaintext, and encrypted
+    /// values can't be read.
+    cipher: Option<DatabaseCipher>,
+    /// Used to compress bodies before writing them. If `None`, bodies are
+    /// written uncompressed. Compressed bodies can be read either way.
+    compression: Option<BodyCompression>,
+    /// Maximum number of exchanges to keep in history, across all
+    /// collections. If `None`, history is unlimited
+    max_rows: Option<usize>,
+    /// Number of exchanges inserted since [Self::max_rows] was last enforced.
+    /// Shared between clones, so the check interval is global
+    inserts_since_check: Arc<AtomicUsize>,
+    /// Retry queries that fail because the database is locked? Retries sleep
+    /// the calling thread, so this should be disabled for handles used on a
+    /// thread that can't block, e.g. the TUI's main thread
+    lock_retry: bool,
 }
 
 impl Database {
     const FILE: &'static str = "state.sqlite";
+    /// How long SQLite waits for another connection to release its lock
+    /// before failing a query
+    pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+    /// Maximum number of attempts for a query that fails because the database
+    /// is locked
+    const LOCK_ATTEMPTS: u32 = 4;
+    /// Delay before the first retry of a locked query. Doubles each attempt
+    const LOCK_RETRY_DELAY: Duration = Duration::from_millis(50);
+    /// Enforce [Self::max_rows] once every this many inserts. Counting rows
+    /// on every insert would scan the table each time, so the limit may be
+    /// exceeded by a few rows in between checks
+    const MAX_ROWS_CHECK_INTERVAL: usize = 20;
 
     /// Load the database. This will perform migrations, but can be called from
     /// anywhere in the app. The migrations will run on first connection, and
@@ -57,7 +105,14 @@ impl Database {
         paths::create_parent(&path)?;
 
         info!(?path, "Loading database");
+        Self::open(&path)
+    }
+
+    /// Open a connection to a database file and migrate it
+    fn open(path: &Path) -> anyhow::Result<Self> {
         let mut connection = Connection::open(path)?;
+        // Set explicitly so migrations wait on other instances too
+        connection.busy_timeout(Self::DEFAULT_BUSY_TIMEOUT)?;
         connection.pragma_update(
             Some(DatabaseName::Main),
             "foreign_keys",
@@ -68,9 +123,59 @@ impl Database {
         Self::migrate(&mut connection)?;
         Ok(Self {
             connection: Arc::new(Mutex::new(connection)),
+            cipher: None,
+            compression: None,
+            max_rows: None,
+            inserts_since_check: Default::default(),
+            lock_retry: true,
         })
     }
 
+    /// Enable at-rest encryption for sensitive values, according to config.
+    /// If encryption is disabled, this does nothing. Return an error if
+    /// encryption is enabled but the key can't be loaded.
+    pub fn with_encryption(
+        mut self,
+        config: &DatabaseEncryptionConfig,
+    ) -> anyhow::Result<Self> {
+        self.cipher = config.cipher()?;
+        Ok(self)
+    }
+
+    /// Compress request/response bodies before storing them. Existing rows
+    /// are unaffected, and rows can be read regardless of this setting.
+    pub fn with_compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled.then_some(BodyCompression::Zstd);
+        self
+    }
+
+    /// Cap the total number of exchanges in history, across all collections.
+    /// When the cap is exceeded, the oldest exchanges are deleted after an
+    /// insert. `None` means unlimited.
+    pub fn with_max_rows(mut self, max_rows: Option<usize>) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// Set how long queries wait for a lock held by another connection (e.g.
+    /// another Slumber process) before failing. This sets SQLite's
+    /// `busy_timeout`.
+    pub fn with_busy_timeout(self, timeout: Duration) -> anyhow::Result<Self> {
+        self.connection()
+            .busy_timeout(timeout)
+            .context("Error setting database busy timeout")?;
+        Ok(self)
+    }
+
+    /// Enable or disable retrying queries that fail because another process
+    /// holds a lock on the database. Retries block the calling thread while
+    /// waiting, so disable them for handles used from an async or UI thread.
+    /// The busy timeout still applies either way.
+    pub fn with_lock_retry(mut self, enabled: bool) -> Self {
+        self.lock_retry = enabled;
+        self
+    }
+
     /// Path to the database file
     pub fn path() -> PathBuf {
         paths::data_directory().join(Self::FILE)
@@ -82,11 +187,75 @@ impl Database {
         Ok(())
     }
 
+    /// If [Self::max_rows] is set, delete the oldest exchanges (from any
+    /// collection) beyond the limit and return the number of deleted rows.
+    /// This only does the work every [Self::MAX_ROWS_CHECK_INTERVAL] calls,
+    /// including the first.
+    fn enforce_max_rows(&self) -> anyhow::Result<usize> {
+        let Some(max_rows) = self.max_rows else {
+            return Ok(0);
+        };
+        let count = self.inserts_since_check.fetch_add(1, Ordering::Relaxed);
+        if count % Self::MAX_ROWS_CHECK_INTERVAL != 0 {
+            return Ok(0);
+        }
+
+        let deleted = self
+            .query(|connection| {
+                connection.execute(
+                    // A negative limit means no limit, so this selects
+                    // everything past the newest `max_rows`
+                    "DELETE FROM requests_v2
+                    WHERE id IN (
+                        SELECT id FROM requests_v2
+                        ORDER BY start_time DESC
+                        LIMIT -1 OFFSET :max_rows
+                    )",
+                    named_params! {":max_rows": max_rows},
+                )
+            })
+            .context("Error deleting excess requests from database")?;
+        if deleted > 0 {
+            debug!(deleted, max_rows, "Evicted oldest requests from history");
+        }
+        Ok(deleted)
+    }
+
     /// Get a reference to the DB connection. Panics if the lock is poisoned
     fn connection(&self) -> impl '_ + DerefMut<Target = Connection> {
         self.connection.lock().expect("Connection lock poisoned")
     }
 
+    /// Run a query, retrying with backoff if it fails because another process
+    /// holds a lock on the database. The busy timeout covers most contention,
+    /// but in WAL mode some lock errors (e.g. upgrading a read transaction to
+    /// a write) skip the busy handler and fail immediately. The connection
+    /// lock is released while waiting. If retries are disabled, the first
+    /// error is returned.
+    fn query<T>(
+        &self,
+        mut f: impl FnMut(&Connection) -> rusqlite::Result<T>,
+    ) -> rusqlite::Result<T> {
+        let mut delay = Self::LOCK_RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
+            let result = f(&self.connection());
+            match result {
+                Err(error)
+                    if self.lock_retry
+                        && is_locked_error(&error)
+                        && attempt < Self::LOCK_ATTEMPTS =>
+                {
+                    warn!(%error, attempt, ?delay, "Database locked, retrying");
+                    thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// Get a list of all collections
     pub fn collections(&self) -> anyhow::Result<Vec<PathBuf>> {
         self.connection()
@@ -182,8 +351,8 @@ impl Database {
 
         // We have to set/get in two separate queries, because RETURNING doesn't
         // return anything if the insert didn't modify
-        self.connection()
-            .execute(
+        self.query(|connection| {
+            connection.execute(
                 "INSERT INTO collections (id, path) VALUES (:id, :path)
                 ON CONFLICT(path) DO NOTHING",
                 named_params! {
@@ -191,15 +360,17 @@ impl Database {
                     ":path": &path,
                 },
             )
-            .context("Error setting collection ID")
-            .traced()?;
+        })
+        .context("Error setting collection ID")
+        .traced()?;
         let collection_id = self
-            .connection()
-            .query_row(
-                "SELECT id FROM collections WHERE path = :path",
-                named_params! {":path": &path},
-                |row| row.get::<_, CollectionId>("id"),
-            )
+            .query(|connection| {
+                connection.query_row(
+                    "SELECT id FROM collections WHERE path = :path",
+                    named_params! {":path": &path},
+                    |row| row.get::<_, CollectionId>("id"),
+                )
+            })
             .context("Error fetching collection ID")
             .traced()?;
 
@@ -222,15 +393,25 @@ pub struct CollectionDatabase {
 }
 
 impl CollectionDatabase {
+    /// Get a copy of this handle with lock retries enabled or disabled. See
+    /// [Database::with_lock_retry]
+    pub fn with_lock_retry(&self, enabled: bool) -> Self {
+        Self {
+            database: self.database.clone().with_lock_retry(enabled),
+            ..self.clone()
+        }
+    }
+
     /// Get the full path for the collection file associated with this DB handle
     pub fn collection_path(&self) -> anyhow::Result<PathBuf> {
         self.database
-            .connection()
-            .query_row(
-                "SELECT path FROM collections WHERE id = :id",
-                named_params! {":id": self.collection_id},
-                |row| row.get::<_, CollectionPath>("path"),
-            )
+            .query(|connection| {
+                connection.query_row(
+                    "SELECT path FROM collections WHERE id = :id",
+                    named_params! {":id": self.collection_id},
+                    |row| row.get::<_, CollectionPath>("path"),
+                )
+            })
             .context("Error fetching collection path")
             .traced()
             .map(PathBuf::from)
@@ -257,20 +438,27 @@ impl CollectionDatabase {
     ) -> anyhow::Result<Option<Exchange>> {
         trace!(request_id = %request_id, "Fetching request from database");
         self.database
-            .connection()
-            .query_row(
-                "SELECT * FROM requests_v2
-                WHERE collection_id = :collection_id
-                    AND id = :request_id
-                ORDER BY start_time DESC LIMIT 1",
-                named_params! {
-                    // Include collection ID just to be extra safe
-                    ":collection_id": self.collection_id,
-                    ":request_id": request_id,
-                },
-                |row| row.try_into(),
-            )
-            .optional()
+            .query(|connection| {
+                connection
+                    .query_row(
+                        "SELECT * FROM requests_v2
+                        WHERE collection_id = :collection_id
+                            AND id = :request_id
+                        ORDER BY start_time DESC LIMIT 1",
+                        named_params! {
+                            // Include collection ID just to be extra safe
+                            ":collection_id": self.collection_id,
+                            ":request_id": request_id,
+                        },
+                        |row| {
+                            exchange_from_row(
+                                row,
+                                self.database.cipher.as_ref(),
+                            )
+                        },
+                    )
+                    .optional()
+            })
             .with_context(|| {
                 format!("Error fetching request {} from database", request_id)
             })
@@ -291,27 +479,37 @@ impl CollectionDatabase {
             "Fetching last request from database"
         );
         self.database
-            .connection()
-            .query_row(
-                // `IS` needed for profile_id so `None` will match `NULL`.
-                // We want to dynamically ignore the profile filter if the user
-                // is asking for all profiles. Dynamically modifying the query
-                // is really ugly so the easiest thing is to use an additional
-                // parameter to bypass the filter
-                "SELECT * FROM requests_v2
-                WHERE collection_id = :collection_id
-                    AND (:ignore_profile_id OR profile_id IS :profile_id)
-                    AND recipe_id = :recipe_id
-                ORDER BY start_time DESC LIMIT 1",
-                named_params! {
-                    ":collection_id": self.collection_id,
-                    ":ignore_profile_id": profile_id == ProfileFilter::All,
-                    ":profile_id": profile_id,
-                    ":recipe_id": recipe_id,
-                },
-                |row| row.try_into(),
-            )
-            .optional()
+            .query(|connection| {
+                connection
+                    .query_row(
+                        // `IS` needed for profile_id so `None` will match
+                        // `NULL`. We want to dynamically ignore the profile
+                        // filter if the user is asking for all profiles.
+                        // Dynamically modifying the query is really ugly so
+                        // the easiest thing is to use an additional parameter
+                        // to bypass the filter
+                        "SELECT * FROM requests_v2
+                        WHERE collection_id = :collection_id
+                            AND (:ignore_profile_id
+                                OR profile_id IS :profile_id)
+                            AND recipe_id = :recipe_id
+                        ORDER BY start_time DESC LIMIT 1",
+                        named_params! {
+                            ":collection_id": self.collection_id,
+                            ":ignore_profile_id":
+                                profile_id == ProfileFilter::All,
+                            ":profile_id": profile_id,
+                            ":recipe_id": recipe_id,
+                        },
+                        |row| {
+                            exchange_from_row(
+                                row,
+                                self.database.cipher.as_ref(),
+                            )
+                        },
+                    )
+                    .optional()
+            })
             .with_context(|| {
                 format!(
                     "Error fetching request [profile={:?}; recipe={}] \
@@ -337,33 +535,38 @@ impl CollectionDatabase {
         // there's no good way to dynamically build a query with sqlite so it
         // ends up not being worth it
         self.database
-            .connection()
-            .prepare(
-                // `IS` needed for profile_id so `None` will match `NULL`.
-                // We want to dynamically ignore the profile filter if the user
-                // is asking for all profiles. Dynamically modifying the query
-                // is really ugly so the easiest thing is to use an additional
-                // parameter to bypass the filter
-                "SELECT id, profile_id, start_time, end_time, status_code
-                FROM requests_v2
-                WHERE collection_id = :collection_id
-                    AND (:ignore_profile_id OR profile_id IS :profile_id)
-                    AND recipe_id = :recipe_id
-                ORDER BY start_time DESC",
-            )?
-            .query_map(
-                named_params! {
-                    ":collection_id": self.collection_id,
-                    ":ignore_profile_id": profile_id == ProfileFilter::All,
-                    ":profile_id": profile_id,
-                    ":recipe_id": recipe_id,
-                },
-                |row| row.try_into(),
-            )
+            .query(|connection| {
+                connection
+                    .prepare(
+                        // `IS` needed for profile_id so `None` will match
+                        // `NULL`. We want to dynamically ignore the profile
+                        // filter if the user is asking for all profiles.
+                        // Dynamically modifying the query is really ugly so
+                        // the easiest thing is to use an additional parameter
+                        // to bypass the filter
+                        "SELECT id, profile_id, start_time, end_time,
+                            status_code
+                        FROM requests_v2
+                        WHERE collection_id = :collection_id
+                            AND (:ignore_profile_id
+                                OR profile_id IS :profile_id)
+                            AND recipe_id = :recipe_id
+                        ORDER BY start_time DESC",
+                    )?
+                    .query_map(
+                        named_params! {
+                            ":collection_id": self.collection_id,
+                            ":ignore_profile_id":
+                                profile_id == ProfileFilter::All,
+                            ":profile_id": profile_id,
+                            ":recipe_id": recipe_id,
+                        },
+                        |row| row.try_into(),
+                    )?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
             .context("Error fetching request history from database")
-            .traced()?
-            .collect::<rusqlite::Result<Vec<_>>>()
-            .context("Error extracting request history")
+            .traced()
     }
 
     /// Add a new exchange to history. The HTTP engine is responsible for
@@ -379,69 +582,179 @@ impl CollectionDatabase {
             url = %exchange.request.url,
             "Adding exchange to database",
         );
+        // Compress, then encrypt bodies if enabled. Compressing encrypted data
+        // is pointless, so the order matters
+        let compression = self.database.compression;
+        let encode = |body: &[u8]| -> anyhow::Result<Cow<'_, [u8]>> {
+            let body: Cow<'_, [u8]> = match compression {
+                Some(compression) => compression.compress(body)?.into(),
+                None => body.into(),
+            };
+            match &self.database.cipher {
+                Some(cipher) => Ok(cipher.encrypt(&body)?.into()),
+                None => Ok(body),
+            }
+        };
+        let request_body = exchange.request.body().map(encode).transpose()?;
+        let response_body = encode(exchange.response.body.bytes())?;
         self.database
-            .connection()
-            .execute(
-                "INSERT INTO
-                requests_v2 (
-                    id,
-                    collection_id,
-                    profile_id,
-                    recipe_id,
-                    start_time,
-                    end_time,
-                    http_version,
-                    method,
-                    url,
-                    request_headers,
-                    request_body,
-                    status_code,
-                    response_headers,
-                    response_body
+            .query(|connection| {
+                connection.execute(
+                    "INSERT INTO
+                    requests_v2 (
+                        id,
+                        collection_id,
+                        profile_id,
+                        recipe_id,
+                        start_time,
+                        end_time,
+                        http_version,
+                        method,
+                        url,
+                        request_headers,
+                        request_body,
+                        status_code,
+                        response_http_version,
+                        response_headers,
+                        response_body,
+                        response_body_full_size,
+                        response_peer_certificate,
+                        body_compression,
+                        body_encrypted,
+                        correlation_id
+                    )
+                    VALUES (
+                        :id,
+                        :collection_id,
+                        :profile_id,
+                        :recipe_id,
+                        :start_time,
+                        :end_time,
+                        :http_version,
+                        :method,
+                        :url,
+                        :request_headers,
+                        :request_body,
+                        :status_code,
+                        :response_http_version,
+                        :response_headers,
+                        :response_body,
+                        :response_body_full_size,
+                        :response_peer_certificate,
+                        :body_compression,
+                        :body_encrypted,
+                        :correlation_id
+                    )",
+                    named_params! {
+                        ":id": exchange.id,
+                        ":collection_id": self.collection_id,
+                        ":profile_id": &exchange.request.profile_id,
+                        ":recipe_id": &exchange.request.recipe_id,
+                        ":start_time": &exchange.start_time,
+                        ":end_time": &exchange.end_time,
+
+                        ":http_version": exchange.request.http_version,
+                        ":method": exchange.request.method,
+                        ":url": exchange.request.url.as_str(),
+                        ":request_headers": SqlWrap(&exchange.request.headers),
+                        ":request_body": request_body.as_deref(),
+
+                        ":status_code": exchange.response.status.as_u16(),
+                        ":response_http_version":
+                            exchange.response.http_version,
+                        ":response_headers":
+                            SqlWrap(&exchange.response.headers),
+                        ":response_body": response_body.deref(),
+                        ":response_body_full_size":
+                            exchange.response.body.full_size(),
+                        ":response_peer_certificate": exchange
+                            .response
+                            .peer_certificate
+                            .as_ref()
+                            .map(PeerCertificate::der),
+                        ":body_compression": compression,
+                        ":body_encrypted": self.database.cipher.is_some(),
+                        ":correlation_id": exchange.request.correlation_id,
+                    },
                 )
-                VALUES (
-                    :id,
-                    :collection_id,
-                    :profile_id,
-                    :recipe_id,
-                    :start_time,
-                    :end_time,
-                    :http_version,
-                    :method,
-                    :url,
-                    :request_headers,
-                    :request_body,
-                    :status_code,
-                    :response_headers,
-                    :response_body
-                )",
-                named_params! {
-                    ":id": exchange.id,
-                    ":collection_id": self.collection_id,
-                    ":profile_id": &exchange.request.profile_id,
-                    ":recipe_id": &exchange.request.recipe_id,
-                    ":start_time": &exchange.start_time,
-                    ":end_time": &exchange.end_time,
-
-                    ":http_version": exchange.request.http_version,
-                    ":method": exchange.request.method,
-                    ":url": exchange.request.url.as_str(),
-                    ":request_headers": SqlWrap(&exchange.request.headers),
-                    ":request_body": exchange.request.body(),
-
-                    ":status_code": exchange.response.status.as_u16(),
-                    ":response_headers": SqlWrap(&exchange.response.headers),
-                    ":response_body": exchange.response.body.bytes().deref(),
-                },
-            )
+            })
             .context(format!(
                 "Error saving request {} to database",
                 exchange.id
             ))
             .traced()?;
+        // The exchange is already saved, so failing to evict old rows
+        // shouldn't be reported as a failed insert. We'll try again later
+        let _ = self.database.enforce_max_rows().traced();
         Ok(())
     }
 
+    /// Delete exchanges that fall outside the given retention limits, and
+    /// return the number of deleted rows. If no limits are set, this does
+    /// nothing.
+    pub fn apply_retention(
+        &self,
+        retention: &HistoryRetention,
+    ) -> anyhow::Result<usize> {
+        if !retention.is_enabled() {
+            return Ok(0);
+        }
+        self.ensure_write()?;
+
+        let mut deleted = 0;
+        if let Some(max_age) = retention.max_age {
+            let cutoff = Utc::now()
+                - chrono::Duration::from_std(max_age)
+                    .context("Invalid history retention max age")?;
+            deleted += self
+                .database
+                .query(|connection| {
+                    connection.execute(
+                        "DELETE FROM requests_v2
+                        WHERE collection_id = :collection_id
+                            AND start_time < :cutoff",
+                        named_params! {
+                            ":collection_id": self.collection_id,
+                            ":cutoff": cutoff,
+                        },
+                    )
+                })
+                .context("Error deleting expired requests from database")
+                .traced()?;
+        }
+        if let Some(max_per_recipe) = retention.max_per_recipe {
+            deleted += self
+                .database
+                .query(|connection| {
+                    connection.execute(
+                        // Number each recipe's requests newest->oldest, then
+                        // delete everything past the limit
+                        "DELETE FROM requests_v2
+                        WHERE id IN (
+                            SELECT id FROM (
+                                SELECT id, ROW_NUMBER() OVER (
+                                    PARTITION BY recipe_id
+                                    ORDER BY start_time DESC
+                                ) AS position
+                                FROM requests_v2
+                                WHERE collection_id = :collection_id
+                            )
+                            WHERE position > :max_per_recipe
+                        )",
+                        named_params! {
+                            ":collection_id": self.collection_id,
+                            ":max_per_recipe": max_per_recipe,
+                        },
+                    )
+                })
+                .context("Error deleting excess requests from database")
+                .traced()?;
+        }
+
+        info!(deleted, ?retention, "Applied history retention");
+        Ok(deleted)
+    }
+
     /// Get the value of a UI state field. Key type is included as part of the
     /// key, to disambiguate between keys of identical structure
     pub fn get_ui<K, V>(
@@ -455,23 +768,25 @@ impl CollectionDatabase {
     {
         let value = self
             .database
-            .connection()
-            .query_row(
-                "SELECT value FROM ui_state_v2
-                WHERE collection_id = :collection_id
-                    AND key_type = :key_type
-                    AND key = :key",
-                named_params! {
-                    ":collection_id": self.collection_id,
-                    ":key_type": key_type,
-                    ":key": JsonEncoded(&key),
-                },
-                |row| {
-                    let value: JsonEncoded<V> = row.get("value")?;
-                    Ok(value.0)
-                },
-            )
-            .optional()
+            .query(|connection| {
+                connection
+                    .query_row(
+                        "SELECT value FROM ui_state_v2
+                        WHERE collection_id = :collection_id
+                            AND key_type = :key_type
+                            AND key = :key",
+                        named_params! {
+                            ":collection_id": self.collection_id,
+                            ":key_type": key_type,
+                            ":key": JsonEncoded(&key),
+                        },
+                        |row| {
+                            let value: JsonEncoded<V> = row.get("value")?;
+                            Ok(value.0)
+                        },
+                    )
+                    .optional()
+            })
             .context(format!("Error fetching UI state for {key:?}"))
             .traced()?;
         debug!(?key, ?value, "Fetched UI state");
@@ -493,19 +808,21 @@ impl CollectionDatabase {
 
         debug!(?key, ?value, "Setting UI state");
         self.database
-            .connection()
-            .execute(
-                // Upsert!
-                "INSERT INTO ui_state_v2 (collection_id, key_type, key, value)
-                VALUES (:collection_id, :key_type, :key, :value)
-                ON CONFLICT DO UPDATE SET value = excluded.value",
-                named_params! {
-                    ":collection_id": self.collection_id,
-                    ":key_type": key_type,
-                    ":key": JsonEncoded(key),
-                    ":value": JsonEncoded(value),
-                },
-            )
+            .query(|connection| {
+                connection.execute(
+                    // Upsert!
+                    "INSERT INTO
+                    ui_state_v2 (collection_id, key_type, key, value)
+                    VALUES (:collection_id, :key_type, :key, :value)
+                    ON CONFLICT DO UPDATE SET value = excluded.value",
+                    named_params! {
+                        ":collection_id": self.collection_id,
+                        ":key_type": key_type,
+                        ":key": JsonEncoded(key),
+                        ":value": JsonEncoded(value),
+                    },
+                )
+            })
             .context("Error saving UI state to database")
             .traced()?;
         Ok(())
@@ -529,6 +846,23 @@ impl CollectionId {
     }
 }
 
+/// Did this error occur because another connection (e.g. another Slumber
+/// process) held a lock on the database? Callers can use this to degrade
+/// gracefully on contention instead of failing outright.
+pub fn is_locked(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|error| error.downcast_ref::<rusqlite::Error>())
+        .any(is_locked_error)
+}
+
+fn is_locked_error(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
 /// Create an in-memory DB, only for testing
 #[cfg(any(test, feature = "test"))]
 impl crate::test_util::Factory for Database {
@@ -537,6 +871,11 @@ impl crate::test_util::Factory for Database {
         Self::migrate(&mut connection).unwrap();
         Self {
             connection: Arc::new(Mutex::new(connection)),
+            cipher: None,
+            compression: None,
+            max_rows: None,
+            inserts_since_check: Default::default(),
+            lock_retry: true,
         }
     }
 }
@@ -591,9 +930,61 @@ impl<'a> From<Option<&'a ProfileId>> for ProfileFilter<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{assert_err, test_util::Factory, util::paths::get_repo_root};
+    use crate::{
+        assert_err,
+        http::{RequestRecord, ResponseRecord},
+        test_util::{temp_dir, Factory, TempDir},
+        util::paths::get_repo_root,
+    };
     use itertools::Itertools;
-    use std::collections::HashMap;
+    use rstest::rstest;
+    use std::{collections::HashMap, time::Instant};
+
+    /// A query blocked by another connection's lock is retried, and reports a
+    /// lock error if the lock is never released
+    #[rstest]
+    fn test_locked(temp_dir: TempDir) {
+        let path = temp_dir.join("state.sqlite");
+        let collection = Database::open(&path)
+            .unwrap()
+            // Fail immediately so we only test our own retries
+            .with_busy_timeout(Duration::ZERO)
+            .unwrap()
+            .into_collection(
+                &get_repo_root().join("slumber.yml"),
+                DatabaseMode::ReadWrite,
+            )
+            .unwrap();
+        let exchange = Exchange::factory(());
+
+        // Another process is holding the write lock
+        let other = Connection::open(&path).unwrap();
+        other.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        let error = collection.insert_exchange(&exchange).unwrap_err();
+        assert!(is_locked(&error), "{error:?}");
+
+        // With retries disabled, the error is returned without sleeping. All
+        // retries would take 350ms total
+        let start = Instant::now();
+        let error = collection
+            .with_lock_retry(false)
+            .insert_exchange(&exchange)
+            .unwrap_err();
+        assert!(is_locked(&error), "{error:?}");
+        assert!(start.elapsed() < Database::LOCK_RETRY_DELAY * 3);
+
+        // Lock is released while we're retrying
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(60));
+            other.execute_batch("COMMIT").unwrap();
+        });
+        collection.insert_exchange(&exchange).unwrap();
+        handle.join().unwrap();
+        assert_eq!(
+            collection.get_request(exchange.id).unwrap().map(|e| e.id),
+            Some(exchange.id)
+        );
+    }
 
     #[test]
     fn test_merge() {
@@ -837,6 +1228,129 @@ mod tests {
         assert_eq!(ids, expected_ids)
     }
 
+    /// The global row limit is enforced periodically on insert, across all
+    /// collections
+    #[test]
+    fn test_max_rows() {
+        let database = Database::factory(()).with_max_rows(Some(2));
+        let other_collection = database
+            .clone()
+            // Has to be a real file
+            .into_collection(
+                &get_repo_root().join("README.md"),
+                DatabaseMode::ReadWrite,
+            )
+            .unwrap();
+        let database = database
+            .into_collection(
+                &get_repo_root().join("slumber.yml"),
+                DatabaseMode::ReadWrite,
+            )
+            .unwrap();
+        let recipe_id: RecipeId = "recipe1".into();
+        let count = |database: &CollectionDatabase| {
+            database
+                .get_all_requests(ProfileFilter::All, &recipe_id)
+                .unwrap()
+                .len()
+        };
+        let exchange = |minutes_ago: i64| Exchange {
+            start_time: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            ..Exchange::factory(recipe_id.clone())
+        };
+
+        // Check only happens every N inserts, so the limit can be exceeded
+        // briefly. The first insert triggers a check, then none until the
+        // interval is hit again
+        let interval = Database::MAX_ROWS_CHECK_INTERVAL;
+        for i in 0..interval {
+            database
+                .insert_exchange(&exchange((interval - i) as i64 + 1))
+                .unwrap();
+        }
+        assert_eq!(count(&database), interval);
+
+        // This insert triggers the check. Only the newest rows are kept,
+        // regardless of collection
+        let newest = exchange(0);
+        other_collection.insert_exchange(&newest).unwrap();
+        assert_eq!(count(&database), 1);
+        assert!(other_collection.get_request(newest.id).unwrap().is_some());
+    }
+
+    /// Old requests, and requests past the per-recipe limit, are deleted
+    #[test]
+    fn test_apply_retention() {
+        let database = Database::factory(());
+        let other_collection = database
+            .clone()
+            // Has to be a real file
+            .into_collection(
+                &get_repo_root().join("README.md"),
+                DatabaseMode::ReadWrite,
+            )
+            .unwrap();
+        let database = database
+            .into_collection(
+                &get_repo_root().join("slumber.yml"),
+                DatabaseMode::ReadWrite,
+            )
+            .unwrap();
+        let recipe1: RecipeId = "recipe1".into();
+        let recipe2: RecipeId = "recipe2".into();
+
+        // Insert from oldest to newest, so the factory timestamps line up
+        let old = Exchange {
+            start_time: Utc::now() - chrono::Duration::days(10),
+            ..Exchange::factory(recipe1.clone())
+        };
+        database.insert_exchange(&old).unwrap();
+        let recipe1_ids = (0..3)
+            .map(|_| {
+                let exchange = Exchange::factory(recipe1.clone());
+                database.insert_exchange(&exchange).unwrap();
+                exchange.id
+            })
+            .collect_vec();
+        let recipe2_exchange = Exchange::factory(recipe2.clone());
+        database.insert_exchange(&recipe2_exchange).unwrap();
+        // Other collections should be untouched
+        let other_exchange = Exchange {
+            start_time: Utc::now() - chrono::Duration::days(10),
+            ..Exchange::factory(recipe1.clone())
+        };
+        other_collection.insert_exchange(&other_exchange).unwrap();
+
+        // No limits
+        assert_eq!(
+            database
+                .apply_retention(&HistoryRetention::default())
+                .unwrap(),
+            0
+        );
+
+        let retention = HistoryRetention {
+            max_age: Some(Duration::from_secs(24 * 60 * 60)),
+            max_per_recipe: Some(2),
+        };
+        assert_eq!(database.apply_retention(&retention).unwrap(), 2);
+
+        let ids = |recipe_id: &RecipeId| {
+            database
+                .get_all_requests(ProfileFilter::All, recipe_id)
+                .unwrap()
+                .into_iter()
+                .map(|exchange| exchange.id)
+                .collect_vec()
+        };
+        assert_eq!(ids(&recipe1), [recipe1_ids[2], recipe1_ids[1]]);
+        assert_eq!(ids(&recipe2), [recipe2_exchange.id]);
+        assert!(other_collection
+            .get_request(other_exchange.id)
+            .unwrap()
+            .is_some());
+    }
+
     /// Test UI state storage and retrieval
     #[test]
     fn test_ui_state() {
@@ -880,4 +1394,159 @@ mod tests {
             "Database in read-only mode"
         );
     }
+
+    /// Bodies should be encrypted at rest, and plaintext rows from before
+    /// encryption was enabled should remain readable. Whether a row is
+    /// encrypted is tracked by a column, not by the content, so plaintext that
+    /// happens to look like ciphertext is left alone.
+    #[test]
+    fn test_encryption() {
+        let path = get_repo_root().join("slumber.yml");
+        let plain = Database::factory(());
+        let encrypted = Database {
+            cipher: Some(DatabaseCipher::new("hunter2")),
+            ..plain.clone()
+        };
+        let plain = plain
+            .into_collection(&path, DatabaseMode::ReadWrite)
+            .unwrap();
+        let encrypted = encrypted
+            .into_collection(&path, DatabaseMode::ReadWrite)
+            .unwrap();
+
+        let plain_id = RequestId::new();
+        let plain_exchange = Exchange::factory((
+            RequestRecord::factory(plain_id),
+            ResponseRecord {
+                body: b"slumber:enc:v1:not actually encrypted"
+                    .as_slice()
+                    .into(),
+                ..ResponseRecord::factory(plain_id)
+            },
+        ));
+        let id = RequestId::new();
+        let encrypted_exchange = Exchange::factory((
+            RequestRecord {
+                body: Some(b"request secret".as_slice().into()),
+                ..RequestRecord::factory(id)
+            },
+            ResponseRecord {
+                body: b"response secret".as_slice().into(),
+                ..ResponseRecord::factory(id)
+            },
+        ));
+        plain.insert_exchange(&plain_exchange).unwrap();
+        encrypted.insert_exchange(&encrypted_exchange).unwrap();
+
+        // Raw blob should not contain the plaintext
+        let raw: Vec<u8> = encrypted
+            .database
+            .connection()
+            .query_row(
+                "SELECT response_body FROM requests_v2 WHERE id = :id",
+                named_params! {":id": id},
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_ne!(raw, b"response secret");
+        let flagged: bool = encrypted
+            .database
+            .connection()
+            .query_row(
+                "SELECT body_encrypted FROM requests_v2 WHERE id = :id",
+                named_params! {":id": id},
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(flagged);
+
+        // Both can be read with the cipher
+        assert_eq!(
+            encrypted.get_request(plain_exchange.id).unwrap().as_ref(),
+            Some(&plain_exchange)
+        );
+        assert_eq!(
+            encrypted.get_request(id).unwrap().as_ref(),
+            Some(&encrypted_exchange)
+        );
+
+        // Plaintext rows can be read without the cipher too
+        assert_eq!(
+            plain.get_request(plain_exchange.id).unwrap().as_ref(),
+            Some(&plain_exchange)
+        );
+
+        // Encrypted data can't be read without the cipher
+        assert_err!(
+            plain.get_request(id),
+            "database encryption is not enabled"
+        );
+    }
+
+    /// Bodies should be compressed at rest, and uncompressed rows from before
+    /// compression was enabled should remain readable. Compression combines
+    /// with encryption.
+    #[rstest]
+    #[case::plain(None)]
+    #[case::encrypted(Some(DatabaseCipher::new("hunter2")))]
+    fn test_compression(#[case] cipher: Option<DatabaseCipher>) {
+        let path = get_repo_root().join("slumber.yml");
+        let uncompressed = Database {
+            cipher,
+            ..Database::factory(())
+        };
+        let compressed = uncompressed.clone().with_compression(true);
+        let uncompressed = uncompressed
+            .into_collection(&path, DatabaseMode::ReadWrite)
+            .unwrap();
+        let compressed = compressed
+            .into_collection(&path, DatabaseMode::ReadWrite)
+            .unwrap();
+
+        let body = br#"{"fish": "blue"}"#.repeat(100);
+        let uncompressed_exchange = Exchange::factory(());
+        let id = RequestId::new();
+        let compressed_exchange = Exchange::factory((
+            RequestRecord {
+                body: Some(body.as_slice().into()),
+                ..RequestRecord::factory(id)
+            },
+            ResponseRecord {
+                body: body.as_slice().into(),
+                ..ResponseRecord::factory(id)
+            },
+        ));
+        uncompressed
+            .insert_exchange(&uncompressed_exchange)
+            .unwrap();
+        compressed.insert_exchange(&compressed_exchange).unwrap();
+
+        let (raw, compression): (Vec<u8>, Option<BodyCompression>) = compressed
+            .database
+            .connection()
+            .query_row(
+                "SELECT response_body, body_compression FROM requests_v2
+                WHERE id = :id",
+                named_params! {":id": id},
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!(raw.len() < body.len());
+        assert_eq!(compression, Some(BodyCompression::Zstd));
+
+        // Both rows are readable regardless of the compression setting
+        for database in [&compressed, &uncompressed] {
+            assert_eq!(
+                database
+                    .get_request(uncompressed_exchange.id)
+                    .unwrap()
+                    .as_ref(),
+                Some(&uncompressed_exchange)
+            );
+            assert_eq!(
+                database.get_request(id).unwrap().as_ref(),
+                Some(&compressed_exchange)
+            );
+        }
+    }
 }
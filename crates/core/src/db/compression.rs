@@ -0,0 +1,74 @@
+//! Compression for request/response bodies in the database. Each row in
+//! `requests_v2` records which algorithm (if any) was applied to its bodies in
+//! the `body_compression` column. Rows written before compression was enabled
+//! have `NULL` there and are read as-is.
+
+use anyhow::Context;
+use derive_more::Display;
+use std::str::FromStr;
+
+/// zstd level to compress with. This is zstd's default, which is a good
+/// balance between speed and size for text-heavy bodies.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Compression algorithm applied to a row's bodies
+#[derive(Copy, Clone, Debug, Display, PartialEq)]
+pub enum BodyCompression {
+    #[display("zstd")]
+    Zstd,
+}
+
+impl BodyCompression {
+    /// Compress a body before writing it
+    pub fn compress(self, body: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Zstd => zstd::encode_all(body, ZSTD_LEVEL)
+                .context("Error compressing body"),
+        }
+    }
+
+    /// Decompress a body that was compressed by [Self::compress]
+    pub fn decompress(self, body: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Zstd => {
+                zstd::decode_all(body).context("Error decompressing body")
+            }
+        }
+    }
+}
+
+impl FromStr for BodyCompression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(anyhow::anyhow!("Unknown body compression `{s}`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_err;
+
+    #[test]
+    fn test_round_trip() {
+        let body = br#"{"fish": "blue"}"#.repeat(100);
+        let compressed = BodyCompression::Zstd.compress(&body).unwrap();
+        assert!(compressed.len() < body.len());
+        assert_eq!(
+            BodyCompression::Zstd.decompress(&compressed).unwrap(),
+            body
+        );
+    }
+
+    #[test]
+    fn test_decompress_error() {
+        assert_err!(
+            BodyCompression::Zstd.decompress(b"not zstd"),
+            "Error decompressing body"
+        );
+    }
+}
@@ -2,10 +2,14 @@
 
 use crate::{
     collection::{ProfileId, RecipeId},
-    db::{CollectionId, ProfileFilter},
+    db::{
+        compression::BodyCompression,
+        encryption::{self, DatabaseCipher},
+        CollectionId, ProfileFilter,
+    },
     http::{
-        Exchange, ExchangeSummary, HttpMethod, HttpVersion, RequestId,
-        RequestRecord, ResponseRecord,
+        Exchange, ExchangeSummary, HttpMethod, HttpVersion, PeerCertificate,
+        RequestId, RequestRecord, ResponseBody, ResponseRecord,
     },
     util::ResultTraced,
 };
@@ -18,7 +22,9 @@ use reqwest::{
     StatusCode,
 };
 use rusqlite::{
-    types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef},
+    types::{
+        FromSql, FromSqlError, FromSqlResult, ToSqlOutput, Type, ValueRef,
+    },
     Row, ToSql,
 };
 use serde::{de::DeserializeOwned, Serialize};
@@ -98,6 +104,18 @@ impl FromSql for HttpVersion {
     }
 }
 
+impl ToSql for BodyCompression {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        self.to_string().to_sql()
+    }
+}
+
+impl FromSql for BodyCompression {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        String::column_result(value)?.parse().map_err(error_other)
+    }
+}
+
 impl ToSql for HttpMethod {
     fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
         self.to_str().to_sql()
@@ -212,39 +230,75 @@ impl<T: DeserializeOwned> FromSql for JsonEncoded<T> {
     }
 }
 
-/// Convert from `SELECT * FROM requests_v2`
-impl<'a, 'b> TryFrom<&'a Row<'b>> for Exchange {
-    type Error = rusqlite::Error;
-
-    fn try_from(row: &'a Row<'b>) -> Result<Self, Self::Error> {
-        let id: RequestId = row.get("id")?;
-        Ok(Self {
+/// Convert from `SELECT * FROM requests_v2`. Bodies will be decrypted if
+/// they're encrypted, which requires a cipher, then decompressed if they're
+/// compressed.
+pub fn exchange_from_row(
+    row: &Row<'_>,
+    cipher: Option<&DatabaseCipher>,
+) -> rusqlite::Result<Exchange> {
+    let encrypted: bool = row.get("body_encrypted")?;
+    let compression: Option<BodyCompression> = row.get("body_compression")?;
+    // Load a body blob, decrypting and decompressing if necessary
+    let get_body = |column: &str| -> rusqlite::Result<Option<Bytes>> {
+        let Some(blob) = row.get::<_, Option<Vec<u8>>>(column)? else {
+            return Ok(None);
+        };
+        let blob = if encrypted {
+            encryption::decrypt(cipher, &blob)
+        } else {
+            Ok(blob)
+        };
+        let blob = blob
+            .and_then(|blob| match compression {
+                Some(compression) => compression.decompress(&blob),
+                None => Ok(blob),
+            })
+            .map_err(|error| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    row.as_ref().column_index(column).unwrap_or_default(),
+                    Type::Blob,
+                    error.into(),
+                )
+            })?;
+        Ok(Some(blob.into()))
+    };
+
+    let id: RequestId = row.get("id")?;
+    Ok(Exchange {
+        id,
+        start_time: row.get("start_time")?,
+        end_time: row.get("end_time")?,
+        request: Arc::new(RequestRecord {
             id,
-            start_time: row.get("start_time")?,
-            end_time: row.get("end_time")?,
-            request: Arc::new(RequestRecord {
-                id,
-                profile_id: row.get("profile_id")?,
-                recipe_id: row.get("recipe_id")?,
-                http_version: row.get("http_version")?,
-                method: row.get("method")?,
-                // Use wrappers for all of these to specify the conversion
-                url: row.get::<_, SqlWrap<_>>("url")?.0,
-                headers: row.get::<_, SqlWrap<HeaderMap>>("request_headers")?.0,
-                body: row
-                    .get::<_, Option<SqlWrap<Bytes>>>("request_body")?
-                    .map(|wrap| wrap.0),
-            }),
-            response: Arc::new(ResponseRecord {
-                id,
-                status: row.get::<_, SqlWrap<StatusCode>>("status_code")?.0,
-                headers: row
-                    .get::<_, SqlWrap<HeaderMap>>("response_headers")?
-                    .0,
-                body: row.get::<_, SqlWrap<Bytes>>("response_body")?.0.into(),
-            }),
-        })
-    }
+            profile_id: row.get("profile_id")?,
+            recipe_id: row.get("recipe_id")?,
+            http_version: row.get("http_version")?,
+            method: row.get("method")?,
+            // Use wrappers for all of these to specify the conversion
+            url: row.get::<_, SqlWrap<_>>("url")?.0,
+            headers: row.get::<_, SqlWrap<HeaderMap>>("request_headers")?.0,
+            body: get_body("request_body")?,
+            correlation_id: row.get("correlation_id")?,
+        }),
+        response: Arc::new(ResponseRecord {
+            id,
+            status: row.get::<_, SqlWrap<StatusCode>>("status_code")?.0,
+            http_version: row.get("response_http_version")?,
+            headers: row.get::<_, SqlWrap<HeaderMap>>("response_headers")?.0,
+            body: {
+                let body = get_body("response_body")?.unwrap_or_default();
+                match row.get("response_body_full_size")? {
+                    Some(full_size) => ResponseBody::truncated(body, full_size),
+                    None => body.into(),
+                }
+            },
+            peer_certificate: row
+                .get::<_, Option<Vec<u8>>>("response_peer_certificate")?
+                .map(|der| PeerCertificate::new(der.into())),
+        }),
+        cached: false,
+    })
 }
 
 /// Convert from `SELECT ... FROM requests_v2`
@@ -272,14 +326,6 @@ impl FromSql for SqlWrap<Url> {
     }
 }
 
-impl FromSql for SqlWrap<Bytes> {
-    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
-        // Clone is necessary because the bytes live in sqlite FFI land
-        let bytes = value.as_blob()?.to_owned();
-        Ok(Self(bytes.into()))
-    }
-}
-
 impl FromSql for SqlWrap<StatusCode> {
     fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
         let code: u16 = value.as_i64()?.try_into().map_err(error_other)?;
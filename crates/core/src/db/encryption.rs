@@ -0,0 +1,211 @@
+//! At-rest encryption for sensitive database values (request/response
+//! bodies). Each row in `requests_v2` records whether its bodies are encrypted
+//! in the `body_encrypted` column. This allows databases to contain a mix of
+//! both, e.g. if encryption was enabled after some requests were stored.
+
+use anyhow::{anyhow, bail, Context};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    env,
+    fmt::{self, Debug},
+};
+
+/// Environment variable to load the encryption key from
+pub const KEY_ENV_VAR: &str = "SLUMBER_DB_KEY";
+/// Service name for the encryption key in the OS keyring
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "slumber";
+/// User name for the encryption key in the OS keyring
+#[cfg(feature = "keyring")]
+const KEYRING_USER: &str = "database";
+
+const NONCE_LENGTH: usize = 24;
+
+/// Configuration for at-rest database encryption
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseEncryptionConfig {
+    /// Encrypt bodies before storing them? Existing encrypted data can be
+    /// read regardless of this setting, as long as a key is available.
+    pub enabled: bool,
+    /// Where to load the encryption key from
+    pub key_source: KeySource,
+}
+
+/// Where to load the database encryption key from
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// `$SLUMBER_DB_KEY`
+    #[default]
+    Env,
+    /// The OS keyring/keychain, under service `slumber` and user `database`.
+    /// Requires the `keyring` cargo feature
+    Keyring,
+}
+
+impl DatabaseEncryptionConfig {
+    /// Load the encryption key and build a cipher from it. Return `None` if
+    /// encryption is disabled.
+    pub fn cipher(&self) -> anyhow::Result<Option<DatabaseCipher>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let key = match self.key_source {
+            KeySource::Env => env::var(KEY_ENV_VAR).with_context(|| {
+                format!(
+                    "Database encryption is enabled, but `{KEY_ENV_VAR}` \
+                    is not set"
+                )
+            })?,
+            #[cfg(feature = "keyring")]
+            KeySource::Keyring => {
+                keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+                    .and_then(|entry| entry.get_password())
+                    .with_context(|| {
+                        format!(
+                            "Error loading database encryption key from OS \
+                            keyring (service `{KEYRING_SERVICE}`, user \
+                            `{KEYRING_USER}`)"
+                        )
+                    })?
+            }
+            #[cfg(not(feature = "keyring"))]
+            KeySource::Keyring => bail!(
+                "Database encryption key source `keyring` is not available; \
+                Slumber was built without the `keyring` feature"
+            ),
+        };
+        if key.is_empty() {
+            bail!("Database encryption key is empty");
+        }
+        Ok(Some(DatabaseCipher::new(&key)))
+    }
+}
+
+/// Encrypts/decrypts database values with a single key
+#[derive(Clone)]
+pub struct DatabaseCipher {
+    cipher: XChaCha20Poly1305,
+}
+
+impl DatabaseCipher {
+    /// Create a new cipher. The user-provided key is hashed to get a key of
+    /// the correct length. This is *not* a password-stretching KDF, so the key
+    /// should be high-entropy.
+    pub fn new(key: &str) -> Self {
+        let key = Sha256::digest(key.as_bytes());
+        Self {
+            cipher: XChaCha20Poly1305::new(&key),
+        }
+    }
+
+    /// Encrypt a value. The output includes the nonce, so it can be passed
+    /// directly back to [Self::decrypt]
+    pub fn encrypt(&self, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("Error encrypting value"))?;
+        let mut output = Vec::with_capacity(NONCE_LENGTH + ciphertext.len());
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(output)
+    }
+
+    /// Decrypt a value that was encrypted by [Self::encrypt]
+    fn decrypt(&self, encrypted: &[u8]) -> anyhow::Result<Vec<u8>> {
+        if encrypted.len() < NONCE_LENGTH {
+            bail!("Encrypted value is truncated");
+        }
+        let (nonce, ciphertext) = encrypted.split_at(NONCE_LENGTH);
+        self.cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                anyhow!(
+                    "Error decrypting value; is the encryption key correct?"
+                )
+            })
+    }
+}
+
+/// Decrypt a value from a row that was flagged as encrypted. Return an error
+/// if no cipher is available.
+pub fn decrypt(
+    cipher: Option<&DatabaseCipher>,
+    encrypted: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let cipher = cipher.ok_or_else(|| {
+        anyhow!("Value is encrypted, but database encryption is not enabled")
+    })?;
+    cipher.decrypt(encrypted)
+}
+
+impl Debug for DatabaseCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Don't leak the key
+        f.debug_struct("DatabaseCipher").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_err;
+    use env_lock::lock_env;
+    use rstest::rstest;
+
+    #[test]
+    fn test_round_trip() {
+        let cipher = DatabaseCipher::new("hunter2");
+        let encrypted = cipher.encrypt(b"secret token").unwrap();
+        assert!(!encrypted
+            .windows(b"secret".len())
+            .any(|window| window == b"secret"));
+        assert_eq!(
+            decrypt(Some(&cipher), &encrypted).unwrap(),
+            b"secret token"
+        );
+    }
+
+    #[test]
+    fn test_decrypt_error() {
+        let encrypted = DatabaseCipher::new("hunter2").encrypt(b"a").unwrap();
+        assert_err!(
+            decrypt(None, &encrypted),
+            "database encryption is not enabled"
+        );
+        assert_err!(
+            decrypt(Some(&DatabaseCipher::new("wrong")), &encrypted),
+            "is the encryption key correct?"
+        );
+    }
+
+    #[rstest]
+    #[case::disabled(false, Some("hunter2"), Ok(false))]
+    #[case::enabled(true, Some("hunter2"), Ok(true))]
+    #[case::missing(true, None, Err("`SLUMBER_DB_KEY` is not set"))]
+    #[case::empty(true, Some(""), Err("Database encryption key is empty"))]
+    fn test_cipher_from_env(
+        #[case] enabled: bool,
+        #[case] env_value: Option<&str>,
+        #[case] expected: Result<bool, &str>,
+    ) {
+        let _guard = lock_env([(KEY_ENV_VAR, env_value)]);
+        let config = DatabaseEncryptionConfig {
+            enabled,
+            key_source: KeySource::Env,
+        };
+        let result = config.cipher().map(|cipher| cipher.is_some());
+        match expected {
+            Ok(expected) => assert_eq!(result.unwrap(), expected),
+            Err(message) => assert_err!(result, message),
+        }
+    }
+}
@@ -98,6 +98,43 @@ pub fn migrations() -> Migrations<'static> {
             http_version TEXT NOT NULL DEFAULT 'HTTP/1.1'",
         )
         .down("ALTER TABLE requests_v2 DROP COLUMN http_version"),
+        M::up(
+            // Before this, the request and response versions were always the
+            // same, because we never asked for anything other than HTTP/1.1
+            "ALTER TABLE requests_v2 ADD COLUMN \
+            response_http_version TEXT NOT NULL DEFAULT 'HTTP/1.1'",
+        )
+        .down("ALTER TABLE requests_v2 DROP COLUMN response_http_version"),
+        // Only set for truncated bodies
+        M::up(
+            "ALTER TABLE requests_v2 ADD COLUMN \
+            response_body_full_size INTEGER",
+        )
+        .down("ALTER TABLE requests_v2 DROP COLUMN response_body_full_size"),
+        // DER-encoded leaf certificate. Only set for TLS connections
+        M::up(
+            "ALTER TABLE requests_v2 ADD COLUMN \
+            response_peer_certificate BLOB",
+        )
+        .down("ALTER TABLE requests_v2 DROP COLUMN response_peer_certificate"),
+        // Algorithm used to compress the request/response bodies. NULL means
+        // they're uncompressed, which is the case for all older rows
+        M::up("ALTER TABLE requests_v2 ADD COLUMN body_compression TEXT")
+            .down("ALTER TABLE requests_v2 DROP COLUMN body_compression"),
+        // Value of the configured request ID header. Only set if enabled
+        M::up("ALTER TABLE requests_v2 ADD COLUMN correlation_id TEXT")
+            .down("ALTER TABLE requests_v2 DROP COLUMN correlation_id"),
+        // Were the request/response bodies encrypted before being stored?
+        // Older rows are all plaintext
+        M::up(
+            "ALTER TABLE requests_v2 ADD COLUMN \
+            body_encrypted INTEGER NOT NULL DEFAULT 0",
+        )
+        .down("ALTER TABLE requests_v2 DROP COLUMN body_encrypted"),
+        // History queries and `history_max_rows` eviction all sort by start
+        // time, which is a full table scan without this
+        M::up("CREATE INDEX requests_v2_start_time ON requests_v2(start_time)")
+            .down("DROP INDEX requests_v2_start_time"),
     ])
 }
 
@@ -0,0 +1,26 @@
+//! Automatic cleanup of old request history
+
+use crate::collection::serde_duration;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Limits on how much request history is kept for each collection. Exchanges
+/// outside these limits are deleted when the TUI starts. Each limit is
+/// optional, and if neither is set, history is kept forever.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryRetention {
+    /// Delete exchanges that started longer ago than this
+    #[serde(with = "serde_duration::option")]
+    pub max_age: Option<Duration>,
+    /// Keep at most this many exchanges per recipe, deleting the oldest ones
+    /// first. The limit applies across all profiles.
+    pub max_per_recipe: Option<u32>,
+}
+
+impl HistoryRetention {
+    /// Are any limits enabled?
+    pub fn is_enabled(&self) -> bool {
+        self.max_age.is_some() || self.max_per_recipe.is_some()
+    }
+}
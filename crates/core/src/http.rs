@@ -36,36 +36,60 @@
 //! ```
 
 pub mod content_type;
+mod credential_helper;
 mod models;
+mod prepared;
 pub mod query;
+mod queue;
+mod request_log;
+pub mod xml;
 
 pub use models::*;
+pub use prepared::{PreparedBody, PreparedHeader, PreparedRequest};
+pub use queue::QueueSlot;
+pub use request_log::{RequestLog, RequestLogConfig, RequestLogFormat};
 
 use crate::{
-    collection::{Authentication, Recipe, RecipeBody},
+    collection::{
+        serde_duration, Authentication, JsonTemplate, Recipe, RecipeBody,
+    },
     db::CollectionDatabase,
-    http::content_type::ContentType,
-    template::{Template, TemplateContext},
+    http::{
+        content_type::ContentType, credential_helper::CredentialHelpers,
+        queue::RequestQueues,
+    },
+    template::{command_env, with_command_env, Template, TemplateContext},
     util::ResultTraced,
 };
-use anyhow::Context;
-use bytes::Bytes;
+use anyhow::{anyhow, bail, Context};
+use bytes::{Bytes, BytesMut};
 use chrono::Utc;
 use futures::{
-    future::{self, try_join_all, OptionFuture},
-    try_join, Future,
+    future::{self, try_join_all, BoxFuture, OptionFuture},
+    try_join, Future, FutureExt,
 };
+use indexmap::IndexMap;
 use mime::Mime;
 use reqwest::{
     header::{self, HeaderMap, HeaderName, HeaderValue},
     multipart::{Form, Part},
+    tls::TlsInfo,
     Client, RequestBuilder, Response, Url,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{io::AsyncWriteExt, process::Command, sync::Semaphore, task};
 use tracing::{info, info_span};
 
 const USER_AGENT: &str = concat!("slumber/", env!("CARGO_PKG_VERSION"));
+/// Replacement for sensitive values (e.g. authentication headers) in output
+/// that's printed or written to disk
+pub const SENSITIVE_PLACEHOLDER: &str = "<sensitive>";
 
 /// Utility for handling all HTTP operations. The main purpose of this is to
 /// de-asyncify HTTP so it can be called in the main TUI thread. All heavy
@@ -80,33 +104,143 @@ pub struct HttpEngine {
     /// for. If the user didn't specify any (99.9% of cases), don't bother
     /// creating a client because it's expensive.
     danger_client: Option<(Client, HashSet<String>)>,
+    /// HTTP version policy for the default clients
+    http_version: HttpVersionPolicy,
+    /// Maximum time to wait for a connection to be established, for all
+    /// clients
+    connect_timeout: Option<Duration>,
+    /// Clients for recipes that override the HTTP version. reqwest sets the
+    /// version at client build time, so these are built lazily the first time
+    /// each version is needed. Keyed by version and whether TLS errors are
+    /// ignored. Shared between all clones of the engine
+    version_clients: Arc<Mutex<HashMap<(HttpVersionPolicy, bool), Client>>>,
     large_body_size: usize,
+    max_response_body_size: Option<usize>,
+    json_body_format: JsonBodyFormat,
+    /// Header to inject a correlation ID into, if enabled
+    request_id_header: Option<HeaderName>,
+    /// Append-only log of completed exchanges, if enabled
+    request_log: Option<RequestLog>,
+    /// Limits how many requests can be in flight at once. Shared between all
+    /// clones of the engine
+    http_semaphore: Arc<Semaphore>,
+    /// Named queues for recipes that must run serially. Shared between all
+    /// clones of the engine
+    queues: RequestQueues,
+    /// Runs credential helpers for authentication, and caches their output.
+    /// Shared between all clones of the engine
+    credential_helpers: CredentialHelpers,
+    /// Hosts that triggered requests aren't allowed to reach. See
+    /// [HttpEngineConfig::protected_hosts]
+    protected_hosts: Vec<String>,
 }
 
 impl HttpEngine {
     /// Build a new HTTP engine, which can be used for the entire program life
     pub fn new(config: &HttpEngineConfig) -> Self {
-        let client = Client::builder()
-            .user_agent(USER_AGENT)
-            .build()
-            .expect("Error building reqwest client");
+        let client =
+            build_client(config.http_version, config.connect_timeout, false);
         let danger_client = if config.ignore_certificate_hosts.is_empty() {
             None
         } else {
             Some((
-                Client::builder()
-                    .user_agent(USER_AGENT)
-                    .danger_accept_invalid_certs(true)
-                    .build()
-                    .expect("Error building reqwest client"),
+                build_client(config.http_version, config.connect_timeout, true),
                 config.ignore_certificate_hosts.iter().cloned().collect(),
             ))
         };
+        // If the log can't be opened, don't kill the app over it
+        let request_log = config
+            .request_log
+            .as_ref()
+            .and_then(|config| RequestLog::open(config).traced().ok());
+        // Same for an invalid header name. The request will be sent without it
+        let request_id_header =
+            config.request_id_header.as_deref().and_then(|header| {
+                HeaderName::try_from(header)
+                    .context(format!("Invalid request ID header `{header}`"))
+                    .traced()
+                    .ok()
+            });
         Self {
             client,
             danger_client,
+            http_version: config.http_version,
+            connect_timeout: config.connect_timeout,
+            version_clients: Default::default(),
             large_body_size: config.large_body_size,
+            max_response_body_size: config.max_response_body_size,
+            json_body_format: config.json_body_format,
+            request_id_header,
+            request_log,
+            // A limit of 0 would block everything forever
+            http_semaphore: Arc::new(Semaphore::new(
+                config.max_concurrent_requests.max(1),
+            )),
+            queues: RequestQueues::default(),
+            credential_helpers: CredentialHelpers::default(),
+            protected_hosts: config.protected_hosts.clone(),
+        }
+    }
+
+    /// Allow triggered requests to be sent to protected hosts. The user should
+    /// opt into this explicitly, e.g. with a CLI flag
+    pub fn allow_protected_hosts(mut self, allow: bool) -> Self {
+        if allow {
+            self.protected_hosts.clear();
         }
+        self
+    }
+
+    /// Does the URL point to a protected host? This is checked before sending
+    /// a triggered request, because there's no one around to confirm it. See
+    /// [HttpEngineConfig::is_protected]
+    pub fn is_protected(&self, url: &Url) -> bool {
+        is_protected_host(&self.protected_hosts, url)
+    }
+
+    /// Take a position in a recipe's request queue (see
+    /// [Recipe::queue](crate::collection::Recipe::queue)). This should be
+    /// called at submission time, *before* building the request, and the
+    /// returned slot should be waited on before building. Hold the slot until
+    /// the request is complete. If the recipe isn't in a queue, the slot is a
+    /// no-op.
+    pub fn enqueue(&self, queue: Option<&str>) -> QueueSlot {
+        queue
+            .map(|queue| self.queues.enqueue(queue))
+            .unwrap_or_default()
+    }
+
+    /// Create a ticket for a request that was built ahead of time and saved
+    /// (see [PreparedRequest]). No templates are rendered; the request is
+    /// sent exactly as it was prepared, but with a new request ID.
+    pub fn load_prepared(
+        &self,
+        prepared: PreparedRequest,
+    ) -> anyhow::Result<RequestTicket> {
+        let client = self.get_client(&prepared.url).0.clone();
+        let request = prepared.to_request(&client)?;
+        let seed =
+            RequestSeed::new(prepared.recipe_id, BuildOptions::default());
+        Ok(RequestTicket {
+            record: RequestRecord::new(
+                seed,
+                prepared.profile_id,
+                &request,
+                self.large_body_size,
+                self.request_id_header.as_ref(),
+            )
+            .into(),
+            client,
+            request,
+            persist: true,
+            max_response_body_size: self.max_response_body_size,
+            progress: Default::default(),
+            request_log: self.request_log.clone(),
+            http_semaphore: Arc::clone(&self.http_semaphore),
+            cache: None,
+            request_id_header: self.request_id_header.clone(),
+            connect_timeout: self.connect_timeout,
+        })
     }
 
     /// Build a [RequestTicket] from a [RequestSeed]. This will render the
@@ -131,48 +265,87 @@ impl HttpEngine {
                 .recipes
                 .try_get_recipe(recipe_id)?;
 
-            // Render everything up front so we can parallelize it
-            let (url, query, headers, authentication, body) = try_join!(
-                recipe.render_url(template_context),
-                recipe.render_query(options, template_context),
-                recipe.render_headers(options, template_context),
-                recipe.render_authentication(options, template_context),
-                recipe.render_body(options, template_context),
-            )?;
-
-            // Build the reqwest request first, so we can have it do all the
-            // hard work of encoding query params/authorization/etc.
-            // We'll just copy its homework at the end to get our
-            // RequestRecord
-            let client = self.get_client(&url);
-            let mut builder =
-                client.request(recipe.method.into(), url).query(&query);
-            if let Some(body) = body {
-                builder = body.apply(builder);
-            }
-            // Set headers *after* body so the use can override the Content-Type
-            // header that was set if they want to
-            builder = builder.headers(headers);
-            if let Some(authentication) = authentication {
-                builder = authentication.apply(builder);
-            }
+            // Commands spawned while rendering get the recipe's environment
+            let env = recipe.render_env(template_context).await?;
+            with_command_env(env, async {
+                // The body variant has to be selected first, because it
+                // determines the implicit Content-Type header
+                let body =
+                    recipe.resolve_body(options, template_context).await?;
+
+                // Render everything up front so we can parallelize it
+                let (url, query, headers, authentication, body) = try_join!(
+                    recipe.render_url(options, template_context),
+                    recipe.render_query(options, template_context),
+                    recipe.render_headers(options, body, template_context),
+                    recipe.render_authentication(
+                        options,
+                        &self.credential_helpers,
+                        template_context
+                    ),
+                    recipe.render_body(
+                        options,
+                        body,
+                        self.json_body_format,
+                        template_context
+                    ),
+                )?;
+
+                // Build the reqwest request first, so we can have it do all
+                // the hard work of encoding query params/authorization/etc.
+                // We'll just copy its homework at the end to get our
+                // RequestRecord
+                let client =
+                    self.get_versioned_client(&url, recipe.http_version);
+                let method = options.method.unwrap_or(recipe.method);
+                let mut builder =
+                    client.request(method.into(), url).query(&query);
+                if let Some(body) = body {
+                    builder = body.apply(builder);
+                }
+                // Set headers *after* body so the use can override the
+                // Content-Type header that was set if they want to
+                builder = builder.headers(headers);
+                if let Some(authentication) = authentication {
+                    builder = authentication.apply(builder);
+                }
 
-            let request = builder.build()?;
-            Ok((client, request))
+                let request = builder.build()?;
+                Ok((client, request, recipe.persist, recipe.cache))
+            })
+            .await
         };
-        let (client, request) =
+        let (client, mut request, persist, cache) =
             seed.convert_error(future, template_context).await?;
 
+        // Inject a correlation ID, unless the recipe already set one. The
+        // request ID is a fresh UUID, so we can reuse it for this
+        if let Some(header) = &self.request_id_header {
+            request.headers_mut().entry(header).or_insert_with(|| {
+                HeaderValue::try_from(seed.id.to_string())
+                    .expect("UUID is a valid header value")
+            });
+        }
+
         Ok(RequestTicket {
             record: RequestRecord::new(
                 seed,
                 template_context.selected_profile.clone(),
                 &request,
                 self.large_body_size,
+                self.request_id_header.as_ref(),
             )
             .into(),
-            client: client.clone(),
+            client,
             request,
+            persist,
+            max_response_body_size: self.max_response_body_size,
+            progress: Default::default(),
+            request_log: self.request_log.clone(),
+            http_semaphore: Arc::clone(&self.http_semaphore),
+            cache,
+            request_id_header: self.request_id_header.clone(),
+            connect_timeout: self.connect_timeout,
         })
     }
 
@@ -197,19 +370,27 @@ impl HttpEngine {
                 .recipes
                 .try_get_recipe(recipe_id)?;
 
-            // Parallelization!
-            let (url, query) = try_join!(
-                recipe.render_url(template_context),
-                recipe.render_query(options, template_context),
-            )?;
-
-            // Use RequestBuilder so we can offload the handling of query params
-            let client = self.get_client(&url);
-            let request = client
-                .request(recipe.method.into(), url)
-                .query(&query)
-                .build()?;
-            Ok(request)
+            let env = recipe.render_env(template_context).await?;
+            with_command_env(env, async {
+                // Parallelization!
+                let (url, query) = try_join!(
+                    recipe.render_url(options, template_context),
+                    recipe.render_query(options, template_context),
+                )?;
+
+                // Use RequestBuilder so we can offload the handling of query
+                // params
+                let (client, _) = self.get_client(&url);
+                let request = client
+                    .request(
+                        options.method.unwrap_or(recipe.method).into(),
+                        url,
+                    )
+                    .query(&query)
+                    .build()?;
+                Ok(request)
+            })
+            .await
         };
         let request = seed.convert_error(future, template_context).await?;
 
@@ -237,51 +418,140 @@ impl HttpEngine {
                 .recipes
                 .try_get_recipe(recipe_id)?;
 
-            let Some(body) =
-                recipe.render_body(options, template_context).await?
-            else {
-                return Ok(None);
-            };
+            let env = recipe.render_env(template_context).await?;
+            with_command_env(env, async {
+                let body =
+                    recipe.resolve_body(options, template_context).await?;
+                let Some(body) = recipe
+                    .render_body(
+                        options,
+                        body,
+                        self.json_body_format,
+                        template_context,
+                    )
+                    .await?
+                else {
+                    return Ok(None);
+                };
 
-            match body {
-                // If we have the bytes, we don't need to bother building a
-                // request
-                RenderedBody::Raw(bytes) => Ok(Some(bytes)),
-                // The body is complex - offload the hard work to RequestBuilder
-                RenderedBody::FormUrlencoded(_)
-                | RenderedBody::FormMultipart(_) => {
-                    let url = Url::parse("http://localhost").unwrap();
-                    let client = self.get_client(&url);
-                    let mut builder = client.request(reqwest::Method::GET, url);
-                    builder = body.apply(builder);
-                    let request = builder.build()?;
-                    // We just added a body so we know it's present, and we
-                    // know it's not a stream. This requires a clone which sucks
-                    // because the bytes are going to get thrown away anyway,
-                    // but nothing we can do about that because of reqwest's API
-                    let bytes = request
-                        .body()
-                        .expect("Body should be present")
-                        .as_bytes()
-                        .expect("Body should be raw bytes")
-                        .to_owned()
-                        .into();
-                    Ok(Some(bytes))
+                match body {
+                    // If we have the bytes, we don't need to bother building a
+                    // request
+                    RenderedBody::Raw(bytes) => Ok(Some(bytes)),
+                    // The body is complex - offload the hard work to
+                    // RequestBuilder
+                    RenderedBody::FormUrlencoded(_)
+                    | RenderedBody::FormMultipart(_) => {
+                        let url = Url::parse("http://localhost").unwrap();
+                        let (client, _) = self.get_client(&url);
+                        let mut builder =
+                            client.request(reqwest::Method::GET, url);
+                        builder = body.apply(builder);
+                        let request = builder.build()?;
+                        // We just added a body so we know it's present, and
+                        // we know it's not a stream. This requires a clone
+                        // which sucks because the bytes are going to get
+                        // thrown away anyway, but nothing we can do about that
+                        // because of reqwest's API
+                        let bytes = request
+                            .body()
+                            .expect("Body should be present")
+                            .as_bytes()
+                            .expect("Body should be raw bytes")
+                            .to_owned()
+                            .into();
+                        Ok(Some(bytes))
+                    }
                 }
-            }
+            })
+            .await
         };
         seed.convert_error(future, template_context).await
     }
 
     /// Get the appropriate client to use for this request. If the request URL's
     /// host is one for which the user wants to ignore TLS certs, use the
-    /// dangerous client.
-    fn get_client(&self, url: &Url) -> &Client {
+    /// dangerous client. Also return whether the dangerous client was chosen.
+    fn get_client(&self, url: &Url) -> (&Client, bool) {
         let host = url.host_str().unwrap_or_default();
         match &self.danger_client {
-            Some((client, hostnames)) if hostnames.contains(host) => client,
-            _ => &self.client,
+            Some((client, hostnames)) if hostnames.contains(host) => {
+                (client, true)
+            }
+            _ => (&self.client, false),
+        }
+    }
+
+    /// Get the client to send a request with, accounting for the recipe's
+    /// HTTP version override. If the effective version matches the engine's
+    /// default, this is the same as [Self::get_client]. Otherwise, a client is
+    /// built for that version on first use and cached.
+    fn get_versioned_client(
+        &self,
+        url: &Url,
+        http_version: Option<HttpVersionPolicy>,
+    ) -> Client {
+        let http_version = http_version
+            .unwrap_or(self.http_version)
+            .for_scheme(url.scheme());
+        let (default, danger) = self.get_client(url);
+        if http_version == self.http_version {
+            return default.clone();
+        }
+
+        self.version_clients
+            .lock()
+            .expect("Client cache lock poisoned")
+            .entry((http_version, danger))
+            .or_insert_with(|| {
+                build_client(http_version, self.connect_timeout, danger)
+            })
+            .clone()
+    }
+}
+
+/// Build a reqwest client. If `danger` is enabled, TLS certificate errors are
+/// ignored
+fn build_client(
+    http_version: HttpVersionPolicy,
+    connect_timeout: Option<Duration>,
+    danger: bool,
+) -> Client {
+    let mut builder = Client::builder()
+        .user_agent(USER_AGENT)
+        .danger_accept_invalid_certs(danger)
+        // Expose the server's certificate on the response, for display
+        .tls_info(true);
+    if let Some(connect_timeout) = connect_timeout {
+        builder = builder.connect_timeout(connect_timeout);
+    }
+    let builder = match http_version {
+        HttpVersionPolicy::Auto => builder,
+        HttpVersionPolicy::Http1 => builder.http1_only(),
+        // Over TLS, prior knowledge restricts ALPN negotiation to HTTP/2.
+        // Over plaintext, it sends HTTP/2 without an upgrade (h2c). Plain
+        // `http2` is mapped to `auto` for plaintext URLs before we get here
+        HttpVersionPolicy::Http2 | HttpVersionPolicy::Http2PriorKnowledge => {
+            builder.http2_prior_knowledge()
+        }
+    };
+    builder.build().expect("Error building reqwest client")
+}
+
+/// Convert an error from sending a request. reqwest's timeout errors don't say
+/// *which* timeout tripped, so add that for connection timeouts
+fn send_error(
+    error: reqwest::Error,
+    connect_timeout: Option<Duration>,
+) -> anyhow::Error {
+    match connect_timeout {
+        Some(timeout) if error.is_connect() && error.is_timeout() => {
+            anyhow::Error::from(error).context(format!(
+                "Could not connect to server within {timeout:?} \
+                (`connect_timeout`)"
+            ))
         }
+        _ => error.into(),
     }
 }
 
@@ -299,6 +569,31 @@ pub struct HttpEngineConfig {
     /// Request/response bodies over this size are treated differently, for
     /// performance reasons
     pub large_body_size: usize,
+    /// Append each completed exchange to this file, if enabled
+    pub request_log: Option<RequestLogConfig>,
+    /// Maximum number of requests that can be in flight at once. Additional
+    /// requests will wait until a slot frees up
+    pub max_concurrent_requests: usize,
+    /// HTTP version(s) to use. Can be overridden per-recipe
+    pub http_version: HttpVersionPolicy,
+    /// Response bodies larger than this are truncated when loaded, to protect
+    /// against running out of memory. `None` for no limit
+    pub max_response_body_size: Option<usize>,
+    /// Serialize `!json` bodies compactly or pretty
+    pub json_body_format: JsonBodyFormat,
+    /// Inject a unique ID into this header of every request, for correlation
+    /// with server logs. Recipes that set the header themselves are left alone
+    pub request_id_header: Option<String>,
+    /// Requests to these hosts (e.g. production) must be confirmed in the TUI,
+    /// and are blocked in the CLI unless explicitly allowed. A leading `*.`
+    /// matches any subdomain
+    pub protected_hosts: Vec<String>,
+    /// Give up on a request if a connection to the server can't be
+    /// established within this long. This only covers connecting, so slow
+    /// responses from a reachable server aren't affected. `None` to wait
+    /// indefinitely
+    #[serde(with = "serde_duration::option")]
+    pub connect_timeout: Option<Duration>,
 }
 
 impl HttpEngineConfig {
@@ -308,6 +603,13 @@ impl HttpEngineConfig {
     pub fn is_large(&self, size: usize) -> bool {
         size > self.large_body_size
     }
+
+    /// Does the URL point to one of the configured protected hosts? Hostnames
+    /// are compared case-insensitively. A pattern `*.example.com` matches
+    /// subdomains of `example.com`, but not `example.com` itself.
+    pub fn is_protected(&self, url: &Url) -> bool {
+        is_protected_host(&self.protected_hosts, url)
+    }
 }
 
 impl Default for HttpEngineConfig {
@@ -315,10 +617,35 @@ impl Default for HttpEngineConfig {
         Self {
             ignore_certificate_hosts: Default::default(),
             large_body_size: 1000 * 1000, // 1MB
+            request_log: None,
+            max_concurrent_requests: 8,
+            http_version: HttpVersionPolicy::default(),
+            max_response_body_size: None,
+            json_body_format: JsonBodyFormat::default(),
+            request_id_header: None,
+            protected_hosts: Vec::new(),
+            connect_timeout: None,
         }
     }
 }
 
+/// Does the URL's host match any of the given protected host patterns?
+fn is_protected_host(patterns: &[String], url: &Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.to_ascii_lowercase();
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.to_ascii_lowercase();
+        match pattern.strip_prefix("*.") {
+            Some(domain) => host
+                .strip_suffix(domain)
+                .is_some_and(|subdomain| subdomain.ends_with('.')),
+            None => host == pattern,
+        }
+    })
+}
+
 impl RequestSeed {
     /// Run the given future and convert any error into [RequestBuildError]
     async fn convert_error<T>(
@@ -351,19 +678,58 @@ impl RequestTicket {
     pub async fn send(
         self,
         database: &CollectionDatabase,
+    ) -> Result<Exchange, RequestError> {
+        self.send_inner(Some(database)).await
+    }
+
+    /// Launch an HTTP request without a collection database. Nothing is read
+    /// from or written to history, so this can be used for requests that were
+    /// prepared ahead of time, which may be sent without their collection.
+    pub async fn send_detached(self) -> Result<Exchange, RequestError> {
+        self.send_inner(None).await
+    }
+
+    async fn send_inner(
+        self,
+        database: Option<&CollectionDatabase>,
     ) -> Result<Exchange, RequestError> {
         let id = self.record.id;
 
         // Capture the rest of this method in a span
         let _ = info_span!("HTTP request", request_id = %id).entered();
 
+        if let Some(exchange) =
+            database.and_then(|database| self.get_cached(database))
+        {
+            return Ok(exchange);
+        }
+
+        // Wait for a slot to open up before starting the timer. The semaphore
+        // is never closed, so this can't fail
+        let _permit = self
+            .http_semaphore
+            .acquire()
+            .await
+            .expect("HTTP semaphore closed");
+
         // This start time will be accurate because the request doesn't launch
         // until this whole future is awaited
         let start_time = Utc::now();
+        let connect_timeout = self.connect_timeout;
         let result = async {
-            let response = self.client.execute(self.request).await?;
+            let response = self
+                .client
+                .execute(self.request)
+                .await
+                .map_err(|error| send_error(error, connect_timeout))?;
             // Load the full response and convert it to our format
-            ResponseRecord::from_response(id, response).await
+            ResponseRecord::from_response(
+                id,
+                response,
+                &self.progress,
+                self.max_response_body_size,
+            )
+            .await
         }
         .await;
         let end_time = Utc::now();
@@ -377,11 +743,36 @@ impl RequestTicket {
                     response: response.into(),
                     start_time,
                     end_time,
+                    cached: false,
                 };
 
-                if database.can_write() {
+                // Recipe can opt out of history, e.g. for sensitive data
+                if let Some(database) = database
+                    .filter(|database| self.persist && database.can_write())
+                {
+                    // Insert on a blocking thread, so we can wait out a locked
+                    // database without stalling the caller's thread (which
+                    // may be the TUI's main thread)
+                    let database = database.with_lock_retry(true);
+                    let inserted = exchange.clone();
                     // Error here should *not* kill the request
-                    let _ = database.insert_exchange(&exchange);
+                    let _ = task::spawn_blocking(move || {
+                        database.insert_exchange(&inserted)
+                    })
+                    .await;
+                }
+                // Same goes for the request log
+                if let Some(request_log) =
+                    self.request_log.filter(|_| self.persist)
+                {
+                    // File I/O blocks, so write on a blocking thread. Wait
+                    // for it, so the entry isn't lost if the process exits
+                    // right after this request
+                    let logged = exchange.clone();
+                    let _ = task::spawn_blocking(move || {
+                        request_log.log(&logged);
+                    })
+                    .await;
                 }
                 Ok(exchange)
             }
@@ -397,42 +788,188 @@ impl RequestTicket {
             .traced(),
         }
     }
+
+    /// Get the most recent response for an identical request from history, if
+    /// caching is enabled for the recipe and that response is still fresh.
+    /// The returned exchange takes on this ticket's ID and request, so it can
+    /// be tracked like any other response.
+    fn get_cached(&self, database: &CollectionDatabase) -> Option<Exchange> {
+        let ttl = self.cache?;
+        // Large bodies aren't recorded, so we can't tell if the request is
+        // the same
+        if self.request.body().is_some() && self.record.body.is_none() {
+            return None;
+        }
+        let exchange = database
+            .get_latest_request(
+                self.record.profile_id.as_ref().into(),
+                &self.record.recipe_id,
+            )
+            .ok()??;
+        if exchange.end_time + ttl < Utc::now()
+            || !exchange
+                .request
+                .is_equivalent(&self.record, self.request_id_header.as_ref())
+        {
+            return None;
+        }
+
+        info!(cached_request_id = %exchange.id, "Using cached response");
+        Some(Exchange {
+            id: self.record.id,
+            request: Arc::clone(&self.record),
+            response: exchange.response,
+            start_time: exchange.start_time,
+            end_time: exchange.end_time,
+            cached: true,
+        })
+    }
 }
 
 impl ResponseRecord {
     /// Convert [reqwest::Response] type into [ResponseRecord]. This is async
     /// because the response content is not necessarily loaded when we first get
     /// the response. Only fails if the response content fails to load.
+    ///
+    /// If the body is larger than `max_body_size`, only that many bytes are
+    /// kept in memory. The rest of the body is still read so we can record its
+    /// true size, and spooled to a temp file so the full body can be saved
+    /// without sending the request again.
     async fn from_response(
         id: RequestId,
-        response: Response,
+        mut response: Response,
+        progress: &DownloadProgress,
+        max_body_size: Option<usize>,
     ) -> reqwest::Result<ResponseRecord> {
         // Copy response metadata out first, because we need to move the
         // response to resolve content (not sure why...)
         let status = response.status();
+        let http_version = response.version().into();
         let headers = response.headers().clone();
-
-        // Pre-resolve the content, so we get all the async work done
-        let body = response.bytes().await?.into();
+        let peer_certificate = response
+            .extensions()
+            .get::<TlsInfo>()
+            .and_then(TlsInfo::peer_certificate)
+            .map(|der| PeerCertificate::new(Bytes::copy_from_slice(der)));
+
+        // Pre-resolve the content, so we get all the async work done. Read it
+        // chunk-by-chunk so we can report progress as we go
+        let content_length = response
+            .content_length()
+            .and_then(|len| len.try_into().ok());
+        if let Some(content_length) = content_length {
+            progress.set_total(content_length);
+        }
+        let max_body_size = max_body_size.unwrap_or(usize::MAX);
+        let mut body = BytesMut::with_capacity(
+            content_length.unwrap_or(0).min(max_body_size),
+        );
+        let mut full_size = 0;
+        let mut spool = OverflowSpool::default();
+        while let Some(chunk) = response.chunk().await? {
+            progress.add_received(chunk.len());
+            full_size += chunk.len();
+            let remaining = max_body_size.saturating_sub(body.len());
+            let kept = chunk.len().min(remaining);
+            body.extend_from_slice(&chunk[..kept]);
+            if kept < chunk.len() {
+                spool.write(&chunk[kept..]).await;
+            }
+        }
+        let body = if full_size > body.len() {
+            info!(full_size, kept = body.len(), "Truncated response body");
+            ResponseBody::truncated(body.freeze(), full_size)
+                .with_overflow(spool.finish().await)
+        } else {
+            body.freeze().into()
+        };
 
         Ok(ResponseRecord {
             id,
             status,
+            http_version,
             headers,
             body,
+            peer_certificate,
         })
     }
 }
 
+/// Writes the truncated portion of a response body to a temp file as it's
+/// received. The file is only created once the limit is exceeded. Spooling is
+/// best-effort: if the file can't be written, the error is logged and the
+/// full body just won't be available. It shouldn't fail the request.
+#[derive(Default)]
+enum OverflowSpool {
+    #[default]
+    Empty,
+    Open(BodyOverflow, tokio::fs::File),
+    Failed,
+}
+
+impl OverflowSpool {
+    async fn write(&mut self, data: &[u8]) {
+        if let Self::Empty = self {
+            *self = match BodyOverflow::create().await.traced() {
+                Ok((overflow, file)) => Self::Open(overflow, file),
+                Err(_) => Self::Failed,
+            };
+        }
+        if let Self::Open(_, file) = self {
+            if file
+                .write_all(data)
+                .await
+                .context("Error spooling response body")
+                .traced()
+                .is_err()
+            {
+                *self = Self::Failed;
+            }
+        }
+    }
+
+    /// Flush the spool file and return it, if spooling succeeded
+    async fn finish(self) -> Option<BodyOverflow> {
+        match self {
+            Self::Open(overflow, mut file) => file
+                .flush()
+                .await
+                .context("Error spooling response body")
+                .traced()
+                .ok()
+                .map(|()| overflow),
+            Self::Empty | Self::Failed => None,
+        }
+    }
+}
+
 /// Render steps for individual pieces of a recipe
 impl Recipe {
+    /// Render environment variables for commands spawned during the build
+    async fn render_env(
+        &self,
+        template_context: &TemplateContext,
+    ) -> anyhow::Result<IndexMap<String, String>> {
+        let iter = self.env.iter().map(|(name, template)| async move {
+            let value = template
+                .render_string(template_context)
+                .await
+                .context(format!("Error rendering env variable `{name}`"))?;
+            Ok::<_, anyhow::Error>((name.clone(), value))
+        });
+        Ok(future::try_join_all(iter).await?.into_iter().collect())
+    }
+
     /// Render base URL, *excluding* query params
     async fn render_url(
         &self,
+        options: &BuildOptions,
         template_context: &TemplateContext,
     ) -> anyhow::Result<Url> {
-        let url = self
+        let url = options
             .url
+            .as_ref()
+            .unwrap_or(&self.url)
             .render_string(template_context)
             .await
             .context("Error rendering URL")?;
@@ -463,20 +1000,20 @@ impl Recipe {
         future::try_join_all(iter).await
     }
 
-    /// Render all headers specified by the user. This will *not* include
-    /// authentication and other implicit headers
+    /// Render all headers specified by the user, including those generated
+    /// from recipe shorthands (body type and `accept`). This will *not*
+    /// include authentication and other implicit headers
     async fn render_headers(
         &self,
         options: &BuildOptions,
+        body: Option<&RecipeBody>,
         template_context: &TemplateContext,
     ) -> anyhow::Result<HeaderMap> {
         let mut headers = HeaderMap::new();
 
         // Set Content-Type based on the body type. This can be overwritten
         // below if the user explicitly passed a Content-Type value
-        if let Some(content_type) =
-            self.body.as_ref().and_then(|body| body.explicit_mime())
-        {
+        if let Some(content_type) = body.and_then(RecipeBody::explicit_mime) {
             headers.insert(
                 header::CONTENT_TYPE,
                 content_type
@@ -487,6 +1024,14 @@ impl Recipe {
             );
         }
 
+        // Same for Accept, from the shorthand
+        if let Some(accept) = self.accept {
+            headers.insert(
+                header::ACCEPT,
+                HeaderValue::from_static(accept.to_str()),
+            );
+        }
+
         // Render headers in an iterator so we can parallelize
         let iter = self.headers.iter().enumerate().filter_map(
             move |(i, (header, value_template))| {
@@ -501,12 +1046,15 @@ impl Recipe {
         );
 
         let rendered = future::try_join_all(iter).await?;
-        headers.reserve(rendered.len());
-        // Do *not* use headers.extend(), because that will append to existing
-        // headers, and we want to overwrite instead
+        // Group the recipe's headers into their own map first, so repeated
+        // headers are all kept. Extending from a HeaderMap then *replaces* any
+        // existing values for those headers (e.g. the implicit Content-Type),
+        // rather than appending to them
+        let mut recipe_headers = HeaderMap::with_capacity(rendered.len());
         for (header, value) in rendered {
-            headers.insert(header, value);
+            recipe_headers.append(header, value);
         }
+        headers.extend(recipe_headers);
 
         Ok(headers)
     }
@@ -542,13 +1090,14 @@ impl Recipe {
         ))
     }
 
-    /// Render authentication and return the same data structure, with resolved
-    /// data. This can be passed to [reqwest::RequestBuilder]
+    /// Render authentication into resolved credentials, which can be applied
+    /// to a [reqwest::RequestBuilder]. Credential helpers are run here.
     async fn render_authentication(
         &self,
         options: &BuildOptions,
+        credential_helpers: &CredentialHelpers,
         template_context: &TemplateContext,
-    ) -> anyhow::Result<Option<Authentication<String>>> {
+    ) -> anyhow::Result<Option<RenderedAuthentication>> {
         let authentication = options
             .authentication
             .as_ref()
@@ -571,7 +1120,7 @@ impl Recipe {
                         .context("Error rendering password")
                     },
                 )?;
-                Ok(Some(Authentication::Basic { username, password }))
+                Ok(Some(RenderedAuthentication::Basic { username, password }))
             }
 
             Some(Authentication::Bearer(token)) => {
@@ -579,19 +1128,78 @@ impl Recipe {
                     .render_string(template_context)
                     .await
                     .context("Error rendering bearer token")?;
-                Ok(Some(Authentication::Bearer(token)))
+                Ok(Some(RenderedAuthentication::Bearer(token)))
+            }
+            Some(Authentication::Helper { command }) => {
+                let command = try_join_all(command.iter().enumerate().map(
+                    |(i, template)| async move {
+                        template.render_string(template_context).await.context(
+                            format!(
+                                "Error rendering credential helper \
+                                command[{i}]"
+                            ),
+                        )
+                    },
+                ))
+                .await?;
+                let (header, value) = credential_helpers.get(command).await?;
+                Ok(Some(RenderedAuthentication::Header(header, value)))
             }
             None => Ok(None),
         }
     }
 
-    /// Render request body
+    /// Get the body to use for a request, accounting for overrides and
+    /// omission. If the body has multiple variants, select one, either from
+    /// the build options or by rendering the recipe's selector. The returned
+    /// body is never [RecipeBody::Variants].
+    async fn resolve_body<'a>(
+        &'a self,
+        options: &'a BuildOptions,
+        template_context: &TemplateContext,
+    ) -> anyhow::Result<Option<&'a RecipeBody>> {
+        if options.omit_body {
+            return Ok(None);
+        }
+        let body = options.body.as_ref().or(self.body.as_ref());
+        let Some(RecipeBody::Variants { select, variants }) = body else {
+            return Ok(body);
+        };
+
+        let name = match (&options.body_variant, select) {
+            (Some(name), _) => name.clone(),
+            (None, Some(select)) => select
+                .render_string(template_context)
+                .await
+                .context("Error rendering body variant selector")?
+                .trim()
+                .to_owned(),
+            // Deserialization ensures the map isn't empty
+            (None, None) => variants.keys().next().cloned().unwrap_or_default(),
+        };
+        let variant = variants.get(&name).ok_or_else(|| {
+            anyhow!(
+                "Unknown body variant `{name}`; must be one of: {}",
+                variants
+                    .keys()
+                    .map(|name| format!("`{name}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+        Ok(Some(variant))
+    }
+
+    /// Render request body. The body should already be resolved with
+    /// [Self::resolve_body]
     async fn render_body(
         &self,
         options: &BuildOptions,
+        body: Option<&RecipeBody>,
+        json_format: JsonBodyFormat,
         template_context: &TemplateContext,
     ) -> anyhow::Result<Option<RenderedBody>> {
-        let Some(body) = options.body.as_ref().or(self.body.as_ref()) else {
+        let Some(body) = body else {
             return Ok(None);
         };
 
@@ -602,6 +1210,25 @@ impl Recipe {
                     .context("Error rendering body")?
                     .into(),
             ),
+            RecipeBody::Json(json) => {
+                let value = json
+                    .render(template_context)
+                    .await
+                    .context("Error rendering body")?;
+                let body = match json_format {
+                    JsonBodyFormat::Compact => value.to_string(),
+                    JsonBodyFormat::Pretty => format!("{value:#}"),
+                };
+                RenderedBody::Raw(body.into())
+            }
+            RecipeBody::Xml(body) => {
+                let body = body
+                    .render(template_context)
+                    .await
+                    .context("Error rendering body")?;
+                xml::validate(&body).context("Body is not valid XML")?;
+                RenderedBody::Raw(body.into())
+            }
             RecipeBody::FormUrlencoded(fields) => {
                 let iter = fields.iter().enumerate().filter_map(
                     |(i, (field, value_template))| {
@@ -640,25 +1267,98 @@ impl Recipe {
                 let rendered = try_join_all(iter).await?;
                 RenderedBody::FormMultipart(rendered)
             }
+            RecipeBody::Command { command, .. } => RenderedBody::Raw(
+                run_body_command(command, template_context).await?,
+            ),
+            RecipeBody::Variants { .. } => {
+                bail!("Body variants cannot be nested")
+            }
         };
         Ok(Some(rendered))
     }
 }
 
-impl Authentication<String> {
+/// Render a body command's arguments, run it, and return its stdout. A
+/// non-zero exit code is an error, with stderr attached so the user can see
+/// what went wrong.
+///
+/// Output is buffered rather than streamed into the request with
+/// `Body::wrap_stream`, for a few reasons:
+/// - We can't know the command failed until it exits. By then a streamed
+///   request would already be in flight with a truncated body
+/// - [RequestRecord] holds the body bytes, for history, display, and
+///   `--dry-run`. A streamed body would be missing from all of those
+/// - reqwest can't replay a streamed body, so 307/308 redirects wouldn't be
+///   followed
+async fn run_body_command(
+    command: &[Template],
+    template_context: &TemplateContext,
+) -> anyhow::Result<Bytes> {
+    let command = try_join_all(command.iter().enumerate().map(
+        |(i, template)| async move {
+            template
+                .render_string(template_context)
+                .await
+                .context(format!("Error rendering body command[{i}]"))
+        },
+    ))
+    .await?;
+    let [program, args @ ..] = command.as_slice() else {
+        bail!("Body command is empty");
+    };
+
+    let _ = info_span!("Executing body command", ?command).entered();
+    // wait_with_output() reads stdout and stderr concurrently, so a large
+    // output can't deadlock the process on a full pipe
+    let output = Command::new(program)
+        .args(args)
+        .envs(command_env().iter())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Error executing body command {command:?}"))?
+        .wait_with_output()
+        .await
+        .with_context(|| format!("Error executing body command {command:?}"))?;
+
+    if !output.status.success() {
+        bail!(
+            "Body command {command:?} failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout.into())
+}
+
+/// Authentication with all templates rendered and credential helpers run
+enum RenderedAuthentication {
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    Bearer(String),
+    /// A header provided by a credential helper
+    Header(HeaderName, HeaderValue),
+}
+
+impl RenderedAuthentication {
     fn apply(self, builder: RequestBuilder) -> RequestBuilder {
         match self {
-            Authentication::Basic { username, password } => {
+            Self::Basic { username, password } => {
                 builder.basic_auth(username, password)
             }
-            Authentication::Bearer(token) => builder.bearer_auth(token),
+            Self::Bearer(token) => builder.bearer_auth(token),
+            Self::Header(header, value) => builder.header(header, value),
         }
     }
 }
 
 impl RecipeBody {
     /// Get the value that we should set for the `Content-Type` header,
-    /// according to the body. This will only return `Some` for JSON, as the
+    /// according to the body. This will only return `Some` for JSON/XML, as the
     /// form content types will have this header set automatically by reqwest
     /// via the builder methods we use.
     fn explicit_mime(&self) -> Option<Mime> {
@@ -666,12 +1366,59 @@ impl RecipeBody {
             RecipeBody::Raw { content_type, .. } => {
                 content_type.as_ref().map(ContentType::to_mime)
             }
+            RecipeBody::Json(_) => Some(mime::APPLICATION_JSON),
+            RecipeBody::Xml(_) => Some(xml::mime()),
+            RecipeBody::Command { content_type, .. } => content_type.clone(),
             // Do *not* set anything for these, because reqwest will do that
-            // automatically and we don't want to interfere
-            RecipeBody::FormUrlencoded(_) | RecipeBody::FormMultipart(_) => {
-                None
-            }
+            // automatically and we don't want to interfere. Variants should
+            // be resolved before this is called.
+            RecipeBody::FormUrlencoded(_)
+            | RecipeBody::FormMultipart(_)
+            | RecipeBody::Variants { .. } => None,
+        }
+    }
+}
+
+impl JsonTemplate {
+    /// Render each string in the value as a template, producing a JSON value.
+    /// Rendered strings must be valid UTF-8. Strings are rendered
+    /// concurrently, like the chunks of a single template
+    fn render<'a>(
+        &'a self,
+        template_context: &'a TemplateContext,
+    ) -> BoxFuture<'a, anyhow::Result<serde_json::Value>> {
+        async move {
+            let value = match self {
+                Self::Null => serde_json::Value::Null,
+                Self::Bool(b) => (*b).into(),
+                Self::Number(n) => n.clone().into(),
+                Self::String(template) => {
+                    template.render_string(template_context).await?.into()
+                }
+                Self::Array(values) => try_join_all(
+                    values.iter().map(|value| value.render(template_context)),
+                )
+                .await?
+                .into(),
+                Self::Object(map) => {
+                    let values = try_join_all(map.iter().map(
+                        |(key, value)| async move {
+                            let value = value
+                                .render(template_context)
+                                .await
+                                .with_context(|| {
+                                    format!("Error rendering field `{key}`")
+                                })?;
+                            Ok::<_, anyhow::Error>((key.clone(), value))
+                        },
+                    ))
+                    .await?;
+                    serde_json::Value::Object(values.into_iter().collect())
+                }
+            };
+            Ok(value)
         }
+        .boxed()
     }
 }
 
@@ -746,19 +1493,23 @@ fn trim_bytes(bytes: &mut Vec<u8>, f: impl Fn(u8) -> bool) {
 mod tests {
     use super::*;
     use crate::{
-        collection::{Authentication, Chain, ChainSource, Collection, Profile},
+        assert_err,
+        collection::{
+            AcceptPreset, Authentication, Chain, ChainSource, Collection,
+            JsonTemplate, Profile,
+        },
         test_util::{
-            by_id, header_map, http_engine, invalid_utf8_chain, Factory,
-            TestPrompter,
+            by_id, header_map, http_engine, invalid_utf8_chain, temp_dir,
+            test_data_dir, Factory, TempDir, TestPrompter,
         },
     };
-    use indexmap::{indexmap, IndexMap};
+    use indexmap::indexmap;
     use pretty_assertions::assert_eq;
     use regex::Regex;
     use reqwest::{Body, StatusCode};
     use rstest::rstest;
     use serde_json::json;
-    use std::ptr;
+    use std::{path::PathBuf, ptr, time::Duration};
     use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
 
     /// Create a template context. Take a set of extra recipes and chains to
@@ -817,8 +1568,9 @@ mod tests {
         #[case] hostname: &str,
         #[case] expected_danger: bool,
     ) {
-        let client = http_engine
+        let (client, danger) = http_engine
             .get_client(&format!("http://{hostname}/").parse().unwrap());
+        assert_eq!(danger, expected_danger);
         if expected_danger {
             assert!(ptr::eq(
                 client,
@@ -829,6 +1581,34 @@ mod tests {
         }
     }
 
+    /// Clients for non-default HTTP versions are built once and cached.
+    /// `http2` is meaningless for plaintext, so it should use the default
+    #[rstest]
+    #[case::default(None, "https", 0)]
+    #[case::http1(Some(HttpVersionPolicy::Http1), "https", 1)]
+    #[case::http2(Some(HttpVersionPolicy::Http2), "https", 1)]
+    #[case::http2_plaintext(Some(HttpVersionPolicy::Http2), "http", 0)]
+    #[case::prior_knowledge_plaintext(
+        Some(HttpVersionPolicy::Http2PriorKnowledge),
+        "http",
+        1
+    )]
+    fn test_get_versioned_client(
+        #[case] http_version: Option<HttpVersionPolicy>,
+        #[case] scheme: &str,
+        #[case] expected_cached: usize,
+    ) {
+        // Use a fresh engine so the cache isn't shared with other tests
+        let http_engine = HttpEngine::default();
+        let url: Url = format!("{scheme}://localhost/").parse().unwrap();
+        http_engine.get_versioned_client(&url, http_version);
+        http_engine.get_versioned_client(&url, http_version);
+        assert_eq!(
+            http_engine.version_clients.lock().unwrap().len(),
+            expected_cached
+        );
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_build_request(http_engine: &HttpEngine) {
@@ -839,11 +1619,11 @@ mod tests {
                 ("mode".into(), "{{mode}}".into()),
                 ("fast".into(), "true".into()),
             ],
-            headers: indexmap! {
+            headers: vec![
                 // Leading/trailing newlines should be stripped
-                "Accept".into() => "application/json".into(),
-                "Content-Type".into() => "application/json".into(),
-            },
+                ("Accept".into(), "application/json".into()),
+                ("Content-Type".into(), "application/json".into()),
+            ],
             body: Some("{\"group_id\":\"{{group_id}}\"}".into()),
             ..Recipe::factory(())
         };
@@ -886,10 +1666,48 @@ mod tests {
                 url: expected_url,
                 body: Some(Vec::from(expected_body).into()),
                 headers: expected_headers,
+                correlation_id: None,
             }
         );
     }
 
+    /// A prepared request can be saved and loaded, and the loaded request is
+    /// identical to the original except for its ID
+    #[rstest]
+    #[tokio::test]
+    async fn test_load_prepared(http_engine: &HttpEngine) {
+        let recipe = Recipe {
+            method: HttpMethod::Post,
+            url: "{{host}}/users/{{user_id}}".into(),
+            headers: vec![("Accept".into(), "application/json".into())],
+            body: Some("{\"group_id\":\"{{group_id}}\"}".into()),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+
+        let prepared = PreparedRequest::new(&ticket).unwrap();
+        let json = serde_json::to_string(&prepared).unwrap();
+        let loaded: PreparedRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, prepared);
+
+        let loaded = http_engine.load_prepared(loaded).unwrap();
+        let (original, loaded_record) = (&ticket.record, &loaded.record);
+        assert_ne!(loaded_record.id, original.id);
+        assert_eq!(loaded_record.profile_id, original.profile_id);
+        assert_eq!(loaded_record.recipe_id, original.recipe_id);
+        assert_eq!(loaded_record.method, original.method);
+        assert_eq!(loaded_record.url, original.url);
+        assert_eq!(loaded_record.headers, original.headers);
+        assert_eq!(loaded_record.body, original.body);
+        assert_eq!(
+            loaded.request.body().and_then(Body::as_bytes),
+            ticket.request.body().and_then(Body::as_bytes),
+        );
+    }
+
     /// Test building just a URL. Should include query params, but headers/body
     /// should *not* be built
     #[rstest]
@@ -920,6 +1738,43 @@ mod tests {
         );
     }
 
+    /// A URL override replaces the recipe's URL, but everything else
+    /// (including query params) still comes from the recipe
+    #[rstest]
+    #[tokio::test]
+    async fn test_override_url(http_engine: &HttpEngine) {
+        let recipe = Recipe {
+            url: "{{host}}/users/{{user_id}}".into(),
+            query: vec![("mode".into(), "{{mode}}".into())],
+            headers: vec![("accept".into(), "application/json".into())],
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+        let options = BuildOptions {
+            url: Some("http://mock:8000/users/{{user_id}}".into()),
+            ..Default::default()
+        };
+
+        let seed = RequestSeed::new(recipe_id.clone(), options.clone());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+        assert_eq!(
+            ticket.record.url.as_str(),
+            "http://mock:8000/users/1?mode=sudo"
+        );
+        assert_eq!(
+            ticket.record.headers.get("accept").unwrap(),
+            "application/json"
+        );
+
+        let seed = RequestSeed::new(recipe_id, options);
+        let url = http_engine
+            .build_url(seed, &template_context)
+            .await
+            .unwrap();
+        assert_eq!(url.as_str(), "http://mock:8000/users/1?mode=sudo");
+    }
+
     /// Test building just a body. URL/query/headers should *not* be built.
     #[rstest]
     #[case::raw(
@@ -974,47 +1829,127 @@ mod tests {
         assert_eq!(body.as_deref(), Some(expected_body));
     }
 
-    /// Test building requests with various authentication methods
+    /// Binary content loaded with `file()` is passed through byte-for-byte,
+    /// even when mixed with text
     #[rstest]
-    #[case::basic(
-        Authentication::Basic {
-            username: "{{username}}".into(),
-            password: Some("{{password}}".into()),
-        },
-        "Basic dXNlcjpodW50ZXIy"
-    )]
-    #[case::basic_no_password(
-        Authentication::Basic {
-            username: "{{username}}".into(),
-            password: None,
-        },
-        "Basic dXNlcjo="
-    )]
-    #[case::bearer(Authentication::Bearer("{{token}}".into()), "Bearer tokenzzz")]
     #[tokio::test]
-    async fn test_authentication(
+    async fn test_build_binary_file_body(
         http_engine: &HttpEngine,
-        #[case] authentication: Authentication,
-        #[case] expected_header: &str,
+        test_data_dir: PathBuf,
     ) {
-        let recipe = Recipe {
-            // `Authorization` header should appear twice. This probably isn't
-            // something a user would ever want to do, but it should be
-            // well-defined
-            headers: indexmap! {"Authorization".into() => "bogus".into()},
-            authentication: Some(authentication),
-            ..Recipe::factory(())
-        };
-        let recipe_id = recipe.id.clone();
-        let template_context = template_context([recipe], []);
-
-        let seed = RequestSeed::new(recipe_id.clone(), BuildOptions::default());
+        let path = test_data_dir.join("invalid_utf8.bin");
+        let body = format!("data:{{{{file('{}')}}}}", path.display());
+        let template_context = template_context(
+            [Recipe {
+                body: Some(RecipeBody::Raw {
+                    body: body.into(),
+                    content_type: None,
+                }),
+                ..Recipe::factory(())
+            }],
+            [],
+        );
+        let seed = RequestSeed::new(
+            template_context.collection.first_recipe_id().clone(),
+            BuildOptions::default(),
+        );
         let ticket = http_engine.build(seed, &template_context).await.unwrap();
 
+        let expected: &[u8] = b"data:\xc3\x28";
         assert_eq!(
-            *ticket.record,
-            RequestRecord {
-                id: ticket.record.id,
+            ticket.request.body().and_then(Body::as_bytes),
+            Some(expected)
+        );
+        assert_eq!(ticket.record.body(), Some(expected));
+    }
+
+    /// `!json` bodies are compact by default, but can be configured to be
+    /// pretty
+    #[rstest]
+    #[case::compact(JsonBodyFormat::Compact, br#"{"group_id":"3","n":3}"#)]
+    #[case::pretty(
+        JsonBodyFormat::Pretty,
+        b"{\n  \"group_id\": \"3\",\n  \"n\": 3\n}"
+    )]
+    #[tokio::test]
+    async fn test_build_body_json_format(
+        #[case] json_body_format: JsonBodyFormat,
+        #[case] expected_body: &[u8],
+    ) {
+        let http_engine = HttpEngine::new(&HttpEngineConfig {
+            json_body_format,
+            ..Default::default()
+        });
+        let template_context = template_context(
+            [Recipe {
+                body: Some(RecipeBody::Json(JsonTemplate::Object(indexmap! {
+                    "group_id".into() =>
+                        JsonTemplate::String("{{group_id}}".into()),
+                    "n".into() => JsonTemplate::Number(3.into()),
+                }))),
+                ..Recipe::factory(())
+            }],
+            [],
+        );
+        let seed = RequestSeed::new(
+            template_context.collection.first_recipe_id().clone(),
+            BuildOptions::default(),
+        );
+        let body = http_engine
+            .build_body(seed, &template_context)
+            .await
+            .unwrap();
+
+        assert_eq!(body.as_deref(), Some(expected_body));
+    }
+
+    /// Test building requests with various authentication methods
+    #[rstest]
+    #[case::basic(
+        Authentication::Basic {
+            username: "{{username}}".into(),
+            password: Some("{{password}}".into()),
+        },
+        "Basic dXNlcjpodW50ZXIy"
+    )]
+    #[case::basic_no_password(
+        Authentication::Basic {
+            username: "{{username}}".into(),
+            password: None,
+        },
+        "Basic dXNlcjo="
+    )]
+    #[case::bearer(Authentication::Bearer("{{token}}".into()), "Bearer tokenzzz")]
+    #[case::helper(
+        Authentication::Helper {
+            command: vec!["echo".into(), "{{token}}".into()],
+        },
+        "Bearer tokenzzz"
+    )]
+    #[tokio::test]
+    async fn test_authentication(
+        http_engine: &HttpEngine,
+        #[case] authentication: Authentication,
+        #[case] expected_header: &str,
+    ) {
+        let recipe = Recipe {
+            // `Authorization` header should appear twice. This probably isn't
+            // something a user would ever want to do, but it should be
+            // well-defined
+            headers: vec![("Authorization".into(), "bogus".into())],
+            authentication: Some(authentication),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let seed = RequestSeed::new(recipe_id.clone(), BuildOptions::default());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+
+        assert_eq!(
+            *ticket.record,
+            RequestRecord {
+                id: ticket.record.id,
                 profile_id: Some(
                     template_context.collection.first_profile_id().clone()
                 ),
@@ -1027,6 +1962,7 @@ mod tests {
                     ("authorization", expected_header)
                 ]),
                 body: None,
+                correlation_id: None,
             }
         );
     }
@@ -1047,6 +1983,26 @@ mod tests {
         "^application/json$",
         &[],
     )]
+    // Each string is rendered individually, then escaped. Other types are
+    // preserved
+    #[case::json_structured(
+        RecipeBody::Json(JsonTemplate::Object(indexmap! {
+            "group_id".into() => JsonTemplate::String("{{group_id}}".into()),
+            "count".into() => JsonTemplate::Number(3.into()),
+            "tags".into() => JsonTemplate::Array(vec![
+                JsonTemplate::String(r#"say "{{user_id}}""#.into()),
+                JsonTemplate::Bool(true),
+                JsonTemplate::Null,
+            ]),
+        })),
+        None,
+        Some(
+            br#"{"group_id":"3","count":3,"tags":["say \"1\"",true,null]}"#
+                .as_slice()
+        ),
+        "^application/json$",
+        &[],
+    )]
     // Content-Type has been overridden by an explicit header
     #[case::json_content_type_override(
         RecipeBody::Raw {
@@ -1058,6 +2014,19 @@ mod tests {
         "^text/plain$",
         &[],
     )]
+    // Rendered as-is; pretty-printing is only for display
+    #[case::xml(
+        RecipeBody::Xml(
+            r#"<g:group xmlns:g="urn:g"><g:id>{{group_id}}</g:id></g:group>"#
+                .into()
+        ),
+        None,
+        Some(
+            br#"<g:group xmlns:g="urn:g"><g:id>3</g:id></g:group>"#.as_slice()
+        ),
+        "^application/xml$",
+        &[],
+    )]
     #[case::form_urlencoded(
         RecipeBody::FormUrlencoded(indexmap! {
             "user_id".into() => "{{user_id}}".into(),
@@ -1090,6 +2059,16 @@ mod tests {
         "^multipart/form-data; boundary=[a-f0-9-]{67}$",
         &[("content-length", "321")],
     )]
+    #[case::command(
+        RecipeBody::Command {
+            command: vec!["echo".into(), "-n".into(), "{{user_id}}".into()],
+            content_type: Some("application/x-protobuf".parse().unwrap()),
+        },
+        None,
+        Some(b"1".as_slice()),
+        "^application/x-protobuf$",
+        &[],
+    )]
     #[tokio::test]
     async fn test_structured_body(
         http_engine: &HttpEngine,
@@ -1102,9 +2081,9 @@ mod tests {
         #[case] extra_headers: &[(&str, &str)],
     ) {
         let headers = if let Some(content_type) = content_type {
-            indexmap! {"content-type".into() => content_type.into()}
+            vec![("content-type".into(), content_type.into())]
         } else {
-            IndexMap::default()
+            Vec::new()
         };
         let recipe = Recipe {
             headers,
@@ -1167,6 +2146,111 @@ mod tests {
         );
     }
 
+    /// A body command that fails should fail the build, and include stderr
+    #[rstest]
+    #[tokio::test]
+    async fn test_command_body_error(http_engine: &HttpEngine) {
+        let recipe = Recipe {
+            body: Some(RecipeBody::Command {
+                command: vec![
+                    "sh".into(),
+                    "-c".into(),
+                    "echo 'bad input' >&2; exit 1".into(),
+                ],
+                content_type: None,
+            }),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        assert_err!(
+            http_engine.build(seed, &template_context).await,
+            "exit status: 1: bad input"
+        );
+    }
+
+    /// An XML body that isn't well-formed after rendering should fail the build
+    #[rstest]
+    #[case::malformed("<user>{{user_id}}</usr>", "Body is not valid XML")]
+    #[case::unbound_prefix(
+        "<s:user>{{user_id}}</s:user>",
+        "undeclared namespace prefix `s`"
+    )]
+    #[tokio::test]
+    async fn test_xml_body_error(
+        http_engine: &HttpEngine,
+        #[case] body: &str,
+        #[case] expected_error: &str,
+    ) {
+        let recipe = Recipe {
+            body: Some(RecipeBody::Xml(body.into())),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        assert_err!(
+            http_engine.build(seed, &template_context).await,
+            expected_error
+        );
+    }
+
+    /// Recipe env variables are passed to command chains and command bodies,
+    /// without modifying the process environment. Chain results aren't shared
+    /// between recipes in the same render group with different environments
+    #[rstest]
+    #[tokio::test]
+    async fn test_env(http_engine: &HttpEngine) {
+        let print_env: Vec<Template> = vec![
+            "sh".into(),
+            "-c".into(),
+            "printf %s \"$SLUMBER_FISH\"".into(),
+        ];
+        let recipe = Recipe {
+            headers: vec![("X-Fish".into(), "{{chains.fish}}".into())],
+            body: Some(RecipeBody::Command {
+                command: print_env.clone(),
+                content_type: None,
+            }),
+            env: indexmap! {"SLUMBER_FISH".into() => "{{mode}}-fish".into()},
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let other_recipe = Recipe {
+            id: "other".into(),
+            headers: vec![("X-Fish".into(), "{{chains.fish}}".into())],
+            env: indexmap! {"SLUMBER_FISH".into() => "red-fish".into()},
+            ..Recipe::factory(())
+        };
+        let chain = Chain {
+            id: "fish".into(),
+            source: ChainSource::Command {
+                command: print_env,
+                stdin: None,
+            },
+            ..Chain::factory(())
+        };
+        let template_context =
+            template_context([recipe, other_recipe], [chain]);
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+        assert_eq!(ticket.request.headers()["X-Fish"], "sudo-fish");
+        assert_eq!(
+            ticket.request.body().and_then(Body::as_bytes),
+            Some(b"sudo-fish".as_slice())
+        );
+
+        // Same context, so the chain cache is shared
+        let seed = RequestSeed::new("other".into(), BuildOptions::default());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+        assert_eq!(ticket.request.headers()["X-Fish"], "red-fish");
+        assert!(std::env::var("SLUMBER_FISH").is_err());
+    }
+
     /// Test disabling and overriding authentication, query params, headers, and
     /// bodies
     #[rstest]
@@ -1177,14 +2261,14 @@ mod tests {
                 username: "username".into(),
                 password: None,
             }),
-            headers: indexmap! {
+            headers: vec![
                 // Included
-                "Accept".into() => "application/json".into(),
+                ("Accept".into(), "application/json".into()),
                 // Overidden
-                "Big-Guy".into() => "style1".into(),
+                ("Big-Guy".into(), "style1".into()),
                 // Excluded
-                "content-type".into() => "text/plain".into(),
-            },
+                ("content-type".into(), "text/plain".into()),
+            ],
             query: vec![
                 // Overridden
                 ("mode".into(), "regular".into()),
@@ -1226,6 +2310,10 @@ mod tests {
                 // Form field override has to be in a different test, because
                 // we're using a raw body
                 form_fields: Default::default(),
+                omit_body: false,
+                body_variant: None,
+                method: Some(HttpMethod::Put),
+                url: None,
             },
         );
         let ticket = http_engine.build(seed, &template_context).await.unwrap();
@@ -1236,7 +2324,7 @@ mod tests {
                 id: ticket.record.id,
                 profile_id: template_context.selected_profile.clone(),
                 recipe_id,
-                method: HttpMethod::Get,
+                method: HttpMethod::Put,
                 http_version: HttpVersion::Http11,
                 url: "http://localhost/url?mode=sudo&fast=true"
                     .parse()
@@ -1250,10 +2338,125 @@ mod tests {
                     ("content-type", "application/json"),
                 ]),
                 body: Some(b"hunter2".as_slice().into()),
+                correlation_id: None,
             }
         );
     }
 
+    /// Omitting the body should also omit the implicit Content-Type header
+    #[rstest]
+    #[tokio::test]
+    async fn test_build_options_omit_body(http_engine: &HttpEngine) {
+        let recipe = Recipe {
+            body: Some(RecipeBody::Raw {
+                body: "{{username}}".into(),
+                content_type: Some(ContentType::Json),
+            }),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let seed = RequestSeed::new(
+            recipe_id,
+            BuildOptions {
+                omit_body: true,
+                ..Default::default()
+            },
+        );
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+
+        assert_eq!(ticket.record.headers, HeaderMap::new());
+        assert_eq!(ticket.record.body, None);
+    }
+
+    /// A body variant can be selected by the recipe's selector template, by
+    /// build options, or by default (first variant)
+    #[rstest]
+    #[case::selector(
+        Some("{{mode}}"),
+        None,
+        "application/json",
+        r#"{"user": "1"}"#
+    )]
+    #[case::build_options(
+        Some("{{mode}}"),
+        Some("form"),
+        "application/x-www-form-urlencoded",
+        "user=1"
+    )]
+    #[case::default(None, None, "application/x-www-form-urlencoded", "user=1")]
+    #[tokio::test]
+    async fn test_body_variants(
+        http_engine: &HttpEngine,
+        #[case] select: Option<&str>,
+        #[case] body_variant: Option<&str>,
+        #[case] expected_content_type: &str,
+        #[case] expected_body: &'static str,
+    ) {
+        let recipe = Recipe {
+            body: Some(RecipeBody::Variants {
+                select: select.map(Template::from),
+                variants: indexmap! {
+                    "form".into() => RecipeBody::FormUrlencoded(indexmap! {
+                        "user".into() => "{{user_id}}".into(),
+                    }),
+                    "sudo".into() => RecipeBody::Raw {
+                        body: r#"{"user": "{{user_id}}"}"#.into(),
+                        content_type: Some(ContentType::Json),
+                    },
+                },
+            }),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let seed = RequestSeed::new(
+            recipe_id,
+            BuildOptions {
+                body_variant: body_variant.map(String::from),
+                ..Default::default()
+            },
+        );
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+
+        assert_eq!(
+            ticket.record.headers,
+            header_map([("content-type", expected_content_type)])
+        );
+        assert_eq!(
+            ticket.record.body.as_deref(),
+            Some(expected_body.as_bytes())
+        );
+    }
+
+    /// Selecting a body variant that doesn't exist is an error
+    #[rstest]
+    #[tokio::test]
+    async fn test_body_variants_unknown(http_engine: &HttpEngine) {
+        let recipe = Recipe {
+            body: Some(RecipeBody::Variants {
+                select: Some("{{username}}".into()),
+                variants: indexmap! {
+                    "json".into() => RecipeBody::Raw {
+                        body: "{}".into(),
+                        content_type: Some(ContentType::Json),
+                    },
+                },
+            }),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        assert_err!(
+            http_engine.build(seed, &template_context).await,
+            "Unknown body variant `user`; must be one of: `json`"
+        );
+    }
+
     /// Test overriding form body fields. This has to be a separate test
     /// because it's incompatible with testing raw body overrides
     #[rstest]
@@ -1302,6 +2505,7 @@ mod tests {
                     "application/x-www-form-urlencoded"
                 ),]),
                 body: Some(b"user_id=1&preference=small".as_slice().into()),
+                correlation_id: None,
             }
         );
     }
@@ -1357,8 +2561,16 @@ mod tests {
         // Build+send the request
         let seed = RequestSeed::new(recipe_id, BuildOptions::default());
         let ticket = http_engine.build(seed, &template_context).await.unwrap();
+        let progress = Arc::clone(ticket.progress());
+        assert_eq!(progress.received(), 0);
+        assert_eq!(progress.total(), None);
         let exchange = ticket.send(&template_context.database).await.unwrap();
 
+        // Download progress should be tracked through the body
+        assert_eq!(progress.received(), 6);
+        assert_eq!(progress.total(), Some(6));
+        assert_eq!(progress.ratio(), Some(1.0));
+
         // Cheat on this one, because we don't know exactly when the server
         // resolved it
         let date_header = exchange
@@ -1373,16 +2585,333 @@ mod tests {
             ResponseRecord {
                 id: exchange.id,
                 status: StatusCode::OK,
+                http_version: HttpVersion::Http11,
                 headers: header_map([
                     ("content-type", "text/plain"),
                     ("content-length", "6"),
                     ("date", date_header),
                 ]),
-                body: ResponseBody::new(b"hello!".as_slice().into())
+                body: ResponseBody::new(b"hello!".as_slice().into()),
+                peer_certificate: None,
             }
         );
     }
 
+    /// Bodies over the max size should be truncated, but remember their full
+    /// size. The truncated portion is spooled to disk, so the full body can be
+    /// saved without re-sending the request
+    #[rstest]
+    #[tokio::test]
+    async fn test_send_request_truncate() {
+        let server = MockServer::start().await;
+        let host = server.uri();
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path("/get"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("hello!"))
+            .mount(&server)
+            .await;
+
+        let http_engine = HttpEngine::new(&HttpEngineConfig {
+            max_response_body_size: Some(3),
+            ..Default::default()
+        });
+        let recipe = Recipe {
+            url: format!("{host}/get").as_str().into(),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+        let exchange = ticket.send(&template_context.database).await.unwrap();
+        assert_eq!(
+            exchange.response.body,
+            ResponseBody::truncated(b"hel".as_slice().into(), 6)
+        );
+
+        // Truncation should survive a round trip through the DB, but the
+        // spooled overflow doesn't
+        let stored = template_context
+            .database
+            .get_request(exchange.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.response.body, exchange.response.body);
+        assert!(!stored.response.body.has_full_body());
+
+        let mut full_body: Vec<u8> = Vec::new();
+        let size = exchange
+            .response
+            .body
+            .write_full(&mut full_body)
+            .await
+            .unwrap();
+        assert_eq!(size, 6);
+        assert_eq!(full_body, b"hello!");
+        // The request was only sent once
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    /// A connection timeout should say which timeout tripped
+    #[rstest]
+    #[tokio::test]
+    async fn test_send_request_connect_timeout() {
+        let http_engine = HttpEngine::new(&HttpEngineConfig {
+            connect_timeout: Some(Duration::from_millis(10)),
+            ..Default::default()
+        });
+        // Non-routable address, so the connection hangs until the timeout
+        let recipe = Recipe {
+            url: "http://10.255.255.1/get".into(),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+        assert_err!(
+            ticket.send_detached().await,
+            "Could not connect to server within 10ms (`connect_timeout`)"
+        );
+    }
+
+    /// The configured request ID header should be injected with the request
+    /// ID, unless the recipe sets it explicitly
+    #[rstest]
+    #[case::injected(None)]
+    #[case::recipe(Some("abc123"))]
+    #[tokio::test]
+    async fn test_build_request_id_header(#[case] recipe_value: Option<&str>) {
+        let http_engine = HttpEngine::new(&HttpEngineConfig {
+            request_id_header: Some("X-Request-Id".into()),
+            ..Default::default()
+        });
+        let recipe = Recipe {
+            headers: recipe_value
+                .map(|value| ("x-request-id".into(), value.into()))
+                .into_iter()
+                .collect(),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        let id = seed.id;
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+
+        let expected = recipe_value
+            .map(String::from)
+            .unwrap_or_else(|| id.to_string());
+        let record = ticket.record();
+        assert_eq!(
+            record
+                .headers
+                .get("X-Request-Id")
+                .map(|value| value.as_bytes()),
+            Some(expected.as_bytes())
+        );
+        assert_eq!(record.correlation_id.as_deref(), Some(expected.as_str()));
+    }
+
+    /// Exchanges should be saved to history unless the recipe opts out
+    #[rstest]
+    #[case::persist(true)]
+    #[case::no_persist(false)]
+    #[tokio::test]
+    async fn test_send_request_persist(
+        http_engine: &HttpEngine,
+        #[case] persist: bool,
+    ) {
+        let server = MockServer::start().await;
+        let host = server.uri();
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path("/get"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let recipe = Recipe {
+            url: format!("{host}/get").as_str().into(),
+            persist,
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+        let exchange = ticket.send(&template_context.database).await.unwrap();
+
+        let stored = template_context
+            .database
+            .get_request(exchange.id)
+            .unwrap()
+            .map(|exchange| exchange.id);
+        assert_eq!(stored, persist.then_some(exchange.id));
+    }
+
+    /// Exchanges that aren't persisted shouldn't be written to the request
+    /// log either
+    #[rstest]
+    #[case::persist(true)]
+    #[case::no_persist(false)]
+    #[tokio::test]
+    async fn test_send_request_persist_log(
+        temp_dir: TempDir,
+        #[case] persist: bool,
+    ) {
+        let server = MockServer::start().await;
+        let host = server.uri();
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path("/get"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let log_path = temp_dir.join("requests.log");
+        let http_engine = HttpEngine::new(&HttpEngineConfig {
+            request_log: Some(RequestLogConfig {
+                path: log_path.clone(),
+                format: RequestLogFormat::Ndjson,
+            }),
+            ..Default::default()
+        });
+        let recipe = Recipe {
+            url: format!("{host}/get").as_str().into(),
+            persist,
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+        let exchange = ticket.send(&template_context.database).await.unwrap();
+
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        assert_eq!(log.contains(&exchange.id.to_string()), persist);
+    }
+
+    /// A recipe with caching enabled should reuse a fresh response for an
+    /// identical request, instead of hitting the server again
+    #[rstest]
+    #[case::cached(Some(Duration::from_secs(60)), true)]
+    #[case::disabled(None, false)]
+    #[tokio::test]
+    async fn test_send_request_cache(
+        http_engine: &HttpEngine,
+        #[case] cache: Option<Duration>,
+        #[case] expected_cached: bool,
+    ) {
+        let server = MockServer::start().await;
+        let host = server.uri();
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path("/get"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("hello!"))
+            .expect(if expected_cached { 1 } else { 2 })
+            .mount(&server)
+            .await;
+
+        let recipe = Recipe {
+            url: format!("{host}/get").as_str().into(),
+            cache,
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context([recipe], []);
+
+        let send = || async {
+            let seed =
+                RequestSeed::new(recipe_id.clone(), BuildOptions::default());
+            let ticket =
+                http_engine.build(seed, &template_context).await.unwrap();
+            ticket.send(&template_context.database).await.unwrap()
+        };
+        let first = send().await;
+        let second = send().await;
+
+        assert!(!first.cached);
+        assert_eq!(second.cached, expected_cached);
+        assert_ne!(second.id, first.id);
+        assert_eq!(second.response.body.bytes(), b"hello!".as_slice());
+    }
+
+    /// Repeated headers should all be included. A recipe header should still
+    /// replace an implicit header, even if the recipe header is repeated.
+    #[rstest]
+    #[tokio::test]
+    async fn test_render_headers_repeated() {
+        let recipe = Recipe {
+            headers: vec![
+                ("cookie".into(), "a=1".into()),
+                ("content-type".into(), "application/json".into()),
+                ("cookie".into(), "b={{user_id}}".into()),
+                ("content-type".into(), "text/plain".into()),
+            ],
+            body: Some(RecipeBody::Raw {
+                body: "{}".into(),
+                content_type: Some(ContentType::Json),
+            }),
+            ..Recipe::factory(())
+        };
+        let template_context = template_context([], []);
+        let rendered = recipe
+            .render_headers(
+                &BuildOptions::default(),
+                recipe.body.as_ref(),
+                &template_context,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            rendered,
+            header_map([
+                ("content-type", "application/json"),
+                ("content-type", "text/plain"),
+                ("cookie", "a=1"),
+                ("cookie", "b=1"),
+            ])
+        );
+    }
+
+    /// The `accept` shorthand sets the Accept header, unless the recipe has an
+    /// explicit Accept header
+    #[rstest]
+    #[case::json(AcceptPreset::Json, &[], "application/json")]
+    #[case::xml(AcceptPreset::Xml, &[], "application/xml")]
+    #[case::any(AcceptPreset::Any, &[], "*/*")]
+    #[case::explicit(AcceptPreset::Json, &["text/html"], "text/html")]
+    #[tokio::test]
+    async fn test_render_headers_accept(
+        #[case] accept: AcceptPreset,
+        #[case] explicit: &[&str],
+        #[case] expected: &str,
+    ) {
+        let recipe = Recipe {
+            accept: Some(accept),
+            headers: explicit
+                .iter()
+                .map(|value| ("Accept".into(), (*value).into()))
+                .collect(),
+            ..Recipe::factory(())
+        };
+        let template_context = template_context([], []);
+        let rendered = recipe
+            .render_headers(
+                &BuildOptions::default(),
+                recipe.body.as_ref(),
+                &template_context,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(rendered, header_map([("accept", expected)]));
+    }
+
     /// Leading/trailing newlines should be stripped from rendered header
     /// values. These characters are invalid and trigger an error, so we assume
     /// they're unintentional and the user won't miss them.
@@ -1391,15 +2920,19 @@ mod tests {
     async fn test_render_headers_strip() {
         let recipe = Recipe {
             // Leading/trailing newlines should be stripped
-            headers: indexmap! {
-                "Accept".into() => "application/json".into(),
-                "Host".into() => "\n{{host}}\n".into(),
-            },
+            headers: vec![
+                ("Accept".into(), "application/json".into()),
+                ("Host".into(), "\n{{host}}\n".into()),
+            ],
             ..Recipe::factory(())
         };
         let template_context = template_context([], []);
         let rendered = recipe
-            .render_headers(&BuildOptions::default(), &template_context)
+            .render_headers(
+                &BuildOptions::default(),
+                recipe.body.as_ref(),
+                &template_context,
+            )
             .await
             .unwrap();
 
@@ -1413,6 +2946,24 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case::exact("https://prod.fish/fishes", true)]
+    #[case::case_insensitive("https://PROD.fish", true)]
+    #[case::port("https://prod.fish:8443", true)]
+    #[case::other_host("https://dev.fish", false)]
+    #[case::subdomain_not_exact("https://api.prod.fish", false)]
+    #[case::wildcard("https://api.live.fish", true)]
+    #[case::wildcard_nested("https://a.b.live.fish", true)]
+    #[case::wildcard_bare("https://live.fish", false)]
+    #[case::wildcard_partial("https://alive.fish", false)]
+    fn test_is_protected(#[case] url: &str, #[case] expected: bool) {
+        let config = HttpEngineConfig {
+            protected_hosts: vec!["prod.fish".into(), "*.live.fish".into()],
+            ..Default::default()
+        };
+        assert_eq!(config.is_protected(&url.parse().unwrap()), expected);
+    }
+
     #[rstest]
     #[case::empty(&[], &[])]
     #[case::start(&[0, 0, 1, 1], &[1, 1])]
@@ -8,10 +8,17 @@
 use crate::util::Mapping;
 use anyhow::{anyhow, Context};
 use derive_more::{Deref, Display, From};
-use mime::{Mime, APPLICATION, JSON};
+use mime::{Mime, APPLICATION, JSON, WWW_FORM_URLENCODED};
 use reqwest::header::{self, HeaderMap, HeaderValue};
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, ffi::OsStr, fmt::Debug, path::Path};
+use serde_json::ser::PrettyFormatter;
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    fmt::{self, Debug},
+    path::Path,
+};
+use url::form_urlencoded;
 
 /// All supported content types. Each variant should have a corresponding
 /// implementation of [ResponseContent].
@@ -28,6 +35,8 @@ use std::{borrow::Cow, ffi::OsStr, fmt::Debug, path::Path};
 #[serde(rename_all = "snake_case")]
 pub enum ContentType {
     Json,
+    /// `application/x-www-form-urlencoded`
+    FormUrlencoded,
 }
 
 impl ContentType {
@@ -54,6 +63,7 @@ impl ContentType {
             (APPLICATION, JSON, _) | (APPLICATION, _, Some("json")) => {
                 Some(Self::Json)
             }
+            (APPLICATION, WWW_FORM_URLENCODED, _) => Some(Self::FormUrlencoded),
             _ => None,
         }
     }
@@ -62,6 +72,9 @@ impl ContentType {
     pub fn to_mime(&self) -> Mime {
         match self {
             ContentType::Json => mime::APPLICATION_JSON,
+            ContentType::FormUrlencoded => {
+                mime::APPLICATION_WWW_FORM_URLENCODED
+            }
         }
     }
 
@@ -95,6 +108,9 @@ impl ContentType {
     ) -> anyhow::Result<Box<dyn ResponseContent>> {
         match self {
             Self::Json => Ok(Box::new(Json::parse(content)?)),
+            Self::FormUrlencoded => {
+                Ok(Box::new(FormUrlencoded::parse(content)?))
+            }
         }
     }
 
@@ -106,12 +122,13 @@ impl ContentType {
     ) -> Box<dyn ResponseContent> {
         match self {
             Self::Json => Box::new(Json(content)),
+            Self::FormUrlencoded => Box::new(FormUrlencoded(content)),
         }
     }
 
     /// Make a response body look pretty. If the input isn't valid for this
     /// content type, return `None`
-    pub fn prettify(&self, body: &str) -> Option<String> {
+    pub fn prettify(&self, body: &str, indent: Indent) -> Option<String> {
         match self {
             ContentType::Json => {
                 // The easiest way to prettify is to parse and restringify.
@@ -120,20 +137,37 @@ impl ContentType {
                 if let Ok(parsed) =
                     serde_json::from_str::<serde_json::Value>(body)
                 {
+                    let indent = indent.to_bytes();
+                    let mut serializer = serde_json::Serializer::with_formatter(
+                        Vec::new(),
+                        PrettyFormatter::with_indent(&indent),
+                    );
                     // serde_json shouldn't fail serializing its own Value type
-                    serde_json::to_string_pretty(&parsed).ok()
+                    parsed.serialize(&mut serializer).ok()?;
+                    String::from_utf8(serializer.into_inner()).ok()
                 } else {
                     // Not valid JSON
                     None
                 }
             }
+            // Decode each field onto its own line. This is for display only,
+            // it doesn't round trip
+            ContentType::FormUrlencoded => {
+                if body.trim().is_empty() {
+                    return None;
+                }
+                let lines = form_urlencoded::parse(body.trim().as_bytes())
+                    .map(|(key, value)| format!("{key} = {value}"))
+                    .collect::<Vec<_>>();
+                Some(lines.join("\n"))
+            }
         }
     }
 
     /// Stringify a single JSON value into this format
     pub fn value_to_string(self, value: &serde_json::Value) -> String {
         match self {
-            ContentType::Json => match value {
+            ContentType::Json | ContentType::FormUrlencoded => match value {
                 serde_json::Value::Null => "".into(),
                 serde_json::Value::String(s) => s.clone(),
                 other => other.to_string(),
@@ -144,7 +178,77 @@ impl ContentType {
     /// Stringify a list of JSON values into this format
     pub fn vec_to_string(self, values: &Vec<&serde_json::Value>) -> String {
         match self {
-            ContentType::Json => serde_json::to_string(&values).unwrap(),
+            // Lists of values have no form representation, use JSON
+            ContentType::Json | ContentType::FormUrlencoded => {
+                serde_json::to_string(&values).unwrap()
+            }
+        }
+    }
+}
+
+/// Indentation to use when prettifying bodies (JSON and XML). Serialized as
+/// a number of spaces, or the string `tab`
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "IndentValue", into = "IndentValue")]
+pub enum Indent {
+    /// Indent each level with this many spaces
+    Spaces(u8),
+    /// Indent each level with a single tab character
+    Tab,
+}
+
+impl Indent {
+    /// Get the character used for indentation, and how many times it's
+    /// repeated for each level
+    pub fn unit(self) -> (u8, usize) {
+        match self {
+            Self::Spaces(count) => (b' ', count.into()),
+            Self::Tab => (b'\t', 1),
+        }
+    }
+
+    /// Get the string of bytes that makes up a single level of indentation
+    fn to_bytes(self) -> Vec<u8> {
+        let (character, count) = self.unit();
+        vec![character; count]
+    }
+}
+
+/// Match the indentation of `serde_json`'s pretty printer, which was used
+/// before indentation was configurable
+impl Default for Indent {
+    fn default() -> Self {
+        Self::Spaces(2)
+    }
+}
+
+/// Serialization form of [Indent]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum IndentValue {
+    Spaces(u8),
+    Named(String),
+}
+
+impl From<Indent> for IndentValue {
+    fn from(indent: Indent) -> Self {
+        match indent {
+            Indent::Spaces(count) => Self::Spaces(count),
+            Indent::Tab => Self::Named("tab".into()),
+        }
+    }
+}
+
+impl TryFrom<IndentValue> for Indent {
+    type Error = anyhow::Error;
+
+    fn try_from(value: IndentValue) -> Result<Self, Self::Error> {
+        match value {
+            IndentValue::Spaces(count) => Ok(Self::Spaces(count)),
+            IndentValue::Named(name) if name == "tab" => Ok(Self::Tab),
+            IndentValue::Named(name) => Err(anyhow!(
+                "Invalid indent `{name}`; expected a number of spaces or `tab`"
+            )),
         }
     }
 }
@@ -192,6 +296,68 @@ impl ResponseContent for Json {
     }
 }
 
+/// `application/x-www-form-urlencoded` content type. Fields are stored as a
+/// JSON object of strings, so they can be queried like JSON. If a field
+/// appears multiple times, its values are collected into an array.
+#[derive(Debug, Deref, From, PartialEq)]
+pub struct FormUrlencoded(serde_json::Value);
+
+impl ResponseContent for FormUrlencoded {
+    fn content_type(&self) -> ContentType {
+        ContentType::FormUrlencoded
+    }
+
+    fn parse(body: &[u8]) -> anyhow::Result<Self> {
+        let mut fields = serde_json::Map::new();
+        for (key, value) in form_urlencoded::parse(body) {
+            let value = serde_json::Value::String(value.into_owned());
+            match fields.get_mut(key.as_ref()) {
+                None => {
+                    fields.insert(key.into_owned(), value);
+                }
+                Some(serde_json::Value::Array(values)) => values.push(value),
+                Some(existing) => {
+                    *existing = vec![existing.take(), value].into();
+                }
+            }
+        }
+        Ok(Self(fields.into()))
+    }
+
+    fn to_json(&self) -> Cow<'_, serde_json::Value> {
+        Cow::Borrowed(&self.0)
+    }
+
+    #[cfg(test)]
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
+
+/// Encode back to form format. Arrays become repeated fields. Anything that
+/// isn't an object has no form representation, so it's shown as JSON.
+impl fmt::Display for FormUrlencoded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let serde_json::Value::Object(fields) = &self.0 else {
+            return write!(f, "{}", self.0);
+        };
+        let mut serializer = form_urlencoded::Serializer::new(String::new());
+        for (key, value) in fields {
+            let values = match value {
+                serde_json::Value::Array(values) => values.as_slice(),
+                value => std::slice::from_ref(value),
+            };
+            for value in values {
+                serializer.append_pair(
+                    key,
+                    &ContentType::FormUrlencoded.value_to_string(value),
+                );
+            }
+        }
+        write!(f, "{}", serializer.finish())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -213,6 +379,10 @@ mod tests {
     )]
     // Test extended MIME type
     #[case::json_extended("application/geo+json", ContentType::Json)]
+    #[case::form(
+        "application/x-www-form-urlencoded; charset=utf-8",
+        ContentType::FormUrlencoded
+    )]
     fn test_try_from_mime(
         #[case] mime_type: &str,
         #[case] expected: ContentType,
@@ -258,6 +428,11 @@ mod tests {
         "{\"hello\": \"goodbye\"}",
         Json(json!({"hello": "goodbye"}))
     )]
+    #[case::form(
+        "application/x-www-form-urlencoded",
+        "name=Big%20Fish&tag=a&tag=b+c",
+        FormUrlencoded(json!({"name": "Big Fish", "tag": ["a", "b c"]}))
+    )]
     fn test_parse_body<T: ResponseContent + PartialEq + 'static>(
         #[case] content_type: &str,
         #[case] body: &str,
@@ -319,6 +494,59 @@ mod tests {
         assert_err!(result, expected_error);
     }
 
+    /// Form bodies are prettified to one decoded field per line
+    #[rstest]
+    #[case::form(
+        ContentType::FormUrlencoded,
+        "name=Big%20Fish&tag=a&tag=b+c",
+        Some("name = Big Fish\ntag = a\ntag = b c")
+    )]
+    #[case::form_empty(ContentType::FormUrlencoded, "", None)]
+    #[case::json(ContentType::Json, "{\"a\":1}", Some("{\n  \"a\": 1\n}"))]
+    #[case::json_invalid(ContentType::Json, "not json", None)]
+    fn test_prettify(
+        #[case] content_type: ContentType,
+        #[case] body: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        assert_eq!(
+            content_type.prettify(body, Indent::default()).as_deref(),
+            expected
+        );
+    }
+
+    /// JSON indentation is configurable
+    #[rstest]
+    #[case::four_spaces(
+        Indent::Spaces(4),
+        "{\n    \"a\": [\n        1\n    ]\n}"
+    )]
+    #[case::tab(Indent::Tab, "{\n\t\"a\": [\n\t\t1\n\t]\n}")]
+    fn test_prettify_indent(#[case] indent: Indent, #[case] expected: &str) {
+        assert_eq!(
+            ContentType::Json.prettify("{\"a\":[1]}", indent).as_deref(),
+            Some(expected)
+        );
+    }
+
+    #[rstest]
+    #[case::spaces(serde_yaml::Value::from(4), Indent::Spaces(4))]
+    #[case::tab(serde_yaml::Value::from("tab"), Indent::Tab)]
+    fn test_deserialize_indent(
+        #[case] yaml: serde_yaml::Value,
+        #[case] expected: Indent,
+    ) {
+        assert_eq!(serde_yaml::from_value::<Indent>(yaml).unwrap(), expected);
+    }
+
+    /// Form content is re-encoded for display, with arrays as repeated fields
+    #[test]
+    fn test_form_display() {
+        let content =
+            FormUrlencoded(json!({"name": "Big Fish", "tag": ["a", 3]}));
+        assert_eq!(content.to_string(), "name=Big+Fish&tag=a&tag=3");
+    }
+
     /// Create header map with the given value for the content-type header
     fn headers(
         content_type: impl TryInto<HeaderValue, Error = InvalidHeaderValue>,
@@ -0,0 +1,248 @@
+//! External credential helpers, for
+//! [Authentication::Helper](crate::collection::Authentication::Helper). Like
+//! git's credential helpers, a helper is a program that prints a credential to
+//! stdout. This lets Slumber integrate with any secret tooling without knowing
+//! about each provider.
+
+use crate::template::command_env;
+use anyhow::{anyhow, bail, Context};
+use reqwest::header::{self, HeaderName, HeaderValue};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{process::Command, time};
+use tracing::{info_span, trace};
+
+/// Maximum time a helper can run before it's killed and the build fails
+const HELPER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runs credential helpers and caches their output. Cheap to clone, all clones
+/// share the same cache.
+#[derive(Clone, Debug, Default)]
+pub(super) struct CredentialHelpers {
+    /// Credentials from helpers that provided a TTL, keyed by the rendered
+    /// command. Helpers without a TTL are run for every request.
+    cache: Arc<Mutex<HashMap<Vec<String>, CachedCredential>>>,
+}
+
+impl CredentialHelpers {
+    /// Get a credential from a helper. If the same command was run recently
+    /// and its TTL hasn't expired, the cached credential is returned without
+    /// running the command again.
+    pub async fn get(
+        &self,
+        command: Vec<String>,
+    ) -> anyhow::Result<(HeaderName, HeaderValue)> {
+        let cached = self
+            .cache
+            .lock()
+            .expect("Credential helper cache lock poisoned")
+            .get(&command)
+            .filter(|cached| cached.expires_at > Instant::now())
+            .map(|cached| (cached.header.clone(), cached.value.clone()));
+        if let Some(credential) = cached {
+            trace!(?command, "Using cached credential");
+            return Ok(credential);
+        }
+
+        let output = run_helper(&command).await?;
+        let (header, value, ttl) = parse_output(&output)
+            .with_context(|| format!("Invalid output from {command:?}"))?;
+        if let Some(ttl) = ttl {
+            self.cache
+                .lock()
+                .expect("Credential helper cache lock poisoned")
+                .insert(
+                    command,
+                    CachedCredential {
+                        header: header.clone(),
+                        value: value.clone(),
+                        expires_at: Instant::now() + ttl,
+                    },
+                );
+        }
+        Ok((header, value))
+    }
+}
+
+/// A credential that can be reused until it expires
+#[derive(Debug)]
+struct CachedCredential {
+    header: HeaderName,
+    value: HeaderValue,
+    expires_at: Instant,
+}
+
+/// Structured helper output. Anything that isn't a JSON object is treated as
+/// a raw token
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HelperOutput {
+    header: String,
+    value: String,
+    /// How long the credential can be reused, in seconds
+    #[serde(default)]
+    ttl: Option<u64>,
+}
+
+/// Run a helper command and return its stdout. A non-zero exit code is an
+/// error, with stderr attached so the user can see what went wrong.
+async fn run_helper(command: &[String]) -> anyhow::Result<Vec<u8>> {
+    let [program, args @ ..] = command else {
+        bail!("Credential helper command is empty");
+    };
+
+    let _ = info_span!("Executing credential helper", ?command).entered();
+    let process = Command::new(program)
+        .args(args)
+        .envs(command_env().iter())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Killed if the timeout drops the future
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| {
+            format!("Error executing credential helper {command:?}")
+        })?;
+    let output = time::timeout(HELPER_TIMEOUT, process.wait_with_output())
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "Credential helper {command:?} timed out after {}s",
+                HELPER_TIMEOUT.as_secs()
+            )
+        })?
+        .with_context(|| {
+            format!("Error executing credential helper {command:?}")
+        })?;
+
+    if !output.status.success() {
+        bail!(
+            "Credential helper {command:?} failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Parse a helper's stdout into a header and optional TTL. A JSON object
+/// gives the header explicitly; anything else is a raw bearer token.
+fn parse_output(
+    stdout: &[u8],
+) -> anyhow::Result<(HeaderName, HeaderValue, Option<Duration>)> {
+    let stdout = std::str::from_utf8(stdout)
+        .context("Output is not valid UTF-8")?
+        .trim();
+    if stdout.is_empty() {
+        bail!("Output is empty");
+    }
+
+    let (header, value, ttl) = if stdout.starts_with('{') {
+        let output: HelperOutput = serde_json::from_str(stdout)?;
+        let header = HeaderName::try_from(&output.header)
+            .with_context(|| format!("Invalid header `{}`", output.header))?;
+        (header, output.value, output.ttl.map(Duration::from_secs))
+    } else {
+        (header::AUTHORIZATION, format!("Bearer {stdout}"), None)
+    };
+    let mut value = HeaderValue::try_from(value)
+        .with_context(|| format!("Invalid value for header `{header}`"))?;
+    value.set_sensitive(true);
+    Ok((header, value, ttl))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_err,
+        test_util::{temp_dir, TempDir},
+    };
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::raw("abc123\n", "authorization", "Bearer abc123", None)]
+    #[case::json(
+        r#"{"header": "X-Api-Key", "value": "abc123"}"#,
+        "x-api-key",
+        "abc123",
+        None
+    )]
+    #[case::json_ttl(
+        r#"{"header": "X-Api-Key", "value": "abc123", "ttl": 60}"#,
+        "x-api-key",
+        "abc123",
+        Some(Duration::from_secs(60))
+    )]
+    fn test_parse_output(
+        #[case] stdout: &str,
+        #[case] expected_header: &str,
+        #[case] expected_value: &str,
+        #[case] expected_ttl: Option<Duration>,
+    ) {
+        let (header, value, ttl) = parse_output(stdout.as_bytes()).unwrap();
+        assert_eq!(header, expected_header);
+        assert_eq!(value, expected_value);
+        assert!(value.is_sensitive());
+        assert_eq!(ttl, expected_ttl);
+    }
+
+    #[rstest]
+    #[case::empty(" \n", "Output is empty")]
+    #[case::missing_field(
+        r#"{"header": "X-Api-Key"}"#,
+        "missing field `value`"
+    )]
+    #[case::invalid_header(
+        r#"{"header": "bad header", "value": "abc123"}"#,
+        "Invalid header `bad header`"
+    )]
+    fn test_parse_output_error(#[case] stdout: &str, #[case] expected: &str) {
+        assert_err!(parse_output(stdout.as_bytes()), expected);
+    }
+
+    /// Helpers with a TTL are only run once until the TTL expires. Helpers
+    /// without one are run every time.
+    #[rstest]
+    #[case::ttl(r#"{"header": "X-Count", "value": "1", "ttl": 60}"#, 1)]
+    #[case::no_ttl("token", 2)]
+    #[tokio::test]
+    async fn test_cache(
+        temp_dir: TempDir,
+        #[case] output: &str,
+        #[case] expected_runs: usize,
+    ) {
+        let log = temp_dir.join("runs");
+        let command = vec![
+            "sh".to_owned(),
+            "-c".to_owned(),
+            format!("echo run >> '{}'; echo '{output}'", log.display()),
+        ];
+        let helpers = CredentialHelpers::default();
+        helpers.get(command.clone()).await.unwrap();
+        helpers.get(command).await.unwrap();
+
+        let runs = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(runs.lines().count(), expected_runs);
+    }
+
+    /// A failing helper includes stderr in the error
+    #[tokio::test]
+    async fn test_helper_error() {
+        let command = vec![
+            "sh".to_owned(),
+            "-c".to_owned(),
+            "echo 'not logged in' >&2; exit 1".to_owned(),
+        ];
+        assert_err!(
+            CredentialHelpers::default().get(command).await,
+            "exit status: 1: not logged in"
+        );
+    }
+}
@@ -5,27 +5,40 @@
 
 use crate::{
     collection::{Authentication, ProfileId, RecipeBody, RecipeId},
-    http::content_type::ContentType,
+    http::{content_type::ContentType, RequestLog},
     template::Template,
+    util::ResultTraced,
 };
-use anyhow::Context;
+use anyhow::{bail, Context};
+use base64::{prelude::BASE64_STANDARD, Engine};
 use bytes::Bytes;
 use chrono::{DateTime, Duration, Utc};
 use derive_more::{Display, From, FromStr};
 use itertools::Itertools;
 use mime::Mime;
 use reqwest::{
-    header::{self, HeaderMap},
+    header::{self, HeaderMap, HeaderName},
     Body, Client, Request, StatusCode, Url,
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
     collections::HashMap,
+    env,
     fmt::{Debug, Write},
-    sync::Arc,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
 };
 use strum::{EnumIter, IntoEnumIterator};
 use thiserror::Error;
+use tokio::{
+    fs,
+    io::{self, AsyncWrite, AsyncWriteExt},
+    sync::Semaphore,
+};
 use tracing::error;
 use uuid::Uuid;
 
@@ -146,13 +159,56 @@ pub struct HttpVersionParseError {
     input: String,
 }
 
+/// Which HTTP version(s) a request is allowed to use. Unlike [HttpVersion],
+/// this is a *policy* for the client, rather than the version actually used
+/// for a request.
+#[derive(
+    Copy, Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpVersionPolicy {
+    /// Negotiate with the server: HTTP/2 via ALPN over TLS, otherwise HTTP/1.1
+    #[default]
+    Auto,
+    /// Only use HTTP/1.x
+    Http1,
+    /// Require HTTP/2 over TLS. For plaintext URLs, this behaves like `auto`
+    /// because there's no support for upgrading a connection
+    Http2,
+    /// Send HTTP/2 immediately, without negotiation. For plaintext URLs, this
+    /// is HTTP/2 cleartext (h2c)
+    Http2PriorKnowledge,
+}
+
+impl HttpVersionPolicy {
+    /// Get the effective policy for a URL with the given scheme
+    pub fn for_scheme(self, scheme: &str) -> Self {
+        match self {
+            Self::Http2 if scheme != "https" => Self::Auto,
+            _ => self,
+        }
+    }
+}
+
+/// How structured JSON bodies (`!json`) are serialized when a request is
+/// built. This only affects the bytes sent; the body is always displayed
+/// pretty in the TUI.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonBodyFormat {
+    /// No whitespace, to keep the body as small as possible
+    #[default]
+    Compact,
+    /// Indented and split over multiple lines
+    Pretty,
+}
+
 /// HTTP method. This is duplicated from [reqwest::Method] so we can enforce
 /// the method is valid during deserialization. This is also generally more
 /// ergonomic at the cost of some flexibility.
 ///
 /// The FromStr implementation will be case-insensitive
-#[derive(Copy, Clone, Debug, EnumIter, Serialize, Deserialize)]
-#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, Serialize, Deserialize)]
 #[serde(into = "&str", try_from = "String")]
 pub enum HttpMethod {
     Connect,
@@ -180,6 +236,12 @@ impl HttpMethod {
             Self::Trace => "TRACE",
         }
     }
+
+    /// Is this a safe (read-only) method? Safe methods shouldn't have any side
+    /// effects on the server, so they can be sent without much thought
+    pub fn is_safe(self) -> bool {
+        matches!(self, Self::Get | Self::Head | Self::Options | Self::Trace)
+    }
 }
 
 impl Display for HttpMethod {
@@ -276,9 +338,14 @@ impl RequestSeed {
 /// These store *indexes* rather than keys because keys may not be necessarily
 /// unique (e.g. in the case of query params). Technically some could use keys
 /// and some could use indexes, but I chose consistency.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
 pub struct BuildOptions {
+    /// Override the recipe's HTTP method
+    pub method: Option<HttpMethod>,
+    /// Override the recipe's URL. Query parameters from the recipe are still
+    /// appended, so this should only be the base URL
+    pub url: Option<Template>,
     /// Authentication can be overridden, but not disabled. For simplicity,
     /// the override is wholesale rather than by field.
     pub authentication: Option<Authentication>,
@@ -288,11 +355,17 @@ pub struct BuildOptions {
     /// Override body. This should *not* be used for form bodies, since those
     /// can be override on a field-by-field basis.
     pub body: Option<RecipeBody>,
+    /// Exclude the body from the request entirely. This takes precedence over
+    /// `body`
+    pub omit_body: bool,
+    /// For bodies with multiple variants, use this variant instead of
+    /// rendering the recipe's selector
+    pub body_variant: Option<String>,
 }
 
 /// A collection of modifications made to a particular section of a recipe
 /// (query params, headers, etc.). See [BuildFieldOverride]
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
 pub struct BuildFieldOverrides {
     overrides: HashMap<usize, BuildFieldOverride>,
@@ -327,7 +400,7 @@ impl FromIterator<(usize, BuildFieldOverride)> for BuildFieldOverrides {
 
 /// Modifications made to a single field (query param, header, etc.) in a
 /// recipe
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
 pub enum BuildFieldOverride {
     /// Do not include this field in the recipe
@@ -348,18 +421,86 @@ pub struct RequestTicket {
     pub(super) client: Client,
     /// Our brave little astronaut, ready to be launched...
     pub(super) request: Request,
+    /// Should the exchange be saved to history? Copied from the recipe
+    pub(super) persist: bool,
+    /// Truncate the response body beyond this size
+    pub(super) max_response_body_size: Option<usize>,
+    /// Updated as the response body is received, so the consumer can track
+    /// the download while the request is in flight
+    pub(super) progress: Arc<DownloadProgress>,
+    /// Log to append the completed exchange to, if enabled
+    pub(super) request_log: Option<RequestLog>,
+    /// Shared with the engine, to limit concurrent requests
+    pub(super) http_semaphore: Arc<Semaphore>,
+    /// Reuse a recent identical response from history, if one was received
+    /// within this duration. Copied from the recipe
+    pub(super) cache: Option<std::time::Duration>,
+    /// Correlation ID header, which is ignored when comparing against cached
+    /// requests
+    pub(super) request_id_header: Option<HeaderName>,
+    /// Connection timeout that the client was built with, to report in errors
+    pub(super) connect_timeout: Option<std::time::Duration>,
 }
 
 impl RequestTicket {
     pub fn record(&self) -> &Arc<RequestRecord> {
         &self.record
     }
+
+    /// Get a handle to the download progress of the response body. This will
+    /// be updated as the response is received, after the ticket is sent.
+    pub fn progress(&self) -> &Arc<DownloadProgress> {
+        &self.progress
+    }
+}
+
+/// Shared tracker for how much of a response body has been received. This is
+/// written by the HTTP engine while the body is streamed in, and can be read
+/// from another thread at any time.
+#[derive(Debug, Default)]
+pub struct DownloadProgress {
+    /// Number of body bytes received so far
+    received: AtomicUsize,
+    /// Expected body size, from the `Content-Length` header. Set once the
+    /// response headers arrive, and never set if the server didn't tell us
+    total: OnceLock<usize>,
+}
+
+impl DownloadProgress {
+    /// Number of body bytes received so far
+    pub fn received(&self) -> usize {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Expected size of the body, if known
+    pub fn total(&self) -> Option<usize> {
+        self.total.get().copied()
+    }
+
+    /// Fraction of the body that's been received, in `[0, 1]`. `None` if the
+    /// expected size is unknown
+    pub fn ratio(&self) -> Option<f64> {
+        let total = self.total()?;
+        if total == 0 {
+            Some(1.0)
+        } else {
+            Some((self.received() as f64 / total as f64).min(1.0))
+        }
+    }
+
+    pub(super) fn set_total(&self, total: usize) {
+        let _ = self.total.set(total);
+    }
+
+    pub(super) fn add_received(&self, bytes: usize) {
+        self.received.fetch_add(bytes, Ordering::Relaxed);
+    }
 }
 
 /// A complete request+response pairing. This is generated by
 /// [RequestTicket::send] when a response is received successfully for a sent
 /// request.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
 pub struct Exchange {
     /// ID to uniquely refer to this exchange
@@ -372,6 +513,10 @@ pub struct Exchange {
     pub start_time: DateTime<Utc>,
     /// When did we finish receiving the *entire* response?
     pub end_time: DateTime<Utc>,
+    /// Was the response reused from history, rather than received from the
+    /// network? See [Recipe::cache](crate::collection::Recipe::cache). This
+    /// isn't persisted, so exchanges loaded from history are never cached.
+    pub cached: bool,
 }
 
 impl Exchange {
@@ -435,6 +580,10 @@ pub struct RequestRecord {
     /// Body content as bytes. This should be decoded as needed. This will
     /// **not** be populated for bodies that are above the "large" threshold.
     pub body: Option<Bytes>,
+    /// Value of the correlation header configured by `request_id_header`, if
+    /// enabled. This is either the value set by the recipe or the one that
+    /// was injected automatically, and is also included in `headers`.
+    pub correlation_id: Option<String>,
 }
 
 impl RequestRecord {
@@ -452,6 +601,7 @@ impl RequestRecord {
         profile_id: Option<ProfileId>,
         request: &Request,
         max_body_size: usize,
+        correlation_header: Option<&HeaderName>,
     ) -> Self {
         Self {
             id: seed.id,
@@ -471,23 +621,55 @@ impl RequestRecord {
                 .and_then(Body::as_bytes)
                 .filter(|body| body.len() <= max_body_size)
                 .map(|body| body.to_owned().into()),
+            correlation_id: correlation_header
+                .and_then(|header| request.headers().get(header))
+                .and_then(|value| value.to_str().ok())
+                .map(String::from),
         }
     }
 
+    /// Would this request be sent identically to another? Metadata such as
+    /// the ID and HTTP version are ignored, as is the given header, which
+    /// should be the correlation header (because it's unique per request)
+    pub fn is_equivalent(
+        &self,
+        other: &Self,
+        ignore_header: Option<&HeaderName>,
+    ) -> bool {
+        let headers = |record: &Self| {
+            let mut headers = record.headers.clone();
+            if let Some(header) = ignore_header {
+                headers.remove(header);
+            }
+            headers
+        };
+        self.method == other.method
+            && self.url == other.url
+            && headers(self) == headers(other)
+            && self.body == other.body
+    }
+
     /// Get the value of the request's `Content-Type` header, if any
     pub fn mime(&self) -> Option<Mime> {
         content_type_header(&self.headers)
     }
 
-    /// Generate a cURL command equivalent to this request
+    /// Generate a cURL command equivalent to this request. A binary body
+    /// can't be included in the command line, so it's base64-encoded and
+    /// piped through `base64 --decode` to curl's stdin instead.
     ///
-    /// This only fails if one of the headers or body is binary and can't be
-    /// converted to UTF-8.
+    /// This only fails if one of the headers is binary and can't be converted
+    /// to UTF-8.
     pub fn to_curl(&self) -> anyhow::Result<String> {
         let mut buf = String::new();
 
         // These writes are all infallible because we're writing to a string,
         // but use ? because it's shorter than unwrap().
+        let body = self.body.as_deref().map(RequestBody::new);
+        if let Some(RequestBody::Binary(encoded)) = &body {
+            write!(&mut buf, "echo '{encoded}' | base64 --decode | ")?;
+        }
+
         let method = &self.method;
         let url = &self.url;
         write!(&mut buf, "curl -X{method} --url '{url}'")?;
@@ -498,13 +680,112 @@ impl RequestRecord {
             write!(&mut buf, " --header '{header}: {value}'")?;
         }
 
-        if let Some(body) = &self.body_str()? {
-            write!(&mut buf, " --data '{body}'")?;
+        match body {
+            None => {}
+            Some(RequestBody::Text(body)) => {
+                write!(&mut buf, " --data '{body}'")?
+            }
+            Some(RequestBody::Binary(_)) => {
+                write!(&mut buf, " --data-binary @-")?
+            }
         }
 
         Ok(buf)
     }
 
+    /// Generate an [HTTPie](https://httpie.io) command equivalent to this
+    /// request. A text body is passed with `--raw`. A binary body can't be
+    /// included in the command line, so it's base64-encoded and piped through
+    /// `base64 --decode` to HTTPie's stdin instead.
+    ///
+    /// This only fails if one of the headers is binary and can't be converted
+    /// to UTF-8.
+    pub fn to_httpie(&self) -> anyhow::Result<String> {
+        let mut buf = String::new();
+
+        // These writes are all infallible because we're writing to a string,
+        // but use ? because it's shorter than unwrap().
+        let body = self.body.as_deref().map(RequestBody::new);
+        if let Some(RequestBody::Binary(encoded)) = &body {
+            write!(&mut buf, "echo '{encoded}' | base64 --decode | ")?;
+        }
+
+        let method = &self.method;
+        let url = &self.url;
+        write!(&mut buf, "http {method} '{url}'")?;
+
+        for (header, value) in &self.headers {
+            let value =
+                value.to_str().context("Error decoding header value")?;
+            // `Header:` with no value tells HTTPie to *remove* the header, so
+            // empty values need the `Header;` syntax
+            if value.is_empty() {
+                write!(&mut buf, " '{header};'")?;
+            } else {
+                write!(&mut buf, " '{header}:{value}'")?;
+            }
+        }
+
+        if let Some(RequestBody::Text(body)) = body {
+            write!(&mut buf, " --raw '{body}'")?;
+        }
+
+        Ok(buf)
+    }
+
+    /// Generate a JavaScript `fetch()` call equivalent to this request. All
+    /// strings are emitted as JSON string literals, which are also valid JS,
+    /// so we get escaping for free. Repeated headers are joined with `, `, the
+    /// same as the `Headers` class does in the browser. A binary body is
+    /// base64-encoded, and decoded into a `Uint8Array` at runtime.
+    ///
+    /// This only fails if one of the headers is binary and can't be converted
+    /// to UTF-8.
+    pub fn to_fetch(&self) -> anyhow::Result<String> {
+        // JSON serialization of a string can't fail
+        let literal = |s: &str| serde_json::to_string(s).unwrap();
+
+        let mut buf = String::new();
+        let method = literal(self.method.to_str());
+        let url = literal(self.url.as_str());
+        writeln!(&mut buf, "fetch({url}, {{")?;
+        writeln!(&mut buf, "  method: {method},")?;
+
+        if !self.headers.is_empty() {
+            writeln!(&mut buf, "  headers: {{")?;
+            for header in self.headers.keys() {
+                let value = self
+                    .headers
+                    .get_all(header)
+                    .iter()
+                    .map(|value| {
+                        value.to_str().context("Error decoding header value")
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?
+                    .join(", ");
+                let header = literal(header.as_str());
+                let value = literal(&value);
+                writeln!(&mut buf, "    {header}: {value},")?;
+            }
+            writeln!(&mut buf, "  }},")?;
+        }
+
+        match self.body.as_deref().map(RequestBody::new) {
+            None => {}
+            Some(RequestBody::Text(body)) => {
+                writeln!(&mut buf, "  body: {},", literal(body))?
+            }
+            Some(RequestBody::Binary(encoded)) => writeln!(
+                &mut buf,
+                "  body: Uint8Array.from(atob({}), (c) => c.charCodeAt(0)),",
+                literal(&encoded)
+            )?,
+        }
+
+        write!(&mut buf, "}});")?;
+        Ok(buf)
+    }
+
     pub fn body(&self) -> Option<&[u8]> {
         self.body.as_deref()
     }
@@ -522,6 +803,23 @@ impl RequestRecord {
     }
 }
 
+/// A request body, prepared for inclusion in generated code. Text bodies can be
+/// included as-is; anything else is base64-encoded so no bytes are lost.
+enum RequestBody<'a> {
+    Text(&'a str),
+    /// Base64-encoded bytes
+    Binary(String),
+}
+
+impl<'a> RequestBody<'a> {
+    fn new(body: &'a [u8]) -> Self {
+        match std::str::from_utf8(body) {
+            Ok(text) => Self::Text(text),
+            Err(_) => Self::Binary(BASE64_STANDARD.encode(body)),
+        }
+    }
+}
+
 #[cfg(any(test, feature = "test"))]
 impl crate::test_util::Factory for RequestRecord {
     fn factory(_: ()) -> Self {
@@ -559,6 +857,7 @@ impl crate::test_util::Factory<(Option<ProfileId>, RecipeId)>
                 ("User-Agent", "slumber"),
             ]),
             body: None,
+            correlation_id: None,
         }
     }
 }
@@ -576,8 +875,10 @@ impl crate::test_util::Factory<RequestId> for ResponseRecord {
         Self {
             id,
             status: StatusCode::OK,
+            http_version: HttpVersion::Http11,
             headers: HeaderMap::new(),
             body: ResponseBody::default(),
+            peer_certificate: None,
         }
     }
 }
@@ -588,8 +889,10 @@ impl crate::test_util::Factory<StatusCode> for ResponseRecord {
         Self {
             id: RequestId::new(),
             status,
+            http_version: HttpVersion::Http11,
             headers: HeaderMap::new(),
             body: ResponseBody::default(),
+            peer_certificate: None,
         }
     }
 }
@@ -640,6 +943,7 @@ impl crate::test_util::Factory<(RequestRecord, ResponseRecord)> for Exchange {
             response: response.into(),
             start_time: Utc::now(),
             end_time: Utc::now(),
+            cached: false,
         }
     }
 }
@@ -663,8 +967,13 @@ impl crate::test_util::Factory<RequestId> for Exchange {
 pub struct ResponseRecord {
     pub id: RequestId,
     pub status: StatusCode,
+    /// HTTP version negotiated with the server
+    pub http_version: HttpVersion,
     pub headers: HeaderMap,
     pub body: ResponseBody,
+    /// Certificate presented by the server during the TLS handshake. `None`
+    /// for plaintext connections
+    pub peer_certificate: Option<PeerCertificate>,
 }
 
 impl ResponseRecord {
@@ -709,6 +1018,32 @@ impl ResponseRecord {
     }
 }
 
+/// A DER-encoded X.509 certificate presented by the server during the TLS
+/// handshake. reqwest only exposes the leaf certificate, so we don't have
+/// access to the rest of the chain, nor the negotiated cipher or TLS version.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerCertificate(Bytes);
+
+impl PeerCertificate {
+    pub fn new(der: Bytes) -> Self {
+        Self(der)
+    }
+
+    /// Raw DER bytes
+    pub fn der(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// SHA-256 fingerprint of the certificate, as colon-separated hex bytes.
+    /// This is the same format browsers and `openssl x509 -fingerprint` use.
+    pub fn fingerprint(&self) -> String {
+        Sha256::digest(&self.0)
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .join(":")
+    }
+}
+
 /// Get the value of the `Content-Type` header, parsed as a MIME. `None` if the
 /// header isn't present or isn't a valid MIME type
 fn content_type_header(headers: &HeaderMap) -> Option<Mime> {
@@ -726,11 +1061,40 @@ fn content_type_header(headers: &HeaderMap) -> Option<Mime> {
 pub struct ResponseBody<T = Bytes> {
     /// Raw body
     data: T,
+    /// If the body was truncated because it exceeded the configured maximum
+    /// size, this is the size of the *full* body. `None` if the body is
+    /// complete
+    full_size: Option<usize>,
+    /// The truncated portion of the body, if it was spooled to disk when the
+    /// response was received. This is only available for the lifetime of the
+    /// response; bodies loaded from history never have it.
+    overflow: Option<BodyOverflow>,
 }
 
 impl<T: AsRef<[u8]>> ResponseBody<T> {
     pub fn new(data: T) -> Self {
-        Self { data }
+        Self {
+            data,
+            full_size: None,
+            overflow: None,
+        }
+    }
+
+    /// Create a body that was truncated from a larger body of `full_size`
+    /// bytes
+    pub fn truncated(data: T, full_size: usize) -> Self {
+        Self {
+            data,
+            full_size: Some(full_size),
+            overflow: None,
+        }
+    }
+
+    /// Attach the truncated portion of the body, so the full body can be
+    /// retrieved later with [ResponseBody::write_full]
+    pub fn with_overflow(mut self, overflow: Option<BodyOverflow>) -> Self {
+        self.overflow = overflow;
+        self
     }
 
     /// Raw content bytes
@@ -748,10 +1112,89 @@ impl<T: AsRef<[u8]>> ResponseBody<T> {
         std::str::from_utf8(self.data.as_ref()).ok()
     }
 
-    /// Get body size, in bytes
+    /// Get body size, in bytes. If the body was truncated, this is the size
+    /// of the *stored* portion
     pub fn size(&self) -> usize {
         self.data.as_ref().len()
     }
+
+    /// If the body was truncated, get the size of the full body. `None` if
+    /// the body is complete
+    pub fn full_size(&self) -> Option<usize> {
+        self.full_size
+    }
+
+    /// Was the body cut off because it exceeded the maximum size?
+    pub fn is_truncated(&self) -> bool {
+        self.full_size.is_some()
+    }
+
+    /// Is the full body available via [ResponseBody::write_full]? This is
+    /// true for complete bodies, and for truncated bodies whose overflow was
+    /// spooled to disk.
+    pub fn has_full_body(&self) -> bool {
+        !self.is_truncated() || self.overflow.is_some()
+    }
+
+    /// Write the *full* body to a writer: the stored portion, followed by the
+    /// truncated portion that was spooled to disk when the response was
+    /// received. The request is never re-sent. Return the number of bytes
+    /// written.
+    pub async fn write_full(
+        &self,
+        writer: &mut (impl AsyncWrite + Unpin),
+    ) -> anyhow::Result<usize> {
+        if !self.has_full_body() {
+            bail!("Full response body is not available");
+        }
+        let data = self.data.as_ref();
+        writer.write_all(data).await?;
+        let mut size = data.len();
+        if let Some(overflow) = &self.overflow {
+            let mut file =
+                fs::File::open(&overflow.path).await.with_context(|| {
+                    format!(
+                        "Error opening spooled body `{}`",
+                        overflow.path.display()
+                    )
+                })?;
+            size += usize::try_from(io::copy(&mut file, writer).await?)
+                .unwrap_or(usize::MAX);
+        }
+        writer.flush().await?;
+        Ok(size)
+    }
+}
+
+/// The portion of a truncated response body past the size limit, spooled to
+/// a temporary file while the response was received. This allows the full
+/// body to be saved without sending the request again. The file is deleted
+/// when this is dropped.
+#[derive(Debug)]
+pub struct BodyOverflow {
+    path: PathBuf,
+}
+
+impl BodyOverflow {
+    /// Create a new empty temp file to spool into
+    pub async fn create() -> anyhow::Result<(Self, fs::File)> {
+        let path =
+            env::temp_dir().join(format!("slumber-body-{}", Uuid::new_v4()));
+        let file = fs::File::create(&path).await.with_context(|| {
+            format!("Error creating spool file `{}`", path.display())
+        })?;
+        Ok((Self { path }, file))
+    }
+}
+
+impl Drop for BodyOverflow {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path)
+            .with_context(|| {
+                format!("Error deleting spool file `{}`", self.path.display())
+            })
+            .traced();
+    }
 }
 
 impl Debug for ResponseBody {
@@ -765,7 +1208,11 @@ impl Debug for ResponseBody {
 
 impl<T: From<Bytes>> From<Bytes> for ResponseBody<T> {
     fn from(data: Bytes) -> Self {
-        Self { data: data.into() }
+        Self {
+            data: data.into(),
+            full_size: None,
+            overflow: None,
+        }
     }
 }
 
@@ -794,7 +1241,7 @@ impl From<serde_json::Value> for ResponseBody {
 impl PartialEq for ResponseBody {
     fn eq(&self, other: &Self) -> bool {
         // Ignore derived data
-        self.data == other.data
+        self.data == other.data && self.full_size == other.full_size
     }
 }
 
@@ -866,10 +1313,11 @@ impl PartialEq for RequestError {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_util::{header_map, Factory};
+    use crate::test_util::{header_map, test_data_dir, Factory};
     use indexmap::indexmap;
     use rstest::rstest;
     use serde_json::json;
+    use std::path::PathBuf;
 
     #[rstest]
     #[case::content_disposition(
@@ -910,6 +1358,17 @@ mod tests {
         assert_eq!(response.file_name().as_deref(), expected);
     }
 
+    #[test]
+    fn test_peer_certificate_fingerprint() {
+        let certificate =
+            PeerCertificate::new(b"not a real cert".as_slice().into());
+        assert_eq!(
+            certificate.fingerprint(),
+            "99:7D:4F:47:4D:CD:8A:E0:08:F7:E9:B1:D4:16:14:9D:\
+            25:E4:5B:E5:A4:2B:D8:4C:0F:58:1B:ED:64:D2:87:24"
+        );
+    }
+
     #[test]
     fn test_to_curl() {
         let headers = indexmap! {
@@ -919,6 +1378,8 @@ mod tests {
         let body = json!({"data": "value"});
         let request = RequestRecord {
             method: HttpMethod::Delete,
+            // Repeated query params should all be included
+            url: "http://localhost/url?id=1&id=2".parse().unwrap(),
             headers: header_map(headers),
             body: Some(serde_json::to_vec(&body).unwrap().into()),
             ..RequestRecord::factory(())
@@ -926,10 +1387,110 @@ mod tests {
 
         assert_eq!(
             request.to_curl().unwrap(),
-            "curl -XDELETE --url 'http://localhost/url' \
+            "curl -XDELETE --url 'http://localhost/url?id=1&id=2' \
             --header 'accept: application/json' \
             --header 'content-type: application/json' \
             --data '{\"data\":\"value\"}'"
         );
     }
+
+    #[test]
+    fn test_to_httpie() {
+        let headers = indexmap! {
+            "accept" => "application/json",
+            "content-type" => "application/json",
+            "x-empty" => "",
+        };
+        let body = json!({"data": "value"});
+        let request = RequestRecord {
+            method: HttpMethod::Delete,
+            url: "http://localhost/url?id=1&id=2".parse().unwrap(),
+            headers: header_map(headers),
+            body: Some(serde_json::to_vec(&body).unwrap().into()),
+            ..RequestRecord::factory(())
+        };
+
+        assert_eq!(
+            request.to_httpie().unwrap(),
+            "http DELETE 'http://localhost/url?id=1&id=2' \
+            'accept:application/json' 'content-type:application/json' \
+            'x-empty;' --raw '{\"data\":\"value\"}'"
+        );
+    }
+
+    #[test]
+    fn test_to_fetch() {
+        let mut headers = header_map([
+            ("accept", "application/json"),
+            ("content-type", "application/json"),
+            ("x-quote", r#"say "hi""#),
+        ]);
+        // Repeated headers are joined
+        headers.append("accept", "text/plain".try_into().unwrap());
+        let body = json!({"data": "multi\nline"});
+        let request = RequestRecord {
+            method: HttpMethod::Post,
+            url: "http://localhost/url?id=1&id=2".parse().unwrap(),
+            headers,
+            body: Some(serde_json::to_vec(&body).unwrap().into()),
+            ..RequestRecord::factory(())
+        };
+
+        assert_eq!(
+            request.to_fetch().unwrap(),
+            r#"fetch("http://localhost/url?id=1&id=2", {
+  method: "POST",
+  headers: {
+    "accept": "application/json, text/plain",
+    "content-type": "application/json",
+    "x-quote": "say \"hi\"",
+  },
+  body: "{\"data\":\"multi\\nline\"}",
+});"#
+        );
+
+        // Headers and body are omitted when empty
+        let request = RequestRecord {
+            headers: HeaderMap::new(),
+            body: None,
+            ..RequestRecord::factory(())
+        };
+        assert_eq!(
+            request.to_fetch().unwrap(),
+            r#"fetch("http://localhost/url", {
+  method: "GET",
+});"#
+        );
+    }
+
+    /// Binary bodies are base64-encoded in generated code, rather than failing
+    #[rstest]
+    fn test_generate_binary_body(test_data_dir: PathBuf) {
+        let body =
+            std::fs::read(test_data_dir.join("invalid_utf8.bin")).unwrap();
+        let request = RequestRecord {
+            method: HttpMethod::Post,
+            url: "http://localhost/url".parse().unwrap(),
+            headers: HeaderMap::new(),
+            body: Some(body.into()),
+            ..RequestRecord::factory(())
+        };
+
+        assert_eq!(
+            request.to_curl().unwrap(),
+            "echo 'wyg=' | base64 --decode | \
+            curl -XPOST --url 'http://localhost/url' --data-binary @-"
+        );
+        assert_eq!(
+            request.to_httpie().unwrap(),
+            "echo 'wyg=' | base64 --decode | http POST 'http://localhost/url'"
+        );
+        assert_eq!(
+            request.to_fetch().unwrap(),
+            r#"fetch("http://localhost/url", {
+  method: "POST",
+  body: Uint8Array.from(atob("wyg="), (c) => c.charCodeAt(0)),
+});"#
+        );
+    }
 }
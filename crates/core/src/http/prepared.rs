@@ -0,0 +1,153 @@
+//! Prepared requests: fully rendered requests that are saved to a file, so
+//! they can be sent later (possibly on another machine) without rendering the
+//! recipe again
+
+use crate::{
+    collection::{ProfileId, RecipeId},
+    http::{HttpMethod, RequestTicket},
+};
+use anyhow::Context;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Body, Client, Request, Url,
+};
+use serde::{Deserialize, Serialize};
+
+/// A fully rendered request. This is the serializable form of a
+/// [RequestTicket]. Unlike [RequestRecord](super::RequestRecord), the body is
+/// always included in full, because the request has to be sent exactly as it
+/// was built.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(deny_unknown_fields)]
+pub struct PreparedRequest {
+    /// The profile used to render this request (for historical context)
+    pub profile_id: Option<ProfileId>,
+    /// The recipe used to generate this request (for historical context)
+    pub recipe_id: RecipeId,
+    pub method: HttpMethod,
+    pub url: Url,
+    /// Headers are stored as a list rather than a map, because names can
+    /// repeat
+    pub headers: Vec<PreparedHeader>,
+    pub body: Option<PreparedBody>,
+}
+
+impl PreparedRequest {
+    /// Capture a built request so it can be saved. Fails if the body is a
+    /// stream (e.g. a file upload), because it can't be read ahead of time,
+    /// or if any header value isn't valid UTF-8.
+    pub fn new(ticket: &RequestTicket) -> anyhow::Result<Self> {
+        let record = ticket.record();
+        let headers = record
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = value.to_str().with_context(|| {
+                    format!("Value of header `{name}` is not valid UTF-8")
+                })?;
+                Ok(PreparedHeader {
+                    name: name.to_string(),
+                    value: value.to_owned(),
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let body = match ticket.request.body() {
+            None => None,
+            Some(body) => {
+                let bytes = body.as_bytes().context(
+                    "Streamed bodies (e.g. file uploads) can't be prepared",
+                )?;
+                Some(PreparedBody::new(bytes))
+            }
+        };
+        Ok(Self {
+            profile_id: record.profile_id.clone(),
+            recipe_id: record.recipe_id.clone(),
+            method: record.method,
+            url: record.url.clone(),
+            headers,
+            body,
+        })
+    }
+
+    /// Convert to a reqwest request, to be sent by the given client
+    pub(super) fn to_request(
+        &self,
+        client: &Client,
+    ) -> anyhow::Result<Request> {
+        let mut headers = HeaderMap::new();
+        for header in &self.headers {
+            let name = HeaderName::try_from(&header.name)
+                .with_context(|| format!("Invalid header `{}`", header.name))?;
+            let value =
+                HeaderValue::try_from(&header.value).with_context(|| {
+                    format!("Invalid value for header `{name}`")
+                })?;
+            headers.append(name, value);
+        }
+        let mut builder = client
+            .request(self.method.into(), self.url.clone())
+            .headers(headers);
+        if let Some(body) = &self.body {
+            builder = builder.body(body.to_body()?);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// A single header in a [PreparedRequest]
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(deny_unknown_fields)]
+pub struct PreparedHeader {
+    pub name: String,
+    pub value: String,
+}
+
+/// Body of a [PreparedRequest]. Text bodies are stored as-is so the file is
+/// readable; anything else is base64-encoded.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(rename_all = "snake_case")]
+pub enum PreparedBody {
+    Text(String),
+    Base64(String),
+}
+
+impl PreparedBody {
+    fn new(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self::Text(text.to_owned()),
+            Err(_) => Self::Base64(BASE64_STANDARD.encode(bytes)),
+        }
+    }
+
+    fn to_body(&self) -> anyhow::Result<Body> {
+        match self {
+            Self::Text(text) => Ok(text.clone().into()),
+            Self::Base64(encoded) => Ok(BASE64_STANDARD
+                .decode(encoded)
+                .context("Invalid base64 body")?
+                .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Binary bodies are base64-encoded, and survive the round trip
+    #[rstest]
+    #[case::text(b"hello", PreparedBody::Text("hello".into()))]
+    #[case::binary(b"\xff\x00", PreparedBody::Base64("/wA=".into()))]
+    fn test_body(#[case] bytes: &[u8], #[case] expected: PreparedBody) {
+        let body = PreparedBody::new(bytes);
+        assert_eq!(body, expected);
+        let decoded = body.to_body().unwrap();
+        assert_eq!(decoded.as_bytes(), Some(bytes));
+    }
+}
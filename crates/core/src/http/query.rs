@@ -2,13 +2,16 @@
 
 use crate::{collection::SelectorMode, http::content_type::ResponseContent};
 use derive_more::{Display, FromStr};
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use serde_json_path::{ExactlyOneError, JsonPath};
 use thiserror::Error;
 
 /// A wrapper around a JSONPath. This combines some common behavior, and will
 /// make it easy to swap out the query language in the future if necessary.
-#[derive(Clone, Debug, Display, FromStr, PartialEq, Serialize, Deserialize)]
+///
+/// Deserialization is implemented manually, so that parse errors include the
+/// offending selector.
+#[derive(Clone, Debug, Display, FromStr, PartialEq, Serialize)]
 #[serde(transparent)]
 pub struct Query(JsonPath);
 
@@ -61,6 +64,18 @@ impl Query {
     }
 }
 
+impl<'de> Deserialize<'de> for Query {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let selector = String::deserialize(deserializer)?;
+        selector.parse().map(Self).map_err(|error| {
+            D::Error::custom(format!("Invalid selector `{selector}`: {error}"))
+        })
+    }
+}
+
 #[cfg(test)]
 impl From<&str> for Query {
     fn from(value: &str) -> Self {
@@ -186,6 +201,16 @@ mod tests {
         assert_err!(query.query_to_string(mode, &*content), expected_err);
     }
 
+    /// Invalid selectors are rejected during deserialization, and the error
+    /// includes the selector
+    #[test]
+    fn test_deserialize_error() {
+        assert_err!(
+            serde_yaml::from_str::<Query>("$.data["),
+            "Invalid selector `$.data[`"
+        );
+    }
+
     /// Helper to create JSON content
     fn json(value: serde_json::Value) -> Box<dyn ResponseContent> {
         Box::new(Json::from(value))
@@ -0,0 +1,97 @@
+//! Ordered request queues. Recipes that share a queue name are run one at a
+//! time, in the order they were submitted. This sits *on top of* the engine's
+//! concurrency limit: a queued request first waits for its turn in the queue,
+//! then waits for a slot in the semaphore like any other request.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::oneshot;
+use tracing::trace;
+
+/// All named queues for an engine. Cheap to clone, all clones share the same
+/// queues.
+#[derive(Clone, Debug, Default)]
+pub(super) struct RequestQueues {
+    /// For each queue, a receiver that resolves once the most recently
+    /// enqueued request in that queue is done. Each new slot takes the
+    /// previous tail and waits on it, forming a chain.
+    tails: Arc<Mutex<HashMap<String, oneshot::Receiver<()>>>>,
+}
+
+impl RequestQueues {
+    /// Take the next slot in the named queue. Position is determined *now*,
+    /// not when the slot is awaited, so callers should enqueue synchronously
+    /// at submission time to preserve ordering.
+    pub fn enqueue(&self, queue: &str) -> QueueSlot {
+        let (done_tx, done_rx) = oneshot::channel();
+        let previous = self
+            .tails
+            .lock()
+            .expect("Request queue lock poisoned")
+            .insert(queue.to_owned(), done_rx);
+        trace!(queue, waiting = previous.is_some(), "Enqueued request");
+        QueueSlot {
+            previous,
+            _done: Some(done_tx),
+        }
+    }
+}
+
+/// A position in a request queue. Call [QueueSlot::wait] to wait until every
+/// request ahead of this one is done. The slot must be held until the request
+/// is complete; dropping it lets the next request in the queue proceed. This
+/// means a cancelled or failed request frees up the queue automatically.
+#[derive(Debug, Default)]
+pub struct QueueSlot {
+    /// Resolves when the request ahead of us is done. `None` if we're at the
+    /// front of the queue, or not in a queue at all
+    previous: Option<oneshot::Receiver<()>>,
+    /// Dropped when we're done, to notify the next request in line
+    _done: Option<oneshot::Sender<()>>,
+}
+
+impl QueueSlot {
+    /// Wait for every request ahead of this one in the queue to finish. For
+    /// unqueued requests, this returns immediately.
+    pub async fn wait(&mut self) {
+        // Borrow rather than take, so the wait is still valid if this future
+        // is dropped before completing
+        if let Some(previous) = &mut self.previous {
+            // The sender is never used; it's dropped when the previous request
+            // finishes, so an error here is the expected outcome
+            let _ = previous.await;
+            self.previous = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    /// Slots in the same queue resolve in submission order, and each one
+    /// blocks until the previous is dropped
+    #[tokio::test]
+    async fn test_queue_order() {
+        let queues = RequestQueues::default();
+        let mut first = queues.enqueue("q");
+        let mut second = queues.enqueue("q");
+        let mut third = queues.enqueue("q");
+        let mut other = queues.enqueue("other");
+
+        // Heads of each queue don't have to wait
+        assert!(first.wait().now_or_never().is_some());
+        assert!(other.wait().now_or_never().is_some());
+
+        assert!(second.wait().now_or_never().is_none());
+        drop(first);
+        // Third is still waiting on second
+        assert!(second.wait().now_or_never().is_some());
+        assert!(third.wait().now_or_never().is_none());
+        drop(second);
+        assert!(third.wait().now_or_never().is_some());
+    }
+}
@@ -0,0 +1,347 @@
+//! Structured log of completed requests. This is separate from both the
+//! tracing log and the history database. It's an append-only file intended to
+//! be consumed by external tools.
+
+use crate::{
+    collection::{ProfileId, RecipeId},
+    http::{
+        Exchange, HttpMethod, HttpVersion, RequestId, SENSITIVE_PLACEHOLDER,
+    },
+    util::{paths::expand_home, ResultTraced},
+};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use reqwest::{header::HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    borrow::Cow,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use tracing::{error, info};
+
+/// Configuration for the request log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[serde(deny_unknown_fields)]
+pub struct RequestLogConfig {
+    /// File to append to. Will be created if it doesn't exist
+    pub path: PathBuf,
+    /// Serialization format for each entry
+    #[serde(default)]
+    pub format: RequestLogFormat,
+}
+
+/// Serialization format for request log entries
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestLogFormat {
+    /// Pretty-printed JSON objects, one after another
+    Json,
+    /// Newline-delimited JSON: one compact object per line
+    #[default]
+    Ndjson,
+}
+
+/// Handle to the request log. Entries are written synchronously and
+/// unbuffered, so each entry is on disk by the time [Self::log] returns. This
+/// means nothing is lost if the process exits immediately after a request,
+/// e.g. in the CLI. Callers on an async thread should log from a blocking
+/// task. Cheap to clone, all clones write to the same file.
+#[derive(Clone, Debug)]
+pub struct RequestLog {
+    format: RequestLogFormat,
+    /// Lock ensures concurrent entries don't get interleaved
+    file: Arc<Mutex<File>>,
+}
+
+impl RequestLog {
+    /// Open the log file for appending
+    pub fn open(config: &RequestLogConfig) -> anyhow::Result<Self> {
+        let path = expand_home(&config.path).into_owned();
+        info!(?path, "Opening request log");
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Error opening request log {path:?}"))?;
+        Ok(Self {
+            format: config.format,
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    /// Append a completed exchange to the log. Errors are traced but not
+    /// returned, because a failed log write shouldn't fail the request
+    pub fn log(&self, exchange: &Exchange) {
+        let Some(entry) = self.format.serialize(exchange).traced().ok() else {
+            return;
+        };
+        let mut file = self.file.lock().expect("Request log lock poisoned");
+        if let Err(error) = file.write_all(entry.as_bytes()) {
+            error!(error = %error, "Error writing to request log");
+        }
+    }
+}
+
+impl RequestLogFormat {
+    /// Serialize a single exchange into a log entry, including the trailing
+    /// newline
+    fn serialize(self, exchange: &Exchange) -> anyhow::Result<String> {
+        let entry = RequestLogEntry::from(exchange);
+        let mut output = match self {
+            Self::Json => serde_json::to_string_pretty(&entry),
+            Self::Ndjson => serde_json::to_string(&entry),
+        }
+        .context("Error serializing request log entry")?;
+        output.push('\n');
+        Ok(output)
+    }
+}
+
+/// Serialization format for a single exchange in the log. Bodies are included
+/// only if they're valid UTF-8. Header values marked sensitive (e.g.
+/// `Authorization` from a recipe's `authentication`) are masked
+#[derive(Debug, Serialize)]
+struct RequestLogEntry<'a> {
+    id: RequestId,
+    profile_id: Option<&'a ProfileId>,
+    recipe_id: &'a RecipeId,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    request: RequestLogRequest<'a>,
+    response: RequestLogResponse<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct RequestLogRequest<'a> {
+    http_version: HttpVersion,
+    method: HttpMethod,
+    url: &'a str,
+    #[serde(serialize_with = "serialize_headers")]
+    headers: &'a HeaderMap,
+    body: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct RequestLogResponse<'a> {
+    #[serde(serialize_with = "serialize_status")]
+    status: StatusCode,
+    #[serde(serialize_with = "serialize_headers")]
+    headers: &'a HeaderMap,
+    body: Option<&'a str>,
+}
+
+impl<'a> From<&'a Exchange> for RequestLogEntry<'a> {
+    fn from(exchange: &'a Exchange) -> Self {
+        let request = &exchange.request;
+        let response = &exchange.response;
+        Self {
+            id: exchange.id,
+            profile_id: request.profile_id.as_ref(),
+            recipe_id: &request.recipe_id,
+            start_time: exchange.start_time,
+            end_time: exchange.end_time,
+            request: RequestLogRequest {
+                http_version: request.http_version,
+                method: request.method,
+                url: request.url.as_str(),
+                headers: &request.headers,
+                body: request
+                    .body
+                    .as_deref()
+                    .and_then(|body| std::str::from_utf8(body).ok()),
+            },
+            response: RequestLogResponse {
+                status: response.status,
+                headers: &response.headers,
+                body: response.body.text(),
+            },
+        }
+    }
+}
+
+fn serialize_status<S: Serializer>(
+    status: &StatusCode,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u16(status.as_u16())
+}
+
+/// Serialize headers as a map of name:value. Repeated headers are joined with
+/// `, `, which is equivalent per the HTTP spec. Non-UTF-8 values are
+/// converted lossily, and sensitive values are replaced with a placeholder.
+fn serialize_headers<S: Serializer>(
+    headers: &&HeaderMap,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(headers.keys().map(|name| {
+        let value = headers
+            .get_all(name)
+            .iter()
+            .map(|value| {
+                if value.is_sensitive() {
+                    Cow::Borrowed(SENSITIVE_PLACEHOLDER)
+                } else {
+                    String::from_utf8_lossy(value.as_bytes())
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        (name.as_str(), value)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::{RequestRecord, ResponseRecord},
+        test_util::{header_map, temp_dir, Factory, TempDir},
+    };
+    use pretty_assertions::assert_eq;
+    use reqwest::header::{self, HeaderValue};
+    use rstest::rstest;
+    use serde_json::json;
+    use serde_test::{assert_de_tokens, Token};
+
+    fn exchange() -> Exchange {
+        let id = RequestId::new();
+        let request = RequestRecord {
+            url: "http://localhost/users".parse().unwrap(),
+            headers: header_map([("accept", "application/json")]),
+            body: Some(b"{\"name\": \"bob\"}".as_slice().into()),
+            ..RequestRecord::factory(id)
+        };
+        let response = ResponseRecord {
+            headers: header_map([
+                ("content-type", "application/json"),
+                ("set-cookie", "a=1"),
+                ("set-cookie", "b=2"),
+            ]),
+            body: b"{\"id\": 3}".as_slice().into(),
+            ..ResponseRecord::factory(id)
+        };
+        Exchange::factory((request, response))
+    }
+
+    fn expected_entry(exchange: &Exchange) -> serde_json::Value {
+        json!({
+            "id": exchange.id,
+            "profile_id": exchange.request.profile_id,
+            "recipe_id": exchange.request.recipe_id,
+            "start_time": exchange.start_time,
+            "end_time": exchange.end_time,
+            "request": {
+                "http_version": "HTTP/1.1",
+                "method": "GET",
+                "url": "http://localhost/users",
+                "headers": {"accept": "application/json"},
+                "body": "{\"name\": \"bob\"}",
+            },
+            "response": {
+                "status": 200,
+                "headers": {
+                    "content-type": "application/json",
+                    "set-cookie": "a=1, b=2",
+                },
+                "body": "{\"id\": 3}",
+            },
+        })
+    }
+
+    /// Each format should produce a single JSON value per entry
+    #[rstest]
+    #[case::json(RequestLogFormat::Json, true)]
+    #[case::ndjson(RequestLogFormat::Ndjson, false)]
+    fn test_serialize(
+        #[case] format: RequestLogFormat,
+        #[case] multiline: bool,
+    ) {
+        let exchange = exchange();
+        let output = format.serialize(&exchange).unwrap();
+
+        assert!(output.ends_with('\n'));
+        assert_eq!(output.trim_end().contains('\n'), multiline);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value, expected_entry(&exchange));
+    }
+
+    /// Non-UTF-8 bodies are omitted
+    #[test]
+    fn test_serialize_binary_body() {
+        let id = RequestId::new();
+        let response = ResponseRecord {
+            body: b"\xc3\x28".as_slice().into(),
+            ..ResponseRecord::factory(id)
+        };
+        let exchange =
+            Exchange::factory((RequestRecord::factory(id), response));
+        let output = RequestLogFormat::Ndjson.serialize(&exchange).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["response"]["body"], serde_json::Value::Null);
+    }
+
+    /// Open a log file and write to it
+    #[rstest]
+    fn test_log(temp_dir: TempDir) {
+        let path = temp_dir.join("requests.log");
+        let log = RequestLog::open(&RequestLogConfig {
+            path: path.clone(),
+            format: RequestLogFormat::Ndjson,
+        })
+        .unwrap();
+        let exchange = exchange();
+        log.log(&exchange);
+
+        // Writes are synchronous, so the entry is there immediately
+        let content = std::fs::read_to_string(&path).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(value, expected_entry(&exchange));
+    }
+
+    /// Header values marked sensitive, such as auth headers, are masked
+    #[test]
+    fn test_serialize_sensitive_header() {
+        let id = RequestId::new();
+        let mut authorization = HeaderValue::from_static("Bearer hunter2");
+        authorization.set_sensitive(true);
+        let mut headers = header_map([("accept", "application/json")]);
+        headers.insert(header::AUTHORIZATION, authorization);
+        let request = RequestRecord {
+            headers,
+            ..RequestRecord::factory(id)
+        };
+        let exchange =
+            Exchange::factory((request, ResponseRecord::factory(id)));
+        let output = RequestLogFormat::Ndjson.serialize(&exchange).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            value["request"]["headers"],
+            json!({
+                "accept": "application/json",
+                "authorization": "<sensitive>",
+            })
+        );
+        assert!(!output.contains("hunter2"));
+    }
+
+    /// Format defaults to ndjson
+    #[test]
+    fn test_deserialize_config() {
+        assert_de_tokens(
+            &RequestLogConfig {
+                path: "requests.log".into(),
+                format: RequestLogFormat::Ndjson,
+            },
+            &[
+                Token::Map { len: Some(1) },
+                Token::Str("path"),
+                Token::Str("requests.log"),
+                Token::MapEnd,
+            ],
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Utilities for XML request bodies
+
+use crate::http::content_type::Indent;
+use anyhow::{anyhow, bail, Context};
+use mime::Mime;
+use quick_xml::{
+    events::Event,
+    name::ResolveResult,
+    reader::{NsReader, Reader},
+    writer::Writer,
+};
+
+/// Get the MIME type for XML bodies, `application/xml`. The `mime` crate
+/// doesn't provide a constant for this one
+pub fn mime() -> Mime {
+    "application/xml"
+        .parse()
+        .expect("Static MIME type is valid")
+}
+
+/// Check that a body is well-formed XML. Beyond basic syntax, this checks that
+/// there is exactly one root element and that every namespace prefix (on
+/// elements and attributes) is bound by an `xmlns:` declaration in scope.
+pub fn validate(body: &[u8]) -> anyhow::Result<()> {
+    let mut reader = NsReader::from_reader(body);
+    let mut depth = 0usize;
+    let mut roots = 0usize;
+    loop {
+        let position = reader.buffer_position();
+        let (resolved, event) = reader
+            .read_resolved_event()
+            .with_context(|| format!("Invalid XML at byte {position}"))?;
+        match event {
+            Event::Start(ref element) | Event::Empty(ref element) => {
+                if depth == 0 {
+                    roots += 1;
+                }
+                if matches!(event, Event::Start(_)) {
+                    depth += 1;
+                }
+
+                check_resolved(resolved, element.name().as_ref())?;
+                for attribute in element.attributes() {
+                    let attribute = attribute.with_context(|| {
+                        format!("Invalid XML attribute at byte {position}")
+                    })?;
+                    // Namespace declarations don't need to be resolved
+                    if attribute.key.as_namespace_binding().is_some() {
+                        continue;
+                    }
+                    let (resolved, _) = reader.resolve_attribute(attribute.key);
+                    check_resolved(resolved, attribute.key.as_ref())?;
+                }
+            }
+            Event::End(_) => depth = depth.saturating_sub(1),
+            Event::Text(text) if depth == 0 => {
+                if !text.iter().all(u8::is_ascii_whitespace) {
+                    bail!("Invalid XML: text outside of root element");
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if depth > 0 {
+        bail!("Invalid XML: unclosed element");
+    }
+    match roots {
+        0 => Err(anyhow!("Invalid XML: no root element")),
+        1 => Ok(()),
+        _ => Err(anyhow!("Invalid XML: multiple root elements")),
+    }
+}
+
+/// Reformat an XML document with consistent indentation. Namespace prefixes
+/// and declarations are preserved as written. Return `None` if the input isn't
+/// valid XML.
+pub fn prettify(body: &str, indent: Indent) -> Option<String> {
+    validate(body.as_bytes()).ok()?;
+
+    let mut reader = Reader::from_str(body);
+    reader.config_mut().trim_text(true);
+    let (indent_char, indent_size) = indent.unit();
+    let mut writer =
+        Writer::new_with_indent(Vec::new(), indent_char, indent_size);
+    loop {
+        match reader.read_event().ok()? {
+            Event::Eof => break,
+            event => writer.write_event(event).ok()?,
+        }
+    }
+    String::from_utf8(writer.into_inner()).ok()
+}
+
+/// Make sure a namespace prefix was bound
+fn check_resolved(resolved: ResolveResult, name: &[u8]) -> anyhow::Result<()> {
+    if let ResolveResult::Unknown(prefix) = resolved {
+        bail!(
+            "Invalid XML: undeclared namespace prefix `{}` in `{}`",
+            String::from_utf8_lossy(&prefix),
+            String::from_utf8_lossy(name),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_err;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::simple("<a><b>text</b></a>")]
+    #[case::declaration(r#"<?xml version="1.0"?><a/>"#)]
+    #[case::namespace(
+        r#"<soap:Envelope
+            xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"
+            soap:encodingStyle="http://www.w3.org/2003/05/soap-encoding">
+            <soap:Body><m:Get xmlns:m="urn:m">1</m:Get></soap:Body>
+        </soap:Envelope>"#
+    )]
+    #[case::xml_prefix(r#"<a xml:lang="en"/>"#)]
+    fn test_validate(#[case] body: &str) {
+        validate(body.as_bytes()).unwrap();
+    }
+
+    #[rstest]
+    #[case::empty("", "no root element")]
+    #[case::unclosed("<a><b></b>", "Invalid XML")]
+    #[case::mismatched("<a></b>", "Invalid XML at byte")]
+    #[case::multiple_roots("<a/><b/>", "multiple root elements")]
+    #[case::trailing_text("<a/>text", "text outside of root element")]
+    #[case::unbound_element(
+        "<soap:Envelope/>",
+        "undeclared namespace prefix `soap` in `soap:Envelope`"
+    )]
+    #[case::unbound_attribute(
+        r#"<a m:id="1"/>"#,
+        "undeclared namespace prefix `m` in `m:id`"
+    )]
+    #[case::out_of_scope(
+        r#"<a><m:b xmlns:m="urn:m"/><m:c/></a>"#,
+        "undeclared namespace prefix `m` in `m:c`"
+    )]
+    fn test_validate_error(#[case] body: &str, #[case] expected_error: &str) {
+        assert_err!(validate(body.as_bytes()), expected_error);
+    }
+
+    #[test]
+    fn test_prettify() {
+        let body = r#"<s:a xmlns:s="urn:s"><s:b>1</s:b><c/></s:a>"#;
+        assert_eq!(
+            prettify(body, Indent::default()).as_deref(),
+            Some(
+                r#"<s:a xmlns:s="urn:s">
+  <s:b>1</s:b>
+  <c/>
+</s:a>"#
+            )
+        );
+        assert_eq!(
+            prettify(body, Indent::Tab).as_deref(),
+            Some("<s:a xmlns:s=\"urn:s\">\n\t<s:b>1</s:b>\n\t<c/>\n</s:a>")
+        );
+        assert_eq!(prettify("<a>", Indent::default()), None);
+    }
+}
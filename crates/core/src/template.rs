@@ -2,18 +2,22 @@
 
 mod cereal;
 mod error;
+mod function;
 mod parse;
 mod prompt;
 mod render;
 
 pub use error::{ChainError, TemplateError, TriggeredRequestError};
-pub use prompt::{Prompt, PromptChannel, Prompter, Select};
+pub use function::{Function, FunctionError, Value};
+pub use prompt::{Prompt, PromptCache, PromptChannel, Prompter, Select};
+pub use render::{command_env, with_command_env};
 
 use crate::{
     collection::{ChainId, Collection, ProfileId},
     db::CollectionDatabase,
     http::HttpEngine,
     template::{
+        function::{Expression, FunctionCall},
         parse::{TemplateInputChunk, CHAIN_PREFIX, ENV_PREFIX},
         render::RenderGroupState,
     },
@@ -23,7 +27,7 @@ use indexmap::IndexMap;
 #[cfg(test)]
 use proptest::{arbitrary::any, strategy::Strategy};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 /// A parsed template, which can contain raw and/or templated content. The
 /// string is parsed during creation to identify template keys, hence the
@@ -60,6 +64,9 @@ pub struct Template {
 pub struct TemplateContext {
     /// Entire request collection
     pub collection: Arc<Collection>,
+    /// Absolute path to the file the collection was loaded from. Exposed to
+    /// templates via `collection_path()` and `collection_dir()`
+    pub collection_path: PathBuf,
     /// ID of the profile whose data should be used for rendering. Generally
     /// the caller should check the ID is valid before passing it, to
     /// provide a better error to the user if not.
@@ -71,10 +78,19 @@ pub struct TemplateContext {
     pub http_engine: Option<HttpEngine>,
     /// Needed for accessing response bodies for chaining
     pub database: CollectionDatabase,
-    /// Additional key=value overrides passed directly from the user
-    pub overrides: IndexMap<String, String>,
+    /// Additional key=value overrides passed directly from the user. Values
+    /// are typed so structured data can be overridden, e.g. from the CLI's
+    /// `key:=value` syntax.
+    pub overrides: IndexMap<String, Value>,
     /// A conduit to ask the user questions
     pub prompter: Box<dyn Prompter>,
+    /// Answers to prompts that should be reused across multiple render groups,
+    /// e.g. for a folder run. If `None`, prompts are asked once per group.
+    pub prompt_cache: Option<PromptCache>,
+    /// Seed for random template functions. If set, random functions generate
+    /// the same sequence of values for each render group. If `None`, values
+    /// are random.
+    pub random_seed: Option<u64>,
     /// State that should be shared across al renders that use this context.
     /// This is meant to be opaque; just use [Default::default] to initialize.
     pub state: RenderGroupState,
@@ -99,6 +115,19 @@ impl Template {
     pub fn is_empty(&self) -> bool {
         self.chunks.is_empty()
     }
+
+    /// Get the ID of every chain referenced by this template, including those
+    /// nested in function arguments. IDs are returned in the order they
+    /// appear, and may contain duplicates.
+    pub fn chain_ids(&self) -> Vec<&ChainId> {
+        let mut ids = Vec::new();
+        for chunk in &self.chunks {
+            if let TemplateInputChunk::Key(key) = chunk {
+                key.collect_chain_ids(&mut ids);
+            }
+        }
+        ids
+    }
 }
 
 #[cfg(any(test, feature = "test"))]
@@ -211,19 +240,43 @@ pub enum TemplateKey {
     /// A value pulled from the process environment
     #[display("{ENV_PREFIX}{_0}")]
     Environment(Identifier),
+    /// A call to a template function, e.g. `add(page, 1)`
+    #[cfg_attr(test, proptest(skip))]
+    Call(FunctionCall),
+}
+
+impl TemplateKey {
+    /// Add the ID of every chain referenced by this key to the list,
+    /// recursing into function arguments
+    fn collect_chain_ids<'a>(&'a self, ids: &mut Vec<&'a ChainId>) {
+        match self {
+            Self::Field(_) | Self::Environment(_) => {}
+            Self::Chain(chain_id) => ids.push(chain_id),
+            Self::Call(call) => {
+                for argument in &call.arguments {
+                    if let Expression::Key(key) = argument {
+                        key.collect_chain_ids(ids);
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[cfg(any(test, feature = "test"))]
 impl crate::test_util::Factory for TemplateContext {
     fn factory(_: ()) -> Self {
-        use crate::test_util::TestPrompter;
+        use crate::{test_util::TestPrompter, util::paths::get_repo_root};
         Self {
             collection: Default::default(),
+            collection_path: get_repo_root().join("slumber.yml"),
             selected_profile: None,
             http_engine: None,
             database: CollectionDatabase::factory(()),
             overrides: IndexMap::new(),
             prompter: Box::<TestPrompter>::default(),
+            prompt_cache: None,
+            random_seed: None,
             state: RenderGroupState::default(),
         }
     }
@@ -263,10 +316,12 @@ mod tests {
         assert_err,
         collection::{
             Chain, ChainOutputTrim, ChainRequestSection, ChainRequestTrigger,
-            ChainSource, Profile, Recipe, RecipeId, SelectOptions,
+            ChainSource, DecodeStep, PathRoot, Profile, ProfileValue, Recipe,
+            RecipeId, SelectOptions,
         },
         http::{
-            content_type::ContentType, Exchange, RequestRecord, ResponseRecord,
+            content_type::ContentType, Exchange, HttpEngineConfig,
+            RequestRecord, ResponseRecord,
         },
         test_util::{
             by_id, header_map, http_engine, invalid_utf8_chain, temp_dir,
@@ -330,6 +385,64 @@ mod tests {
         );
     }
 
+    /// Test precedence between collection variables, profile values, and
+    /// overrides
+    #[rstest]
+    #[case::variable("variable", "from variable")]
+    #[case::profile("profile", "from profile")]
+    #[case::override_("override", "from override")]
+    #[tokio::test]
+    async fn test_collection_variables(
+        #[case] field: &str,
+        #[case] expected: &str,
+    ) {
+        let profile = Profile {
+            data: indexmap! {
+                "profile".into() => "from profile".into(),
+                "override".into() => "from profile".into(),
+            },
+            ..Profile::factory(())
+        };
+        let profile_id = profile.id.clone();
+        let context = TemplateContext {
+            collection: Collection {
+                profiles: by_id([profile]),
+                variables: indexmap! {
+                    "variable".into() => "from variable".into(),
+                    "profile".into() => "from variable".into(),
+                    "override".into() => "from variable".into(),
+                },
+                ..Collection::factory(())
+            }
+            .into(),
+            selected_profile: Some(profile_id),
+            overrides: indexmap! {"override".into() => "from override".into()},
+            ..TemplateContext::factory(())
+        };
+
+        assert_eq!(
+            render!(format!("{{{{{field}}}}}"), context).unwrap(),
+            expected
+        );
+    }
+
+    /// Collection variables are available even when no profile is selected
+    #[tokio::test]
+    async fn test_collection_variables_no_profile() {
+        let context = TemplateContext {
+            collection: Collection {
+                variables: indexmap! {"version".into() => "v2".into()},
+                ..Collection::factory(())
+            }
+            .into(),
+            selected_profile: None,
+            ..TemplateContext::factory(())
+        };
+
+        assert_eq!(render!("/api/{{version}}", context).unwrap(), "/api/v2");
+        assert_err!(render!("{{unknown}}", context), "No profile selected");
+    }
+
     /// Test that a field key renders correctly
     #[rstest]
     #[case::empty("", "")]
@@ -371,6 +484,383 @@ mod tests {
         assert_err!(render!(template, context), expected);
     }
 
+    /// Non-string profile values are rendered as JSON, so they can be embedded
+    /// in structured bodies
+    #[rstest]
+    #[case::int(json!(3), "3")]
+    #[case::float(json!(2.5), "2.5")]
+    #[case::bool(json!(true), "true")]
+    #[case::null(json!(null), "null")]
+    #[case::array(json!([1, "two", false]), r#"[1,"two",false]"#)]
+    #[case::object(json!({"a": [1]}), r#"{"a":[1]}"#)]
+    #[tokio::test]
+    async fn test_field_typed(
+        #[case] value: serde_json::Value,
+        #[case] expected: &str,
+    ) {
+        let context = profile_context(indexmap! {
+            "value".into() => value.into(),
+            "body".into() => r#"{"value": {{value}}}"#.into(),
+        });
+        assert_eq!(render!("{{value}}", context).unwrap(), expected);
+        assert_eq!(
+            render!("{{body}}", context).unwrap(),
+            format!(r#"{{"value": {expected}}}"#)
+        );
+    }
+
+    /// Test function calls, including nested calls and typed arguments
+    #[rstest]
+    #[case::literals("{{add(1,2)}}", "3")]
+    #[case::whitespace("{{add( 1 ,  2 )}}", "3")]
+    #[case::fields("{{mul(page, size)}}", "60")]
+    #[case::nested("offset={{mul(sub(page, 1), size)}}", "offset=30")]
+    #[case::float("{{div(count, 4)}}", "2.5")]
+    #[case::float_literal("{{mul(-1.5, 2)}}", "-3.0")]
+    #[case::string_literals(r#"{{sub('10', "4")}}"#, "6")]
+    #[case::nested_field("{{offset}}", "30")]
+    #[case::override_("{{add(size, overridden)}}", "130")]
+    #[tokio::test]
+    async fn test_function(#[case] template: &str, #[case] expected: &str) {
+        let mut context = profile_context(indexmap! {
+            "page".into() => "2".into(),
+            "size".into() => json!(30).into(),
+            "count".into() => json!(10).into(),
+            "offset".into() => "{{mul(sub(page, 1), size)}}".into(),
+            "overridden".into() => "1".into(),
+        });
+        context.overrides = indexmap! {"overridden".into() => "100".into()};
+        assert_eq!(&render!(template, context).unwrap(), expected);
+    }
+
+    /// Test errors that occur while calling a function
+    #[rstest]
+    #[case::argument(
+        "{{add(page, unknown)}}",
+        "Calling function `add`: Evaluating argument 1: Unknown field `unknown`"
+    )]
+    #[case::argument_count("{{add(1)}}", "Expected 2 argument(s), got 1")]
+    #[case::if_argument_count(
+        "{{if(page, 1)}}",
+        "Expected 3 argument(s), got 2"
+    )]
+    #[case::coalesce_no_arguments(
+        "{{coalesce()}}",
+        "Expected at least 1 argument(s), got 0"
+    )]
+    #[case::profile_argument_count(
+        "{{profile('p1', 'a', 'b')}}",
+        "Expected 2 argument(s), got 3"
+    )]
+    #[case::if_condition(
+        "{{if(unknown, 1, 2)}}",
+        "Calling function `if`: Evaluating argument 0: Unknown field `unknown`"
+    )]
+    #[case::not_a_number(
+        "{{mul(page, 'three')}}",
+        "Expected number, got string `three`"
+    )]
+    #[case::nested(
+        "{{add(1, div(page, 0))}}",
+        "Calling function `add`: Evaluating argument 1: \
+        Calling function `div`: Division by zero"
+    )]
+    #[tokio::test]
+    async fn test_function_error(
+        #[case] template: &str,
+        #[case] expected: &str,
+    ) {
+        let context = profile_context(indexmap! {"page".into() => "2".into()});
+        assert_err!(render!(template, context), expected);
+    }
+
+    /// Test conditional functions. The unused branches reference an unknown
+    /// field, which would fail if evaluated, to check that evaluation is lazy.
+    #[rstest]
+    #[case::if_true("{{if(enabled, 'yes', unknown)}}", "yes")]
+    #[case::if_false("{{if(disabled, unknown, 'no')}}", "no")]
+    #[case::if_false_string("{{if(false_string, unknown, 'no')}}", "no")]
+    #[case::if_empty("{{if(empty, unknown, 'no')}}", "no")]
+    #[case::if_null("{{if(null, unknown, 'no')}}", "no")]
+    #[case::if_zero("{{if(0, 'yes', unknown)}}", "yes")]
+    #[case::if_nested("{{if(enabled, add(1, 2), unknown)}}", "3")]
+    #[case::coalesce_first("{{coalesce(name, unknown)}}", "Ted")]
+    #[case::coalesce_skip("{{coalesce(empty, null, name, unknown)}}", "Ted")]
+    #[case::coalesce_all_empty("{{coalesce(null, empty)}}", "")]
+    #[case::coalesce_false("{{coalesce(disabled, name)}}", "false")]
+    #[tokio::test]
+    async fn test_function_conditional(
+        #[case] template: &str,
+        #[case] expected: &str,
+    ) {
+        let context = profile_context(indexmap! {
+            "enabled".into() => json!(true).into(),
+            "disabled".into() => json!(false).into(),
+            "false_string".into() => "false".into(),
+            "empty".into() => "".into(),
+            "name".into() => "Ted".into(),
+        });
+        assert_eq!(&render!(template, context).unwrap(), expected);
+    }
+
+    /// `file()`, `files()`, and file chains resolve relative paths against the
+    /// collection directory
+    #[rstest]
+    #[tokio::test]
+    async fn test_function_file(temp_dir: TempDir) {
+        let data_dir = temp_dir.join("data");
+        fs::create_dir(&data_dir).await.unwrap();
+        fs::write(data_dir.join("a.json"), "a").await.unwrap();
+        fs::write(data_dir.join("b.json"), "b").await.unwrap();
+        fs::write(data_dir.join("c.txt"), "c").await.unwrap();
+        // Directories are excluded from files()
+        fs::create_dir(data_dir.join("d.json")).await.unwrap();
+
+        let chain = Chain {
+            source: ChainSource::File {
+                path: "data/a.json".into(),
+                relative_to: PathRoot::Collection,
+            },
+            ..Chain::factory(())
+        };
+        let context = TemplateContext {
+            collection: Collection {
+                chains: by_id([chain]),
+                ..Collection::factory(())
+            }
+            .into(),
+            collection_path: temp_dir.join("slumber.yml"),
+            ..TemplateContext::factory(())
+        };
+
+        assert_eq!(render!("{{file('data/a.json')}}", context).unwrap(), "a");
+        let absolute =
+            format!("{{{{file('{}')}}}}", data_dir.join("c.txt").display());
+        assert_eq!(render!(absolute.as_str(), context).unwrap(), "c");
+        assert_eq!(render!("{{chains.chain1}}", context).unwrap(), "a");
+        assert_eq!(
+            render!("{{files('data/*.json')}}", context).unwrap(),
+            serde_json::to_string(&[
+                data_dir.join("a.json"),
+                data_dir.join("b.json"),
+            ])
+            .unwrap()
+        );
+        assert_eq!(render!("{{files('data/*.xml')}}", context).unwrap(), "[]");
+
+        assert_err!(
+            render!("{{file('data/missing.txt')}}", context),
+            &format!(
+                "Reading file `{}`",
+                data_dir.join("missing.txt").display()
+            )
+        );
+        assert_err!(
+            render!("{{files('data/[')}}", context),
+            "Invalid glob pattern"
+        );
+    }
+
+    /// `env_file()` pulls single values out of a dotenv file, relative to the
+    /// collection directory
+    #[rstest]
+    #[case::value("{{env_file('.env.secrets', 'TOKEN')}}", Ok("abc123"))]
+    #[case::quoted(
+        "{{env_file('.env.secrets', 'GREETING')}}",
+        Ok("hello world")
+    )]
+    #[case::unknown_key(
+        "{{env_file('.env.secrets', 'MISSING')}}",
+        Err("Unknown key `MISSING` in env file")
+    )]
+    #[case::missing_file(
+        "{{env_file('.env.missing', 'TOKEN')}}",
+        Err("Reading file")
+    )]
+    #[case::invalid_file(
+        "{{env_file('.env.invalid', 'TOKEN')}}",
+        Err("Loading env file")
+    )]
+    #[case::argument_count("{{env_file('.env.secrets')}}", Err("Expected 2"))]
+    #[tokio::test]
+    async fn test_function_env_file(
+        temp_dir: TempDir,
+        #[case] template: &str,
+        #[case] expected: Result<&str, &str>,
+    ) {
+        fs::write(
+            temp_dir.join(".env.secrets"),
+            "TOKEN=abc123\nGREETING=\"hello world\"\n",
+        )
+        .await
+        .unwrap();
+        fs::write(temp_dir.join(".env.invalid"), "not a valid line\n")
+            .await
+            .unwrap();
+        let context = TemplateContext {
+            collection_path: temp_dir.join("slumber.yml"),
+            ..TemplateContext::factory(())
+        };
+
+        match expected {
+            Ok(expected) => {
+                assert_eq!(render!(template, context).unwrap(), expected)
+            }
+            Err(expected) => assert_err!(render!(template, context), expected),
+        }
+    }
+
+    /// Values loaded from env files are always sensitive
+    #[rstest]
+    #[tokio::test]
+    async fn test_function_env_file_sensitive(temp_dir: TempDir) {
+        fs::write(temp_dir.join(".env"), "TOKEN=abc123\n")
+            .await
+            .unwrap();
+        let context = TemplateContext {
+            collection_path: temp_dir.join("slumber.yml"),
+            ..TemplateContext::factory(())
+        };
+        assert_eq!(
+            Template::from("{{env_file('.env', 'TOKEN')}}")
+                .render_chunks(&context)
+                .await,
+            vec![TemplateChunk::Rendered {
+                value: Arc::new("abc123".into()),
+                sensitive: true
+            }]
+        );
+        assert_eq!(
+            context.state.sensitive_values(),
+            vec![Arc::new(b"abc123".to_vec())]
+        );
+    }
+
+    /// `profile()` renders a field from another profile. Nested references
+    /// are resolved against the selected profile.
+    #[rstest]
+    #[case::template("{{profile('shared', 'greeting')}}", Ok("hello Ted"))]
+    #[case::value("{{add(profile('shared', 'count'), 1)}}", Ok("4"))]
+    #[case::selected("{{profile('default', 'name')}}", Ok("Ted"))]
+    #[case::unknown_profile(
+        "{{profile('other', 'name')}}",
+        Err("Unknown profile `other`")
+    )]
+    #[case::unknown_field(
+        "{{profile('shared', 'name')}}",
+        Err("Unknown field `name` in profile `shared`")
+    )]
+    #[case::not_a_string("{{profile(1, 'name')}}", Err("Expected string"))]
+    #[case::cycle(
+        "{{profile('shared', 'infinite')}}",
+        Err("Infinite loop detected")
+    )]
+    #[tokio::test]
+    async fn test_function_profile(
+        #[case] template: &str,
+        #[case] expected: Result<&str, &str>,
+    ) {
+        let selected = Profile {
+            id: "default".into(),
+            data: indexmap! {"name".into() => "Ted".into()},
+            ..Profile::factory(())
+        };
+        let shared = Profile {
+            id: "shared".into(),
+            data: indexmap! {
+                "greeting".into() => "hello {{name}}".into(),
+                "count".into() => json!(3).into(),
+                "infinite".into() =>
+                    "{{profile('shared', 'infinite')}}".into(),
+            },
+            ..Profile::factory(())
+        };
+        let context = TemplateContext {
+            collection: Collection {
+                profiles: by_id([selected, shared]),
+                ..Collection::factory(())
+            }
+            .into(),
+            selected_profile: Some("default".into()),
+            ..TemplateContext::factory(())
+        };
+        match expected {
+            Ok(expected) => {
+                assert_eq!(render!(template, context).unwrap(), expected)
+            }
+            Err(expected) => assert_err!(render!(template, context), expected),
+        }
+    }
+
+    /// `collection_path()` and `collection_dir()` expose the location of the
+    /// collection file, which can be used to load sibling files
+    #[rstest]
+    #[tokio::test]
+    async fn test_function_collection_path(temp_dir: TempDir) {
+        fs::write(temp_dir.join("fixture.txt"), "hello!")
+            .await
+            .unwrap();
+        let chain = Chain {
+            source: ChainSource::File {
+                path: "{{collection_dir()}}/fixture.txt".into(),
+                relative_to: PathRoot::Collection,
+            },
+            ..Chain::factory(())
+        };
+        let collection_path = temp_dir.join("slumber.yml");
+        let context = TemplateContext {
+            collection: Collection {
+                chains: by_id([chain]),
+                ..Collection::factory(())
+            }
+            .into(),
+            collection_path: collection_path.clone(),
+            ..TemplateContext::factory(())
+        };
+
+        assert_eq!(
+            render!("{{collection_path()}}", context).unwrap(),
+            collection_path.to_str().unwrap()
+        );
+        assert_eq!(
+            render!("{{collection_dir()}}", context).unwrap(),
+            temp_dir.to_str().unwrap()
+        );
+        assert_eq!(render!("{{chains.chain1}}", context).unwrap(), "hello!");
+        assert_err!(
+            render!("{{collection_dir(1)}}", context),
+            "Expected 0 argument(s), got 1"
+        );
+    }
+
+    /// Function output derived from a sensitive value is also sensitive
+    #[tokio::test]
+    async fn test_function_sensitive() {
+        let chain = Chain {
+            source: ChainSource::command(["echo", "3"]),
+            trim: ChainOutputTrim::Both,
+            sensitive: true,
+            ..Chain::factory(())
+        };
+        let context = TemplateContext {
+            collection: Collection {
+                chains: by_id([chain]),
+                ..Collection::factory(())
+            }
+            .into(),
+            ..TemplateContext::factory(())
+        };
+        assert_eq!(
+            Template::from("{{add(chains.chain1, 1)}}")
+                .render_chunks(&context)
+                .await,
+            vec![TemplateChunk::Rendered {
+                value: Arc::new("4".into()),
+                sensitive: true
+            }]
+        );
+    }
+
     /// Test success cases with chained responses
     #[rstest]
     #[case::no_selector(
@@ -699,6 +1189,62 @@ mod tests {
         assert_eq!(render!("{{chains.chain1}}", context).unwrap(), "hello!");
     }
 
+    /// Triggered requests to a protected host are blocked, unless the engine
+    /// explicitly allows them
+    #[rstest]
+    #[case::blocked(false)]
+    #[case::allowed(true)]
+    #[tokio::test]
+    async fn test_triggered_request_protected(#[case] allow: bool) {
+        let server = MockServer::start().await;
+        let host = server.uri();
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path("/get"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("hello!"))
+            // The request should never reach the server if it's blocked
+            .expect(u64::from(allow))
+            .mount(&server)
+            .await;
+
+        let http_engine = HttpEngine::new(&HttpEngineConfig {
+            protected_hosts: vec!["127.0.0.1".into()],
+            ..Default::default()
+        })
+        .allow_protected_hosts(allow);
+        let recipe = Recipe {
+            url: format!("{host}/get").into(),
+            ..Recipe::factory(())
+        };
+        let chain = Chain {
+            source: ChainSource::Request {
+                recipe: recipe.id.clone(),
+                trigger: ChainRequestTrigger::Always,
+                section: Default::default(),
+            },
+            ..Chain::factory(())
+        };
+        let context = TemplateContext {
+            collection: Collection {
+                recipes: by_id([recipe]).into(),
+                chains: by_id([chain]),
+                ..Collection::factory(())
+            }
+            .into(),
+            http_engine: Some(http_engine),
+            ..TemplateContext::factory(())
+        };
+
+        let result = render!("{{chains.chain1}}", context);
+        if allow {
+            assert_eq!(result.unwrap(), "hello!");
+        } else {
+            assert_err!(
+                result,
+                "Triggered request to protected host `127.0.0.1` not allowed"
+            );
+        }
+    }
+
     /// Test success with chained command
     #[rstest]
     #[case::with_stdin(&["tail"], Some("hello!"), "hello!")]
@@ -802,6 +1348,69 @@ mod tests {
         assert_eq!(render!("{{chains.chain1}}", context).unwrap(), expected);
     }
 
+    /// Test decode pipeline on chained output
+    #[rstest]
+    #[case::none(&[], "aGVsbG8h", "aGVsbG8h")]
+    #[case::base64(&[DecodeStep::Base64], "aGVsbG8h", "hello!")]
+    #[case::json(&[DecodeStep::Json], r#""hello!""#, "hello!")]
+    #[case::json_object(&[DecodeStep::Json], r#"{"a": 1}"#, r#"{"a": 1}"#)]
+    #[case::base64_json(
+        &[DecodeStep::Base64, DecodeStep::Json],
+        "ImhlbGxvISI=",
+        "hello!",
+    )]
+    #[case::base64_gunzip(
+        &[DecodeStep::Base64, DecodeStep::Gunzip],
+        "H4sIAAAAAAACA8tIzcnJVwQAYMmGmgYAAAA=",
+        "hello!",
+    )]
+    #[tokio::test]
+    async fn test_chain_decode(
+        #[case] decode: &[DecodeStep],
+        #[case] output: &str,
+        #[case] expected: &str,
+    ) {
+        let chain = Chain {
+            source: ChainSource::command(["echo", "-n", output]),
+            decode: decode.to_vec(),
+            ..Chain::factory(())
+        };
+        let context = TemplateContext {
+            collection: Collection {
+                chains: by_id([chain]),
+                ..Collection::factory(())
+            }
+            .into(),
+            ..TemplateContext::factory(())
+        };
+
+        assert_eq!(render!("{{chains.chain1}}", context).unwrap(), expected);
+    }
+
+    /// Decode errors should say which step failed
+    #[tokio::test]
+    async fn test_chain_decode_error() {
+        let chain = Chain {
+            // "not json"
+            source: ChainSource::command(["echo", "-n", "bm90IGpzb24="]),
+            decode: vec![DecodeStep::Base64, DecodeStep::Json],
+            ..Chain::factory(())
+        };
+        let context = TemplateContext {
+            collection: Collection {
+                chains: by_id([chain]),
+                ..Collection::factory(())
+            }
+            .into(),
+            ..TemplateContext::factory(())
+        };
+
+        assert_err!(
+            render!("{{chains.chain1}}", context),
+            "Decode step 2 (`json`)"
+        );
+    }
+
     /// Test success with a chained environment variable
     #[rstest]
     #[case::present(Some("test!"), "test!")]
@@ -847,7 +1456,10 @@ mod tests {
         let path: Template = path.to_str().unwrap().into();
 
         let chain = Chain {
-            source: ChainSource::File { path: path.clone() },
+            source: ChainSource::File {
+                path: path.clone(),
+                relative_to: PathRoot::Collection,
+            },
             ..Chain::factory(())
         };
         let context = TemplateContext {
@@ -872,6 +1484,7 @@ mod tests {
         let chain = Chain {
             source: ChainSource::File {
                 path: "not-real".into(),
+                relative_to: PathRoot::Cwd,
             },
             ..Chain::factory(())
         };
@@ -920,6 +1533,47 @@ mod tests {
         assert_eq!(render!("{{chains.chain1}}", context).unwrap(), expected);
     }
 
+    /// A prompt cache shares answers across render groups, so the user is
+    /// only asked once per batch
+    #[rstest]
+    #[case::cached(true, "first")]
+    #[case::uncached(false, "second")]
+    #[tokio::test]
+    async fn test_chain_prompt_cache(
+        #[case] cached: bool,
+        #[case] expected: &str,
+    ) {
+        let collection: Arc<Collection> = Collection {
+            chains: by_id([Chain {
+                source: ChainSource::Prompt {
+                    message: Some("password".into()),
+                    default: None,
+                },
+                ..Chain::factory(())
+            }]),
+            ..Collection::factory(())
+        }
+        .into();
+        let prompt_cache = cached.then(PromptCache::default);
+        let context1 = TemplateContext {
+            collection: Arc::clone(&collection),
+            prompter: Box::new(TestPrompter::new(["first"])),
+            prompt_cache: prompt_cache.clone(),
+            ..TemplateContext::factory(())
+        };
+        // Each context is a separate render group, so the chain is evaluated
+        // again. Only the prompt cache is shared
+        let context2 = TemplateContext {
+            collection,
+            prompter: Box::new(TestPrompter::new(["second"])),
+            prompt_cache,
+            ..TemplateContext::factory(())
+        };
+
+        assert_eq!(render!("{{chains.chain1}}", context1).unwrap(), "first");
+        assert_eq!(render!("{{chains.chain1}}", context2).unwrap(), expected);
+    }
+
     /// Prompting gone wrong
     #[tokio::test]
     async fn test_chain_prompt_error() {
@@ -1237,6 +1891,10 @@ mod tests {
                 sensitive: true
             }]
         );
+        assert_eq!(
+            context.state.sensitive_values(),
+            vec![Arc::new(b"hello!".to_vec())]
+        );
     }
 
     /// Test linking two chains together. This example is contribed because the
@@ -1251,7 +1909,10 @@ mod tests {
         let path: Template = path.to_str().unwrap().into();
         let file_chain = Chain {
             id: "file".into(),
-            source: ChainSource::File { path },
+            source: ChainSource::File {
+                path,
+                relative_to: PathRoot::Collection,
+            },
             ..Chain::factory(())
         };
 
@@ -1288,6 +1949,7 @@ mod tests {
             id: "file".into(),
             source: ChainSource::File {
                 path: "bogus.txt".into(),
+                relative_to: PathRoot::Cwd,
             },
 
             ..Chain::factory(())
@@ -1416,6 +2078,36 @@ mod tests {
         );
     }
 
+    /// Progress should be reported once per key, with unrendered keys shown
+    /// as their source text
+    #[tokio::test]
+    async fn test_render_chunks_progress() {
+        let context =
+            profile_context(indexmap! { "user_id".into() => "🧡💛".into() });
+        let template = Template::from("intro {{user_id}} {{unknown}} outro");
+
+        // Count how many keys are still unrendered at each step
+        let mut pending = Vec::new();
+        let chunks = template
+            .render_chunks_progress(&context, |chunks| {
+                pending.push(
+                    chunks
+                        .iter()
+                        .filter(|chunk| {
+                            matches!(
+                                chunk,
+                                TemplateChunk::Raw(text)
+                                    if text.starts_with("{{")
+                            )
+                        })
+                        .count(),
+                );
+            })
+            .await;
+        assert_eq!(pending, vec![1, 0]);
+        assert_eq!(chunks, template.render_chunks(&context).await);
+    }
+
     /// Tested rendering a template with escaped keys, which should be treated
     /// as raw text
     #[tokio::test]
@@ -1430,7 +2122,9 @@ mod tests {
     }
 
     /// Build a template context that only has simple profile data
-    fn profile_context(data: IndexMap<String, Template>) -> TemplateContext {
+    fn profile_context(
+        data: IndexMap<String, ProfileValue>,
+    ) -> TemplateContext {
         let profile = Profile {
             data,
             ..Profile::factory(())
@@ -1506,6 +2200,21 @@ mod tests {
         );
     }
 
+    /// Chain references are found at the top level and inside function
+    /// arguments
+    #[rstest]
+    #[case::none("{{field}} {{env.ENV}}", &[])]
+    #[case::top_level("{{chains.a}}{{chains.b}}{{chains.a}}", &["a", "b", "a"])]
+    #[case::nested("{{add(div(chains.a, 2), field)}}", &["a"])]
+    fn test_chain_ids(
+        #[case] template: Template,
+        #[case] expected: &[&'static str],
+    ) {
+        let expected: Vec<ChainId> =
+            expected.iter().map(|id| (*id).into()).collect();
+        assert_eq!(template.chain_ids(), expected.iter().collect::<Vec<_>>());
+    }
+
     /// Helper for rendering a template to a string
     macro_rules! render {
         ($template:expr, $context:expr) => {
@@ -1,7 +1,7 @@
 use crate::{
-    collection::{ChainId, ProfileId, RecipeId},
+    collection::{ChainId, DecodeStep, ProfileId, RecipeId},
     http::{query::QueryError, RequestBuildError, RequestError},
-    template::TemplateKey,
+    template::{Function, FunctionError, TemplateKey},
     util::doc_link,
 };
 use itertools::Itertools;
@@ -77,6 +77,13 @@ pub enum TemplateError {
         #[source]
         error: ChainError,
     },
+
+    #[error("Calling function `{function}`")]
+    Function {
+        function: Function,
+        #[source]
+        error: FunctionError,
+    },
 }
 
 impl TemplateError {
@@ -148,6 +155,16 @@ pub enum ChainError {
         error: Arc<anyhow::Error>,
     },
 
+    /// A step in the chain's `decode` pipeline failed. The step number is
+    /// 1-based, to match the user's perspective
+    #[error("Decode step {step_number} (`{step}`)")]
+    Decode {
+        step_number: usize,
+        step: DecodeStep,
+        #[source]
+        error: Arc<anyhow::Error>,
+    },
+
     /// Got either 0 or 2+ results for JSON path query. This is generated by
     /// internal code so we don't need extra context
     #[error(transparent)]
@@ -214,6 +231,14 @@ pub enum TriggeredRequestError {
     #[error("Triggered request execution not allowed in this context")]
     NotAllowed,
 
+    /// The triggered request points to a protected host. These have to be
+    /// sent manually, so the user can confirm them
+    #[error(
+        "Triggered request to protected host `{host}` not allowed; send it \
+        manually instead"
+    )]
+    Protected { host: String },
+
     /// Tried to auto-execute a chained request but couldn't build it
     #[error(transparent)]
     Build(#[from] Arc<RequestBuildError>),
@@ -0,0 +1,1257 @@
+//! Template functions, e.g. `{{add(page, 1)}}`. Function arguments are
+//! evaluated to typed [Value]s, and functions return typed values. This allows
+//! function calls to be nested without losing type information. The final
+//! output of a call is converted to bytes when the template is rendered.
+
+use crate::{
+    collection::{serde_duration, ProfileId},
+    template::{TemplateError, TemplateKey},
+};
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use derive_more::Display;
+use fake::{
+    faker::{
+        address::en::{CityName, CountryName, StreetName, ZipCode},
+        company::en::CompanyName,
+        internet::en::{IPv4, SafeEmail, Username},
+        lorem::en::{Sentence, Word},
+        name::en::{FirstName, LastName, Name},
+        phone_number::en::PhoneNumber,
+    },
+    Fake,
+};
+use indexmap::IndexMap;
+use rand::{distributions::Alphanumeric, seq::SliceRandom, Rng};
+use std::{
+    fmt::{self, Write},
+    io,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+};
+use thiserror::Error;
+use url::Url;
+
+/// A call to a template function, e.g. `add(page, 1)`
+#[derive(Clone, Debug, Display, PartialEq)]
+#[display("{source}")]
+pub struct FunctionCall {
+    pub(super) function: Function,
+    pub(super) arguments: Vec<Expression>,
+    /// The exact text this was parsed from, so templates can be stringified
+    /// back to their source without having to normalize whitespace and quotes
+    pub(super) source: String,
+}
+
+/// An argument to a function call
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    /// A literal string, number, boolean, or null
+    Literal(Value),
+    /// Any template key: a field, chain, environment variable, or a nested
+    /// function call
+    Key(TemplateKey),
+}
+
+/// All available template functions
+#[derive(Copy, Clone, Debug, Display, PartialEq)]
+pub enum Function {
+    #[display("add")]
+    Add,
+    #[display("sub")]
+    Sub,
+    #[display("mul")]
+    Mul,
+    #[display("div")]
+    Div,
+    #[display("mod")]
+    Mod,
+    /// `if(condition, then, else)`. Arguments are evaluated lazily by the
+    /// renderer, so only the chosen branch is rendered
+    #[display("if")]
+    If,
+    /// `coalesce(a, b, ...)`: First non-empty argument. Arguments are
+    /// evaluated lazily by the renderer, in order
+    #[display("coalesce")]
+    Coalesce,
+    /// `profile(profile_id, field)`: A field from a specific profile,
+    /// regardless of which profile is selected. Needs access to the
+    /// collection, so it's evaluated by the renderer
+    #[display("profile")]
+    Profile,
+    /// `collection_path()`: Absolute path to the collection file. Evaluated by
+    /// the renderer, which has access to the path
+    #[display("collection_path")]
+    CollectionPath,
+    /// `collection_dir()`: Absolute path to the directory containing the
+    /// collection file. Evaluated by the renderer
+    #[display("collection_dir")]
+    CollectionDir,
+    /// `file(path)`: Contents of a file. Relative paths are resolved against
+    /// the collection directory. Evaluated by the renderer
+    #[display("file")]
+    File,
+    /// `files(pattern)`: Paths of all files matching a glob pattern. Evaluated
+    /// by the renderer
+    #[display("files")]
+    Files,
+    /// `env_file(path, key)`: A single value from a `.env`-style file.
+    /// Relative paths are resolved against the collection directory. Evaluated
+    /// by the renderer, which caches each parsed file within a render group
+    #[display("env_file")]
+    EnvFile,
+    /// `now()`: The current time. Evaluated fresh on every render, never
+    /// cached
+    #[display("now")]
+    Now,
+    /// `time_add(time, span)`: Shift a time by a [TimeSpan]
+    #[display("time_add")]
+    TimeAdd,
+    /// `format_time(time, format)`: Format a time as a string or timestamp
+    #[display("format_time")]
+    FormatTime,
+    /// `url_join(base, path)`: Join a path onto a base URL, without doubled
+    /// or missing slashes. See [url_join]
+    #[display("url_join")]
+    UrlJoin,
+    /// `random_int(min, max)`: Random integer in an inclusive range. Like all
+    /// random functions, this is evaluated fresh on every render
+    #[display("random_int")]
+    RandomInt,
+    /// `random_string(length)`: Random alphanumeric string
+    #[display("random_string")]
+    RandomString,
+    /// `random_uuid()`: Random v4 UUID
+    #[display("random_uuid")]
+    RandomUuid,
+    /// `random_choice(a, b, ...)`: One of the arguments, chosen at random. If
+    /// a single array is passed, choose one of its elements instead
+    #[display("random_choice")]
+    RandomChoice,
+    /// `fake(kind)`: Realistic fake data of a particular kind, e.g. `email`.
+    /// See [fake_value] for supported kinds
+    #[display("fake")]
+    Fake,
+}
+
+impl Function {
+    /// Get a function by its name in a template. Return `None` if there's no
+    /// function with the given name
+    pub(super) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "add" => Some(Self::Add),
+            "sub" => Some(Self::Sub),
+            "mul" => Some(Self::Mul),
+            "div" => Some(Self::Div),
+            "mod" => Some(Self::Mod),
+            "if" => Some(Self::If),
+            "coalesce" => Some(Self::Coalesce),
+            "profile" => Some(Self::Profile),
+            "collection_path" => Some(Self::CollectionPath),
+            "collection_dir" => Some(Self::CollectionDir),
+            "file" => Some(Self::File),
+            "files" => Some(Self::Files),
+            "env_file" => Some(Self::EnvFile),
+            "now" => Some(Self::Now),
+            "time_add" => Some(Self::TimeAdd),
+            "format_time" => Some(Self::FormatTime),
+            "url_join" => Some(Self::UrlJoin),
+            "random_int" => Some(Self::RandomInt),
+            "random_string" => Some(Self::RandomString),
+            "random_uuid" => Some(Self::RandomUuid),
+            "random_choice" => Some(Self::RandomChoice),
+            "fake" => Some(Self::Fake),
+            _ => None,
+        }
+    }
+
+    /// Call this function with fully evaluated arguments. Lazy functions
+    /// (`if` and `coalesce`) can't be called this way, because they need
+    /// control over which arguments get evaluated.
+    pub(super) fn call(
+        self,
+        arguments: Vec<Value>,
+    ) -> Result<Value, FunctionError> {
+        match self {
+            Self::Add => arithmetic(arguments, i64::checked_add, |a, b| a + b),
+            Self::Sub => arithmetic(arguments, i64::checked_sub, |a, b| a - b),
+            Self::Mul => arithmetic(arguments, i64::checked_mul, |a, b| a * b),
+            Self::Div => {
+                let [a, b] = arity(arguments)?;
+                match (a.to_number()?, b.to_number()?) {
+                    (_, Number::Integer(0)) => Err(FunctionError::DivideByZero),
+                    (_, Number::Float(b)) if b == 0.0 => {
+                        Err(FunctionError::DivideByZero)
+                    }
+                    // Only stay in integer land if the division is exact.
+                    // Otherwise `div(7, 2)` would give a surprising result
+                    (Number::Integer(a), Number::Integer(b))
+                        if a.checked_rem(b) == Some(0) =>
+                    {
+                        a.checked_div(b)
+                            .map(Value::Integer)
+                            .ok_or(FunctionError::Overflow)
+                    }
+                    (a, b) => Ok(Value::Float(a.as_f64() / b.as_f64())),
+                }
+            }
+            Self::Mod => {
+                let [a, b] = arity(arguments)?;
+                match (a.to_number()?, b.to_number()?) {
+                    (_, Number::Integer(0)) => Err(FunctionError::DivideByZero),
+                    (_, Number::Float(b)) if b == 0.0 => {
+                        Err(FunctionError::DivideByZero)
+                    }
+                    (Number::Integer(a), Number::Integer(b)) => a
+                        .checked_rem(b)
+                        .map(Value::Integer)
+                        .ok_or(FunctionError::Overflow),
+                    (a, b) => Ok(Value::Float(a.as_f64() % b.as_f64())),
+                }
+            }
+            Self::Now => {
+                let [] = arity(arguments)?;
+                Ok(Value::Time(Utc::now()))
+            }
+            Self::TimeAdd => {
+                let [time, span] = arity(arguments)?;
+                let TimeSpan(span) = span.to_time_span()?;
+                time.to_time()?
+                    .checked_add_signed(span)
+                    .map(Value::Time)
+                    .ok_or(FunctionError::Overflow)
+            }
+            Self::FormatTime => {
+                let [time, format] = arity(arguments)?;
+                format_time(time.to_time()?, &format.into_string()?)
+            }
+            Self::UrlJoin => {
+                let [base, path] = arity(arguments)?;
+                url_join(&base.into_string()?, &path.into_string()?)
+            }
+            Self::If
+            | Self::Coalesce
+            | Self::Profile
+            | Self::CollectionPath
+            | Self::CollectionDir
+            | Self::File
+            | Self::Files
+            | Self::EnvFile => {
+                unreachable!("`{self}` is evaluated by the renderer")
+            }
+            Self::RandomInt
+            | Self::RandomString
+            | Self::RandomUuid
+            | Self::RandomChoice
+            | Self::Fake => {
+                unreachable!("`{self}` must be called with an RNG")
+            }
+        }
+    }
+
+    /// Does this function generate random values? Random functions must be
+    /// called with [Self::call_random], so the RNG can be seeded
+    pub(super) fn is_random(self) -> bool {
+        matches!(
+            self,
+            Self::RandomInt
+                | Self::RandomString
+                | Self::RandomUuid
+                | Self::RandomChoice
+                | Self::Fake
+        )
+    }
+
+    /// Call a random function with fully evaluated arguments. All randomness
+    /// is drawn from the given RNG, so a seeded RNG gives reproducible output
+    pub(super) fn call_random(
+        self,
+        arguments: Vec<Value>,
+        rng: &mut impl Rng,
+    ) -> Result<Value, FunctionError> {
+        match self {
+            Self::RandomInt => {
+                let [min, max] = arity(arguments)?;
+                let (min, max) = (min.to_integer()?, max.to_integer()?);
+                if min > max {
+                    return Err(FunctionError::InvalidRange { min, max });
+                }
+                Ok(Value::Integer(rng.gen_range(min..=max)))
+            }
+            Self::RandomString => {
+                let [length] = arity(arguments)?;
+                let length = length.to_length()?;
+                let s = rng
+                    .sample_iter(Alphanumeric)
+                    .take(length)
+                    .map(char::from)
+                    .collect();
+                Ok(Value::String(s))
+            }
+            Self::RandomUuid => {
+                let [] = arity(arguments)?;
+                let uuid =
+                    uuid::Builder::from_random_bytes(rng.gen()).into_uuid();
+                Ok(Value::String(uuid.to_string()))
+            }
+            Self::RandomChoice => {
+                let choices = match <[Value; 1]>::try_from(arguments) {
+                    Ok([Value::Array(choices)]) => choices,
+                    Ok([value]) => vec![value],
+                    Err(arguments) => arguments,
+                };
+                choices
+                    .choose(rng)
+                    .cloned()
+                    .ok_or(FunctionError::EmptyChoice)
+            }
+            Self::Fake => {
+                let [kind] = arity(arguments)?;
+                fake_value(&kind.into_string()?, rng)
+            }
+            _ => unreachable!("`{self}` is not a random function"),
+        }
+    }
+}
+
+/// Apply a binary arithmetic operation. If both operands are integers, the
+/// integer operation is used. If either is a float, both are converted to
+/// floats.
+fn arithmetic(
+    arguments: Vec<Value>,
+    int_op: fn(i64, i64) -> Option<i64>,
+    float_op: fn(f64, f64) -> f64,
+) -> Result<Value, FunctionError> {
+    let [a, b] = arity(arguments)?;
+    match (a.to_number()?, b.to_number()?) {
+        (Number::Integer(a), Number::Integer(b)) => int_op(a, b)
+            .map(Value::Integer)
+            .ok_or(FunctionError::Overflow),
+        (a, b) => Ok(Value::Float(float_op(a.as_f64(), b.as_f64()))),
+    }
+}
+
+/// Format a time for `format_time`. A few well-known formats are supported by
+/// name; anything else is treated as a `strftime`-style format string.
+fn format_time(
+    time: DateTime<Utc>,
+    format: &str,
+) -> Result<Value, FunctionError> {
+    match format {
+        "iso8601" | "rfc3339" => Ok(Value::String(
+            time.to_rfc3339_opts(SecondsFormat::Secs, true),
+        )),
+        "rfc2822" => Ok(Value::String(time.to_rfc2822())),
+        "unix" => Ok(Value::Integer(time.timestamp())),
+        "unix_ms" => Ok(Value::Integer(time.timestamp_millis())),
+        _ => {
+            // Use write! instead of to_string(), because to_string() panics
+            // on an invalid format
+            let mut output = String::new();
+            write!(output, "{}", time.format(format)).map_err(|_| {
+                FunctionError::TimeFormat {
+                    format: format.to_owned(),
+                }
+            })?;
+            Ok(Value::String(output))
+        }
+    }
+}
+
+/// Join a path onto a base URL for `url_join`. The base is always treated as a
+/// directory, and leading slashes on the path are ignored, so the path is
+/// appended to the base path rather than replacing it:
+/// `url_join("https://example.com/v1", "/users")` gives
+/// `https://example.com/v1/users`. Otherwise this follows [Url::join], so
+/// `..` segments and query strings in the path work as expected, and a full
+/// URL replaces the base entirely.
+fn url_join(base: &str, path: &str) -> Result<Value, FunctionError> {
+    let mut base = Url::parse(base).map_err(|error| FunctionError::Url {
+        url: base.to_owned(),
+        error,
+    })?;
+    if !base.path().ends_with('/') {
+        let directory = format!("{}/", base.path());
+        base.set_path(&directory);
+    }
+    let joined = base.join(path.trim_start_matches('/')).map_err(|error| {
+        FunctionError::Url {
+            url: path.to_owned(),
+            error,
+        }
+    })?;
+    Ok(Value::String(joined.into()))
+}
+
+/// Generate a fake value for `fake()`. Kinds are named after the data they
+/// generate, e.g. `email` or `first_name`
+fn fake_value(kind: &str, rng: &mut impl Rng) -> Result<Value, FunctionError> {
+    let value: String = match kind {
+        "name" => Name().fake_with_rng(rng),
+        "first_name" => FirstName().fake_with_rng(rng),
+        "last_name" => LastName().fake_with_rng(rng),
+        "email" => SafeEmail().fake_with_rng(rng),
+        "username" => Username().fake_with_rng(rng),
+        "phone_number" => PhoneNumber().fake_with_rng(rng),
+        "company" => CompanyName().fake_with_rng(rng),
+        "street" => StreetName().fake_with_rng(rng),
+        "city" => CityName().fake_with_rng(rng),
+        "country" => CountryName().fake_with_rng(rng),
+        "zip_code" => ZipCode().fake_with_rng(rng),
+        "ipv4" => IPv4().fake_with_rng(rng),
+        "word" => Word().fake_with_rng(rng),
+        "sentence" => Sentence(4..10).fake_with_rng(rng),
+        _ => {
+            return Err(FunctionError::FakeKindUnknown {
+                kind: kind.to_owned(),
+            })
+        }
+    };
+    Ok(Value::String(value))
+}
+
+/// A signed span of time, e.g. `15m` or `-1d`. This uses the same format as
+/// durations elsewhere in the collection, plus an optional leading `-` to go
+/// backwards.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TimeSpan(TimeDelta);
+
+impl FromStr for TimeSpan {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, duration) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let duration = serde_duration::parse(duration).map_err(|_| ())?;
+        let delta = TimeDelta::from_std(duration).map_err(|_| ())?;
+        Ok(Self(if negative { -delta } else { delta }))
+    }
+}
+
+/// Unpack a fixed number of arguments, or return an error if the count is
+/// wrong
+fn arity<const N: usize>(
+    arguments: Vec<Value>,
+) -> Result<[Value; N], FunctionError> {
+    let actual = arguments.len();
+    arguments
+        .try_into()
+        .map_err(|_| FunctionError::ArgumentCount {
+            expected: N,
+            actual,
+        })
+}
+
+/// A dynamically typed value, produced by evaluating a function argument or
+/// calling a function
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    /// Output of a template render that isn't valid UTF-8, e.g. a binary file
+    Bytes(Vec<u8>),
+    Array(Vec<Self>),
+    Object(IndexMap<String, Self>),
+    /// A point in time, e.g. from `now()`. Rendered as RFC 3339
+    Time(DateTime<Utc>),
+}
+
+impl Value {
+    /// Convert rendered bytes into a value. Valid UTF-8 becomes a string,
+    /// anything else remains bytes.
+    pub(super) fn from_bytes(bytes: Vec<u8>) -> Self {
+        match String::from_utf8(bytes) {
+            Ok(s) => Self::String(s),
+            Err(error) => Self::Bytes(error.into_bytes()),
+        }
+    }
+
+    /// Convert this value to bytes for the final render output. Strings and
+    /// bytes are used as-is. Everything else is converted to JSON.
+    pub(super) fn into_bytes(self) -> Vec<u8> {
+        match self {
+            Self::String(s) => s.into_bytes(),
+            Self::Bytes(bytes) => bytes,
+            Self::Time(time) => {
+                time.to_rfc3339_opts(SecondsFormat::Secs, true).into_bytes()
+            }
+            other => serde_json::Value::from(other).to_string().into_bytes(),
+        }
+    }
+
+    /// Is this value truthy? `null`, `false`, and empty strings are falsy.
+    /// The string `"false"` is also falsy, because fields and chains always
+    /// render to strings, so a boolean from a template arrives as `"false"`.
+    /// Everything else is truthy, including `0` and empty arrays/objects.
+    pub(super) fn is_truthy(&self) -> bool {
+        match self {
+            Self::Boolean(b) => *b,
+            Self::String(s) if s == "false" => false,
+            other => !other.is_empty(),
+        }
+    }
+
+    /// Is this value empty? `null` and empty strings are empty. This is used by
+    /// `coalesce` to skip values that aren't set.
+    pub(super) fn is_empty(&self) -> bool {
+        match self {
+            Self::Null => true,
+            Self::String(s) => s.is_empty(),
+            Self::Bytes(bytes) => bytes.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Get this value as a string. Only strings are accepted, to avoid
+    /// surprising coercions for identifiers such as profile IDs
+    pub(super) fn into_string(self) -> Result<String, FunctionError> {
+        match self {
+            Self::String(s) => Ok(s),
+            other => Err(FunctionError::Type {
+                expected: "string",
+                actual: other.to_string(),
+            }),
+        }
+    }
+
+    /// Get this value as a time. Integers are treated as Unix timestamps (in
+    /// seconds), and strings are parsed as RFC 3339
+    fn to_time(&self) -> Result<DateTime<Utc>, FunctionError> {
+        let error = || FunctionError::Type {
+            expected: "time",
+            actual: self.to_string(),
+        };
+        match self {
+            Self::Time(time) => Ok(*time),
+            Self::Integer(timestamp) => {
+                DateTime::from_timestamp(*timestamp, 0).ok_or_else(error)
+            }
+            Self::String(s) => DateTime::parse_from_rfc3339(s.trim())
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|_| error()),
+            _ => Err(error()),
+        }
+    }
+
+    /// Get this value as a [TimeSpan]. Integers are treated as seconds
+    fn to_time_span(&self) -> Result<TimeSpan, FunctionError> {
+        let error = || FunctionError::Type {
+            expected: "time span (e.g. `15m`)",
+            actual: self.to_string(),
+        };
+        match self {
+            Self::Integer(seconds) => TimeDelta::try_seconds(*seconds)
+                .map(TimeSpan)
+                .ok_or_else(error),
+            Self::String(s) => s.parse().map_err(|()| error()),
+            _ => Err(error()),
+        }
+    }
+
+    /// Get this value as an integer. Strings will be parsed, but floats are
+    /// not accepted
+    fn to_integer(&self) -> Result<i64, FunctionError> {
+        match self.to_number() {
+            Ok(Number::Integer(i)) => Ok(i),
+            _ => Err(FunctionError::Type {
+                expected: "integer",
+                actual: self.to_string(),
+            }),
+        }
+    }
+
+    /// Get this value as a non-negative integer, e.g. for a length
+    fn to_length(&self) -> Result<usize, FunctionError> {
+        self.to_integer()?
+            .try_into()
+            .map_err(|_| FunctionError::Type {
+                expected: "non-negative integer",
+                actual: self.to_string(),
+            })
+    }
+
+    /// Get this value as a number. Strings will be parsed, integer first
+    /// then float
+    fn to_number(&self) -> Result<Number, FunctionError> {
+        let error = || FunctionError::Type {
+            expected: "number",
+            actual: self.to_string(),
+        };
+        match self {
+            Self::Integer(i) => Ok(Number::Integer(*i)),
+            Self::Float(f) => Ok(Number::Float(*f)),
+            Self::String(s) => {
+                let s = s.trim();
+                s.parse()
+                    .map(Number::Integer)
+                    .or_else(|_| s.parse().map(Number::Float))
+                    .map_err(|_| error())
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+/// Format a value for error messages
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(s) => write!(f, "string `{s}`"),
+            Self::Bytes(bytes) => write!(f, "{} bytes of binary", bytes.len()),
+            Self::Time(time) => write!(
+                f,
+                "time `{}`",
+                time.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ),
+            other => {
+                write!(f, "`{}`", serde_json::Value::from(other.clone()))
+            }
+        }
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::Bool(b) => Self::Boolean(b),
+            serde_json::Value::Number(number) => number
+                .as_i64()
+                .map(Self::Integer)
+                // This is infallible unless the arbitrary_precision feature
+                // is enabled
+                .unwrap_or_else(|| {
+                    Self::Float(number.as_f64().unwrap_or_default())
+                }),
+            serde_json::Value::String(s) => Self::String(s),
+            serde_json::Value::Array(array) => {
+                Self::Array(array.into_iter().map(Self::from).collect())
+            }
+            serde_json::Value::Object(object) => Self::Object(
+                object.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            ),
+        }
+    }
+}
+
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::Boolean(b) => Self::Bool(b),
+            Value::Integer(i) => i.into(),
+            // NaN and infinity aren't representable in JSON, they become null
+            Value::Float(f) => f.into(),
+            Value::String(s) => Self::String(s),
+            Value::Bytes(bytes) => {
+                Self::String(String::from_utf8_lossy(&bytes).into_owned())
+            }
+            Value::Array(array) => {
+                Self::Array(array.into_iter().map(Self::from).collect())
+            }
+            Value::Object(object) => Self::Object(
+                object.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            ),
+            Value::Time(time) => {
+                Self::String(time.to_rfc3339_opts(SecondsFormat::Secs, true))
+            }
+        }
+    }
+}
+
+/// A numeric value, for arithmetic
+#[derive(Copy, Clone, Debug)]
+enum Number {
+    Integer(i64),
+    Float(f64),
+}
+
+impl Number {
+    fn as_f64(self) -> f64 {
+        match self {
+            Self::Integer(i) => i as f64,
+            Self::Float(f) => f,
+        }
+    }
+}
+
+/// An error that occurred while calling a template function. This is always
+/// wrapped in [TemplateError::Function], which provides the function name.
+#[derive(Clone, Debug, Error)]
+#[cfg_attr(test, derive(PartialEq))]
+pub enum FunctionError {
+    /// Wrong number of arguments passed
+    #[error("Expected {expected} argument(s), got {actual}")]
+    ArgumentCount { expected: usize, actual: usize },
+
+    /// Not enough arguments passed to a variadic function
+    #[error("Expected at least {minimum} argument(s), got {actual}")]
+    ArgumentCountMin { minimum: usize, actual: usize },
+
+    /// Argument count outside the range accepted by a function with optional
+    /// arguments
+    #[error("Expected {minimum} to {maximum} argument(s), got {actual}")]
+    ArgumentCountRange {
+        minimum: usize,
+        maximum: usize,
+        actual: usize,
+    },
+
+    /// An argument failed to evaluate
+    #[error("Evaluating argument {index}")]
+    Argument {
+        /// 0-based index of the argument
+        index: usize,
+        #[source]
+        error: Box<TemplateError>,
+    },
+
+    /// An argument had the wrong type and couldn't be coerced
+    #[error("Expected {expected}, got {actual}")]
+    Type {
+        expected: &'static str,
+        actual: String,
+    },
+
+    #[error("Division by zero")]
+    DivideByZero,
+
+    #[error("Integer overflow")]
+    Overflow,
+
+    /// `random_int()` was given a minimum greater than its maximum
+    #[error("Invalid range: minimum {min} is greater than maximum {max}")]
+    InvalidRange { min: i64, max: i64 },
+
+    /// `random_choice()` was given nothing to choose from
+    #[error("Nothing to choose from")]
+    EmptyChoice,
+
+    /// `fake()` was given a kind that isn't supported
+    #[error("Unknown fake data kind `{kind}`")]
+    FakeKindUnknown { kind: String },
+
+    /// `format_time()` was given an invalid format string
+    #[error("Invalid time format `{format}`")]
+    TimeFormat { format: String },
+
+    /// `url_join()` was given an invalid base URL, or the joined URL was
+    /// invalid
+    #[error("Invalid URL `{url}`")]
+    Url {
+        url: String,
+        #[source]
+        error: url::ParseError,
+    },
+
+    /// `file()` or `files()` couldn't read a file
+    #[error("Reading file `{path}`")]
+    File {
+        path: PathBuf,
+        #[source]
+        error: SharedError<io::Error>,
+    },
+
+    /// `env_file()` couldn't read or parse a dotenv file
+    #[error("Loading env file `{path}`")]
+    EnvFile {
+        path: PathBuf,
+        #[source]
+        error: SharedError<dotenvy::Error>,
+    },
+
+    /// `env_file()` was given a key that isn't in the file
+    #[error("Unknown key `{key}` in env file `{path}`")]
+    EnvFileKeyUnknown { path: PathBuf, key: String },
+
+    /// `files()` was given an invalid glob pattern
+    #[error("Invalid glob pattern `{pattern}`")]
+    Glob {
+        pattern: String,
+        #[source]
+        error: SharedError<glob::PatternError>,
+    },
+
+    /// `profile()` referenced a profile that doesn't exist
+    #[error("Unknown profile `{profile_id}`")]
+    ProfileUnknown { profile_id: ProfileId },
+
+    /// `profile()` referenced a field that doesn't exist in the profile
+    #[error("Unknown field `{field}` in profile `{profile_id}`")]
+    ProfileFieldUnknown {
+        profile_id: ProfileId,
+        field: String,
+    },
+
+    /// Rendering the field referenced by `profile()` failed
+    #[error("Rendering field `{field}` of profile `{profile_id}`")]
+    ProfileFieldNested {
+        profile_id: ProfileId,
+        field: String,
+        #[source]
+        error: Box<TemplateError>,
+    },
+}
+
+/// A cloneable wrapper for an error type that doesn't implement `Clone` or
+/// `PartialEq`. In tests, two wrappers are equal only if they share the same
+/// inner error.
+#[derive(Debug)]
+pub struct SharedError<E>(Arc<E>);
+
+impl<E> Clone for SharedError<E> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<E> From<E> for SharedError<E> {
+    fn from(error: E) -> Self {
+        Self(Arc::new(error))
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for SharedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<E: std::error::Error> std::error::Error for SharedError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+#[cfg(test)]
+impl<E> PartialEq for SharedError<E> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_err;
+    use rand::{rngs::StdRng, SeedableRng};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::add(Function::Add, 3.into(), 2.into(), Value::Integer(5))]
+    #[case::add_float(Function::Add, 3.into(), 0.5.into(), Value::Float(3.5))]
+    #[case::add_string(Function::Add, "3".into(), " 2 ".into(), Value::Integer(5))]
+    #[case::sub(Function::Sub, 3.into(), 5.into(), Value::Integer(-2))]
+    #[case::mul(Function::Mul, 3.into(), 5.into(), Value::Integer(15))]
+    #[case::mul_float(Function::Mul, "1.5".into(), 2.into(), Value::Float(3.0))]
+    #[case::div_exact(Function::Div, 6.into(), 2.into(), Value::Integer(3))]
+    #[case::div_inexact(Function::Div, 7.into(), 2.into(), Value::Float(3.5))]
+    #[case::modulo(Function::Mod, 7.into(), 3.into(), Value::Integer(1))]
+    #[case::mod_float(Function::Mod, 7.5.into(), 2.into(), Value::Float(1.5))]
+    fn test_arithmetic(
+        #[case] function: Function,
+        #[case] a: Value,
+        #[case] b: Value,
+        #[case] expected: Value,
+    ) {
+        assert_eq!(function.call(vec![a, b]).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::too_few(Function::Add, vec![1.into()], "Expected 2 argument(s), got 1")]
+    #[case::too_many(
+        Function::Add,
+        vec![1.into(), 2.into(), 3.into()],
+        "Expected 2 argument(s), got 3"
+    )]
+    #[case::not_a_number(
+        Function::Mul,
+        vec!["three".into(), 2.into()],
+        "Expected number, got string `three`"
+    )]
+    #[case::bool(
+        Function::Sub,
+        vec![true.into(), 2.into()],
+        "Expected number, got `true`"
+    )]
+    #[case::div_zero(Function::Div, vec![1.into(), 0.into()], "Division by zero")]
+    #[case::div_zero_float(
+        Function::Div,
+        vec![1.into(), 0.0.into()],
+        "Division by zero"
+    )]
+    #[case::mod_zero(Function::Mod, vec![1.into(), 0.into()], "Division by zero")]
+    #[case::overflow(
+        Function::Add,
+        vec![i64::MAX.into(), 1.into()],
+        "Integer overflow"
+    )]
+    fn test_call_error(
+        #[case] function: Function,
+        #[case] arguments: Vec<Value>,
+        #[case] expected_error: &str,
+    ) {
+        assert_err!(function.call(arguments), expected_error);
+    }
+
+    #[test]
+    fn test_now() {
+        let before = Utc::now();
+        let Value::Time(now) = Function::Now.call(vec![]).unwrap() else {
+            panic!("Expected time")
+        };
+        assert!(before <= now && now <= Utc::now());
+    }
+
+    #[rstest]
+    #[case::iso(
+        Function::TimeAdd,
+        vec!["2024-01-01T00:00:00Z".into(), "15m".into()],
+        time("2024-01-01T00:15:00Z")
+    )]
+    #[case::offset(
+        Function::TimeAdd,
+        vec!["2024-01-01T02:00:00+02:00".into(), "-1d".into()],
+        time("2023-12-31T00:00:00Z")
+    )]
+    #[case::unix_seconds(
+        Function::TimeAdd,
+        vec![1_704_067_200.into(), 90.into()],
+        time("2024-01-01T00:01:30Z")
+    )]
+    #[case::format_iso(
+        Function::FormatTime,
+        vec![time("2024-01-01T00:15:00Z"), "iso8601".into()],
+        "2024-01-01T00:15:00Z".into()
+    )]
+    #[case::format_rfc2822(
+        Function::FormatTime,
+        vec![time("2024-01-01T00:15:00Z"), "rfc2822".into()],
+        "Mon, 1 Jan 2024 00:15:00 +0000".into()
+    )]
+    #[case::format_unix(
+        Function::FormatTime,
+        vec![time("2024-01-01T00:00:00Z"), "unix".into()],
+        1_704_067_200.into()
+    )]
+    #[case::format_unix_ms(
+        Function::FormatTime,
+        vec![time("2024-01-01T00:00:01Z"), "unix_ms".into()],
+        1_704_067_201_000_i64.into()
+    )]
+    #[case::format_strftime(
+        Function::FormatTime,
+        vec!["2024-01-01T00:15:00Z".into(), "%Y/%m/%d %H:%M".into()],
+        "2024/01/01 00:15".into()
+    )]
+    fn test_time(
+        #[case] function: Function,
+        #[case] arguments: Vec<Value>,
+        #[case] expected: Value,
+    ) {
+        assert_eq!(function.call(arguments).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::not_a_time(
+        Function::TimeAdd,
+        vec!["yesterday".into(), "1h".into()],
+        "Expected time, got string `yesterday`"
+    )]
+    #[case::not_a_span(
+        Function::TimeAdd,
+        vec![time("2024-01-01T00:00:00Z"), "1 hour".into()],
+        "Expected time span (e.g. `15m`), got string `1 hour`"
+    )]
+    #[case::bad_format(
+        Function::FormatTime,
+        vec![time("2024-01-01T00:00:00Z"), "%Q".into()],
+        "Invalid time format `%Q`"
+    )]
+    #[case::now_args(Function::Now, vec![1.into()], "Expected 0 argument(s), got 1")]
+    fn test_time_error(
+        #[case] function: Function,
+        #[case] arguments: Vec<Value>,
+        #[case] expected_error: &str,
+    ) {
+        assert_err!(function.call(arguments), expected_error);
+    }
+
+    #[rstest]
+    #[case::trailing_slash(
+        "https://example.com/",
+        "users",
+        "https://example.com/users"
+    )]
+    #[case::double_slash(
+        "https://example.com/",
+        "/users",
+        "https://example.com/users"
+    )]
+    #[case::missing_slash(
+        "https://example.com",
+        "users",
+        "https://example.com/users"
+    )]
+    #[case::base_path(
+        "https://example.com/v1",
+        "/users/1",
+        "https://example.com/v1/users/1"
+    )]
+    #[case::query(
+        "https://example.com/v1/?ignored=1",
+        "users?page=2",
+        "https://example.com/v1/users?page=2"
+    )]
+    #[case::parent(
+        "https://example.com/v1/",
+        "../v2/users",
+        "https://example.com/v2/users"
+    )]
+    #[case::absolute(
+        "https://example.com/v1",
+        "http://localhost:3000/users",
+        "http://localhost:3000/users"
+    )]
+    #[case::empty_path("https://example.com/v1", "", "https://example.com/v1/")]
+    fn test_url_join(
+        #[case] base: &str,
+        #[case] path: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            Function::UrlJoin
+                .call(vec![base.into(), path.into()])
+                .unwrap(),
+            expected.into()
+        );
+    }
+
+    #[rstest]
+    #[case::relative_base(vec!["/v1".into(), "users".into()], "Invalid URL `/v1`")]
+    #[case::no_scheme(
+        vec!["example.com".into(), "users".into()],
+        "Invalid URL `example.com`"
+    )]
+    #[case::args(vec!["https://example.com".into()], "Expected 2 argument(s), got 1")]
+    fn test_url_join_error(
+        #[case] arguments: Vec<Value>,
+        #[case] expected_error: &str,
+    ) {
+        assert_err!(Function::UrlJoin.call(arguments), expected_error);
+    }
+
+    /// Random functions can't be checked for exact output, but we can check
+    /// that the output falls within the expected bounds
+    #[test]
+    fn test_random() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let Value::Integer(i) = Function::RandomInt
+                .call_random(vec![(-2).into(), "2".into()], &mut rng)
+                .unwrap()
+            else {
+                panic!("Expected integer")
+            };
+            assert!((-2..=2).contains(&i));
+        }
+        assert_eq!(
+            Function::RandomInt
+                .call_random(vec![3.into(), 3.into()], &mut rng)
+                .unwrap(),
+            Value::Integer(3)
+        );
+
+        let Value::String(s) = Function::RandomString
+            .call_random(vec![12.into()], &mut rng)
+            .unwrap()
+        else {
+            panic!("Expected string")
+        };
+        assert_eq!(s.len(), 12);
+        assert!(s.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        let Value::String(uuid) =
+            Function::RandomUuid.call_random(vec![], &mut rng).unwrap()
+        else {
+            panic!("Expected string")
+        };
+        assert!(uuid::Uuid::parse_str(&uuid).is_ok());
+
+        let choice = Function::RandomChoice
+            .call_random(vec!["a".into(), "b".into()], &mut rng)
+            .unwrap();
+        assert!(choice == "a".into() || choice == "b".into());
+        // A single array is unpacked
+        assert_eq!(
+            Function::RandomChoice
+                .call_random(vec![Value::Array(vec![1.into()])], &mut rng)
+                .unwrap(),
+            Value::Integer(1)
+        );
+
+        let Value::String(email) = Function::Fake
+            .call_random(vec!["email".into()], &mut rng)
+            .unwrap()
+        else {
+            panic!("Expected string")
+        };
+        assert!(email.contains('@'));
+    }
+
+    /// Seeding the RNG makes random functions deterministic
+    #[rstest]
+    #[case::int(Function::RandomInt, vec![0.into(), 1_000_000.into()])]
+    #[case::string(Function::RandomString, vec![16.into()])]
+    #[case::uuid(Function::RandomUuid, vec![])]
+    #[case::choice(
+        Function::RandomChoice,
+        vec!["a".into(), "b".into(), "c".into(), "d".into()]
+    )]
+    #[case::fake(Function::Fake, vec!["name".into()])]
+    fn test_random_seeded(
+        #[case] function: Function,
+        #[case] arguments: Vec<Value>,
+    ) {
+        let generate = || {
+            let mut rng = StdRng::seed_from_u64(1234);
+            (0..5)
+                .map(|_| function.call_random(arguments.clone(), &mut rng))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+        assert_eq!(generate(), generate());
+    }
+
+    #[rstest]
+    #[case::range(
+        Function::RandomInt,
+        vec![5.into(), 1.into()],
+        "Invalid range: minimum 5 is greater than maximum 1"
+    )]
+    #[case::float(
+        Function::RandomInt,
+        vec![0.into(), 1.5.into()],
+        "Expected integer, got `1.5`"
+    )]
+    #[case::negative_length(
+        Function::RandomString,
+        vec![(-1).into()],
+        "Expected non-negative integer, got `-1`"
+    )]
+    #[case::no_choices(Function::RandomChoice, vec![], "Nothing to choose from")]
+    #[case::empty_array(
+        Function::RandomChoice,
+        vec![Value::Array(vec![])],
+        "Nothing to choose from"
+    )]
+    #[case::unknown_fake(
+        Function::Fake,
+        vec!["dinosaur".into()],
+        "Unknown fake data kind `dinosaur`"
+    )]
+    fn test_random_error(
+        #[case] function: Function,
+        #[case] arguments: Vec<Value>,
+        #[case] expected_error: &str,
+    ) {
+        assert_err!(
+            function.call_random(arguments, &mut rand::thread_rng()),
+            expected_error
+        );
+    }
+
+    #[rstest]
+    #[case::seconds("30s", Some(30_000))]
+    #[case::milliseconds("250ms", Some(250))]
+    #[case::hours("90h", Some(90 * 60 * 60 * 1000))]
+    #[case::negative("-2d", Some(-2 * 24 * 60 * 60 * 1000))]
+    #[case::empty("", None)]
+    #[case::sign_only("-", None)]
+    #[case::no_unit("15", None)]
+    #[case::no_quantity("m", None)]
+    #[case::unknown_unit("3y", None)]
+    #[case::compound("1h30m", None)]
+    #[case::overflow("9999999999999999d", None)]
+    fn test_parse_time_span(
+        #[case] input: &str,
+        #[case] expected_milliseconds: Option<i64>,
+    ) {
+        assert_eq!(
+            input.parse::<TimeSpan>().ok(),
+            expected_milliseconds
+                .map(|ms| TimeSpan(TimeDelta::try_milliseconds(ms).unwrap()))
+        );
+    }
+
+    fn time(s: &str) -> Value {
+        Value::Time(
+            DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc),
+        )
+    }
+
+    #[rstest]
+    #[case::null(Value::Null, false)]
+    #[case::false_(false.into(), false)]
+    #[case::true_(true.into(), true)]
+    #[case::empty_string("".into(), false)]
+    #[case::string("hello".into(), true)]
+    #[case::false_string("false".into(), false)]
+    #[case::true_string("true".into(), true)]
+    #[case::false_string_case("False".into(), true)]
+    #[case::empty_bytes(Value::Bytes(vec![]), false)]
+    #[case::zero(0.into(), true)]
+    #[case::empty_array(Value::Array(vec![]), true)]
+    fn test_is_truthy(#[case] value: Value, #[case] expected: bool) {
+        assert_eq!(value.is_truthy(), expected);
+    }
+
+    #[rstest]
+    #[case::null(Value::Null, "null")]
+    #[case::int(Value::Integer(-3), "-3")]
+    #[case::float(Value::Float(2.5), "2.5")]
+    #[case::string(Value::String("hello".into()), "hello")]
+    #[case::array(
+        Value::Array(vec![1.into(), "two".into()]),
+        r#"[1,"two"]"#
+    )]
+    #[case::time(time("2024-01-01T00:15:00Z"), "2024-01-01T00:15:00Z")]
+    fn test_into_bytes(#[case] value: Value, #[case] expected: &str) {
+        assert_eq!(value.into_bytes(), expected.as_bytes());
+    }
+
+    impl From<i64> for Value {
+        fn from(value: i64) -> Self {
+            Self::Integer(value)
+        }
+    }
+
+    impl From<i32> for Value {
+        fn from(value: i32) -> Self {
+            Self::Integer(value.into())
+        }
+    }
+
+    impl From<f64> for Value {
+        fn from(value: f64) -> Self {
+            Self::Float(value)
+        }
+    }
+
+    impl From<bool> for Value {
+        fn from(value: bool) -> Self {
+            Self::Boolean(value)
+        }
+    }
+
+    impl From<&str> for Value {
+        fn from(value: &str) -> Self {
+            Self::String(value.into())
+        }
+    }
+}
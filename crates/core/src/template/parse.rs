@@ -2,7 +2,11 @@
 
 use crate::{
     collection::ChainId,
-    template::{error::TemplateParseError, Identifier, Template, TemplateKey},
+    template::{
+        error::TemplateParseError,
+        function::{Expression, Function, FunctionCall, Value},
+        Identifier, Template, TemplateKey,
+    },
 };
 #[cfg(test)]
 use proptest::strategy::Strategy;
@@ -14,20 +18,22 @@ use std::{
     sync::{Arc, LazyLock},
 };
 use winnow::{
+    ascii::{digit1, multispace0},
     combinator::{
-        alt, cut_err, eof, not, peek, preceded, repeat, repeat_till, terminated,
+        alt, cut_err, delimited, empty, eof, not, opt, peek, preceded, repeat,
+        repeat_till, separated, terminated,
     },
     error::StrContext,
-    token::{any, take_while},
+    token::{any, take_till, take_while},
     PResult, Parser,
 };
 
 /// Character used to escape key openings
 const ESCAPE: &str = "_";
 /// Marks the start of a template key
-const KEY_OPEN: &str = "{{";
+pub(super) const KEY_OPEN: &str = "{{";
 /// Marks the end of a template key
-const KEY_CLOSE: &str = "}}";
+pub(super) const KEY_CLOSE: &str = "}}";
 // Export these so they can be used in TemplateKey's Display impl
 pub const CHAIN_PREFIX: &str = "chains.";
 pub const ENV_PREFIX: &str = "env.";
@@ -238,6 +244,7 @@ fn key(input: &mut &str) -> PResult<TemplateKey> {
 /// Parse the contents of a key (inside the `{{ }}`)
 fn key_contents(input: &mut &str) -> PResult<TemplateKey> {
     alt((
+        call.map(TemplateKey::Call),
         preceded(
             CHAIN_PREFIX,
             identifier.map(|id| TemplateKey::Chain(id.into())),
@@ -252,6 +259,80 @@ fn key_contents(input: &mut &str) -> PResult<TemplateKey> {
     .parse_next(input)
 }
 
+/// Parse a function call, e.g. `add(page, 1)`. Arguments can be literals or
+/// any other template key, including nested function calls. Whitespace is
+/// allowed around arguments.
+fn call(input: &mut &str) -> PResult<FunctionCall> {
+    let start = *input;
+    let name = terminated(identifier, '(').parse_next(input)?;
+    // Once we've seen the opening paren, this is definitely a function call
+    // so any errors are fatal
+    let function = cut_err(
+        empty
+            .verify_map(|()| Function::from_name(&name))
+            .context(StrContext::Label("function")),
+    )
+    .parse_next(input)?;
+    let arguments = cut_err(terminated(
+        separated(0.., delimited(multispace0, expression, multispace0), ','),
+        ')',
+    ))
+    .context(StrContext::Label("function arguments"))
+    .parse_next(input)?;
+    let source = start[..start.len() - input.len()].to_owned();
+    Ok(FunctionCall {
+        function,
+        arguments,
+        source,
+    })
+}
+
+/// Parse a function argument
+fn expression(input: &mut &str) -> PResult<Expression> {
+    alt((
+        literal.map(Expression::Literal),
+        key_contents.map(Expression::Key),
+    ))
+    .context(StrContext::Label("expression"))
+    .parse_next(input)
+}
+
+/// Parse a literal value in a function argument: a number, quoted string,
+/// boolean, or null. Strings can be quoted with `'` or `"`, and there are no
+/// escape sequences.
+fn literal(input: &mut &str) -> PResult<Value> {
+    // Keywords and numbers can't be followed by more identifier characters,
+    // otherwise they're a field name
+    fn end_of_token(input: &mut &str) -> PResult<()> {
+        not(take_while(1, Identifier::is_char_allowed)).parse_next(input)
+    }
+
+    alt((
+        // Float has to go first, otherwise we'd grab just the integer part
+        terminated((opt('-'), digit1, '.', digit1).take(), end_of_token)
+            .try_map(str::parse::<f64>)
+            .map(Value::Float),
+        terminated((opt('-'), digit1).take(), end_of_token)
+            .try_map(str::parse::<i64>)
+            .map(Value::Integer),
+        terminated(
+            alt((
+                "true".value(Value::Boolean(true)),
+                "false".value(Value::Boolean(false)),
+                "null".value(Value::Null),
+            )),
+            end_of_token,
+        ),
+        alt((
+            delimited('\'', take_till(0.., '\''), '\''),
+            delimited('"', take_till(0.., '"'), '"'),
+        ))
+        .map(|s: &str| Value::String(s.to_owned())),
+    ))
+    .context(StrContext::Label("literal"))
+    .parse_next(input)
+}
+
 /// Parse a field name/chain ID/env variable etc, inside a key. See [Identifier]
 /// for the definition of allowed syntax.
 fn identifier(input: &mut &str) -> PResult<Identifier> {
@@ -376,6 +457,88 @@ mod tests {
         assert_err!(template.parse::<Template>(), expected_error);
     }
 
+    /// Function calls should parse into the correct structure, and stringify
+    /// back to exactly the source text
+    #[rstest]
+    #[case::empty_args("{{add()}}", Function::Add, vec![])]
+    #[case::literals(
+        r#"{{sub(1, -2.5,'a' , "b",true,false,null)}}"#,
+        Function::Sub,
+        vec![
+            Expression::Literal(Value::Integer(1)),
+            Expression::Literal(Value::Float(-2.5)),
+            Expression::Literal(Value::String("a".into())),
+            Expression::Literal(Value::String("b".into())),
+            Expression::Literal(Value::Boolean(true)),
+            Expression::Literal(Value::Boolean(false)),
+            Expression::Literal(Value::Null),
+        ],
+    )]
+    #[case::keys(
+        "{{mul(field, chains.chain1, env.ENV, 1abc, true_)}}",
+        Function::Mul,
+        vec![
+            Expression::Key(TemplateKey::Field("field".into())),
+            Expression::Key(TemplateKey::Chain("chain1".into())),
+            Expression::Key(TemplateKey::Environment("ENV".into())),
+            // These look like literals but they're actually field names
+            Expression::Key(TemplateKey::Field("1abc".into())),
+            Expression::Key(TemplateKey::Field("true_".into())),
+        ],
+    )]
+    #[case::nested(
+        "{{add(div(a, 2), 1)}}",
+        Function::Add,
+        vec![
+            Expression::Key(TemplateKey::Call(FunctionCall {
+                function: Function::Div,
+                arguments: vec![
+                    Expression::Key(TemplateKey::Field("a".into())),
+                    Expression::Literal(Value::Integer(2)),
+                ],
+                source: "div(a, 2)".into(),
+            })),
+            Expression::Literal(Value::Integer(1)),
+        ],
+    )]
+    fn test_parse_call(
+        #[case] input: &str,
+        #[case] function: Function,
+        #[case] arguments: Vec<Expression>,
+    ) {
+        let source = input
+            .strip_prefix(KEY_OPEN)
+            .and_then(|s| s.strip_suffix(KEY_CLOSE))
+            .unwrap();
+        let expected =
+            tmpl([TemplateInputChunk::Key(TemplateKey::Call(FunctionCall {
+                function,
+                arguments,
+                source: source.into(),
+            }))]);
+        let parsed: Template = input.parse().expect("Parsing failed");
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed.display(), input);
+    }
+
+    /// A field can share a name with a function, as long as it isn't called
+    #[test]
+    fn test_parse_field_function_name() {
+        let parsed: Template = "{{add}}".parse().unwrap();
+        assert_eq!(parsed, tmpl([key_field("add")]));
+    }
+
+    #[rstest]
+    #[case::unknown_function("{{bogus(1)}}", "invalid function")]
+    #[case::unclosed_call("{{add(1, 2}}", "invalid function arguments")]
+    #[case::unclosed_string("{{add('1, 2)}}", "invalid function arguments")]
+    fn test_parse_call_error(
+        #[case] template: &str,
+        #[case] expected_error: &str,
+    ) {
+        assert_err!(template.parse::<Template>(), expected_error);
+    }
+
     /// Test that [Template::from_field] generates the correct template
     #[test]
     fn test_from_field() {
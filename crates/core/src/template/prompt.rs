@@ -1,7 +1,7 @@
-use crate::util::ResultTraced;
+use crate::util::{FutureCache, ResultTraced};
 use anyhow::anyhow;
 use derive_more::From;
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 use tokio::sync::oneshot;
 
 /// A prompter is a bridge between the user and the template engine. It enables
@@ -66,3 +66,10 @@ impl<T> PromptChannel<T> {
             .traced();
     }
 }
+
+/// Answers to prompts, shared across a batch of renders (e.g. every request in
+/// a folder run). Each prompt is only asked once per batch, and the answer is
+/// reused for any other prompt with the same message. The cache lives as long
+/// as the batch holds onto it, so just drop it when the batch is done.
+#[derive(Clone, Debug, Default)]
+pub struct PromptCache(pub(super) Arc<FutureCache<String, Option<String>>>);
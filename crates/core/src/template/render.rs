@@ -3,21 +3,36 @@
 use crate::{
     collection::{
         ChainId, ChainOutputTrim, ChainRequestSection, ChainRequestTrigger,
-        ChainSource, RecipeId, SelectOptions,
+        ChainSource, DecodeStep, PathRoot, ProfileId, ProfileValue, RecipeId,
+        SelectOptions,
     },
     http::{content_type::ContentType, Exchange, RequestSeed, ResponseRecord},
     template::{
-        error::TriggeredRequestError, parse::TemplateInputChunk, ChainError,
-        Prompt, Select, Template, TemplateChunk, TemplateContext,
-        TemplateError, TemplateKey,
+        error::TriggeredRequestError,
+        function::{Expression, FunctionCall, Value},
+        parse::{TemplateInputChunk, KEY_CLOSE, KEY_OPEN},
+        ChainError, Function, FunctionError, Prompt, PromptCache, Select,
+        Template, TemplateChunk, TemplateContext, TemplateError, TemplateKey,
     },
     util::{paths::expand_home, FutureCache, FutureCacheOutcome, ResultTraced},
 };
 use async_trait::async_trait;
+use base64::{prelude::BASE64_STANDARD, Engine};
 use chrono::Utc;
-use futures::future;
-use std::{env, path::PathBuf, process::Stdio, sync::Arc};
-use tokio::{fs, io::AsyncWriteExt, process::Command, sync::oneshot};
+use flate2::read::GzDecoder;
+use futures::{future, stream::FuturesUnordered, Future, StreamExt};
+use indexmap::IndexMap;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    collections::BTreeMap,
+    env,
+    io::Read,
+    panic,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, Mutex, OnceLock},
+};
+use tokio::{fs, io::AsyncWriteExt, process::Command, sync::oneshot, task};
 use tracing::{debug, debug_span, error, instrument, trace, trace_span};
 
 /// Outcome of rendering a single chunk. This allows attaching some metadata to
@@ -35,6 +50,15 @@ struct RenderedChunk {
 
 type TemplateResult = Result<RenderedChunk, TemplateError>;
 
+/// Outcome of evaluating a function argument. This is similar to
+/// [RenderedChunk], but the value retains its type so it can be passed between
+/// functions.
+#[derive(Clone, Debug)]
+struct EvaluatedValue {
+    value: Value,
+    sensitive: bool,
+}
+
 impl Template {
     /// Render the template using values from the given context. If any chunk
     /// failed to render, return an error. The template is rendered as bytes.
@@ -71,6 +95,50 @@ impl Template {
             .await
     }
 
+    /// Render the template string like [Self::render_chunks], but report
+    /// progress as each key finishes rendering. After each key resolves, the
+    /// callback is called with the full list of chunks. Keys that are still
+    /// being rendered appear as raw chunks of their source text (e.g.
+    /// `{{username}}`). The chunks passed to the final call match the return
+    /// value. This is useful for previews of templates that take a while to
+    /// render (e.g. multiple chained requests), so each value can be shown as
+    /// soon as it's available.
+    pub async fn render_chunks_progress(
+        &self,
+        context: &TemplateContext,
+        mut on_progress: impl FnMut(&[TemplateChunk]),
+    ) -> Vec<TemplateChunk> {
+        let stack = RenderKeyStack::default();
+        let mut chunks: Vec<TemplateChunk> = self
+            .chunks
+            .iter()
+            .map(|chunk| match chunk {
+                TemplateInputChunk::Raw(text) => {
+                    TemplateChunk::Raw(Arc::clone(text))
+                }
+                TemplateInputChunk::Key(key) => TemplateChunk::Raw(
+                    format!("{KEY_OPEN}{key}{KEY_CLOSE}").into(),
+                ),
+            })
+            .collect();
+
+        // Raw chunks are already done, so only keys need to be awaited
+        let mut futures: FuturesUnordered<_> = self
+            .chunk_futures(context, &stack)
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                matches!(self.chunks[*i], TemplateInputChunk::Key(_))
+            })
+            .map(|(i, future)| async move { (i, future.await) })
+            .collect();
+        while let Some((i, chunk)) = futures.next().await {
+            chunks[i] = chunk;
+            on_progress(&chunks);
+        }
+        chunks
+    }
+
     /// Internal version of [Self::render] with local render state
     async fn render_impl<'a>(
         &'a self,
@@ -122,6 +190,17 @@ impl Template {
         context: &'a TemplateContext,
         stack: &mut RenderKeyStack<'a>,
     ) -> Vec<TemplateChunk> {
+        // Parallelization!
+        future::join_all(self.chunk_futures(context, stack)).await
+    }
+
+    /// Get a future to render each chunk in the template. The raw text chunks
+    /// will be mapped 1:1. Each future gets its own fork of the render stack.
+    fn chunk_futures<'a>(
+        &'a self,
+        context: &'a TemplateContext,
+        stack: &RenderKeyStack<'a>,
+    ) -> Vec<impl 'a + Future<Output = TemplateChunk>> {
         async fn render_key<'a>(
             key: &'a TemplateKey,
             context: &'a TemplateContext,
@@ -137,7 +216,7 @@ impl Template {
                 Some(value) => {
                     trace!(
                         key = raw,
-                        value,
+                        ?value,
                         "Rendered template key from override"
                     );
                     Ok(RenderedChunk {
@@ -163,26 +242,26 @@ impl Template {
             }
         }
 
-        // Map over each parsed chunk, and render the keys into strings. The
-        // raw text chunks will be mapped 1:1. This clone is pretty cheap
-        // because raw text uses Arc and keys just contain metadata
-        let futures = self.chunks.iter().map(|chunk| {
-            // Fork the local state, one copy for each new branch we're spawning
-            let mut stack = stack.clone();
-            async move {
-                match chunk {
-                    TemplateInputChunk::Raw(text) => {
-                        TemplateChunk::Raw(Arc::clone(text))
-                    }
-                    TemplateInputChunk::Key(key) => {
-                        render_key(key, context, &mut stack).await.into()
+        // This clone is pretty cheap because raw text uses Arc and keys just
+        // contain metadata
+        self.chunks
+            .iter()
+            .map(|chunk| {
+                // Fork the local state, one copy for each new branch we're
+                // spawning
+                let mut stack = stack.clone();
+                async move {
+                    match chunk {
+                        TemplateInputChunk::Raw(text) => {
+                            TemplateChunk::Raw(Arc::clone(text))
+                        }
+                        TemplateInputChunk::Key(key) => {
+                            render_key(key, context, &mut stack).await.into()
+                        }
                     }
                 }
-            }
-        });
-
-        // Parallelization!
-        future::join_all(futures).await
+            })
+            .collect()
     }
 
     /// Render a template whose result will be used as configuration for a
@@ -224,6 +303,44 @@ impl TemplateKey {
             Self::Environment(variable) => {
                 Box::new(EnvironmentTemplateSource { variable })
             }
+            Self::Call(call) => Box::new(CallTemplateSource { call }),
+        }
+    }
+
+    /// Evaluate this key to a typed value, for use as a function argument.
+    /// This is the typed equivalent of rendering a key within a template.
+    async fn evaluate<'a>(
+        &'a self,
+        context: &'a TemplateContext,
+        stack: &mut RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, TemplateError> {
+        // Overrides apply here too, same as in a normal render
+        if let Some(value) = context.overrides.get(&self.to_string()) {
+            return Ok(EvaluatedValue {
+                value: value.clone(),
+                sensitive: false,
+            });
+        }
+        stack.push(self)?;
+        let result = self.to_source().evaluate(context, stack).await;
+        stack.pop();
+        result
+    }
+}
+
+impl Expression {
+    /// Evaluate a function argument to a typed value
+    async fn evaluate<'a>(
+        &'a self,
+        context: &'a TemplateContext,
+        stack: &mut RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, TemplateError> {
+        match self {
+            Self::Literal(value) => Ok(EvaluatedValue {
+                value: value.clone(),
+                sensitive: false,
+            }),
+            Self::Key(key) => key.evaluate(context, stack).await,
         }
     }
 }
@@ -245,6 +362,22 @@ trait TemplateSource<'a>: 'a + Send + Sync {
         context: &'a TemplateContext,
         stack: &mut RenderKeyStack<'a>,
     ) -> TemplateResult;
+
+    /// Evaluate this source to a typed value, for use as a function argument.
+    /// By default this renders to bytes, which are converted to a string if
+    /// they're valid UTF-8. Sources that have a more specific type should
+    /// override this to preserve it.
+    async fn evaluate(
+        &self,
+        context: &'a TemplateContext,
+        stack: &mut RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, TemplateError> {
+        let chunk = self.render(context, stack).await?;
+        Ok(EvaluatedValue {
+            value: Value::from_bytes(Arc::unwrap_or_clone(chunk.value)),
+            sensitive: chunk.sensitive,
+        })
+    }
 }
 
 /// A simple field value (e.g. from the profile or an override)
@@ -260,40 +393,83 @@ impl<'a> TemplateSource<'a> for FieldTemplateSource<'a> {
         stack: &mut RenderKeyStack<'a>,
     ) -> TemplateResult {
         let field = self.field;
-
-        // Get the value from the profile
-        let profile_id = context
-            .selected_profile
-            .as_ref()
-            .ok_or_else(|| TemplateError::NoProfileSelected)?;
-        // Typically the caller should validate the ID is valid, this is just
-        // a backup check
-        let profile =
-            context.collection.profiles.get(profile_id).ok_or_else(|| {
-                TemplateError::ProfileUnknown {
-                    profile_id: profile_id.clone(),
-                }
-            })?;
-        let template = profile.data.get(field).ok_or_else(|| {
-            TemplateError::FieldUnknown {
-                field: field.to_owned(),
-            }
-        })?;
-
-        // recursion!
-        let rendered =
-            template
+        let rendered = match self.get_value(context)? {
+            // recursion!
+            ProfileValue::Template(template) => template
                 .render_impl(context, stack)
                 .await
                 .map_err(|error| TemplateError::FieldNested {
                     field: field.to_owned(),
                     error: Box::new(error),
-                })?;
+                })?,
+            // Typed values are rendered as JSON, so structured values can be
+            // embedded in JSON bodies
+            ProfileValue::Value(value) => value.to_string().into_bytes(),
+        };
         Ok(RenderedChunk {
             value: rendered.into(),
             sensitive: false,
         })
     }
+
+    async fn evaluate(
+        &self,
+        context: &'a TemplateContext,
+        stack: &mut RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, TemplateError> {
+        match self.get_value(context)? {
+            ProfileValue::Value(value) => Ok(EvaluatedValue {
+                value: value.clone().into(),
+                sensitive: false,
+            }),
+            // Templates render to bytes, so there's nothing to gain by
+            // special-casing them
+            ProfileValue::Template(_) => {
+                let chunk = self.render(context, stack).await?;
+                Ok(EvaluatedValue {
+                    value: Value::from_bytes(Arc::unwrap_or_clone(chunk.value)),
+                    sensitive: chunk.sensitive,
+                })
+            }
+        }
+    }
+}
+
+impl<'a> FieldTemplateSource<'a> {
+    /// Get the value of this field from the selected profile, falling back to
+    /// the collection's variables
+    fn get_value(
+        &self,
+        context: &'a TemplateContext,
+    ) -> Result<&'a ProfileValue, TemplateError> {
+        let field = self.field;
+
+        // Check the profile first
+        if let Some(profile_id) = &context.selected_profile {
+            // Typically the caller should validate the ID is valid, this is
+            // just a backup check
+            let profile =
+                context.collection.profiles.get(profile_id).ok_or_else(
+                    || TemplateError::ProfileUnknown {
+                        profile_id: profile_id.clone(),
+                    },
+                )?;
+            if let Some(value) = profile.data.get(field) {
+                return Ok(value);
+            }
+        }
+
+        // Fall back to collection-wide variables
+        context.collection.variables.get(field).ok_or_else(|| {
+            if context.selected_profile.is_some() {
+                TemplateError::FieldUnknown {
+                    field: field.to_owned(),
+                }
+            } else {
+                TemplateError::NoProfileSelected
+            }
+        })
+    }
 }
 
 /// A chained value from a complex source. Could be an HTTP response, file, etc.
@@ -312,9 +488,18 @@ impl<'a> TemplateSource<'a> for ChainTemplateSource<'a> {
         // somewhere else. If it is, we'll block on that and re-use the result.
         // If not, we get a guard back, meaning we're responsible for the
         // computation. At the end, we'll write back to the guard so everyone
-        // else can copy our homework.
+        // else can copy our homework. Commands see the environment of the
+        // recipe being built, so results are only shared between recipes with
+        // the same environment.
         let cache = &context.state.chain_results;
-        let guard = match cache.get_or_init(self.chain_id.clone()).await {
+        let key = (
+            self.chain_id.clone(),
+            command_env()
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        );
+        let guard = match cache.get_or_init(key).await {
             FutureCacheOutcome::Hit(result) => return result,
             FutureCacheOutcome::Miss(guard) => guard,
             // The future responsible for writing to the guard didn't. That's a
@@ -327,10 +512,13 @@ impl<'a> TemplateSource<'a> for ChainTemplateSource<'a> {
         // Any error in here is the chain error subtype
         let result: TemplateResult = async {
             // Resolve chained value
-            let chain =
-                context.collection.chains.get(self.chain_id).ok_or_else(
-                    || ChainError::ChainUnknown(self.chain_id.clone()),
-                )?;
+            // The selected profile can override collection-level chains
+            let chain = context
+                .collection
+                .get_chain(self.chain_id, context.selected_profile.as_ref())
+                .ok_or_else(|| {
+                    ChainError::ChainUnknown(self.chain_id.clone())
+                })?;
 
             // Resolve the value based on the source type. Also resolve its
             // content type. For responses this will come from its header, from
@@ -351,8 +539,8 @@ impl<'a> TemplateSource<'a> for ChainTemplateSource<'a> {
                     // No way to guess content type on this
                     None,
                 ),
-                ChainSource::File { path } => {
-                    self.render_file(context, stack, path).await?
+                ChainSource::File { path, relative_to } => {
+                    self.render_file(context, stack, path, *relative_to).await?
                 }
                 ChainSource::Environment { variable } => (
                     self.render_environment_variable(context, stack, variable)
@@ -406,6 +594,18 @@ impl<'a> TemplateSource<'a> for ChainTemplateSource<'a> {
             // detected one
             let content_type = chain.content_type.or(content_type);
 
+            // Apply decode steps in order, before the selector
+            let value = chain.decode.iter().enumerate().try_fold(
+                value,
+                |value, (i, step)| {
+                    step.apply(value).map_err(|error| ChainError::Decode {
+                        step_number: i + 1,
+                        step: *step,
+                        error: error.into(),
+                    })
+                },
+            )?;
+
             // If a selector path is present, filter down the value
             let value = if let Some(selector) = &chain.selector {
                 let content_type =
@@ -435,6 +635,13 @@ impl<'a> TemplateSource<'a> for ChainTemplateSource<'a> {
             error,
         });
 
+        // Remember sensitive values so they can be masked in output later
+        if let Ok(chunk) = &result {
+            if chunk.sensitive {
+                context.state.add_sensitive_value(Arc::clone(&chunk.value));
+            }
+        }
+
         // Store value in the cache so other instances of this chain can use it
         guard.set(result.clone());
 
@@ -504,6 +711,12 @@ impl<'a> ChainTemplateSource<'a> {
                     .map_err(|error| {
                         TriggeredRequestError::Build(error.into())
                     })?;
+                let url = &ticket.record().url;
+                if http_engine.is_protected(url) {
+                    return Err(TriggeredRequestError::Protected {
+                        host: url.host_str().unwrap_or_default().to_owned(),
+                    });
+                }
                 ticket
                     .send(&context.database)
                     .await
@@ -594,12 +807,16 @@ impl<'a> ChainTemplateSource<'a> {
         context: &'a TemplateContext,
         stack: &mut RenderKeyStack<'a>,
         path: &'a Template,
+        relative_to: PathRoot,
     ) -> Result<(Vec<u8>, Option<ContentType>), ChainError> {
         let path: PathBuf = path
             .render_chain_config("path", context, stack)
             .await?
             .into();
-        let path = expand_home(path).into_owned(); // Expand ~
+        let path = match relative_to {
+            PathRoot::Collection => resolve_path(context, path),
+            PathRoot::Cwd => expand_home(path).into_owned(), // Expand ~
+        };
 
         // Guess content type based on file extension
         let content_type = ContentType::from_path(&path).ok();
@@ -657,6 +874,7 @@ impl<'a> ChainTemplateSource<'a> {
         // Spawn the command process
         let mut process = Command::new(program)
             .args(args)
+            .envs(command_env().iter())
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -730,13 +948,31 @@ impl<'a> ChainTemplateSource<'a> {
             None
         };
 
+        // If we're in a batch, the same prompt may have already been asked
+        let guard = if let Some(PromptCache(cache)) = &context.prompt_cache {
+            match cache.get_or_init(message.clone()).await {
+                FutureCacheOutcome::Hit(Some(value)) => return Ok(value),
+                FutureCacheOutcome::Hit(None)
+                | FutureCacheOutcome::NoResponse => {
+                    return Err(ChainError::PromptNoResponse)
+                }
+                FutureCacheOutcome::Miss(guard) => Some(guard),
+            }
+        } else {
+            None
+        };
+
         context.prompter.prompt(Prompt {
             message,
             default,
             sensitive,
             channel: tx.into(),
         });
-        rx.await.map_err(|_| ChainError::PromptNoResponse)
+        let response = rx.await.ok();
+        if let Some(guard) = guard {
+            guard.set(response.clone());
+        }
+        response.ok_or(ChainError::PromptNoResponse)
     }
 
     async fn render_select(
@@ -836,6 +1072,394 @@ impl<'a> TemplateSource<'a> for EnvironmentTemplateSource<'a> {
     }
 }
 
+/// A call to a template function. For most functions, arguments are evaluated
+/// concurrently then passed to the function. Conditional functions (`if` and
+/// `coalesce`) evaluate their arguments lazily, so an unused branch is never
+/// rendered.
+struct CallTemplateSource<'a> {
+    call: &'a FunctionCall,
+}
+
+impl<'a> CallTemplateSource<'a> {
+    /// Evaluate all arguments concurrently, then call the function
+    async fn evaluate_eager(
+        &self,
+        context: &'a TemplateContext,
+        stack: &RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, FunctionError> {
+        let futures = (0..self.call.arguments.len())
+            .map(|index| self.evaluate_argument(index, context, stack));
+        let arguments = future::try_join_all(futures).await?;
+
+        let sensitive = arguments.iter().any(|argument| argument.sensitive);
+        let function = self.call.function;
+        let arguments = arguments
+            .into_iter()
+            .map(|argument| argument.value)
+            .collect();
+        let value = if function.is_random() {
+            context.state.with_rng(context.random_seed, |rng| {
+                function.call_random(arguments, rng)
+            })
+        } else {
+            function.call(arguments)
+        }?;
+        Ok(EvaluatedValue { value, sensitive })
+    }
+
+    /// `if(condition, then, else)`: Evaluate the condition, then evaluate
+    /// only the chosen branch
+    async fn evaluate_if(
+        &self,
+        context: &'a TemplateContext,
+        stack: &RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, FunctionError> {
+        self.expect_arity(3, Some(3))?;
+
+        let condition = self.evaluate_argument(0, context, stack).await?;
+        let index = if condition.value.is_truthy() { 1 } else { 2 };
+        let branch = self.evaluate_argument(index, context, stack).await?;
+        Ok(EvaluatedValue {
+            value: branch.value,
+            // The condition leaks into the output, so if it's sensitive then
+            // the output is as well
+            sensitive: condition.sensitive || branch.sensitive,
+        })
+    }
+
+    /// `coalesce(a, b, ...)`: Evaluate arguments in order until one is
+    /// non-empty. If they're all empty, return the last one.
+    async fn evaluate_coalesce(
+        &self,
+        context: &'a TemplateContext,
+        stack: &RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, FunctionError> {
+        let actual = self.expect_arity(1, None)?;
+
+        let mut sensitive = false;
+        for index in 0..actual {
+            let argument =
+                self.evaluate_argument(index, context, stack).await?;
+            // Skipped arguments influence the output too
+            sensitive |= argument.sensitive;
+            if !argument.value.is_empty() || index == actual - 1 {
+                return Ok(EvaluatedValue {
+                    value: argument.value,
+                    sensitive,
+                });
+            }
+        }
+        unreachable!("Loop always returns on the last argument")
+    }
+
+    /// `profile(profile_id, field)`: Look up a field in a specific profile and
+    /// render it. Nested references in the field's template are resolved in
+    /// the current context, i.e. against the *selected* profile.
+    async fn evaluate_profile(
+        &self,
+        context: &'a TemplateContext,
+        stack: &RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, FunctionError> {
+        self.expect_arity(2, Some(2))?;
+
+        let (profile_id, field) = future::try_join(
+            self.evaluate_argument(0, context, stack),
+            self.evaluate_argument(1, context, stack),
+        )
+        .await?;
+        let sensitive = profile_id.sensitive || field.sensitive;
+        let profile_id: ProfileId = profile_id.value.into_string()?.into();
+        let field = field.value.into_string()?;
+
+        let profile =
+            context
+                .collection
+                .profiles
+                .get(&profile_id)
+                .ok_or_else(|| FunctionError::ProfileUnknown {
+                    profile_id: profile_id.clone(),
+                })?;
+        let value = match profile.data.get(&field) {
+            Some(ProfileValue::Template(template)) => {
+                let mut stack = stack.clone();
+                let bytes = template
+                    .render_impl(context, &mut stack)
+                    .await
+                    .map_err(|error| FunctionError::ProfileFieldNested {
+                        profile_id: profile_id.clone(),
+                        field: field.clone(),
+                        error: Box::new(error),
+                    })?;
+                Value::from_bytes(bytes)
+            }
+            Some(ProfileValue::Value(value)) => value.clone().into(),
+            None => {
+                return Err(FunctionError::ProfileFieldUnknown {
+                    profile_id,
+                    field,
+                })
+            }
+        };
+        Ok(EvaluatedValue { value, sensitive })
+    }
+
+    /// `collection_path()`/`collection_dir()`: Get the path of the loaded
+    /// collection file, or the directory containing it
+    fn evaluate_collection_path(
+        &self,
+        context: &'a TemplateContext,
+        dir: bool,
+    ) -> Result<EvaluatedValue, FunctionError> {
+        self.expect_arity(0, Some(0))?;
+
+        let path = if dir {
+            collection_dir(context)
+        } else {
+            context.collection_path.as_path()
+        };
+        Ok(EvaluatedValue {
+            value: Value::String(path.to_string_lossy().into_owned()),
+            sensitive: false,
+        })
+    }
+
+    /// `file(path)`: Read a file. Relative paths are resolved against the
+    /// collection directory
+    async fn evaluate_file(
+        &self,
+        context: &'a TemplateContext,
+        stack: &RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, FunctionError> {
+        self.expect_arity(1, Some(1))?;
+
+        let path = self.evaluate_argument(0, context, stack).await?;
+        let sensitive = path.sensitive;
+        let path = resolve_path(context, path.value.into_string()?.into());
+        let content =
+            fs::read(&path).await.map_err(|error| FunctionError::File {
+                path,
+                error: error.into(),
+            })?;
+        Ok(EvaluatedValue {
+            value: Value::from_bytes(content),
+            sensitive,
+        })
+    }
+
+    /// `files(pattern)`: Get the paths of all files matching a glob pattern,
+    /// in alphabetical order. Relative patterns are resolved against the
+    /// collection directory
+    async fn evaluate_files(
+        &self,
+        context: &'a TemplateContext,
+        stack: &RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, FunctionError> {
+        self.expect_arity(1, Some(1))?;
+
+        let pattern = self.evaluate_argument(0, context, stack).await?;
+        let sensitive = pattern.sensitive;
+        let pattern = pattern.value.into_string()?;
+        let pattern = expand_home(PathBuf::from(pattern));
+        // The directory is joined onto the pattern, so escape it to prevent
+        // any special characters in it from being treated as a glob
+        let full_pattern = if pattern.is_absolute() {
+            pattern.to_string_lossy().into_owned()
+        } else {
+            let dir = collection_dir(context).to_string_lossy();
+            format!(
+                "{}{}{}",
+                glob::Pattern::escape(&dir),
+                std::path::MAIN_SEPARATOR,
+                pattern.to_string_lossy()
+            )
+        };
+
+        // Walking the file system is blocking, so do it on a different thread
+        let paths = task::spawn_blocking(move || {
+            glob::glob(&full_pattern)
+                .map_err(|error| FunctionError::Glob {
+                    pattern: full_pattern.clone(),
+                    error: error.into(),
+                })?
+                .filter_map(|entry| match entry {
+                    // Only include files. Directories can't be read by file()
+                    Ok(path) if path.is_file() => Some(Ok(Value::String(
+                        path.to_string_lossy().into_owned(),
+                    ))),
+                    Ok(_) => None,
+                    Err(error) => Some(Err(FunctionError::File {
+                        path: error.path().to_owned(),
+                        error: error.into_error().into(),
+                    })),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await
+        // This error only occurs if the task panics, so pass the panic along
+        .unwrap_or_else(|error| panic::resume_unwind(error.into_panic()))?;
+        Ok(EvaluatedValue {
+            value: Value::Array(paths),
+            sensitive,
+        })
+    }
+
+    /// `env_file(path, key)`: Get a single value from a dotenv file. Relative
+    /// paths are resolved against the collection directory. Each file is
+    /// parsed at most once per render group
+    async fn evaluate_env_file(
+        &self,
+        context: &'a TemplateContext,
+        stack: &RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, FunctionError> {
+        self.expect_arity(2, Some(2))?;
+
+        let (path, key) = future::try_join(
+            self.evaluate_argument(0, context, stack),
+            self.evaluate_argument(1, context, stack),
+        )
+        .await?;
+        let path = resolve_path(context, path.value.into_string()?.into());
+        let key = key.value.into_string()?;
+
+        let values = context.state.load_env_file(path.clone()).await?;
+        let value = values
+            .get(&key)
+            .ok_or(FunctionError::EnvFileKeyUnknown { path, key })?;
+        Ok(EvaluatedValue {
+            value: Value::String(value.clone()),
+            // Env files are typically used for secrets, so always mask these
+            sensitive: true,
+        })
+    }
+
+    /// Check that the number of arguments is at least `min` and at most `max`,
+    /// and return the count. Pass `None` for `max` if the function is
+    /// variadic
+    fn expect_arity(
+        &self,
+        min: usize,
+        max: Option<usize>,
+    ) -> Result<usize, FunctionError> {
+        let actual = self.call.arguments.len();
+        if actual >= min && !max.is_some_and(|max| actual > max) {
+            return Ok(actual);
+        }
+        Err(match max {
+            Some(max) if max == min => FunctionError::ArgumentCount {
+                expected: min,
+                actual,
+            },
+            Some(max) => FunctionError::ArgumentCountRange {
+                minimum: min,
+                maximum: max,
+                actual,
+            },
+            None => FunctionError::ArgumentCountMin {
+                minimum: min,
+                actual,
+            },
+        })
+    }
+
+    /// Evaluate a single argument by index
+    async fn evaluate_argument(
+        &self,
+        index: usize,
+        context: &'a TemplateContext,
+        stack: &RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, FunctionError> {
+        // Fork the local state for each argument, same as template chunks
+        let mut stack = stack.clone();
+        self.call.arguments[index]
+            .evaluate(context, &mut stack)
+            .await
+            .map_err(|error| FunctionError::Argument {
+                index,
+                error: Box::new(error),
+            })
+    }
+}
+
+#[async_trait]
+impl<'a> TemplateSource<'a> for CallTemplateSource<'a> {
+    async fn render(
+        &self,
+        context: &'a TemplateContext,
+        stack: &mut RenderKeyStack<'a>,
+    ) -> TemplateResult {
+        let EvaluatedValue { value, sensitive } =
+            self.evaluate(context, stack).await?;
+        let value: Arc<Vec<u8>> = value.into_bytes().into();
+        // Anything derived from a sensitive value is also sensitive
+        if sensitive {
+            context.state.add_sensitive_value(Arc::clone(&value));
+        }
+        Ok(RenderedChunk { value, sensitive })
+    }
+
+    async fn evaluate(
+        &self,
+        context: &'a TemplateContext,
+        stack: &mut RenderKeyStack<'a>,
+    ) -> Result<EvaluatedValue, TemplateError> {
+        let function = self.call.function;
+        match function {
+            Function::If => self.evaluate_if(context, stack).await,
+            Function::Coalesce => self.evaluate_coalesce(context, stack).await,
+            Function::Profile => self.evaluate_profile(context, stack).await,
+            Function::CollectionPath => {
+                self.evaluate_collection_path(context, false)
+            }
+            Function::CollectionDir => {
+                self.evaluate_collection_path(context, true)
+            }
+            Function::File => self.evaluate_file(context, stack).await,
+            Function::Files => self.evaluate_files(context, stack).await,
+            Function::EnvFile => self.evaluate_env_file(context, stack).await,
+            _ => self.evaluate_eager(context, stack).await,
+        }
+        .map_err(|error| TemplateError::Function { function, error })
+    }
+}
+
+/// Get the directory containing the collection file
+fn collection_dir(context: &TemplateContext) -> &Path {
+    let path = &context.collection_path;
+    path.parent().unwrap_or(path)
+}
+
+/// Resolve a user-provided path for reading. `~` is expanded, then relative
+/// paths are resolved against the collection directory, so they work no matter
+/// where Slumber is run from
+fn resolve_path(context: &TemplateContext, path: PathBuf) -> PathBuf {
+    // join() replaces the base if the path is already absolute
+    collection_dir(context).join(expand_home(path))
+}
+
+tokio::task_local! {
+    /// Environment variables for subprocesses spawned during the current
+    /// recipe build. This is task-local rather than stored in the template
+    /// context because a context is shared with any requests it triggers,
+    /// and each triggered recipe gets its own environment.
+    static COMMAND_ENV: Arc<IndexMap<String, String>>;
+}
+
+/// Run a future with the given environment variables applied to every command
+/// spawned within it, e.g. for command chains. The process environment is
+/// never modified, so this is safe with concurrent renders.
+pub async fn with_command_env<F: Future>(
+    env: IndexMap<String, String>,
+    future: F,
+) -> F::Output {
+    COMMAND_ENV.scope(Arc::new(env), future).await
+}
+
+/// Get the environment variables that should be passed to a spawned command.
+/// Empty if called outside [with_command_env]
+pub fn command_env() -> Arc<IndexMap<String, String>> {
+    COMMAND_ENV.try_with(Arc::clone).unwrap_or_default()
+}
+
 /// State for a render group, which consists of one or more related renders
 /// (e.g. all the template renders for a single recipe). This state is stored in
 /// the template context.
@@ -843,8 +1467,91 @@ impl<'a> TemplateSource<'a> for EnvironmentTemplateSource<'a> {
 pub struct RenderGroupState {
     /// Cache the result of each chain, so multiple references to the same
     /// chain within a render group don't have to do the work multiple
-    /// times.
-    chain_results: FutureCache<ChainId, TemplateResult>,
+    /// times. Keyed by the chain and the command environment it was rendered
+    /// with (see [with_command_env]), because recipes in one group (e.g. a
+    /// folder run) can each set their own environment.
+    chain_results:
+        FutureCache<(ChainId, BTreeMap<String, String>), TemplateResult>,
+    /// Every value rendered in this group that was marked sensitive. Chain
+    /// results are deduplicated by the cache, so each chain appears at most
+    /// once.
+    sensitive_values: Mutex<Vec<Arc<Vec<u8>>>>,
+    /// RNG for random template functions. This is initialized on first use,
+    /// from the context's seed if there is one. Sharing one RNG across the
+    /// group means a seeded group always generates the same sequence.
+    rng: OnceLock<Mutex<StdRng>>,
+    /// Parsed dotenv files loaded by `env_file()`, keyed by resolved path, so
+    /// pulling multiple keys from one file only reads it once
+    env_files: FutureCache<PathBuf, EnvFileResult>,
+}
+
+/// Outcome of loading a dotenv file for `env_file()`
+type EnvFileResult = Result<Arc<IndexMap<String, String>>, FunctionError>;
+
+impl RenderGroupState {
+    /// Get all values rendered so far in this group that were marked
+    /// sensitive. Useful for masking those values in rendered output, after
+    /// the individual chunks have been stitched together.
+    pub fn sensitive_values(&self) -> Vec<Arc<Vec<u8>>> {
+        self.sensitive_values
+            .lock()
+            .expect("Sensitive value lock poisoned")
+            .clone()
+    }
+
+    fn add_sensitive_value(&self, value: Arc<Vec<u8>>) {
+        self.sensitive_values
+            .lock()
+            .expect("Sensitive value lock poisoned")
+            .push(value);
+    }
+
+    /// Load and parse a dotenv file, or get it from the cache if it's already
+    /// been loaded in this group. Errors are cached too, so a bad file is only
+    /// reported once per render group.
+    async fn load_env_file(&self, path: PathBuf) -> EnvFileResult {
+        let guard = match self.env_files.get_or_init(path.clone()).await {
+            FutureCacheOutcome::Hit(result) => return result,
+            FutureCacheOutcome::Miss(guard) => guard,
+            FutureCacheOutcome::NoResponse => {
+                panic!("Cached future did not set a value. This is a bug!")
+            }
+        };
+
+        let result = fs::read(&path)
+            .await
+            .map_err(|error| FunctionError::File {
+                path: path.clone(),
+                error: error.into(),
+            })
+            .and_then(|content| {
+                dotenvy::from_read_iter(content.as_slice())
+                    .collect::<Result<IndexMap<_, _>, _>>()
+                    .map(Arc::new)
+                    .map_err(|error| FunctionError::EnvFile {
+                        path,
+                        error: error.into(),
+                    })
+            });
+        guard.set(result.clone());
+        result
+    }
+
+    /// Run a function with this group's RNG. The seed is only used the first
+    /// time the RNG is accessed
+    fn with_rng<T>(
+        &self,
+        seed: Option<u64>,
+        f: impl FnOnce(&mut StdRng) -> T,
+    ) -> T {
+        let rng = self.rng.get_or_init(|| {
+            Mutex::new(match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            })
+        });
+        f(&mut rng.lock().expect("RNG lock poisoned"))
+    }
 }
 
 /// Track the series of template keys that we've followed to get to the current
@@ -906,6 +1613,36 @@ impl ChainOutputTrim {
     }
 }
 
+impl DecodeStep {
+    /// Apply this transformation to a value
+    fn apply(self, value: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Base64 => {
+                // Whitespace is ignored, so that trailing newlines (e.g. from
+                // a file or command) don't break decoding
+                let value: Vec<u8> = value
+                    .into_iter()
+                    .filter(|b| !b.is_ascii_whitespace())
+                    .collect();
+                Ok(BASE64_STANDARD.decode(value)?)
+            }
+            Self::Json => {
+                let json: serde_json::Value = serde_json::from_slice(&value)?;
+                match json {
+                    serde_json::Value::String(s) => Ok(s.into_bytes()),
+                    // Already decoded, so pass the original bytes through
+                    _ => Ok(value),
+                }
+            }
+            Self::Gunzip => {
+                let mut decoded = Vec::new();
+                GzDecoder::new(value.as_slice()).read_to_end(&mut decoded)?;
+                Ok(decoded)
+            }
+        }
+    }
+}
+
 /// Load variable from environment. If the variable is missing or otherwise
 /// inaccessible, return an empty string. This models standard shell behavior,
 /// so it should be intuitive for users.
@@ -1,7 +1,7 @@
 //! General test utilities, that apply to all parts of the program
 
 use crate::{
-    collection::{ChainSource, HasId},
+    collection::{ChainSource, HasId, PathRoot},
     http::{HttpEngine, HttpEngineConfig},
     template::{Prompt, Prompter, Select},
     util::{paths::get_repo_root, ResultTraced},
@@ -46,6 +46,7 @@ pub fn invalid_utf8_chain(test_data_dir: PathBuf) -> ChainSource {
             .to_string_lossy()
             .to_string()
             .into(),
+        relative_to: PathRoot::Collection,
     }
 }
 
@@ -5,7 +5,7 @@ pub mod paths;
 use crate::{http::RequestError, template::ChainError};
 use chrono::{
     format::{DelayedFormat, StrftimeItems},
-    DateTime, Duration, Local, Utc,
+    DateTime, Duration, TimeZone,
 };
 use derive_more::{DerefMut, Display};
 use serde::de::DeserializeOwned;
@@ -54,14 +54,28 @@ pub fn parse_yaml<T: DeserializeOwned>(reader: impl Read) -> anyhow::Result<T> {
     Ok(output)
 }
 
-/// Format a datetime for the user
-pub fn format_time(time: &DateTime<Utc>) -> DelayedFormat<StrftimeItems> {
-    time.with_timezone(&Local).format("%b %-d %H:%M:%S")
+/// Format a datetime for the user. The caller is responsible for converting
+/// the datetime to the user's preferred timezone first.
+pub fn format_time<Tz>(
+    time: &DateTime<Tz>,
+) -> DelayedFormat<StrftimeItems<'static>>
+where
+    Tz: TimeZone,
+    Tz::Offset: fmt::Display,
+{
+    time.format("%b %-d %H:%M:%S")
 }
 
-/// Format a datetime in ISO 8601 format
-pub fn format_time_iso(time: &DateTime<Utc>) -> DelayedFormat<StrftimeItems> {
-    time.with_timezone(&Local).format("%FT%TZ%Z")
+/// Format a datetime in ISO 8601 format. The caller is responsible for
+/// converting the datetime to the user's preferred timezone first.
+pub fn format_time_iso<Tz>(
+    time: &DateTime<Tz>,
+) -> DelayedFormat<StrftimeItems<'static>>
+where
+    Tz: TimeZone,
+    Tz::Offset: fmt::Display,
+{
+    time.format("%FT%TZ%Z")
 }
 
 /// Format a duration for the user
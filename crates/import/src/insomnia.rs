@@ -9,9 +9,9 @@ use reqwest::header;
 use serde::{de::Error as _, Deserialize, Deserializer};
 use slumber_core::{
     collection::{
-        self, Chain, ChainId, ChainSource, Collection, Folder, HasId, Profile,
-        ProfileId, Recipe, RecipeBody, RecipeId, RecipeNode, RecipeTree,
-        SelectorMode,
+        self, Chain, ChainId, ChainSource, Collection, Folder, HasId, PathRoot,
+        Profile, ProfileId, ProfileValue, Recipe, RecipeBody, RecipeId,
+        RecipeNode, RecipeTree, SelectorMode,
     },
     http::{content_type::ContentType, HttpMethod},
     template::{Identifier, Template},
@@ -69,7 +69,7 @@ pub fn from_insomnia(
         profiles,
         recipes,
         chains,
-        _ignore: serde::de::IgnoredAny,
+        variables: Default::default(),
     })
 }
 
@@ -354,8 +354,18 @@ impl From<Request> for RecipeNode {
                     (parameter.name, Template::raw(parameter.value))
                 })
                 .collect(),
-            headers,
+            headers: headers.into_iter().collect(),
             authentication,
+            persist: true,
+            cache: None,
+            confirm: false,
+            queue: None,
+            http_version: None,
+            accept: None,
+            auto_send: None,
+            env: IndexMap::new(),
+            profile: None,
+            script: None,
         })
     }
 }
@@ -365,9 +375,15 @@ impl TryFrom<Body> for RecipeBody {
 
     fn try_from(body: Body) -> anyhow::Result<Self> {
         let body = if body.mime_type == mime::APPLICATION_JSON {
-            RecipeBody::Raw {
-                body: Template::raw(body.try_text()?),
-                content_type: Some(ContentType::Json),
+            let text = body.try_text()?;
+            // Use a structured body if possible. If the JSON is invalid, fall
+            // back to the raw text so the user can fix it themselves
+            match serde_json::from_str(&text) {
+                Ok(json) => RecipeBody::untemplated_json(json),
+                Err(_) => RecipeBody::Raw {
+                    body: Template::raw(text),
+                    content_type: Some(ContentType::Json),
+                },
             }
         } else if body.mime_type == mime::APPLICATION_WWW_FORM_URLENCODED {
             RecipeBody::FormUrlencoded(
@@ -435,8 +451,8 @@ fn build_profiles(
 ) -> IndexMap<ProfileId, Profile> {
     fn convert_data(
         data: IndexMap<String, String>,
-    ) -> impl Iterator<Item = (String, Template)> {
-        data.into_iter().map(|(k, v)| (k, Template::raw(v)))
+    ) -> impl Iterator<Item = (String, ProfileValue)> {
+        data.into_iter().map(|(k, v)| (k, Template::raw(v).into()))
     }
 
     // The Base Environment is the one with the workspace as a parent. We
@@ -446,7 +462,7 @@ fn build_profiles(
     let base_index = environments
         .iter()
         .position(|environment| environment.parent_id == workspace_id);
-    let base_data: IndexMap<String, Template> = base_index
+    let base_data: IndexMap<String, ProfileValue> = base_index
         .map(|i| {
             let environment = environments.remove(i);
             convert_data(environment.data).collect()
@@ -470,6 +486,10 @@ fn build_profiles(
                     id,
                     name: Some(environment.name),
                     default: false,
+                    from_dotenv: None,
+                    color: None,
+                    label: None,
+                    chains: IndexMap::new(),
                     data,
                 },
             )
@@ -509,12 +529,14 @@ fn build_chains(requests: &[Request]) -> IndexMap<ChainId, Chain> {
                         id,
                         source: ChainSource::File {
                             path: Template::raw(path.to_owned()),
+                            relative_to: PathRoot::default(),
                         },
                         sensitive: false,
                         selector: None,
                         selector_mode: SelectorMode::default(),
                         content_type: None,
                         trim: Default::default(),
+                        decode: Vec::new(),
                     },
                 );
             }
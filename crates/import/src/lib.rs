@@ -3,5 +3,5 @@ mod openapi;
 mod rest;
 
 pub use insomnia::from_insomnia;
-pub use openapi::from_openapi;
+pub use openapi::{from_openapi, to_openapi};
 pub use rest::from_rest;
@@ -8,8 +8,11 @@
 //! - References are resolved within the same file. We don't support resolving
 //!   from other files.
 
+mod export;
 mod resolve;
 
+pub use export::to_openapi;
+
 use crate::openapi::resolve::ReferenceResolver;
 use anyhow::{anyhow, Context};
 use indexmap::IndexMap;
@@ -76,7 +79,7 @@ pub fn from_openapi(
         profiles,
         recipes,
         chains: IndexMap::new(),
-        _ignore: serde::de::IgnoredAny,
+        variables: Default::default(),
     })
 }
 
@@ -88,14 +91,14 @@ fn build_profiles(servers: Vec<Server>) -> IndexMap<ProfileId, Profile> {
             let id: ProfileId = url.clone().into();
             // Include a "host" variable for each server, but allow the
             // user-defined variables to override that
-            let data =
-                iter::once(("host".to_owned(), Template::raw(url.clone())))
-                    .chain(variables.into_iter().flatten().map(
-                        |(name, variable)| {
-                            (name, Template::raw(variable.default))
-                        },
-                    ))
-                    .collect();
+            let data = iter::once((
+                "host".to_owned(),
+                Template::raw(url.clone()).into(),
+            ))
+            .chain(variables.into_iter().flatten().map(|(name, variable)| {
+                (name, Template::raw(variable.default).into())
+            }))
+            .collect();
             (
                 id.clone(),
                 Profile {
@@ -105,6 +108,10 @@ fn build_profiles(servers: Vec<Server>) -> IndexMap<ProfileId, Profile> {
                     // discoverability; the user may want to rename it
                     name: Some(url),
                     default: false,
+                    from_dotenv: None,
+                    color: None,
+                    label: None,
+                    chains: IndexMap::new(),
                     data,
                 },
             )
@@ -305,7 +312,17 @@ impl<'a> RecipeBuilder<'a> {
             body: builder.body,
             authentication: builder.authentication,
             query: builder.query,
-            headers: builder.headers,
+            headers: builder.headers.into_iter().collect(),
+            persist: true,
+            cache: None,
+            confirm: false,
+            queue: None,
+            http_version: None,
+            accept: None,
+            auto_send: None,
+            env: IndexMap::new(),
+            profile: None,
+            script: None,
         }
     }
 
@@ -0,0 +1,561 @@
+//! Export a request collection as an OpenAPI v3.0.X specification. This is
+//! the inverse of the importer, and maps concepts in the opposite direction:
+//!
+//! - Profiles are mapped to servers
+//!     - The server URL is taken from the profile's `host` field, falling back
+//!       to the collection-level `host` variable. Profiles without a `host` are
+//!       skipped.
+//! - Recipes are mapped to operations
+//!     - The leading template key of the URL (e.g. `{{host}}`) is assumed to be
+//!       the server URL, and is stripped from the path
+//!     - Other template keys in the path become path parameters
+//!     - Query parameters and headers become parameters, with the template
+//!       source as the example
+//!     - Bodies become request bodies, with the template source as the example
+//! - Folders are mapped to tags
+//!     - Each recipe is tagged with its immediate parent folder
+//!
+//! Templates are never rendered, so the exported spec is static.
+
+use indexmap::IndexMap;
+use openapiv3::{
+    Components, Info, MediaType, OpenAPI, Operation, Parameter, ParameterData,
+    ParameterSchemaOrContent, PathItem, Paths, ReferenceOr, RequestBody,
+    Schema, SchemaData, SchemaKind, SecurityScheme, Server, StringType, Tag,
+    Type,
+};
+use slumber_core::{
+    collection::{
+        Authentication, Collection, Profile, ProfileValue, Recipe, RecipeBody,
+        RecipeNode,
+    },
+    http::HttpMethod,
+    template::Template,
+};
+use tracing::{info, warn};
+
+/// OpenAPI version of the generated spec
+const OPENAPI_VERSION: &str = "3.0.3";
+/// Profile field/variable that holds the server URL. This matches the field
+/// generated by the importer
+const HOST_FIELD: &str = "host";
+/// Name of the security scheme generated for basic authentication
+const BASIC_AUTH_SCHEME: &str = "basicAuth";
+/// Name of the security scheme generated for bearer authentication
+const BEARER_AUTH_SCHEME: &str = "bearerAuth";
+
+/// Generate an OpenAPI v3 specification from a collection
+pub fn to_openapi(collection: &Collection) -> OpenAPI {
+    info!("Exporting collection to OpenAPI");
+    let mut exporter = Exporter::default();
+    for (lookup_key, node) in collection.recipes.iter() {
+        match node {
+            RecipeNode::Folder(folder) => exporter.tags.push(Tag {
+                name: folder.name().to_owned(),
+                ..Default::default()
+            }),
+            RecipeNode::Recipe(recipe) => {
+                // Tag with the immediate parent folder, if any
+                let tag = lookup_key
+                    .ancestors()
+                    .last()
+                    .and_then(|id| collection.recipes.get(id))
+                    .map(|folder| folder.name().to_owned());
+                exporter.add_recipe(recipe, tag);
+            }
+        }
+    }
+
+    OpenAPI {
+        openapi: OPENAPI_VERSION.to_owned(),
+        info: Info {
+            // There's no equivalent for these in the collection, so the user
+            // will probably want to fill them in
+            title: "Slumber Collection".to_owned(),
+            version: "1.0.0".to_owned(),
+            ..Default::default()
+        },
+        servers: build_servers(collection),
+        paths: exporter.paths,
+        components: if exporter.security_schemes.is_empty() {
+            None
+        } else {
+            Some(Components {
+                security_schemes: exporter.security_schemes,
+                ..Default::default()
+            })
+        },
+        tags: exporter.tags,
+        ..Default::default()
+    }
+}
+
+/// Build one server per profile that defines a host
+fn build_servers(collection: &Collection) -> Vec<Server> {
+    let default_host = collection.variables.get(HOST_FIELD);
+    collection
+        .profiles
+        .values()
+        .filter_map(|profile| {
+            let Some(host) = profile.data.get(HOST_FIELD).or(default_host)
+            else {
+                warn!(
+                    "Profile `{}` has no `{HOST_FIELD}` field; it will not be \
+                    exported as a server",
+                    profile.id
+                );
+                return None;
+            };
+            Some(build_server(profile, host))
+        })
+        .collect()
+}
+
+fn build_server(profile: &Profile, host: &ProfileValue) -> Server {
+    let url = match host {
+        ProfileValue::Template(template) => template.display().into_owned(),
+        // Structured values are unlikely here, but use the JSON
+        // representation to be safe. Strings would have been templates
+        ProfileValue::Value(value) => value.to_string(),
+    };
+    Server {
+        url,
+        description: Some(profile.name().to_owned()),
+        ..Default::default()
+    }
+}
+
+/// Intermediate state accumulated while walking the recipe tree
+#[derive(Default)]
+struct Exporter {
+    paths: Paths,
+    tags: Vec<Tag>,
+    security_schemes: IndexMap<String, ReferenceOr<SecurityScheme>>,
+}
+
+impl Exporter {
+    /// Convert a recipe to an operation and insert it into the path map
+    fn add_recipe(&mut self, recipe: &Recipe, tag: Option<String>) {
+        let (path, path_params) = build_path(&recipe.url);
+
+        let mut operation = Operation {
+            tags: tag.into_iter().collect(),
+            summary: recipe.name.clone(),
+            operation_id: Some(recipe.id.to_string()),
+            parameters: path_params
+                .into_iter()
+                .map(|name| Parameter::Path {
+                    parameter_data: parameter_data(name, None, true),
+                    style: Default::default(),
+                })
+                .chain(build_query(&recipe.query))
+                .chain(build_headers(&recipe.headers))
+                .map(ReferenceOr::Item)
+                .collect(),
+            request_body: recipe
+                .body
+                .as_ref()
+                .map(|body| ReferenceOr::Item(build_body(body))),
+            ..Default::default()
+        };
+        let scheme_name = recipe
+            .authentication
+            .as_ref()
+            .and_then(|auth| self.add_security_scheme(auth));
+        if let Some(scheme_name) = scheme_name {
+            operation.security =
+                Some(vec![IndexMap::from([(scheme_name.to_owned(), vec![])])]);
+        }
+
+        let item = self
+            .paths
+            .paths
+            .entry(path.clone())
+            .or_insert_with(|| ReferenceOr::Item(PathItem::default()));
+        let ReferenceOr::Item(item) = item else {
+            // We only ever insert items
+            unreachable!("Exported path `{path}` is a reference")
+        };
+        match operation_mut(item, recipe.method) {
+            Some(slot) if slot.is_none() => *slot = Some(operation),
+            // Operations are unique per path+method, so only the first
+            // recipe can be exported
+            Some(_) => warn!(
+                "Multiple recipes for `{} {path}`; recipe `{}` will not be \
+                exported",
+                recipe.method, recipe.id
+            ),
+            None => warn!(
+                "Method {} is not supported by OpenAPI; recipe `{}` will not \
+                be exported",
+                recipe.method, recipe.id
+            ),
+        }
+    }
+
+    /// Add the security scheme corresponding to an authentication type, and
+    /// return its name. Each scheme is added at most once. Credential helpers
+    /// have no scheme, because the header they set isn't known until they run
+    fn add_security_scheme(
+        &mut self,
+        authentication: &Authentication,
+    ) -> Option<&'static str> {
+        let (name, scheme) = match authentication {
+            Authentication::Basic { .. } => (BASIC_AUTH_SCHEME, "basic"),
+            Authentication::Bearer(_) => (BEARER_AUTH_SCHEME, "bearer"),
+            Authentication::Helper { .. } => return None,
+        };
+        self.security_schemes
+            .entry(name.to_owned())
+            .or_insert_with(|| {
+                ReferenceOr::Item(SecurityScheme::HTTP {
+                    scheme: scheme.to_owned(),
+                    bearer_format: None,
+                    description: None,
+                    extensions: Default::default(),
+                })
+            });
+        Some(name)
+    }
+}
+
+/// Get a mutable reference to the operation slot for a method. Return `None`
+/// if the method can't be represented in OpenAPI
+fn operation_mut(
+    path_item: &mut PathItem,
+    method: HttpMethod,
+) -> Option<&mut Option<Operation>> {
+    match method {
+        HttpMethod::Connect => None,
+        HttpMethod::Delete => Some(&mut path_item.delete),
+        HttpMethod::Get => Some(&mut path_item.get),
+        HttpMethod::Head => Some(&mut path_item.head),
+        HttpMethod::Options => Some(&mut path_item.options),
+        HttpMethod::Patch => Some(&mut path_item.patch),
+        HttpMethod::Post => Some(&mut path_item.post),
+        HttpMethod::Put => Some(&mut path_item.put),
+        HttpMethod::Trace => Some(&mut path_item.trace),
+    }
+}
+
+/// Convert a URL template to an OpenAPI path. The server portion of the URL
+/// is stripped, and any remaining template keys are converted to path
+/// parameters: `{{host}}/users/{{user_id}}` -> `/users/{user_id}`. Return the
+/// path and the names of its parameters.
+fn build_path(url: &Template) -> (String, Vec<String>) {
+    let url = url.display();
+    // Query params are exported separately
+    let url = url.split_once('?').map_or(&*url, |(url, _)| url);
+
+    // The server is either an origin (which may contain template keys, e.g.
+    // `{{scheme}}://{{host}}`), or a single leading template key
+    let path = if let Some((_, rest)) = url.split_once("://") {
+        rest.find('/').map_or("", |index| &rest[index..])
+    } else if let Some(rest) = url.strip_prefix("{{") {
+        rest.split_once("}}").map_or("", |(_, path)| path)
+    } else {
+        url
+    };
+
+    let mut output = String::with_capacity(path.len() + 1);
+    if !path.starts_with('/') {
+        output.push('/');
+    }
+    let mut params = Vec::new();
+    let mut rest = path;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let param = &rest[start + 2..start + end];
+        output.push_str(&format!("{{{param}}}"));
+        params.push(param.to_owned());
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+    (output, params)
+}
+
+/// Map query parameters to OpenAPI parameters. Each parameter is only
+/// included once, even if it appears multiple times in the recipe.
+fn build_query(
+    query: &[(String, Template)],
+) -> impl '_ + Iterator<Item = Parameter> {
+    let mut seen = Vec::new();
+    query.iter().filter_map(move |(name, value)| {
+        if seen.contains(&name) {
+            return None;
+        }
+        seen.push(name);
+        Some(Parameter::Query {
+            parameter_data: parameter_data(name.clone(), Some(value), false),
+            allow_reserved: false,
+            style: Default::default(),
+            allow_empty_value: None,
+        })
+    })
+}
+
+/// Map headers to OpenAPI parameters
+fn build_headers(
+    headers: &[(String, Template)],
+) -> impl '_ + Iterator<Item = Parameter> {
+    headers
+        .iter()
+        // These are defined by other parts of the spec, and parameter
+        // definitions for them are ignored.
+        // https://spec.openapis.org/oas/v3.0.3#fixed-fields-9
+        .filter(|(name, _)| {
+            !["accept", "content-type", "authorization"]
+                .contains(&name.to_ascii_lowercase().as_str())
+        })
+        .map(|(name, value)| Parameter::Header {
+            parameter_data: parameter_data(name.clone(), Some(value), false),
+            style: Default::default(),
+        })
+}
+
+/// Build the common parameter fields. All parameters are typed as strings,
+/// because we have no way of knowing what the server actually expects.
+fn parameter_data(
+    name: String,
+    example: Option<&Template>,
+    required: bool,
+) -> ParameterData {
+    ParameterData {
+        name,
+        description: None,
+        required,
+        deprecated: None,
+        format: ParameterSchemaOrContent::Schema(ReferenceOr::Item(
+            string_schema(),
+        )),
+        example: example
+            .map(Template::display)
+            .filter(|example| !example.is_empty())
+            .map(|example| example.into_owned().into()),
+        examples: Default::default(),
+        explode: None,
+        extensions: Default::default(),
+    }
+}
+
+fn string_schema() -> Schema {
+    Schema {
+        schema_data: SchemaData::default(),
+        schema_kind: SchemaKind::Type(Type::String(StringType::default())),
+    }
+}
+
+/// Convert a recipe body to a request body. Each body variant becomes a
+/// separate content type.
+fn build_body(body: &RecipeBody) -> RequestBody {
+    let mut content = IndexMap::new();
+    let mut add = |body: &RecipeBody| {
+        let Some((content_type, media_type)) = build_media_type(body) else {
+            return;
+        };
+        if content.contains_key(&content_type) {
+            warn!(
+                "Multiple body variants with content type `{content_type}`; \
+                only the first will be exported"
+            );
+        } else {
+            content.insert(content_type, media_type);
+        }
+    };
+
+    match body {
+        RecipeBody::Variants { variants, .. } => {
+            variants.values().for_each(add)
+        }
+        _ => add(body),
+    }
+
+    RequestBody {
+        content,
+        required: true,
+        ..Default::default()
+    }
+}
+
+/// Get the content type and example for a single (non-variant) body
+fn build_media_type(body: &RecipeBody) -> Option<(String, MediaType)> {
+    /// Convert a form to an example object of strings
+    fn form_example(fields: &IndexMap<String, Template>) -> serde_json::Value {
+        fields
+            .iter()
+            .map(|(field, value)| {
+                (field.clone(), value.display().into_owned().into())
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    let body_mime = body.mime();
+    let example = match body {
+        RecipeBody::Raw { body, .. } => {
+            let body = body.display();
+            // If the body is JSON, export it as structured data
+            let example = if body_mime.as_ref() == Some(&mime::APPLICATION_JSON)
+            {
+                serde_json::from_str(&body).ok()
+            } else {
+                None
+            };
+            Some(example.unwrap_or_else(|| body.into_owned().into()))
+        }
+        RecipeBody::Json(json) => Some(json.to_json()),
+        RecipeBody::Xml(body) => Some(body.display().into_owned().into()),
+        RecipeBody::FormUrlencoded(fields)
+        | RecipeBody::FormMultipart(fields) => Some(form_example(fields)),
+        // We have no way of knowing what the command will output
+        RecipeBody::Command { .. } => None,
+        RecipeBody::Variants { .. } => {
+            warn!("Nested body variants cannot be exported");
+            return None;
+        }
+    };
+    let content_type = match body_mime {
+        Some(body_mime) => body_mime.to_string(),
+        None if matches!(body, RecipeBody::Command { .. }) => {
+            mime::APPLICATION_OCTET_STREAM.to_string()
+        }
+        None => mime::TEXT_PLAIN.to_string(),
+    };
+
+    Some((
+        content_type,
+        MediaType {
+            schema: Some(ReferenceOr::Item(Schema {
+                schema_data: SchemaData::default(),
+                schema_kind: SchemaKind::Any(Default::default()),
+            })),
+            example,
+            ..Default::default()
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+    use slumber_core::{
+        collection::Folder,
+        test_util::{by_id, Factory},
+    };
+
+    /// Server segment is stripped and template keys become path params
+    #[rstest]
+    #[case::host_key(
+        "{{host}}/users/{{user_id}}",
+        "/users/{user_id}",
+        &["user_id"],
+    )]
+    #[case::literal_host(
+        "https://example.com/users/{{id}}/posts",
+        "/users/{id}/posts",
+        &["id"],
+    )]
+    #[case::templated_origin(
+        "{{scheme}}://{{host}}/users/{{id}}",
+        "/users/{id}",
+        &["id"],
+    )]
+    #[case::templated_origin_root("{{scheme}}://{{host}}", "/", &[])]
+    #[case::query("{{host}}/users?limit=10", "/users", &[])]
+    #[case::root("{{host}}", "/", &[])]
+    #[case::no_host("users/{{id}}", "/users/{id}", &["id"])]
+    fn test_build_path(
+        #[case] url: &str,
+        #[case] expected_path: &str,
+        #[case] expected_params: &[&str],
+    ) {
+        let (path, params) = build_path(&url.parse().unwrap());
+        assert_eq!(path, expected_path);
+        assert_eq!(params, expected_params);
+    }
+
+    /// Export a full collection and check the generated spec
+    #[test]
+    fn test_to_openapi() {
+        let recipe = Recipe {
+            id: "get_user".into(),
+            name: Some("Get User".into()),
+            method: HttpMethod::Post,
+            url: "{{host}}/users/{{user_id}}".parse().unwrap(),
+            query: vec![
+                ("expand".into(), "true".into()),
+                ("expand".into(), "false".into()),
+            ],
+            headers: vec![
+                ("X-Request".into(), "".into()),
+                ("Content-Type".into(), "application/json".into()),
+            ],
+            body: Some(RecipeBody::untemplated_json(json!({"name": "Fish"}))),
+            authentication: Some(Authentication::Bearer("token".into())),
+            ..Recipe::factory(())
+        };
+        let folder = Folder {
+            id: "users".into(),
+            name: Some("Users".into()),
+            children: by_id([RecipeNode::from(recipe)]),
+        };
+        let collection = Collection {
+            profiles: by_id([Profile {
+                id: "local".into(),
+                name: Some("Local".into()),
+                data: [("host".into(), "http://localhost".into())].into(),
+                ..Profile::factory(())
+            }]),
+            recipes: by_id([RecipeNode::from(folder)]).into(),
+            ..Collection::default()
+        };
+
+        let spec = serde_json::to_value(to_openapi(&collection)).unwrap();
+        assert_eq!(
+            spec["servers"],
+            json!([{"url": "http://localhost", "description": "Local"}])
+        );
+        assert_eq!(spec["tags"], json!([{"name": "Users"}]));
+        assert_eq!(
+            spec["components"]["securitySchemes"]["bearerAuth"],
+            json!({"type": "http", "scheme": "bearer"})
+        );
+
+        let operation = &spec["paths"]["/users/{user_id}"]["post"];
+        assert_eq!(operation["operationId"], json!("get_user"));
+        assert_eq!(operation["summary"], json!("Get User"));
+        assert_eq!(operation["tags"], json!(["Users"]));
+        assert_eq!(operation["security"], json!([{"bearerAuth": []}]));
+        // Duplicate query params are merged, and Content-Type is excluded
+        let parameters = operation["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|parameter| {
+                (
+                    parameter["in"].as_str().unwrap(),
+                    parameter["name"].as_str().unwrap(),
+                    parameter.get("example"),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parameters,
+            [
+                ("path", "user_id", None),
+                ("query", "expand", Some(&json!("true"))),
+                ("header", "X-Request", None),
+            ]
+        );
+        assert_eq!(
+            operation["requestBody"]["content"]["application/json"]["example"],
+            json!({"name": "Fish"})
+        );
+    }
+}
@@ -5,12 +5,11 @@
 use anyhow::anyhow;
 use indexmap::IndexMap;
 use itertools::Itertools;
-use serde::de::IgnoredAny;
 use slumber_core::{
     collection::{
         Authentication, Chain, ChainId, ChainOutputTrim, ChainSource,
-        Collection, HasId, Profile, ProfileId, Recipe, RecipeBody, RecipeId,
-        RecipeNode, RecipeTree, SelectorMode,
+        Collection, HasId, PathRoot, Profile, ProfileId, Recipe, RecipeBody,
+        RecipeId, RecipeNode, RecipeTree, SelectorMode,
     },
     http::{content_type::ContentType, HttpMethod},
     template::{Identifier, Template},
@@ -125,7 +124,11 @@ fn try_build_chain_from_load_body(
         id: id.into(),
         content_type,
         trim: ChainOutputTrim::None,
-        source: ChainSource::File { path },
+        decode: Vec::new(),
+        source: ChainSource::File {
+            path,
+            relative_to: PathRoot::default(),
+        },
         sensitive: false,
         selector: None,
         selector_mode: SelectorMode::Single,
@@ -233,7 +236,9 @@ fn try_build_recipe(
         _ => (None, None),
     };
 
-    let headers = build_slumber_templates(request.headers);
+    let headers = build_slumber_templates(request.headers)
+        .into_iter()
+        .collect();
 
     let recipe = Recipe {
         id,
@@ -244,6 +249,16 @@ fn try_build_recipe(
         body,
         headers,
         query,
+        persist: true,
+        cache: None,
+        confirm: false,
+        queue: None,
+        http_version: None,
+        accept: None,
+        auto_send: None,
+        env: IndexMap::new(),
+        profile: None,
+        script: None,
     };
 
     Ok(CompleteRecipe { recipe, chain })
@@ -292,7 +307,14 @@ fn build_profile_map(
         id: profile_id.clone(),
         name: Some(flavor_name),
         default: true,
-        data: build_slumber_templates(variables),
+        from_dotenv: None,
+        color: None,
+        label: None,
+        chains: IndexMap::new(),
+        data: build_slumber_templates(variables)
+            .into_iter()
+            .map(|(name, template)| (name, template.into()))
+            .collect(),
     };
 
     IndexMap::from([(profile_id.clone(), default_profile)])
@@ -321,7 +343,7 @@ fn try_build_collection(rest_format: RestFormat) -> anyhow::Result<Collection> {
         profiles,
         chains,
         recipes,
-        _ignore: IgnoredAny,
+        variables: Default::default(),
     })
 }
 
@@ -430,6 +452,7 @@ mod tests {
         assert_eq!(chain.id().clone(), ChainId::from("Request_0_body"));
         let expected_source = ChainSource::File {
             path: Template::raw("./test_data/rest_pets.json".into()),
+            relative_to: PathRoot::Collection,
         };
         assert_eq!(chain.source, expected_source);
         assert_eq!(chain.content_type, Some(ContentType::Json));
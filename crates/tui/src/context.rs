@@ -1,7 +1,9 @@
 use crate::{input::InputEngine, view::Styles};
-use slumber_config::Config;
+use ratatui_image::picker::{Picker, ProtocolType};
+use slumber_config::{Config, InlineImages, Theme};
 use slumber_core::http::HttpEngine;
 use std::sync::OnceLock;
+use tracing::{info, warn};
 
 /// The singleton value for the context. Initialized once during startup, then
 /// freely available *read only* everywhere.
@@ -25,13 +27,19 @@ pub struct TuiContext {
     pub input_engine: InputEngine,
     /// For sending HTTP requests
     pub http_engine: HttpEngine,
+    /// Used to render image responses. `None` if inline images are disabled
+    /// or unsupported by the terminal
+    pub image_picker: Option<Picker>,
 }
 
 impl TuiContext {
     /// Initialize global context. Should be called only once, during startup.
     pub fn init(config: Config) {
+        // Only query the terminal if the user hasn't picked a theme
+        let theme = config.theme.clone().unwrap_or_else(detect_theme);
+        let image_picker = detect_image_picker(config.inline_images);
         INSTANCE
-            .set(Self::new(config))
+            .set(Self::new(config, theme, image_picker))
             .expect("Global context is already initialized");
     }
 
@@ -39,11 +47,13 @@ impl TuiContext {
     /// and if the context is already initialized, do nothing.
     #[cfg(test)]
     pub fn init_test() {
-        INSTANCE.get_or_init(|| Self::new(Config::default()));
+        INSTANCE.get_or_init(|| {
+            Self::new(Config::default(), Theme::default(), None)
+        });
     }
 
-    fn new(config: Config) -> Self {
-        let styles = Styles::new(&config.theme);
+    fn new(config: Config, theme: Theme, image_picker: Option<Picker>) -> Self {
+        let styles = Styles::new(&theme);
         let input_engine = InputEngine::new(config.input_bindings.clone());
         let http_engine = HttpEngine::new(&config.http);
         Self {
@@ -51,6 +61,7 @@ impl TuiContext {
             styles,
             input_engine,
             http_engine,
+            image_picker,
         }
     }
 
@@ -61,3 +72,58 @@ impl TuiContext {
         INSTANCE.get().expect("Global context is not initialized")
     }
 }
+
+/// Pick a default theme based on the terminal's background color. Detection
+/// queries the terminal (OSC 11), so this has to be called before the TUI takes
+/// over the terminal. If detection fails, assume a dark background.
+fn detect_theme() -> Theme {
+    /// Luma above this is considered a light background
+    const LIGHT_THRESHOLD: f32 = 0.6;
+
+    match terminal_light::luma() {
+        Ok(luma) if luma > LIGHT_THRESHOLD => {
+            info!(luma, "Detected light terminal background");
+            Theme::light()
+        }
+        Ok(luma) => {
+            info!(luma, "Detected dark terminal background");
+            Theme::default()
+        }
+        Err(error) => {
+            warn!(%error, "Error detecting terminal background, assuming dark");
+            Theme::default()
+        }
+    }
+}
+
+/// Detect which graphics protocol the terminal supports, for rendering images.
+/// Like [detect_theme], this queries the terminal so it has to be called
+/// before the TUI takes over the terminal.
+fn detect_image_picker(mode: InlineImages) -> Option<Picker> {
+    if mode == InlineImages::Never {
+        return None;
+    }
+
+    #[cfg(unix)]
+    let picker = Picker::from_termios();
+    // Font size can't be queried on Windows, so guess
+    #[cfg(not(unix))]
+    let picker = Ok::<_, std::io::Error>(Picker::new((8, 16)));
+
+    let mut picker = match picker {
+        Ok(picker) => picker,
+        Err(error) => {
+            warn!(%error, "Error querying terminal for image support");
+            return None;
+        }
+    };
+    let protocol = picker.guess_protocol();
+    info!(?protocol, "Detected terminal image protocol");
+    // Half-blocks work anywhere, but they're low fidelity. Only use them if
+    // the user explicitly asked for images
+    if protocol == ProtocolType::Halfblocks && mode == InlineImages::Auto {
+        None
+    } else {
+        Some(picker)
+    }
+}
@@ -8,10 +8,10 @@ use itertools::Itertools;
 use reqwest::StatusCode;
 use slumber_core::{
     collection::{ProfileId, RecipeId},
-    db::CollectionDatabase,
+    db::{self, CollectionDatabase},
     http::{
-        Exchange, ExchangeSummary, RequestBuildError, RequestError, RequestId,
-        RequestRecord,
+        DownloadProgress, Exchange, ExchangeSummary, RequestBuildError,
+        RequestError, RequestId, RequestRecord,
     },
 };
 use std::{
@@ -79,7 +79,11 @@ impl RequestStore {
     }
 
     /// Mark a request as loading. Return the updated state.
-    pub fn loading(&mut self, request: Arc<RequestRecord>) -> &RequestState {
+    pub fn loading(
+        &mut self,
+        request: Arc<RequestRecord>,
+        progress: Arc<DownloadProgress>,
+    ) -> &RequestState {
         self.replace(request.id, |state| {
             // Requests should go building->loading, but it's possible it got
             // cancelled right before this was called
@@ -88,6 +92,7 @@ impl RequestStore {
                     request,
                     // Reset timer
                     start_time: Utc::now(),
+                    progress,
                     join_handle,
                 }
             } else {
@@ -181,6 +186,7 @@ impl RequestStore {
                 request,
                 start_time,
                 join_handle,
+                ..
             } => {
                 join_handle.abort();
                 RequestState::Cancelled {
@@ -213,10 +219,13 @@ impl RequestStore {
     ) -> anyhow::Result<Option<&RequestState>> {
         let request = match self.requests.entry(id) {
             Entry::Occupied(entry) => Some(entry.into_mut()),
-            Entry::Vacant(entry) => self
-                .database
-                .get_request(id)?
-                .map(|exchange| entry.insert(RequestState::response(exchange))),
+            Entry::Vacant(entry) => {
+                skip_if_locked(self.database.get_request(id))?
+                    .flatten()
+                    .map(|exchange| {
+                        entry.insert(RequestState::response(exchange))
+                    })
+            }
         };
         Ok(request.map(|r| &*r))
     }
@@ -229,9 +238,11 @@ impl RequestStore {
         recipe_id: &RecipeId,
     ) -> anyhow::Result<Option<&RequestState>> {
         // Get the latest record in the DB
-        let exchange = self
-            .database
-            .get_latest_request(profile_id.into(), recipe_id)?;
+        let exchange = skip_if_locked(
+            self.database
+                .get_latest_request(profile_id.into(), recipe_id),
+        )?
+        .flatten();
         if let Some(exchange) = exchange {
             // Cache this record if it isn't already
             self.requests
@@ -266,9 +277,10 @@ impl RequestStore {
     ) -> anyhow::Result<impl 'a + Iterator<Item = RequestStateSummary>> {
         // Load summaries from the DB. We do *not* want to insert these into the
         // store, because they don't include request/response data
-        let loaded = self
-            .database
-            .get_all_requests(profile_id.into(), recipe_id)?;
+        let loaded = skip_if_locked(
+            self.database.get_all_requests(profile_id.into(), recipe_id),
+        )?
+        .unwrap_or_default();
 
         // Find what we have in memory already
         let iter = self
@@ -320,6 +332,20 @@ impl RequestStore {
     }
 }
 
+/// Convert a database error caused by another process holding a lock into
+/// `None`. History is a nice-to-have, so when the database is busy we'd rather
+/// show only what's in memory than block the user with an error.
+fn skip_if_locked<T>(result: anyhow::Result<T>) -> anyhow::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(error) if db::is_locked(&error) => {
+            warn!(%error, "Database is locked; skipping history");
+            Ok(None)
+        }
+        Err(error) => Err(error),
+    }
+}
+
 /// State of an HTTP response, which can be in various states of
 /// completion/failure. Each request *recipe* should have one request state
 /// stored in the view at a time.
@@ -346,6 +372,9 @@ pub enum RequestState {
         /// pointer to the request as well
         request: Arc<RequestRecord>,
         start_time: DateTime<Utc>,
+        /// Download progress of the response body, updated by the request
+        /// task as data comes in
+        progress: Arc<DownloadProgress>,
         join_handle: JoinHandle<()>,
     },
 
@@ -462,6 +491,8 @@ impl RequestState {
             Some(ResponseMetadata {
                 status: exchange.response.status,
                 size: exchange.response.body.size(),
+                full_size: exchange.response.body.full_size(),
+                cached: exchange.cached,
             })
         } else {
             None
@@ -581,6 +612,10 @@ pub struct ResponseMetadata {
     pub status: StatusCode,
     /// Size of the response *body*
     pub size: usize,
+    /// If the body was truncated, the size of the full body
+    pub full_size: Option<usize>,
+    /// Was the response reused from history instead of being sent?
+    pub cached: bool,
 }
 
 /// A simplified version of [RequestState], which only stores metadata. This is
@@ -49,7 +49,7 @@ async fn test_life_cycle_success() {
     );
     assert_matches!(store.get(id), Some(RequestState::Building { .. }));
 
-    store.loading(Arc::clone(&exchange.request));
+    store.loading(Arc::clone(&exchange.request), Default::default());
     assert_matches!(store.get(id), Some(RequestState::Loading { .. }));
 
     store.response(exchange);
@@ -115,7 +115,7 @@ async fn test_life_cycle_request_error() {
     );
     assert_matches!(store.get(id), Some(RequestState::Building { .. }));
 
-    store.loading(Arc::clone(&exchange.request));
+    store.loading(Arc::clone(&exchange.request), Default::default());
     assert_matches!(store.get(id), Some(RequestState::Loading { .. }));
 
     store.request_error(RequestError {
@@ -164,7 +164,7 @@ async fn test_life_cycle_cancel() {
             ff.store(true, Ordering::Relaxed);
         }),
     );
-    store.loading(exchange.request);
+    store.loading(exchange.request, Default::default());
     assert_matches!(store.get(id), Some(RequestState::Loading { .. }));
     store.cancel(id);
     assert_matches!(store.get(id), Some(RequestState::Cancelled { .. }));
@@ -308,6 +308,7 @@ async fn test_load_summaries(harness: TestHarness) {
         RequestState::Loading {
             request: request.into(),
             start_time: Utc::now(),
+            progress: Default::default(),
             join_handle: tokio::spawn(async {}),
         },
     );
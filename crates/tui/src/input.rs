@@ -157,9 +157,18 @@ impl Default for InputEngine {
                     modifiers: KeyModifiers::SHIFT,
                 }.into(),
                 Action::OpenActions => KeyCode::Char('x').into(),
+                Action::CommandPalette => KeyCombination {
+                    code: KeyCode::Char('p'),
+                    modifiers: KeyModifiers::CONTROL,
+                }.into(),
+                Action::NotificationLog => KeyCode::Char('n').into(),
                 Action::OpenHelp => KeyCode::Char('?').into(),
                 Action::Fullscreen => KeyCode::Char('f').into(),
                 Action::ReloadCollection => KeyCode::F(5).into(),
+                Action::ResendLast => KeyCombination {
+                    code: KeyCode::Char('r'),
+                    modifiers: KeyModifiers::SHIFT,
+                }.into(),
                 Action::History => KeyCode::Char('h').into(),
                 Action::Search => KeyCode::Char('/').into(),
                 Action::Export => KeyCode::Char(':').into(),
@@ -178,6 +187,9 @@ impl Default for InputEngine {
                 Action::Cancel => KeyCode::Esc.into(),
                 Action::Edit => KeyCode::Char('e').into(),
                 Action::Reset => KeyCode::Char('z').into(),
+                Action::CycleMethod => KeyCode::Char('m').into(),
+                Action::ToggleRaw => KeyCode::Char('t').into(),
+                Action::ToggleGrouping => KeyCode::Char('g').into(),
                 Action::View => KeyCode::Char('v').into(),
                 Action::SelectProfileList => KeyCode::Char('p').into(),
                 Action::SelectRecipeList => KeyCode::Char('l').into(),
@@ -19,13 +19,15 @@ mod view;
 use crate::{
     context::TuiContext,
     http::{RequestState, RequestStore},
-    message::{Callback, Message, MessageSender, RequestConfig},
+    message::{Message, MessageSender, ProgressCallback, RequestConfig},
     util::{
-        clear_event_buffer, delete_temp_file, get_editor_command,
-        get_pager_command, save_file, signals, spawn, spawn_result,
-        ResultReported, CANCEL_TOKEN,
+        self, clear_event_buffer, delete_temp_file, get_editor_command,
+        get_pager_command, open_save_file, save_file, signals, spawn,
+        spawn_result, ResultReported, CANCEL_TOKEN,
+    },
+    view::{
+        highlight_command, PreviewPrompter, UpdateContext, View, ViewContext,
     },
-    view::{PreviewPrompter, UpdateContext, View},
 };
 use anyhow::{anyhow, bail, Context};
 use bytes::Bytes;
@@ -35,25 +37,29 @@ use crossterm::{
 };
 use futures::{pin_mut, StreamExt};
 use notify::{event::ModifyKind, RecursiveMode, Watcher};
-use ratatui::{prelude::CrosstermBackend, Terminal};
+use ratatui::{prelude::CrosstermBackend, style::Color, Terminal};
+use reqwest::StatusCode;
 use slumber_config::{Action, Config};
 use slumber_core::{
-    collection::{Collection, CollectionFile, ProfileId},
+    collection::{Collection, CollectionFile, ProfileId, RecipeId},
     db::{CollectionDatabase, Database, DatabaseMode},
-    http::{RequestId, RequestSeed},
-    template::{Prompter, Template, TemplateChunk, TemplateContext},
+    http::{BuildOptions, RequestId, RequestRecord, RequestSeed},
+    template::{
+        PromptCache, Prompter, Template, TemplateChunk, TemplateContext,
+    },
+    util::format_byte_size,
 };
 use std::{
     io::{self, Stdout},
     ops::Deref,
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::Command,
     sync::Arc,
     time::Duration,
 };
 use tokio::{
     select,
-    sync::mpsc::{self, UnboundedReceiver},
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
     task, time,
 };
 use tracing::{debug, error, info, info_span, trace};
@@ -76,17 +82,32 @@ pub struct Tui {
     collection_file: CollectionFile,
     should_run: bool,
     request_store: RequestStore,
+    /// Configuration of the most recently sent request, so it can be re-sent
+    /// even after the selection changes
+    last_request: Option<RequestConfig>,
+    /// Watch mode: re-send the selected recipe after each collection reload
+    watch: bool,
+    /// Pending auto-send for the selected recipe. Aborted if the selection
+    /// changes again before it fires
+    auto_send_handle: Option<task::JoinHandle<()>>,
 }
 
 type Term = Terminal<CrosstermBackend<Stdout>>;
 
 impl Tui {
     /// Rough **maximum** time for each iteration of the main loop
-    const TICK_TIME: Duration = Duration::from_millis(250);
+    pub(crate) const TICK_TIME: Duration = Duration::from_millis(250);
+    /// How long a recipe has to stay selected before it's auto-sent. This
+    /// prevents a flurry of requests when scrolling through the recipe list
+    const AUTO_SEND_DELAY: Duration = Duration::from_millis(500);
 
     /// Start the TUI. Any errors that occur during startup will be panics,
-    /// because they prevent TUI execution.
-    pub async fn start(collection_path: Option<PathBuf>) -> anyhow::Result<()> {
+    /// because they prevent TUI execution. If `watch` is enabled, the selected
+    /// recipe is re-sent every time the collection file changes.
+    pub async fn start(
+        collection_path: Option<PathBuf>,
+        watch: bool,
+    ) -> anyhow::Result<()> {
         initialize_panic_handler();
         let collection_path = CollectionFile::try_path(None, collection_path)?;
 
@@ -101,8 +122,20 @@ impl Tui {
         // to default, just show an error to the user
         let config = Config::load().reported(&messages_tx).unwrap_or_default();
         // Load a database for this particular collection
+        // Queries from the view run on the main thread, so don't block it by
+        // retrying when the database is locked. Those reads already degrade
+        // gracefully, and request history is written off the main thread
         let database = Database::load()?
+            .with_busy_timeout(config.database_busy_timeout)?
+            .with_lock_retry(false)
+            .with_compression(config.database_compression)
+            .with_max_rows(config.history_max_rows)
+            .with_encryption(&config.database_encryption)?
             .into_collection(&collection_path, DatabaseMode::ReadWrite)?;
+        // Clean up old history. This isn't fatal, so just show the error
+        database
+            .apply_retention(&config.history_retention)
+            .reported(&messages_tx);
         // Initialize global view context
         TuiContext::init(config);
 
@@ -114,6 +147,10 @@ impl Tui {
             .await
             .reported(&messages_tx)
             .unwrap_or_else(|| CollectionFile::with_path(collection_path));
+        report_collection_problems(&collection_file.collection, &messages_tx);
+        if collection_file.has_includes() {
+            messages_tx.send(Message::Notify(includes_not_watched()));
+        }
         let request_store = RequestStore::new(database.clone());
         let view = View::new(
             &collection_file,
@@ -138,6 +175,9 @@ impl Tui {
 
             view,
             request_store,
+            last_request: None,
+            watch,
+            auto_send_handle: None,
         };
 
         // Run everything in one local set, so that we can use !Send values
@@ -220,8 +260,11 @@ impl Tui {
             if needs_draw {
                 self.draw()?;
             }
-        }
 
+            // Write pending UI state if it's due. This is a no-op if nothing
+            // has changed, or if state is written immediately
+            ViewContext::save_ui_state(false);
+        }
         Ok(())
     }
 
@@ -253,7 +296,13 @@ impl Tui {
                 self.copy_request_body()?;
             }
             Message::CopyRequestCurl => {
-                self.copy_request_curl()?;
+                self.copy_request_as("cURL", RequestRecord::to_curl)?;
+            }
+            Message::CopyRequestFetch => {
+                self.copy_request_as("fetch()", RequestRecord::to_fetch)?;
+            }
+            Message::CopyRequestHttpie => {
+                self.copy_request_as("HTTPie", RequestRecord::to_httpie)?;
             }
             Message::CopyText(text) => self.view.copy_text(text),
             Message::SaveResponseBody { request_id, data } => {
@@ -266,6 +315,16 @@ impl Tui {
                     },
                 )?;
             }
+            Message::SaveFullResponseBody { request_id } => {
+                self.save_full_response_body(request_id).with_context(
+                    || {
+                        format!(
+                            "Error saving full response body \
+                            for request {request_id}"
+                        )
+                    },
+                )?;
+            }
 
             Message::FileEdit { path, on_complete } => {
                 let command = get_editor_command(&path)?;
@@ -285,12 +344,17 @@ impl Tui {
 
             // Manage HTTP life cycle
             Message::HttpBeginRequest => self.send_request()?,
+            Message::HttpResendLast => self.resend_last_request()?,
+            Message::HttpAutoSend => self.auto_send(),
+            Message::HttpBeginFolder { folder_id } => {
+                self.send_folder(folder_id)?
+            }
             Message::HttpBuildError { error } => {
                 let state = self.request_store.build_error(error);
                 self.view.update_request(state);
             }
-            Message::HttpLoading { request } => {
-                let state = self.request_store.loading(request);
+            Message::HttpLoading { request, progress } => {
+                let state = self.request_store.loading(request, progress);
                 self.view.update_request(state);
             }
             Message::HttpComplete(result) => {
@@ -338,7 +402,7 @@ impl Tui {
 
             Message::TemplatePreview {
                 template,
-                on_complete,
+                on_progress,
             } => {
                 self.render_template_preview(
                     template,
@@ -348,7 +412,7 @@ impl Tui {
                     // and this shortcut saves us a lot of plumbing so it's
                     // worth it
                     self.view.selected_profile_id().cloned(),
-                    on_complete,
+                    on_progress,
                 )?;
             }
 
@@ -377,40 +441,84 @@ impl Tui {
 
     /// Spawn a watcher to automatically reload the collection when the file
     /// changes. Return the watcher because it stops when dropped.
+    ///
+    /// We watch the file's parent directory rather than the file itself.
+    /// Some editors save by writing a temp file and renaming it over the
+    /// original, which would orphan a watch on the file. Watching the
+    /// directory picks up the new file at the same path.
     fn watch_collection(&self) -> anyhow::Result<impl Watcher> {
-        // Spawn a watcher for the collection file
-        let messages_tx = self.messages_tx();
-        let f = move |result: notify::Result<_>| match result {
-            // Only reload if the file *content* changes
-            Ok(
-                event @ notify::Event {
-                    kind: notify::EventKind::Modify(ModifyKind::Data(_)),
-                    ..
-                },
-            ) => {
-                info!(?event, "Collection file changed, reloading");
-                messages_tx.send(Message::CollectionStartReload);
+        // Resolve relative paths and symlinks, so we watch the directory of
+        // the real file and can compare it to the paths in change events
+        let path = canonicalize_lossy(self.collection_file.path());
+        let directory = path.parent().unwrap_or(&path).to_owned();
+
+        // Change events are funneled through a channel to a task that
+        // debounces them, so a flurry of writes triggers a single reload
+        let (changes_tx, changes_rx) = mpsc::unbounded_channel();
+        let debounce = TuiContext::get().config.reload_debounce;
+        spawn(Self::debounce_reloads(
+            changes_rx,
+            debounce,
+            self.messages_tx(),
+        ));
+
+        let watched_path = path.clone();
+        let f = move |result: notify::Result<notify::Event>| match result {
+            Ok(event) => {
+                // Only reload if the *content* of our file changes. A rename
+                // counts, because it may have replaced our file
+                let is_change = matches!(
+                    event.kind,
+                    notify::EventKind::Create(_)
+                        | notify::EventKind::Modify(
+                            ModifyKind::Data(_) | ModifyKind::Name(_)
+                        )
+                );
+                if is_change
+                    && event
+                        .paths
+                        .iter()
+                        .any(|p| canonicalize_lossy(p) == watched_path)
+                {
+                    trace!(?event, "Collection file changed");
+                    let _ = changes_tx.send(());
+                }
             }
-            // Do nothing for other event kinds
-            Ok(_) => {}
             Err(err) => {
                 error!(error = %err, "Error watching collection file");
             }
         };
         let mut watcher = notify::recommended_watcher(f)?;
-        watcher
-            .watch(self.collection_file.path(), RecursiveMode::NonRecursive)?;
-        info!(
-            path = ?self.collection_file.path(), ?watcher,
-            "Watching collection file for changes"
-        );
+        watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+        info!(?path, ?watcher, "Watching collection file for changes");
         Ok(watcher)
     }
 
+    /// Wait for changes to the collection file, and trigger a reload once no
+    /// more changes have arrived for the debounce period
+    async fn debounce_reloads(
+        mut changes_rx: UnboundedReceiver<()>,
+        debounce: Duration,
+        messages_tx: MessageSender,
+    ) {
+        while changes_rx.recv().await.is_some() {
+            // Keep waiting as long as changes keep coming in
+            while let Ok(Some(())) =
+                time::timeout(debounce, changes_rx.recv()).await
+            {}
+            info!("Collection file changed, reloading");
+            messages_tx.send(Message::CollectionStartReload);
+        }
+    }
+
     /// Reload state with a new collection
     fn reload_collection(&mut self, collection: Collection) {
+        report_collection_problems(&collection, &self.messages_tx);
         self.collection_file.collection = collection.into();
 
+        // The view context is about to be replaced, so save any pending state
+        // first or it'll be lost
+        ViewContext::save_ui_state(true);
         // Rebuild the whole view, because tons of things can change
         self.view = View::new(
             &self.collection_file,
@@ -418,10 +526,22 @@ impl Tui {
             self.database.clone(),
             self.messages_tx(),
         );
-        self.view.notify(format!(
+        let mut notification = format!(
             "Reloaded collection from {}",
             self.collection_file.path().to_string_lossy()
-        ));
+        );
+        if self.collection_file.has_includes() {
+            notification.push_str(". ");
+            notification.push_str(&includes_not_watched());
+        }
+        self.view.notify(notification);
+
+        // In watch mode, re-send the selected recipe with the new collection
+        if self.watch {
+            if let Some(config) = self.view.request_config() {
+                self.launch_config(config).reported(&self.messages_tx);
+            }
+        }
     }
 
     /// GOODBYE
@@ -499,7 +619,9 @@ impl Tui {
         Ok(())
     }
 
-    /// Render body for a request, then copy it to the clipboard
+    /// Render body for a request, then copy it to the clipboard. Binary
+    /// bodies can't go in the clipboard, so the user is prompted to save them
+    /// to a file instead.
     fn copy_request_body(&self) -> anyhow::Result<()> {
         let RequestConfig {
             profile_id,
@@ -517,16 +639,32 @@ impl Tui {
                 .await?
                 .ok_or(anyhow!("Request has no body"))?;
             // Clone the bytes :(
-            let body = String::from_utf8(body.into())
-                .context("Cannot copy request body")?;
-            messages_tx.send(Message::CopyText(body));
-            Ok(())
+            match String::from_utf8(body.to_vec()) {
+                Ok(body) => {
+                    messages_tx.send(Message::CopyText(body));
+                    Ok(())
+                }
+                Err(_) => {
+                    messages_tx.send(Message::Notify(
+                        "Body is binary and can't be copied; save it to a \
+                        file instead"
+                            .into(),
+                    ));
+                    save_file(messages_tx, None, body).await
+                }
+            }
         });
         Ok(())
     }
 
-    /// Render a request, then copy the equivalent curl command to the clipboard
-    fn copy_request_curl(&self) -> anyhow::Result<()> {
+    /// Render a request, then generate an equivalent for a third-party client
+    /// (e.g. a curl command). The generated text is shown in a preview modal,
+    /// and copied to the clipboard if the user confirms
+    fn copy_request_as(
+        &self,
+        format: &str,
+        generate: fn(&RequestRecord) -> anyhow::Result<String>,
+    ) -> anyhow::Result<()> {
         let RequestConfig {
             profile_id,
             recipe_id,
@@ -535,14 +673,24 @@ impl Tui {
         let seed = RequestSeed::new(recipe_id, options);
         let template_context = self.template_context(profile_id, false)?;
         let messages_tx = self.messages_tx();
+        let title = format!("Copy as {format}?");
         // Spawn a task to do the render+copy
         spawn_result(async move {
             let ticket = TuiContext::get()
                 .http_engine
                 .build(seed, &template_context)
                 .await?;
-            let command = ticket.record().to_curl()?;
-            messages_tx.send(Message::CopyText(command));
+            let text = generate(ticket.record())?;
+            // Show the text before copying, so the user can check it
+            if util::confirm_content(
+                &messages_tx,
+                title,
+                highlight_command(&text),
+            )
+            .await
+            {
+                messages_tx.send(Message::CopyText(text));
+            }
             Ok(())
         });
         Ok(())
@@ -576,6 +724,42 @@ impl Tui {
         Ok(())
     }
 
+    /// Save the full body of a truncated response to a file. The truncated
+    /// portion was spooled to a temp file when the response was received, so
+    /// this doesn't need to send the request again.
+    fn save_full_response_body(
+        &self,
+        request_id: RequestId,
+    ) -> anyhow::Result<()> {
+        let Some(request_state) = self.request_store.get(request_id) else {
+            bail!("Request not in store")
+        };
+        let RequestState::Response { exchange } = request_state else {
+            bail!("Request is not complete")
+        };
+        let default_path = exchange.response.file_name();
+        let response = Arc::clone(&exchange.response);
+        let messages_tx = self.messages_tx();
+
+        spawn_result(async move {
+            let Some((path, mut file)) =
+                open_save_file(&messages_tx, default_path).await?
+            else {
+                return Ok(());
+            };
+            let size = response.body.write_full(&mut file).await.with_context(
+                || format!("Error saving to `{}`", path.display()),
+            )?;
+            messages_tx.send(Message::Notify(format!(
+                "Saved {} to {}",
+                format_byte_size(size),
+                path.display()
+            )));
+            Ok(())
+        });
+        Ok(())
+    }
+
     /// Get the current request config for the selected recipe. The config
     /// defines how to build a request. If no recipe is selected, this returns
     /// an error. This should only be called in contexts where we can safely
@@ -589,18 +773,163 @@ impl Tui {
 
     /// Launch an HTTP request in a separate task
     fn send_request(&mut self) -> anyhow::Result<()> {
+        let config = self.request_config()?;
+        self.launch_config(config)
+    }
+
+    /// The recipe selection changed. If the selected recipe is configured to
+    /// auto-send, send it once the selection has settled. Any pending
+    /// auto-send for the previous selection is cancelled.
+    fn auto_send(&mut self) {
+        if let Some(handle) = self.auto_send_handle.take() {
+            handle.abort();
+        }
+
+        let default = TuiContext::get().config.auto_send;
+        let Some(config) = self.view.request_config() else {
+            return;
+        };
+        let should_send = self
+            .collection_file
+            .collection
+            .recipes
+            .get_recipe(&config.recipe_id)
+            .is_some_and(|recipe| recipe.auto_send(default));
+        if should_send {
+            let messages_tx = self.messages_tx();
+            self.auto_send_handle = Some(spawn(async move {
+                time::sleep(Self::AUTO_SEND_DELAY).await;
+                // The selection hasn't changed (otherwise we would've been
+                // aborted), so we can send whatever is selected
+                messages_tx.send(Message::HttpBeginRequest);
+            }));
+        }
+    }
+
+    /// Re-send the most recently sent request, using the same recipe, profile,
+    /// and build options. This doesn't depend on the current selection.
+    fn resend_last_request(&mut self) -> anyhow::Result<()> {
+        let Some(config) = self.last_request.clone() else {
+            self.view.notify("No request to resend");
+            return Ok(());
+        };
+        self.launch_config(config)
+    }
+
+    /// Launch a request from a config, remember it as the last sent request,
+    /// and select it in the UI
+    fn launch_config(&mut self, config: RequestConfig) -> anyhow::Result<()> {
+        self.last_request = Some(config.clone());
         let RequestConfig {
             profile_id,
             recipe_id,
             options,
-        } = self.request_config()?;
+        } = config;
+        let request_id =
+            self.launch_request(profile_id, recipe_id, options, None, None)?;
+
+        // New requests should get shown in the UI
+        self.view
+            .select_request(&mut self.request_store, request_id);
+
+        Ok(())
+    }
+
+    /// Launch a request for every recipe in a folder (recursively), using the
+    /// selected profile. Once they've all completed, show a summary of the
+    /// results. Concurrency is limited by the HTTP engine.
+    fn send_folder(&mut self, folder_id: RecipeId) -> anyhow::Result<()> {
+        let profile_id = self.view.selected_profile_id().cloned();
+        let collection = Arc::clone(&self.collection_file.collection);
+        let recipe_ids = collection.recipes.folder_recipe_ids(&folder_id)?;
+        if recipe_ids.is_empty() {
+            bail!("Folder `{folder_id}` does not contain any recipes");
+        }
+
+        // Each request reports its outcome (status code, or None for an error)
+        // here. If a request is cancelled, its sender is dropped without
+        // sending anything
+        let (outcome_tx, mut outcome_rx) = mpsc::unbounded_channel();
+        // Share prompt answers across the folder, so the user isn't asked the
+        // same thing once per recipe. This is dropped once every request has
+        // been built
+        let prompt_cache = PromptCache::default();
+        for recipe_id in &recipe_ids {
+            self.launch_request(
+                profile_id.clone(),
+                (*recipe_id).clone(),
+                BuildOptions::default(),
+                Some(outcome_tx.clone()),
+                Some(prompt_cache.clone()),
+            )?;
+        }
+        drop(outcome_tx);
+
+        let total = recipe_ids.len();
+        let messages_tx = self.messages_tx();
+        spawn(async move {
+            let success_statuses = &TuiContext::get().config.success_statuses;
+            let mut num_success = 0;
+            let mut num_http_error = 0;
+            let mut num_error = 0;
+            while let Some(outcome) = outcome_rx.recv().await {
+                match outcome {
+                    Some(status)
+                        if success_statuses.contains(status.as_u16()) =>
+                    {
+                        num_success += 1
+                    }
+                    Some(_) => num_http_error += 1,
+                    None => num_error += 1,
+                }
+            }
+            let num_cancelled =
+                total - num_success - num_http_error - num_error;
+            let mut message = format!(
+                "Folder `{folder_id}`: {num_success} succeeded, \
+                {num_http_error} HTTP error(s), {num_error} failed"
+            );
+            if num_cancelled > 0 {
+                message.push_str(&format!(", {num_cancelled} cancelled"));
+            }
+            messages_tx.send(Message::Notify(message));
+        });
+
+        Ok(())
+    }
+
+    /// Build and send a request in a separate task, and add it to the request
+    /// store. If `outcome_tx` is given, the response status (or `None` on
+    /// error) will be sent to it once the request completes. If `prompt_cache`
+    /// is given, prompt answers are shared with other requests using the same
+    /// cache.
+    fn launch_request(
+        &mut self,
+        profile_id: Option<ProfileId>,
+        recipe_id: RecipeId,
+        options: BuildOptions,
+        outcome_tx: Option<UnboundedSender<Option<StatusCode>>>,
+        prompt_cache: Option<PromptCache>,
+    ) -> anyhow::Result<RequestId> {
         // Launch the request in a separate task so it doesn't block.
         // These clones are all cheap.
 
-        let template_context =
+        let mut template_context =
             self.template_context(profile_id.clone(), false)?;
+        template_context.prompt_cache = prompt_cache;
         let messages_tx = self.messages_tx();
 
+        let recipe = self
+            .collection_file
+            .collection
+            .recipes
+            .get_recipe(&recipe_id);
+        let confirm = recipe.is_some_and(|recipe| recipe.confirm);
+        // Take a spot in the recipe's queue (if any) *now*, so queued requests
+        // run in the order they were submitted
+        let mut queue_slot = TuiContext::get()
+            .http_engine
+            .enqueue(recipe.and_then(|recipe| recipe.queue.as_deref()));
         let seed = RequestSeed::new(recipe_id.clone(), options);
         let request_id = seed.id;
 
@@ -608,6 +937,17 @@ impl Tui {
         // Don't use spawn_result here, because errors are handled specially for
         // requests
         let join_handle = spawn(async move {
+            let report_outcome = |outcome| {
+                if let Some(outcome_tx) = &outcome_tx {
+                    let _ = outcome_tx.send(outcome);
+                }
+            };
+
+            // Wait for everything ahead of us in the queue to finish. The slot
+            // is held until this task exits, so the next request in the queue
+            // waits for this one
+            queue_slot.wait().await;
+
             // Build the request
             let result = TuiContext::get()
                 .http_engine
@@ -618,17 +958,49 @@ impl Tui {
                 Err(error) => {
                     // Report the error, but don't actually return anything
                     messages_tx.send(Message::HttpBuildError { error });
+                    report_outcome(None);
                     return;
                 }
             };
 
+            // Show the rendered request and make the user confirm before
+            // sending. Declining cancels the request. Requests to protected
+            // hosts always need confirmation, since the host isn't known until
+            // the URL is rendered
+            let request = ticket.record();
+            let protected =
+                TuiContext::get().config.http.is_protected(&request.url);
+            if confirm || protected {
+                let message = if protected {
+                    format!(
+                        "{} is a protected host. Send {} {}?",
+                        request.url.host_str().unwrap_or_default(),
+                        request.method,
+                        request.url
+                    )
+                } else {
+                    format!("Send {} {}?", request.method, request.url)
+                };
+                if !util::confirm(&messages_tx, message).await {
+                    messages_tx.send(Message::HttpCancel(request_id));
+                    return;
+                }
+            }
+
             // Report liftoff
             messages_tx.send(Message::HttpLoading {
                 request: Arc::clone(ticket.record()),
+                progress: Arc::clone(ticket.progress()),
             });
 
             // Send the request and report the result to the main thread
             let result = ticket.send(&database).await;
+            report_outcome(
+                result
+                    .as_ref()
+                    .ok()
+                    .map(|exchange| exchange.response.status),
+            );
             messages_tx.send(Message::HttpComplete(result));
         });
 
@@ -641,11 +1013,7 @@ impl Tui {
             join_handle,
         );
 
-        // New requests should get shown in the UI
-        self.view
-            .select_request(&mut self.request_store, request_id);
-
-        Ok(())
+        Ok(request_id)
     }
 
     /// Spawn a task to render a template, storing the result in a pre-defined
@@ -655,13 +1023,20 @@ impl Tui {
         &self,
         template: Template,
         profile_id: Option<ProfileId>,
-        on_complete: Callback<Vec<TemplateChunk>>,
+        mut on_progress: ProgressCallback<[TemplateChunk]>,
     ) -> anyhow::Result<()> {
         let context = self.template_context(profile_id, true)?;
+        let messages_tx = self.messages_tx();
         spawn(async move {
-            // Render chunks, then write them to the output destination
-            let chunks = template.render_chunks(&context).await;
-            on_complete(chunks);
+            // Write the chunks to the output destination as each one is
+            // rendered, so slow keys (e.g. chained requests) don't hold up the
+            // rest of the preview. Each update needs a redraw to be visible
+            template
+                .render_chunks_progress(&context, |chunks| {
+                    on_progress(chunks);
+                    messages_tx.send(Message::Tick);
+                })
+                .await;
         });
         Ok(())
     }
@@ -688,10 +1063,13 @@ impl Tui {
         Ok(TemplateContext {
             selected_profile: profile_id,
             collection: collection.clone(),
+            collection_path: self.collection_file.path().to_owned(),
             http_engine,
             database: self.database.clone(),
-            overrides: Default::default(),
+            overrides: self.view.overrides().to_template_overrides(),
             prompter,
+            prompt_cache: None,
+            random_seed: context.config.random_seed,
             state: Default::default(),
         })
     }
@@ -700,12 +1078,57 @@ impl Tui {
 /// Restore terminal on app exit
 impl Drop for Tui {
     fn drop(&mut self) {
+        // Flush whatever UI state is left, so nothing is lost on exit. This
+        // runs on a graceful quit (including quit signals), and also if the
+        // main loop panics, because the task's future is dropped after the
+        // panic is caught
+        ViewContext::save_ui_state(true);
         if let Err(err) = restore_terminal() {
             error!(error = err.deref(), "Error restoring terminal, sorry!");
         }
     }
 }
 
+/// Show any problems found by [Collection::validate] to the user, plus
+/// TUI-specific problems such as invalid profile colors. These aren't fatal,
+/// but would otherwise go unnoticed until a request fails.
+fn report_collection_problems(
+    collection: &Collection,
+    messages_tx: &MessageSender,
+) {
+    let mut problems = collection.validate();
+    problems.extend(collection.profiles.values().filter_map(|profile| {
+        let color = profile.color.as_ref()?;
+        color.parse::<Color>().err().map(|_| {
+            format!("Profile `{}` has invalid color `{color}`", profile.id)
+        })
+    }));
+    if !problems.is_empty() {
+        let error = anyhow!(problems.join("\n")).context(format!(
+            "Found {} problem(s) in collection",
+            problems.len()
+        ));
+        messages_tx.send(Message::Error { error });
+    }
+}
+
+/// Only the main collection file is watched, so let the user know that
+/// changes to included files need a manual reload
+fn includes_not_watched() -> String {
+    let binding = TuiContext::get()
+        .input_engine
+        .binding_display(Action::ReloadCollection);
+    format!(
+        "Included files aren't watched for changes; press {binding} to reload"
+    )
+}
+
+/// Resolve a path to its canonical form. If that fails, e.g. because the file
+/// was just deleted, fall back to the original path
+fn canonicalize_lossy(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
 /// Restore terminal state during a panic
 fn initialize_panic_handler() {
     let original_hook = std::panic::take_hook();
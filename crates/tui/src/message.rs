@@ -9,8 +9,8 @@ use slumber_config::Action;
 use slumber_core::{
     collection::{Collection, ProfileId, RecipeId},
     http::{
-        BuildOptions, Exchange, RequestBuildError, RequestError, RequestId,
-        RequestRecord,
+        BuildOptions, DownloadProgress, Exchange, RequestBuildError,
+        RequestError, RequestId, RequestRecord,
     },
     template::{Prompt, Prompter, Select, Template, TemplateChunk},
     util::ResultTraced,
@@ -76,6 +76,11 @@ pub enum Message {
     CopyRequestBody,
     /// Render request, then generate an equivalent cURL command and copy it
     CopyRequestCurl,
+    /// Render request, then generate an equivalent JavaScript `fetch()` call
+    /// and copy it
+    CopyRequestFetch,
+    /// Render request, then generate an equivalent HTTPie command and copy it
+    CopyRequestHttpie,
     /// Copy some text to the clipboard
     CopyText(String),
 
@@ -99,10 +104,22 @@ pub enum Message {
 
     /// Launch an HTTP request from the given recipe/profile.
     HttpBeginRequest,
+    /// The selected recipe changed. If auto-send is enabled for it, send a
+    /// request once the selection settles
+    HttpAutoSend,
+    /// Launch the most recently sent request again, with the same
+    /// recipe/profile/options, regardless of current selection
+    HttpResendLast,
+    /// Launch a request for every recipe under a folder, using the selected
+    /// profile. A summary will be shown once they all finish.
+    HttpBeginFolder { folder_id: RecipeId },
     /// Request failed to build
     HttpBuildError { error: RequestBuildError },
     /// We launched the HTTP request
-    HttpLoading { request: Arc<RequestRecord> },
+    HttpLoading {
+        request: Arc<RequestRecord>,
+        progress: Arc<DownloadProgress>,
+    },
     /// The HTTP request either succeeded or failed. We don't need to store the
     /// recipe ID here because it's in the inner container already. Combining
     /// these two cases saves a bit of boilerplate.
@@ -138,6 +155,11 @@ pub enum Message {
         data: Option<String>,
     },
 
+    /// Save the *full* body of a truncated response to a file, by re-sending
+    /// the request and streaming the body to disk. This will prompt the user
+    /// for confirmation and a file name
+    SaveFullResponseBody { request_id: RequestId },
+
     /// Show a select list to the user, asking them to choose an item
     /// Use the included channel to return the selection.
     SelectStart(Select),
@@ -145,14 +167,17 @@ pub enum Message {
     /// Render a template string, to be previewed in the UI. Ideally this could
     /// be launched directly by the component that needs it, but only the
     /// controller has the data needed to build the template context. The given
-    /// callback will be called with the outcome (including inline errors).
+    /// callback will be called each time a key in the template finishes
+    /// rendering, with every chunk rendered so far (including inline errors).
+    /// Keys that haven't finished are included as raw text. The final call
+    /// contains the complete render.
     ///
     /// By holding a callback here, we avoid having to plumb the result all the
     /// way back down the component tree.
     TemplatePreview {
         template: Template,
         #[debug(skip)]
-        on_complete: Callback<Vec<TemplateChunk>>,
+        on_progress: ProgressCallback<[TemplateChunk]>,
     },
 
     /// Trigger a redraw. This should be called whenever we have reason to
@@ -163,8 +188,12 @@ pub enum Message {
 /// A static callback included in a message
 pub type Callback<T> = Box<dyn 'static + Send + Sync + FnOnce(T)>;
 
+/// A static callback included in a message, which can be called repeatedly to
+/// report progress
+pub type ProgressCallback<T> = Box<dyn 'static + Send + Sync + FnMut(&T)>;
+
 /// Configuration that defines how to render a request
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct RequestConfig {
     pub profile_id: Option<ProfileId>,
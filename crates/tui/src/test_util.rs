@@ -81,6 +81,15 @@ impl TestHarness {
         self.messages_rx.recv().await.expect("Message queue closed")
     }
 
+    /// Assert that the message queue is empty
+    pub fn assert_messages_empty(&mut self) {
+        let message = self.messages_rx.try_recv().ok();
+        assert!(
+            message.is_none(),
+            "Expected empty message queue, but got {message:?}"
+        );
+    }
+
     /// Clear all messages in the queue
     pub fn clear_messages(&mut self) {
         while self.messages_rx.try_recv().is_ok() {}
@@ -9,6 +9,7 @@ use crossterm::event;
 use editor_command::EditorBuilder;
 use futures::{future, FutureExt};
 use mime::Mime;
+use ratatui::text::Text;
 use slumber_core::{
     template::Prompt,
     util::{doc_link, paths::expand_home, ResultTraced},
@@ -24,7 +25,7 @@ use std::{
     time::Duration,
 };
 use tokio::{
-    fs::OpenOptions,
+    fs::{File, OpenOptions},
     io::AsyncWriteExt,
     select,
     sync::oneshot,
@@ -176,58 +177,14 @@ pub async fn save_file(
     default_path: Option<String>,
     data: Bytes,
 ) -> anyhow::Result<()> {
-    // If the user closed the prompt, just exit
-    let Some(path) =
-        prompt(&messages_tx, "Enter a path for the file", default_path).await
+    let Some((path, mut file)) =
+        open_save_file(&messages_tx, default_path).await?
     else {
         return Ok(());
     };
 
-    // If the user input nothing, assume they just want to exit
-    if path.is_empty() {
-        return Ok(());
-    }
-
-    let path = expand_home(PathBuf::from(path)); // Expand ~
-    let result = {
-        // Attempt to open the file *if it doesn't exist already*
-        let result = OpenOptions::new()
-            .create_new(true)
-            .write(true)
-            .open(&path)
-            .await;
-
-        match result {
-            Ok(file) => Ok(file),
-            // If the file already exists, ask for confirmation to overwrite
-            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
-                warn!(?path, "File already exists, asking to overwrite");
-
-                // Hi, sorry, follow up question. Are you sure?
-                if confirm(
-                    &messages_tx,
-                    format!("`{}` already exists, overwrite?", path.display()),
-                )
-                .await
-                {
-                    // REALLY attempt to open the file
-                    OpenOptions::new()
-                        .create(true)
-                        .write(true)
-                        .truncate(true)
-                        .open(&path)
-                        .await
-                } else {
-                    return Ok(());
-                }
-            }
-            Err(error) => Err(error),
-        }
-    };
-
     debug!(?path, bytes = data.len(), "Writing to file");
     async {
-        let mut file = result?;
         file.write_all(&data).await?;
         file.flush().await
     }
@@ -242,6 +199,66 @@ pub async fn save_file(
     Ok(())
 }
 
+/// Ask the user for a path and open that file for writing. If the file
+/// already exists, ask for confirmation before overwriting it. Return `None`
+/// if the user cancels at any point.
+pub async fn open_save_file(
+    messages_tx: &MessageSender,
+    default_path: Option<String>,
+) -> anyhow::Result<Option<(PathBuf, File)>> {
+    // If the user closed the prompt, just exit
+    let Some(path) =
+        prompt(messages_tx, "Enter a path for the file", default_path).await
+    else {
+        return Ok(None);
+    };
+
+    // If the user input nothing, assume they just want to exit
+    if path.is_empty() {
+        return Ok(None);
+    }
+
+    let path = expand_home(PathBuf::from(path)); // Expand ~
+
+    // Attempt to open the file *if it doesn't exist already*
+    let result = OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .open(&path)
+        .await;
+
+    let result = match result {
+        Ok(file) => Ok(file),
+        // If the file already exists, ask for confirmation to overwrite
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+            warn!(?path, "File already exists, asking to overwrite");
+
+            // Hi, sorry, follow up question. Are you sure?
+            if confirm(
+                messages_tx,
+                format!("`{}` already exists, overwrite?", path.display()),
+            )
+            .await
+            {
+                // REALLY attempt to open the file
+                OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .truncate(true)
+                    .open(&path)
+                    .await
+            } else {
+                return Ok(None);
+            }
+        }
+        Err(error) => Err(error),
+    };
+    let file = result
+        .with_context(|| format!("Error opening file `{}`", path.display()))
+        .traced()?;
+    Ok(Some((path, file)))
+}
+
 /// Get a command to open the given file in the user's configured editor.
 /// Default editor is `vim`. Return an error if the command couldn't be built.
 pub fn get_editor_command(file: &Path) -> anyhow::Result<Command> {
@@ -370,10 +387,32 @@ async fn prompt(
 }
 
 /// Ask the user a yes/no question and wait for a response
-async fn confirm(messages_tx: &MessageSender, message: impl ToString) -> bool {
+pub async fn confirm(
+    messages_tx: &MessageSender,
+    message: impl ToString,
+) -> bool {
+    send_confirm(messages_tx, message.to_string(), None).await
+}
+
+/// Ask the user a yes/no question about some content, showing the content
+/// alongside the question, and wait for a response
+pub async fn confirm_content(
+    messages_tx: &MessageSender,
+    message: impl ToString,
+    content: Text<'static>,
+) -> bool {
+    send_confirm(messages_tx, message.to_string(), Some(content)).await
+}
+
+async fn send_confirm(
+    messages_tx: &MessageSender,
+    message: String,
+    content: Option<Text<'static>>,
+) -> bool {
     let (tx, rx) = oneshot::channel();
     let confirm = Confirm {
-        message: message.to_string(),
+        message,
+        content,
         channel: tx.into(),
     };
     messages_tx.send(Message::ConfirmStart(confirm));
@@ -13,7 +13,7 @@ mod util;
 pub use common::modal::{IntoModal, ModalPriority};
 pub use context::{UpdateContext, ViewContext};
 pub use styles::Styles;
-pub use util::{Confirm, PreviewPrompter};
+pub use util::{highlight::highlight_command, Confirm, PreviewPrompter};
 
 use crate::{
     context::TuiContext,
@@ -22,7 +22,7 @@ use crate::{
     util::ResultReported,
     view::{
         common::modal::Modal,
-        component::{Component, Root},
+        component::{Component, Overrides, Root},
         debug::DebugMonitor,
         event::Event,
     },
@@ -103,6 +103,11 @@ impl View {
         self.root.data().selected_profile_id()
     }
 
+    /// Field overrides for the current session
+    pub fn overrides(&self) -> &Overrides {
+        self.root.data().overrides()
+    }
+
     /// Get a definition of the request that should be sent from the current
     /// recipe settings
     pub fn request_config(&self) -> Option<RequestConfig> {
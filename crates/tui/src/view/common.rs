@@ -16,8 +16,9 @@ pub mod text_window;
 use crate::{
     context::TuiContext,
     view::{draw::Generate, state::Notification},
+    Tui,
 };
-use chrono::{DateTime, Duration, Local, Utc};
+use chrono::{DateTime, Duration, Utc};
 use itertools::{Itertools, Position};
 use ratatui::{
     text::{Line, Span, Text},
@@ -53,6 +54,34 @@ impl<'a> Generate for Pane<'a> {
     }
 }
 
+/// Animated spinner to indicate that something is in progress. The frame is
+/// derived from the elapsed time, so it advances once per tick of the main
+/// loop, which redraws continuously while a request is active.
+pub struct Spinner {
+    pub start_time: DateTime<Utc>,
+}
+
+impl Spinner {
+    const FRAMES: &'static [&'static str] =
+        &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+}
+
+impl Generate for Spinner {
+    type Output<'this> = Span<'static>;
+
+    fn generate<'this>(self) -> Self::Output<'this>
+    where
+        Self: 'this,
+    {
+        let elapsed = (Utc::now() - self.start_time)
+            .to_std()
+            .unwrap_or_default()
+            .as_millis();
+        let ticks = elapsed / Tui::TICK_TIME.as_millis();
+        Self::FRAMES[ticks as usize % Self::FRAMES.len()].into()
+    }
+}
+
 /// Yes or no?
 pub struct Checkbox {
     pub checked: bool,
@@ -115,16 +144,17 @@ impl Generate for &Notification {
     where
         Self: 'this,
     {
+        let timezone = TuiContext::get().config.timezone;
         format!(
             "[{}] {}",
-            self.timestamp.with_timezone(&Local).format("%H:%M:%S"),
+            timezone.localize(&self.timestamp).format("%H:%M:%S"),
             self.message
         )
         .into()
     }
 }
 
-/// Format a timestamp in the local timezone
+/// Format a timestamp in the configured timezone
 impl Generate for DateTime<Utc> {
     type Output<'this> = Span<'this> where Self: 'this;
 
@@ -132,7 +162,8 @@ impl Generate for DateTime<Utc> {
     where
         Self: 'this,
     {
-        format_time(&self).to_string().into()
+        let timezone = TuiContext::get().config.timezone;
+        format_time(&timezone.localize(&self)).to_string().into()
     }
 }
 
@@ -170,14 +201,14 @@ impl Generate for StatusCode {
     where
         Self: 'this,
     {
-        let styles = &TuiContext::get().styles.status_code;
-        let is_error = self.is_client_error() || self.is_server_error();
+        let tui_context = TuiContext::get();
+        let styles = &tui_context.styles.status_code;
         Span::styled(
             self.to_string(),
-            if is_error {
-                styles.error
-            } else {
+            if tui_context.config.success_statuses.contains(self.as_u16()) {
                 styles.success
+            } else {
+                styles.error
             },
         )
     }
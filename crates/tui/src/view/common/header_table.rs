@@ -1,11 +1,12 @@
 use crate::view::{common::table::Table, draw::Generate};
 use itertools::Itertools;
 use ratatui::text::Text;
-use reqwest::header::HeaderMap;
+use reqwest::header::{HeaderName, HeaderValue};
 
-/// Render HTTP request/response headers in a table
+/// Render HTTP request/response headers in a table. Headers are passed as a
+/// list of pairs rather than a map so callers can filter/reorder them first.
 pub struct HeaderTable<'a> {
-    pub headers: &'a HeaderMap,
+    pub headers: Vec<(&'a HeaderName, &'a HeaderValue)>,
 }
 
 impl<'a> Generate for HeaderTable<'a> {
@@ -18,7 +19,7 @@ impl<'a> Generate for HeaderTable<'a> {
         Table {
             rows: self
                 .headers
-                .iter()
+                .into_iter()
                 .map(|(k, v)| [Text::from(k.as_str()), v.generate().into()])
                 .collect_vec(),
             header: Some(["Header", "Value"]),
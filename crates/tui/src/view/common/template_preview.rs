@@ -31,10 +31,10 @@ pub struct TemplatePreview {
     /// restitch the chunks or reapply highlighting on every render. Arc is
     /// needed to make the callback 'static.
     ///
-    /// This should only ever be written to once, but we can't use `OnceLock`
-    /// because it also gets an initial value. There should be effectively zero
-    /// contention on the mutex because of the single write, and reads being
-    /// single-threaded.
+    /// This is written once for each key in the template as it finishes
+    /// rendering, so the preview fills in incrementally. There should be
+    /// effectively zero contention on the mutex because writes are rare and
+    /// reads are single-threaded.
     text: Arc<Mutex<Identified<Text<'static>>>>,
 }
 
@@ -71,12 +71,12 @@ impl TemplatePreview {
         let text = Arc::new(Mutex::new(text));
 
         // Trigger a task to render the preview and write the answer back into
-        // the mutex
+        // the mutex. This will be called again for each key that's rendered
         if tui_context.config.preview_templates {
             let destination = Arc::clone(&text);
-            let on_complete = move |c| {
+            let on_progress = move |chunks: &[TemplateChunk]| {
                 Self::calculate_rendered_text(
-                    c,
+                    chunks,
                     &destination,
                     content_type,
                     style,
@@ -85,7 +85,7 @@ impl TemplatePreview {
 
             ViewContext::send_message(Message::TemplatePreview {
                 template,
-                on_complete: Box::new(on_complete),
+                on_progress: Box::new(on_progress),
             });
         }
 
@@ -101,12 +101,12 @@ impl TemplatePreview {
     /// Generate text from the rendered template, and replace the text in the
     /// mutex
     fn calculate_rendered_text(
-        chunks: Vec<TemplateChunk>,
+        chunks: &[TemplateChunk],
         destination: &Mutex<Identified<Text<'static>>>,
         content_type: Option<ContentType>,
         style: Style,
     ) {
-        let text = TextStitcher::stitch_chunks(&chunks);
+        let text = TextStitcher::stitch_chunks(chunks);
         let text = highlight::highlight_if(content_type, text).set_style(style);
         *destination
             .lock()
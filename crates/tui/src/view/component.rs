@@ -1,8 +1,11 @@
+mod command_palette;
 mod exchange_pane;
 mod help;
 mod history;
 mod internal;
 mod misc;
+mod notification_log;
+mod overrides;
 mod primary;
 mod profile_select;
 mod queryable_body;
@@ -12,7 +15,9 @@ mod request_view;
 mod response_view;
 mod root;
 
+pub use command_palette::CommandPaletteModal;
 pub use internal::Component;
+pub use overrides::Overrides;
 pub use root::Root;
 // Exported for the view context
 pub use recipe_pane::RecipeOverrideStore;
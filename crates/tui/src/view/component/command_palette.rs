@@ -0,0 +1,181 @@
+use crate::{
+    context::TuiContext,
+    message::Message,
+    view::{
+        common::{
+            list::List,
+            modal::Modal,
+            text_box::{TextBox, TextBoxEvent, TextBoxProps},
+        },
+        component::Component,
+        context::{UpdateContext, ViewContext},
+        draw::{Draw, DrawMetadata, Generate},
+        event::{Child, Event, EventHandler, OptionEvent, ToEmitter},
+        state::select::{SelectState, SelectStateEvent, SelectStateEventType},
+    },
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::{Line, Span},
+    Frame,
+};
+use slumber_config::Action;
+
+/// Modal to search all bound actions by name and run one. This is an
+/// alternative to memorizing keybindings. Selecting an action closes the
+/// palette and re-dispatches the action as if its binding had been pressed.
+#[derive(Debug)]
+pub struct CommandPaletteModal {
+    filter: Component<TextBox>,
+    actions: Component<SelectState<PaletteItem>>,
+}
+
+impl CommandPaletteModal {
+    /// Maximum number of actions to show at once
+    const MAX_HEIGHT: u16 = 15;
+
+    pub fn new() -> Self {
+        Self {
+            filter: TextBox::default()
+                .placeholder("Type to filter actions")
+                .into(),
+            actions: Self::build_select_state("").into(),
+        }
+    }
+
+    /// Build the list of actions, including only those whose name contains
+    /// the given filter. Actions are sourced from the input engine, so
+    /// unbound actions and the palette itself are excluded.
+    fn build_select_state(filter: &str) -> SelectState<PaletteItem> {
+        let filter = filter.trim().to_lowercase();
+        let items = TuiContext::get()
+            .input_engine
+            .bindings()
+            .keys()
+            .copied()
+            .filter(|action| {
+                action.visible()
+                    && *action != Action::CommandPalette
+                    && action.to_string().to_lowercase().contains(&filter)
+            })
+            .map(|action| PaletteItem { action })
+            .collect();
+        SelectState::builder(items)
+            .subscribe([SelectStateEventType::Submit])
+            .build()
+    }
+}
+
+impl Default for CommandPaletteModal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Modal for CommandPaletteModal {
+    fn title(&self) -> Line<'_> {
+        "Command Palette".into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        // Size based on the full list, so the modal doesn't jump around as the
+        // user types
+        let num_actions = TuiContext::get().input_engine.bindings().len();
+        (
+            Constraint::Percentage(60),
+            Constraint::Length((num_actions as u16).min(Self::MAX_HEIGHT) + 1),
+        )
+    }
+
+    fn on_close(self: Box<Self>, submitted: bool) {
+        if submitted {
+            let Some(item) = self.actions.into_data().into_selected() else {
+                return;
+            };
+            // Dispatch as a fresh input. The raw event is a no-op key, so
+            // nothing interprets it as text
+            ViewContext::send_message(Message::Input {
+                event: crossterm::event::Event::Key(KeyEvent::from(
+                    KeyCode::Null,
+                )),
+                action: Some(item.action),
+            });
+        }
+    }
+}
+
+impl EventHandler for CommandPaletteModal {
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> Option<Event> {
+        event
+            .opt()
+            .emitted(self.filter.to_emitter(), |event| match event {
+                TextBoxEvent::Focus => {}
+                TextBoxEvent::Change => {
+                    self.actions =
+                        Self::build_select_state(self.filter.data().text())
+                            .into();
+                }
+                TextBoxEvent::Cancel => self.close(false),
+                TextBoxEvent::Submit => self.close(true),
+            })
+            .emitted(self.actions.to_emitter(), |event| {
+                if let SelectStateEvent::Submit(_) = event {
+                    self.close(true);
+                }
+            })
+    }
+
+    fn children(&mut self) -> Vec<Component<Child<'_>>> {
+        // Text box gets first dibs on input, but it doesn't handle up/down so
+        // the user can still navigate the list while typing
+        vec![self.filter.to_child_mut(), self.actions.to_child_mut()]
+    }
+}
+
+impl Draw for CommandPaletteModal {
+    fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        let [filter_area, list_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
+                .areas(metadata.area());
+
+        self.filter
+            .draw(frame, TextBoxProps::default(), filter_area, true);
+        self.actions.draw(
+            frame,
+            List::from(self.actions.data()),
+            list_area,
+            true,
+        );
+    }
+}
+
+/// One action in the palette
+#[derive(Debug)]
+struct PaletteItem {
+    action: Action,
+}
+
+impl Generate for &PaletteItem {
+    type Output<'this>
+        = Line<'this>
+    where
+        Self: 'this;
+
+    fn generate<'this>(self) -> Self::Output<'this>
+    where
+        Self: 'this,
+    {
+        let tui_context = TuiContext::get();
+        Line::from(vec![
+            tui_context
+                .input_engine
+                .add_hint(self.action, self.action)
+                .into(),
+            Span::styled(
+                format!(" - {}", self.action.description()),
+                tui_context.styles.text.primary,
+            ),
+        ])
+    }
+}
@@ -2,10 +2,13 @@ use crate::{
     context::TuiContext,
     http::{RequestMetadata, ResponseMetadata},
     view::{
-        common::{tabs::Tabs, Pane},
+        common::{tabs::Tabs, Pane, Spinner},
         component::{
             request_view::RequestView,
-            response_view::{ResponseBodyView, ResponseHeadersView},
+            response_view::{
+                ResponseBodyView, ResponseCookiesView, ResponseHeadersView,
+                ResponseRawView, ResponseTimingView,
+            },
             Component,
         },
         context::UpdateContext,
@@ -15,18 +18,21 @@ use crate::{
         RequestState,
     },
 };
+use chrono::{DateTime, Utc};
 use derive_more::Display;
 use persisted::SingletonKey;
 use ratatui::{
-    layout::{Alignment, Constraint, Layout},
+    layout::{Alignment, Constraint, Layout, Rect},
     style::Style,
     text::{Line, Span},
-    widgets::{block::Title, Paragraph},
+    widgets::{block::Title, LineGauge, Paragraph},
     Frame,
 };
 use serde::{Deserialize, Serialize};
 use slumber_config::Action;
-use slumber_core::{collection::RecipeNodeType, util::format_byte_size};
+use slumber_core::{
+    collection::RecipeNodeType, http::DownloadProgress, util::format_byte_size,
+};
 use std::sync::Arc;
 use strum::{EnumCount, EnumIter};
 
@@ -157,6 +163,9 @@ enum Tab {
     #[default]
     Body,
     Headers,
+    Cookies,
+    Timing,
+    Raw,
 }
 
 /// Emitted event for the exchange pane component
@@ -233,6 +242,11 @@ impl Draw for ExchangePaneMetadata {
         if let Some(metadata) = self.response {
             frame.render_widget(
                 Line::from(vec![
+                    if metadata.cached {
+                        Span::styled("(cached) ", styles.text.edited)
+                    } else {
+                        Span::default()
+                    },
                     metadata.status.generate(),
                     " ".into(),
                     Span::styled(
@@ -245,6 +259,19 @@ impl Draw for ExchangePaneMetadata {
                             Style::default()
                         },
                     ),
+                    // Make it clear that we're not showing the whole body
+                    metadata
+                        .full_size
+                        .map(|full_size| {
+                            Span::styled(
+                                format!(
+                                    " (truncated from {})",
+                                    format_byte_size(full_size)
+                                ),
+                                styles.text.error,
+                            )
+                        })
+                        .unwrap_or_default(),
                 ])
                 .alignment(Alignment::Right),
                 area,
@@ -256,18 +283,25 @@ impl Draw for ExchangePaneMetadata {
 /// Content under the tab bar. Only rendered when a request state is present
 #[derive(Debug)]
 enum ExchangePaneContent {
-    Building,
+    Building {
+        start_time: DateTime<Utc>,
+    },
     BuildError {
         error: Paragraph<'static>,
     },
     Loading {
         request: Component<RequestView>,
+        start_time: DateTime<Utc>,
+        progress: Arc<DownloadProgress>,
     },
     Cancelled,
     Response {
         request: Component<RequestView>,
         response_headers: Component<ResponseHeadersView>,
         response_body: Component<ResponseBodyView>,
+        response_cookies: Component<ResponseCookiesView>,
+        response_timing: Component<ResponseTimingView>,
+        response_raw: Component<ResponseRawView>,
     },
     RequestError {
         request: Component<RequestView>,
@@ -282,12 +316,21 @@ struct ExchangePaneContentProps {
 impl ExchangePaneContent {
     fn new(request_state: &RequestState) -> Self {
         match request_state {
-            RequestState::Building { .. } => Self::Building,
+            RequestState::Building { start_time, .. } => Self::Building {
+                start_time: *start_time,
+            },
             RequestState::BuildError { error } => Self::BuildError {
                 error: error.generate(),
             },
-            RequestState::Loading { request, .. } => Self::Loading {
+            RequestState::Loading {
+                request,
+                start_time,
+                progress,
+                ..
+            } => Self::Loading {
                 request: RequestView::new(Arc::clone(request)).into(),
+                start_time: *start_time,
+                progress: Arc::clone(progress),
             },
             RequestState::Cancelled { .. } => Self::Cancelled,
             RequestState::Response { exchange } => Self::Response {
@@ -296,10 +339,13 @@ impl ExchangePaneContent {
                     &exchange.response,
                 ))
                 .into(),
-                response_body: ResponseBodyView::new(
-                    exchange.request.recipe_id.clone(),
-                    Arc::clone(&exchange.response),
-                )
+                response_body: ResponseBodyView::new(exchange).into(),
+                response_cookies: ResponseCookiesView::new(&exchange.response)
+                    .into(),
+                response_timing: ResponseTimingView::new(exchange).into(),
+                response_raw: ResponseRawView::new(Arc::clone(
+                    &exchange.response,
+                ))
                 .into(),
             },
             RequestState::RequestError { error } => Self::RequestError {
@@ -313,18 +359,23 @@ impl ExchangePaneContent {
 impl EventHandler for ExchangePaneContent {
     fn children(&mut self) -> Vec<Component<Child<'_>>> {
         match self {
-            Self::Building | Self::BuildError { .. } | Self::Cancelled => {
+            Self::Building { .. }
+            | Self::BuildError { .. }
+            | Self::Cancelled => {
                 vec![]
             }
-            Self::Loading { request } => vec![request.to_child_mut()],
+            Self::Loading { request, .. } => vec![request.to_child_mut()],
             Self::Response {
                 request,
                 response_headers,
                 response_body,
+                response_raw,
+                ..
             } => vec![
                 request.to_child_mut(),
                 response_headers.to_child_mut(),
                 response_body.to_child_mut(),
+                response_raw.to_child_mut(),
             ],
             Self::RequestError { request, .. } => vec![request.to_child_mut()],
         }
@@ -340,15 +391,28 @@ impl Draw<ExchangePaneContentProps> for ExchangePaneContent {
     ) {
         let area = metadata.area();
         match self {
-            Self::Building => {
-                frame.render_widget("Initializing request...", area)
-            }
+            Self::Building { start_time } => frame.render_widget(
+                Line::from(vec![
+                    Spinner {
+                        start_time: *start_time,
+                    }
+                    .generate(),
+                    " Initializing request...".into(),
+                ]),
+                area,
+            ),
             Self::BuildError { error } => frame.render_widget(error, area),
-            Self::Loading { request } => match props.selected_tab {
+            Self::Loading {
+                request,
+                start_time,
+                progress,
+            } => match props.selected_tab {
                 Tab::Request => request.draw(frame, (), area, true),
-                Tab::Body | Tab::Headers => {
-                    frame.render_widget("Loading...", area)
-                }
+                Tab::Body
+                | Tab::Headers
+                | Tab::Cookies
+                | Tab::Timing
+                | Tab::Raw => draw_loading(frame, area, *start_time, progress),
             },
             // Can't show cancelled request here because we might've cancelled
             // during the build
@@ -357,15 +421,60 @@ impl Draw<ExchangePaneContentProps> for ExchangePaneContent {
                 request,
                 response_body,
                 response_headers,
+                response_cookies,
+                response_timing,
+                response_raw,
             } => match props.selected_tab {
                 Tab::Request => request.draw(frame, (), area, true),
                 Tab::Body => response_body.draw(frame, (), area, true),
                 Tab::Headers => response_headers.draw(frame, (), area, true),
+                Tab::Cookies => response_cookies.draw(frame, (), area, true),
+                Tab::Timing => response_timing.draw(frame, (), area, true),
+                Tab::Raw => response_raw.draw(frame, (), area, true),
             },
             Self::RequestError { request, error } => match props.selected_tab {
                 Tab::Request => request.draw(frame, (), area, true),
-                Tab::Body | Tab::Headers => frame.render_widget(error, area),
+                Tab::Body
+                | Tab::Headers
+                | Tab::Cookies
+                | Tab::Timing
+                | Tab::Raw => frame.render_widget(error, area),
             },
         }
     }
 }
+
+/// Draw the body/headers placeholder for an in-flight request: a spinner, and
+/// a progress bar if we know how big the response body is
+fn draw_loading(
+    frame: &mut Frame,
+    area: Rect,
+    start_time: DateTime<Utc>,
+    progress: &DownloadProgress,
+) {
+    let [spinner_area, progress_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Length(1)])
+            .areas(area);
+    frame.render_widget(
+        Line::from(vec![
+            Spinner { start_time }.generate(),
+            " Loading...".into(),
+        ]),
+        spinner_area,
+    );
+
+    if let (Some(total), Some(ratio)) = (progress.total(), progress.ratio()) {
+        let label = format!(
+            "{} / {}",
+            format_byte_size(progress.received()),
+            format_byte_size(total)
+        );
+        frame.render_widget(
+            LineGauge::default()
+                .filled_style(TuiContext::get().styles.text.primary)
+                .label(label)
+                .ratio(ratio),
+            progress_area,
+        );
+    }
+}
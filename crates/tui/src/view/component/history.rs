@@ -11,9 +11,11 @@ use crate::{
         UpdateContext, ViewContext,
     },
 };
+use chrono::TimeDelta;
 use ratatui::{
-    layout::Constraint,
+    layout::{Constraint, Layout},
     text::{Line, Span},
+    widgets::Sparkline,
     Frame,
 };
 use slumber_core::{collection::RecipeId, http::RequestId};
@@ -23,6 +25,9 @@ use slumber_core::{collection::RecipeId, http::RequestId};
 pub struct History {
     recipe_name: String,
     select: Component<SelectState<RequestStateSummary>>,
+    /// Durations of all successful exchanges in the list, in milliseconds,
+    /// oldest first. Used to draw a latency trend above the list
+    durations: Vec<u64>,
 }
 
 impl History {
@@ -39,6 +44,7 @@ impl History {
             .reported(&ViewContext::messages_tx())
             .map(|recipe| recipe.name().to_owned())
             .unwrap_or_else(|| recipe_id.to_string());
+        let durations = latency_durations(&requests);
         let select = SelectState::builder(requests)
             .subscribe([SelectStateEventType::Select])
             .preselect_opt(selected_request_id.as_ref())
@@ -47,8 +53,21 @@ impl History {
         Self {
             recipe_name,
             select: select.into(),
+            durations,
         }
     }
+
+    /// Height of the latency chart, including its summary line. Zero if
+    /// there's not enough data to show a trend
+    fn chart_height(&self) -> u16 {
+        if self.durations.len() >= 2 {
+            Self::CHART_HEIGHT
+        } else {
+            0
+        }
+    }
+
+    const CHART_HEIGHT: u16 = 3;
 }
 
 impl Modal for History {
@@ -66,7 +85,9 @@ impl Modal for History {
     fn dimensions(&self) -> (Constraint, Constraint) {
         (
             Constraint::Length(40),
-            Constraint::Length(self.select.data().len().min(20) as u16),
+            Constraint::Length(
+                self.select.data().len().min(20) as u16 + self.chart_height(),
+            ),
         )
     }
 }
@@ -89,10 +110,47 @@ impl EventHandler for History {
 
 impl Draw for History {
     fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        let [summary_area, chart_area, list_area] = Layout::vertical([
+            Constraint::Length(self.chart_height().min(1)),
+            Constraint::Length(self.chart_height().saturating_sub(1)),
+            Constraint::Min(0),
+        ])
+        .areas(metadata.area());
+
+        if self.chart_height() > 0 {
+            let min = self.durations.iter().min().copied().unwrap_or_default();
+            let max = self.durations.iter().max().copied().unwrap_or_default();
+            let avg = self.durations.iter().sum::<u64>()
+                / self.durations.len() as u64;
+            let ms = |ms: u64| TimeDelta::milliseconds(ms as i64).generate();
+            frame.render_widget(
+                Line::from(vec![
+                    "min ".into(),
+                    ms(min),
+                    " / avg ".into(),
+                    ms(avg),
+                    " / max ".into(),
+                    ms(max),
+                ]),
+                summary_area,
+            );
+            // Show the most recent runs if there are more than fit
+            let visible = self
+                .durations
+                .len()
+                .saturating_sub(chart_area.width as usize);
+            frame.render_widget(
+                Sparkline::default()
+                    .data(&self.durations[visible..])
+                    .style(TuiContext::get().styles.text.primary),
+                chart_area,
+            );
+        }
+
         self.select.draw(
             frame,
             List::from(self.select.data()),
-            metadata.area(),
+            list_area,
             true,
         );
     }
@@ -131,6 +189,22 @@ impl Generate for &RequestStateSummary {
     }
 }
 
+/// Get durations of successful exchanges in milliseconds, ordered oldest to
+/// newest so the chart reads left-to-right. Errors and incomplete requests are
+/// excluded because their durations don't reflect server latency.
+fn latency_durations(requests: &[RequestStateSummary]) -> Vec<u64> {
+    let mut exchanges: Vec<_> = requests
+        .iter()
+        .filter(|request| matches!(request, RequestStateSummary::Response(_)))
+        .map(|request| (request.start_time(), request.duration()))
+        .collect();
+    exchanges.sort_by_key(|(start_time, _)| *start_time);
+    exchanges
+        .into_iter()
+        .map(|(_, duration)| duration.num_milliseconds().max(0) as u64)
+        .collect()
+}
+
 /// Allow selection by ID
 impl PartialEq<RequestStateSummary> for RequestId {
     fn eq(&self, other: &RequestStateSummary) -> bool {
@@ -9,7 +9,7 @@ use crate::view::{
         text_box::{TextBox, TextBoxEvent, TextBoxProps},
     },
     component::Component,
-    context::UpdateContext,
+    context::{UpdateContext, ViewContext},
     draw::{Draw, DrawMetadata, Generate},
     event::{Child, Event, EventHandler, OptionEvent, ToEmitter},
     state::{
@@ -20,9 +20,10 @@ use crate::view::{
 };
 use derive_more::Display;
 use ratatui::{
+    layout::Layout,
     prelude::Constraint,
     text::{Line, Text},
-    widgets::Paragraph,
+    widgets::{Paragraph, Wrap},
     Frame,
 };
 use slumber_core::template::{Prompt, Select};
@@ -60,6 +61,9 @@ impl IntoModal for anyhow::Error {
     type Target = ErrorModal;
 
     fn into_modal(self) -> Self::Target {
+        // Every error modal passes through here, so this is the one place to
+        // record errors in the notification log
+        ViewContext::log_notification(Notification::error(format!("{self:#}")));
         ErrorModal(self)
     }
 }
@@ -271,6 +275,8 @@ impl IntoModal for Select {
 pub struct ConfirmModal {
     /// Modal title, from the prompt message
     title: String,
+    /// Optional content to show above the buttons
+    content: Option<Text<'static>>,
     buttons: Component<ButtonGroup<ConfirmButton>>,
     /// Store which answer was selected during submission. Answering no is
     /// semantically different from not answering, so we can't just check the
@@ -294,11 +300,19 @@ impl ConfirmModal {
     pub fn new(title: String, on_submit: impl 'static + FnOnce(bool)) -> Self {
         Self {
             title,
+            content: None,
             buttons: Default::default(),
             on_submit: Box::new(on_submit),
             answer: false,
         }
     }
+
+    /// Show some content above the buttons, e.g. a preview of whatever is
+    /// being confirmed
+    pub fn content(mut self, content: Text<'static>) -> Self {
+        self.content = Some(content);
+        self
+    }
 }
 
 impl Modal for ConfirmModal {
@@ -307,11 +321,15 @@ impl Modal for ConfirmModal {
     }
 
     fn dimensions(&self) -> (Constraint, Constraint) {
-        (
-            // Add some arbitrary padding
-            Constraint::Length((self.title.len() + 4) as u16),
-            Constraint::Length(1),
-        )
+        if self.content.is_some() {
+            (Constraint::Percentage(80), Constraint::Percentage(50))
+        } else {
+            (
+                // Add some arbitrary padding
+                Constraint::Length((self.title.len() + 4) as u16),
+                Constraint::Length(1),
+            )
+        }
     }
 
     fn on_close(self: Box<Self>, submitted: bool) {
@@ -337,7 +355,20 @@ impl EventHandler for ConfirmModal {
 
 impl Draw for ConfirmModal {
     fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
-        self.buttons.draw(frame, (), metadata.area(), true);
+        let [content_area, _, buttons_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(u16::from(self.content.is_some())),
+            Constraint::Length(1),
+        ])
+        .areas(metadata.area());
+
+        if let Some(content) = &self.content {
+            frame.render_widget(
+                Paragraph::new(content.clone()).wrap(Wrap { trim: false }),
+                content_area,
+            );
+        }
+        self.buttons.draw(frame, (), buttons_area, true);
     }
 }
 
@@ -345,9 +376,17 @@ impl IntoModal for Confirm {
     type Target = ConfirmModal;
 
     fn into_modal(self) -> Self::Target {
-        ConfirmModal::new(self.message, |response| {
-            self.channel.respond(response)
-        })
+        let Confirm {
+            message,
+            content,
+            channel,
+        } = self;
+        let modal =
+            ConfirmModal::new(message, |response| channel.respond(response));
+        match content {
+            Some(content) => modal.content(content),
+            None => modal,
+        }
     }
 }
 
@@ -371,3 +410,49 @@ impl Draw for NotificationText {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{harness, terminal, TestHarness, TestTerminal},
+        view::test_util::{Empty, TestComponent},
+    };
+    use crossterm::event::KeyCode;
+    use rstest::rstest;
+    use tokio::sync::oneshot;
+
+    /// A confirmation can show content along with the question, e.g. to
+    /// preview a generated command before copying it
+    #[rstest]
+    #[case::yes(&[KeyCode::Enter], true)]
+    #[case::no(&[KeyCode::Left, KeyCode::Enter], false)]
+    fn test_confirm_content(
+        harness: TestHarness,
+        terminal: TestTerminal,
+        #[case] keys: &[KeyCode],
+        #[case] expected: bool,
+    ) {
+        let mut component = TestComponent::new(&harness, &terminal, Empty);
+        let (tx, mut rx) = oneshot::channel();
+        Confirm {
+            message: "Copy as cURL?".into(),
+            content: Some("curl -XGET".into()),
+            channel: tx.into(),
+        }
+        .into_modal()
+        .open();
+        component.int().drain_draw().assert_empty();
+        assert_eq!(
+            component.modal().map(|modal| modal.title().to_string()),
+            Some("Copy as cURL?".into())
+        );
+
+        component
+            .int()
+            .send_keys(keys.iter().copied())
+            .assert_empty();
+        assert!(component.modal().is_none());
+        assert_eq!(rx.try_recv().unwrap(), expected);
+    }
+}
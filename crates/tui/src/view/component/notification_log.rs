@@ -0,0 +1,126 @@
+use crate::{
+    context::TuiContext,
+    view::{
+        common::{
+            modal::Modal,
+            text_window::{TextWindow, TextWindowProps},
+        },
+        component::Component,
+        context::ViewContext,
+        draw::{Draw, DrawMetadata, Generate},
+        event::{Child, EventHandler},
+        state::{Identified, NotificationLevel},
+    },
+};
+use ratatui::{
+    layout::Constraint,
+    style::Style,
+    text::{Line, Text},
+    Frame,
+};
+
+/// Modal to review notifications and errors from the current session, newest
+/// first. Notifications in the footer disappear quickly, so this lets the user
+/// catch anything they missed. The content is a snapshot of the log from when
+/// the modal was opened.
+#[derive(Debug)]
+pub struct NotificationLogModal {
+    text: Identified<Text<'static>>,
+    text_window: Component<TextWindow>,
+}
+
+impl NotificationLogModal {
+    pub fn new() -> Self {
+        let styles = &TuiContext::get().styles;
+        let text = ViewContext::with_notification_log(|log| {
+            if log.is_empty() {
+                return Text::from("No notifications yet");
+            }
+            log.iter()
+                .rev()
+                .map(|notification| {
+                    let style = match notification.level {
+                        NotificationLevel::Info => Style::default(),
+                        NotificationLevel::Error => styles.text.error,
+                    };
+                    Line::styled(
+                        notification.generate().content.into_owned(),
+                        style,
+                    )
+                })
+                .collect()
+        });
+        Self {
+            text: text.into(),
+            text_window: Default::default(),
+        }
+    }
+}
+
+impl Default for NotificationLogModal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Modal for NotificationLogModal {
+    fn title(&self) -> Line<'_> {
+        "Notifications".into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        (Constraint::Percentage(80), Constraint::Percentage(60))
+    }
+}
+
+impl EventHandler for NotificationLogModal {
+    fn children(&mut self) -> Vec<Component<Child<'_>>> {
+        vec![self.text_window.to_child_mut()]
+    }
+}
+
+impl Draw for NotificationLogModal {
+    fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        self.text_window.draw(
+            frame,
+            TextWindowProps {
+                text: &self.text,
+                margins: Default::default(),
+            },
+            metadata.area(),
+            true,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{harness, TestHarness},
+        view::state::Notification,
+    };
+    use rstest::rstest;
+
+    /// Notifications are listed newest first, including errors
+    #[rstest]
+    fn test_notification_log(_harness: TestHarness) {
+        ViewContext::notify("first");
+        ViewContext::log_notification(Notification::error("second".into()));
+
+        let modal = NotificationLogModal::new();
+        let lines = modal
+            .text
+            .lines
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("] second"), "{lines:?}");
+        assert!(lines[1].ends_with("] first"), "{lines:?}");
+        assert_eq!(
+            modal.text.lines[0].style,
+            TuiContext::get().styles.text.error
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! Session-only template field overrides, which take precedence over profile
+//! fields. This is the TUI equivalent of the CLI's `key=value` arguments.
+
+use crate::{
+    context::TuiContext,
+    view::{
+        common::{
+            modal::Modal,
+            table::Table,
+            text_box::{TextBox, TextBoxEvent, TextBoxProps},
+            Checkbox,
+        },
+        component::Component,
+        context::UpdateContext,
+        draw::{Draw, DrawMetadata, Generate},
+        event::{Child, Emitter, Event, EventHandler, OptionEvent, ToEmitter},
+        state::select::{SelectState, SelectStateEvent, SelectStateEventType},
+    },
+};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::{Line, Text},
+    widgets::TableState,
+    Frame,
+};
+use slumber_config::Action;
+use slumber_core::template::Value;
+
+/// A set of field overrides for the current session. These are never
+/// persisted, because they're meant to be ad hoc.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overrides(IndexMap<String, Override>);
+
+/// The value for a single overridden field
+#[derive(Clone, Debug, PartialEq)]
+pub struct Override {
+    /// Overrides are plain strings, same as `key=value` in the CLI
+    pub value: String,
+    /// Pinned overrides are retained when the selected profile changes
+    pub pinned: bool,
+}
+
+impl Overrides {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Add or replace the override for a field. If the field is already
+    /// overridden, its pinned state is retained.
+    pub fn insert(&mut self, key: String, value: String) {
+        let pinned = self.0.get(&key).is_some_and(|ovr| ovr.pinned);
+        self.0.insert(key, Override { value, pinned });
+    }
+
+    /// Remove all overrides that aren't pinned. Called when the selected
+    /// profile changes
+    pub fn clear_unpinned(&mut self) {
+        self.0.retain(|_, ovr| ovr.pinned);
+    }
+
+    /// Convert to the format expected by the template context
+    pub fn to_template_overrides(&self) -> IndexMap<String, Value> {
+        self.0
+            .iter()
+            .map(|(key, ovr)| (key.clone(), Value::String(ovr.value.clone())))
+            .collect()
+    }
+}
+
+/// Modal to add, edit, and remove overrides. The modal operates on its own
+/// copy of the overrides, and emits the full set back to the opener whenever
+/// it changes, so templates can be re-previewed immediately.
+#[derive(Debug)]
+pub struct OverridesModal {
+    emitter: Emitter<OverridesEvent>,
+    overrides: Overrides,
+    select: Component<SelectState<OverrideRow, TableState>>,
+    /// Input for new or modified overrides, in the form `key=value`
+    text_box: Component<TextBox>,
+    /// Is the user typing in the text box, or navigating the table?
+    editing: bool,
+}
+
+impl OverridesModal {
+    pub fn new(overrides: Overrides) -> Self {
+        let input_engine = &TuiContext::get().input_engine;
+        let edit_binding = input_engine.binding_display(Action::Edit);
+        let text_box = TextBox::default()
+            .placeholder(format!("{edit_binding} to add or edit an override"))
+            .placeholder_focused("Enter override (ex: `host=localhost`)")
+            .validator(|value| {
+                value
+                    .split_once('=')
+                    .is_some_and(|(key, _)| !key.is_empty())
+            });
+        let mut slf = Self {
+            emitter: Default::default(),
+            // Jump straight to input if there's nothing to navigate
+            editing: overrides.is_empty(),
+            overrides,
+            select: Default::default(),
+            text_box: text_box.into(),
+        };
+        slf.rebuild_select(None);
+        slf
+    }
+
+    /// Rebuild the table from the current set of overrides. Optionally select
+    /// a particular key; otherwise, the first row will be selected.
+    fn rebuild_select(&mut self, selected: Option<&String>) {
+        let rows = self
+            .overrides
+            .0
+            .iter()
+            .map(|(key, ovr)| OverrideRow {
+                key: key.clone(),
+                value: ovr.value.clone(),
+                pinned: ovr.pinned,
+            })
+            .collect();
+        self.select = SelectState::builder(rows)
+            .preselect_opt(selected)
+            .subscribe([
+                SelectStateEventType::Submit,
+                SelectStateEventType::Toggle,
+            ])
+            .build()
+            .into();
+    }
+
+    /// Send the current set of overrides to the opener
+    fn emit_overrides(&self) {
+        self.emitter.emit(OverridesEvent(self.overrides.clone()));
+    }
+
+    /// Move focus to the text box. If a row is selected, pre-fill the box with
+    /// its current value so it can be edited
+    fn edit_selected(&mut self) {
+        let text = self
+            .select
+            .data()
+            .selected()
+            .map(|row| format!("{}={}", row.key, row.value))
+            .unwrap_or_default();
+        self.text_box.data_mut().set_text(text);
+        self.editing = true;
+    }
+
+    /// Parse the text box contents and add it to the set
+    fn submit_text(&mut self) {
+        let text = self.text_box.data_mut().clear();
+        // The validator guarantees this is present
+        if let Some((key, value)) = text.split_once('=') {
+            let key = key.to_owned();
+            self.overrides.insert(key.clone(), value.to_owned());
+            self.rebuild_select(Some(&key));
+            self.emit_overrides();
+        }
+        self.editing = false;
+    }
+
+    fn remove_selected(&mut self) {
+        if let Some(row) = self.select.data().selected() {
+            let key = row.key.clone();
+            self.overrides.0.shift_remove(&key);
+            self.rebuild_select(None);
+            self.emit_overrides();
+        }
+    }
+
+    fn toggle_pinned(&mut self, index: usize) {
+        let key = self.select.data()[index].key.clone();
+        if let Some(ovr) = self.overrides.0.get_mut(&key) {
+            ovr.pinned ^= true;
+            self.select.data_mut()[index].pinned = ovr.pinned;
+            self.emit_overrides();
+        }
+    }
+}
+
+impl Modal for OverridesModal {
+    fn title(&self) -> Line<'_> {
+        "Overrides".into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        (Constraint::Percentage(60), Constraint::Percentage(40))
+    }
+}
+
+impl EventHandler for OverridesModal {
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> Option<Event> {
+        event
+            .opt()
+            .action(|action, propagate| match action {
+                // Consume submission so it doesn't send a request
+                Action::Edit | Action::Submit => self.edit_selected(),
+                Action::Reset => self.remove_selected(),
+                _ => propagate.set(),
+            })
+            .emitted(self.select.to_emitter(), |event| match event {
+                SelectStateEvent::Submit(_) => self.edit_selected(),
+                SelectStateEvent::Toggle(index) => self.toggle_pinned(index),
+                SelectStateEvent::Select(_) => {}
+            })
+            .emitted(self.text_box.to_emitter(), |event| match event {
+                TextBoxEvent::Focus => self.editing = true,
+                TextBoxEvent::Change => {}
+                TextBoxEvent::Cancel => {
+                    self.text_box.data_mut().clear();
+                    self.editing = false;
+                }
+                TextBoxEvent::Submit => self.submit_text(),
+            })
+    }
+
+    fn children(&mut self) -> Vec<Component<Child<'_>>> {
+        vec![self.text_box.to_child_mut(), self.select.to_child_mut()]
+    }
+}
+
+impl Draw for OverridesModal {
+    fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        let [table_area, input_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)])
+                .areas(metadata.area());
+
+        let select = self.select.data();
+        if select.is_empty() {
+            frame.render_widget(
+                Text::from(
+                    "No overrides; values entered here take precedence over \
+                    profile fields for this session.",
+                ),
+                table_area,
+            );
+        } else {
+            let table = Table {
+                header: Some(["Field", "Value", "Pinned"]),
+                rows: select.items().map(Generate::generate).collect_vec(),
+                column_widths: &[
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(60),
+                    Constraint::Min(6),
+                ],
+                alternate_row_style: true,
+                ..Default::default()
+            };
+            self.select.draw(
+                frame,
+                table.generate(),
+                table_area,
+                !self.editing,
+            );
+        }
+
+        self.text_box.draw(
+            frame,
+            TextBoxProps::default(),
+            input_area,
+            self.editing,
+        );
+    }
+}
+
+impl ToEmitter<OverridesEvent> for OverridesModal {
+    fn to_emitter(&self) -> Emitter<OverridesEvent> {
+        self.emitter
+    }
+}
+
+/// Emitted whenever the set of overrides is modified in the modal
+#[derive(Debug, PartialEq)]
+pub struct OverridesEvent(pub Overrides);
+
+/// One row in the overrides table
+#[derive(Debug)]
+struct OverrideRow {
+    key: String,
+    value: String,
+    pinned: bool,
+}
+
+impl PartialEq<OverrideRow> for String {
+    fn eq(&self, row: &OverrideRow) -> bool {
+        self == &row.key
+    }
+}
+
+impl Generate for &OverrideRow {
+    type Output<'this> = [Text<'this>; 3]
+    where
+        Self: 'this;
+
+    fn generate<'this>(self) -> Self::Output<'this>
+    where
+        Self: 'this,
+    {
+        [
+            self.key.as_str().into(),
+            self.value.as_str().into(),
+            Checkbox {
+                checked: self.pinned,
+            }
+            .generate(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{harness, terminal, TestHarness, TestTerminal},
+        view::test_util::TestComponent,
+    };
+    use crossterm::event::KeyCode;
+    use indexmap::indexmap;
+    use rstest::rstest;
+
+    #[test]
+    fn test_clear_unpinned() {
+        let mut overrides = Overrides::default();
+        overrides.insert("host".into(), "localhost".into());
+        overrides.insert("token".into(), "abc".into());
+        overrides.0.get_mut("token").unwrap().pinned = true;
+        // Re-inserting retains pinned state
+        overrides.insert("token".into(), "def".into());
+
+        overrides.clear_unpinned();
+        assert_eq!(
+            overrides.to_template_overrides(),
+            indexmap! {"token".into() => Value::String("def".into())}
+        );
+    }
+
+    /// Add, pin, edit, and remove overrides via the modal
+    #[rstest]
+    fn test_modal(harness: TestHarness, terminal: TestTerminal) {
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            OverridesModal::new(Overrides::default()),
+        );
+        let overrides = |value: &str, pinned: bool| {
+            OverridesEvent(Overrides(indexmap! {
+                "host".into() => Override { value: value.into(), pinned },
+            }))
+        };
+
+        // Modal starts in input mode because there are no overrides
+        component.int().send_text("host=localhost").assert_empty();
+        component
+            .int()
+            .send_key(KeyCode::Enter)
+            .assert_emitted([overrides("localhost", false)]);
+
+        // Pin it
+        component
+            .int()
+            .send_key(KeyCode::Char(' '))
+            .assert_emitted([overrides("localhost", true)]);
+
+        // Edit the selected row; existing value is pre-filled
+        component.int().send_key(KeyCode::Char('e')).assert_empty();
+        assert_eq!(component.data().text_box.data().text(), "host=localhost");
+        component.int().send_text(":3000").assert_empty();
+        component
+            .int()
+            .send_key(KeyCode::Enter)
+            .assert_emitted([overrides("localhost:3000", true)]);
+
+        // Remove it
+        component
+            .int()
+            .send_key(KeyCode::Char('z'))
+            .assert_emitted([OverridesEvent(Overrides::default())]);
+    }
+}
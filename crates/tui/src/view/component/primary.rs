@@ -12,6 +12,7 @@ use crate::{
         component::{
             exchange_pane::{ExchangePane, ExchangePaneEvent},
             help::HelpModal,
+            overrides::Overrides,
             profile_select::ProfilePane,
             recipe_list::{RecipeListPane, RecipeListPaneEvent},
             recipe_pane::{RecipePane, RecipePaneEvent, RecipePaneProps},
@@ -110,6 +111,11 @@ impl PrimaryView {
         self.profile_pane.data().selected_profile_id()
     }
 
+    /// Field overrides for the current session
+    pub fn overrides(&self) -> &Overrides {
+        self.profile_pane.data().overrides()
+    }
+
     /// Set the state of the currently selected request. Call whenever a new
     /// request is selected, or the selected request changes state
     pub fn set_request_state(
@@ -306,6 +312,9 @@ impl EventHandler for PrimaryView {
                     GlobalMenuAction::EditCollection => {
                         ViewContext::send_message(Message::CollectionEdit)
                     }
+                    GlobalMenuAction::EditOverrides => {
+                        self.profile_pane.data_mut().open_overrides_modal()
+                    }
                 }
             })
     }
@@ -416,6 +425,8 @@ struct FullscreenModeKey;
 enum GlobalMenuAction {
     #[display("Edit Collection")]
     EditCollection,
+    #[display("Edit Overrides")]
+    EditOverrides,
 }
 
 impl IntoMenuAction<PrimaryView> for GlobalMenuAction {}
@@ -530,7 +541,7 @@ mod tests {
             .send_key(KeyCode::Char('l')) // Select recipe list
             .open_actions()
             // Copy URL
-            .send_keys([KeyCode::Down, KeyCode::Enter])
+            .send_keys([KeyCode::Down, KeyCode::Down, KeyCode::Enter])
             .assert_empty();
 
         assert_matches!(harness.pop_message_now(), Message::CopyRequestUrl);
@@ -547,7 +558,12 @@ mod tests {
             .send_key(KeyCode::Char('l')) // Select recipe list
             .open_actions()
             // Copy as cURL
-            .send_keys([KeyCode::Down, KeyCode::Down, KeyCode::Enter])
+            .send_keys([
+                KeyCode::Down,
+                KeyCode::Down,
+                KeyCode::Down,
+                KeyCode::Enter,
+            ])
             .assert_empty();
 
         assert_matches!(harness.pop_message_now(), Message::CopyRequestCurl);
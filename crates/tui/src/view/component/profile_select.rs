@@ -11,6 +11,7 @@ use crate::{
             template_preview::TemplatePreview,
             Pane,
         },
+        component::overrides::{Overrides, OverridesEvent, OverridesModal},
         context::UpdateContext,
         draw::{Draw, DrawMetadata, Generate},
         event::{Child, Emitter, Event, EventHandler, OptionEvent, ToEmitter},
@@ -27,7 +28,8 @@ use itertools::Itertools;
 use persisted::PersistedKey;
 use ratatui::{
     layout::{Constraint, Layout},
-    text::{Line, Text},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
     Frame,
 };
 use serde::Serialize;
@@ -49,6 +51,10 @@ pub struct ProfilePane {
     selected_profile_id: Persisted<SelectedProfileKey>,
     /// Handle events from the opened modal
     modal_handle: ModalHandle<SelectProfile>,
+    /// Ad hoc field overrides for this session. These take precedence over
+    /// the selected profile's fields
+    overrides: Overrides,
+    overrides_handle: ModalHandle<OverridesEvent>,
 }
 
 /// Persisted key for the ID of the selected profile
@@ -61,20 +67,35 @@ impl ProfilePane {
         let mut selected_profile_id =
             Persisted::new_default(SelectedProfileKey);
 
+        // `$SLUMBER_PROFILE` takes precedence over the persisted selection.
+        // Resolve it through the collection so an unknown ID gets reported
+        // to the user, rather than silently using another profile
+        if let Some(profile_id) = ProfileId::from_env() {
+            if let Some(profile_id) = collection
+                .resolve_profile(Some(profile_id), None, None)
+                .reported(&ViewContext::messages_tx())
+                .flatten()
+            {
+                *selected_profile_id.get_mut() = Some(profile_id);
+            }
+        }
+
         // Two invalid cases we need to handle here:
         // - Nothing is persisted but the map has values now
         // - Persisted ID isn't in the map now
         // In either case, just fall back to:
-        // - Default profile if available
+        // - `default_profile` from the config, then the collection's default
+        //   profile, if available
         // - First profile if available
         // - `None` if map is empty
         match &*selected_profile_id {
             Some(id) if collection.profiles.contains_key(id) => {}
             _ => {
+                let config_default =
+                    TuiContext::get().config.default_profile.as_ref();
                 *selected_profile_id.get_mut() = collection
-                    .default_profile()
-                    .or(collection.profiles.values().next())
-                    .map(Profile::id)
+                    .fallback_profile(config_default)
+                    .or(collection.profiles.keys().next())
                     .cloned()
             }
         }
@@ -82,6 +103,8 @@ impl ProfilePane {
         Self {
             selected_profile_id,
             modal_handle: ModalHandle::new(),
+            overrides: Overrides::default(),
+            overrides_handle: ModalHandle::new(),
         }
     }
 
@@ -89,11 +112,21 @@ impl ProfilePane {
         self.selected_profile_id.as_ref()
     }
 
+    pub fn overrides(&self) -> &Overrides {
+        &self.overrides
+    }
+
     /// Open the profile list modal
     pub fn open_modal(&mut self) {
         self.modal_handle
             .open(ProfileListModal::new(self.selected_profile_id.as_ref()));
     }
+
+    /// Open the modal to edit field overrides
+    pub fn open_overrides_modal(&mut self) {
+        self.overrides_handle
+            .open(OverridesModal::new(self.overrides.clone()));
+    }
 }
 
 impl EventHandler for ProfilePane {
@@ -110,6 +143,16 @@ impl EventHandler for ProfilePane {
                     // Handle message from the modal
                     *self.selected_profile_id.get_mut() =
                         Some(profile_id.clone());
+                    // Overrides are generally specific to one profile
+                    self.overrides.clear_unpinned();
+                    // Refresh template previews
+                    ViewContext::push_event(Event::HttpSelectRequest(None));
+                },
+            )
+            .emitted(
+                self.overrides_handle.to_emitter(),
+                |OverridesEvent(overrides)| {
+                    self.overrides = overrides;
                     // Refresh template previews
                     ViewContext::push_event(Event::HttpSelectRequest(None));
                 },
@@ -122,27 +165,35 @@ impl Draw for ProfilePane {
         let title = TuiContext::get()
             .input_engine
             .add_hint("Profile", Action::SelectProfileList);
-        let block = Pane {
+        let mut block = Pane {
             title: &title,
             has_focus: false,
         }
         .generate();
-        frame.render_widget(&block, metadata.area());
-        let area = block.inner(metadata.area());
 
         // Grab global profile selection state
         let collection = ViewContext::collection();
         let selected_profile = (*self.selected_profile_id)
             .as_ref()
-            .and_then(|profile_id| collection.profiles.get(profile_id));
-        frame.render_widget(
-            if let Some(profile) = selected_profile {
-                profile.name()
-            } else {
-                "No profiles defined"
-            },
-            area,
-        );
+            .and_then(|profile_id| collection.profiles.get(profile_id))
+            .map(ProfileListItem::from);
+        let mut line = if let Some(profile) = &selected_profile {
+            // Tint the border too, so the profile stands out at a glance
+            if let Some(color) = profile.color {
+                block = block.border_style(Style::default().fg(color));
+            }
+            profile.generate()
+        } else {
+            "No profiles defined".into()
+        };
+        match self.overrides.len() {
+            0 => {}
+            1 => line.push_span(" (1 override)"),
+            n => line.push_span(format!(" ({n} overrides)")),
+        }
+
+        frame.render_widget(&block, metadata.area());
+        frame.render_widget(line, block.inner(metadata.area()));
     }
 }
 
@@ -255,6 +306,8 @@ struct SelectProfile(ProfileId);
 struct ProfileListItem {
     id: ProfileId,
     name: String,
+    label: Option<String>,
+    color: Option<Color>,
 }
 
 impl HasId for ProfileListItem {
@@ -280,12 +333,20 @@ impl From<&Profile> for ProfileListItem {
         Self {
             id: profile.id.clone(),
             name: profile.name().to_owned(),
+            label: profile.label.clone(),
+            // Invalid colors are reported when the collection is loaded, so
+            // we can ignore them here
+            color: profile
+                .color
+                .as_deref()
+                .and_then(|color| color.parse().ok()),
         }
     }
 }
 
 impl<'a> Generate for &'a ProfileListItem {
-    type Output<'this> = Text<'this>
+    type Output<'this>
+        = Line<'this>
     where
         Self: 'this;
 
@@ -293,7 +354,19 @@ impl<'a> Generate for &'a ProfileListItem {
     where
         Self: 'this,
     {
-        self.name.as_str().into()
+        let style = self
+            .color
+            .map(|color| Style::default().fg(color))
+            .unwrap_or_default();
+        let mut line = Line::default();
+        if let Some(label) = &self.label {
+            line.push_span(Span::styled(
+                format!("[{label}] "),
+                style.add_modifier(Modifier::BOLD),
+            ));
+        }
+        line.push_span(Span::styled(self.name.as_str(), style));
+        line
     }
 }
 
@@ -331,10 +404,10 @@ impl<'a> Draw<ProfileDetailProps<'a>> for ProfileDetail {
             };
             profile_data
                 .iter()
-                .map(|(key, template)| {
+                .map(|(key, value)| {
                     (
                         key.clone(),
-                        TemplatePreview::new(template.clone(), None, false),
+                        TemplatePreview::new(value.to_template(), None, false),
                     )
                 })
                 .collect_vec()
@@ -356,12 +429,19 @@ impl<'a> Draw<ProfileDetailProps<'a>> for ProfileDetail {
 #[cfg(test)]
 mod tests {
     use crate::{
-        test_util::{harness, TestHarness},
-        view::util::persistence::DatabasePersistedStore,
+        test_util::{harness, terminal, TestHarness, TestTerminal},
+        view::{
+            test_util::TestComponent, util::persistence::DatabasePersistedStore,
+        },
     };
+    use crossterm::event::KeyCode;
+    use indexmap::indexmap;
     use persisted::PersistedStore;
     use rstest::rstest;
-    use slumber_core::test_util::{by_id, Factory};
+    use slumber_core::{
+        assert_matches,
+        test_util::{by_id, Factory},
+    };
 
     use super::*;
 
@@ -398,4 +478,90 @@ mod tests {
         });
         assert_eq!(*component.selected_profile_id, expected);
     }
+
+    /// Overrides are edited in a modal, and cleared when the profile changes
+    #[rstest]
+    fn test_overrides(harness: TestHarness, terminal: TestTerminal) {
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            ProfilePane::new(&harness.collection),
+        );
+
+        // Each change refreshes template previews
+        component.data_mut().open_overrides_modal();
+        assert_matches!(
+            component
+                .int()
+                .drain_draw()
+                .send_text("host=localhost")
+                .send_keys([KeyCode::Enter, KeyCode::Esc])
+                .events(),
+            &[Event::HttpSelectRequest(None)]
+        );
+        assert!(component.modal().is_none());
+        assert_eq!(
+            component.data().overrides().to_template_overrides(),
+            indexmap! {"host".into() => "localhost".into()}
+        );
+
+        // Re-select the profile to clear overrides
+        component.data_mut().open_modal();
+        assert_matches!(
+            component
+                .int()
+                .drain_draw()
+                .send_key(KeyCode::Enter)
+                .events(),
+            &[Event::HttpSelectRequest(None)]
+        );
+        assert!(component.data().overrides().is_empty());
+    }
+
+    /// Profile label and color are included in the display, and invalid
+    /// colors are ignored
+    #[rstest]
+    #[case::plain(None, None, vec![("Profile", Style::default())])]
+    #[case::color(
+        None,
+        Some("red"),
+        vec![("Profile", Style::default().fg(Color::Red))],
+    )]
+    #[case::label_and_color(
+        Some("PROD"),
+        Some("#ff0000"),
+        vec![
+            (
+                "[PROD] ",
+                Style::default()
+                    .fg(Color::Rgb(0xff, 0, 0))
+                    .add_modifier(Modifier::BOLD),
+            ),
+            ("Profile", Style::default().fg(Color::Rgb(0xff, 0, 0))),
+        ],
+    )]
+    #[case::invalid_color(
+        None,
+        Some("not a color"),
+        vec![("Profile", Style::default())],
+    )]
+    fn test_profile_list_item(
+        #[case] label: Option<&str>,
+        #[case] color: Option<&str>,
+        #[case] expected: Vec<(&str, Style)>,
+    ) {
+        let profile = Profile {
+            name: Some("Profile".into()),
+            label: label.map(String::from),
+            color: color.map(String::from),
+            ..Profile::factory(())
+        };
+        let item = ProfileListItem::from(&profile);
+        let line = item.generate();
+        let expected = expected
+            .into_iter()
+            .map(|(content, style)| Span::styled(content, style))
+            .collect::<Vec<_>>();
+        assert_eq!(line.spans, expected);
+    }
 }
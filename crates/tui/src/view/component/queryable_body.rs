@@ -13,22 +13,23 @@ use crate::{
         draw::{Draw, DrawMetadata, Generate},
         event::{Child, Emitter, Event, EventHandler, OptionEvent, ToEmitter},
         state::Identified,
-        util::{highlight, str_to_text},
+        util::{hex_dump, highlight, str_to_text},
         Component, IntoModal, ViewContext,
     },
 };
 use anyhow::Context;
 use bytes::Bytes;
+use mime::Mime;
 use persisted::PersistedContainer;
 use ratatui::{
     layout::{Constraint, Layout},
-    text::Text,
+    text::{Line, Text},
     Frame,
 };
-use slumber_config::Action;
+use slumber_config::{Action, BodyFormat};
 use slumber_core::{
-    http::{content_type::ContentType, ResponseBody, ResponseRecord},
-    util::MaybeStr,
+    http::{content_type::ContentType, xml, ResponseBody, ResponseRecord},
+    util::format_byte_size,
 };
 use std::{borrow::Cow, mem, sync::Arc};
 use tokio::task::AbortHandle;
@@ -46,6 +47,9 @@ pub struct QueryableBody {
     /// can apply it when an empty query is loaded from persistence. Generally
     /// this will come from the config but it's parameterized for testing
     default_query: Option<String>,
+    /// Forced format for prettification/highlighting, overriding the
+    /// `Content-Type` header. Also from the config, parameterized for testing
+    format: Option<BodyFormat>,
     /// Track status of the current query command
     query_state: QueryState,
     /// Where the user enters their body query
@@ -71,6 +75,7 @@ impl QueryableBody {
     pub fn new(
         response: Arc<ResponseRecord>,
         default_query: Option<String>,
+        format: Option<BodyFormat>,
     ) -> Self {
         let input_engine = &TuiContext::get().input_engine;
         let query_bind = input_engine.binding_display(Action::Search);
@@ -88,14 +93,19 @@ impl QueryableBody {
             "Enter export command (ex: `tee > response.json`)",
         );
 
-        let text_state =
-            TextState::new(response.content_type(), &response.body, true);
+        let text_state = TextState::new(
+            TextFormat::new(&response, format),
+            &response.body,
+            true,
+            format.is_none() && is_binary_mime(response.mime().as_ref()),
+        );
 
         let mut slf = Self {
             emitter: Default::default(),
             response,
             command_focus: CommandFocus::None,
             default_query,
+            format,
             query_state: QueryState::None,
             query_text_box: query_text_box.into(),
             last_executed_query: None,
@@ -151,9 +161,11 @@ impl QueryableBody {
             self.last_executed_query = None;
             self.query_state = QueryState::None;
             self.text_state = TextState::new(
-                self.response.content_type(),
+                TextFormat::new(&self.response, self.format),
                 &self.response.body,
                 true, // Prettify
+                self.format.is_none()
+                    && is_binary_mime(self.response.mime().as_ref()),
             );
         } else {
             // Send it
@@ -231,11 +243,14 @@ impl EventHandler for QueryableBody {
                     self.query_state = QueryState::Ok;
                     self.text_state = TextState::new(
                         // Assume the output has the same content type
-                        self.response.content_type(),
+                        TextFormat::new(&self.response, self.format),
                         &ResponseBody::new(stdout),
                         // Don't prettify - user controls this output. If
                         // it's not pretty already, that's on them
                         false,
+                        // Output content type is unknown, so only treat it as
+                        // binary if it isn't valid UTF-8
+                        false,
                     );
                 }
                 // Trigger error state. Error will be shown in the pane
@@ -367,12 +382,20 @@ struct TextState {
 }
 
 impl TextState {
-    /// Calculate display text based on current body/query
+    /// Calculate display text based on current body/query. Content is shown
+    /// as binary if it's not valid UTF-8, or if `binary` is set (e.g. based on
+    /// the `Content-Type` header).
     fn new<T: AsRef<[u8]>>(
-        content_type: Option<ContentType>,
+        format: TextFormat,
         body: &ResponseBody<T>,
         prettify: bool,
+        binary: bool,
     ) -> Self {
+        let text = match body.text() {
+            Some(text) if !binary => text,
+            _ => return Self::binary(body.bytes().as_ref()),
+        };
+
         if TuiContext::get().config.http.is_large(body.size()) {
             // For bodies over the "large" size, skip prettification and
             // highlighting because it's slow. We could try to push this work
@@ -382,53 +405,135 @@ impl TextState {
             //
             // We don't show a hint to the user in this case because it's not
             // worth the screen real estate
-            if let Some(text) = body.text() {
-                TextState {
-                    text: str_to_text(text).into(),
-                    pretty: false,
-                }
-            } else {
-                // Showing binary content is a bit of a novelty, there's not
-                // much value in it. For large bodies it's not
-                // worth the CPU cycles
-                let text: Text = "<binary>".into();
-                TextState {
-                    text: text.into(),
-                    pretty: false,
-                }
+            TextState {
+                text: str_to_text(text).into(),
+                pretty: false,
             }
-        } else if let Some(text) = body.text() {
+        } else {
             // Prettify for known content types. We _don't_ do this in a
             // separate task because it's generally very fast. If this is slow
             // enough that it affects the user, the "large" body size is
             // probably too low
             // 2024 edition: if-let chain
-            let (text, pretty): (Cow<str>, bool) = match content_type {
-                Some(content_type) if prettify => content_type
+            let (text, pretty): (Cow<str>, bool) = if prettify {
+                format
                     .prettify(text)
                     .map(|body| (Cow::Owned(body), true))
-                    .unwrap_or((Cow::Borrowed(text), false)),
-                _ => (Cow::Borrowed(text), false),
+                    .unwrap_or((Cow::Borrowed(text), false))
+            } else {
+                (Cow::Borrowed(text), false)
             };
 
-            let text =
-                highlight::highlight_if(content_type, str_to_text(&text));
+            let text = highlight::highlight_if(
+                format.highlight_type(),
+                str_to_text(&text),
+            );
             TextState {
                 text: text.into(),
                 pretty,
             }
-        } else {
-            // Content is binary, show a textual representation of it
-            let text: Text =
-                format!("{:#}", MaybeStr(body.bytes().as_ref())).into();
-            TextState {
-                text: text.into(),
-                pretty: false,
+        }
+    }
+
+    /// Show a hex preview of binary content, instead of mangled text. The
+    /// preview is capped so this is cheap even for large bodies
+    fn binary(bytes: &[u8]) -> Self {
+        let bind = TuiContext::get()
+            .input_engine
+            .binding_display(Action::OpenActions);
+        let mut lines = vec![
+            Line::from(format!(
+                "Binary content ({}); use Save Body as File ({bind}) to save it",
+                format_byte_size(bytes.len())
+            )),
+            Line::default(),
+        ];
+        lines.extend(hex_dump(bytes, HEX_DUMP_LIMIT));
+        TextState {
+            text: Text::from(lines).into(),
+            pretty: false,
+        }
+    }
+}
+
+/// How to prettify and highlight body text
+#[derive(Copy, Clone, Debug)]
+enum TextFormat {
+    /// A content type that Slumber supports natively
+    ContentType(ContentType),
+    Xml,
+    /// Show the text as-is
+    Plain,
+}
+
+impl TextFormat {
+    /// Use the format from the `content_type_overrides` config if given,
+    /// otherwise fall back to the response's `Content-Type` header
+    fn new(response: &ResponseRecord, format: Option<BodyFormat>) -> Self {
+        match format {
+            Some(BodyFormat::Json) => Self::ContentType(ContentType::Json),
+            Some(BodyFormat::Xml) => Self::Xml,
+            Some(BodyFormat::Text) => Self::Plain,
+            None => response
+                .content_type()
+                .map(Self::ContentType)
+                .unwrap_or(Self::Plain),
+        }
+    }
+
+    /// Prettify text in this format. Return `None` if the text isn't valid
+    /// for the format, or there's no prettifier for it
+    fn prettify(self, text: &str) -> Option<String> {
+        let indent = TuiContext::get().config.indent;
+        match self {
+            Self::ContentType(content_type) => {
+                content_type.prettify(text, indent)
             }
+            Self::Xml => xml::prettify(text, indent),
+            Self::Plain => None,
+        }
+    }
+
+    /// Content type to use for syntax highlighting. There's no highlighter
+    /// for XML
+    fn highlight_type(self) -> Option<ContentType> {
+        match self {
+            Self::ContentType(content_type) => Some(content_type),
+            Self::Xml | Self::Plain => None,
         }
     }
 }
 
+/// Maximum number of bytes to show in the hex preview of a binary body
+const HEX_DUMP_LIMIT: usize = 4096;
+
+/// Does the MIME type indicate binary content? This catches binary bodies that
+/// happen to be valid UTF-8, which would otherwise be shown as garbled text.
+fn is_binary_mime(mime: Option<&Mime>) -> bool {
+    let Some(mime) = mime else {
+        return false;
+    };
+    match (mime.type_(), mime.subtype().as_str()) {
+        (mime::IMAGE, subtype) => subtype != "svg",
+        (mime::AUDIO | mime::VIDEO | mime::FONT, _) => true,
+        (mime::APPLICATION, subtype) => matches!(
+            subtype,
+            "octet-stream"
+                | "pdf"
+                | "zip"
+                | "gzip"
+                | "protobuf"
+                | "x-protobuf"
+                | "grpc"
+                | "msgpack"
+                | "x-msgpack"
+                | "cbor"
+                | "wasm"
+        ),
+        _ => false,
+    }
+}
+
 /// Which command box, if any, is focused?
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum CommandFocus {
@@ -488,7 +593,7 @@ mod tests {
     use slumber_core::{
         assert_matches,
         http::{ResponseBody, ResponseRecord},
-        test_util::{temp_dir, Factory, TempDir},
+        test_util::{header_map, temp_dir, Factory, TempDir},
     };
     use tokio::fs;
 
@@ -529,7 +634,7 @@ mod tests {
         let mut component = TestComponent::new(
             &harness,
             &terminal,
-            QueryableBody::new(response, None),
+            QueryableBody::new(response, None, None),
         );
 
         // Assert initial state/view
@@ -615,7 +720,7 @@ mod tests {
                 PersistedLazy::new(
                     Key,
                     // Default value should get tossed out
-                    QueryableBody::new(response, Some("initial".into())),
+                    QueryableBody::new(response, Some("initial".into()), None),
                 ),
             )
         })
@@ -644,7 +749,7 @@ mod tests {
             TestComponent::new(
                 &harness,
                 &terminal,
-                QueryableBody::new(response, Some("head -n 1".into())),
+                QueryableBody::new(response, Some("head -n 1".into()), None),
             )
         })
         .await;
@@ -677,7 +782,11 @@ mod tests {
                 PersistedLazy::new(
                     Key,
                     // Default should override the persisted value
-                    QueryableBody::new(response, Some("head -n 1".into())),
+                    QueryableBody::new(
+                        response,
+                        Some("head -n 1".into()),
+                        None,
+                    ),
                 ),
             )
         })
@@ -705,7 +814,7 @@ mod tests {
         let mut component = TestComponent::new(
             &harness,
             &terminal,
-            QueryableBody::new(response, None),
+            QueryableBody::new(response, None, None),
         );
 
         let path = temp_dir.join("test_export.json");
@@ -740,4 +849,101 @@ mod tests {
         let modal = component.modal().expect("Error modal should be visible");
         assert_eq!(&modal.title().to_string(), "Error");
     }
+
+    /// Binary bodies should be shown as a hex dump, detected either by invalid
+    /// UTF-8 or the content type
+    #[rstest]
+    #[case::invalid_utf8(
+        None,
+        b"\x00\x01hi\xff",
+        "00000000  00 01 68 69 ff                                    |..hi.|"
+    )]
+    #[case::content_type(
+        Some("application/octet-stream"),
+        b"\x00\x01hi",
+        "00000000  00 01 68 69                                       |..hi|"
+    )]
+    #[tokio::test]
+    async fn test_binary_body(
+        harness: TestHarness,
+        terminal: TestTerminal,
+        #[case] content_type: Option<&str>,
+        #[case] body: &'static [u8],
+        #[case] expected_hex: &str,
+    ) {
+        let response = ResponseRecord {
+            headers: header_map(
+                content_type.map(|content_type| ("content-type", content_type)),
+            ),
+            body: ResponseBody::new(body.into()),
+            ..ResponseRecord::factory(())
+        };
+        let component = TestComponent::new(
+            &harness,
+            &terminal,
+            QueryableBody::new(response.into(), None, None),
+        );
+
+        let lines = component
+            .data()
+            .visible_text()
+            .lines
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert!(
+            lines[0].starts_with(&format!("Binary content ({} B)", body.len()))
+        );
+        assert_eq!(lines[2], expected_hex);
+    }
+
+    /// A format override takes precedence over the `Content-Type` header
+    #[rstest]
+    #[case::json(
+        "text/plain",
+        TEXT,
+        BodyFormat::Json,
+        Some("{\n  \"greeting\": \"hello\"\n}")
+    )]
+    #[case::xml(
+        "text/plain",
+        "<a><b>hi</b></a>",
+        BodyFormat::Xml,
+        Some("<a>\n  <b>hi</b>\n</a>")
+    )]
+    #[case::text("application/json", TEXT, BodyFormat::Text, None)]
+    #[tokio::test]
+    async fn test_format_override(
+        harness: TestHarness,
+        terminal: TestTerminal,
+        #[case] content_type: &str,
+        #[case] body: &'static str,
+        #[case] format: BodyFormat,
+        #[case] expected: Option<&str>,
+    ) {
+        let response = ResponseRecord {
+            headers: header_map([("content-type", content_type)]),
+            body: ResponseBody::new(body.into()),
+            ..ResponseRecord::factory(())
+        };
+        let component = TestComponent::new(
+            &harness,
+            &terminal,
+            QueryableBody::new(response.into(), None, Some(format)),
+        );
+        assert_eq!(component.data().modified_text().as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case::none(None, false)]
+    #[case::json(Some("application/json"), false)]
+    #[case::text(Some("text/plain"), false)]
+    #[case::svg(Some("image/svg+xml"), false)]
+    #[case::png(Some("image/png"), true)]
+    #[case::octet_stream(Some("application/octet-stream"), true)]
+    #[case::protobuf(Some("application/x-protobuf"), true)]
+    fn test_is_binary_mime(#[case] mime: Option<&str>, #[case] expected: bool) {
+        let mime: Option<Mime> = mime.map(|mime| mime.parse().unwrap());
+        assert_eq!(is_binary_mime(mime.as_ref()), expected);
+    }
 }
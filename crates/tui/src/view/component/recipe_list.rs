@@ -17,6 +17,7 @@ use crate::{
     },
 };
 use derive_more::{Deref, DerefMut};
+use itertools::Itertools;
 use persisted::{PersistedKey, SingletonKey};
 use ratatui::{
     layout::{Constraint, Layout},
@@ -25,8 +26,12 @@ use ratatui::{
 };
 use serde::{Deserialize, Serialize};
 use slumber_config::Action;
-use slumber_core::collection::{
-    HasId, RecipeId, RecipeLookupKey, RecipeNode, RecipeNodeType, RecipeTree,
+use slumber_core::{
+    collection::{
+        HasId, Recipe, RecipeId, RecipeLookupKey, RecipeNode, RecipeNodeType,
+        RecipeTree,
+    },
+    http::HttpMethod,
 };
 use std::collections::HashSet;
 use strum::{EnumIter, IntoEnumIterator};
@@ -61,6 +66,8 @@ pub struct RecipeListPane {
     /// issue though, it just means it'll be pre-collapsed if the user ever
     /// adds the folder back. Not worth working around.
     collapsed: Persisted<SingletonKey<Collapsed>>,
+    /// Show the folder tree, or a flat list grouped by method?
+    view: Persisted<SingletonKey<RecipeListView>>,
 
     filter: Component<TextBox>,
     filter_focused: bool,
@@ -75,9 +82,11 @@ impl RecipeListPane {
         // recipes
         let collapsed: Persisted<SingletonKey<Collapsed>> =
             Persisted::default();
+        let view: Persisted<SingletonKey<RecipeListView>> =
+            Persisted::default();
         let select = PersistedLazy::new(
             SelectedRecipeKey,
-            collapsed.build_select_state(recipes, ""),
+            collapsed.build_select_state(recipes, *view, ""),
         );
         let filter =
             TextBox::default().placeholder(format!("{binding} to filter"));
@@ -86,6 +95,7 @@ impl RecipeListPane {
             actions_emitter: Default::default(),
             select: select.into(),
             collapsed,
+            view,
             filter: filter.into(),
             filter_focused: false,
         }
@@ -137,10 +147,21 @@ impl RecipeListPane {
         changed
     }
 
-    /// Rebuild the select list based on current filter/collapsed state
+    /// Switch between the folder tree and the method-grouped list
+    fn toggle_grouping(&mut self) {
+        let view = match *self.view {
+            RecipeListView::Tree => RecipeListView::Method,
+            RecipeListView::Method => RecipeListView::Tree,
+        };
+        *self.view.get_mut() = view;
+        self.rebuild_select_state();
+    }
+
+    /// Rebuild the select list based on current filter/collapsed/view state
     fn rebuild_select_state(&mut self) {
         let mut new_select_state = self.collapsed.build_select_state(
             &ViewContext::collection().recipes,
+            *self.view,
             &self.filter.data().text().trim().to_lowercase(),
         );
 
@@ -170,6 +191,7 @@ impl EventHandler for RecipeListPane {
                 Action::Search => {
                     self.filter_focused = true;
                 }
+                Action::ToggleGrouping => self.toggle_grouping(),
                 _ => propagate.set(),
             })
             .emitted(self.select.to_emitter(), |event| match event {
@@ -178,6 +200,7 @@ impl EventHandler for RecipeListPane {
                     // request from the DB. If a recipe isn't selected, this
                     // will do nothing
                     ViewContext::push_event(Event::HttpSelectRequest(None));
+                    ViewContext::send_message(Message::HttpAutoSend);
                 }
                 SelectStateEvent::Submit(_) => {}
                 SelectStateEvent::Toggle(_) => {
@@ -198,6 +221,22 @@ impl EventHandler for RecipeListPane {
                 RecipeListMenuAction::CopyCurl => {
                     ViewContext::send_message(Message::CopyRequestCurl)
                 }
+                RecipeListMenuAction::CopyFetch => {
+                    ViewContext::send_message(Message::CopyRequestFetch)
+                }
+                RecipeListMenuAction::CopyHttpie => {
+                    ViewContext::send_message(Message::CopyRequestHttpie)
+                }
+                RecipeListMenuAction::SendFolder => {
+                    if let Some((folder_id, RecipeNodeType::Folder)) =
+                        self.selected_node()
+                    {
+                        ViewContext::send_message(Message::HttpBeginFolder {
+                            folder_id: folder_id.clone(),
+                        })
+                    }
+                }
+                RecipeListMenuAction::ToggleGrouping => self.toggle_grouping(),
             })
     }
 
@@ -218,9 +257,13 @@ impl Draw for RecipeListPane {
     fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
         let context = TuiContext::get();
 
+        let title = match *self.view {
+            RecipeListView::Tree => "Recipes",
+            RecipeListView::Method => "Recipes by Method",
+        };
         let title = context
             .input_engine
-            .add_hint("Recipes", Action::SelectRecipeList);
+            .add_hint(title, Action::SelectRecipeList);
         let block = Pane {
             title: &title,
             has_focus: metadata.has_focus(),
@@ -274,21 +317,47 @@ enum RecipeListMenuAction {
     CopyUrl,
     #[display("Copy as cURL")]
     CopyCurl,
+    #[display("Copy as fetch()")]
+    CopyFetch,
+    #[display("Copy as HTTPie")]
+    CopyHttpie,
+    #[display("Send All in Folder")]
+    SendFolder,
+    #[display("Toggle Method Grouping")]
+    ToggleGrouping,
 }
 
 impl IntoMenuAction<RecipeListPane> for RecipeListMenuAction {
     fn enabled(&self, data: &RecipeListPane) -> bool {
-        let recipe = data
-            .select
-            .data()
-            .selected()
-            .filter(|node| node.is_recipe());
+        let selected = data.select.data().selected();
+        match self {
+            Self::CopyUrl
+            | Self::CopyCurl
+            | Self::CopyFetch
+            | Self::CopyHttpie => selected.is_some_and(|node| node.is_recipe()),
+            Self::SendFolder => selected.is_some_and(|node| node.is_folder()),
+            Self::ToggleGrouping => true,
+        }
+    }
+
+    fn shortcut(&self, _: &RecipeListPane) -> Option<Action> {
         match self {
-            Self::CopyUrl | Self::CopyCurl => recipe.is_some(),
+            Self::ToggleGrouping => Some(Action::ToggleGrouping),
+            _ => None,
         }
     }
 }
 
+/// How recipes are arranged in the list
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+enum RecipeListView {
+    /// Folders and recipes, as defined in the collection
+    #[default]
+    Tree,
+    /// Flat list of recipes, grouped by HTTP method. Folders are hidden
+    Method,
+}
+
 /// Simplified version of [RecipeNode], to be used in the display tree. This
 /// only stores whatever data is necessary to render the list
 #[derive(Debug)]
@@ -298,6 +367,8 @@ struct RecipeListItem {
     kind: RecipeNodeType,
     depth: usize,
     collapsed: bool,
+    /// Method to label the item with. Only set when grouping by method
+    method: Option<HttpMethod>,
 }
 
 impl RecipeListItem {
@@ -308,6 +379,19 @@ impl RecipeListItem {
             kind: node.into(),
             collapsed,
             depth,
+            method: None,
+        }
+    }
+
+    /// Build an item for the method-grouped list
+    fn with_method(recipe: &Recipe) -> Self {
+        Self {
+            id: recipe.id.clone(),
+            name: recipe.name().to_owned(),
+            kind: RecipeNodeType::Recipe,
+            collapsed: false,
+            depth: 0,
+            method: Some(recipe.method),
         }
     }
 
@@ -339,7 +423,8 @@ impl PartialEq<RecipeListItem> for RecipeId {
 }
 
 impl<'a> Generate for &'a RecipeListItem {
-    type Output<'this> = Text<'this>
+    type Output<'this>
+        = Text<'this>
     where
         Self: 'this;
 
@@ -347,6 +432,11 @@ impl<'a> Generate for &'a RecipeListItem {
     where
         Self: 'this,
     {
+        if let Some(method) = self.method {
+            // Pad to the longest method name, so the names line up
+            return format!("{:<7} {}", method.to_str(), self.name).into();
+        }
+
         let icon = match self.kind {
             RecipeNodeType::Folder if self.collapsed => "▶",
             RecipeNodeType::Folder => "▼",
@@ -397,9 +487,21 @@ impl Collapsed {
     fn build_select_state(
         &self,
         recipes: &RecipeTree,
+        view: RecipeListView,
         filter: &str,
     ) -> SelectState<RecipeListItem> {
-        let items = if filter.is_empty() {
+        let items = if view == RecipeListView::Method {
+            // Collapsed state doesn't apply because there are no folders.
+            // Sorting is stable, so recipes keep their collection order
+            // within each method
+            recipes
+                .iter()
+                .filter_map(|(_, node)| node.recipe())
+                .filter(|recipe| recipe.name().to_lowercase().contains(filter))
+                .sorted_by_key(|recipe| recipe.method as usize)
+                .map(RecipeListItem::with_method)
+                .collect()
+        } else if filter.is_empty() {
             // No filter - calculate visible nodes based on collapsed state
             recipes
                 .iter()
@@ -454,7 +556,7 @@ mod tests {
     use rstest::{fixture, rstest};
     use slumber_core::{
         assert_matches,
-        collection::{Collection, Recipe},
+        collection::{Collection, Folder},
         test_util::{by_id, Factory},
     };
 
@@ -506,6 +608,90 @@ mod tests {
         assert!(!component.data().filter_focused);
     }
 
+    /// Toggle between the folder tree and the method-grouped list
+    #[rstest]
+    fn test_toggle_grouping(terminal: TestTerminal) {
+        let recipes: RecipeTree = by_id([
+            RecipeNode::Folder(Folder {
+                id: "folder1".into(),
+                children: by_id([
+                    RecipeNode::Recipe(Recipe {
+                        id: "recipe1".into(),
+                        method: HttpMethod::Post,
+                        ..Recipe::factory(())
+                    }),
+                    RecipeNode::Recipe(Recipe {
+                        id: "recipe2".into(),
+                        method: HttpMethod::Get,
+                        ..Recipe::factory(())
+                    }),
+                ]),
+                ..Folder::factory(())
+            }),
+            RecipeNode::Recipe(Recipe {
+                id: "recipe3".into(),
+                method: HttpMethod::Post,
+                ..Recipe::factory(())
+            }),
+        ])
+        .into();
+        let harness = TestHarness::new(Collection {
+            recipes,
+            ..Collection::factory(())
+        });
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            RecipeListPane::new(&harness.collection.recipes),
+        );
+        assert_matches!(
+            component.int().drain_draw().events(),
+            &[Event::HttpSelectRequest(None)],
+        );
+        assert_eq!(
+            item_ids(component.data()),
+            ["folder1", "recipe1", "recipe2", "recipe3"]
+        );
+
+        // Folders are hidden, and recipes are grouped by method
+        assert_matches!(
+            component.int().send_key(KeyCode::Char('g')).events(),
+            &[Event::HttpSelectRequest(None)],
+        );
+        assert_eq!(*component.data().view, RecipeListView::Method);
+        assert_eq!(
+            item_ids(component.data()),
+            ["recipe2", "recipe1", "recipe3"]
+        );
+
+        // Back to the tree. Selection is carried over
+        assert_matches!(
+            component.int().send_key(KeyCode::Char('g')).events(),
+            &[
+                Event::HttpSelectRequest(None),
+                Event::HttpSelectRequest(None)
+            ],
+        );
+        assert_eq!(*component.data().view, RecipeListView::Tree);
+        assert_eq!(
+            item_ids(component.data()),
+            ["folder1", "recipe1", "recipe2", "recipe3"]
+        );
+        assert_eq!(
+            component.data().selected_node(),
+            Some((&"recipe2".into(), RecipeNodeType::Recipe))
+        );
+    }
+
+    /// Get the IDs of all visible items in the list
+    fn item_ids(pane: &RecipeListPane) -> Vec<String> {
+        pane.select
+            .data()
+            .items()
+            .map(|item| item.id.to_string())
+            .collect()
+    }
+
     #[fixture]
     fn recipes() -> RecipeTree {
         by_id([
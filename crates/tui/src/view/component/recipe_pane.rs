@@ -90,6 +90,12 @@ impl EventHandler for RecipePane {
                 RecipePaneMenuAction::CopyCurl => {
                     ViewContext::send_message(Message::CopyRequestCurl)
                 }
+                RecipePaneMenuAction::CopyFetch => {
+                    ViewContext::send_message(Message::CopyRequestFetch)
+                }
+                RecipePaneMenuAction::CopyHttpie => {
+                    ViewContext::send_message(Message::CopyRequestHttpie)
+                }
             })
     }
 
@@ -200,6 +206,10 @@ enum RecipePaneMenuAction {
     CopyUrl,
     #[display("Copy as cURL")]
     CopyCurl,
+    #[display("Copy as fetch()")]
+    CopyFetch,
+    #[display("Copy as HTTPie")]
+    CopyHttpie,
 }
 
 impl IntoMenuAction<RecipePane> for RecipePaneMenuAction {
@@ -209,14 +219,18 @@ impl IntoMenuAction<RecipePane> for RecipePaneMenuAction {
         });
         match self {
             // Enabled if we have any recipe
-            Self::CopyUrl | Self::CopyCurl => recipe.is_some(),
+            Self::CopyUrl
+            | Self::CopyCurl
+            | Self::CopyFetch
+            | Self::CopyHttpie => recipe.is_some(),
         }
     }
 }
 
 /// Render folder as a tree
 impl<'a> Generate for &'a Folder {
-    type Output<'this> = Text<'this>
+    type Output<'this>
+        = Text<'this>
     where
         Self: 'this;
 
@@ -69,6 +69,7 @@ impl AuthenticationDisplay {
                     None,
                 ),
             },
+            Authentication::Helper { command } => State::Helper { command },
         };
         Self {
             override_emitter: Default::default(),
@@ -92,6 +93,9 @@ impl AuthenticationDisplay {
                 State::Bearer { token, .. } => {
                     Authentication::Bearer(token.template().clone())
                 }
+                State::Helper { .. } => {
+                    unreachable!("Credential helpers can't be overridden")
+                }
             })
         } else {
             None
@@ -135,7 +139,7 @@ impl EventHandler for AuthenticationDisplay {
             State::Basic { selected_field, .. } => {
                 vec![selected_field.to_child_mut()]
             }
-            State::Bearer { .. } => vec![],
+            State::Bearer { .. } | State::Helper { .. } => vec![],
         }
     }
 }
@@ -150,6 +154,7 @@ impl Draw for AuthenticationDisplay {
         let label = match &self.state {
             State::Basic { .. } => "Basic",
             State::Bearer { .. } => "Bearer",
+            State::Helper { .. } => "Credential Helper",
         };
         frame.render_widget(
             Span::styled(
@@ -183,6 +188,15 @@ impl Draw for AuthenticationDisplay {
             State::Bearer { token } => {
                 frame.render_widget(token.preview().generate(), content_area);
             }
+            // The command isn't rendered, because that would run the helper
+            State::Helper { command } => {
+                let command = command
+                    .iter()
+                    .map(|template| template.display())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                frame.render_widget(command, content_area);
+            }
         }
     }
 }
@@ -204,7 +218,7 @@ enum AuthenticationMenuAction {
 impl IntoMenuAction<AuthenticationDisplay> for AuthenticationMenuAction {
     fn enabled(&self, data: &AuthenticationDisplay) -> bool {
         match self {
-            Self::Edit => true,
+            Self::Edit => data.state.is_editable(),
             Self::Reset => data.state.is_overridden(),
         }
     }
@@ -233,6 +247,11 @@ enum State {
     Bearer {
         token: RecipeTemplate,
     },
+    /// Credential helpers are read-only. Overriding the command would be of
+    /// little use, since the helper decides what credential to provide
+    Helper {
+        command: Vec<Template>,
+    },
 }
 
 impl State {
@@ -243,9 +262,15 @@ impl State {
                 username, password, ..
             } => username.is_overridden() || password.is_overridden(),
             Self::Bearer { token } => token.is_overridden(),
+            Self::Helper { .. } => false,
         }
     }
 
+    /// Can the fields be temporarily overridden?
+    fn is_editable(&self) -> bool {
+        !matches!(self, Self::Helper { .. })
+    }
+
     /// Open a modal to let the user edit temporary override values
     fn open_edit_modal(&self, emitter: Emitter<SaveAuthenticationOverride>) {
         let (label, value) = match &self {
@@ -265,6 +290,7 @@ impl State {
             Self::Bearer { token, .. } => {
                 ("bearer token", token.template().display())
             }
+            Self::Helper { .. } => return,
         };
         TextBoxModal::new(
             format!("Edit {label}"),
@@ -306,6 +332,7 @@ impl State {
             Self::Bearer { token } => {
                 token.set_override(template);
             }
+            Self::Helper { .. } => {}
         }
     }
 
@@ -328,6 +355,7 @@ impl State {
             Self::Bearer { token } => {
                 token.reset_override();
             }
+            Self::Helper { .. } => {}
         }
     }
 }
@@ -1,4 +1,5 @@
 use crate::{
+    context::TuiContext,
     message::Message,
     util::{delete_temp_file, temp_file, ResultReported},
     view::{
@@ -18,13 +19,18 @@ use crate::{
     },
 };
 use anyhow::Context;
+use itertools::Itertools;
 use mime::Mime;
-use ratatui::Frame;
+use ratatui::{
+    text::Line,
+    widgets::{Paragraph, Wrap},
+    Frame,
+};
 use serde::Serialize;
 use slumber_config::Action;
 use slumber_core::{
     collection::{Recipe, RecipeBody, RecipeId},
-    http::content_type::ContentType,
+    http::{content_type::ContentType, xml},
     template::Template,
 };
 use std::{
@@ -39,7 +45,19 @@ use tracing::debug;
 #[derive(Debug)]
 pub enum RecipeBodyDisplay {
     Raw(Component<RawBody>),
+    /// XML is displayed the same as a raw body, but we need to remember the
+    /// type so an override keeps the XML content type
+    Xml(Component<RawBody>),
     Form(Component<RecipeFieldTable<FormRowKey, FormRowToggleKey>>),
+    /// Body is generated by an external command. We just show the command,
+    /// since the output isn't known until the request is built
+    Command(String),
+    /// Body has multiple variants, selected at build time. We just show the
+    /// selector and the available variants
+    Variants {
+        select: Option<String>,
+        variants: Vec<String>,
+    },
 }
 
 impl RecipeBodyDisplay {
@@ -51,6 +69,24 @@ impl RecipeBodyDisplay {
             RecipeBody::Raw { body, .. } => {
                 Self::Raw(RawBody::new(body.clone(), recipe).into())
             }
+            // Show structured JSON as its source text. If it's edited, the
+            // override becomes a raw body
+            RecipeBody::Json(json) => {
+                let text = format!("{:#}", json.to_json());
+                let template =
+                    text.parse().unwrap_or_else(|_| Template::raw(text));
+                Self::Raw(RawBody::new(template, recipe).into())
+            }
+            // Pretty-print XML for readability. Templates in the document
+            // generally don't interfere with formatting, but if the source
+            // isn't valid XML just show it as-is
+            RecipeBody::Xml(template) => {
+                let indent = TuiContext::get().config.indent;
+                let template = xml::prettify(&template.display(), indent)
+                    .and_then(|text| text.parse().ok())
+                    .unwrap_or_else(|| template.clone());
+                Self::Xml(RawBody::new(template, recipe).into())
+            }
             RecipeBody::FormUrlencoded(fields)
             | RecipeBody::FormMultipart(fields) => {
                 let inner = RecipeFieldTable::new(
@@ -70,6 +106,15 @@ impl RecipeBodyDisplay {
                 );
                 Self::Form(inner.into())
             }
+            RecipeBody::Command { command, .. } => {
+                Self::Command(command.iter().map(Template::display).join(" "))
+            }
+            RecipeBody::Variants { select, variants } => Self::Variants {
+                select: select
+                    .as_ref()
+                    .map(|select| select.display().into_owned()),
+                variants: variants.keys().cloned().collect(),
+            },
         }
     }
 
@@ -86,6 +131,11 @@ impl RecipeBodyDisplay {
                     content_type: inner.body.content_type(),
                 })
             }
+            RecipeBodyDisplay::Xml(inner)
+                if inner.data().body.is_overridden() =>
+            {
+                Some(RecipeBody::Xml(inner.data().body.template().clone()))
+            }
             _ => None,
         }
     }
@@ -94,8 +144,11 @@ impl RecipeBodyDisplay {
 impl EventHandler for RecipeBodyDisplay {
     fn children(&mut self) -> Vec<Component<Child<'_>>> {
         match self {
-            Self::Raw(inner) => vec![inner.to_child_mut()],
+            Self::Raw(inner) | Self::Xml(inner) => {
+                vec![inner.to_child_mut()]
+            }
             Self::Form(form) => vec![form.to_child_mut()],
+            Self::Command(_) | Self::Variants { .. } => vec![],
         }
     }
 }
@@ -103,7 +156,7 @@ impl EventHandler for RecipeBodyDisplay {
 impl Draw for RecipeBodyDisplay {
     fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
         match self {
-            RecipeBodyDisplay::Raw(inner) => {
+            RecipeBodyDisplay::Raw(inner) | RecipeBodyDisplay::Xml(inner) => {
                 inner.draw(frame, (), metadata.area(), true)
             }
             RecipeBodyDisplay::Form(form) => form.draw(
@@ -115,6 +168,35 @@ impl Draw for RecipeBodyDisplay {
                 metadata.area(),
                 true,
             ),
+            RecipeBodyDisplay::Command(command) => frame.render_widget(
+                Paragraph::new(vec![
+                    "Body is generated by command:".into(),
+                    Line::from(command.as_str()),
+                ])
+                .wrap(Wrap::default()),
+                metadata.area(),
+            ),
+            RecipeBodyDisplay::Variants { select, variants } => {
+                let select = match select {
+                    Some(select) => format!("Variant is selected by: {select}"),
+                    None => format!(
+                        "Variant `{}` is used",
+                        variants.first().map_or("", String::as_str)
+                    ),
+                };
+                frame.render_widget(
+                    Paragraph::new(vec![
+                        "Body has multiple variants".into(),
+                        Line::from(select),
+                        Line::from(format!(
+                            "Variants: {}",
+                            variants.iter().join(", ")
+                        )),
+                    ])
+                    .wrap(Wrap::default()),
+                    metadata.area(),
+                )
+            }
         }
     }
 }
@@ -318,7 +400,6 @@ struct SaveBodyOverride(PathBuf);
 mod tests {
     use super::*;
     use crate::{
-        context::TuiContext,
         test_util::{harness, terminal, TestHarness, TestTerminal},
         view::{
             component::recipe_pane::persistence::{
@@ -440,6 +521,52 @@ mod tests {
         ]]);
     }
 
+    /// XML bodies are pretty-printed for display, and an override keeps the
+    /// XML content type
+    #[rstest]
+    fn test_xml(
+        mut harness: TestHarness,
+        #[with(30, 4)] terminal: TestTerminal,
+    ) {
+        let recipe = Recipe {
+            body: Some(RecipeBody::Xml(
+                r#"<a xmlns:s="urn:s"><s:b>{{x}}</s:b></a>"#.into(),
+            )),
+            ..Recipe::factory(())
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            RecipeBodyDisplay::new(recipe.body.as_ref().unwrap(), &recipe),
+        );
+        let template = assert_matches!(
+            component.data(),
+            RecipeBodyDisplay::Xml(inner) => inner.data().body.template(),
+        );
+        assert_eq!(
+            template.display(),
+            "<a xmlns:s=\"urn:s\">\n  <s:b>{{x}}</s:b>\n</a>"
+        );
+
+        harness.clear_messages();
+        component.int().send_key(KeyCode::Char('e')).assert_empty();
+        let (path, on_complete) = assert_matches!(
+            harness.pop_message_now(),
+            Message::FileEdit {
+                path,
+                on_complete,
+            } => (path, on_complete),
+        );
+        fs::write(&path, "<a/>").unwrap();
+        on_complete(path);
+        component.int().drain_draw().assert_empty();
+
+        assert_eq!(
+            component.data().override_value(),
+            Some(RecipeBody::Xml("<a/>".into()))
+        );
+    }
+
     /// Style text to match the text window gutter
     fn gutter(text: &str) -> Span {
         let styles = &TuiContext::get().styles;
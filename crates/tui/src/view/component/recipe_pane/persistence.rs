@@ -1,7 +1,11 @@
 //! Single-session persistence for recipe overrides
 
-use crate::view::{common::template_preview::TemplatePreview, ViewContext};
+use crate::{
+    context::TuiContext,
+    view::{common::template_preview::TemplatePreview, ViewContext},
+};
 use persisted::{PersistedContainer, PersistedLazy, PersistedStore};
+use ratatui::{style::Style, text::Text};
 use slumber_core::{
     collection::RecipeId, http::content_type::ContentType, template::Template,
 };
@@ -101,6 +105,17 @@ impl RecipeTemplate {
         &self.0.preview
     }
 
+    /// Get the unrendered template source, for when the user wants to see
+    /// the template instead of the preview
+    pub fn raw_text(&self) -> Text<'static> {
+        let style = if self.is_overridden() {
+            TuiContext::get().styles.text.edited
+        } else {
+            Style::default()
+        };
+        Text::styled(self.template().display().into_owned(), style)
+    }
+
     pub fn content_type(&self) -> Option<ContentType> {
         self.0.content_type
     }
@@ -175,6 +190,13 @@ pub struct RecipeOverrideKey {
 }
 
 impl RecipeOverrideKey {
+    pub fn url(recipe_id: RecipeId) -> Self {
+        Self {
+            kind: RecipeOverrideKeyKind::Url,
+            recipe_id,
+        }
+    }
+
     pub fn body(recipe_id: RecipeId) -> Self {
         Self {
             kind: RecipeOverrideKeyKind::Body,
@@ -238,6 +260,7 @@ impl RecipeOverrideKey {
 /// through methods on [RecipeOverrideKey] to make usage a bit terser.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 enum RecipeOverrideKeyKind {
+    Url,
     Body,
     AuthenticationBasicUsername,
     AuthenticationBasicPassword,
@@ -1,23 +1,41 @@
-use crate::view::{
-    common::{tabs::Tabs, template_preview::TemplatePreview},
-    component::recipe_pane::{
-        authentication::AuthenticationDisplay,
-        body::RecipeBodyDisplay,
-        persistence::RecipeOverrideKey,
-        table::{RecipeFieldTable, RecipeFieldTableProps},
+use crate::{
+    context::TuiContext,
+    util::ResultReported,
+    view::{
+        common::{
+            actions::{IntoMenuAction, MenuAction},
+            modal::Modal,
+            tabs::Tabs,
+            text_box::TextBox,
+        },
+        component::{
+            misc::TextBoxModal,
+            recipe_pane::{
+                authentication::AuthenticationDisplay,
+                body::RecipeBodyDisplay,
+                persistence::{RecipeOverrideKey, RecipeTemplate},
+                table::{RecipeFieldTable, RecipeFieldTableProps},
+            },
+        },
+        context::UpdateContext,
+        draw::{Draw, DrawMetadata},
+        event::{Child, Emitter, Event, EventHandler, OptionEvent},
+        util::persistence::{Persisted, PersistedLazy},
+        Component, ViewContext,
     },
-    draw::{Draw, DrawMetadata},
-    event::{Child, EventHandler},
-    util::persistence::PersistedLazy,
-    Component,
 };
 use derive_more::Display;
 use persisted::SingletonKey;
-use ratatui::{layout::Layout, prelude::Constraint, widgets::Paragraph, Frame};
+use ratatui::{
+    layout::Layout, prelude::Constraint, style::Style, widgets::Paragraph,
+    Frame,
+};
 use serde::{Deserialize, Serialize};
+use slumber_config::Action;
 use slumber_core::{
     collection::{Recipe, RecipeId},
     http::{BuildOptions, HttpMethod},
+    template::Template,
 };
 use strum::{EnumCount, EnumIter};
 
@@ -27,12 +45,20 @@ use strum::{EnumCount, EnumIter};
 #[derive(Debug)]
 pub struct RecipeDisplay {
     tabs: Component<PersistedLazy<SingletonKey<Tab>, Tabs<Tab>>>,
-    url: TemplatePreview,
+    /// URL can be temporarily overridden via the actions menu, e.g. to point
+    /// the recipe at a local mock server
+    url: RecipeTemplate,
+    url_override_emitter: Emitter<SaveUrlOverride>,
     method: HttpMethod,
+    /// Temporary method override for this session, set by cycling methods
+    method_override: Option<HttpMethod>,
     query: Component<RecipeFieldTable<QueryRowKey, QueryRowToggleKey>>,
     headers: Component<RecipeFieldTable<HeaderRowKey, HeaderRowToggleKey>>,
     body: Component<Option<RecipeBodyDisplay>>,
+    /// Should the body be included in the request? Toggled via menu action
+    body_enabled: Persisted<BodyToggleKey>,
     authentication: Component<Option<AuthenticationDisplay>>,
+    actions_emitter: Emitter<RecipeMenuAction>,
 }
 
 impl RecipeDisplay {
@@ -42,7 +68,13 @@ impl RecipeDisplay {
         Self {
             tabs: Default::default(),
             method: recipe.method,
-            url: TemplatePreview::new(recipe.url.clone(), None, false),
+            method_override: None,
+            url: RecipeTemplate::new(
+                RecipeOverrideKey::url(recipe.id.clone()),
+                recipe.url.clone(),
+                None,
+            ),
+            url_override_emitter: Default::default(),
             query: RecipeFieldTable::new(
                 "Parameter",
                 QueryRowKey(recipe.id.clone()),
@@ -82,6 +114,10 @@ impl RecipeDisplay {
                 .as_ref()
                 .map(|body| RecipeBodyDisplay::new(body, recipe))
                 .into(),
+            body_enabled: Persisted::new(
+                BodyToggleKey(recipe.id.clone()),
+                true,
+            ),
             // Map authentication type
             authentication: recipe
                 .authentication
@@ -93,9 +129,44 @@ impl RecipeDisplay {
                     )
                 })
                 .into(),
+            actions_emitter: Default::default(),
         }
     }
 
+    /// Switch to the next method in the configured cycle. If the current
+    /// method isn't in the list, start from the beginning. When we cycle back
+    /// to the recipe's method, the override is cleared. If the cycle is
+    /// empty, do nothing.
+    fn cycle_method(&mut self) {
+        let methods = &TuiContext::get().config.cycle_methods;
+        let Some(&first) = methods.first() else {
+            return;
+        };
+        let current = self.method_override.unwrap_or(self.method);
+        let next = methods
+            .iter()
+            .position(|method| *method == current)
+            .map(|i| methods[(i + 1) % methods.len()])
+            .unwrap_or(first);
+        self.method_override = Some(next).filter(|next| *next != self.method);
+    }
+
+    /// Open a modal to let the user enter a temporary URL override
+    fn open_url_modal(&self) {
+        let emitter = self.url_override_emitter;
+        TextBoxModal::new(
+            "Edit URL".into(),
+            TextBox::default()
+                .default_value(self.url.template().display().into_owned())
+                .validator(|value| value.parse::<Template>().is_ok()),
+            move |value| {
+                // Defer the state update into an event, so it can get &mut
+                emitter.emit(SaveUrlOverride(value))
+            },
+        )
+        .open()
+    }
+
     /// Generate a [BuildOptions] instance based on current UI state
     pub fn build_options(&self) -> BuildOptions {
         let authentication = self
@@ -108,7 +179,10 @@ impl RecipeDisplay {
             .data()
             .as_ref()
             .and_then(|body| match body {
-                RecipeBodyDisplay::Raw(_) => None,
+                RecipeBodyDisplay::Raw(_)
+                | RecipeBodyDisplay::Xml(_)
+                | RecipeBodyDisplay::Command(_)
+                | RecipeBodyDisplay::Variants { .. } => None,
                 RecipeBodyDisplay::Form(form) => {
                     Some(form.data().to_build_overrides())
                 }
@@ -121,16 +195,68 @@ impl RecipeDisplay {
             .and_then(|body| body.override_value());
 
         BuildOptions {
+            method: self.method_override,
+            url: self
+                .url
+                .is_overridden()
+                .then(|| self.url.template().clone()),
             authentication,
             headers: self.headers.data().to_build_overrides(),
             query_parameters: self.query.data().to_build_overrides(),
             form_fields,
             body,
+            omit_body: !*self.body_enabled,
+            // Variant is selected by the recipe
+            body_variant: None,
         }
     }
 }
 
 impl EventHandler for RecipeDisplay {
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> Option<Event> {
+        event
+            .opt()
+            .action(|action, propagate| match action {
+                Action::CycleMethod => self.cycle_method(),
+                _ => propagate.set(),
+            })
+            .emitted(self.actions_emitter, |menu_action| match menu_action {
+                RecipeMenuAction::DisableBody
+                | RecipeMenuAction::EnableBody => {
+                    *self.body_enabled.get_mut() ^= true
+                }
+                RecipeMenuAction::EditUrl => self.open_url_modal(),
+                RecipeMenuAction::ResetUrl => self.url.reset_override(),
+            })
+            .emitted(self.url_override_emitter, |SaveUrlOverride(value)| {
+                // The template *should* always parse because the text box has
+                // a validator, but this is just a safety check
+                if let Some(template) = value
+                    .parse::<Template>()
+                    .reported(&ViewContext::messages_tx())
+                {
+                    self.url.set_override(template);
+                }
+            })
+    }
+
+    fn menu_actions(&self) -> Vec<MenuAction> {
+        // Only show the body action that applies to the current state
+        let body_action = if *self.body_enabled {
+            RecipeMenuAction::DisableBody
+        } else {
+            RecipeMenuAction::EnableBody
+        };
+        [
+            body_action,
+            RecipeMenuAction::EditUrl,
+            RecipeMenuAction::ResetUrl,
+        ]
+        .into_iter()
+        .map(MenuAction::with_data(self, self.actions_emitter))
+        .collect()
+    }
+
     fn children(&mut self) -> Vec<Component<Child<'_>>> {
         vec![
             self.tabs.to_child_mut(),
@@ -145,7 +271,7 @@ impl EventHandler for RecipeDisplay {
 impl Draw for RecipeDisplay {
     fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
         // Render request contents
-        let method = self.method.to_string();
+        let method = self.method_override.unwrap_or(self.method).to_string();
 
         let [metadata_area, tabs_area, content_area] = Layout::vertical([
             Constraint::Length(1),
@@ -161,14 +287,29 @@ impl Draw for RecipeDisplay {
         .areas(metadata_area);
 
         // First line: Method + URL
-        frame.render_widget(Paragraph::new(method), method_area);
-        frame.render_widget(&self.url, url_area);
+        let method_style = if self.method_override.is_some() {
+            TuiContext::get().styles.text.edited
+        } else {
+            Style::default()
+        };
+        frame.render_widget(
+            Paragraph::new(method).style(method_style),
+            method_area,
+        );
+        frame.render_widget(self.url.preview(), url_area);
 
         // Navigation tabs
         self.tabs.draw(frame, (), tabs_area, true);
 
         // Recipe content
         match self.tabs.data().selected() {
+            Tab::Body if self.body.data().is_some() && !*self.body_enabled => {
+                frame.render_widget(
+                    Paragraph::new("Body is disabled and will not be sent")
+                        .style(TuiContext::get().styles.table.disabled),
+                    content_area,
+                )
+            }
             Tab::Body => self.body.draw_opt(frame, (), content_area, true),
             Tab::Query => self.query.draw(
                 frame,
@@ -241,3 +382,111 @@ struct HeaderRowToggleKey {
     recipe_id: RecipeId,
     header: String,
 }
+
+/// Persistence key for the body toggle state, per recipe
+#[derive(Debug, Serialize, persisted::PersistedKey)]
+#[persisted(bool)]
+struct BodyToggleKey(RecipeId);
+
+/// Local event to save the user's URL override. Triggered from the edit
+/// modal. This is the raw string value, consumer has to parse it to a
+/// template.
+#[derive(Debug)]
+struct SaveUrlOverride(String);
+
+/// Action menu items for a recipe
+#[derive(Copy, Clone, Debug, Display)]
+enum RecipeMenuAction {
+    #[display("Disable Body")]
+    DisableBody,
+    #[display("Enable Body")]
+    EnableBody,
+    #[display("Edit URL")]
+    EditUrl,
+    #[display("Reset URL")]
+    ResetUrl,
+}
+
+impl IntoMenuAction<RecipeDisplay> for RecipeMenuAction {
+    fn enabled(&self, data: &RecipeDisplay) -> bool {
+        match self {
+            Self::DisableBody | Self::EnableBody => data.body.data().is_some(),
+            Self::EditUrl => true,
+            Self::ResetUrl => data.url.is_overridden(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{harness, terminal, TestHarness, TestTerminal},
+        view::test_util::TestComponent,
+    };
+    use crossterm::event::KeyCode;
+    use rstest::rstest;
+    use slumber_core::test_util::Factory;
+
+    /// Cycling the method rotates through the configured methods, and clears
+    /// the override when we get back to the recipe's method
+    #[rstest]
+    #[case::next(HttpMethod::Get, 1, Some(HttpMethod::Post))]
+    #[case::wrap(HttpMethod::Delete, 1, Some(HttpMethod::Get))]
+    #[case::uncommon(HttpMethod::Head, 1, Some(HttpMethod::Get))]
+    #[case::full_cycle(HttpMethod::Put, 5, None)]
+    fn test_cycle_method(
+        harness: TestHarness,
+        terminal: TestTerminal,
+        #[case] method: HttpMethod,
+        #[case] presses: usize,
+        #[case] expected: Option<HttpMethod>,
+    ) {
+        let recipe = Recipe {
+            method,
+            ..Recipe::factory(())
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            RecipeDisplay::new(&recipe),
+        );
+
+        component
+            .int()
+            .send_keys(vec![KeyCode::Char('m'); presses])
+            .assert_empty();
+        assert_eq!(component.data().build_options().method, expected);
+    }
+
+    /// The URL can be overridden from the actions menu, and reset
+    #[rstest]
+    fn test_override_url(harness: TestHarness, terminal: TestTerminal) {
+        let recipe = Recipe::factory(());
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            RecipeDisplay::new(&recipe),
+        );
+        assert_eq!(component.data().build_options().url, None);
+
+        component.data().open_url_modal();
+        component
+            .int()
+            .drain_draw()
+            .send_text("/mock")
+            .send_key(KeyCode::Enter)
+            .assert_empty();
+        assert_eq!(
+            component.data().build_options().url,
+            Some("http://localhost/url/mock".into())
+        );
+
+        component
+            .data()
+            .actions_emitter
+            .emit(RecipeMenuAction::ResetUrl);
+        component.int().drain_draw().assert_empty();
+        assert_eq!(component.data().build_options().url, None);
+    }
+}
@@ -85,6 +85,7 @@ where
                     None,
                 ),
                 enabled: Persisted::new(toggle_key, true),
+                show_raw: false,
             })
             .collect();
         let select = SelectState::builder(items)
@@ -122,6 +123,14 @@ where
             selected_row.value.reset_override();
         }
     }
+
+    fn toggle_raw_selected_row(&mut self) {
+        if let Some(selected_row) =
+            self.select.data_mut().get_mut().selected_mut()
+        {
+            selected_row.show_raw ^= true;
+        }
+    }
 }
 
 impl<RowSelectKey, RowToggleKey> EventHandler
@@ -137,6 +146,7 @@ where
                 // Consume the event even if we have no rows, for consistency
                 Action::Edit => self.edit_selected_row(),
                 Action::Reset => self.reset_selected_row(),
+                Action::ToggleRaw => self.toggle_raw_selected_row(),
                 _ => propagate.set(),
             })
             .emitted(self.select.to_emitter(), |event| {
@@ -164,6 +174,9 @@ where
                 RecipeTableMenuAction::Reset { .. } => {
                     self.reset_selected_row()
                 }
+                RecipeTableMenuAction::ToggleRaw { .. } => {
+                    self.toggle_raw_selected_row()
+                }
             })
     }
 
@@ -171,6 +184,7 @@ where
         [
             RecipeTableMenuAction::Edit { noun: self.noun },
             RecipeTableMenuAction::Reset { noun: self.noun },
+            RecipeTableMenuAction::ToggleRaw { noun: self.noun },
         ]
         .into_iter()
         .map(MenuAction::with_data(self, self.actions_emitter))
@@ -228,6 +242,8 @@ enum RecipeTableMenuAction {
     Edit { noun: &'static str },
     #[display("Reset {noun}")]
     Reset { noun: &'static str },
+    #[display("Toggle Raw {noun}")]
+    ToggleRaw { noun: &'static str },
 }
 
 impl<RowSelectKey, RowToggleKey>
@@ -243,7 +259,7 @@ where
     ) -> bool {
         let selected = data.select.data().selected();
         match self {
-            Self::Edit { .. } => selected.is_some(),
+            Self::Edit { .. } | Self::ToggleRaw { .. } => selected.is_some(),
             Self::Reset { .. } => {
                 selected.is_some_and(|row| row.value.is_overridden())
             }
@@ -257,6 +273,7 @@ where
         match self {
             Self::Edit { .. } => Some(Action::Edit),
             Self::Reset { .. } => Some(Action::Reset),
+            Self::ToggleRaw { .. } => Some(Action::ToggleRaw),
         }
     }
 }
@@ -291,6 +308,9 @@ struct RowState<K: PersistedKey<Value = bool>> {
     /// by index (the actual unique key), then adding/removing any field to the
     /// table will mess with persistence.
     enabled: Persisted<K>,
+    /// Show the raw template instead of the rendered preview? This is just a
+    /// view toggle for the current session, so it isn't persisted
+    show_raw: bool,
 }
 
 impl<K: PersistedKey<Value = bool>> Generate for &RowState<K> {
@@ -302,11 +322,13 @@ impl<K: PersistedKey<Value = bool>> Generate for &RowState<K> {
     where
         Self: 'this,
     {
-        ToggleRow::new(
-            [self.key.as_str().into(), self.value.preview().generate()],
-            *self.enabled,
-        )
-        .generate()
+        let value = if self.show_raw {
+            self.value.raw_text()
+        } else {
+            self.value.preview().generate()
+        };
+        ToggleRow::new([self.key.as_str().into(), value], *self.enabled)
+            .generate()
     }
 }
 
@@ -397,6 +419,7 @@ mod tests {
     };
     use crossterm::event::KeyCode;
     use persisted::PersistedStore;
+    use ratatui::text::Text;
     use rstest::rstest;
     use serde::Serialize;
     use slumber_core::{collection::RecipeId, test_util::Factory};
@@ -475,6 +498,49 @@ mod tests {
         );
     }
 
+    /// User can flip a single row between the rendered preview and the raw
+    /// template
+    #[rstest]
+    fn test_toggle_raw(harness: TestHarness, terminal: TestTerminal) {
+        let recipe_id = RecipeId::factory(());
+        let rows = [(
+            "row0".into(),
+            "{{user_id}}".parse().unwrap(),
+            RecipeOverrideKey::query_param(recipe_id.clone(), 0),
+            TestRowToggleKey {
+                recipe_id: recipe_id.clone(),
+                key: "row0".into(),
+            },
+        )];
+        let mut component = TestComponent::with_props(
+            &harness,
+            &terminal,
+            RecipeFieldTable::new("Row", TestRowKey(recipe_id.clone()), rows),
+            RecipeFieldTableProps {
+                key_header: "Key",
+                value_header: "Value",
+            },
+        );
+        let show_raw = |component: &TestComponent<_, _>| {
+            component.data().select.data().selected().unwrap().show_raw
+        };
+
+        assert!(!show_raw(&component));
+        component.int().send_key(KeyCode::Char('t')).assert_empty();
+        assert!(show_raw(&component));
+        let row = component.data().select.data().selected().unwrap();
+        assert_eq!(row.value.raw_text(), Text::from("{{user_id}}"));
+
+        // Toggling doesn't affect the request that gets built
+        assert_eq!(
+            component.data().to_build_overrides(),
+            BuildFieldOverrides::default()
+        );
+
+        component.int().send_key(KeyCode::Char('t')).assert_empty();
+        assert!(!show_raw(&component));
+    }
+
     /// User can edit the value for a row
     #[rstest]
     fn test_override_row(harness: TestHarness, terminal: TestTerminal) {
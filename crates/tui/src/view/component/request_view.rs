@@ -116,7 +116,7 @@ impl Draw for RequestView {
         frame.render_widget(request.url.to_string(), url_area);
         frame.render_widget(
             HeaderTable {
-                headers: &request.headers,
+                headers: request.headers.iter().collect(),
             }
             .generate(),
             headers_area,
@@ -3,27 +3,55 @@
 use crate::{
     context::TuiContext,
     message::Message,
+    util::spawn,
     view::{
         common::{
             actions::{IntoMenuAction, MenuAction},
             header_table::HeaderTable,
+            table::Table,
+            text_box::{TextBox, TextBoxEvent, TextBoxProps},
+            text_window::{TextWindow, TextWindowProps},
         },
         component::queryable_body::QueryableBody,
         context::UpdateContext,
         draw::{Draw, DrawMetadata, Generate},
-        event::{Child, Emitter, Event, EventHandler, OptionEvent},
+        event::{Child, Emitter, Event, EventHandler, OptionEvent, ToEmitter},
+        state::{Identified, StateCell},
         util::{persistence::PersistedLazy, view_text},
         Component, ViewContext,
     },
 };
+use anyhow::Context;
+use bytes::Bytes;
+use chrono::{DateTime, TimeDelta, Utc};
 use derive_more::Display;
+use image::DynamicImage;
+use itertools::Itertools;
 use persisted::PersistedKey;
-use ratatui::Frame;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Text},
+    widgets::Paragraph,
+    Frame,
+};
+use ratatui_image::{
+    picker::Picker, protocol::StatefulProtocol, StatefulImage,
+};
+use reqwest::header::{self, HeaderName, HeaderValue};
 use serde::Serialize;
 use slumber_config::Action;
-use slumber_core::{collection::RecipeId, http::ResponseRecord};
-use std::sync::Arc;
+use slumber_core::{
+    collection::RecipeId,
+    http::{Exchange, ResponseRecord},
+    util::{format_byte_size, MaybeStr, ResultTraced},
+};
+use std::{
+    cell::RefCell,
+    fmt::{self, Debug},
+    sync::Arc,
+};
 use strum::{EnumIter, IntoEnumIterator};
+use tokio::task;
 
 /// Display response body
 #[derive(Debug)]
@@ -34,24 +62,37 @@ pub struct ResponseBodyView {
     /// match the response body. We apply transformations such as filter,
     /// prettification, or in the case of binary responses, a hex dump.
     body: Component<PersistedLazy<ResponseQueryKey, QueryableBody>>,
+    /// If the response is an image and the terminal can display it, render
+    /// the image instead of the body text
+    image: ImageState,
+    image_emitter: Emitter<ImageDecoded>,
 }
 
 impl ResponseBodyView {
-    pub fn new(recipe_id: RecipeId, response: Arc<ResponseRecord>) -> Self {
+    pub fn new(exchange: &Exchange) -> Self {
+        let response = Arc::clone(&exchange.response);
+        let config = &TuiContext::get().config;
+        let mime = response.mime();
         // Select default query based on content type
-        let config = &TuiContext::get().config.commands;
-        let default_query = response
-            .mime()
-            .and_then(|mime| config.default_query.get(&mime).cloned());
+        let default_query = mime
+            .as_ref()
+            .and_then(|mime| config.commands.default_query.get(mime).cloned());
+        let format = config
+            .content_type_overrides
+            .get(exchange.request.url.as_str(), mime.as_ref());
         let body = PersistedLazy::new(
-            ResponseQueryKey(recipe_id),
-            QueryableBody::new(Arc::clone(&response), default_query),
+            ResponseQueryKey(exchange.request.recipe_id.clone()),
+            QueryableBody::new(Arc::clone(&response), default_query, format),
         )
         .into();
+        let image_emitter = Emitter::default();
+        let image = ImageState::new(&response, image_emitter);
         Self {
             actions_emitter: Default::default(),
             response,
             body,
+            image,
+            image_emitter,
         }
     }
 
@@ -87,8 +128,18 @@ impl EventHandler for ResponseBodyView {
                             data: self.body.data().modified_text(),
                         });
                     }
+                    ResponseBodyMenuAction::SaveFullBody => {
+                        ViewContext::send_message(
+                            Message::SaveFullResponseBody {
+                                request_id: self.response.id,
+                            },
+                        );
+                    }
                 }
             })
+            .emitted(self.image_emitter, |ImageDecoded(result)| {
+                self.image = ImageState::decoded(result);
+            })
     }
 
     fn menu_actions(&self) -> Vec<MenuAction> {
@@ -104,7 +155,107 @@ impl EventHandler for ResponseBodyView {
 
 impl Draw for ResponseBodyView {
     fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
-        self.body.draw(frame, (), metadata.area(), true);
+        match &self.image {
+            ImageState::None => {
+                self.body.draw(frame, (), metadata.area(), true)
+            }
+            ImageState::Decoding => frame.render_widget(
+                Paragraph::new("Loading image..."),
+                metadata.area(),
+            ),
+            ImageState::Decoded(image) => image.draw(frame, metadata.area()),
+        }
+    }
+}
+
+/// Image preview state for a response body
+#[derive(Debug)]
+enum ImageState {
+    /// The terminal doesn't support images, the response isn't an image, or
+    /// it failed to decode. Show the body text instead
+    None,
+    /// Image is being decoded in a background task
+    Decoding,
+    Decoded(ImagePreview),
+}
+
+impl ImageState {
+    /// If the response is an image that the terminal can display, start
+    /// decoding it in the background. Decoding a large image can take a while,
+    /// so it can't block the UI thread. The result is sent to the emitter.
+    fn new(response: &ResponseRecord, emitter: Emitter<ImageDecoded>) -> Self {
+        if TuiContext::get().image_picker.is_none() {
+            return Self::None;
+        }
+        // SVG is text, so it can't be decoded as a raster image
+        let Some(mime) = response.mime() else {
+            return Self::None;
+        };
+        if mime.type_() != mime::IMAGE || mime.subtype() == mime::SVG {
+            return Self::None;
+        }
+
+        // Clone is cheap because Bytes uses refcounting
+        let body = response.body.bytes().clone();
+        spawn(async move {
+            let result = task::spawn_blocking(move || decode_image(&body))
+                .await
+                // This error only occurs if the task panics
+                .context("Error decoding image response")
+                .and_then(|result| result);
+            emitter.emit(ImageDecoded(result));
+        });
+        Self::Decoding
+    }
+
+    /// Build the final state from the result of a background decode
+    fn decoded(result: anyhow::Result<DynamicImage>) -> Self {
+        // Picker is cheap to copy, and needs mutable access
+        let Some(picker) = TuiContext::get().image_picker else {
+            return Self::None;
+        };
+        match result.traced() {
+            Ok(image) => Self::Decoded(ImagePreview::new(picker, image)),
+            // Fall back to showing the body
+            Err(_) => Self::None,
+        }
+    }
+}
+
+fn decode_image(body: &Bytes) -> anyhow::Result<DynamicImage> {
+    image::load_from_memory(body).context("Error decoding image response")
+}
+
+/// Emitted event when a background image decode has finished
+#[derive(Debug)]
+struct ImageDecoded(anyhow::Result<DynamicImage>);
+
+/// An image response body, encoded for the terminal's graphics protocol
+struct ImagePreview {
+    /// Drawing requires mutable access to re-encode the image when the area
+    /// is resized
+    protocol: RefCell<Box<dyn StatefulProtocol>>,
+}
+
+impl ImagePreview {
+    fn new(mut picker: Picker, image: DynamicImage) -> Self {
+        Self {
+            protocol: RefCell::new(picker.new_resize_protocol(image)),
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, area: Rect) {
+        frame.render_stateful_widget(
+            StatefulImage::new(None),
+            area,
+            &mut *self.protocol.borrow_mut(),
+        );
+    }
+}
+
+impl Debug for ImagePreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImagePreview").finish_non_exhaustive()
     }
 }
 
@@ -118,13 +269,28 @@ enum ResponseBodyMenuAction {
     CopyBody,
     #[display("Save Body as File")]
     SaveBody,
+    /// Only available for truncated bodies
+    #[display("Save Full Body as File")]
+    SaveFullBody,
 }
 
 impl IntoMenuAction<ResponseBodyView> for ResponseBodyMenuAction {
+    fn enabled(&self, data: &ResponseBodyView) -> bool {
+        match self {
+            // The truncated portion is only available for responses received
+            // in this session, not those loaded from history
+            Self::SaveFullBody => {
+                data.response.body.is_truncated()
+                    && data.response.body.has_full_body()
+            }
+            Self::ViewBody | Self::CopyBody | Self::SaveBody => true,
+        }
+    }
+
     fn shortcut(&self, _: &ResponseBodyView) -> Option<Action> {
         match self {
             Self::ViewBody => Some(Action::View),
-            Self::CopyBody | Self::SaveBody => None,
+            Self::CopyBody | Self::SaveBody | Self::SaveFullBody => None,
         }
     }
 }
@@ -134,28 +300,334 @@ impl IntoMenuAction<ResponseBodyView> for ResponseBodyMenuAction {
 #[persisted(String)]
 struct ResponseQueryKey(RecipeId);
 
+/// Display response headers, with a text box to filter them and menu actions
+/// to change their ordering
 #[derive(Debug)]
 pub struct ResponseHeadersView {
+    actions_emitter: Emitter<ResponseHeadersMenuAction>,
     response: Arc<ResponseRecord>,
+    /// Narrow down the visible headers by name or value
+    filter_text_box: Component<TextBox>,
+    filter_focused: bool,
+    sort: HeaderSort,
 }
 
 impl ResponseHeadersView {
     pub fn new(response: Arc<ResponseRecord>) -> Self {
-        Self { response }
+        let input_engine = &TuiContext::get().input_engine;
+        let filter_bind = input_engine.binding_display(Action::Search);
+        let filter_text_box = TextBox::default()
+            .placeholder(format!("{filter_bind} to filter"))
+            .placeholder_focused("Enter filter (matches name or value)");
+        Self {
+            actions_emitter: Default::default(),
+            response,
+            filter_text_box: filter_text_box.into(),
+            filter_focused: false,
+            sort: HeaderSort::default(),
+        }
+    }
+
+    /// Get the headers that should be visible, based on the current filter
+    /// and sort settings
+    fn visible_headers(&self) -> Vec<(&HeaderName, &HeaderValue)> {
+        let filter = self.filter_text_box.data().text().trim().to_lowercase();
+        let mut headers = self
+            .response
+            .headers
+            .iter()
+            .filter(|(name, value)| {
+                filter.is_empty()
+                    || name.as_str().contains(&filter)
+                    || String::from_utf8_lossy(value.as_bytes())
+                        .to_lowercase()
+                        .contains(&filter)
+            })
+            .collect_vec();
+        match self.sort {
+            HeaderSort::Insertion => {}
+            // Stable sort, so duplicate headers retain their relative order
+            HeaderSort::Name => {
+                headers.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()))
+            }
+        }
+        headers
     }
 }
 
-impl EventHandler for ResponseHeadersView {}
+impl EventHandler for ResponseHeadersView {
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> Option<Event> {
+        event
+            .opt()
+            .action(|action, propagate| match action {
+                Action::Search => self.filter_focused = true,
+                _ => propagate.set(),
+            })
+            .emitted(self.filter_text_box.to_emitter(), |event| match event {
+                TextBoxEvent::Focus => self.filter_focused = true,
+                // Filtering is done at draw time, nothing to do here
+                TextBoxEvent::Change => {}
+                TextBoxEvent::Cancel => {
+                    self.filter_text_box.data_mut().clear();
+                    self.filter_focused = false;
+                }
+                TextBoxEvent::Submit => self.filter_focused = false,
+            })
+            .emitted(self.actions_emitter, |menu_action| {
+                self.sort = match menu_action {
+                    ResponseHeadersMenuAction::SortByName => HeaderSort::Name,
+                    ResponseHeadersMenuAction::SortByInsertion => {
+                        HeaderSort::Insertion
+                    }
+                };
+            })
+    }
+
+    fn menu_actions(&self) -> Vec<MenuAction> {
+        ResponseHeadersMenuAction::iter()
+            .map(MenuAction::with_data(self, self.actions_emitter))
+            .collect()
+    }
+
+    fn children(&mut self) -> Vec<Component<Child<'_>>> {
+        vec![self.filter_text_box.to_child_mut()]
+    }
+}
 
 impl Draw for ResponseHeadersView {
     fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        let connection = connection_text(&self.response);
+        let [connection_area, table_area, filter_area] = Layout::vertical([
+            Constraint::Length(connection.height() as u16 + 1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(metadata.area());
+        frame.render_widget(connection, connection_area);
         frame.render_widget(
             HeaderTable {
-                headers: &self.response.headers,
+                headers: self.visible_headers(),
             }
             .generate(),
+            table_area,
+        );
+        self.filter_text_box.draw(
+            frame,
+            TextBoxProps::default(),
+            filter_area,
+            self.filter_focused,
+        );
+    }
+}
+
+/// Details of the connection the response was received on, shown above the
+/// headers. reqwest only gives us the server's leaf certificate, so that's all
+/// we can show for TLS.
+fn connection_text(response: &ResponseRecord) -> Text<'static> {
+    let styles = &TuiContext::get().styles;
+    let mut lines = vec![Line::styled("Connection", styles.text.title)];
+    match &response.peer_certificate {
+        Some(certificate) => {
+            lines.push(format!("{} over TLS", response.http_version).into());
+            lines.push(
+                format!(
+                    "Certificate ({}) SHA-256: {}",
+                    format_byte_size(certificate.der().len()),
+                    certificate.fingerprint()
+                )
+                .into(),
+            );
+        }
+        None => lines.push(response.http_version.to_string().into()),
+    }
+    lines.into()
+}
+
+/// Display cookies set by the response, one row per `Set-Cookie` header
+#[derive(Debug)]
+pub struct ResponseCookiesView {
+    cookies: Vec<SetCookie>,
+}
+
+impl ResponseCookiesView {
+    pub fn new(response: &ResponseRecord) -> Self {
+        let cookies = response
+            .headers
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(SetCookie::parse)
+            .collect();
+        Self { cookies }
+    }
+}
+
+impl Draw for ResponseCookiesView {
+    fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        if self.cookies.is_empty() {
+            frame.render_widget("No cookies set", metadata.area());
+            return;
+        }
+        let table = Table {
+            header: Some(["Name", "Value", "Attributes"]),
+            rows: self
+                .cookies
+                .iter()
+                .map(|cookie| {
+                    [
+                        cookie.name.as_str(),
+                        cookie.value.as_str(),
+                        cookie.attributes.as_str(),
+                    ]
+                })
+                .collect(),
+            alternate_row_style: true,
+            column_widths: &[
+                Constraint::Percentage(25),
+                Constraint::Percentage(40),
+                Constraint::Percentage(35),
+            ],
+            ..Default::default()
+        };
+        frame.render_widget(table.generate(), metadata.area());
+    }
+}
+
+/// A cookie from a `Set-Cookie` header. Attributes (`Path`, `Expires`, etc.)
+/// are kept as a single string, because they're only displayed
+#[derive(Debug, PartialEq)]
+struct SetCookie {
+    name: String,
+    value: String,
+    attributes: String,
+}
+
+impl SetCookie {
+    /// Parse a `Set-Cookie` header value. Return `None` if the header doesn't
+    /// start with a `name=value` pair
+    fn parse(header: &HeaderValue) -> Option<Self> {
+        let header = String::from_utf8_lossy(header.as_bytes());
+        let mut parts = header.split(';').map(str::trim);
+        let (name, value) = parts.next()?.split_once('=')?;
+        Some(Self {
+            name: name.trim().to_owned(),
+            value: value.trim().to_owned(),
+            attributes: parts.filter(|part| !part.is_empty()).join("; "),
+        })
+    }
+}
+
+/// Display timing details for an exchange
+#[derive(Debug)]
+pub struct ResponseTimingView {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    duration: TimeDelta,
+    cached: bool,
+}
+
+impl ResponseTimingView {
+    pub fn new(exchange: &Exchange) -> Self {
+        Self {
+            start_time: exchange.start_time,
+            end_time: exchange.end_time,
+            duration: exchange.duration(),
+            cached: exchange.cached,
+        }
+    }
+}
+
+impl Draw for ResponseTimingView {
+    fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        let source = if self.cached {
+            "Reused from history"
+        } else {
+            "Network"
+        };
+        let table = Table {
+            rows: vec![
+                ["Sent".into(), self.start_time.generate()],
+                ["Completed".into(), self.end_time.generate()],
+                ["Duration".into(), self.duration.generate()],
+                ["Source".into(), source.into()],
+            ],
+            column_widths: &[Constraint::Length(10), Constraint::Min(0)],
+            ..Default::default()
+        };
+        frame.render_widget(table.generate(), metadata.area());
+    }
+}
+
+/// Display the response body exactly as it was received, without any
+/// prettification or querying. Binary bodies are shown as hex.
+#[derive(Debug)]
+pub struct ResponseRawView {
+    response: Arc<ResponseRecord>,
+    /// Formatting is deferred until the first draw, because hex formatting a
+    /// large binary body is expensive and most responses never open this tab
+    body: StateCell<(), Identified<Text<'static>>>,
+    text_window: Component<TextWindow>,
+}
+
+impl ResponseRawView {
+    pub fn new(response: Arc<ResponseRecord>) -> Self {
+        Self {
+            response,
+            body: Default::default(),
+            text_window: Default::default(),
+        }
+    }
+}
+
+impl EventHandler for ResponseRawView {
+    fn children(&mut self) -> Vec<Component<Child<'_>>> {
+        vec![self.text_window.to_child_mut()]
+    }
+}
+
+impl Draw for ResponseRawView {
+    fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        let body = self.body.get_or_update(&(), || {
+            let body = format!("{:#}", MaybeStr(self.response.body.bytes()));
+            Identified::new(body.into())
+        });
+        self.text_window.draw(
+            frame,
+            TextWindowProps {
+                text: &body,
+                margins: Default::default(),
+            },
             metadata.area(),
-        )
+            true,
+        );
+    }
+}
+
+/// Order to display response headers in
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+enum HeaderSort {
+    /// Order the headers were received in
+    #[default]
+    Insertion,
+    /// Alphabetical by header name
+    Name,
+}
+
+/// Items in the actions popup menu for the Headers tab
+#[derive(Copy, Clone, Debug, Display, EnumIter)]
+enum ResponseHeadersMenuAction {
+    #[display("Sort by Name")]
+    SortByName,
+    #[display("Sort by Received Order")]
+    SortByInsertion,
+}
+
+impl IntoMenuAction<ResponseHeadersView> for ResponseHeadersMenuAction {
+    fn enabled(&self, data: &ResponseHeadersView) -> bool {
+        // Only offer the ordering that isn't already active
+        match self {
+            Self::SortByName => data.sort != HeaderSort::Name,
+            Self::SortByInsertion => data.sort != HeaderSort::Insertion,
+        }
     }
 }
 
@@ -213,10 +685,7 @@ mod tests {
         let mut component = TestComponent::new(
             &harness,
             &terminal,
-            ResponseBodyView::new(
-                exchange.request.recipe_id.clone(),
-                exchange.response,
-            ),
+            ResponseBodyView::new(&exchange),
         );
 
         // Open actions modal and select the copy action
@@ -288,10 +757,7 @@ mod tests {
         let mut component = TestComponent::new(
             &harness,
             &terminal,
-            ResponseBodyView::new(
-                exchange.request.recipe_id.clone(),
-                exchange.response,
-            ),
+            ResponseBodyView::new(&exchange),
         );
 
         if let Some(query) = query {
@@ -328,4 +794,94 @@ mod tests {
         assert_eq!(request_id, exchange.id);
         assert_eq!(data.as_deref(), expected_body);
     }
+
+    /// Test parsing `Set-Cookie` headers for the Cookies tab
+    #[rstest]
+    #[case::simple("session=abc123", Some(("session", "abc123", "")))]
+    #[case::attributes(
+        "session=abc123; Path=/; HttpOnly",
+        Some(("session", "abc123", "Path=/; HttpOnly"))
+    )]
+    #[case::empty_value(
+        "session=; Max-Age=0",
+        Some(("session", "", "Max-Age=0"))
+    )]
+    #[case::invalid("HttpOnly", None)]
+    fn test_parse_set_cookie(
+        #[case] header: &str,
+        #[case] expected: Option<(&str, &str, &str)>,
+    ) {
+        let expected = expected.map(|(name, value, attributes)| SetCookie {
+            name: name.into(),
+            value: value.into(),
+            attributes: attributes.into(),
+        });
+        assert_eq!(
+            SetCookie::parse(&HeaderValue::from_str(header).unwrap()),
+            expected
+        );
+    }
+
+    /// Test filtering and sorting headers
+    #[rstest]
+    #[tokio::test]
+    async fn test_headers_filter_sort(
+        harness: TestHarness,
+        terminal: TestTerminal,
+    ) {
+        let response = ResponseRecord {
+            headers: header_map(indexmap! {
+                "x-request-id" => "abc",
+                "content-type" => "application/json",
+                "accept" => "application/json",
+            }),
+            ..ResponseRecord::factory(())
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            ResponseHeadersView::new(response.into()),
+        );
+
+        let header_names = |component: &ResponseHeadersView| {
+            component
+                .visible_headers()
+                .into_iter()
+                .map(|(name, _)| name.as_str().to_owned())
+                .collect_vec()
+        };
+
+        assert_eq!(
+            header_names(component.data()),
+            ["x-request-id", "content-type", "accept"]
+        );
+
+        // Filter is case-insensitive and matches values as well as names
+        component
+            .int()
+            .send_key(KeyCode::Char('/'))
+            .send_text("JSON")
+            .assert_empty();
+        assert_eq!(header_names(component.data()), ["content-type", "accept"]);
+
+        // Sort via the actions menu
+        component
+            .int()
+            .send_key(KeyCode::Enter)
+            .open_actions()
+            .send_key(KeyCode::Enter)
+            .assert_empty();
+        assert_eq!(header_names(component.data()), ["accept", "content-type"]);
+
+        // Cancelling the filter clears it
+        component
+            .int()
+            .send_key(KeyCode::Char('/'))
+            .send_key(KeyCode::Esc)
+            .assert_empty();
+        assert_eq!(
+            header_names(component.data()),
+            ["accept", "content-type", "x-request-id"]
+        );
+    }
 }
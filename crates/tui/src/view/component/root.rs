@@ -11,7 +11,10 @@ use crate::{
             help::HelpFooter,
             history::History,
             misc::{ConfirmModal, NotificationText},
+            notification_log::NotificationLogModal,
+            overrides::Overrides,
             primary::PrimaryView,
+            CommandPaletteModal,
         },
         context::UpdateContext,
         draw::{Draw, DrawMetadata, Generate},
@@ -68,6 +71,11 @@ impl Root {
         self.primary_view.data().selected_profile_id()
     }
 
+    /// Field overrides for the current session
+    pub fn overrides(&self) -> &Overrides {
+        self.primary_view.data().overrides()
+    }
+
     /// Get a definition of the request that should be sent from the current
     /// recipe settings
     pub fn request_config(&self) -> Option<RequestConfig> {
@@ -179,6 +187,8 @@ impl EventHandler for Root {
                     let actions = self.primary_view.collect_actions();
                     ActionsModal::new(actions).open();
                 }
+                Action::CommandPalette => CommandPaletteModal::new().open(),
+                Action::NotificationLog => NotificationLogModal::new().open(),
                 Action::History => {
                     self.open_history(context.request_store)
                         .reported(&ViewContext::messages_tx());
@@ -205,6 +215,9 @@ impl EventHandler for Root {
                 Action::ReloadCollection => {
                     ViewContext::send_message(Message::CollectionStartReload)
                 }
+                Action::ResendLast => {
+                    ViewContext::send_message(Message::HttpResendLast)
+                }
                 _ => propagate.set(),
             })
             .any(|event| match event {
@@ -304,9 +317,10 @@ mod tests {
             test_util::TestComponent, util::persistence::DatabasePersistedStore,
         },
     };
+    use crossterm::event::{KeyCode, KeyModifiers};
     use persisted::PersistedStore;
     use rstest::rstest;
-    use slumber_core::{http::Exchange, test_util::Factory};
+    use slumber_core::{assert_matches, http::Exchange, test_util::Factory};
 
     /// Test that, on first render, the view loads the most recent historical
     /// request for the first recipe+profile
@@ -419,4 +433,64 @@ mod tests {
             Some(new_exchange.id)
         );
     }
+
+    /// Open the command palette, filter the list, then run the selected action
+    #[rstest]
+    fn test_command_palette(mut harness: TestHarness, terminal: TestTerminal) {
+        let collection = Collection::factory(());
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            Root::new(&collection, &harness.request_store.borrow()),
+        );
+        component.int().drain_draw().assert_empty();
+        harness.clear_messages();
+
+        component
+            .int()
+            .send_key_modifiers(KeyCode::Char('p'), KeyModifiers::CONTROL)
+            .assert_empty();
+        assert_eq!(
+            component.modal().map(|modal| modal.title().to_string()),
+            Some("Command Palette".into())
+        );
+
+        component
+            .int()
+            .send_text("hist")
+            .send_key(KeyCode::Enter)
+            .assert_empty();
+        assert!(component.modal().is_none());
+        assert_matches!(
+            harness.pop_message_now(),
+            Message::Input {
+                action: Some(Action::History),
+                ..
+            }
+        );
+    }
+
+    /// Cancelling closes the palette without running anything
+    #[rstest]
+    fn test_command_palette_cancel(
+        mut harness: TestHarness,
+        terminal: TestTerminal,
+    ) {
+        let collection = Collection::factory(());
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            Root::new(&collection, &harness.request_store.borrow()),
+        );
+        component.int().drain_draw().assert_empty();
+        harness.clear_messages();
+
+        component
+            .int()
+            .send_key_modifiers(KeyCode::Char('p'), KeyModifiers::CONTROL)
+            .send_key(KeyCode::Esc)
+            .assert_empty();
+        assert!(component.modal().is_none());
+        harness.assert_messages_empty();
+    }
 }
@@ -1,10 +1,12 @@
 use crate::{
+    context::TuiContext,
     http::RequestStore,
     message::{Message, MessageSender},
     view::{
         component::RecipeOverrideStore,
         event::{Event, EventQueue},
-        state::Notification,
+        state::{Notification, NotificationLog},
+        util::persistence::UiStateBuffer,
     },
 };
 use slumber_core::{collection::Collection, db::CollectionDatabase};
@@ -37,6 +39,13 @@ pub struct ViewContext {
     /// Sender to the async message queue, which is used to transmit data and
     /// trigger callbacks that require additional threading/background work.
     messages_tx: MessageSender,
+    /// Modified UI state that hasn't been written to the DB yet. `None` if
+    /// UI state is written immediately
+    ui_state_buffer: Option<UiStateBuffer>,
+    /// Recent notifications and errors. Unlike everything else here, this
+    /// carries over when the context is re-initialized (i.e. on collection
+    /// reload), so it covers the whole session
+    notification_log: NotificationLog,
 }
 
 impl ViewContext {
@@ -64,12 +73,21 @@ impl ViewContext {
     ) {
         debug!("Initializing view context");
         Self::INSTANCE.with_borrow_mut(|context| {
+            let notification_log = context
+                .take()
+                .map(|context| context.notification_log)
+                .unwrap_or_default();
             *context = Some(Self {
                 collection,
                 database,
                 recipe_override_store: Default::default(),
                 event_queue: EventQueue::default(),
                 messages_tx,
+                ui_state_buffer: TuiContext::get()
+                    .config
+                    .ui_state_save_interval
+                    .map(UiStateBuffer::new),
+                notification_log,
             })
         })
     }
@@ -116,6 +134,28 @@ impl ViewContext {
         Self::with_mut(|context| f(&mut context.recipe_override_store))
     }
 
+    /// Execute a function with mutable access to the buffer of unsaved UI
+    /// state. Return `None` if UI state isn't buffered
+    pub fn with_ui_state_buffer<T>(
+        f: impl FnOnce(&mut UiStateBuffer) -> T,
+    ) -> Option<T> {
+        Self::with_mut(|context| context.ui_state_buffer.as_mut().map(f))
+    }
+
+    /// Write buffered UI state to the database, if the save interval has
+    /// elapsed. If `force` is enabled, write regardless of the interval. This
+    /// should be called on every tick of the main loop.
+    pub fn save_ui_state(force: bool) {
+        Self::with_mut(|context| {
+            if let Some(buffer) = &mut context.ui_state_buffer {
+                let count = buffer.save(&context.database, force);
+                if count > 0 {
+                    debug!(count, "Saved UI state");
+                }
+            }
+        })
+    }
+
     /// Queue a view event to be handled by the component tree
     pub fn push_event(event: Event) {
         Self::with_mut(|context| context.event_queue.push(event));
@@ -129,9 +169,23 @@ impl ViewContext {
     /// Queue an event to send an informational notification to the user
     pub fn notify(message: impl ToString) {
         let notification = Notification::new(message.to_string());
+        Self::log_notification(notification.clone());
         Self::push_event(Event::Notify(notification));
     }
 
+    /// Record a notification in the session's notification log, without
+    /// showing it to the user
+    pub fn log_notification(notification: Notification) {
+        Self::with_mut(|context| context.notification_log.push(notification));
+    }
+
+    /// Execute a function with access to the session's notification log
+    pub fn with_notification_log<T>(
+        f: impl FnOnce(&NotificationLog) -> T,
+    ) -> T {
+        Self::with(|context| f(&context.notification_log))
+    }
+
     /// Get a clone of the async message sender. Generally you should use
     /// [Self::send_message] instead, but in some contexts you need the whole
     /// sender.
@@ -166,7 +220,10 @@ pub struct UpdateContext<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_util::{assert_events, harness, TestHarness};
+    use crate::{
+        test_util::{assert_events, harness, TestHarness},
+        view::state::NotificationLevel,
+    };
     use rstest::rstest;
     use slumber_core::assert_matches;
 
@@ -194,4 +251,32 @@ mod tests {
         );
         assert_matches!(harness.pop_message_now(), Message::CollectionEdit);
     }
+
+    /// Notifications and errors are logged, and the log survives
+    /// re-initialization of the context
+    #[rstest]
+    fn test_notification_log(harness: TestHarness) {
+        ViewContext::notify("Copied text");
+        ViewContext::log_notification(Notification::error("Bad!".into()));
+        ViewContext::init(
+            Arc::clone(&harness.collection),
+            harness.database.clone(),
+            ViewContext::messages_tx(),
+        );
+
+        let logged = ViewContext::with_notification_log(|log| {
+            log.iter()
+                .map(|notification| {
+                    (notification.message.clone(), notification.level)
+                })
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            logged,
+            [
+                ("Copied text".to_owned(), NotificationLevel::Info),
+                ("Bad!".to_owned(), NotificationLevel::Error),
+            ]
+        );
+    }
 }
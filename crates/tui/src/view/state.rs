@@ -5,7 +5,10 @@ pub mod select;
 
 use chrono::{DateTime, Utc};
 use derive_more::Deref;
-use std::cell::{Ref, RefCell};
+use std::{
+    cell::{Ref, RefCell},
+    collections::VecDeque,
+};
 use uuid::Uuid;
 
 /// An internally mutable cell for UI state. Certain state needs to be updated
@@ -116,9 +119,10 @@ impl<T> From<T> for Identified<T> {
 /// A notification is an ephemeral informational message generated by some async
 /// action. It doesn't grab focus, but will be useful to the user nonetheless.
 /// It should be shown for a short period of time, then disappear on its own.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Notification {
     pub message: String,
+    pub level: NotificationLevel,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -126,7 +130,51 @@ impl Notification {
     pub fn new(message: String) -> Self {
         Self {
             message,
+            level: NotificationLevel::Info,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Create a notification for an error. These are never shown in the
+    /// footer, because errors get a modal. They're only recorded in the
+    /// [NotificationLog].
+    pub fn error(message: String) -> Self {
+        Self {
+            message,
+            level: NotificationLevel::Error,
             timestamp: Utc::now(),
         }
     }
 }
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NotificationLevel {
+    Info,
+    Error,
+}
+
+/// Recent notifications and errors from this session, oldest first. This lets
+/// the user review messages they may have missed. The log is capped, so old
+/// entries are dropped during a long session.
+#[derive(Debug, Default)]
+pub struct NotificationLog(VecDeque<Notification>);
+
+impl NotificationLog {
+    /// Maximum number of notifications to retain
+    const CAPACITY: usize = 100;
+
+    pub fn push(&mut self, notification: Notification) {
+        if self.0.len() >= Self::CAPACITY {
+            self.0.pop_front();
+        }
+        self.0.push_back(notification);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Notification> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
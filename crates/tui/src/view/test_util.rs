@@ -351,6 +351,16 @@ where
     }
 }
 
+/// A component that does nothing. Useful for testing modals, which need
+/// something to be opened over
+pub struct Empty;
+
+impl EventHandler for Empty {}
+
+impl Draw for Empty {
+    fn draw(&self, _: &mut Frame, _: (), _: DrawMetadata) {}
+}
+
 /// A wrapper component to provide global functionality to a component in unit
 /// tests. This provides a modal queue and action menu, which are provided by
 /// the root component during app operation. This is included automatically in
@@ -23,6 +23,9 @@ use std::{io::Write, path::Path};
 pub struct Confirm {
     /// Question to ask the user
     pub message: String,
+    /// Content to show along with the question, e.g. a preview of whatever is
+    /// being confirmed
+    pub content: Option<Text<'static>>,
     /// A channel to pass back the user's response
     pub channel: PromptChannel<bool>,
 }
@@ -85,6 +88,48 @@ pub fn str_to_text(s: &str) -> Text<'static> {
         .into()
 }
 
+/// Generate a hex dump of binary content, in the style of `hexdump -C`. Each
+/// line contains the offset, up to 16 bytes in hex, and the printable ASCII
+/// representation of those bytes. Only the first `limit` bytes are included;
+/// if there is more content, a final line will indicate how much was omitted.
+pub fn hex_dump(bytes: &[u8], limit: usize) -> Vec<Line<'static>> {
+    const WIDTH: usize = 16;
+    let mut lines = bytes[..bytes.len().min(limit)]
+        .chunks(WIDTH)
+        .enumerate()
+        .map(|(i, chunk)| {
+            // Split the hex into two groups of 8, same as hexdump
+            let hex = (0..WIDTH)
+                .map(|j| match chunk.get(j) {
+                    Some(byte) => format!("{byte:02x}"),
+                    None => "  ".to_owned(),
+                })
+                .chunks(WIDTH / 2)
+                .into_iter()
+                .map(|mut group| group.join(" "))
+                .join("  ");
+            let ascii: String = chunk
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            Line::from(format!("{:08x}  {hex}  |{ascii}|", i * WIDTH))
+        })
+        .collect_vec();
+    if bytes.len() > limit {
+        lines.push(Line::from(format!(
+            "... {} more bytes",
+            bytes.len() - limit
+        )));
+    }
+    lines
+}
+
 /// Open a [Text] object in the user's external pager. This will write the text
 /// to a random temporary file, without having to copy the contents. If an
 /// error occurs, it will be traced and reported to the user. `content_type`
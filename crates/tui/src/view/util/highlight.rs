@@ -2,6 +2,7 @@
 //!
 //! Warning: this thing is kinda fucked.
 
+use crate::context::TuiContext;
 use anyhow::Context;
 use itertools::Itertools;
 use ratatui::{
@@ -24,7 +25,7 @@ thread_local! {
     /// per thread. The view is single threaded, which means we only create one
     static HIGHLIGHTER: RefCell<(
         Highlighter,
-        HashMap<ContentType, HighlightConfiguration>,
+        HashMap<ContentType, Option<HighlightConfiguration>>,
     )> = RefCell::default();
 }
 
@@ -32,9 +33,13 @@ thread_local! {
 /// from the content type.
 pub fn highlight(content_type: ContentType, mut text: Text<'_>) -> Text<'_> {
     HIGHLIGHTER.with_borrow_mut(|(highlighter, configs)| {
-        let config = configs
+        let Some(config) = configs
             .entry(content_type)
-            .or_insert_with(|| get_config(content_type));
+            .or_insert_with(|| get_config(content_type))
+        else {
+            // No highlighter for this language
+            return text;
+        };
 
         // Each line in the input corresponds to one line in the output, so we
         // can mutate each line inline
@@ -90,8 +95,68 @@ pub fn highlight_if(
     }
 }
 
-/// Map [ContentType] to a syntax highlighting language
-fn get_config(content_type: ContentType) -> HighlightConfiguration {
+/// Apply simple highlighting to a generated command. This isn't a real parser;
+/// it just picks out quoted strings and `-`/`--` flags, which is enough to
+/// make the structure of a cURL/HTTPie command or `fetch()` call easy to
+/// scan.
+pub fn highlight_command(text: &str) -> Text<'static> {
+    let styles = &TuiContext::get().styles;
+    let lines = text.lines().map(|line| {
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        let mut chars = line.char_indices().peekable();
+        // Start of the current unstyled chunk
+        let mut plain_start = 0;
+        while let Some((start, c)) = chars.next() {
+            let at_word_start = start == 0
+                || line[..start].ends_with(|c: char| c.is_whitespace());
+            let end = if c == '\'' || c == '"' {
+                // Consume through the closing quote. Backslash escapes only
+                // apply within double quotes
+                let mut escaped = false;
+                let mut end = line.len();
+                for (i, next) in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if c == '"' && next == '\\' {
+                        escaped = true;
+                    } else if next == c {
+                        end = i + next.len_utf8();
+                        break;
+                    }
+                }
+                Some((end, styles.text.primary))
+            } else if c == '-' && at_word_start {
+                let mut end = line.len();
+                while let Some((i, next)) = chars.peek() {
+                    if next.is_whitespace() {
+                        end = *i;
+                        break;
+                    }
+                    chars.next();
+                }
+                Some((end, styles.text.highlight))
+            } else {
+                None
+            };
+            if let Some((end, style)) = end {
+                if plain_start < start {
+                    spans.push(line[plain_start..start].to_owned().into());
+                }
+                spans.push(Span::styled(line[start..end].to_owned(), style));
+                plain_start = end;
+            }
+        }
+        if plain_start < line.len() {
+            spans.push(line[plain_start..].to_owned().into());
+        }
+        Line::from(spans)
+    });
+    Text::from_iter(lines)
+}
+
+/// Map [ContentType] to a syntax highlighting language. Return `None` if
+/// there's no highlighter for the content type
+fn get_config(content_type: ContentType) -> Option<HighlightConfiguration> {
     let mut config = match content_type {
         ContentType::Json => HighlightConfiguration::new(
             tree_sitter_json::language(),
@@ -101,6 +166,7 @@ fn get_config(content_type: ContentType) -> HighlightConfiguration {
             "",
         )
         .expect("Error initializing JSON syntax highlighter"),
+        ContentType::FormUrlencoded => return None,
     };
     config.configure(
         HighlightName::iter()
@@ -108,7 +174,7 @@ fn get_config(content_type: ContentType) -> HighlightConfiguration {
             .collect_vec()
             .as_slice(),
     );
-    config
+    Some(config)
 }
 
 /// All highlight names that we support
@@ -379,7 +445,9 @@ fn split_cow(s: Cow<'_, str>, at: usize) -> (Cow<'_, str>, Cow<'_, str>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{harness, TestHarness};
     use pretty_assertions::assert_eq;
+    use rstest::rstest;
 
     /// Test that JSON is highlighted, by existing styling is retained
     #[test]
@@ -467,4 +535,29 @@ mod tests {
             )
         );
     }
+
+    /// Strings and flags are styled, everything else is left alone
+    #[rstest]
+    fn test_highlight_command(_harness: TestHarness) {
+        let styles = &TuiContext::get().styles;
+        assert_eq!(
+            highlight_command("curl -XGET --url 'http://localhost'"),
+            Text::from(Line::from(vec![
+                Span::raw("curl "),
+                Span::styled("-XGET", styles.text.highlight),
+                Span::raw(" "),
+                Span::styled("--url", styles.text.highlight),
+                Span::raw(" "),
+                Span::styled("'http://localhost'", styles.text.primary),
+            ]))
+        );
+        assert_eq!(
+            highlight_command(r#"  body: "a\"b","#),
+            Text::from(Line::from(vec![
+                Span::raw("  body: "),
+                Span::styled(r#""a\"b""#, styles.text.primary),
+                Span::raw(","),
+            ]))
+        );
+    }
 }
@@ -1,9 +1,14 @@
 //! Implementation of the [persisted] crate for UI data
 
 use crate::view::ViewContext;
+use indexmap::IndexMap;
 use persisted::PersistedStore;
 use serde::{de::DeserializeOwned, Serialize};
-use std::fmt::Debug;
+use slumber_core::{db::CollectionDatabase, util::ResultTraced};
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
 
 /// This struct exists solely to hold an impl of [PersistedStore], which
 /// persists UI state into the database.
@@ -30,6 +35,14 @@ where
     K::Value: Debug + Serialize + DeserializeOwned,
 {
     fn load_persisted(key: &K) -> Option<K::Value> {
+        // Unsaved changes take precedence over the DB
+        if let Some(value) = ViewContext::with_ui_state_buffer(|buffer| {
+            buffer.get(K::type_name(), key)
+        })
+        .flatten()
+        {
+            return Some(value);
+        }
         ViewContext::with_database(|database| {
             database.get_ui(K::type_name(), key)
         })
@@ -39,6 +52,15 @@ where
     }
 
     fn store_persisted(key: &K, value: &K::Value) {
+        // If saves are batched, hold onto the value until the next save
+        if ViewContext::with_ui_state_buffer(|buffer| {
+            buffer.insert(K::type_name(), key, value)
+        })
+        .flatten()
+        .is_some()
+        {
+            return;
+        }
         ViewContext::with_database(|database| {
             database.set_ui(K::type_name(), key, value)
         })
@@ -46,3 +68,100 @@ where
         .ok();
     }
 }
+
+/// UI state that has been modified but not yet written to the database. This
+/// is only used when `ui_state_save_interval` is enabled in the config, to
+/// batch writes together instead of writing on every change (e.g. every
+/// keypress while scrolling). Dirty values are written when the interval
+/// elapses, and on exit.
+#[derive(Debug)]
+pub struct UiStateBuffer {
+    interval: Duration,
+    last_save: Instant,
+    /// Modified values, keyed by (key type, key). Keys and values are stored
+    /// in their serialized form so we can hold any type, and are deserialized
+    /// on read. Subsequent changes to the same key overwrite each other, so
+    /// only the latest value is written.
+    dirty: IndexMap<(String, serde_json::Value), serde_json::Value>,
+}
+
+impl UiStateBuffer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_save: Instant::now(),
+            dirty: IndexMap::new(),
+        }
+    }
+
+    /// Get an unsaved value for a key, if there is one
+    fn get<K, V>(&self, key_type: &str, key: &K) -> Option<V>
+    where
+        K: Debug + Serialize,
+        V: DeserializeOwned,
+    {
+        let key = serde_json::to_value(key).ok()?;
+        let value = self.dirty.get(&(key_type.to_owned(), key))?;
+        serde_json::from_value(value.clone()).ok()
+    }
+
+    /// Mark a value as modified. Return `None` if serialization fails, in
+    /// which case the value should be written directly instead.
+    fn insert<K, V>(&mut self, key_type: &str, key: &K, value: &V) -> Option<()>
+    where
+        K: Debug + Serialize,
+        V: Debug + Serialize,
+    {
+        let key = serde_json::to_value(key).traced().ok()?;
+        let value = serde_json::to_value(value).traced().ok()?;
+        self.dirty.insert((key_type.to_owned(), key), value);
+        Some(())
+    }
+
+    /// Write all modified values to the database, if the save interval has
+    /// elapsed since the last save. If `force` is enabled, save regardless of
+    /// the interval. Return the number of values written.
+    pub fn save(
+        &mut self,
+        database: &CollectionDatabase,
+        force: bool,
+    ) -> usize {
+        if !force && self.last_save.elapsed() < self.interval {
+            return 0;
+        }
+        self.last_save = Instant::now();
+        let count = self.dirty.len();
+        for ((key_type, key), value) in self.dirty.drain(..) {
+            // Error is already traced in the DB, nothing to do with it here
+            let _ = database.set_ui(&key_type, key, value);
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slumber_core::test_util::Factory;
+
+    /// Changes should be held in memory until the interval elapses
+    #[test]
+    fn test_ui_state_buffer() {
+        let database = CollectionDatabase::factory(());
+        let mut buffer = UiStateBuffer::new(Duration::from_secs(60));
+
+        buffer.insert("Key", &"key1", &"value1").unwrap();
+        buffer.insert("Key", &"key1", &"value2").unwrap();
+        assert_eq!(buffer.get::<_, String>("Key", &"key1").unwrap(), "value2");
+        assert_eq!(buffer.save(&database, false), 0);
+        assert_eq!(database.get_ui::<_, String>("Key", "key1").unwrap(), None);
+
+        // Latest value is written
+        assert_eq!(buffer.save(&database, true), 1);
+        assert_eq!(
+            database.get_ui::<_, String>("Key", "key1").unwrap(),
+            Some("value2".into())
+        );
+        assert_eq!(buffer.get::<_, String>("Key", &"key1"), None);
+    }
+}
@@ -2,7 +2,7 @@
 #![deny(clippy::all)]
 
 use anyhow::Context;
-use slumber_cli::Args;
+use slumber_cli::{print_error, Args};
 use slumber_core::util::{paths, ResultTraced};
 use std::{
     fs::{self, File, OpenOptions},
@@ -18,7 +18,10 @@ async fn main() -> anyhow::Result<ExitCode> {
     Args::complete(); // If COMPLETE var is enabled, process will stop here
     let args = Args::parse();
 
-    initialize_tracing(args.subcommand.is_some());
+    initialize_tracing(
+        args.subcommand.is_some(),
+        args.global.color_enabled(&io::stderr()),
+    );
 
     // Select mode based on whether request ID(s) were given
     match args.subcommand {
@@ -27,26 +30,26 @@ async fn main() -> anyhow::Result<ExitCode> {
         #[cfg(feature = "tui")]
         None => {
             // This should return the error so we get a full stack trace
-            slumber_tui::Tui::start(args.global.file).await?;
+            slumber_tui::Tui::start(args.global.file, args.watch).await?;
             Ok(ExitCode::SUCCESS)
         }
         #[cfg(not(feature = "tui"))]
         None => Err(anyhow::anyhow!("TUI feature is disabled")),
 
         // Execute one request without a TUI
-        Some(subcommand) => Ok(subcommand
-            .execute(args.global)
-            .await
-            // Do *not* return the error, because that prints a stack trace
-            // which is way too verbose. Just print the error messages instead
-            .unwrap_or_else(|error| {
-                eprintln!("{error}");
-                error
-                    .chain()
-                    .skip(1)
-                    .for_each(|cause| eprintln!("  {cause}"));
-                ExitCode::FAILURE
-            })),
+        Some(subcommand) => {
+            let error_format = args.global.error_format;
+            Ok(subcommand
+                .execute(args.global)
+                .await
+                // Do *not* return the error, because that prints a stack trace
+                // which is way too verbose. Just print the error messages
+                // instead
+                .unwrap_or_else(|error| {
+                    print_error(&error, error_format);
+                    ExitCode::FAILURE
+                }))
+        }
     }
 }
 
@@ -54,7 +57,7 @@ async fn main() -> anyhow::Result<ExitCode> {
 /// an error creating the log file, we'll skip that part. This means in the TUI
 /// the error (and all other tracing) will never be visible, but that's a
 /// problem for another day.
-fn initialize_tracing(console_output: bool) {
+fn initialize_tracing(console_output: bool, console_color: bool) {
     // Failing to log shouldn't be a fatal crash, so just move on
     let log_file = initialize_log_file()
         .context("Error creating log file")
@@ -87,6 +90,7 @@ fn initialize_tracing(console_output: bool) {
         Some(
             tracing_subscriber::fmt::layer()
                 .with_writer(io::stderr)
+                .with_ansi(console_color)
                 .with_target(false)
                 .with_span_events(FmtSpan::NEW)
                 .without_time()